@@ -23,6 +23,15 @@ fn main() {
                                         "GL_NVX_gpu_memory_info".to_string(),
                                         "GL_ATI_meminfo".to_string(),
                                         "GL_EXT_texture_filter_anisotropic".to_string(),
+                                        "GL_EXT_texture_compression_s3tc".to_string(),
+                                        "GL_EXT_texture_sRGB".to_string(),
+                                        "GL_NV_mesh_shader".to_string(),
+                                        "GL_ARB_ES3_2_compatibility".to_string(),
+                                        "GL_EXT_primitive_bounding_box".to_string(),
+                                        "GL_OES_primitive_bounding_box".to_string(),
+                                        "GL_NV_vdpau_interop".to_string(),
+                                        "GL_EXT_transform_feedback".to_string(),
+                                        "GL_ARB_robustness".to_string(),
                                     ],
                                     "4.5", "compatibility", &mut gl_bindings).unwrap();
 }
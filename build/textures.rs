@@ -58,394 +58,6 @@ pub fn build_texture_file<W: Writer>(mut dest: &mut W) {
 }
 
 fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: TextureDimensions) {
-<<<<<<< HEAD
-	// building the name of the texture type
-	let name: String = {
-		let prefix = match ty {
-			TextureType::Regular => "",
-			TextureType::Compressed => "Compressed",
-			TextureType::Integral => "Integral",
-			TextureType::Unsigned => "Unsigned",
-			TextureType::Depth => "Depth",
-			TextureType::Stencil => "Stencil",
-			TextureType::DepthStencil => "DepthStencil",
-		};
-
-		let suffix = match dimensions {
-			TextureDimensions::Texture1d => "Texture1d",
-			TextureDimensions::Texture2d => "Texture2d",
-			TextureDimensions::Texture3d => "Texture3d",
-			TextureDimensions::Texture1dArray => "Texture1dArray",
-			TextureDimensions::Texture2dArray => "Texture2dArray",
-		};
-
-		format!("{}{}", prefix, suffix)
-	};
-
-	// writing the struct with doc-comment
-	(write!(dest, "/// ")).unwrap();
-	(write!(dest, "{}", match dimensions {
-		TextureDimensions::Texture1d | TextureDimensions::Texture2d |
-		TextureDimensions::Texture3d => "A ",
-		TextureDimensions::Texture1dArray | TextureDimensions::Texture2dArray => "An array of ",
-	})).unwrap();
-	if ty == TextureType::Compressed {
-		(write!(dest, "compressed ")).unwrap();
-	}
-	(write!(dest, "{}", match dimensions {
-		TextureDimensions::Texture1d | TextureDimensions::Texture1dArray => "one-dimensional ",
-		TextureDimensions::Texture2d | TextureDimensions::Texture2dArray => "two-dimensional ",
-		TextureDimensions::Texture3d => "three-dimensional ",
-	})).unwrap();
-	(write!(dest, "{}", match dimensions {
-		TextureDimensions::Texture1d | TextureDimensions::Texture2d |
-		TextureDimensions::Texture3d => "texture ",
-		TextureDimensions::Texture1dArray | TextureDimensions::Texture2dArray => "textures ",
-	})).unwrap();
-	(write!(dest, "{}", match ty {
-		TextureType::Regular | TextureType::Compressed => " containing floating-point data",
-		TextureType::Integral => " containing signed integral data",
-		TextureType::Unsigned => " containing unsigned integral data",
-		TextureType::Depth => " containing depth data",
-		TextureType::Stencil => " containing stencil data",
-		TextureType::DepthStencil => " containing both depth and stencil data",
-	})).unwrap();
-	(writeln!(dest, ".")).unwrap();
-	(writeln!(dest, "pub struct {}(TextureImplementation);", name)).unwrap();
-
-	// `Texture` trait impl
-	(writeln!(dest, "
-				impl Texture for {} {{
-					fn get_implementation(&self) -> &TextureImplementation {{
-						&self.0
-					}}
-				}}
-			", name)).unwrap();
-
-	// `GlObject` trait impl
-	(writeln!(dest, "
-				impl GlObject for {} {{
-					fn get_id(&self) -> gl::types::GLuint {{
-						self.0.get_id()
-					}}
-				}}
-			", name)).unwrap();
-
-	// `UniformValue` trait impl
-	(writeln!(dest, "
-				impl<'a> UniformValue for &'a {} {{
-					fn to_binder(&self) -> UniformValueBinder {{
-						self.get_implementation().to_binder()
-					}}
-				}}
-			", name)).unwrap();
-
-	(writeln!(dest, "
-		impl<'a> UniformValue for Arc<{}> {{
-			fn to_binder(&self) -> UniformValueBinder {{
-				self.get_implementation().to_binder()
-			}}
-		}}
-	", name)).unwrap();
-
-	(writeln!(dest, "
-		impl<'a> UniformValue for Rc<{}> {{
-			fn to_binder(&self) -> UniformValueBinder {{
-				self.get_implementation().to_binder()
-			}}
-		}}
-	", name)).unwrap();
-
-	// `ToXXXAttachment` trait impl
-	if dimensions == TextureDimensions::Texture2d {
-		match ty {
-			TextureType::Regular => {
-				(writeln!(dest, "
-						impl ::framebuffer::ToColorAttachment for {} {{
-							fn to_color_attachment(&self) -> ::framebuffer::ColorAttachment {{
-								::framebuffer::ColorAttachment::Texture2d(self)
-							}}
-						}}
-					", name)).unwrap();
-			},
-			TextureType::Depth => {
-				(writeln!(dest, "
-						impl ::framebuffer::ToDepthAttachment for {} {{
-							fn to_depth_attachment(&self) -> ::framebuffer::DepthAttachment {{
-								::framebuffer::DepthAttachment::Texture2d(self)
-							}}
-						}}
-					", name)).unwrap();
-			},
-			TextureType::Stencil => {
-				(writeln!(dest, "
-						impl ::framebuffer::ToStencilAttachment for {} {{
-							fn to_stencil_attachment(&self) -> ::framebuffer::StencilAttachment {{
-								::framebuffer::StencilAttachment::Texture2d(self)
-							}}
-						}}
-					", name)).unwrap();
-			},
-			TextureType::DepthStencil => {
-				(writeln!(dest, "
-						impl ::framebuffer::ToDepthStencilAttachment for {} {{
-							fn to_depth_stencil_attachment(&self) -> ::framebuffer::DepthStencilAttachment {{
-								::framebuffer::DepthStencilAttachment::Texture2d(self)
-							}}
-						}}
-					", name)).unwrap();
-			},
-			_ => ()
-		}
-	}
-
-	// opening `impl Texture` block
-	(writeln!(dest, "impl {} {{", name)).unwrap();
-
-	// writing the `new` function
-	{
-		let data_type = match dimensions {
-			TextureDimensions::Texture1d | TextureDimensions::Texture1dArray => "Texture1dData<P>",
-			TextureDimensions::Texture2d | TextureDimensions::Texture2dArray => "Texture2dData<P>",
-			TextureDimensions::Texture3d => "Texture3dData<P>",
-		};
-
-		let param = match dimensions {
-			TextureDimensions::Texture1d | TextureDimensions::Texture2d |
-			TextureDimensions::Texture3d => "T",
-
-			TextureDimensions::Texture1dArray |
-			TextureDimensions::Texture2dArray => "Vec<T>",
-		};
-
-		(writeln!(dest, "
-				/// Builds a new texture by uploading data.
-				///
-				/// This function will automatically generate all mipmaps of the texture.
-				pub fn new<P: PixelValue, T: {data_type}>(display: &::Display, data: {param})
-					-> {name}
-				{{
-			", data_type = data_type, param = param, name = name)).unwrap();
-
-
-		// writing the `let format = ...` line
-		match dimensions {
-			TextureDimensions::Texture1d | TextureDimensions::Texture1dArray => {
-				(writeln!(dest, "let format = Texture1dData::get_format(None::<T>);")).unwrap();
-			},
-			TextureDimensions::Texture2d | TextureDimensions::Texture2dArray => {
-				(writeln!(dest, "let format = Texture2dData::get_format(None::<T>);")).unwrap();
-			},
-			TextureDimensions::Texture3d => {
-				(writeln!(dest, "let format = Texture3dData::get_format(None::<T>);")).unwrap();
-			},
-		}
-		match ty {
-			TextureType::Compressed => {
-				(write!(dest, "let format = format.to_default_compressed_format();")).unwrap();
-			},
-			TextureType::Regular | TextureType::Integral | TextureType::Unsigned => {
-				(write!(dest, "let format = format.to_default_float_format();")).unwrap();
-			},
-			TextureType::Depth => {
-				(write!(dest, "let format = DepthFormat::I24.to_glenum();")).unwrap();
-			},
-			TextureType::Stencil => {
-				(write!(dest, "let format = StencilFormat::I8.to_glenum();")).unwrap();
-			},
-			TextureType::DepthStencil => {
-				(write!(dest, "let format = DepthStencilFormat::I24I8.to_glenum();")).unwrap();
-			},
-		};
-
-		// writing the `let (client_format, client_type) = ...` line
-		match dimensions {
-			TextureDimensions::Texture1d | TextureDimensions::Texture1dArray => {
-				(writeln!(dest, "let client_format = Texture1dData::get_format(None::<T>);")).unwrap();
-			},
-			TextureDimensions::Texture2d | TextureDimensions::Texture2dArray => {
-				(writeln!(dest, "let client_format = Texture2dData::get_format(None::<T>);")).unwrap();
-			},
-			TextureDimensions::Texture3d => {
-				(writeln!(dest, "let client_format = Texture3dData::get_format(None::<T>);")).unwrap();
-			},
-		}
-		(write!(dest, "let (client_format, client_type) = ")).unwrap();
-		match ty {
-			TextureType::Compressed | TextureType::Regular | TextureType::Depth => {
-				(write!(dest, "client_format.to_gl_enum()")).unwrap();
-			},
-			TextureType::Integral | TextureType::Stencil => {
-				(write!(dest, "client_format.to_gl_enum_int().expect(\"Client format must \
-							   have an integral format\")")).unwrap();
-			},
-			TextureType::Unsigned => {
-				(write!(dest, "client_format.to_gl_enum_uint().expect(\"Client format must \
-							   have an integral format\")")).unwrap();
-			},
-			TextureType::DepthStencil => {
-				(write!(dest, "unimplemented!()")).unwrap();
-			},
-		};
-		(writeln!(dest, ";")).unwrap();
-
-
-		match dimensions {
-			TextureDimensions::Texture1d => (write!(dest, "
-					let data = data.into_vec();
-					let width = data.len() as u32;
-				")).unwrap(),
-
-			TextureDimensions::Texture2d => (write!(dest, "
-					let (width, height) = data.get_dimensions();
-					let width = width as u32; let height = height as u32;
-					let data = data.into_vec();
-				")).unwrap(),
-
-			TextureDimensions::Texture3d => (write!(dest, "
-					let (width, height, depth) = data.get_dimensions();
-					let width = width as u32; let height = height as u32; let depth = depth as u32;
-					let data = data.into_vec();
-				")).unwrap(),
-
-			TextureDimensions::Texture1dArray => (write!(dest, "
-					let array_size = data.len() as u32;
-					let mut width = 0;
-					let data = data.into_iter().flat_map(|t| {{
-						let d = t.into_vec(); width = d.len(); d.into_iter()
-					}}).collect();
-					let width = width as u32;
-				")).unwrap(),   // TODO: panic if dimensions are inconsistent
-
-			TextureDimensions::Texture2dArray => (write!(dest, "
-					let array_size = data.len() as u32;
-					let mut dimensions = (0, 0);
-					let data = data.into_iter().flat_map(|t| {{
-						dimensions = t.get_dimensions(); t.into_vec().into_iter()
-					}}).collect();
-					let (width, height) = dimensions;
-					let width = width as u32; let height = height as u32;
-				")).unwrap(),   // TODO: panic if dimensions are inconsistent
-		}
-		// writing the constructor
-		(write!(dest, "{}(TextureImplementation::new(display, format, Some(data), \
-					   client_format, client_type, ", name)).unwrap();
-		match dimensions {
-			TextureDimensions::Texture1d => (write!(dest, "width, None, None, None")).unwrap(),
-			TextureDimensions::Texture2d => (write!(dest, "width, Some(height), None, None")).unwrap(),
-			TextureDimensions::Texture3d => (write!(dest, "width, Some(height), Some(depth), None")).unwrap(),
-			TextureDimensions::Texture1dArray => (write!(dest, "width, None, None, Some(array_size)")).unwrap(),
-			TextureDimensions::Texture2dArray => (write!(dest, "width, Some(height), None, Some(array_size)")).unwrap(),
-		}
-		(writeln!(dest, "))")).unwrap();
-
-		// end of "new" function block
-		(writeln!(dest, "}}")).unwrap();
-	}
-
-	// writing the `new_empty` function
-	if ty != TextureType::Compressed {
-		let format = match ty {
-			TextureType::Regular => "UncompressedFloatFormat",
-			TextureType::Compressed => "CompressedFormat",
-			TextureType::Integral => "UncompressedIntFormat",
-			TextureType::Unsigned => "UncompressedUintFormat",
-			TextureType::Depth => "DepthFormat",
-			TextureType::Stencil => "StencilFormat",
-			TextureType::DepthStencil => "DepthStencilFormat",
-		};
-
-		let dim_params = match dimensions {
-			TextureDimensions::Texture1d => "width: u32",
-			TextureDimensions::Texture2d => "width: u32, height: u32",
-			TextureDimensions::Texture3d => "width: u32, height: u32, depth: u32",
-			TextureDimensions::Texture1dArray => "width: u32, array_size: u32",
-			TextureDimensions::Texture2dArray => "width: u32, height: u32, array_size: u32",
-		};
-
-		// opening function
-		(writeln!(dest, "
-				/// Creates an empty texture.
-				///
-				/// The texture will contain undefined data.
-				pub fn new_empty(display: &::Display, format: {format}, {dim_params}) -> {name} {{
-					let format = format.to_glenum();
-			", format = format, dim_params = dim_params, name = name)).unwrap();
-
-		// writing the constructor
-		(write!(dest, "{}(TextureImplementation::new::<u8>(display, format, None, \
-					   gl::RGBA, gl::UNSIGNED_BYTE, ", name)).unwrap();
-		match dimensions {
-			TextureDimensions::Texture1d => (write!(dest, "width, None, None, None")).unwrap(),
-			TextureDimensions::Texture2d => (write!(dest, "width, Some(height), None, None")).unwrap(),
-			TextureDimensions::Texture3d => (write!(dest, "width, Some(height), Some(depth), None")).unwrap(),
-			TextureDimensions::Texture1dArray => (write!(dest, "width, None, None, Some(array_size)")).unwrap(),
-			TextureDimensions::Texture2dArray => (write!(dest, "width, Some(height), None, Some(array_size)")).unwrap(),
-		}
-		(writeln!(dest, "))")).unwrap();
-
-		// closing function
-		(writeln!(dest, "}}")).unwrap();
-	}
-
-	// writing the `as_surface` function
-	if dimensions == TextureDimensions::Texture2d && ty == TextureType::Regular {
-		(write!(dest, "
-				/// Starts drawing on the texture.
-				///
-				/// All the function calls to the `TextureSurface` will draw on the texture instead
-				/// of the screen.
-				///
-				/// ## Low-level informations
-				///
-				/// The first time that this function is called, a FrameBuffer Object will be
-				/// created and cached. The following calls to `as_surface` will load the existing
-				/// FBO and re-use it. When the texture is destroyed, the FBO is destroyed too.
-				///
-				pub fn as_surface<'a>(&'a self) -> TextureSurface<'a> {{
-					// TODO: hacky, shouldn't recreate a Display
-					let display = ::Display {{ context: self.0.display.clone() }};
-					TextureSurface(framebuffer::SimpleFrameBuffer::new(&display, self))
-				}}
-			")).unwrap();
-	}
-
-	// writing the `read` function
-	// TODO: implement for arrays too
-	if dimensions != TextureDimensions::Texture1dArray && dimensions != TextureDimensions::Texture2dArray {
-		let (data_type, constructor) = match dimensions {
-			TextureDimensions::Texture1d | TextureDimensions::Texture1dArray => (
-					"Texture1dData<P>",
-					"Texture1dData::from_vec(data)"
-				),
-			TextureDimensions::Texture2d | TextureDimensions::Texture2dArray => (
-					"Texture2dData<P>",
-					"Texture2dData::from_vec(data, self.get_width() as u32)"
-				),
-			TextureDimensions::Texture3d => (
-					"Texture3dData<P>",
-					"Texture3dData::from_vec(data, self.get_width() as u32, \
-											 self.get_height().unwrap() as u32)"
-				),
-		};
-
-		(write!(dest, r#"
-				/// Reads the content of the texture.
-				///
-				/// # Features
-				///
-				/// This method is always only if the `gl_extensions` feature is enabled.
-				#[cfg(feature = "gl_extensions")]
-				pub fn read<P, T>(&self) -> T where P: PixelValue, T: {data_type} {{
-					let data = self.0.read::<P>(0);
-					{constructor}
-				}}
-			"#, data_type = data_type, constructor = constructor)).unwrap();
-	}
-
-	// closing `impl Texture` block
-	(writeln!(dest, "}}")).unwrap();
-=======
     // building the name of the texture type
     let name: String = {
         let prefix = match ty {
@@ -518,6 +130,10 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                     fn get_array_size(&self) -> Option<u32> {{
                         self.0.get_array_size()
                     }}
+
+                    fn get_mipmap_levels(&self) -> u32 {{
+                        self.0.get_mipmap_levels()
+                    }}
                 }}
             ", name)).unwrap();
 
@@ -548,6 +164,57 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                         }}
                     ", myname = name)).unwrap();
         },
+        TextureType::Depth if dimensions != TextureDimensions::Texture3d => {
+            (writeln!(dest, "
+                        impl<'a> IntoUniformValue<'a> for &'a {myname} {{
+                            fn into_uniform_value(self) -> UniformValue<'a> {{
+                                UniformValue::{myname}(self, None)
+                            }}
+                        }}
+
+                        impl<'a> IntoUniformValue<'a> for Sampler<'a, {myname}> {{
+                            fn into_uniform_value(self) -> UniformValue<'a> {{
+                                UniformValue::{myname}(self.0, Some(self.1))
+                            }}
+                        }}
+                    ", myname = name)).unwrap();
+        },
+        TextureType::DepthStencil if dimensions == TextureDimensions::Texture2d => {
+            (writeln!(dest, "
+                        impl<'a> IntoUniformValue<'a> for &'a {myname} {{
+                            fn into_uniform_value(self) -> UniformValue<'a> {{
+                                UniformValue::{myname}(self, None, DepthStencilTextureMode::Depth)
+                            }}
+                        }}
+
+                        impl<'a> IntoUniformValue<'a> for Sampler<'a, {myname}> {{
+                            fn into_uniform_value(self) -> UniformValue<'a> {{
+                                UniformValue::{myname}(self.0, Some(self.1),
+                                                       DepthStencilTextureMode::Depth)
+                            }}
+                        }}
+                    ", myname = name)).unwrap();
+        },
+        _ => ()
+    }
+
+    // `ImageUnit` uniform impl
+    match ty {
+        TextureType::Regular | TextureType::Integral | TextureType::Unsigned => {
+            let layered = match dimensions {
+                TextureDimensions::Texture1d | TextureDimensions::Texture2d => false,
+                _ => true,
+            };
+
+            (writeln!(dest, "
+                        impl<'a> IntoUniformValue<'a> for ::uniforms::ImageUnit<'a, {myname}> {{
+                            fn into_uniform_value(self) -> UniformValue<'a> {{
+                                UniformValue::Image(::uniforms::ImageUnitBinding::new(
+                                    self.0.get_id(), {layered}, self.1, self.2))
+                            }}
+                        }}
+                    ", myname = name, layered = layered)).unwrap();
+        },
         _ => ()
     }
 
@@ -594,6 +261,33 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
         }
     }
 
+    // layered attachments
+    match (&ty, &dimensions) {
+        (&TextureType::Regular, &TextureDimensions::Texture2dArray) |
+        (&TextureType::Regular, &TextureDimensions::Texture3d) => {
+            (writeln!(dest, "
+                    impl ::framebuffer::ToColorAttachment for {name} {{
+                        fn to_color_attachment(&self) -> ::framebuffer::ColorAttachment {{
+                            ::framebuffer::ColorAttachment::{dimensions}(self)
+                        }}
+                    }}
+                ", name = name, dimensions = match dimensions {
+                    TextureDimensions::Texture3d => "Texture3d",
+                    _ => "Texture2dArray",
+                })).unwrap();
+        },
+        (&TextureType::Depth, &TextureDimensions::Texture2dArray) => {
+            (writeln!(dest, "
+                    impl ::framebuffer::ToDepthAttachment for {} {{
+                        fn to_depth_attachment(&self) -> ::framebuffer::DepthAttachment {{
+                            ::framebuffer::DepthAttachment::Texture2dArray(self)
+                        }}
+                    }}
+                ", name)).unwrap();
+        },
+        _ => ()
+    }
+
     // opening `impl Texture` block
     (writeln!(dest, "impl {} {{", name)).unwrap();
 
@@ -613,14 +307,72 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
             TextureDimensions::Texture2dArray => "Vec<T>",
         };
 
+        let (default_mipmaps, mipmaps_doc) = match ty {
+            TextureType::Depth | TextureType::Stencil | TextureType::DepthStencil => (
+                "MipmapsOption::NoMipmap",
+                "The texture will only have one mipmap level."
+            ),
+            _ => (
+                "MipmapsOption::AutoGeneratedMipmaps",
+                "This function will automatically generate all mipmaps of the texture."
+            ),
+        };
+
+        // the expression that checks whether the format used by `new` is supported
+        let supported = match ty {
+            TextureType::Regular => format!("{0}::get_format(None::<T>).is_supported(display) && \
+                                             {0}::get_format(None::<T>).to_float_internal_format()\
+                                             .map(|f| f.is_supported(display)).unwrap_or(true)",
+                                            data_type),
+            TextureType::Compressed => "true".to_string(),
+            TextureType::Integral => "UncompressedIntFormat::I8I8I8I8.is_supported(display)"
+                                         .to_string(),
+            TextureType::Unsigned => "UncompressedUintFormat::U8U8U8U8.is_supported(display)"
+                                         .to_string(),
+            TextureType::Depth => "DepthFormat::I24.is_supported(display)".to_string(),
+            TextureType::Stencil => "StencilFormat::I8.is_supported(display)".to_string(),
+            TextureType::DepthStencil => "DepthStencilFormat::I24I8.is_supported(display)"
+                                             .to_string(),
+        };
+
         (writeln!(dest, "
                 /// Builds a new texture by uploading data.
                 ///
-                /// This function will automatically generate all mipmaps of the texture.
+                /// {mipmaps_doc}
                 pub fn new<T>(display: &::Display, data: {param})
                               -> {name} where T: {data_type}
                 {{
-            ", data_type = data_type, param = param, name = name)).unwrap();
+                    {name}::with_mipmaps(display, data, {default_mipmaps})
+                }}
+
+                /// Builds a new texture by uploading data.
+                ///
+                /// {mipmaps_doc}
+                ///
+                /// Returns an error if the format of the texture or the format of the data is
+                /// not supported by the backend, instead of creating a texture that can't be
+                /// used.
+                pub fn new_checked<T>(display: &::Display, data: {param})
+                                      -> Result<{name}, TextureCreationError> where T: {data_type}
+                {{
+                    if !{supported} {{
+                        return Err(TextureCreationError::FormatNotSupported);
+                    }}
+
+                    Ok({name}::new(display, data))
+                }}
+
+                /// Builds a new texture by uploading data, with the given mipmaps policy.
+                ///
+                /// # Panic
+                ///
+                /// - Panics if mipmaps are automatically generated for a depth or stencil texture.
+                /// - Panics if the number of empty mipmaps is zero or too large for the dimensions.
+                pub fn with_mipmaps<T>(display: &::Display, data: {param}, mipmaps: MipmapsOption)
+                                       -> {name} where T: {data_type}
+                {{
+            ", data_type = data_type, param = param, name = name, mipmaps_doc = mipmaps_doc,
+               default_mipmaps = default_mipmaps, supported = supported)).unwrap();
 
 
         // writing the `let format = ...` line
@@ -667,10 +419,16 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
         }
         (write!(dest, "let (client_format, client_type) = ")).unwrap();
         match ty {
-            TextureType::Compressed | TextureType::Regular | TextureType::Depth => {
+            TextureType::Compressed | TextureType::Regular => {
                 (write!(dest, "client_format.to_gl_enum()")).unwrap();
             },
-            TextureType::Integral | TextureType::Stencil => {
+            TextureType::Depth => {
+                (write!(dest, "(gl::DEPTH_COMPONENT, client_format.to_gl_enum().1)")).unwrap();
+            },
+            TextureType::Stencil => {
+                (write!(dest, "(gl::STENCIL_INDEX, client_format.to_gl_enum().1)")).unwrap();
+            },
+            TextureType::Integral => {
                 (write!(dest, "client_format.to_gl_enum_int().expect(\"Client format must \
                                have an integral format\")")).unwrap();
             },
@@ -679,7 +437,14 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                                have an integral format\")")).unwrap();
             },
             TextureType::DepthStencil => {
-                (write!(dest, "unimplemented!()")).unwrap();
+                // each value contains the depth in its 24 upper bits and the stencil in its 8
+                // lower bits
+                (write!(dest, "match client_format {{
+                                   ClientFormat::U32 => (gl::DEPTH_STENCIL,
+                                                         gl::UNSIGNED_INT_24_8),
+                                   _ => panic!(\"The data of a depth-stencil texture must be \
+                                                 made of `u32`s\")
+                               }}")).unwrap();
             },
         };
         (writeln!(dest, ";")).unwrap();
@@ -705,22 +470,32 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
 
             TextureDimensions::Texture1dArray => (write!(dest, "
                     let array_size = data.len() as u32;
-                    let mut width = 0;
+                    let mut width = None;
                     let data = data.into_iter().flat_map(|t| {{
-                        let d = t.into_vec(); width = d.len(); d.into_iter()
+                        let d = t.into_vec();
+                        if width.is_some() && width != Some(d.len()) {{
+                            panic!(\"All the layers of a texture array must have the same \
+                                    dimensions\");
+                        }}
+                        width = Some(d.len()); d.into_iter()
                     }}).collect();
-                    let width = width as u32;
-                ")).unwrap(),   // TODO: panic if dimensions are inconsistent
+                    let width = width.unwrap_or(0) as u32;
+                ")).unwrap(),
 
             TextureDimensions::Texture2dArray => (write!(dest, "
                     let array_size = data.len() as u32;
-                    let mut dimensions = (0, 0);
+                    let mut dimensions = None;
                     let data = data.into_iter().flat_map(|t| {{
-                        dimensions = t.get_dimensions(); t.into_vec().into_iter()
+                        let d = t.get_dimensions();
+                        if dimensions.is_some() && dimensions != Some(d) {{
+                            panic!(\"All the layers of a texture array must have the same \
+                                    dimensions\");
+                        }}
+                        dimensions = Some(d); t.into_vec().into_iter()
                     }}).collect();
-                    let (width, height) = dimensions;
+                    let (width, height) = dimensions.unwrap_or((0, 0));
                     let width = width as u32; let height = height as u32;
-                ")).unwrap(),   // TODO: panic if dimensions are inconsistent
+                ")).unwrap(),
         }
         // writing the constructor
         (write!(dest, "{}(TextureImplementation::new(display, format, Some(data), \
@@ -732,9 +507,9 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
             TextureDimensions::Texture1dArray => (write!(dest, "width, None, None, Some(array_size)")).unwrap(),
             TextureDimensions::Texture2dArray => (write!(dest, "width, Some(height), None, Some(array_size)")).unwrap(),
         }
-        (writeln!(dest, "))")).unwrap();
+        (writeln!(dest, ", mipmaps))")).unwrap();
 
-        // end of "new" function block
+        // end of "with_mipmaps" function block
         (writeln!(dest, "}}")).unwrap();
     }
 
@@ -750,12 +525,21 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
             TextureType::DepthStencil => "DepthStencilFormat",
         };
 
-        let dim_params = match dimensions {
-            TextureDimensions::Texture1d => "width: u32",
-            TextureDimensions::Texture2d => "width: u32, height: u32",
-            TextureDimensions::Texture3d => "width: u32, height: u32, depth: u32",
-            TextureDimensions::Texture1dArray => "width: u32, array_size: u32",
-            TextureDimensions::Texture2dArray => "width: u32, height: u32, array_size: u32",
+        let (dim_params, dim_args) = match dimensions {
+            TextureDimensions::Texture1d => ("width: u32", "width"),
+            TextureDimensions::Texture2d => ("width: u32, height: u32", "width, height"),
+            TextureDimensions::Texture3d => ("width: u32, height: u32, depth: u32",
+                                             "width, height, depth"),
+            TextureDimensions::Texture1dArray => ("width: u32, array_size: u32",
+                                                  "width, array_size"),
+            TextureDimensions::Texture2dArray => ("width: u32, height: u32, array_size: u32",
+                                                  "width, height, array_size"),
+        };
+
+        let default_mipmaps = match ty {
+            TextureType::Depth | TextureType::Stencil |
+            TextureType::DepthStencil => "MipmapsOption::NoMipmap",
+            _ => "MipmapsOption::AutoGeneratedMipmaps",
         };
 
         // opening function
@@ -764,12 +548,38 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                 ///
                 /// The texture will contain undefined data.
                 pub fn new_empty(display: &::Display, format: {format}, {dim_params}) -> {name} {{
+                    {name}::new_empty_with_mipmaps(display, format, {dim_args}, {default_mipmaps})
+                }}
+
+                /// Creates an empty texture, with the given mipmaps policy.
+                ///
+                /// The texture will contain undefined data.
+                ///
+                /// # Panic
+                ///
+                /// - Panics if mipmaps are automatically generated for a depth or stencil texture.
+                /// - Panics if the number of empty mipmaps is zero or too large for the dimensions.
+                pub fn new_empty_with_mipmaps(display: &::Display, format: {format}, {dim_params},
+                                              mipmaps: MipmapsOption) -> {name}
+                {{
                     let format = format.to_glenum();
-            ", format = format, dim_params = dim_params, name = name)).unwrap();
+            ", format = format, dim_params = dim_params, dim_args = dim_args, name = name,
+               default_mipmaps = default_mipmaps)).unwrap();
+
+        // the client format must be compatible with the internal format, even if no data is
+        // uploaded
+        let (client_format, client_type) = match ty {
+            TextureType::Regular | TextureType::Compressed => ("gl::RGBA", "gl::UNSIGNED_BYTE"),
+            TextureType::Integral => ("gl::RGBA_INTEGER", "gl::INT"),
+            TextureType::Unsigned => ("gl::RGBA_INTEGER", "gl::UNSIGNED_INT"),
+            TextureType::Depth => ("gl::DEPTH_COMPONENT", "gl::FLOAT"),
+            TextureType::Stencil => ("gl::STENCIL_INDEX", "gl::UNSIGNED_BYTE"),
+            TextureType::DepthStencil => ("gl::DEPTH_STENCIL", "gl::UNSIGNED_INT_24_8"),
+        };
 
         // writing the constructor
         (write!(dest, "{}(TextureImplementation::new::<u8>(display, format, None, \
-                       gl::RGBA, gl::UNSIGNED_BYTE, ", name)).unwrap();
+                       {}, {}, ", name, client_format, client_type)).unwrap();
         match dimensions {
             TextureDimensions::Texture1d => (write!(dest, "width, None, None, None")).unwrap(),
             TextureDimensions::Texture2d => (write!(dest, "width, Some(height), None, None")).unwrap(),
@@ -777,10 +587,665 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
             TextureDimensions::Texture1dArray => (write!(dest, "width, None, None, Some(array_size)")).unwrap(),
             TextureDimensions::Texture2dArray => (write!(dest, "width, Some(height), None, Some(array_size)")).unwrap(),
         }
-        (writeln!(dest, "))")).unwrap();
+        (writeln!(dest, ", mipmaps))")).unwrap();
 
         // closing function
         (writeln!(dest, "}}")).unwrap();
+
+        // writing the constructors that check the format
+        (writeln!(dest, "
+                /// Creates an empty texture, with the given mipmaps policy.
+                ///
+                /// Returns an error if the format is not supported by the backend, instead
+                /// of creating a texture that can't be used.
+                pub fn new_empty_checked(display: &::Display, format: {format}, {dim_params},
+                                         mipmaps: MipmapsOption)
+                                         -> Result<{name}, TextureCreationError>
+                {{
+                    if !format.is_supported(display) {{
+                        return Err(TextureCreationError::FormatNotSupported);
+                    }}
+
+                    Ok({name}::new_empty_with_mipmaps(display, format, {dim_args}, mipmaps))
+                }}
+
+                /// Creates an empty texture, with the given mipmaps policy.
+                ///
+                /// If the format is not supported by the backend, the closest supported format
+                /// is used instead. See `get_fallback` for the formats that are tried. Returns
+                /// the texture and the format that has been chosen, or an error if no format
+                /// is supported.
+                pub fn new_empty_with_fallback(display: &::Display, format: {format},
+                                               {dim_params}, mipmaps: MipmapsOption)
+                                               -> Result<({name}, {format}),
+                                                         TextureCreationError>
+                {{
+                    let mut format = format;
+
+                    while !format.is_supported(display) {{
+                        format = match format.get_fallback() {{
+                            Some(f) => f,
+                            None => return Err(TextureCreationError::FormatNotSupported),
+                        }};
+                    }}
+
+                    let texture = {name}::new_empty_with_mipmaps(display, format, {dim_args},
+                                                                 mipmaps);
+                    Ok((texture, format))
+                }}
+            ", format = format, dim_params = dim_params, dim_args = dim_args,
+               name = name)).unwrap();
+    }
+
+    // writing the functions that upload already-compressed data
+    if ty == TextureType::Compressed && dimensions == TextureDimensions::Texture2d {
+        (writeln!(dest, "
+                /// Builds a new texture from data that is already compressed with `format`.
+                ///
+                /// The texture will only have one mipmap level.
+                ///
+                /// Returns an error if `format` is not supported by the backend, or if the
+                /// length of `data` doesn't match the dimensions.
+                pub fn with_compressed_data(display: &::Display, data: Vec<u8>, width: u32,
+                                            height: u32, format: CompressedFormat)
+                                            -> Result<{name}, TextureCreationError>
+                {{
+                    {name}::with_compressed_mipmaps(display, vec![data], width, height, format)
+                }}
+
+                /// Builds a new texture from data that is already compressed with `format`,
+                /// including its mipmaps.
+                ///
+                /// Each element of `levels` is the content of a mipmap level, starting with the
+                /// largest one. Each level must be half the size of the previous one, rounded
+                /// down. It is possible to provide only some of the levels.
+                ///
+                /// Returns an error if `format` is not supported by the backend, or if the
+                /// length of a level doesn't match its dimensions.
+                ///
+                /// # Panic
+                ///
+                /// Panics if `levels` is empty.
+                pub fn with_compressed_mipmaps(display: &::Display, levels: Vec<Vec<u8>>,
+                                               width: u32, height: u32, format: CompressedFormat)
+                                               -> Result<{name}, TextureCreationError>
+                {{
+                    use std::cmp;
+
+                    if !format.is_supported(display) {{
+                        return Err(TextureCreationError::FormatNotSupported);
+                    }}
+
+                    if levels.len() == 0 {{
+                        panic!(\"At least one mipmap level must be provided\");
+                    }}
+
+                    let (mut level_width, mut level_height) = (width, height);
+                    for level in levels.iter() {{
+                        if level.len() != format.get_data_size(level_width, level_height) {{
+                            return Err(TextureCreationError::DataSizeMismatch);
+                        }}
+
+                        level_width = cmp::max(1, level_width / 2);
+                        level_height = cmp::max(1, level_height / 2);
+                    }}
+
+                    Ok({name}(TextureImplementation::new_compressed_2d(display,
+                                                                       format.to_glenum(),
+                                                                       width, height, levels)))
+                }}
+            ", name = name)).unwrap();
+    }
+
+    if ty == TextureType::Compressed && dimensions == TextureDimensions::Texture2dArray {
+        (writeln!(dest, "
+                /// Builds a new texture array from data that is already compressed with
+                /// `format`.
+                ///
+                /// Each element of `layers` is the content of a layer. The texture will only
+                /// have one mipmap level.
+                ///
+                /// Returns an error if `format` is not supported by the backend, or if the
+                /// length of a layer doesn't match the dimensions.
+                ///
+                /// # Panic
+                ///
+                /// Panics if `layers` is empty.
+                pub fn with_compressed_data(display: &::Display, layers: Vec<Vec<u8>>,
+                                            width: u32, height: u32, format: CompressedFormat)
+                                            -> Result<{name}, TextureCreationError>
+                {{
+                    let layers = layers.into_iter().map(|l| vec![l]).collect();
+                    {name}::with_compressed_mipmaps(display, layers, width, height, format)
+                }}
+
+                /// Builds a new texture array from data that is already compressed with
+                /// `format`, including the mipmaps of each layer.
+                ///
+                /// Each element of `layers` is the list of the mipmap levels of a layer,
+                /// starting with the largest one. Each level must be half the size of the
+                /// previous one, rounded down. All the layers must have the same number of
+                /// levels, but it is possible to provide only some of them.
+                ///
+                /// Returns an error if `format` is not supported by the backend, or if the
+                /// length of a level doesn't match its dimensions.
+                ///
+                /// # Panic
+                ///
+                /// Panics if `layers` is empty, or if the layers don't have the same number of
+                /// levels.
+                pub fn with_compressed_mipmaps(display: &::Display, layers: Vec<Vec<Vec<u8>>>,
+                                               width: u32, height: u32, format: CompressedFormat)
+                                               -> Result<{name}, TextureCreationError>
+                {{
+                    use std::cmp;
+
+                    if !format.is_supported(display) {{
+                        return Err(TextureCreationError::FormatNotSupported);
+                    }}
+
+                    if layers.len() == 0 {{
+                        panic!(\"At least one layer must be provided\");
+                    }}
+
+                    let levels_count = layers[0].len();
+                    if levels_count == 0 {{
+                        panic!(\"At least one mipmap level must be provided\");
+                    }}
+
+                    for levels in layers.iter() {{
+                        if levels.len() != levels_count {{
+                            panic!(\"All the layers must have the same number of mipmap levels\");
+                        }}
+
+                        let (mut level_width, mut level_height) = (width, height);
+                        for level in levels.iter() {{
+                            if level.len() != format.get_data_size(level_width, level_height) {{
+                                return Err(TextureCreationError::DataSizeMismatch);
+                            }}
+
+                            level_width = cmp::max(1, level_width / 2);
+                            level_height = cmp::max(1, level_height / 2);
+                        }}
+                    }}
+
+                    Ok({name}(TextureImplementation::new_compressed_layered(display,
+                                                                            gl::TEXTURE_2D_ARRAY,
+                                                                            format.to_glenum(),
+                                                                            width, height,
+                                                                            layers)))
+                }}
+
+                /// Replaces the content of a mipmap level of one of the layers with data that
+                /// is already compressed with `format`.
+                ///
+                /// `format` must be the format that was used to create the texture.
+                ///
+                /// # Panic
+                ///
+                /// - Panics if the texture doesn't have this level.
+                /// - Panics if the layer is outside of the array.
+                /// - Panics if the length of the data doesn't match the dimensions of the level.
+                pub fn write_compressed_layer(&self, layer: u32, level: u32, data: Vec<u8>,
+                                              format: CompressedFormat)
+                {{
+                    self.0.upload_compressed_layer(level, layer, format, data)
+                }}
+            ", name = name)).unwrap();
+    }
+
+    // writing the `empty` function
+    match ty {
+        TextureType::Depth | TextureType::Stencil | TextureType::DepthStencil => {
+            let default_format = match ty {
+                TextureType::Depth => "DepthFormat::I24",
+                TextureType::Stencil => "StencilFormat::I8",
+                TextureType::DepthStencil => "DepthStencilFormat::I24I8",
+                _ => unreachable!()
+            };
+
+            let (dim_params, dim_args) = match dimensions {
+                TextureDimensions::Texture1d => ("width: u32", "width"),
+                TextureDimensions::Texture2d => ("width: u32, height: u32", "width, height"),
+                TextureDimensions::Texture3d => ("width: u32, height: u32, depth: u32",
+                                                 "width, height, depth"),
+                TextureDimensions::Texture1dArray => ("width: u32, array_size: u32",
+                                                      "width, array_size"),
+                TextureDimensions::Texture2dArray => ("width: u32, height: u32, array_size: u32",
+                                                      "width, height, array_size"),
+            };
+
+            (writeln!(dest, "
+                    /// Creates an empty texture with the default format.
+                    ///
+                    /// The texture will contain undefined data. This is the same as calling
+                    /// `new_empty` with `{default_format}`.
+                    pub fn empty(display: &::Display, {dim_params}) -> {name} {{
+                        {name}::new_empty(display, {default_format}, {dim_args})
+                    }}
+                ", default_format = default_format, dim_params = dim_params,
+                   dim_args = dim_args, name = name)).unwrap();
+        },
+        _ => ()
+    }
+
+    // writing the `write` function
+    match ty {
+        TextureType::Regular | TextureType::Integral | TextureType::Unsigned => {
+            let client_format = match ty {
+                TextureType::Integral => "client_format.to_gl_enum_int().expect(\"Client format \
+                                          must have an integral format\")",
+                TextureType::Unsigned => "client_format.to_gl_enum_uint().expect(\"Client format \
+                                          must have an integral format\")",
+                _ => "client_format.to_gl_enum()",
+            };
+
+            match dimensions {
+                TextureDimensions::Texture1d => (write!(dest, "
+                        /// Uploads some data in the texture, starting at `offset`.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// Panics if the data doesn't fit in the texture.
+                        pub fn write<T>(&self, offset: u32, data: T) where T: Texture1dData {{
+                            let client_format = Texture1dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            let data = data.into_vec();
+                            let width = data.len() as u32;
+                            self.0.upload(0, offset, 0, 0, width, 1, 1, client_format,
+                                          client_type, data)
+                        }}
+
+                        /// Uploads some data in a mipmap level of the texture, starting at
+                        /// `offset`.
+                        ///
+                        /// Level `0` is the main level. This is useful to fill the levels of a
+                        /// texture created with `MipmapsOption::EmptyMipmaps`.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the texture doesn't have this level.
+                        /// - Panics if the data doesn't fit in the level.
+                        pub fn write_mipmap<T>(&self, level: u32, offset: u32, data: T)
+                                               where T: Texture1dData
+                        {{
+                            let client_format = Texture1dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            let data = data.into_vec();
+                            let width = data.len() as u32;
+                            self.0.upload(level, offset, 0, 0, width, 1, 1, client_format,
+                                          client_type, data)
+                        }}
+                    ", client_format = client_format)).unwrap(),
+
+                TextureDimensions::Texture2d => (write!(dest, "
+                        /// Uploads some data in the texture.
+                        ///
+                        /// Only the area described by `rect` is modified. The dimensions of the
+                        /// data must match the dimensions of the rectangle. This is useful for
+                        /// example to add glyphs to a texture atlas.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the rectangle is outside of the texture.
+                        /// - Panics if the dimensions of the data don't match the rectangle.
+                        pub fn write<T>(&self, rect: ::Rect, data: T) where T: Texture2dData {{
+                            if data.get_dimensions() != (rect.width, rect.height) {{
+                                panic!(\"The dimensions of the data must match the rectangle\");
+                            }}
+
+                            let client_format = Texture2dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(0, rect.left, rect.bottom, 0, rect.width, rect.height,
+                                          1, client_format, client_type, data.into_vec())
+                        }}
+
+                        /// Uploads some data in a mipmap level of the texture.
+                        ///
+                        /// Level `0` is the main level. This is useful to fill the levels of a
+                        /// texture created with `MipmapsOption::EmptyMipmaps`.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the texture doesn't have this level.
+                        /// - Panics if the rectangle is outside of the level.
+                        /// - Panics if the dimensions of the data don't match the rectangle.
+                        pub fn write_mipmap<T>(&self, level: u32, rect: ::Rect, data: T)
+                                               where T: Texture2dData
+                        {{
+                            if data.get_dimensions() != (rect.width, rect.height) {{
+                                panic!(\"The dimensions of the data must match the rectangle\");
+                            }}
+
+                            let client_format = Texture2dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(level, rect.left, rect.bottom, 0, rect.width,
+                                          rect.height, 1, client_format, client_type,
+                                          data.into_vec())
+                        }}
+
+                        /// Uploads some data in the texture, with the given layout of the data
+                        /// in memory.
+                        ///
+                        /// Only the area described by `rect` is modified. `data` contains the
+                        /// pixels of the rectangle laid out as described by `parameters`. This
+                        /// is useful for example to upload a part of a larger image, or the
+                        /// output of a video decoder whose rows are padded.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the rectangle is outside of the texture.
+                        /// - Panics if `data` is too small for the rectangle and the parameters.
+                        /// - Panics if the alignment is not 1, 2, 4 or 8.
+                        /// - Panics if the row length or the origin of the data are used and the
+                        ///   backend doesn't support them.
+                        pub fn write_with_parameters<P>(&self, rect: ::Rect, data: &[P],
+                                                        parameters: &UploadParameters)
+                                                        where P: PixelValue + Clone + Send
+                        {{
+                            use std::mem;
+
+                            if !rect.is_inside((self.get_width(), self.get_height().unwrap())) {{
+                                panic!(\"The area to write is outside of the texture\");
+                            }}
+
+                            let client_format = PixelValue::get_format(None::<P>);
+                            let size = parameters.get_data_size(rect.width, rect.height,
+                                                                client_format.get_size());
+                            if data.len() * mem::size_of::<P>() < size {{
+                                panic!(\"The data is too small for the upload parameters\");
+                            }}
+
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload_2d_with_parameters(0, rect.left, rect.bottom,
+                                                             rect.width, rect.height,
+                                                             client_format, client_type,
+                                                             data.to_vec(), *parameters)
+                        }}
+
+                        /// Uploads some data in the texture without waiting for the GPU.
+                        ///
+                        /// The data is copied into a pixel buffer, and the GPU copies it into
+                        /// the texture asynchronously. This avoids stalling the rendering when
+                        /// the texture is still being used by previous draws, which is useful
+                        /// for large updates like video frames. Use the returned handle to
+                        /// know when the texture contains the new data.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated once the copy is finished.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the rectangle is outside of the texture.
+                        /// - Panics if the dimensions of the data don't match the rectangle.
+                        pub fn write_async<T>(&self, rect: ::Rect, data: T) -> TextureUpload
+                                              where T: Texture2dData
+                        {{
+                            if data.get_dimensions() != (rect.width, rect.height) {{
+                                panic!(\"The dimensions of the data must match the rectangle\");
+                            }}
+
+                            let client_format = Texture2dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload_2d_async(rect.left, rect.bottom, rect.width,
+                                                   rect.height, client_format, client_type,
+                                                   data.into_vec());
+                            TextureUpload::new(self.0.get_display())
+                        }}
+                    ", client_format = client_format)).unwrap(),
+
+                TextureDimensions::Texture3d => (write!(dest, "
+                        /// Uploads some data in the texture, starting at `offset`.
+                        ///
+                        /// `offset` contains the X, Y and Z coordinates of the first pixel
+                        /// to write.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// Panics if the data doesn't fit in the texture.
+                        pub fn write<T>(&self, offset: (u32, u32, u32), data: T)
+                                        where T: Texture3dData
+                        {{
+                            let (width, height, depth) = data.get_dimensions();
+                            let client_format = Texture3dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(0, offset.0, offset.1, offset.2, width, height,
+                                          depth, client_format, client_type, data.into_vec())
+                        }}
+
+                        /// Uploads some data in a mipmap level of the texture, starting at
+                        /// `offset`.
+                        ///
+                        /// Level `0` is the main level. This is useful to fill the levels of a
+                        /// texture created with `MipmapsOption::EmptyMipmaps`.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the texture doesn't have this level.
+                        /// - Panics if the data doesn't fit in the level.
+                        pub fn write_mipmap<T>(&self, level: u32, offset: (u32, u32, u32),
+                                               data: T) where T: Texture3dData
+                        {{
+                            let (width, height, depth) = data.get_dimensions();
+                            let client_format = Texture3dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(level, offset.0, offset.1, offset.2, width, height,
+                                          depth, client_format, client_type, data.into_vec())
+                        }}
+                    ", client_format = client_format)).unwrap(),
+
+                TextureDimensions::Texture2dArray => (write!(dest, "
+                        /// Uploads some data in one of the layers of the array.
+                        ///
+                        /// Only the area described by `rect` is modified. The dimensions of the
+                        /// data must match the dimensions of the rectangle.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the layer is outside of the array.
+                        /// - Panics if the rectangle is outside of the texture.
+                        /// - Panics if the dimensions of the data don't match the rectangle.
+                        pub fn write<T>(&self, layer: u32, rect: ::Rect, data: T)
+                                        where T: Texture2dData
+                        {{
+                            if layer >= self.get_array_size().unwrap() {{
+                                panic!(\"The layer {{}} is outside of the texture array\", layer);
+                            }}
+
+                            if data.get_dimensions() != (rect.width, rect.height) {{
+                                panic!(\"The dimensions of the data must match the rectangle\");
+                            }}
+
+                            let client_format = Texture2dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(0, rect.left, rect.bottom, layer, rect.width,
+                                          rect.height, 1, client_format, client_type,
+                                          data.into_vec())
+                        }}
+
+                        /// Uploads some data in a mipmap level of one of the layers of the
+                        /// array.
+                        ///
+                        /// Level `0` is the main level. This is useful to fill the levels of a
+                        /// texture created with `MipmapsOption::EmptyMipmaps`.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the texture doesn't have this level.
+                        /// - Panics if the layer is outside of the array.
+                        /// - Panics if the rectangle is outside of the level.
+                        /// - Panics if the dimensions of the data don't match the rectangle.
+                        pub fn write_mipmap<T>(&self, level: u32, layer: u32, rect: ::Rect,
+                                               data: T) where T: Texture2dData
+                        {{
+                            if layer >= self.get_array_size().unwrap() {{
+                                panic!(\"The layer {{}} is outside of the texture array\", layer);
+                            }}
+
+                            if data.get_dimensions() != (rect.width, rect.height) {{
+                                panic!(\"The dimensions of the data must match the rectangle\");
+                            }}
+
+                            let client_format = Texture2dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            self.0.upload(level, rect.left, rect.bottom, layer, rect.width,
+                                          rect.height, 1, client_format, client_type,
+                                          data.into_vec())
+                        }}
+                    ", client_format = client_format)).unwrap(),
+
+                TextureDimensions::Texture1dArray => (write!(dest, "
+                        /// Uploads some data in one of the layers of the array, starting at
+                        /// `offset`.
+                        ///
+                        /// If the other mipmap levels are generated from the main level, they
+                        /// are regenerated.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the layer is outside of the array.
+                        /// - Panics if the data doesn't fit in the texture.
+                        pub fn write<T>(&self, layer: u32, offset: u32, data: T)
+                                        where T: Texture1dData
+                        {{
+                            self.write_mipmap(0, layer, offset, data)
+                        }}
+
+                        /// Uploads some data in a mipmap level of one of the layers of the
+                        /// array, starting at `offset`.
+                        ///
+                        /// Level `0` is the main level. This is useful to fill the levels of a
+                        /// texture created with `MipmapsOption::EmptyMipmaps`.
+                        ///
+                        /// # Panic
+                        ///
+                        /// - Panics if the texture doesn't have this level.
+                        /// - Panics if the layer is outside of the array.
+                        /// - Panics if the data doesn't fit in the level.
+                        pub fn write_mipmap<T>(&self, level: u32, layer: u32, offset: u32,
+                                               data: T) where T: Texture1dData
+                        {{
+                            if layer >= self.get_array_size().unwrap() {{
+                                panic!(\"The layer {{}} is outside of the texture array\", layer);
+                            }}
+
+                            let client_format = Texture1dData::get_format(None::<T>);
+                            let (client_format, client_type) = {client_format};
+                            let data = data.into_vec();
+                            let width = data.len() as u32;
+                            self.0.upload(level, offset, layer, 0, width, 1, 1, client_format,
+                                          client_type, data)
+                        }}
+                    ", client_format = client_format)).unwrap(),
+            }
+        },
+        _ => ()
+    }
+
+    // writing the functions that build views
+    {
+        let bind_point = match dimensions {
+            TextureDimensions::Texture1d => "gl::TEXTURE_1D",
+            TextureDimensions::Texture2d => "gl::TEXTURE_2D",
+            TextureDimensions::Texture3d => "gl::TEXTURE_3D",
+            TextureDimensions::Texture1dArray => "gl::TEXTURE_1D_ARRAY",
+            TextureDimensions::Texture2dArray => "gl::TEXTURE_2D_ARRAY",
+        };
+
+        (write!(dest, "
+                /// Builds a view of some of the mipmap levels of the texture, without copying.
+                ///
+                /// Level `first` of the texture becomes the main level of the view.
+                ///
+                /// Returns `None` if the backend doesn't support texture views, or if the
+                /// storage of the texture is not immutable.
+                ///
+                /// # Panic
+                ///
+                /// Panics if `count` is zero or if the levels are out of range.
+                pub fn view_mipmaps(&self, first: u32, count: u32)
+                                    -> Option<TextureView<{name}>>
+                {{
+                    if count == 0 || first + count > self.get_mipmap_levels() {{
+                        panic!(\"The mipmap levels are out of range\");
+                    }}
+
+                    let layers = self.get_array_size().unwrap_or(1);
+                    self.0.new_view({bind_point}, None, first, count, 0, layers)
+                          .map(|t| TextureView({name}(t)))
+                }}
+            ", name = name, bind_point = bind_point)).unwrap();
+
+        let format = match ty {
+            TextureType::Regular => Some("UncompressedFloatFormat"),
+            TextureType::Integral => Some("UncompressedIntFormat"),
+            TextureType::Unsigned => Some("UncompressedUintFormat"),
+            _ => None,
+        };
+
+        if let Some(format) = format {
+            (write!(dest, "
+                    /// Builds a view of the texture that interprets its content with another
+                    /// format, without copying.
+                    ///
+                    /// The two formats must have the same size per texel. For example a view
+                    /// with `U8U8U8U8` of a texture created with `SrgbAlpha8` is sampled
+                    /// without the conversion from sRGB to linear.
+                    ///
+                    /// Returns `None` if the backend doesn't support texture views, if the
+                    /// storage of the texture is not immutable, or if the formats are not
+                    /// compatible.
+                    pub fn view_as(&self, format: {format}) -> Option<TextureView<{name}>> {{
+                        let levels = self.get_mipmap_levels();
+                        let layers = self.get_array_size().unwrap_or(1);
+                        self.0.new_view({bind_point}, Some(format.to_glenum()), 0, levels, 0,
+                                        layers).map(|t| TextureView({name}(t)))
+                    }}
+                ", format = format, name = name, bind_point = bind_point)).unwrap();
+        }
+
+        let layer = match dimensions {
+            TextureDimensions::Texture1dArray => Some("gl::TEXTURE_1D"),
+            TextureDimensions::Texture2dArray => Some("gl::TEXTURE_2D"),
+            _ => None,
+        };
+
+        if let Some(layer_bind_point) = layer {
+            (write!(dest, "
+                    /// Builds a view of one layer of the array, without copying.
+                    ///
+                    /// Returns `None` if the backend doesn't support texture views, or if the
+                    /// storage of the texture is not immutable.
+                    ///
+                    /// # Panic
+                    ///
+                    /// Panics if the layer is out of range.
+                    pub fn view_layer(&self, layer: u32) -> Option<TextureView<{element}>> {{
+                        if layer >= self.get_array_size().unwrap() {{
+                            panic!(\"The layer is out of range\");
+                        }}
+
+                        let levels = self.get_mipmap_levels();
+                        self.0.new_view({bind_point}, None, 0, levels, layer, 1)
+                              .map(|t| TextureView({element}(t)))
+                    }}
+                ", element = name.replace("Array", ""), bind_point = layer_bind_point)).unwrap();
+        }
     }
 
     // writing the `as_surface` function
@@ -800,6 +1265,64 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                 pub fn as_surface<'a>(&'a self) -> TextureSurface<'a> {{
                     TextureSurface(framebuffer::SimpleFrameBuffer::new(self.0.get_display(), self))
                 }}
+
+                /// Starts drawing on the texture, using `depth` as the depth buffer.
+                ///
+                /// Returns an error if the depth buffer doesn't have the same dimensions as the
+                /// texture.
+                pub fn as_surface_with_depth_buffer<'a, D>(&'a self, depth: &'a D)
+                    -> Result<TextureSurface<'a>, framebuffer::FramebufferValidationError>
+                    where D: framebuffer::ToDepthAttachment
+                {{
+                    let framebuffer = try!(framebuffer::SimpleFrameBuffer::with_depth_buffer(
+                                           self.0.get_display(), self, depth));
+                    Ok(TextureSurface(framebuffer))
+                }}
+
+                /// Copies a rectangle of the texture to another texture, without going
+                /// through the CPU.
+                ///
+                /// `source_rect` is the area of this texture to copy, and `target_offset` is the
+                /// position in `target` where the bottom-left corner of the area is copied.
+                ///
+                /// This uses `glCopyImageSubData` if the backend supports it and if the
+                /// texels of the two textures have the same size, and a blit otherwise. A blit
+                /// converts the texels to the format of the target.
+                ///
+                /// # Panic
+                ///
+                /// Panics if the area is outside of one of the two textures.
+                pub fn copy_to(&self, target: &Texture2d, source_rect: ::Rect,
+                               target_offset: (u32, u32))
+                {{
+                    if !source_rect.is_inside((self.get_width(), self.get_height().unwrap())) {{
+                        panic!(\"The source rectangle is outside of the texture\");
+                    }}
+
+                    if target_offset.0 + source_rect.width > target.get_width() ||
+                       target_offset.1 + source_rect.height > target.get_height().unwrap()
+                    {{
+                        panic!(\"The copied area is outside of the target texture\");
+                    }}
+
+                    if self.0.copy_image_2d(&target.0, source_rect.left, source_rect.bottom,
+                                            source_rect.width, source_rect.height,
+                                            target_offset.0, target_offset.1)
+                    {{
+                        return;
+                    }}
+
+                    let target_rect = ::Rect {{
+                        left: target_offset.0,
+                        bottom: target_offset.1,
+                        width: source_rect.width,
+                        height: source_rect.height,
+                    }};
+
+                    self.as_surface().blit_color(&source_rect, &target.as_surface(),
+                                                 &target_rect,
+                                                 ::uniforms::MagnifySamplerFilter::Nearest);
+                }}
             ")).unwrap();
     }
 
@@ -822,21 +1345,35 @@ fn build_texture<W: Writer>(mut dest: &mut W, ty: TextureType, dimensions: Textu
                 ),
         };
 
+        // integral textures must be read with the `_INTEGER` formats
+        let client_format = match ty {
+            TextureType::Integral => "client_format.to_gl_enum_int().expect(\"Client format \
+                                      must have an integral format\")",
+            TextureType::Unsigned => "client_format.to_gl_enum_uint().expect(\"Client format \
+                                      must have an integral format\")",
+            _ => "client_format.to_gl_enum()",
+        };
+
         (write!(dest, r#"
                 /// Reads the content of the texture.
                 ///
+                /// Use `f32` or `f16` pixels to read floating-point textures without losing
+                /// precision.
+                ///
                 /// # Features
                 ///
                 /// This method is always only if the `gl_extensions` feature is enabled.
                 #[cfg(feature = "gl_extensions")]
                 pub fn read<P, T>(&self) -> T where T: {data_type}<Data = P>, P: PixelValue {{
-                    let data = self.0.read::<P>(0);
+                    let client_format = PixelValue::get_format(None::<P>);
+                    let (client_format, client_type) = {client_format};
+                    let data = self.0.read_with_format::<P>(0, client_format, client_type);
                     {constructor}
                 }}
-            "#, data_type = data_type, constructor = constructor)).unwrap();
+            "#, data_type = data_type, constructor = constructor,
+                client_format = client_format)).unwrap();
     }
 
     // closing `impl Texture` block
     (writeln!(dest, "}}")).unwrap();
->>>>>>> upstream/master
 }
@@ -106,8 +106,9 @@ fn main() {
         // drawing a frame
         let mut target = display.draw();
         target.clear_color(0.0, 0.0, 0.0, 0.0);
-        target.draw(&vertex_buffer, &index_buffer, &program, &uniforms, &std::default::Default::default());
-        target.finish();
+        target.draw(&vertex_buffer, &index_buffer, &program, &uniforms,
+                    &std::default::Default::default()).unwrap();
+        target.finish().unwrap();
 
         // sleeping for some time in order not to use up too much CPU
         timer::sleep(Duration::milliseconds(17));
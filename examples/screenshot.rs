@@ -104,8 +104,9 @@ fn main() {
     // drawing a frame
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vertex_buffer, &index_buffer, &program, &uniforms, &std::default::Default::default());
-    target.finish();
+    target.draw(&vertex_buffer, &index_buffer, &program, &uniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
 
     // reading the front buffer into an image
     let image: image::DynamicImage = display.read_front_buffer();
@@ -43,31 +43,9 @@ pub fn expand(ecx: &mut base::ExtCtxt, span: codemap::Span,
         statements
     };
 
-    // implementing "Copy" if necessary
-    // TODO: VERY HACKY
-    {
-        let mut found = false;
-
-        for attr in item.attrs.iter() {
-            let ref attr = attr.node;
-            let src = ::syntax::ext::quote::rt::ToSource::to_source(attr);
-
-            if src.as_slice().contains("deriving") && src.as_slice().contains("Copy") {
-                found = true;
-                break;
-            }
-        }
-
-        if !found {
-            push.call_mut((quote_item!(ecx,
-                impl $struct_generics Copy for $struct_name $struct_generics {}
-            ).unwrap(),));
-        }
-    }
-
     push.call_mut((quote_item!(ecx,
         impl $struct_generics ::glium::uniforms::Uniforms for $struct_name $struct_generics {
-            fn visit_values<F: FnMut(&str, &::glium::uniforms::UniformValue)>(self, mut output: F) {
+            fn visit_values<F: FnMut(&str, &::glium::uniforms::UniformValue)>(&self, mut output: F) {
                 $statements
             }
         }
@@ -1,4 +1,5 @@
 use syntax::ast;
+use syntax::attr::AttrMetaMethods;
 use syntax::ext::base;
 use syntax::ext::build::AstBuilder;
 use syntax::ext::deriving::generic;
@@ -67,6 +68,10 @@ fn body(ecx: &mut base::ExtCtxt, span: codemap::Span,
                     let ident_str = token::get_ident(ident);
                     let ident_str = ident_str.get();
 
+                    // `#[normalized]` fields are converted to floats by OpenGL
+                    let normalize = def.node.attrs.iter().any(|a| a.check_name("normalized"));
+                    let normalize = ecx.expr_bool(span, normalize);
+
                     quote_expr!(ecx, {
                         let offset = {
                             let dummy: &$self_ty = unsafe { mem::transmute(0u) };
@@ -79,6 +84,7 @@ fn body(ecx: &mut base::ExtCtxt, span: codemap::Span,
                             $ident_str.to_string(),
                             offset,
                             Attribute::get_type(None::<$elem_type>),
+                            $normalize,
                         ));
                     })
 
@@ -1,9 +1,10 @@
-use context::{mod, GlVersion};
+use context;
 use gl;
 use libc;
 use std::c_vec::CVec;
 use std::{fmt, mem, ptr};
 use std::sync::Arc;
+use std::sync::atomic::Relaxed;
 use GlObject;
 
 /// A buffer in the graphics card's memory.
@@ -12,6 +13,9 @@ pub struct Buffer {
     id: gl::types::GLuint,
     elements_size: uint,
     elements_count: uint,
+    persistent_mapping: Option<ptr::Unique<libc::c_void>>,
+    // false if the buffer has been created outside of glium and must not be deleted
+    owned: bool,
 }
 
 /// Type of a buffer.
@@ -68,6 +72,36 @@ impl BufferType for PixelUnpackBuffer {
     }
 }
 
+/// Used for buffer textures.
+pub struct TextureBuffer;
+
+impl BufferType for TextureBuffer {
+    fn get_storage_point(_: Option<TextureBuffer>, state: &mut context::GLState)
+        -> &mut gl::types::GLuint
+    {
+        &mut state.texture_buffer_binding
+    }
+
+    fn get_bind_point(_: Option<TextureBuffer>) -> gl::types::GLenum {
+        gl::TEXTURE_BUFFER
+    }
+}
+
+/// Used for draw commands buffers.
+pub struct DrawIndirectBuffer;
+
+impl BufferType for DrawIndirectBuffer {
+    fn get_storage_point(_: Option<DrawIndirectBuffer>, state: &mut context::GLState)
+        -> &mut gl::types::GLuint
+    {
+        &mut state.draw_indirect_buffer_binding
+    }
+
+    fn get_bind_point(_: Option<DrawIndirectBuffer>) -> gl::types::GLenum {
+        gl::DRAW_INDIRECT_BUFFER
+    }
+}
+
 impl Buffer {
     pub fn new<T, D>(display: &super::Display, data: Vec<D>, usage: gl::types::GLenum)
         -> Buffer where T: BufferType, D: Send + Copy
@@ -87,36 +121,133 @@ impl Buffer {
 
         let (tx, rx) = channel();
 
+        display.context.context.exec(move |: ctxt| {
+            let data = data;
+
+            unsafe {
+                let mut id: gl::types::GLuint = mem::uninitialized();
+                let mut obtained_size: gl::types::GLint = mem::uninitialized();
+
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.CreateBuffers(1, &mut id);
+                    tx.send(id);
+
+                    ctxt.gl.NamedBufferData(id, buffer_size as gl::types::GLsizeiptr,
+                                            data.as_ptr() as *const libc::c_void, usage);
+                    ctxt.gl.GetNamedBufferParameteriv(id, gl::BUFFER_SIZE, &mut obtained_size);
+
+                } else {
+                    ctxt.gl.GenBuffers(1, &mut id);
+                    tx.send(id);
+
+                    let storage = BufferType::get_storage_point(None::<T>, ctxt.state);
+                    let bind = BufferType::get_bind_point(None::<T>);
+
+                    ctxt.gl.BindBuffer(bind, id);
+                    *storage = id;
+                    ctxt.gl.BufferData(bind, buffer_size as gl::types::GLsizeiptr,
+                                       data.as_ptr() as *const libc::c_void, usage);
+                    ctxt.gl.GetBufferParameteriv(bind, gl::BUFFER_SIZE, &mut obtained_size);
+                }
+
+                ctxt.state.statistics.buffer_uploads += 1;
+                ctxt.state.statistics.bytes_transferred += buffer_size;
+
+                if buffer_size != obtained_size as uint {
+                    ctxt.gl.DeleteBuffers(1, [id].as_ptr());
+                    panic!("Not enough available memory for buffer");
+                }
+            }
+        });
+
+        Buffer {
+            display: display.context.clone(),
+            id: rx.recv(),
+            elements_size: elements_size,
+            elements_count: elements_count,
+            persistent_mapping: None,
+            owned: true,
+        }
+    }
+
+    /// Builds a buffer whose content is permanently mapped in memory.
+    ///
+    /// Returns `None` if persistent mapping is not supported by the backend.
+    pub fn new_persistent<T, D>(display: &super::Display, data: Vec<D>) -> Option<Buffer>
+                                where T: BufferType, D: Send + Copy
+    {
+        if !display.context.context.capabilities().buffer_storage {
+            return None;
+        }
+
+        let elements_size = if data.len() <= 1 {
+            mem::size_of::<D>()
+        } else {
+            let d0: *const D = &data[0];
+            let d1: *const D = &data[1];
+            (d1 as uint) - (d0 as uint)
+        };
+
+        let elements_count = data.len();
+        let buffer_size = elements_count * elements_size as uint;
+
+        let (tx, rx) = channel();
+
         display.context.context.exec(move |: ctxt| {
             let data = data;
 
             unsafe {
                 let mut id: gl::types::GLuint = mem::uninitialized();
                 ctxt.gl.GenBuffers(1, &mut id);
-                tx.send(id);
 
                 let storage = BufferType::get_storage_point(None::<T>, ctxt.state);
                 let bind = BufferType::get_bind_point(None::<T>);
 
+                let flags = gl::MAP_READ_BIT | gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT |
+                            gl::MAP_COHERENT_BIT;
+
                 ctxt.gl.BindBuffer(bind, id);
                 *storage = id;
-                ctxt.gl.BufferData(bind, buffer_size as gl::types::GLsizeiptr,
-                                   data.as_ptr() as *const libc::c_void, usage);
-
-                let mut obtained_size: gl::types::GLint = mem::uninitialized();
-                ctxt.gl.GetBufferParameteriv(bind, gl::BUFFER_SIZE, &mut obtained_size);
-                if buffer_size != obtained_size as uint {
+                ctxt.gl.BufferStorage(bind, buffer_size as gl::types::GLsizeiptr,
+                                      data.as_ptr() as *const libc::c_void, flags);
+                ctxt.state.statistics.buffer_uploads += 1;
+                ctxt.state.statistics.bytes_transferred += buffer_size;
+
+                let ptr = ctxt.gl.MapBufferRange(bind, 0, buffer_size as gl::types::GLsizeiptr,
+                                                 flags);
+                if ptr.is_null() {
                     ctxt.gl.DeleteBuffers(1, [id].as_ptr());
                     panic!("Not enough available memory for buffer");
                 }
+
+                tx.send((id, ptr::Unique(ptr)));
             }
         });
 
+        let (id, ptr) = rx.recv();
+
+        Some(Buffer {
+            display: display.context.clone(),
+            id: id,
+            elements_size: elements_size,
+            elements_count: elements_count,
+            persistent_mapping: Some(ptr),
+            owned: true,
+        })
+    }
+
+    /// Wraps a buffer that has been created outside of glium. If `owned` is true, the buffer
+    /// is deleted when the object is destroyed.
+    pub unsafe fn from_id(display: &super::Display, id: gl::types::GLuint, elements_size: uint,
+                          elements_count: uint, owned: bool) -> Buffer
+    {
         Buffer {
             display: display.context.clone(),
-            id: rx.recv(),
+            id: id,
             elements_size: elements_size,
             elements_count: elements_count,
+            persistent_mapping: None,
+            owned: owned,
         }
     }
 
@@ -124,22 +255,46 @@ impl Buffer {
                         usage: gl::types::GLenum) -> Buffer where T: BufferType
     {
         let buffer_size = elements_count * elements_size as uint;
+        let zero_initialize = display.context.zero_initialization.load(Relaxed);
 
         let (tx, rx) = channel();
         display.context.context.exec(move |: ctxt| {
             unsafe {
                 let mut id: gl::types::GLuint = mem::uninitialized();
-                ctxt.gl.GenBuffers(1, &mut id);
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.CreateBuffers(1, &mut id);
+                } else {
+                    ctxt.gl.GenBuffers(1, &mut id);
+                }
 
                 let storage = BufferType::get_storage_point(None::<T>, ctxt.state);
                 let bind = BufferType::get_bind_point(None::<T>);
 
-                ctxt.gl.BindBuffer(bind, id);
-                *storage = id;
-                ctxt.gl.BufferData(bind, buffer_size as gl::types::GLsizeiptr, ptr::null(), usage);
+                // the content of the buffer is undefined if no data is passed
+                let zeroes = if zero_initialize {
+                    Some(Vec::from_elem(buffer_size, 0u8))
+                } else {
+                    None
+                };
+
+                let data = match zeroes {
+                    Some(ref zeroes) => zeroes.as_ptr() as *const libc::c_void,
+                    None => ptr::null(),
+                };
 
                 let mut obtained_size: gl::types::GLint = mem::uninitialized();
-                ctxt.gl.GetBufferParameteriv(bind, gl::BUFFER_SIZE, &mut obtained_size);
+
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.NamedBufferData(id, buffer_size as gl::types::GLsizeiptr, data, usage);
+                    ctxt.gl.GetNamedBufferParameteriv(id, gl::BUFFER_SIZE, &mut obtained_size);
+
+                } else {
+                    ctxt.gl.BindBuffer(bind, id);
+                    *storage = id;
+                    ctxt.gl.BufferData(bind, buffer_size as gl::types::GLsizeiptr, data, usage);
+                    ctxt.gl.GetBufferParameteriv(bind, gl::BUFFER_SIZE, &mut obtained_size);
+                }
+
                 if buffer_size != obtained_size as uint {
                     ctxt.gl.DeleteBuffers(1, [id].as_ptr());
                     panic!("Not enough available memory for buffer");
@@ -154,6 +309,8 @@ impl Buffer {
             id: rx.recv(),
             elements_size: elements_size,
             elements_count: elements_count,
+            persistent_mapping: None,
+            owned: true,
         }
     }
 
@@ -173,6 +330,11 @@ impl Buffer {
         self.elements_count * self.elements_size
     }
 
+    /// Returns true if the content of the buffer is permanently mapped in memory.
+    pub fn is_persistent(&self) -> bool {
+        self.persistent_mapping.is_some()
+    }
+
     /// Offset and size are in number of elements
     pub fn map<'a, T, D>(&'a mut self, offset: uint, size: uint)
                          -> Mapping<'a, T, D> where T: BufferType, D: Send
@@ -184,12 +346,23 @@ impl Buffer {
             panic!("Trying to map out of range of buffer");
         }
 
+        // persistent buffers are already mapped
+        if let Some(ptr) = self.persistent_mapping {
+            let ptr = (ptr.0 as uint + offset * self.elements_size) as *mut D;
+
+            return Mapping {
+                buffer: self,
+                data: unsafe { CVec::new(ptr, size) },
+                persistent: true,
+            };
+        }
+
         let offset_bytes = offset * self.elements_size;
         let size_bytes = size * self.elements_size;
 
         self.display.context.exec(move |: ctxt| {
             let ptr = unsafe {
-                if ctxt.version >= &GlVersion(4, 5) {
+                if ctxt.capabilities.direct_state_access {
                     ctxt.gl.MapNamedBufferRange(id, offset_bytes as gl::types::GLintptr,
                                                 size_bytes as gl::types::GLsizei,
                                                 gl::MAP_READ_BIT | gl::MAP_WRITE_BIT)
@@ -212,6 +385,7 @@ impl Buffer {
         Mapping {
             buffer: self,
             data: unsafe { CVec::new(rx.recv().0, size) },
+            persistent: false,
         }
     }
 
@@ -239,7 +413,7 @@ impl Buffer {
                 let mut data = Vec::with_capacity(size);
                 data.set_len(size);
 
-                if !ctxt.opengl_es && ctxt.version >= &GlVersion(4, 5) {
+                if ctxt.capabilities.direct_state_access {
                     ctxt.gl.GetNamedBufferSubData(id, (offset * elements_size) as gl::types::GLintptr,
                         (size * elements_size) as gl::types::GLsizei,
                         data.as_mut_ptr() as *mut libc::c_void);
@@ -255,12 +429,53 @@ impl Buffer {
                         data.as_mut_ptr() as *mut libc::c_void);
                 }
 
+                ctxt.state.statistics.bytes_transferred += size * elements_size;
                 tx.send_opt(data).ok();
             }
         });
 
         rx.recv()
     }
+
+    /// Replaces a part of the content of the buffer. The offset is in number of elements.
+    ///
+    /// The backend waits for the commands that are still using the buffer, so you should
+    /// make sure that they are finished beforehand to avoid a stall.
+    pub fn upload<T, D>(&self, offset: uint, data: Vec<D>) where T: BufferType, D: Send + Copy {
+        let offset_bytes = offset * self.elements_size;
+        let size_bytes = data.len() * mem::size_of::<D>();
+
+        if offset_bytes + size_bytes > self.get_total_size() {
+            panic!("Trying to upload out of range of buffer");
+        }
+
+        let id = self.id.clone();
+
+        self.display.context.exec(move |: ctxt| {
+            let data = data;
+
+            unsafe {
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.NamedBufferSubData(id, offset_bytes as gl::types::GLintptr,
+                                               size_bytes as gl::types::GLsizei,
+                                               data.as_ptr() as *const libc::c_void);
+
+                } else {
+                    let storage = BufferType::get_storage_point(None::<T>, ctxt.state);
+                    let bind = BufferType::get_bind_point(None::<T>);
+
+                    ctxt.gl.BindBuffer(bind, id);
+                    *storage = id;
+                    ctxt.gl.BufferSubData(bind, offset_bytes as gl::types::GLintptr,
+                                          size_bytes as gl::types::GLsizeiptr,
+                                          data.as_ptr() as *const libc::c_void);
+                }
+
+                ctxt.state.statistics.buffer_uploads += 1;
+                ctxt.state.statistics.bytes_transferred += size_bytes;
+            }
+        });
+    }
 }
 
 impl fmt::Show for Buffer {
@@ -271,22 +486,12 @@ impl fmt::Show for Buffer {
 
 impl Drop for Buffer {
     fn drop(&mut self) {
-        let id = self.id.clone();
-        self.display.context.exec(move |: ctxt| {
-            if ctxt.state.array_buffer_binding == id {
-                ctxt.state.array_buffer_binding = 0;
-            }
-
-            if ctxt.state.pixel_pack_buffer_binding == id {
-                ctxt.state.pixel_pack_buffer_binding = 0;
-            }
-
-            if ctxt.state.pixel_unpack_buffer_binding == id {
-                ctxt.state.pixel_unpack_buffer_binding = 0;
-            }
-
-            unsafe { ctxt.gl.DeleteBuffers(1, [ id ].as_ptr()); }
-        });
+        // also removes the VAOs which contain this buffer
+        if self.owned {
+            self.display.delete_object(context::Deletion::Buffer(self.id));
+        } else {
+            self.display.forget_object(context::Deletion::Buffer(self.id));
+        }
     }
 }
 
@@ -300,15 +505,21 @@ impl GlObject for Buffer {
 pub struct Mapping<'b, T, D> {
     buffer: &'b mut Buffer,
     data: CVec<D>,
+    persistent: bool,
 }
 
 #[unsafe_destructor]
 impl<'a, T, D> Drop for Mapping<'a, T, D> where T: BufferType {
     fn drop(&mut self) {
+        // persistent mappings stay valid until the buffer is destroyed
+        if self.persistent {
+            return;
+        }
+
         let id = self.buffer.id.clone();
         self.buffer.display.context.exec(move |: ctxt| {
             unsafe {
-                if ctxt.version >= &GlVersion(4, 5) {
+                if ctxt.capabilities.direct_state_access {
                     ctxt.gl.UnmapNamedBuffer(id);
 
                 } else {
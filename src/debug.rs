@@ -4,7 +4,8 @@
 */
 
 use Display;
-use {context, gl};
+use GlObject;
+use {context, gl, libc};
 
 /// Severity of a debug message.
 #[deriving(Clone, Copy, Show, FromPrimitive, PartialEq, Eq)]
@@ -172,3 +173,83 @@ impl TimestampQuery {
         rx.recv()
     }
 }
+
+impl GlObject for TimestampQuery {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.id
+    }
+}
+
+/// A frame that the GPU hasn't finished executing in time.
+///
+/// See `Display::set_gpu_watchdog`.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub struct SlowFrame {
+    /// Number of the frame. The first frame that ends after the watchdog has been enabled
+    /// has the number 0.
+    pub frame: u64,
+
+    /// Number of nanoseconds that the GPU has spent executing the frame, measured between
+    /// the timestamps of its start and of its end. If the frame was still being executed
+    /// when it was reported, this is the time elapsed since its start.
+    pub elapsed_ns: u64,
+}
+
+/// Groups all the commands that are executed while it is alive under a label.
+///
+/// Debugging tools like apitrace or RenderDoc show the draws, clears and blits of a group
+/// as children of its label, which makes captures of large scenes easier to navigate. Groups
+/// can be nested.
+///
+/// The group ends when the `DebugGroup` is destroyed. Does nothing if the backend doesn't
+/// support debug groups.
+///
+/// ## Example
+///
+/// ```no_run
+/// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+/// {
+///     let _group = glium::debug::DebugGroup::new(&display, "Shadow pass");
+///     // every draw here belongs to the "Shadow pass" group
+/// }
+/// ```
+pub struct DebugGroup {
+    display: Display,
+}
+
+impl DebugGroup {
+    /// Starts a new group of commands with the given label.
+    pub fn new(display: &Display, label: &str) -> DebugGroup {
+        let label = label.to_string();
+
+        display.context.context.exec(move |: ctxt| {
+            if !ctxt.capabilities.debug_groups {
+                return;
+            }
+
+            unsafe {
+                ctxt.gl.PushDebugGroup(gl::DEBUG_SOURCE_APPLICATION, 0,
+                                       label.len() as gl::types::GLsizei,
+                                       label.as_ptr() as *const libc::c_char);
+            }
+        });
+
+        DebugGroup {
+            display: display.clone(),
+        }
+    }
+}
+
+impl Drop for DebugGroup {
+    fn drop(&mut self) {
+        self.display.context.context.exec(move |: ctxt| {
+            if !ctxt.capabilities.debug_groups {
+                return;
+            }
+
+            unsafe {
+                ctxt.gl.PopDebugGroup();
+            }
+        });
+    }
+}
@@ -0,0 +1,145 @@
+/*!
+Buffers containing draw commands, used for indirect drawing.
+
+Instead of passing the number of vertices or indices to draw when calling a draw function,
+you can store a list of draw commands in a buffer in video memory and pass this buffer to
+`Surface::draw_indirect` or `Surface::draw_arrays_indirect`. All the commands of the buffer
+are then executed at once.
+
+Since the commands are stored in video memory, they can also be written by the GPU itself.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+use glium::draw_commands::{DrawCommandsBuffer, DrawElementsIndirectCommand};
+
+let commands = DrawCommandsBuffer::new(&display, vec![
+    DrawElementsIndirectCommand {
+        count: 6,
+        instance_count: 1,
+        first_index: 0,
+        base_vertex: 0,
+        base_instance: 0,
+    },
+]);
+```
+
+*/
+use buffer::{mod, Buffer};
+use gl;
+use Display;
+use GlObject;
+
+/// A command that draws vertices without an index buffer.
+///
+/// Used with `Surface::draw_arrays_indirect`.
+#[repr(C)]
+#[deriving(Copy, Clone, Show, PartialEq, Eq)]
+pub struct DrawArraysIndirectCommand {
+    /// Number of vertices to draw.
+    pub count: u32,
+    /// Number of instances to draw.
+    pub instance_count: u32,
+    /// Index of the first vertex to draw.
+    pub first_vertex: u32,
+    /// Value of the first instance ID. Must be 0 before OpenGL 4.2.
+    pub base_instance: u32,
+}
+
+/// A command that draws vertices with an index buffer.
+///
+/// Used with `Surface::draw_indirect`.
+#[repr(C)]
+#[deriving(Copy, Clone, Show, PartialEq, Eq)]
+pub struct DrawElementsIndirectCommand {
+    /// Number of indices to draw.
+    pub count: u32,
+    /// Number of instances to draw.
+    pub instance_count: u32,
+    /// Offset of the first index to use in the index buffer.
+    pub first_index: u32,
+    /// Value that is added to each index before reading the vertex buffer.
+    pub base_vertex: i32,
+    /// Value of the first instance ID. Must be 0 before OpenGL 4.2.
+    pub base_instance: u32,
+}
+
+/// A type of command that can be stored in a `DrawCommandsBuffer`.
+///
+/// This trait is unsafe because the layout of the type must match the one expected by
+/// OpenGL.
+pub unsafe trait DrawCommand: Copy + Send {
+}
+
+unsafe impl DrawCommand for DrawArraysIndirectCommand {
+}
+
+unsafe impl DrawCommand for DrawElementsIndirectCommand {
+}
+
+/// Returns true if the backend supports indirect drawing.
+pub fn is_supported(display: &Display) -> bool {
+    display.context.context.capabilities().draw_indirect
+}
+
+/// A list of draw commands loaded in the graphics card's memory.
+#[deriving(Show)]
+pub struct DrawCommandsBuffer<T> {
+    buffer: Buffer,
+}
+
+impl<T> DrawCommandsBuffer<T> where T: DrawCommand {
+    /// Builds a new buffer containing draw commands.
+    pub fn new(display: &Display, commands: Vec<T>) -> DrawCommandsBuffer<T> {
+        DrawCommandsBuffer {
+            buffer: Buffer::new::<buffer::DrawIndirectBuffer, T>(display, commands,
+                                                                 gl::STATIC_DRAW),
+        }
+    }
+
+    /// Builds a new buffer containing draw commands.
+    ///
+    /// This function will create a buffer that has better performances when it is modified
+    /// frequently.
+    pub fn new_dynamic(display: &Display, commands: Vec<T>) -> DrawCommandsBuffer<T> {
+        DrawCommandsBuffer {
+            buffer: Buffer::new::<buffer::DrawIndirectBuffer, T>(display, commands,
+                                                                 gl::DYNAMIC_DRAW),
+        }
+    }
+
+    /// Returns the number of commands in the buffer.
+    pub fn len(&self) -> uint {
+        self.buffer.get_elements_count()
+    }
+
+    /// Maps the buffer to allow write access to the commands.
+    ///
+    /// **Warning**: using this function can slow things down a lot because the function
+    /// waits for all the previous commands to be executed before returning.
+    pub fn map<'a>(&'a mut self) -> Mapping<'a, T> {
+        let len = self.buffer.get_elements_count();
+        let mapping = self.buffer.map::<buffer::DrawIndirectBuffer, T>(0, len);
+        Mapping(mapping)
+    }
+}
+
+impl<T> GlObject for DrawCommandsBuffer<T> {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.buffer.get_id()
+    }
+}
+
+/// A mapping of a `DrawCommandsBuffer`.
+pub struct Mapping<'a, T>(buffer::Mapping<'a, buffer::DrawIndirectBuffer, T>);
+
+impl<'a, T> Deref<[T]> for Mapping<'a, T> {
+    fn deref<'b>(&'b self) -> &'b [T] {
+        self.0.deref()
+    }
+}
+
+impl<'a, T> DerefMut<[T]> for Mapping<'a, T> {
+    fn deref_mut<'b>(&'b mut self) -> &'b mut [T] {
+        self.0.deref_mut()
+    }
+}
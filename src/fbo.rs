@@ -3,21 +3,73 @@ use std::sync::Arc;
 
 use DisplayImpl;
 use GlObject;
+use ToGlEnum;
+
+use framebuffer::{ColorAttachment, DepthAttachment};
 
 use gl;
 use context;
 
-#[deriving(Hash, Clone, PartialEq, Eq)]
+#[deriving(Show, Hash, Clone, PartialEq, Eq)]
 pub struct FramebufferAttachments {
     pub colors: Vec<(u32, Attachment)>,
     pub depth: Option<Attachment>,
     pub stencil: Option<Attachment>,
 }
 
-#[deriving(Hash, Copy, Clone, PartialEq, Eq)]
+#[deriving(Show, Hash, Copy, Clone, PartialEq, Eq)]
 pub enum Attachment {
     Texture(gl::types::GLuint),
+    /// A face of a cubemap. The second parameter is the `GL_TEXTURE_CUBE_MAP_*` target.
+    CubemapFace(gl::types::GLuint, gl::types::GLenum),
     RenderBuffer(gl::types::GLuint),
+    /// A layer of a texture array. The second parameter is the index of the layer.
+    TextureLayer(gl::types::GLuint, u32),
+    /// All the layers of a texture array or of a 3D texture. The layer is selected by the
+    /// geometry shader with `gl_Layer`.
+    LayeredTexture(gl::types::GLuint),
+}
+
+impl Attachment {
+    /// Returns the id of the texture if this attachment is a texture or a part of a texture.
+    pub fn get_texture_id(&self) -> Option<gl::types::GLuint> {
+        match *self {
+            Attachment::Texture(id) => Some(id),
+            Attachment::CubemapFace(id, _) => Some(id),
+            Attachment::TextureLayer(id, _) => Some(id),
+            Attachment::LayeredTexture(id) => Some(id),
+            Attachment::RenderBuffer(_) => None,
+        }
+    }
+
+    /// Builds the attachment corresponding to a public `ColorAttachment`.
+    pub fn from_color(color: ColorAttachment) -> Attachment {
+        match color {
+            ColorAttachment::Texture2d(tex) => Attachment::Texture(tex.get_id()),
+            ColorAttachment::CubemapFace(tex, face) => {
+                Attachment::CubemapFace(tex.get_id(), face.to_glenum())
+            },
+            ColorAttachment::RenderBuffer(buffer) => Attachment::RenderBuffer(buffer.get_id()),
+            ColorAttachment::Texture2dMultisampleArrayLayer(tex, layer) => {
+                Attachment::TextureLayer(tex.get_id(), layer)
+            },
+            ColorAttachment::Texture2dArray(tex) => Attachment::LayeredTexture(tex.get_id()),
+            ColorAttachment::Texture2dArrayLayer(tex, layer) => {
+                Attachment::TextureLayer(tex.get_id(), layer)
+            },
+            ColorAttachment::Texture3d(tex) => Attachment::LayeredTexture(tex.get_id()),
+            ColorAttachment::Cubemap(tex) => Attachment::LayeredTexture(tex.get_id()),
+        }
+    }
+
+    /// Builds the attachment corresponding to a public `DepthAttachment`.
+    pub fn from_depth(depth: DepthAttachment) -> Attachment {
+        match depth {
+            DepthAttachment::Texture2d(tex) => Attachment::Texture(tex.get_id()),
+            DepthAttachment::Texture2dArray(tex) => Attachment::LayeredTexture(tex.get_id()),
+            DepthAttachment::RenderBuffer(buffer) => Attachment::RenderBuffer(buffer.get_id()),
+        }
+    }
 }
 
 /// Frame buffer.
@@ -39,15 +91,26 @@ impl FrameBufferObject {
             unsafe fn attach(ctxt: &mut context::CommandContext, slot: gl::types::GLenum,
                              id: gl::types::GLuint, attachment: Attachment)
             {
-                if ctxt.version >= &GlVersion(4, 5) {
+                if ctxt.capabilities.direct_state_access {
                     match attachment {
                         Attachment::Texture(tex_id) => {
                             ctxt.gl.NamedFramebufferTexture(id, slot, tex_id, 0);
                         },
+                        Attachment::CubemapFace(tex_id, face) => {
+                            ctxt.gl.NamedFramebufferTextureLayer(id, slot, tex_id, 0,
+                                (face - gl::TEXTURE_CUBE_MAP_POSITIVE_X) as gl::types::GLint);
+                        },
                         Attachment::RenderBuffer(buf_id) => {
                             ctxt.gl.NamedFramebufferRenderbuffer(id, slot, gl::RENDERBUFFER,
                                                                  buf_id);
                         },
+                        Attachment::TextureLayer(tex_id, layer) => {
+                            ctxt.gl.NamedFramebufferTextureLayer(id, slot, tex_id, 0,
+                                                                 layer as gl::types::GLint);
+                        },
+                        Attachment::LayeredTexture(tex_id) => {
+                            ctxt.gl.NamedFramebufferTexture(id, slot, tex_id, 0);
+                        },
                     }
 
                 } else if ctxt.extensions.gl_ext_direct_state_access &&
@@ -57,10 +120,20 @@ impl FrameBufferObject {
                         Attachment::Texture(tex_id) => {
                             ctxt.gl.NamedFramebufferTextureEXT(id, slot, tex_id, 0);
                         },
+                        Attachment::CubemapFace(tex_id, face) => {
+                            ctxt.gl.NamedFramebufferTexture2DEXT(id, slot, face, tex_id, 0);
+                        },
                         Attachment::RenderBuffer(buf_id) => {
                             ctxt.gl.NamedFramebufferRenderbufferEXT(id, slot, gl::RENDERBUFFER,
                                                                     buf_id);
                         },
+                        Attachment::TextureLayer(tex_id, layer) => {
+                            ctxt.gl.NamedFramebufferTextureLayerEXT(id, slot, tex_id, 0,
+                                                                    layer as gl::types::GLint);
+                        },
+                        Attachment::LayeredTexture(tex_id) => {
+                            ctxt.gl.NamedFramebufferTextureEXT(id, slot, tex_id, 0);
+                        },
                     }
 
                 } else if ctxt.version >= &GlVersion(3, 2) {
@@ -71,10 +144,22 @@ impl FrameBufferObject {
                             ctxt.gl.FramebufferTexture(gl::DRAW_FRAMEBUFFER,
                                                        slot, tex_id, 0);
                         },
+                        Attachment::CubemapFace(tex_id, face) => {
+                            ctxt.gl.FramebufferTexture2D(gl::DRAW_FRAMEBUFFER,
+                                                         slot, face, tex_id, 0);
+                        },
                         Attachment::RenderBuffer(buf_id) => {
                             ctxt.gl.FramebufferRenderbuffer(gl::DRAW_FRAMEBUFFER, slot,
                                                             gl::RENDERBUFFER, buf_id);
                         },
+                        Attachment::TextureLayer(tex_id, layer) => {
+                            ctxt.gl.FramebufferTextureLayer(gl::DRAW_FRAMEBUFFER, slot, tex_id,
+                                                            0, layer as gl::types::GLint);
+                        },
+                        Attachment::LayeredTexture(tex_id) => {
+                            ctxt.gl.FramebufferTexture(gl::DRAW_FRAMEBUFFER,
+                                                       slot, tex_id, 0);
+                        },
                     }
 
                 } else if ctxt.version >= &GlVersion(3, 0) {
@@ -85,10 +170,22 @@ impl FrameBufferObject {
                             ctxt.gl.FramebufferTexture2D(gl::DRAW_FRAMEBUFFER,
                                                          slot, gl::TEXTURE_2D, tex_id, 0);
                         },
+                        Attachment::CubemapFace(tex_id, face) => {
+                            ctxt.gl.FramebufferTexture2D(gl::DRAW_FRAMEBUFFER,
+                                                         slot, face, tex_id, 0);
+                        },
                         Attachment::RenderBuffer(buf_id) => {
                             ctxt.gl.FramebufferRenderbuffer(gl::DRAW_FRAMEBUFFER, slot,
                                                             gl::RENDERBUFFER, buf_id);
                         },
+                        Attachment::TextureLayer(tex_id, layer) => {
+                            ctxt.gl.FramebufferTextureLayer(gl::DRAW_FRAMEBUFFER, slot, tex_id,
+                                                            0, layer as gl::types::GLint);
+                        },
+                        Attachment::LayeredTexture(_) => {
+                            // layered rendering requires at least OpenGL 3.2
+                            unreachable!()
+                        },
                     }
 
                 } else {
@@ -99,17 +196,29 @@ impl FrameBufferObject {
                             ctxt.gl.FramebufferTexture2DEXT(gl::FRAMEBUFFER_EXT,
                                                             slot, gl::TEXTURE_2D, tex_id, 0);
                         },
+                        Attachment::CubemapFace(tex_id, face) => {
+                            ctxt.gl.FramebufferTexture2DEXT(gl::FRAMEBUFFER_EXT,
+                                                            slot, face, tex_id, 0);
+                        },
                         Attachment::RenderBuffer(buf_id) => {
                             ctxt.gl.FramebufferRenderbufferEXT(gl::DRAW_FRAMEBUFFER, slot,
                                                                gl::RENDERBUFFER, buf_id);
                         },
+                        Attachment::TextureLayer(_, _) | Attachment::LayeredTexture(_) => {
+                            // texture arrays that can be attached require at least OpenGL 3.0
+                            unreachable!()
+                        },
                     }
                 }
             }
 
             unsafe {
                 let id: gl::types::GLuint = mem::uninitialized();
-                if ctxt.version >= &context::GlVersion(3, 0) {
+                if ctxt.capabilities.direct_state_access {
+                    // the `Named*` functions of `GL_ARB_direct_state_access` require an object
+                    // that has been created, not just a name
+                    ctxt.gl.CreateFramebuffers(1, mem::transmute(&id));
+                } else if ctxt.version >= &context::GlVersion(3, 0) {
                     ctxt.gl.GenFramebuffers(1, mem::transmute(&id));
                 } else {
                     ctxt.gl.GenFramebuffersEXT(1, mem::transmute(&id));
@@ -128,6 +237,31 @@ impl FrameBufferObject {
                 if let Some(atchmnt) = attachments.stencil {
                     attach(&mut ctxt, gl::STENCIL_ATTACHMENT, id, atchmnt);
                 }
+
+                // only the first color attachment is written to by default, the outputs of
+                // the fragment shader must be routed to the other ones
+                if attachments.colors.iter().any(|&(slot, _)| slot != 0) {
+                    let max_slot = attachments.colors.iter().map(|&(slot, _)| slot).max()
+                                                              .unwrap();
+
+                    let buffers = range(0, max_slot + 1).map(|slot| {
+                        if attachments.colors.iter().any(|&(s, _)| s == slot) {
+                            gl::COLOR_ATTACHMENT0 + slot as u32
+                        } else {
+                            gl::NONE
+                        }
+                    }).collect::<Vec<gl::types::GLenum>>();
+
+                    if ctxt.capabilities.direct_state_access {
+                        ctxt.gl.NamedFramebufferDrawBuffers(id,
+                                                            buffers.len() as gl::types::GLsizei,
+                                                            buffers.as_ptr());
+                    } else {
+                        bind_framebuffer(&mut ctxt, Some(id), true, false);
+                        ctxt.gl.DrawBuffers(buffers.len() as gl::types::GLsizei,
+                                            buffers.as_ptr());
+                    }
+                }
             }
         });
 
@@ -150,14 +284,17 @@ impl Drop for FrameBufferObject {
                         ctxt.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
                         ctxt.state.draw_framebuffer = 0;
                         ctxt.state.read_framebuffer = 0;
+                        ctxt.state.statistics.state_changes += 1;
 
                     } else if ctxt.state.draw_framebuffer == id {
                         ctxt.gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
                         ctxt.state.draw_framebuffer = 0;
+                        ctxt.state.statistics.state_changes += 1;
 
                     } else if ctxt.state.read_framebuffer == id {
                         ctxt.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
                         ctxt.state.read_framebuffer = 0;
+                        ctxt.state.statistics.state_changes += 1;
                     }
 
                 } else {
@@ -165,6 +302,7 @@ impl Drop for FrameBufferObject {
                         ctxt.gl.BindFramebufferEXT(gl::FRAMEBUFFER_EXT, 0);
                         ctxt.state.draw_framebuffer = 0;
                         ctxt.state.read_framebuffer = 0;
+                        ctxt.state.statistics.state_changes += 1;
                     }
                 }
 
@@ -221,6 +359,8 @@ pub fn bind_framebuffer(ctxt: &mut context::CommandContext, fbo_id: Option<gl::t
                 ctxt.state.read_framebuffer = fbo_id;
             }
         }
+
+        ctxt.state.statistics.state_changes += 1;
     }
 
     if read && ctxt.state.read_framebuffer != fbo_id {
@@ -228,10 +368,12 @@ pub fn bind_framebuffer(ctxt: &mut context::CommandContext, fbo_id: Option<gl::t
             if ctxt.version >= &context::GlVersion(3, 0) {
                 ctxt.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, fbo_id);
                 ctxt.state.read_framebuffer = fbo_id;
+                ctxt.state.statistics.state_changes += 1;
             } else {
                 ctxt.gl.BindFramebufferEXT(gl::FRAMEBUFFER_EXT, fbo_id);
                 ctxt.state.draw_framebuffer = fbo_id;
                 ctxt.state.read_framebuffer = fbo_id;
+                ctxt.state.statistics.state_changes += 1;
             }
         }
     }
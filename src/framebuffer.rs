@@ -19,7 +19,7 @@ a `MultiOutputFrameBuffer`.
 # let texture1: glium::texture::Texture2d = unsafe { ::std::mem::uninitialized() };
 # let texture2: glium::texture::Texture2d = unsafe { ::std::mem::uninitialized() };
 let output = &[ ("output1", &texture1), ("output2", &texture2) ];
-let framebuffer = glium::framebuffer::MultiOutputFrameBuffer::new(&display, output);
+let framebuffer = glium::framebuffer::MultiOutputFrameBuffer::new(&display, output).unwrap();
 // framebuffer.draw(...);
 
 // example shader:
@@ -33,7 +33,21 @@ let framebuffer = glium::framebuffer::MultiOutputFrameBuffer::new(&display, outp
 //     }
 ```
 
-**Note**: depth-stencil attachments are not yet implemented.
+If you don't need to read the depth or stencil values afterwards, you can use a
+`DepthRenderBuffer`, a `StencilRenderBuffer` or a `DepthStencilRenderBuffer` instead of a
+texture as the depth and stencil attachments.
+
+## Layered rendering
+
+A `Texture2dArray`, a `Texture3d` or a `Cubemap` can be attached as a whole to a framebuffer.
+A geometry shader then chooses the layer each primitive is drawn to by writing `gl_Layer`.
+This allows you to draw to all the faces of a shadow map or to all the slices of a volume in
+a single pass. The faces of a cubemap are numbered in the order returned by
+`CubeLayer::all()`.
+
+All the attachments of a layered framebuffer must be layered textures of the same kind. In
+other words the depth attachment must be absent, or be a `DepthTexture2dArray` if the color
+attachment is a `Texture2dArray`.
 
 */
 #![experimental]
@@ -41,12 +55,51 @@ let framebuffer = glium::framebuffer::MultiOutputFrameBuffer::new(&display, outp
 use std::kinds::marker::ContravariantLifetime;
 
 use texture::{Texture, Texture2d, DepthTexture2d, StencilTexture2d, DepthStencilTexture2d};
+use texture::{Cubemap, CubeLayer, Texture2dMultisampleArray};
+use texture::{Texture2dArray, Texture3d, DepthTexture2dArray};
 use fbo::FramebufferAttachments;
 
-use {Display, Program, Surface, GlObject};
+use {Display, DrawError, Program, Surface, GlObject};
 
 use {fbo, gl, ops};
 
+/// Error that can happen while creating a framebuffer.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum FramebufferValidationError {
+    /// The attachments don't all have the same dimensions.
+    DimensionsMismatch,
+
+    /// No color attachment has been passed.
+    NoColorAttachment,
+
+    /// Some attachments are layered and others are not, or the layered attachments are not
+    /// textures of the same kind.
+    LayeredAttachmentsMismatch,
+
+    /// An attachment is layered but layered rendering is not supported by the backend.
+    LayeredRenderingNotSupported,
+}
+
+impl ::std::error::Error for FramebufferValidationError {
+    fn description(&self) -> &str {
+        match self {
+            &FramebufferValidationError::DimensionsMismatch => {
+                "All the attachments of a framebuffer must have the same dimensions"
+            },
+            &FramebufferValidationError::NoColorAttachment => {
+                "A framebuffer must have at least one color attachment"
+            },
+            &FramebufferValidationError::LayeredAttachmentsMismatch => {
+                "Either all the attachments of a framebuffer must be layered textures of the \
+                 same kind or none of them"
+            },
+            &FramebufferValidationError::LayeredRenderingNotSupported => {
+                "Layered rendering is not supported by the backend"
+            },
+        }
+    }
+}
+
 /// A framebuffer which has only one color attachment.
 pub struct SimpleFrameBuffer<'a> {
     display: Display,
@@ -60,81 +113,164 @@ pub struct SimpleFrameBuffer<'a> {
 impl<'a> SimpleFrameBuffer<'a> {
     /// Creates a `SimpleFrameBuffer` with a single color attachment and no depth
     /// nor stencil buffer.
+    ///
+    /// Panics if the attachment is layered and layered rendering is not supported by
+    /// the backend.
     pub fn new<C>(display: &Display, color: &'a C) -> SimpleFrameBuffer<'a>
                   where C: ToColorAttachment
     {
-        use render_buffer;
-
-        SimpleFrameBuffer::new_impl(display, color, None::<&render_buffer::DepthRenderBuffer>,
-                                    None::<&render_buffer::StencilRenderBuffer>)
+        SimpleFrameBuffer::from_color_attachment(display, color.to_color_attachment())
     }
 
     /// Creates a `SimpleFrameBuffer` with a single color attachment and a depth
     /// buffer, but no stencil buffer.
+    ///
+    /// Returns an error if the attachments don't have the same dimensions.
     pub fn with_depth_buffer<C, D>(display: &Display, color: &'a C, depth: &'a D)
-                                   -> SimpleFrameBuffer<'a>
+                                   -> Result<SimpleFrameBuffer<'a>, FramebufferValidationError>
                                    where C: ToColorAttachment, D: ToDepthAttachment
     {
-        use render_buffer;
-
-        SimpleFrameBuffer::new_impl(display, color, Some(depth),
-                                    None::<&render_buffer::StencilRenderBuffer>)
+        SimpleFrameBuffer::new_impl(display, color.to_color_attachment(),
+                                    Some(depth.to_depth_attachment()), None, None)
     }
 
     /// Creates a `SimpleFrameBuffer` with a single color attachment, a depth
     /// buffer, and a stencil buffer.
+    ///
+    /// Returns an error if the attachments don't have the same dimensions.
     pub fn with_depth_and_stencil_buffer<C, D, S>(display: &Display, color: &'a C, depth: &'a D,
-                                                  stencil: &'a S) -> SimpleFrameBuffer<'a>
+                                                  stencil: &'a S)
+                                                  -> Result<SimpleFrameBuffer<'a>,
+                                                            FramebufferValidationError>
                                                   where C: ToColorAttachment, D: ToDepthAttachment,
                                                   S: ToStencilAttachment
     {
-        SimpleFrameBuffer::new_impl(display, color, Some(depth), Some(stencil))
+        SimpleFrameBuffer::new_impl(display, color.to_color_attachment(),
+                                    Some(depth.to_depth_attachment()),
+                                    Some(stencil.to_stencil_attachment()), None)
     }
 
     /// Creates a `SimpleFrameBuffer` with a single color attachment and a stencil
     /// buffer, but no buffer buffer.
+    ///
+    /// Returns an error if the attachments don't have the same dimensions.
     pub fn with_stencil_buffer<C, S>(display: &Display, color: &'a C, stencil: &'a S)
-                                     -> SimpleFrameBuffer<'a>
+                                     -> Result<SimpleFrameBuffer<'a>, FramebufferValidationError>
                                      where C: ToColorAttachment, S: ToStencilAttachment
     {
-        use render_buffer;
+        SimpleFrameBuffer::new_impl(display, color.to_color_attachment(), None,
+                                    Some(stencil.to_stencil_attachment()), None)
+    }
 
-        SimpleFrameBuffer::new_impl(display, color, None::<&render_buffer::DepthRenderBuffer>,
-                                    Some(stencil))
+    /// Creates a `SimpleFrameBuffer` with a single color attachment and a buffer that
+    /// contains both the depth and the stencil values.
+    ///
+    /// Use a `DepthStencilRenderBuffer` if you don't need to sample the depth or stencil
+    /// values afterwards.
+    ///
+    /// Returns an error if the attachments don't have the same dimensions.
+    pub fn with_depth_stencil_buffer<C, D>(display: &Display, color: &'a C,
+                                           depth_stencil: &'a D)
+                                           -> Result<SimpleFrameBuffer<'a>,
+                                                     FramebufferValidationError>
+                                           where C: ToColorAttachment,
+                                           D: ToDepthStencilAttachment
+    {
+        SimpleFrameBuffer::new_impl(display, color.to_color_attachment(), None, None,
+                                    Some(depth_stencil.to_depth_stencil_attachment()))
     }
 
+    /// Creates a `SimpleFrameBuffer` from a `ColorAttachment`, with no depth nor stencil buffer.
+    ///
+    /// Panics if the attachment is layered and layered rendering is not supported by
+    /// the backend.
+    pub fn from_color_attachment(display: &Display, color: ColorAttachment<'a>)
+                                 -> SimpleFrameBuffer<'a>
+    {
+        // a framebuffer with a single attachment is always valid, unless it is layered
+        match SimpleFrameBuffer::new_impl(display, color, None, None, None) {
+            Ok(framebuffer) => framebuffer,
+            Err(err) => panic!("{}", ::std::error::Error::description(&err))
+        }
+    }
 
-    fn new_impl<C, D, S>(display: &Display, color: &'a C, depth: Option<&'a D>,
-                         stencil: Option<&'a S>) -> SimpleFrameBuffer<'a>
-                         where C: ToColorAttachment, D: ToDepthAttachment, S: ToStencilAttachment
+    fn new_impl(display: &Display, color: ColorAttachment<'a>,
+                depth: Option<DepthAttachment<'a>>, stencil: Option<StencilAttachment<'a>>,
+                depth_stencil: Option<DepthStencilAttachment<'a>>)
+                -> Result<SimpleFrameBuffer<'a>, FramebufferValidationError>
     {
-        let (dimensions, color_attachment) = match color.to_color_attachment() {
-            ColorAttachment::Texture2d(tex) => {
-                let dimensions = (tex.get_width(), tex.get_height().unwrap());
-                let id = fbo::Attachment::Texture(tex.get_id());
-                (dimensions, id)
+        let dimensions = match color {
+            ColorAttachment::Texture2d(tex) => (tex.get_width(), tex.get_height().unwrap()),
+            ColorAttachment::CubemapFace(tex, _) => (tex.get_width(), tex.get_height().unwrap()),
+            ColorAttachment::RenderBuffer(buffer) => buffer.get_dimensions(),
+            ColorAttachment::Texture2dMultisampleArrayLayer(tex, _) => {
+                (tex.get_width(), tex.get_height().unwrap())
             },
-
-            ColorAttachment::RenderBuffer(buffer) => {
-                let dimensions = buffer.get_dimensions();
-                let id = fbo::Attachment::RenderBuffer(buffer.get_id());
-                (dimensions, id)
+            ColorAttachment::Texture2dArray(tex) => (tex.get_width(), tex.get_height().unwrap()),
+            ColorAttachment::Texture2dArrayLayer(tex, _) => {
+                (tex.get_width(), tex.get_height().unwrap())
             },
+            ColorAttachment::Texture3d(tex) => (tex.get_width(), tex.get_height().unwrap()),
+            ColorAttachment::Cubemap(tex) => (tex.get_width(), tex.get_height().unwrap()),
         };
 
+        // the texture target of the color attachment if it is layered, which the other
+        // attachments must match
+        let layered = match color {
+            ColorAttachment::Texture2dArray(_) => Some(gl::TEXTURE_2D_ARRAY),
+            ColorAttachment::Texture3d(_) => Some(gl::TEXTURE_3D),
+            ColorAttachment::Cubemap(_) => Some(gl::TEXTURE_CUBE_MAP),
+            _ => None
+        };
+
+        if layered.is_some() {
+            // the layer can only be chosen by a geometry shader
+            if !display.context.context.capabilities().geometry_shader {
+                return Err(FramebufferValidationError::LayeredRenderingNotSupported);
+            }
+
+            if stencil.is_some() || depth_stencil.is_some() {
+                return Err(FramebufferValidationError::LayeredAttachmentsMismatch);
+            }
+        }
+
+        let color_attachment = fbo::Attachment::from_color(color);
+
         let (depth, depth_bits) = if let Some(depth) = depth {
-            match depth.to_depth_attachment() {
+            match depth {
                 DepthAttachment::Texture2d(tex) => {
                     if (tex.get_width(), tex.get_height().unwrap()) != dimensions {
-                        panic!("The depth attachment must have the same dimensions \
-                                as the color attachment");
+                        return Err(FramebufferValidationError::DimensionsMismatch);
+                    }
+
+                    if layered.is_some() {
+                        return Err(FramebufferValidationError::LayeredAttachmentsMismatch);
                     }
 
                     (Some(fbo::Attachment::Texture(tex.get_id())), Some(32))      // FIXME: wrong number
                 },
 
+                DepthAttachment::Texture2dArray(tex) => {
+                    if (tex.get_width(), tex.get_height().unwrap()) != dimensions {
+                        return Err(FramebufferValidationError::DimensionsMismatch);
+                    }
+
+                    if layered != Some(gl::TEXTURE_2D_ARRAY) {
+                        return Err(FramebufferValidationError::LayeredAttachmentsMismatch);
+                    }
+
+                    // FIXME: wrong number
+                    (Some(fbo::Attachment::LayeredTexture(tex.get_id())), Some(32))
+                },
+
                 DepthAttachment::RenderBuffer(buffer) => {
-                    // TODO: dimensions
+                    if buffer.get_dimensions() != dimensions {
+                        return Err(FramebufferValidationError::DimensionsMismatch);
+                    }
+
+                    if layered.is_some() {
+                        return Err(FramebufferValidationError::LayeredAttachmentsMismatch);
+                    }
 
                     (Some(fbo::Attachment::RenderBuffer(buffer.get_id())), Some(32))      // FIXME: wrong number
                 },
@@ -145,18 +281,19 @@ impl<'a> SimpleFrameBuffer<'a> {
         };
 
         let (stencil, stencil_bits) = if let Some(stencil) = stencil {
-            match stencil.to_stencil_attachment() {
+            match stencil {
                 StencilAttachment::Texture2d(tex) => {
                     if (tex.get_width(), tex.get_height().unwrap()) != dimensions {
-                        panic!("The stencil attachment must have the same dimensions \
-                                as the color attachment");
+                        return Err(FramebufferValidationError::DimensionsMismatch);
                     }
 
                     (Some(fbo::Attachment::Texture(tex.get_id())), Some(8))       // FIXME: wrong number
                 },
 
                 StencilAttachment::RenderBuffer(buffer) => {
-                    // TODO: dimensions
+                    if buffer.get_dimensions() != dimensions {
+                        return Err(FramebufferValidationError::DimensionsMismatch);
+                    }
 
                     (Some(fbo::Attachment::RenderBuffer(buffer.get_id())), Some(8))
                 },
@@ -166,7 +303,30 @@ impl<'a> SimpleFrameBuffer<'a> {
             (None, None)
         };
 
-        SimpleFrameBuffer {
+        // a depth-stencil buffer is attached both as the depth and the stencil attachment
+        let (depth, depth_bits, stencil, stencil_bits) = match depth_stencil {
+            None => (depth, depth_bits, stencil, stencil_bits),
+
+            Some(DepthStencilAttachment::Texture2d(tex)) => {
+                if (tex.get_width(), tex.get_height().unwrap()) != dimensions {
+                    return Err(FramebufferValidationError::DimensionsMismatch);
+                }
+
+                let attachment = fbo::Attachment::Texture(tex.get_id());
+                (Some(attachment), Some(24), Some(attachment), Some(8))
+            },
+
+            Some(DepthStencilAttachment::RenderBuffer(buffer)) => {
+                if buffer.get_dimensions() != dimensions {
+                    return Err(FramebufferValidationError::DimensionsMismatch);
+                }
+
+                let attachment = fbo::Attachment::RenderBuffer(buffer.get_id());
+                (Some(attachment), Some(24), Some(attachment), Some(8))
+            },
+        };
+
+        Ok(SimpleFrameBuffer {
             display: display.clone(),
             attachments: FramebufferAttachments {
                 colors: vec![(0, color_attachment)],
@@ -177,7 +337,7 @@ impl<'a> SimpleFrameBuffer<'a> {
             dimensions: dimensions,
             depth_buffer_bits: depth_bits,
             stencil_buffer_bits: stencil_bits,
-        }
+        })
     }
 }
 
@@ -207,23 +367,13 @@ impl<'a> Surface for SimpleFrameBuffer<'a> {
     }
 
     fn draw<'b, 'v, V, I, ID, U>(&mut self, vb: V, ib: &I, program: &::Program,
-        uniforms: U, draw_parameters: &::DrawParameters) where I: ::index_buffer::ToIndicesSource<ID>,
-        U: ::uniforms::Uniforms, ID: ::index_buffer::Index, V: ::vertex_buffer::IntoVerticesSource<'v>
+        uniforms: U, draw_parameters: &::DrawParameters) -> Result<(), DrawError>
+        where I: ::index_buffer::ToIndicesSource<ID>, U: ::uniforms::Uniforms,
+        ID: ::index_buffer::Index, V: ::vertex_buffer::IntoVerticesSource<'v>
     {
         use index_buffer::ToIndicesSource;
-        
-        draw_parameters.validate();
 
-        if draw_parameters.depth_function.requires_depth_buffer() && !self.has_depth_buffer() {
-            panic!("Requested a depth function but no depth buffer is attached");
-        }
-
-        if let Some(viewport) = draw_parameters.viewport {
-            assert!(viewport.width <= self.display.context.context.capabilities().max_viewport_dims.0
-                    as u32, "Viewport dimensions are too large");
-            assert!(viewport.height <= self.display.context.context.capabilities().max_viewport_dims.1
-                    as u32, "Viewport dimensions are too large");
-        }
+        try!(draw_parameters.validate_for_surface(&self.display, &*self));
 
         ops::draw(&self.display, Some(&self.attachments), vb.into_vertices_source(),
                   &ib.to_indices_source(), program, uniforms, draw_parameters, self.dimensions)
@@ -245,12 +395,10 @@ pub struct MultiOutputFrameBuffer<'a> {
 impl<'a> MultiOutputFrameBuffer<'a> {
     /// Creates a new `MultiOutputFramebuffer`.
     ///
-    /// # Panic
-    ///
-    /// Panics if all attachments don't have the same dimensions.
-    ///
+    /// Returns an error if `color_attachments` is empty or if the attachments don't all have
+    /// the same dimensions.
     pub fn new(display: &Display, color_attachments: &[(&str, &'a Texture2d)])
-               -> MultiOutputFrameBuffer<'a>
+               -> Result<MultiOutputFrameBuffer<'a>, FramebufferValidationError>
     {
         let mut attachments = Vec::new();
         let mut dimensions = None;
@@ -260,8 +408,7 @@ impl<'a> MultiOutputFrameBuffer<'a> {
 
             if let Some(ref dimensions) = dimensions {
                 if dimensions != &tex_dims {
-                    panic!("All textures of a MultiOutputFrameBuffer must have \
-                            the same dimensions");
+                    return Err(FramebufferValidationError::DimensionsMismatch);
                 }
             }
 
@@ -269,17 +416,17 @@ impl<'a> MultiOutputFrameBuffer<'a> {
             attachments.push((name.to_string(), texture.get_id()));
         }
 
-        if dimensions.is_none() {
-            panic!("Cannot pass an empty color_attachments when \
-                    creating a MultiOutputFrameBuffer");
-        }
+        let dimensions = match dimensions {
+            Some(dimensions) => dimensions,
+            None => return Err(FramebufferValidationError::NoColorAttachment),
+        };
 
-        MultiOutputFrameBuffer {
+        Ok(MultiOutputFrameBuffer {
             display: display.clone(),
             marker: ContravariantLifetime,
-            dimensions: dimensions.unwrap(),
+            dimensions: dimensions,
             color_attachments: attachments,
-        }
+        })
     }
 
     fn build_attachments(&self, program: &Program) -> FramebufferAttachments {
@@ -307,8 +454,22 @@ impl<'a> MultiOutputFrameBuffer<'a> {
 pub enum ColorAttachment<'a> {
     /// A texture.
     Texture2d(&'a Texture2d),
+    /// One of the faces of a cubemap.
+    CubemapFace(&'a Cubemap, CubeLayer),
     /// A render buffer.
     RenderBuffer(&'a ::render_buffer::RenderBuffer),
+    /// One of the layers of a multisample texture array.
+    Texture2dMultisampleArrayLayer(&'a Texture2dMultisampleArray, u32),
+    /// All the layers of a texture array. See the module documentation about layered
+    /// rendering.
+    Texture2dArray(&'a Texture2dArray),
+    /// One of the layers of a texture array.
+    Texture2dArrayLayer(&'a Texture2dArray, u32),
+    /// All the slices of a three-dimensional texture. See the module documentation about
+    /// layered rendering.
+    Texture3d(&'a Texture3d),
+    /// All the faces of a cubemap. See the module documentation about layered rendering.
+    Cubemap(&'a Cubemap),
 }
 
 /// Trait for objects that can be used as color attachments.
@@ -322,6 +483,8 @@ pub trait ToColorAttachment {
 pub enum DepthAttachment<'a> {
     /// A texture.
     Texture2d(&'a DepthTexture2d),
+    /// All the layers of a texture array. The color attachment must be layered as well.
+    Texture2dArray(&'a DepthTexture2dArray),
     /// A render buffer.
     RenderBuffer(&'a ::render_buffer::DepthRenderBuffer),
 }
@@ -0,0 +1,299 @@
+/*!
+Helpers to compare the result of rendering with reference images.
+
+Rendering tests usually draw something on an offscreen surface, read the result back and
+compare it with an image that is known to be correct. This module provides the building
+blocks for this:
+
+ - `render_offscreen` draws on a RGBA8 texture and reads it back.
+ - `read_surface` reads any surface with the same fixed format.
+ - `compare` checks two images with a tolerance and builds a diff image.
+ - `assert_matches_reference` loads a PNG file, compares it, and saves the actual image and
+   the diff image next to the reference if they don't match. It is only available with the
+   `image` feature.
+
+The images are always stored as 8 bits RGBA, with the first row at the top, so that they can
+be saved and compared byte by byte on every platform.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+use glium::Surface;
+use glium::golden;
+
+let image = golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+    surface.clear_color(1.0, 0.0, 0.0, 1.0);
+});
+
+golden::assert_matches_reference(&image, &Path::new("tests/reference/red.png"), 1);
+```
+
+*/
+use std::error::Error;
+
+use {Display, Rect, Surface};
+use texture::{ClientFormat, MipmapsOption, Texture2d, TextureSurface, UncompressedFloatFormat};
+
+use gl;
+
+#[cfg(feature = "image")]
+use image;
+
+/// An image read from a surface.
+///
+/// The pixels are stored as 8 bits RGBA, row by row, starting with the top-left hand corner.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct GoldenImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl GoldenImage {
+    /// Builds an image from raw RGBA data, starting with the top-left hand corner.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the length of `data` is not `width * height * 4`.
+    pub fn from_raw(width: u32, height: u32, data: Vec<u8>) -> GoldenImage {
+        assert!(data.len() == width as uint * height as uint * 4,
+                "The size of the data doesn't match the dimensions of the image");
+
+        GoldenImage {
+            width: width,
+            height: height,
+            data: data,
+        }
+    }
+
+    /// Builds an image from an image of the `image` library.
+    ///
+    /// # Features
+    ///
+    /// Only available if the `image` feature is enabled.
+    #[cfg(feature = "image")]
+    pub fn from_image(image: image::DynamicImage) -> GoldenImage {
+        use image::GenericImage;
+
+        let image = image.to_rgba();
+        let (width, height) = image.dimensions();
+        GoldenImage::from_raw(width, height, image.into_vec())
+    }
+
+    /// Turns the image into an image of the `image` library.
+    ///
+    /// # Features
+    ///
+    /// Only available if the `image` feature is enabled.
+    #[cfg(feature = "image")]
+    pub fn to_image(&self) -> image::DynamicImage {
+        let buffer = image::ImageBuffer::from_raw(self.width, self.height, self.data.clone());
+        image::DynamicImage::ImageRgba8(buffer.unwrap())
+    }
+
+    /// Returns the width and height of the image.
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the RGBA components of a pixel. `(0, 0)` is the top-left hand corner.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the pixel is outside of the image.
+    pub fn get_pixel(&self, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        assert!(x < self.width && y < self.height, "The pixel is outside of the image");
+
+        let offset = (y as uint * self.width as uint + x as uint) * 4;
+        let p = self.data.slice(offset, offset + 4);
+        (p[0], p[1], p[2], p[3])
+    }
+
+    /// Returns the raw RGBA data of the image, starting with the top-left hand corner.
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+}
+
+/// Error returned by `compare` when two images don't match.
+#[deriving(Show, Clone)]
+pub enum ImageMismatch {
+    /// The images don't have the same dimensions.
+    DimensionsMismatch {
+        /// Dimensions of the reference image.
+        expected: (u32, u32),
+        /// Dimensions of the actual image.
+        obtained: (u32, u32),
+    },
+
+    /// Some pixels are different.
+    PixelsMismatch {
+        /// Number of pixels that differ by more than the tolerance.
+        differing_pixels: uint,
+        /// Largest difference between two components.
+        max_difference: u8,
+        /// An image where the pixels that differ are red, and the others are a dimmed gray
+        /// version of the reference.
+        diff: GoldenImage,
+    },
+}
+
+impl Error for ImageMismatch {
+    fn description(&self) -> &str {
+        match self {
+            &ImageMismatch::DimensionsMismatch { .. } => {
+                "The images don't have the same dimensions"
+            },
+            &ImageMismatch::PixelsMismatch { .. } => {
+                "Some pixels of the images are different"
+            },
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match self {
+            &ImageMismatch::DimensionsMismatch { expected, obtained } => {
+                Some(format!("Expected an image of {}x{}, got {}x{}", expected.0, expected.1,
+                             obtained.0, obtained.1))
+            },
+            &ImageMismatch::PixelsMismatch { differing_pixels, max_difference, .. } => {
+                Some(format!("{} pixels are different, with a maximum difference of {}",
+                             differing_pixels, max_difference))
+            },
+        }
+    }
+}
+
+/// Creates a RGBA8 texture, calls `draw` with a surface that draws on it, then reads it back.
+///
+/// The texture is cleared with transparent black before `draw` is called. Dithering is
+/// disabled, so that the result is the same every time.
+pub fn render_offscreen<F>(display: &Display, width: u32, height: u32, mut draw: F)
+                           -> GoldenImage where F: FnMut(&mut TextureSurface)
+{
+    let texture = Texture2d::new_empty_with_mipmaps(display, UncompressedFloatFormat::U8U8U8U8,
+                                                    width, height, MipmapsOption::NoMipmap);
+
+    // dithering is enabled by default in OpenGL, even though glium doesn't track it
+    display.context.context.exec(move |: ctxt| {
+        unsafe { ctxt.gl.Disable(gl::DITHER); }
+        ctxt.state.enabled_dither = false;
+        ctxt.state.statistics.state_changes += 1;
+    });
+
+    let mut surface = texture.as_surface();
+    surface.clear_color(0.0, 0.0, 0.0, 0.0);
+    draw.call_mut((&mut surface,));
+
+    read_surface(&surface)
+}
+
+/// Reads the content of a surface as 8 bits RGBA, with the first row at the top.
+pub fn read_surface<S>(surface: &S) -> GoldenImage where S: Surface {
+    let (width, height) = surface.get_dimensions();
+    let rect = Rect::from_dimensions(width as u32, height as u32);
+    let data: Vec<u8> = surface.read_rect_with_format(&rect, ClientFormat::U8U8U8U8);
+
+    // OpenGL returns the rows from bottom to top
+    let data = if width == 0 {
+        data
+    } else {
+        data.as_slice().chunks(width * 4).rev().flat_map(|row| row.iter())
+            .map(|p| *p).collect()
+    };
+
+    GoldenImage::from_raw(width as u32, height as u32, data)
+}
+
+/// Compares an image with a reference.
+///
+/// Two pixels are considered identical if none of their components differ by more than
+/// `tolerance`.
+pub fn compare(reference: &GoldenImage, image: &GoldenImage, tolerance: u8)
+               -> Result<(), ImageMismatch>
+{
+    if reference.get_dimensions() != image.get_dimensions() {
+        return Err(ImageMismatch::DimensionsMismatch {
+            expected: reference.get_dimensions(),
+            obtained: image.get_dimensions(),
+        });
+    }
+
+    let mut differing_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(reference.data.len());
+
+    for (r, i) in reference.data.as_slice().chunks(4).zip(image.data.as_slice().chunks(4)) {
+        let difference = r.iter().zip(i.iter())
+                          .map(|(&a, &b)| if a > b { a - b } else { b - a })
+                          .max().unwrap_or(0);
+
+        if difference > max_difference {
+            max_difference = difference;
+        }
+
+        if difference > tolerance {
+            differing_pixels += 1;
+            diff.push_all(&[255, 0, 0, 255]);
+        } else {
+            let gray = ((r[0] as u32 + r[1] as u32 + r[2] as u32) / 12) as u8;
+            diff.push_all(&[gray, gray, gray, 255]);
+        }
+    }
+
+    if differing_pixels == 0 {
+        return Ok(());
+    }
+
+    Err(ImageMismatch::PixelsMismatch {
+        differing_pixels: differing_pixels,
+        max_difference: max_difference,
+        diff: GoldenImage::from_raw(reference.width, reference.height, diff),
+    })
+}
+
+/// Compares an image with the reference stored in a PNG file.
+///
+/// If the images don't match or if the reference doesn't exist, the image is saved next to
+/// the reference with the `.actual.png` extension, and the diff image with the `.diff.png`
+/// extension. You can then inspect them, or rename the actual image to update the reference.
+///
+/// # Panic
+///
+/// Panics if the images don't match or if the reference can't be loaded.
+///
+/// # Features
+///
+/// Only available if the `image` feature is enabled.
+#[cfg(feature = "image")]
+pub fn assert_matches_reference(image: &GoldenImage, reference: &Path, tolerance: u8) {
+    let actual_path = reference.with_extension("actual.png");
+    let diff_path = reference.with_extension("diff.png");
+
+    let reference_image = match image::open(reference) {
+        Ok(img) => GoldenImage::from_image(img),
+        Err(err) => {
+            save_png(image, &actual_path);
+            panic!("Could not load the reference image {}: {}. The actual image has been \
+                    saved to {}", reference.display(), err, actual_path.display());
+        }
+    };
+
+    if let Err(err) = compare(&reference_image, image, tolerance) {
+        save_png(image, &actual_path);
+
+        if let ImageMismatch::PixelsMismatch { ref diff, .. } = err {
+            save_png(diff, &diff_path);
+        }
+
+        panic!("The image doesn't match the reference {}: {}. The actual image has been saved \
+                to {} and the differences to {}", reference.display(),
+               err.detail().unwrap_or_else(|| err.description().to_string()),
+               actual_path.display(), diff_path.display());
+    }
+}
+
+#[cfg(feature = "image")]
+fn save_png(image: &GoldenImage, path: &Path) {
+    let output = ::std::io::fs::File::create(path);
+    image.to_image().save(output, image::ImageFormat::PNG).unwrap().unwrap();
+}
@@ -116,6 +116,28 @@ pub enum PrimitiveType {
     TriangleFan,
 }
 
+impl PrimitiveType {
+    /// Returns the number of primitives that are drawn with the given number of vertices.
+    ///
+    /// Incomplete primitives at the end are not counted.
+    pub fn get_primitives_count(&self, vertices: uint) -> uint {
+        use std::num::Int;
+
+        match self {
+            &PrimitiveType::Points => vertices,
+            &PrimitiveType::LinesList => vertices / 2,
+            &PrimitiveType::LinesListAdjacency => vertices / 4,
+            &PrimitiveType::LineStrip => vertices.saturating_sub(1),
+            &PrimitiveType::LineStripAdjacency => vertices.saturating_sub(3),
+            &PrimitiveType::TrianglesList => vertices / 3,
+            &PrimitiveType::TrianglesListAdjacency => vertices / 6,
+            &PrimitiveType::TriangleStrip => vertices.saturating_sub(2),
+            &PrimitiveType::TriangleStripAdjacency => vertices.saturating_sub(4) / 2,
+            &PrimitiveType::TriangleFan => vertices.saturating_sub(2),
+        }
+    }
+}
+
 impl ToGlEnum for PrimitiveType {
     fn to_glenum(&self) -> gl::types::GLenum {
         match self {
@@ -168,6 +190,55 @@ impl IndexBuffer {
         data.into_index_buffer(display)
     }
 
+    /// Builds a new index buffer from a list of `u32`s, and stores the indices as `u16`s if
+    /// they fit, or as `u32`s otherwise.
+    ///
+    /// `u8` indices are never chosen, because many drivers convert them on the CPU before each
+    /// draw.
+    ///
+    /// This is useful when the number of vertices is only known at runtime, for example when
+    /// loading models. Use `get_indices_type` to know which type has been chosen.
+    ///
+    /// The value `0xffffffff` is kept as the maximum value of the chosen type, so that it can
+    /// still be used with the `primitive_restart_index` draw parameter.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+    /// use glium::index_buffer::{IndexType, PrimitiveType};
+    ///
+    /// let index_buffer = glium::IndexBuffer::from_u32(&display, PrimitiveType::TrianglesList,
+    ///                                                 &[0, 1, 2, 1, 3, 300]);
+    /// assert_eq!(index_buffer.get_indices_type(), IndexType::U16);
+    /// ```
+    pub fn from_u32(display: &super::Display, primitives: PrimitiveType, indices: &[u32])
+                    -> IndexBuffer
+    {
+        use std::cmp;
+
+        let max = indices.iter().filter(|&&i| i != 0xffffffff).fold(0, |a, &b| cmp::max(a, b));
+
+        if max < 0xffff {
+            let data = indices.iter().map(|&i| if i == 0xffffffff { 0xffff } else { i as u16 })
+                              .collect::<Vec<u16>>();
+            IndexBuffer::from_vec(display, data, primitives)
+
+        } else {
+            IndexBuffer::from_vec(display, indices.to_vec(), primitives)
+        }
+    }
+
+    fn from_vec<T>(display: &super::Display, data: Vec<T>, primitives: PrimitiveType)
+                   -> IndexBuffer where T: Index + Send + Copy
+    {
+        IndexBuffer {
+            buffer: Buffer::new::<buffer::ArrayBuffer, _>(display, data, gl::STATIC_DRAW),
+            data_type: Index::get_type(None::<T>),
+            primitives: primitives,
+        }
+    }
+
     /// Returns the type of primitives associated with this index buffer.
     pub fn get_primitives_type(&self) -> PrimitiveType {
         self.primitives
@@ -177,6 +248,44 @@ impl IndexBuffer {
     pub fn get_indices_type(&self) -> IndexType {
         self.data_type
     }
+
+    /// Returns the number of indices in this index buffer.
+    pub fn len(&self) -> uint {
+        self.buffer.get_elements_count()
+    }
+
+    /// Returns a slice of this index buffer that can be used as a source of indices.
+    ///
+    /// `offset` and `length` are in number of indices. This allows you to store the indices
+    /// of multiple objects in the same buffer and to draw them independently.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the slice is out of range of the buffer.
+    pub fn slice<'a>(&'a self, offset: uint, length: uint) -> IndexBufferSlice<'a> {
+        if offset > self.len() || offset + length > self.len() {
+            panic!("Trying to slice out of range of the index buffer");
+        }
+
+        IndexBufferSlice {
+            buffer: self,
+            offset: offset,
+            length: length,
+        }
+    }
+
+    /// Reads back some of the indices, converted to `u32`.
+    #[doc(hidden)]      // TODO: shouldn't be pub
+    #[cfg(feature = "gl_extensions")]
+    pub fn read_indices(&self, offset: uint, length: uint) -> Vec<u32> {
+        match self.data_type {
+            IndexType::U8 => self.buffer.read_slice::<buffer::ArrayBuffer, u8>(offset, length)
+                                        .into_iter().map(|i| i as u32).collect(),
+            IndexType::U16 => self.buffer.read_slice::<buffer::ArrayBuffer, u16>(offset, length)
+                                         .into_iter().map(|i| i as u32).collect(),
+            IndexType::U32 => self.buffer.read_slice::<buffer::ArrayBuffer, u32>(offset, length),
+        }
+    }
 }
 
 impl GlObject for IndexBuffer {
@@ -195,14 +304,22 @@ impl ToIndicesSource<u16> for IndexBuffer {      // TODO: u16?
     }
 }
 
-impl Drop for IndexBuffer {
-    fn drop(&mut self) {
-        // removing VAOs which contain this index buffer
-        let mut vaos = self.buffer.get_display().vertex_array_objects.lock().unwrap();
-        let to_delete = vaos.keys().filter(|&&(_, i, _)| i == self.buffer.get_id())
-            .map(|k| k.clone()).collect::<Vec<_>>();
-        for k in to_delete.into_iter() {
-            vaos.remove(&k);
+/// A slice of an `IndexBuffer`.
+///
+/// Obtained with `index_buffer.slice(offset, length)`.
+#[deriving(Copy, Clone)]
+pub struct IndexBufferSlice<'a> {
+    buffer: &'a IndexBuffer,
+    offset: uint,
+    length: uint,
+}
+
+impl<'a> ToIndicesSource<u16> for IndexBufferSlice<'a> {
+    fn to_indices_source(&self) -> IndicesSource<u16> {
+        IndicesSource::IndexBuffer {
+            buffer: self.buffer,
+            offset: self.offset,
+            length: self.length,
         }
     }
 }
@@ -218,6 +335,17 @@ pub enum IndexType {
     U32,
 }
 
+impl IndexType {
+    /// Returns the number of bytes taken by an index of this type.
+    pub fn get_size(&self) -> uint {
+        match self {
+            &IndexType::U8 => 1,
+            &IndexType::U16 => 2,
+            &IndexType::U32 => 4,
+        }
+    }
+}
+
 impl ToGlEnum for IndexType {
     fn to_glenum(&self) -> gl::types::GLenum {
         match self {
@@ -232,24 +360,39 @@ impl ToGlEnum for IndexType {
 pub unsafe trait Index: Copy + Send {
     /// Returns the `IndexType` corresponding to this type.
     fn get_type(Option<Self>) -> IndexType;
+
+    /// Returns the value of the index.
+    fn to_u32(&self) -> u32;
 }
 
 unsafe impl Index for u8 {
     fn get_type(_: Option<u8>) -> IndexType {
         IndexType::U8
     }
+
+    fn to_u32(&self) -> u32 {
+        *self as u32
+    }
 }
 
 unsafe impl Index for u16 {
     fn get_type(_: Option<u16>) -> IndexType {
         IndexType::U16
     }
+
+    fn to_u32(&self) -> u32 {
+        *self as u32
+    }
 }
 
 unsafe impl Index for u32 {
     fn get_type(_: Option<u32>) -> IndexType {
         IndexType::U32
     }
+
+    fn to_u32(&self) -> u32 {
+        *self
+    }
 }
 
 /// Object is convertible to an index buffer.
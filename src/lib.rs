@@ -3,8 +3,9 @@ Easy-to-use high-level OpenGL3+ wrapper.
 
 # Initialization
 
-This library defines the `DisplayBuild` trait which is curently implemented only on
-`glutin::WindowBuilder`.
+This library defines the `DisplayBuild` trait which is curently implemented on
+`glutin::WindowBuilder`, and on `glutin::HeadlessRendererBuilder` if the `headless`
+feature is enabled.
 
 Initialization is done by creating a `WindowBuilder` and calling `build_glium`.
 
@@ -24,6 +25,20 @@ fn main() {
 
 The `display` object is the most important object of this library.
 
+## Headless rendering
+
+If the `headless` feature is enabled, `DisplayBuild` is also implemented on
+`glutin::HeadlessRendererBuilder`. This creates a context without any window, which is
+useful for tests that compare images or for generating images on a server. The default
+framebuffer of a headless display can't be shown, so you should draw on textures or render
+buffers instead, for example with `golden::render_offscreen`.
+
+```ignore
+use glium::DisplayBuild;
+
+let display = glutin::HeadlessRendererBuilder::new(1024, 768).build_glium().unwrap();
+```
+
 The window where you are drawing on will produce events. They can be received by calling
 `display.poll_events()`.
 
@@ -171,8 +186,9 @@ use glium::Surface;
 # let uniforms = glium::uniforms::EmptyUniforms;
 let mut target = display.draw();
 target.clear_color(0.0, 0.0, 0.0, 0.0);  // filling the output with the black color
-target.draw(&vertex_buffer, &index_buffer, &program, &uniforms, &std::default::Default::default());
-target.finish();
+target.draw(&vertex_buffer, &index_buffer, &program, &uniforms,
+            &std::default::Default::default()).unwrap();
+target.finish().unwrap();
 ```
 
 */
@@ -202,17 +218,32 @@ extern crate nalgebra;
 
 pub use index_buffer::IndexBuffer;
 pub use vertex_buffer::{VertexBuffer, Vertex, VertexFormat};
-pub use program::{Program, ProgramCreationError};
+pub use program::{Program, ProgramCache, ProgramSources, ProgramCreationError, SubgroupSupport};
+pub use program::{SubgroupFeatures, SubgroupStages, ShaderStage};
+pub use program::{ComputeShader, TransformFeedbackMode, TransformFeedbackVarying};
+pub use program::{Precision, ShaderPrecision};
+pub use program::ProgramBinary;
+pub use program::{UniformInfo, AttributeInfo, UniformBlockInfo, UniformBlockMember};
+pub use program::VertexFormatMismatch;
 pub use program::ProgramCreationError::{CompilationError, LinkingError, ShaderTypeNotSupported};
 pub use texture::{Texture, Texture2d};
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, Relaxed};
 
 pub mod debug;
+pub mod draw_commands;
 pub mod framebuffer;
+pub mod golden;
 pub mod index_buffer;
+pub mod lines;
+pub mod native;
+pub mod oit;
+pub mod present;
+pub mod query;
 pub mod render_buffer;
+pub mod sync;
 pub mod uniforms;
 pub mod vertex_buffer;
 pub mod texture;
@@ -228,8 +259,11 @@ mod gl {
 	include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
 
-/// Internal trait for objects that are OpenGL objects.
-trait GlObject {
+/// Trait for objects that are OpenGL objects.
+///
+/// The id can be passed to OpenGL functions that are called from `Display::exec_in_context`,
+/// or to a library that uses the same OpenGL context.
+pub trait GlObject {
 	/// Returns the id of the object.
 	fn get_id(&self) -> gl::types::GLuint;
 }
@@ -259,8 +293,170 @@ pub enum BlendingFunction {
 	///
 	/// Means `(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA)` in Openctxt.gl.
 	LerpBySourceAlpha,
+
+	/// For each individual component (red, green, blue, and alpha), the minimum value is chosen
+	/// between the source and the destination.
+	Min,
+
+	/// For each individual component (red, green, blue, and alpha), the maximum value is chosen
+	/// between the source and the destination.
+	Max,
+
+	/// The source and the destination are multiplied by their factor, then added together.
+	///
+	/// Means `GL_FUNC_ADD` in OpenGL.
+	Addition {
+		/// The factor to apply to the source pixel.
+		source: LinearBlendingFactor,
+		/// The factor to apply to the destination pixel.
+		destination: LinearBlendingFactor,
+	},
+
+	/// The source and the destination are multiplied by their factor, then the destination
+	/// is subtracted from the source.
+	///
+	/// Means `GL_FUNC_SUBTRACT` in OpenGL.
+	Subtraction {
+		/// The factor to apply to the source pixel.
+		source: LinearBlendingFactor,
+		/// The factor to apply to the destination pixel.
+		destination: LinearBlendingFactor,
+	},
+
+	/// The source and the destination are multiplied by their factor, then the source
+	/// is subtracted from the destination.
+	///
+	/// Means `GL_FUNC_REVERSE_SUBTRACT` in OpenGL.
+	ReverseSubtraction {
+		/// The factor to apply to the source pixel.
+		source: LinearBlendingFactor,
+		/// The factor to apply to the destination pixel.
+		destination: LinearBlendingFactor,
+	},
+}
+
+impl BlendingFunction {
+	/// Returns the equation, the source factor and the destination factor.
+	fn to_gl(&self) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+		match *self {
+			BlendingFunction::AlwaysReplace => (gl::FUNC_ADD, gl::ONE, gl::ZERO),
+			BlendingFunction::LerpBySourceAlpha => {
+				(gl::FUNC_ADD, gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)
+			},
+			BlendingFunction::Min => (gl::MIN, gl::ONE, gl::ONE),
+			BlendingFunction::Max => (gl::MAX, gl::ONE, gl::ONE),
+			BlendingFunction::Addition { source, destination } => {
+				(gl::FUNC_ADD, source.to_glenum(), destination.to_glenum())
+			},
+			BlendingFunction::Subtraction { source, destination } => {
+				(gl::FUNC_SUBTRACT, source.to_glenum(), destination.to_glenum())
+			},
+			BlendingFunction::ReverseSubtraction { source, destination } => {
+				(gl::FUNC_REVERSE_SUBTRACT, source.to_glenum(), destination.to_glenum())
+			},
+		}
+	}
+}
+
+/// Indicates which value to multiply each component with.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum LinearBlendingFactor {
+	/// Multiply the source or destination component by zero, which always
+	/// gives `0.0`.
+	Zero,
+
+	/// Multiply the source or destination component by one, which always
+	/// gives you the original value.
+	One,
+
+	/// Multiply the source or destination component by its corresponding value
+	/// in the source.
+	SourceColor,
+
+	/// Equivalent to `1 - SourceColor`.
+	OneMinusSourceColor,
+
+	/// Multiply the source or destination component by its corresponding value
+	/// in the destination.
+	DestinationColor,
+
+	/// Equivalent to `1 - DestinationColor`.
+	OneMinusDestinationColor,
+
+	/// Multiply the source or destination component by the alpha value of the source.
+	SourceAlpha,
+
+	/// Equivalent to `1 - SourceAlpha`.
+	OneMinusSourceAlpha,
+
+	/// Multiply the source or destination component by the alpha value of the destination.
+	DestinationAlpha,
+
+	/// Equivalent to `1 - DestinationAlpha`.
+	OneMinusDestinationAlpha,
+
+	/// Multiply the source or destination component by its corresponding value in
+	/// `DrawParameters::blending_constant_color`.
+	ConstantColor,
+
+	/// Equivalent to `1 - ConstantColor`.
+	OneMinusConstantColor,
+
+	/// Multiply the source or destination component by the alpha value of
+	/// `DrawParameters::blending_constant_color`.
+	ConstantAlpha,
+
+	/// Equivalent to `1 - ConstantAlpha`.
+	OneMinusConstantAlpha,
+
+	/// Multiply the red, green and blue components by the minimum between the alpha of the
+	/// source and `1 -` the alpha of the destination. The alpha component is multiplied by one.
+	SourceAlphaSaturate,
+}
+
+impl ToGlEnum for LinearBlendingFactor {
+	fn to_glenum(&self) -> gl::types::GLenum {
+		match *self {
+			LinearBlendingFactor::Zero => gl::ZERO,
+			LinearBlendingFactor::One => gl::ONE,
+			LinearBlendingFactor::SourceColor => gl::SRC_COLOR,
+			LinearBlendingFactor::OneMinusSourceColor => gl::ONE_MINUS_SRC_COLOR,
+			LinearBlendingFactor::DestinationColor => gl::DST_COLOR,
+			LinearBlendingFactor::OneMinusDestinationColor => gl::ONE_MINUS_DST_COLOR,
+			LinearBlendingFactor::SourceAlpha => gl::SRC_ALPHA,
+			LinearBlendingFactor::OneMinusSourceAlpha => gl::ONE_MINUS_SRC_ALPHA,
+			LinearBlendingFactor::DestinationAlpha => gl::DST_ALPHA,
+			LinearBlendingFactor::OneMinusDestinationAlpha => gl::ONE_MINUS_DST_ALPHA,
+			LinearBlendingFactor::ConstantColor => gl::CONSTANT_COLOR,
+			LinearBlendingFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+			LinearBlendingFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+			LinearBlendingFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+			LinearBlendingFactor::SourceAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+		}
+	}
+}
+
+/// Blending functions of a single color attachment.
+///
+/// See `DrawParameters::attachments_blending`.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub struct AttachmentBlending {
+	/// The function to use for the red, green and blue components.
+	pub color: BlendingFunction,
+
+	/// The function to use for the alpha component.
+	pub alpha: BlendingFunction,
 }
 
+/// Number of color attachments whose blending functions can be specified individually.
+pub const MAX_BLENDING_ATTACHMENTS: uint = 8;
+
+/// Number of viewports that can be specified in `DrawParameters::viewports`.
+///
+/// This is the minimum value of `GL_MAX_VIEWPORTS` required by OpenGL. The actual limit of
+/// the backend is in `Capabilities::max_viewports`.
+pub const MAX_VIEWPORTS: uint = 16;
+
 /// Describes how triangles should be filtered before the fragment processing. Backface culling
 /// is purely an optimization. If you don't know what this does, just use `CullingDisabled`.
 ///
@@ -437,6 +633,131 @@ impl ToGlEnum for PolygonMode {
 	}
 }
 
+/// Hint about the quality of the smoothing of lines and polygons.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum Smooth {
+	/// The most efficient option should be chosen.
+	Fastest,
+
+	/// The most correct or highest quality option should be chosen.
+	Nicest,
+
+	/// No preference.
+	DontCare,
+}
+
+impl ToGlEnum for Smooth {
+	fn to_glenum(&self) -> gl::types::GLenum {
+		match *self {
+			Smooth::Fastest => gl::FASTEST,
+			Smooth::Nicest => gl::NICEST,
+			Smooth::DontCare => gl::DONT_CARE,
+		}
+	}
+}
+
+/// Vertex of a primitive whose values are used for the outputs of the vertex shader that are
+/// declared `flat`.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum ProvokingVertex {
+	/// The last vertex of each primitive is used. This is the default.
+	LastVertex,
+
+	/// The first vertex of each primitive is used.
+	///
+	/// Not supported by OpenGL ES. See `Capabilities::supports_provoking_vertex`.
+	FirstVertex,
+}
+
+impl ToGlEnum for ProvokingVertex {
+	fn to_glenum(&self) -> gl::types::GLenum {
+		match *self {
+			ProvokingVertex::LastVertex => gl::LAST_VERTEX_CONVENTION,
+			ProvokingVertex::FirstVertex => gl::FIRST_VERTEX_CONVENTION,
+		}
+	}
+}
+
+/// Enables or disables `GL_LINE_SMOOTH` or `GL_POLYGON_SMOOTH` and sets its hint.
+///
+/// Returns the number of state changes.
+fn sync_smooth(gl: &gl::Gl, smooth: Option<Smooth>, cap: gl::types::GLenum,
+			   hint_target: gl::types::GLenum, enabled: &mut bool,
+			   current_hint: &mut gl::types::GLenum) -> uint
+{
+	let mut changes = 0;
+
+	match smooth {
+		Some(smooth) => unsafe {
+			if !*enabled {
+				gl.Enable(cap);
+				*enabled = true;
+				changes += 1;
+			}
+
+			let hint = smooth.to_glenum();
+			if *current_hint != hint {
+				gl.Hint(hint_target, hint);
+				*current_hint = hint;
+				changes += 1;
+			}
+		},
+		None => unsafe {
+			if *enabled {
+				gl.Disable(cap);
+				*enabled = false;
+				changes += 1;
+			}
+		},
+	}
+
+	changes
+}
+
+/// Enables or disables a capability if its current state is different.
+///
+/// Returns the number of state changes.
+fn sync_enable(gl: &gl::Gl, cap: gl::types::GLenum, enable: bool, enabled: &mut bool) -> uint {
+	if *enabled == enable {
+		return 0;
+	}
+
+	unsafe {
+		if enable {
+			gl.Enable(cap);
+		} else {
+			gl.Disable(cap);
+		}
+	}
+
+	*enabled = enable;
+	1
+}
+
+/// A condition that must be fulfilled for the draw commands to be executed.
+///
+/// Built with `OcclusionQuery::condition`. The draw commands are only executed if at least
+/// one sample passed the depth test while the query was active. The query is borrowed, so
+/// it can't be destroyed while the condition exists.
+#[deriving(Clone, Copy)]
+pub struct ConditionalRendering<'a> {
+	query: &'a query::OcclusionQuery,
+	wait: query::WaitMode,
+}
+
+impl<'a> std::fmt::Show for ConditionalRendering<'a> {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		(format!("ConditionalRendering {{ query: {}, wait: {} }}", self.query.get_id(),
+				 self.wait)).fmt(formatter)
+	}
+}
+
+impl<'a> PartialEq for ConditionalRendering<'a> {
+	fn eq(&self, other: &ConditionalRendering) -> bool {
+		self.query.get_id() == other.query.get_id() && self.wait == other.wait
+	}
+}
+
 /// Represents the parameters to use when drawing.
 ///
 /// Example:
@@ -449,7 +770,7 @@ impl ToGlEnum for PolygonMode {
 /// ```
 ///
 #[deriving(Clone, Copy, Show, PartialEq)]
-pub struct DrawParameters {
+pub struct DrawParameters<'a> {
 
 	/// The function that the GPU will use to determine whether to write over an existing pixel
 	/// on the target.
@@ -459,6 +780,14 @@ pub struct DrawParameters {
 	/// The default is `Overwrite`.
 	pub depth_function: DepthFunction,
 
+	/// Whether or not the depth value of the pixels that pass the depth test should be written
+	/// to the depth buffer.
+	///
+	/// Set this to `false` if you want to test against the depth buffer without modifying it.
+	///
+	/// The default is `true`.
+	pub depth_write: bool,
+
 	/// The range of Z coordinates in surface coordinates.
 	///
 	/// Just like OpenGL turns X and Y coordinates between `-1.0` and `1.0` into surface
@@ -473,17 +802,107 @@ pub struct DrawParameters {
 	/// It is possible for the "near" value to be greater than the "far" value.
 	pub depth_range: (f32, f32),
 
+	/// If `true`, the primitives are not clipped against the near and far planes. Instead,
+	/// their depth values are clamped to the depth range. Default is `false`.
+	///
+	/// This is useful for shadow volumes and for objects that are so close to the camera
+	/// that they would otherwise be cut. Not supported by OpenGL ES. See
+	/// `Capabilities::supports_depth_clamp`.
+	pub depth_clamp: bool,
+
 	/// The function that the GPU will use to merge the existing pixel with the pixel that is
 	/// being written.
 	///
 	/// `None` means "don't care" (usually when you know that the alpha is always 1).
 	pub blending_function: Option<BlendingFunction>,
 
+	/// The function to use for the alpha component, if it must be different from the one
+	/// of the red, green and blue components.
+	///
+	/// `None` means "same as `blending_function`". The default is `None`.
+	pub blending_alpha_function: Option<BlendingFunction>,
+
+	/// The color used by the `Constant*` blending factors.
+	///
+	/// The default is `(0.0, 0.0, 0.0, 0.0)`.
+	pub blending_constant_color: (f32, f32, f32, f32),
+
+	/// Blending functions to use for individual color attachments, indexed by the location
+	/// of the fragment shader output.
+	///
+	/// Attachments whose entry is `None` use `blending_function` and
+	/// `blending_alpha_function`. The default is `None` for all attachments.
+	///
+	/// Requires OpenGL 4.0, OpenGL ES 3.2 or the `GL_ARB_draw_buffers_blend` extension.
+	/// Drawing panics if one of the entries is `Some` and the backend doesn't support it.
+	pub attachments_blending: [Option<AttachmentBlending>, ..MAX_BLENDING_ATTACHMENTS],
+
+	/// Which of the red, green, blue and alpha components of the pixels should be written to
+	/// the color buffer.
+	///
+	/// The default is `(true, true, true, true)`.
+	pub color_mask: (bool, bool, bool, bool),
+
+	/// If `true`, the colors written by the fragment shader are considered to be linear and
+	/// are converted to the sRGB color space when they are written to an sRGB attachment.
+	/// Blending is also done in linear space. Attachments that are not sRGB are unaffected.
+	///
+	/// The default framebuffer is sRGB only if it has been requested when creating the
+	/// window, with the `with_srgb` method of the `WindowBuilder`.
+	///
+	/// The default is `false`. Drawing panics if this is `true` and the backend doesn't
+	/// support toggling the conversion.
+	pub framebuffer_srgb: bool,
+
+	/// Hint about the bounding box of the primitives that are drawn, in clip space, as a
+	/// `(min, max)` pair of `(x, y, z, w)` coordinates.
+	///
+	/// Some GPUs, mostly on mobile, use this to discard early the primitives and tessellated
+	/// patches that are outside of the box. Specifying a box that is too small gives
+	/// undefined results for the primitives that exceed it.
+	///
+	/// `None` means "no hint". The default is `None`. This hint is ignored if the backend
+	/// doesn't support it.
+	pub primitive_bounding_box: Option<((f32, f32, f32, f32), (f32, f32, f32, f32))>,
+
 	/// Width in pixels of the lines to draw when drawing lines.
 	///
 	/// `None` means "don't care". Use this when you don't draw lines.
 	pub line_width: Option<f32>,
 
+	/// Diameter in pixels of the points to draw when drawing points.
+	///
+	/// `None` means that the vertex shader writes the size of each point in `gl_PointSize`,
+	/// which is useful for particles. The size is undefined if the vertex shader doesn't
+	/// write it. The default is `Some(1.0)`. Drawing returns an error if the size is not
+	/// positive or is outside of the range supported by the backend.
+	///
+	/// OpenGL ES always uses `gl_PointSize`, and drawing returns an error if this is `Some`
+	/// with a value other than `1.0`. In the fragment shader, `gl_PointCoord` contains the
+	/// coordinates of the pixel within the point, which lets you draw a texture on each point.
+	pub point_size: Option<f32>,
+
+	/// If specified, the edges of lines are antialiased by computing the coverage of each
+	/// pixel. The value is a hint about the quality of the antialiasing. Default is `None`.
+	///
+	/// This doesn't require a multisampled surface, but the coverage is stored in the alpha
+	/// component, so you must enable blending with `Addition { source: SourceAlpha,
+	/// destination: OneMinusSourceAlpha }` for it to have any effect. Some drivers only
+	/// smooth lines whose width is 1.
+	///
+	/// Not supported by OpenGL ES. See `Capabilities::supports_smoothing`.
+	pub line_smooth: Option<Smooth>,
+
+	/// If specified, the edges of polygons are antialiased by computing the coverage of each
+	/// pixel. The value is a hint about the quality of the antialiasing. Default is `None`.
+	///
+	/// Like `line_smooth`, this requires blending. Adjacent triangles show seams unless they
+	/// are drawn from front to back with a `SourceAlphaSaturate` blending function, and many
+	/// drivers ignore this setting entirely. Prefer multisampling when it is available.
+	///
+	/// Not supported by OpenGL ES. See `Capabilities::supports_smoothing`.
+	pub polygon_smooth: Option<Smooth>,
+
 	/// Whether or not the GPU should filter out some faces.
 	///
 	/// After the vertex shader stage, the GPU will try to remove the faces that aren't facing
@@ -497,6 +916,19 @@ pub struct DrawParameters {
 	/// See the documentation of `PolygonMode` for more infos.
 	pub polygon_mode: PolygonMode,
 
+	/// If specified, the depth values of the polygons are offset by
+	/// `factor * DZ + units * r` before the depth test, where `DZ` is the slope of the
+	/// polygon and `r` the smallest resolvable depth difference. Default is `None`.
+	///
+	/// The value is a `(factor, units)` tuple. Use this to avoid z-fighting between decals
+	/// and the surfaces they are drawn on, or shadow acne when rendering shadow maps.
+	/// The offset applies to the primitives drawn with the current `polygon_mode`.
+	pub polygon_offset: Option<(f32, f32)>,
+
+	/// Vertex whose values are used for the `flat` outputs of the vertex shader. The default
+	/// is `LastVertex`.
+	pub provoking_vertex: ProvokingVertex,
+
 	/// Whether multisample antialiasing (MSAA) should be used. Default value is `true`.
 	///
 	/// Note that you will need to set the appropriate option when creating the window.
@@ -514,30 +946,270 @@ pub struct DrawParameters {
 	///
 	/// `None` means "use the whole surface".
 	pub viewport: Option<Rect>,
+
+	/// Viewports selected by writing `gl_ViewportIndex` in a geometry shader, indexed by
+	/// the value written.
+	///
+	/// This lets you draw to multiple regions of the target at once, for example for
+	/// split-screen or stereo rendering, without using multiple framebuffers. Entries that
+	/// are `None` use the same value as `viewport`. The default is `None` for all entries.
+	///
+	/// Requires OpenGL 4.1 or the `GL_ARB_viewport_array` extension. See
+	/// `Capabilities::max_viewports`.
+	pub viewports: [Option<Rect>, ..MAX_VIEWPORTS],
+
+	/// If specified, only pixels in this rect will be displayed. Default is `None`.
+	///
+	/// This is different from a viewport. The image will stretch to fill the viewport, but
+	/// not the scissor box.
+	pub scissor: Option<Rect>,
+
+	/// If `true`, the maximum value of the type of the indices (`0xFF`, `0xFFFF` or
+	/// `0xFFFFFFFF`) is not a vertex but ends the current primitive and starts a new one.
+	/// Default is `false`.
+	///
+	/// This lets you draw multiple line strips or triangle strips with a single index buffer.
+	/// It has no effect on lists of points, lines or triangles, and when drawing without
+	/// indices. See `Capabilities::supports_primitive_restart`.
+	pub primitive_restart_index: bool,
+
+	/// If specified, the draw commands are only executed if the condition is fulfilled.
+	/// Default is `None`.
+	///
+	/// The condition is evaluated by the GPU, which avoids waiting for the result of an
+	/// occlusion query on the CPU. See the `query` module.
+	pub condition: Option<ConditionalRendering<'a>>,
 }
 
-impl std::default::Default for DrawParameters {
-	fn default() -> DrawParameters {
+impl<'a> std::default::Default for DrawParameters<'a> {
+	fn default() -> DrawParameters<'a> {
 		DrawParameters {
 			depth_function: DepthFunction::Overwrite,
+			depth_write: true,
 			depth_range: (0.0, 1.0),
+			depth_clamp: false,
 			blending_function: Some(BlendingFunction::AlwaysReplace),
+			blending_alpha_function: None,
+			blending_constant_color: (0.0, 0.0, 0.0, 0.0),
+			attachments_blending: [None, ..MAX_BLENDING_ATTACHMENTS],
+			color_mask: (true, true, true, true),
+			framebuffer_srgb: false,
+			primitive_bounding_box: None,
 			line_width: None,
+			point_size: Some(1.0),
+			line_smooth: None,
+			polygon_smooth: None,
 			backface_culling: BackfaceCullingMode::CullingDisabled,
 			polygon_mode: PolygonMode::Fill,
+			polygon_offset: None,
+			provoking_vertex: ProvokingVertex::LastVertex,
 			multisampling: true,
 			viewport: None,
+			viewports: [None, ..MAX_VIEWPORTS],
+			scissor: None,
+			primitive_restart_index: false,
+			condition: None,
 		}
 	}
 }
 
-impl DrawParameters {
-	/// Checks parameters and panics if something is wrong.
-	fn validate(&self) {
+impl<'a> DrawParameters<'a> {
+	/// Returns a copy of these parameters without their condition, which can be sent to the
+	/// rendering thread, and the query id and wait mode of the condition.
+	fn split_condition(&self) -> (DrawParameters<'static>,
+								  Option<(gl::types::GLuint, gl::types::GLenum)>)
+	{
+		let condition = self.condition.map(|c| (c.query.get_id(), c.wait.to_glenum()));
+		let parameters = DrawParameters { condition: None, .. *self };
+
+		// the condition is the only member that borrows something
+		(unsafe { std::mem::transmute(parameters) }, condition)
+	}
+
+	/// Checks parameters and returns an error if something is wrong.
+	fn validate(&self, display: &Display) -> Result<(), DrawError> {
 		if self.depth_range.0 < 0.0 || self.depth_range.0 > 1.0 ||
 		   self.depth_range.1 < 0.0 || self.depth_range.1 > 1.0
 		{
-			panic!("Depth range must be between 0 and 1");
+			return Err(DrawError::InvalidDepthRange);
+		}
+
+		if self.depth_clamp && !display.context.context.capabilities().depth_clamp {
+			return Err(DrawError::DepthClampNotSupported);
+		}
+
+		if self.polygon_mode != PolygonMode::Fill &&
+		   !display.context.context.capabilities().polygon_mode
+		{
+			return Err(DrawError::PolygonModeNotSupported);
+		}
+
+		if self.point_size.map(|s| s != 1.0).unwrap_or(false) &&
+		   display.context.context.capabilities().opengl_es
+		{
+			return Err(DrawError::PointSizeNotSupported);
+		}
+
+		if let Some(point_size) = self.point_size {
+			let range = display.context.context.capabilities().point_size_range;
+			if !(point_size > 0.0) || point_size < range.0 || point_size > range.1 {
+				return Err(DrawError::InvalidPointSize);
+			}
+		}
+
+		if self.provoking_vertex != ProvokingVertex::LastVertex &&
+		   !display.context.context.capabilities().provoking_vertex
+		{
+			return Err(DrawError::ProvokingVertexNotSupported);
+		}
+
+		if self.attachments_blending.iter().any(|b| b.is_some()) &&
+		   !display.context.context.capabilities().draw_buffers_blend
+		{
+			return Err(DrawError::PerAttachmentBlendingNotSupported);
+		}
+
+		if self.framebuffer_srgb && !display.context.context.capabilities().framebuffer_srgb {
+			return Err(DrawError::FramebufferSrgbNotSupported);
+		}
+
+		if self.condition.is_some() &&
+		   !display.context.context.capabilities().conditional_render
+		{
+			return Err(DrawError::ConditionalRenderingNotSupported);
+		}
+
+		if (self.line_smooth.is_some() || self.polygon_smooth.is_some()) &&
+		   !display.context.context.capabilities().smoothing
+		{
+			return Err(DrawError::SmoothingNotSupported);
+		}
+
+		if self.primitive_restart_index {
+			let capabilities = display.context.context.capabilities();
+			if !capabilities.primitive_restart_fixed_index && !capabilities.primitive_restart {
+				return Err(DrawError::PrimitiveRestartNotSupported);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Checks the parameters against a surface and returns an error if something is wrong.
+	fn validate_for_surface<S>(&self, display: &Display, surface: &S) -> Result<(), DrawError>
+		where S: Surface
+	{
+		try!(self.validate(display));
+
+		if self.depth_function.requires_depth_buffer() && !surface.has_depth_buffer() {
+			return Err(DrawError::NoDepthBuffer);
+		}
+
+		if let Some(viewport) = self.viewport {
+			let max = display.context.context.capabilities().max_viewport_dims;
+			if viewport.width > max.0 as u32 || viewport.height > max.1 as u32 {
+				return Err(DrawError::ViewportTooLarge);
+			}
+		}
+
+		if let Some(count) = self.viewports.iter().rposition(|v| v.is_some()).map(|n| n + 1) {
+			let capabilities = display.context.context.capabilities();
+
+			let max_viewports = match capabilities.max_viewports {
+				Some(max) => max as uint,
+				None => return Err(DrawError::ViewportArrayNotSupported)
+			};
+
+			if count > max_viewports {
+				return Err(DrawError::TooManyViewports);
+			}
+
+			let max = capabilities.max_viewport_dims;
+			for viewport in self.viewports.iter().filter_map(|v| v.as_ref()) {
+				if viewport.width > max.0 as u32 || viewport.height > max.1 as u32 {
+					return Err(DrawError::ViewportTooLarge);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Synchronizes the blending functions with the current ctxt.state.
+	fn sync_blending(&self, ctxt: &mut context::CommandContext) {
+		let per_attachment = self.attachments_blending.iter().any(|b| b.is_some());
+
+		if self.blending_function.is_none() && self.blending_alpha_function.is_none() &&
+		   !per_attachment
+		{
+			return;     // don't care
+		}
+
+		let color = self.blending_function.unwrap_or(BlendingFunction::AlwaysReplace);
+		let alpha = self.blending_alpha_function.unwrap_or(color);
+
+		if color == BlendingFunction::AlwaysReplace && alpha == BlendingFunction::AlwaysReplace &&
+		   !per_attachment
+		{
+			if ctxt.state.enabled_blend {
+				unsafe { ctxt.gl.Disable(gl::BLEND); }
+				ctxt.state.enabled_blend = false;
+				ctxt.state.statistics.state_changes += 1;
+			}
+
+			return;
+		}
+
+		let (color_eq, color_src, color_dst) = color.to_gl();
+		let (alpha_eq, alpha_src, alpha_dst) = alpha.to_gl();
+
+		unsafe {
+			if ctxt.state.blend_equation != (color_eq, alpha_eq) {
+				ctxt.gl.BlendEquationSeparate(color_eq, alpha_eq);
+				ctxt.state.blend_equation = (color_eq, alpha_eq);
+				ctxt.state.statistics.state_changes += 1;
+			}
+
+			if ctxt.state.blend_func != (color_src, color_dst, alpha_src, alpha_dst) {
+				ctxt.gl.BlendFuncSeparate(color_src, color_dst, alpha_src, alpha_dst);
+				ctxt.state.blend_func = (color_src, color_dst, alpha_src, alpha_dst);
+				ctxt.state.statistics.state_changes += 1;
+			}
+
+			if ctxt.state.blend_color != self.blending_constant_color {
+				let (r, g, b, a) = self.blending_constant_color;
+				ctxt.gl.BlendColor(r, g, b, a);
+				ctxt.state.blend_color = self.blending_constant_color;
+				ctxt.state.statistics.state_changes += 1;
+			}
+
+			if per_attachment {
+				for (buffer, blending) in self.attachments_blending.iter().enumerate() {
+					let blending = match blending {
+						&Some(ref b) => b,
+						&None => continue
+					};
+
+					let (color_eq, color_src, color_dst) = blending.color.to_gl();
+					let (alpha_eq, alpha_src, alpha_dst) = blending.alpha.to_gl();
+					let buffer = buffer as gl::types::GLuint;
+
+					ctxt.gl.BlendEquationSeparatei(buffer, color_eq, alpha_eq);
+					ctxt.gl.BlendFuncSeparatei(buffer, color_src, color_dst,
+											   alpha_src, alpha_dst);
+				}
+
+				// the indexed calls leave the global state in an unknown configuration
+				ctxt.state.blend_equation = (0, 0);
+				ctxt.state.blend_func = (0, 0, 0, 0);
+				ctxt.state.statistics.state_changes += 1;
+			}
+
+			if !ctxt.state.enabled_blend {
+				ctxt.gl.Enable(gl::BLEND);
+				ctxt.state.enabled_blend = true;
+				ctxt.state.statistics.state_changes += 1;
+			}
 		}
 	}
 
@@ -549,6 +1221,7 @@ impl DrawParameters {
 				if ctxt.state.enabled_depth_test {
 					ctxt.gl.Disable(gl::DEPTH_TEST);
 					ctxt.state.enabled_depth_test = false;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			},
 			depth_function => unsafe {
@@ -556,41 +1229,95 @@ impl DrawParameters {
 				if ctxt.state.depth_func != depth_function {
 					ctxt.gl.DepthFunc(depth_function);
 					ctxt.state.depth_func = depth_function;
+					ctxt.state.statistics.state_changes += 1;
 				}
 				if !ctxt.state.enabled_depth_test {
 					ctxt.gl.Enable(gl::DEPTH_TEST);
 					ctxt.state.enabled_depth_test = true;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			}
 		}
 
+		// depth mask
+		if self.depth_write != ctxt.state.depth_mask {
+			unsafe {
+				ctxt.gl.DepthMask(if self.depth_write { gl::TRUE } else { gl::FALSE });
+			}
+			ctxt.state.depth_mask = self.depth_write;
+			ctxt.state.statistics.state_changes += 1;
+		}
+
 		// depth range
 		if self.depth_range != ctxt.state.depth_range {
 			unsafe {
-				ctxt.gl.DepthRange(self.depth_range.0 as f64, self.depth_range.1 as f64);
+				if ctxt.opengl_es {
+					ctxt.gl.DepthRangef(self.depth_range.0, self.depth_range.1);
+				} else {
+					ctxt.gl.DepthRange(self.depth_range.0 as f64, self.depth_range.1 as f64);
+				}
 			}
 			ctxt.state.depth_range = self.depth_range;
+			ctxt.state.statistics.state_changes += 1;
 		}
 
-		// blending function
-		match self.blending_function {
-			Some(BlendingFunction::AlwaysReplace) => unsafe {
-				if ctxt.state.enabled_blend {
-					ctxt.gl.Disable(gl::BLEND);
-					ctxt.state.enabled_blend = false;
+		// depth clamp
+		if self.depth_clamp != ctxt.state.enabled_depth_clamp {
+			unsafe {
+				if self.depth_clamp {
+					ctxt.gl.Enable(gl::DEPTH_CLAMP);
+				} else {
+					ctxt.gl.Disable(gl::DEPTH_CLAMP);
 				}
-			},
-			Some(BlendingFunction::LerpBySourceAlpha) => unsafe {
-				if ctxt.state.blend_func != (gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA) {
-					ctxt.gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-					ctxt.state.blend_func = (gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+			}
+			ctxt.state.enabled_depth_clamp = self.depth_clamp;
+			ctxt.state.statistics.state_changes += 1;
+		}
+
+		// blending function
+		self.sync_blending(ctxt);
+
+		// color mask
+		if self.color_mask != ctxt.state.color_mask {
+			let to_gl = |&: b: bool| if b { gl::TRUE } else { gl::FALSE };
+			unsafe {
+				ctxt.gl.ColorMask(to_gl(self.color_mask.0), to_gl(self.color_mask.1),
+								  to_gl(self.color_mask.2), to_gl(self.color_mask.3));
+			}
+			ctxt.state.color_mask = self.color_mask;
+			ctxt.state.statistics.state_changes += 1;
+		}
+
+		// sRGB conversion
+		if self.framebuffer_srgb != ctxt.state.enabled_framebuffer_srgb &&
+		   ctxt.capabilities.framebuffer_srgb
+		{
+			unsafe {
+				if self.framebuffer_srgb {
+					ctxt.gl.Enable(gl::FRAMEBUFFER_SRGB);
+				} else {
+					ctxt.gl.Disable(gl::FRAMEBUFFER_SRGB);
 				}
-				if !ctxt.state.enabled_blend {
-					ctxt.gl.Enable(gl::BLEND);
-					ctxt.state.enabled_blend = true;
+			}
+			ctxt.state.enabled_framebuffer_srgb = self.framebuffer_srgb;
+			ctxt.state.statistics.state_changes += 1;
+		}
+
+		// primitive bounding box
+		if ctxt.capabilities.primitive_bounding_box {
+			// when there is no hint, the box covers the whole clip space
+			let bounding_box = self.primitive_bounding_box.unwrap_or(((-1.0, -1.0, -1.0, 1.0),
+																	  (1.0, 1.0, 1.0, 1.0)));
+
+			if ctxt.state.primitive_bounding_box != bounding_box {
+				let ((min_x, min_y, min_z, min_w), (max_x, max_y, max_z, max_w)) = bounding_box;
+				unsafe {
+					ctxt.gl.PrimitiveBoundingBoxARB(min_x, min_y, min_z, min_w,
+													max_x, max_y, max_z, max_w);
 				}
-			},
-			_ => ()
+				ctxt.state.primitive_bounding_box = bounding_box;
+				ctxt.state.statistics.state_changes += 1;
+			}
 		}
 
 		// line width
@@ -599,60 +1326,150 @@ impl DrawParameters {
 				unsafe {
 					ctxt.gl.LineWidth(line_width);
 					ctxt.state.line_width = line_width;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			}
 		}
 
-		// back-face culling
-		// note: we never change the value of `glFrontFace`, whose default is GL_CCW
-		//  that's why `CullClockWise` uses `GL_BACK` for example
-		match self.backface_culling {
-			BackfaceCullingMode::CullingDisabled => unsafe {
-				if ctxt.state.enabled_cull_face {
+		// point size
+		if !ctxt.opengl_es {
+			let changes = sync_enable(ctxt.gl, gl::PROGRAM_POINT_SIZE, self.point_size.is_none(),
+									  &mut ctxt.state.enabled_program_point_size);
+			ctxt.state.statistics.state_changes += changes;
+
+			if let Some(point_size) = self.point_size {
+				if ctxt.state.point_size != point_size {
+					unsafe { ctxt.gl.PointSize(point_size) };
+					ctxt.state.point_size = point_size;
+					ctxt.state.statistics.state_changes += 1;
+				}
+			}
+		}
+
+		// point sprites, which only affect points
+		if ctxt.capabilities.point_sprite {
+			let changes = sync_enable(ctxt.gl, gl::POINT_SPRITE, true,
+									  &mut ctxt.state.enabled_point_sprite);
+			ctxt.state.statistics.state_changes += changes;
+		}
+
+		// smoothing
+		if ctxt.capabilities.smoothing {
+			let changes = sync_smooth(ctxt.gl, self.line_smooth, gl::LINE_SMOOTH,
+									  gl::LINE_SMOOTH_HINT, &mut ctxt.state.enabled_line_smooth,
+									  &mut ctxt.state.line_smooth_hint);
+			ctxt.state.statistics.state_changes += changes;
+
+			let changes = sync_smooth(ctxt.gl, self.polygon_smooth, gl::POLYGON_SMOOTH,
+									  gl::POLYGON_SMOOTH_HINT,
+									  &mut ctxt.state.enabled_polygon_smooth,
+									  &mut ctxt.state.polygon_smooth_hint);
+			ctxt.state.statistics.state_changes += changes;
+		}
+
+		// back-face culling
+		// note: we never change the value of `glFrontFace`, whose default is GL_CCW
+		//  that's why `CullClockWise` uses `GL_BACK` for example
+		match self.backface_culling {
+			BackfaceCullingMode::CullingDisabled => unsafe {
+				if ctxt.state.enabled_cull_face {
 					ctxt.gl.Disable(gl::CULL_FACE);
 					ctxt.state.enabled_cull_face = false;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			},
 			BackfaceCullingMode::CullCounterClockWise => unsafe {
 				if !ctxt.state.enabled_cull_face {
 					ctxt.gl.Enable(gl::CULL_FACE);
 					ctxt.state.enabled_cull_face = true;
+					ctxt.state.statistics.state_changes += 1;
 				}
 				if ctxt.state.cull_face != gl::FRONT {
 					ctxt.gl.CullFace(gl::FRONT);
 					ctxt.state.cull_face = gl::FRONT;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			},
 			BackfaceCullingMode::CullClockWise => unsafe {
 				if !ctxt.state.enabled_cull_face {
 					ctxt.gl.Enable(gl::CULL_FACE);
 					ctxt.state.enabled_cull_face = true;
+					ctxt.state.statistics.state_changes += 1;
 				}
 				if ctxt.state.cull_face != gl::BACK {
 					ctxt.gl.CullFace(gl::BACK);
 					ctxt.state.cull_face = gl::BACK;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			},
 		}
 
 		// polygon mode
-		unsafe {
+		if ctxt.capabilities.polygon_mode {
 			let polygon_mode = self.polygon_mode.to_glenum();
 			if ctxt.state.polygon_mode != polygon_mode {
-				ctxt.gl.PolygonMode(gl::FRONT_AND_BACK, polygon_mode);
+				unsafe { ctxt.gl.PolygonMode(gl::FRONT_AND_BACK, polygon_mode) };
 				ctxt.state.polygon_mode = polygon_mode;
+				ctxt.state.statistics.state_changes += 1;
+			}
+		}
+
+		// provoking vertex
+		if ctxt.capabilities.provoking_vertex {
+			let provoking_vertex = self.provoking_vertex.to_glenum();
+			if ctxt.state.provoking_vertex != provoking_vertex {
+				unsafe { ctxt.gl.ProvokingVertex(provoking_vertex) };
+				ctxt.state.provoking_vertex = provoking_vertex;
+				ctxt.state.statistics.state_changes += 1;
+			}
+		}
+
+		// polygon offset
+		{
+			let polygon_mode = self.polygon_mode.to_glenum();
+			let offset = self.polygon_offset.is_some();
+
+			unsafe {
+				if let Some(polygon_offset) = self.polygon_offset {
+					if ctxt.state.polygon_offset != polygon_offset {
+						ctxt.gl.PolygonOffset(polygon_offset.0, polygon_offset.1);
+						ctxt.state.polygon_offset = polygon_offset;
+						ctxt.state.statistics.state_changes += 1;
+					}
+				}
+
+				let changes = sync_enable(ctxt.gl, gl::POLYGON_OFFSET_FILL,
+										  offset && polygon_mode == gl::FILL,
+										  &mut ctxt.state.enabled_polygon_offset_fill);
+				ctxt.state.statistics.state_changes += changes;
+
+				// `GL_POLYGON_OFFSET_LINE` and `GL_POLYGON_OFFSET_POINT` don't exist in GLES
+				if !ctxt.opengl_es {
+					let changes = sync_enable(ctxt.gl, gl::POLYGON_OFFSET_LINE,
+											  offset && polygon_mode == gl::LINE,
+											  &mut ctxt.state.enabled_polygon_offset_line);
+					ctxt.state.statistics.state_changes += changes;
+
+					let changes = sync_enable(ctxt.gl, gl::POLYGON_OFFSET_POINT,
+											  offset && polygon_mode == gl::POINT,
+											  &mut ctxt.state.enabled_polygon_offset_point);
+					ctxt.state.statistics.state_changes += changes;
+				}
 			}
 		}
 
 		// multisampling
-		if ctxt.state.enabled_multisample != self.multisampling {
+		// OpenGL ES always enables multisampling if the framebuffer has samples
+		if ctxt.state.enabled_multisample != self.multisampling && !ctxt.opengl_es {
 			unsafe {
 				if self.multisampling {
 					ctxt.gl.Enable(gl::MULTISAMPLE);
 					ctxt.state.enabled_multisample = true;
+					ctxt.state.statistics.state_changes += 1;
 				} else {
 					ctxt.gl.Disable(gl::MULTISAMPLE);
 					ctxt.state.enabled_multisample = false;
+					ctxt.state.statistics.state_changes += 1;
 				}
 			}
 		}
@@ -671,6 +1488,8 @@ impl DrawParameters {
 			if ctxt.state.viewport != viewport {
 				unsafe { ctxt.gl.Viewport(viewport.0, viewport.1, viewport.2, viewport.3); }
 				ctxt.state.viewport = viewport;
+				ctxt.state.viewport_array.clear();
+				ctxt.state.statistics.state_changes += 1;
 			}
 
 		} else {
@@ -685,14 +1504,348 @@ impl DrawParameters {
 			if ctxt.state.viewport != viewport {
 				unsafe { ctxt.gl.Viewport(viewport.0, viewport.1, viewport.2, viewport.3); }
 				ctxt.state.viewport = viewport;
+				ctxt.state.viewport_array.clear();
+				ctxt.state.statistics.state_changes += 1;
 			}
 		}
+
+		// viewport array
+		if let Some(count) = self.viewports.iter().rposition(|v| v.is_some()).map(|n| n + 1) {
+			// entries that are `None` use the viewport that has just been set
+			let default = ctxt.state.viewport;
+			let default = [default.0 as f32, default.1 as f32, default.2 as f32,
+						   default.3 as f32];
+
+			let mut data = Vec::with_capacity(count * 4);
+			for viewport in self.viewports.iter().take(count) {
+				match viewport {
+					&Some(ref viewport) => {
+						data.push(viewport.left as f32);
+						data.push(viewport.bottom as f32);
+						data.push(viewport.width as f32);
+						data.push(viewport.height as f32);
+					},
+					&None => data.push_all(&default),
+				}
+			}
+
+			if ctxt.state.viewport_array != data {
+				unsafe {
+					ctxt.gl.ViewportArrayv(0, count as gl::types::GLsizei, data.as_ptr());
+				}
+
+				// `glViewport` would now have to be called again for the viewport 0
+				ctxt.state.viewport = (data[0] as gl::types::GLint, data[1] as gl::types::GLint,
+									   data[2] as gl::types::GLsizei,
+									   data[3] as gl::types::GLsizei);
+				ctxt.state.viewport_array = data;
+				ctxt.state.statistics.state_changes += 1;
+			}
+		}
+
+		// scissor
+		if let Some(scissor) = self.scissor {
+			let scissor = (scissor.left as gl::types::GLint, scissor.bottom as gl::types::GLint,
+						   scissor.width as gl::types::GLsizei,
+						   scissor.height as gl::types::GLsizei);
+
+			unsafe {
+				if ctxt.state.scissor != scissor {
+					ctxt.gl.Scissor(scissor.0, scissor.1, scissor.2, scissor.3);
+					ctxt.state.scissor = scissor;
+					ctxt.state.statistics.state_changes += 1;
+				}
+
+				if !ctxt.state.enabled_scissor_test {
+					ctxt.gl.Enable(gl::SCISSOR_TEST);
+					ctxt.state.enabled_scissor_test = true;
+					ctxt.state.statistics.state_changes += 1;
+				}
+			}
+
+		} else {
+			if ctxt.state.enabled_scissor_test {
+				unsafe { ctxt.gl.Disable(gl::SCISSOR_TEST); }
+				ctxt.state.enabled_scissor_test = false;
+				ctxt.state.statistics.state_changes += 1;
+			}
+		}
+	}
+}
+
+/// Error that can happen while drawing.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub enum DrawError {
+	/// The requested depth function requires a depth buffer, but none is attached.
+	NoDepthBuffer,
+
+	/// The attributes of the vertex source don't match the ones of the program.
+	VertexFormatMismatch(VertexFormatMismatch),
+
+	/// The viewport is larger than the dimensions supported by the backend.
+	ViewportTooLarge,
+
+	/// `viewports` has been specified but viewport arrays are not supported by the backend.
+	ViewportArrayNotSupported,
+
+	/// One of the `viewports` is at an index greater than the number of viewports
+	/// supported by the backend.
+	TooManyViewports,
+
+	/// The depth range is outside of `(0, 1)`.
+	InvalidDepthRange,
+
+	/// Per-attachment blending has been requested but is not supported by the backend.
+	PerAttachmentBlendingNotSupported,
+
+	/// `framebuffer_srgb` has been requested but is not supported by the backend.
+	FramebufferSrgbNotSupported,
+
+	/// A `condition` has been requested but conditional rendering is not supported by
+	/// the backend.
+	ConditionalRenderingNotSupported,
+
+	/// `line_smooth` or `polygon_smooth` has been requested but is not supported by the
+	/// backend.
+	SmoothingNotSupported,
+
+	/// `primitive_restart_index` has been requested but is not supported by the backend.
+	PrimitiveRestartNotSupported,
+
+	/// `depth_clamp` has been requested but is not supported by the backend.
+	DepthClampNotSupported,
+
+	/// A `polygon_mode` other than `Fill` has been requested but is not supported by the
+	/// backend. This is the case with OpenGL ES.
+	PolygonModeNotSupported,
+
+	/// `point_size` has been set to a value other than `1.0` but the backend always uses
+	/// `gl_PointSize`. This is the case with OpenGL ES.
+	PointSizeNotSupported,
+
+	/// `point_size` is not positive or is outside of the range supported by the backend.
+	InvalidPointSize,
+
+	/// A `provoking_vertex` other than `LastVertex` has been requested but is not supported
+	/// by the backend.
+	ProvokingVertexNotSupported,
+
+	/// The vertex source is a slice that doesn't start at 0, and drawing with a base vertex
+	/// is not supported by the backend.
+	BaseVertexNotSupported,
+
+	/// The vertex source contains a per-instance buffer, but instanced arrays are not
+	/// supported by the backend.
+	InstancingNotSupported,
+
+	/// Indirect drawing has been requested but is not supported by the backend.
+	DrawIndirectNotSupported,
+
+	/// A mesh shader program has been drawn but mesh shaders are not supported by the
+	/// backend.
+	MeshShadersNotSupported,
+
+	/// A compute shader has been dispatched with more work groups than the backend supports.
+	TooManyWorkGroups,
+
+	/// The stencil component of a depth-stencil texture is sampled, but stencil texturing is
+	/// not supported by the backend.
+	StencilTexturingNotSupported,
+
+	/// An image is bound to a uniform, but image load/store is not supported by the backend.
+	ImageLoadStoreNotSupported,
+
+	/// The draw uses more images than the number of image units of the backend.
+	TooManyImageUnits,
+
+	/// A depth-stencil texture is sampled both for its depth and for its stencil component
+	/// by the same draw. This is impossible because the component that is read is a
+	/// parameter of the texture.
+	DepthStencilModeConflict {
+		/// Name of the uniform that reads the other component.
+		name: String,
+	},
+
+	/// The value of a uniform can't be used with the type declared in the program.
+	UniformTypeMismatch {
+		/// Name of the uniform.
+		name: String,
+		/// Type of the uniform in the program.
+		expected: uniforms::UniformType,
+		/// Type of the value that was passed, or `None` for images, whose exact type
+		/// isn't known.
+		got: Option<uniforms::UniformType>,
+	},
+
+	/// A texture is sampled with a mipmapped minify filter but doesn't have any mipmap, so
+	/// sampling it would return black. On OpenGL ES 2, all the mipmap levels down to 1x1 are
+	/// required.
+	///
+	/// This is only checked in debug builds.
+	TextureMipmapsIncomplete {
+		/// Name of the uniform.
+		name: String,
+	},
+
+	/// An integral texture is sampled with a `Linear` filter, so sampling it would return
+	/// black.
+	///
+	/// This is only checked in debug builds.
+	IntegralTextureFiltered {
+		/// Name of the uniform.
+		name: String,
+	},
+
+	/// One of the indices is outside of the vertex source.
+	///
+	/// This is only checked if `Display::set_indices_validation` has been enabled.
+	IndexOutOfRange {
+		/// Position of the index in the index source.
+		position: uint,
+		/// Value of the index.
+		index: u32,
+		/// Number of vertices in the vertex source.
+		vertices_count: uint,
+	},
+}
+
+impl std::error::Error for DrawError {
+	fn description(&self) -> &str {
+		match self {
+			&DrawError::NoDepthBuffer => {
+				"Requested a depth function but no depth buffer is attached"
+			},
+			&DrawError::VertexFormatMismatch(_) => {
+				"The vertex format doesn't match the program"
+			},
+			&DrawError::ViewportTooLarge => {
+				"Viewport dimensions are too large"
+			},
+			&DrawError::ViewportArrayNotSupported => {
+				"Viewport arrays are not supported by the backend"
+			},
+			&DrawError::TooManyViewports => {
+				"The backend doesn't support this number of viewports"
+			},
+			&DrawError::InvalidDepthRange => {
+				"Depth range must be between 0 and 1"
+			},
+			&DrawError::PerAttachmentBlendingNotSupported => {
+				"Per-attachment blending is not supported by the backend"
+			},
+			&DrawError::FramebufferSrgbNotSupported => {
+				"sRGB framebuffers are not supported by the backend"
+			},
+			&DrawError::ConditionalRenderingNotSupported => {
+				"Conditional rendering is not supported by the backend"
+			},
+			&DrawError::SmoothingNotSupported => {
+				"Smoothing lines and polygons is not supported by the backend"
+			},
+			&DrawError::PrimitiveRestartNotSupported => {
+				"Primitive restart is not supported by the backend"
+			},
+			&DrawError::DepthClampNotSupported => {
+				"Depth clamping is not supported by the backend"
+			},
+			&DrawError::PolygonModeNotSupported => {
+				"Polygon modes other than `Fill` are not supported by the backend"
+			},
+			&DrawError::PointSizeNotSupported => {
+				"Setting the size of the points outside of the vertex shader is not supported \
+				 by the backend"
+			},
+			&DrawError::InvalidPointSize => {
+				"The size of the points is not positive or is not supported by the backend"
+			},
+			&DrawError::ProvokingVertexNotSupported => {
+				"Using the first vertex as provoking vertex is not supported by the backend"
+			},
+			&DrawError::BaseVertexNotSupported => {
+				"Drawing with a slice of a vertex buffer that doesn't start at 0 is not \
+				 supported by the backend"
+			},
+			&DrawError::InstancingNotSupported => {
+				"Per-instance vertex buffers are not supported by the backend"
+			},
+			&DrawError::DrawIndirectNotSupported => {
+				"Indirect drawing is not supported by the backend"
+			},
+			&DrawError::MeshShadersNotSupported => {
+				"Mesh shaders are not supported by the backend"
+			},
+			&DrawError::TooManyWorkGroups => {
+				"The number of work groups is too large"
+			},
+			&DrawError::StencilTexturingNotSupported => {
+				"Sampling the stencil component of a texture is not supported by the backend"
+			},
+			&DrawError::ImageLoadStoreNotSupported => {
+				"Image load/store is not supported by the backend"
+			},
+			&DrawError::TooManyImageUnits => {
+				"The draw uses more images than the backend supports"
+			},
+			&DrawError::DepthStencilModeConflict { .. } => {
+				"A depth-stencil texture is sampled both for its depth and for its stencil"
+			},
+			&DrawError::UniformTypeMismatch { .. } => {
+				"The type of a uniform doesn't match the program"
+			},
+			&DrawError::TextureMipmapsIncomplete { .. } => {
+				"A texture is sampled with a mipmapped filter but doesn't have all its \
+				 mipmap levels"
+			},
+			&DrawError::IntegralTextureFiltered { .. } => {
+				"An integral texture is sampled with a linear filter"
+			},
+			&DrawError::IndexOutOfRange { .. } => {
+				"An index is out of range of the vertex source"
+			},
+		}
+	}
+
+	fn detail(&self) -> Option<String> {
+		match self {
+			&DrawError::VertexFormatMismatch(ref err) => std::error::Error::detail(err),
+			&DrawError::UniformTypeMismatch { ref name, ref expected, got: Some(ref got) } => {
+				Some(format!("The type of the uniform `{}` doesn't match the program: \
+							  expected `{}`, got `{}`", name, expected, got))
+			},
+			&DrawError::UniformTypeMismatch { ref name, ref expected, got: None } => {
+				Some(format!("The type of the uniform `{}` doesn't match the program: \
+							  expected `{}`, got an image", name, expected))
+			},
+			&DrawError::TextureMipmapsIncomplete { ref name } => {
+				Some(format!("The texture `{}` is sampled with a mipmapped minify filter but \
+							  doesn't have all its mipmap levels", name))
+			},
+			&DrawError::DepthStencilModeConflict { ref name } => {
+				Some(format!("The uniform `{}` reads a different component of a depth-stencil \
+							  texture than another uniform of the same draw", name))
+			},
+			&DrawError::IntegralTextureFiltered { ref name } => {
+				Some(format!("The integral texture `{}` must be sampled with a `Nearest` or \
+							  `NearestMipmapNearest` filter", name))
+			},
+			&DrawError::IndexOutOfRange { position, index, vertices_count } => {
+				Some(format!("Index {} at position {} is out of range of the vertex source \
+							  ({} vertices)", index, position, vertices_count))
+			},
+			_ => None,
+		}
 	}
 }
 
 /// Area of a surface in pixels.
 ///
 /// In the OpenGL ecosystem, the (0,0) coordinate is at the bottom-left hand corner of the images.
+/// This is the convention used by all the functions of glium that take a `Rect`, including
+/// the viewport and the scissor box of `DrawParameters`, the blit functions, `read_rect`
+/// and the `write` functions of the textures.
+///
+/// Windowing systems and image formats usually put the origin at the top-left hand corner
+/// instead. Use `from_top_left` and `get_top` to convert between the two conventions.
 #[deriving(Show, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Rect {
 	/// Number of pixels between the left border of the surface and the left border of
@@ -707,6 +1860,119 @@ pub struct Rect {
 	pub height: u32,
 }
 
+impl Rect {
+	/// Builds a rectangle that covers a whole surface of the given dimensions.
+	pub fn from_dimensions(width: u32, height: u32) -> Rect {
+		Rect { left: 0, bottom: 0, width: width, height: height }
+	}
+
+	/// Builds a rectangle from coordinates whose origin is the top-left hand corner of the
+	/// surface. `top` is the number of pixels between the top border of the surface and the
+	/// top border of the rectangle.
+	///
+	/// # Panic
+	///
+	/// Panics if the rectangle goes past the bottom of the surface.
+	pub fn from_top_left(left: u32, top: u32, width: u32, height: u32, surface_height: u32)
+						 -> Rect
+	{
+		if top as u64 + height as u64 > surface_height as u64 {
+			panic!("The rectangle goes past the bottom of the surface");
+		}
+
+		Rect { left: left, bottom: surface_height - top - height, width: width, height: height }
+	}
+
+	/// Builds a rectangle from normalized coordinates, where `(0.0, 0.0)` is the bottom-left
+	/// hand corner of the surface and `(1.0, 1.0)` is the top-right hand corner.
+	///
+	/// The borders are rounded to the nearest pixel and clamped to the surface.
+	pub fn from_normalized(left: f32, bottom: f32, width: f32, height: f32,
+						   surface_dimensions: (u32, u32)) -> Rect
+	{
+		use std::num::Float;
+
+		let to_pixels = |&: value: f32, size: u32| -> u32 {
+			(value * size as f32).round().max(0.0).min(size as f32) as u32
+		};
+
+		let (surface_width, surface_height) = surface_dimensions;
+		let left_px = to_pixels(left, surface_width);
+		let right_px = to_pixels(left + width, surface_width);
+		let bottom_px = to_pixels(bottom, surface_height);
+		let top_px = to_pixels(bottom + height, surface_height);
+
+		Rect {
+			left: left_px,
+			bottom: bottom_px,
+			width: if right_px > left_px { right_px - left_px } else { 0 },
+			height: if top_px > bottom_px { top_px - bottom_px } else { 0 },
+		}
+	}
+
+	/// Returns the number of pixels between the top border of a surface and the top border
+	/// of the rectangle.
+	///
+	/// # Panic
+	///
+	/// Panics if the rectangle goes past the top of the surface.
+	pub fn get_top(&self, surface_height: u32) -> u32 {
+		if self.bottom as u64 + self.height as u64 > surface_height as u64 {
+			panic!("The rectangle goes past the top of the surface");
+		}
+
+		surface_height - self.bottom - self.height
+	}
+
+	/// Returns the left, bottom, width and height of the rectangle relative to the
+	/// dimensions of a surface, where `(0.0, 0.0)` is the bottom-left hand corner of the
+	/// surface and `(1.0, 1.0)` is the top-right hand corner.
+	pub fn to_normalized(&self, surface_dimensions: (u32, u32)) -> (f32, f32, f32, f32) {
+		let (surface_width, surface_height) = surface_dimensions;
+		let (surface_width, surface_height) = (surface_width as f32, surface_height as f32);
+
+		(self.left as f32 / surface_width, self.bottom as f32 / surface_height,
+		 self.width as f32 / surface_width, self.height as f32 / surface_height)
+	}
+
+	/// Returns true if the rectangle is entirely inside a surface of the given dimensions.
+	pub fn is_inside(&self, surface_dimensions: (u32, u32)) -> bool {
+		self.left as u64 + self.width as u64 <= surface_dimensions.0 as u64 &&
+			self.bottom as u64 + self.height as u64 <= surface_dimensions.1 as u64
+	}
+}
+
+/// Buffers that are copied by `Surface::blit_from`.
+#[deriving(Show, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlitMask {
+	/// If true, the color values are copied.
+	pub color: bool,
+	/// If true, the depth values are copied.
+	pub depth: bool,
+	/// If true, the stencil values are copied.
+	pub stencil: bool,
+}
+
+impl BlitMask {
+	/// Copies only the color values.
+	pub fn color() -> BlitMask {
+		BlitMask { color: true, depth: false, stencil: false }
+	}
+
+	/// Copies the color, depth and stencil values.
+	pub fn all() -> BlitMask {
+		BlitMask { color: true, depth: true, stencil: true }
+	}
+
+	fn to_glbitfield(&self) -> gl::types::GLbitfield {
+		let mut mask = 0;
+		if self.color { mask |= gl::COLOR_BUFFER_BIT; }
+		if self.depth { mask |= gl::DEPTH_BUFFER_BIT; }
+		if self.stencil { mask |= gl::STENCIL_BUFFER_BIT; }
+		mask
+	}
+}
+
 /// Object which can be drawn upon.
 pub trait Surface {
 	/// Clears the color components of the target.
@@ -741,24 +2007,256 @@ pub trait Surface {
 		self.get_stencil_buffer_bits().is_some()
 	}
 
+	/// Clears only the pixels of the target that are inside `rect`.
+	///
+	/// Each component is cleared only if its value is `Some`. This is useful to repaint only
+	/// the areas of a surface that have changed. Clearing with an empty rectangle is a no-op.
+	fn clear_rect(&mut self, rect: &Rect, color: Option<(f32, f32, f32, f32)>,
+		depth: Option<f32>, stencil: Option<int>)
+	{
+		ops::clear_rect(self, rect, color, depth, stencil)
+	}
+
 	/// Draws.
 	///
-	/// # Panic
+	/// Drawing is a no-op if the vertex source or the indices are empty, or if the surface,
+	/// the viewport or the scissor box has a width or a height of zero. In this situation no
+	/// OpenGL command is sent, but the draw parameters are still checked.
 	///
-	/// - Panics if the requested depth function requires a depth buffer and none is attached.
-	/// - Panics if the type of some of the vertex source's attributes do not match the program's.
-	/// - Panics if a program's attribute is not in the vertex source (does *not* panic if a
+	/// Returns an error and doesn't draw anything if:
+	///
+	/// - The requested depth function requires a depth buffer and none is attached.
+	/// - The type of some of the vertex source's attributes do not match the program's.
+	/// - A program's attribute is not in the vertex source (it is *not* an error if a
 	///   vertex's attribute is not used by the program).
-	/// - Panics if the viewport is larger than the dimensions supported by the hardware.
-	/// - Panics if the depth range is outside of `(0, 1)`.
+	/// - The type of a uniform doesn't match the program.
+	/// - The viewport is larger than the dimensions supported by the hardware.
+	/// - The depth range is outside of `(0, 1)`.
 	///
 	fn draw<'a, 'b, V, I, ID, U>(&mut self, V, &I, program: &Program, uniforms: U,
-		draw_parameters: &DrawParameters) where V: vertex_buffer::IntoVerticesSource<'b>,
-		I: index_buffer::ToIndicesSource<ID>, U: uniforms::Uniforms;
+		draw_parameters: &DrawParameters) -> Result<(), DrawError>
+		where V: vertex_buffer::IntoVerticesSource<'b>, I: index_buffer::ToIndicesSource<ID>,
+		U: uniforms::Uniforms;
+
+	/// Draws twice, first filling only the depth buffer and then the color buffer.
+	///
+	/// The `draws` function is called twice and must perform the same draw commands each time
+	/// with the parameters that it receives.
+	///
+	/// During the first pass, the color buffer is not modified and the depth buffer is written
+	/// using the `depth_function` of `draw_parameters`. During the second pass, the depth buffer
+	/// is left untouched and only the pixels whose depth is equal to the one in the depth buffer
+	/// are written. This ensures that the fragment shader runs only once for each pixel, which
+	/// is useful when it is expensive.
+	///
+	/// # Panic
+	///
+	/// - Panics if no depth buffer is attached to the surface.
+	/// - Panics if the depth function of `draw_parameters` doesn't use the depth buffer.
+	///
+	fn draw_with_depth_prepass<F>(&mut self, draw_parameters: &DrawParameters, mut draws: F)
+		where F: FnMut(&mut Self, &DrawParameters)
+	{
+		if !self.has_depth_buffer() {
+			panic!("A depth pre-pass requires a depth buffer");
+		}
+
+		if !draw_parameters.depth_function.requires_depth_buffer() {
+			panic!("A depth pre-pass requires a depth function that uses the depth buffer");
+		}
+
+		let depth_pass = DrawParameters {
+			depth_write: true,
+			color_mask: (false, false, false, false),
+			.. *draw_parameters
+		};
+
+		let color_pass = DrawParameters {
+			depth_function: DepthFunction::IfEqual,
+			depth_write: false,
+			.. *draw_parameters
+		};
+
+		draws(self, &depth_pass);
+		draws(self, &color_pass);
+	}
+
+	/// Draws the objects described by the commands of a `DrawCommandsBuffer`.
+	///
+	/// Each command reads a range of the index buffer, and the vertex buffer starting at
+	/// `base_vertex`. The offset of the vertex source, if it is a slice, is ignored. If the
+	/// backend supports it, all the commands are executed with a single call to
+	/// `glMultiDrawElementsIndirect`.
+	///
+	/// Drawing is a no-op if the buffer doesn't contain any command, or if the surface, the
+	/// viewport or the scissor box has a width or a height of zero.
+	///
+	/// Returns an error and doesn't draw anything if indirect drawing is not supported by the
+	/// backend, and in the same situations as `draw`.
+	///
+	fn draw_indirect<'b, V, U>(&mut self, vertex_buffer: V, index_buffer: &IndexBuffer,
+		commands: &draw_commands::DrawCommandsBuffer<draw_commands::DrawElementsIndirectCommand>,
+		program: &Program, uniforms: U, draw_parameters: &DrawParameters)
+		-> Result<(), DrawError>
+		where V: vertex_buffer::IntoVerticesSource<'b>, U: uniforms::Uniforms
+	{
+		use index_buffer::ToIndicesSource;
+
+		ops::draw_indirect(self, vertex_buffer.into_vertices_source(),
+						   &index_buffer.to_indices_source(), commands, program, uniforms,
+						   draw_parameters)
+	}
+
+	/// Draws the objects described by the commands of a `DrawCommandsBuffer` without using
+	/// an index buffer.
+	///
+	/// Each command reads a range of the vertex buffer, and the vertices are assembled into
+	/// primitives of type `primitives`.
+	///
+	/// Drawing is a no-op if the buffer doesn't contain any command, or if the surface, the
+	/// viewport or the scissor box has a width or a height of zero.
+	///
+	/// Returns an error and doesn't draw anything if indirect drawing is not supported by the
+	/// backend, and in the same situations as `draw`.
+	///
+	fn draw_arrays_indirect<'b, V, U>(&mut self, vertex_buffer: V,
+		primitives: index_buffer::PrimitiveType,
+		commands: &draw_commands::DrawCommandsBuffer<draw_commands::DrawArraysIndirectCommand>,
+		program: &Program, uniforms: U, draw_parameters: &DrawParameters)
+		-> Result<(), DrawError>
+		where V: vertex_buffer::IntoVerticesSource<'b>, U: uniforms::Uniforms
+	{
+		let indices = index_buffer::IndicesSource::Buffer {
+			pointer: &[],
+			primitives: primitives,
+			offset: 0,
+			length: 0,
+		};
+
+		ops::draw_indirect(self, vertex_buffer.into_vertices_source(), &indices, commands,
+						   program, uniforms, draw_parameters)
+	}
+
+	/// Draws with a program that contains a mesh shader.
+	///
+	/// Launches `count` work groups of the task shader, or of the mesh shader if the program
+	/// doesn't have a task shader, starting with the work group `first`. No vertex source
+	/// is used.
+	///
+	/// Returns an error and doesn't draw anything if mesh shaders are not supported by the
+	/// backend, and in the same situations as `draw`.
+	///
+	/// # Features
+	///
+	/// Only available if the `nv_mesh_shader` feature is enabled.
+	#[cfg(feature = "nv_mesh_shader")]
+	fn draw_mesh_tasks<U>(&mut self, first: u32, count: u32, program: &Program, uniforms: U,
+		draw_parameters: &DrawParameters) -> Result<(), DrawError> where U: uniforms::Uniforms
+	{
+		ops::draw_mesh_tasks(self, first, count, program, uniforms, draw_parameters)
+	}
 
 	/// Returns an opaque type that is used by the implementation of blit functions.
 	fn get_blit_helper(&self) -> BlitHelper;
 
+	/// Reads the content of the whole surface.
+	///
+	/// The format of the pixels is determined by the type of the output.
+	///
+	/// # Panic
+	///
+	/// Panics if the surface is not readable, for example if it is a compressed texture.
+	fn read<P, T>(&self) -> T where P: texture::PixelValue + Clone + Send,
+		T: texture::Texture2dData<Data = P>
+	{
+		let dimensions = self.get_dimensions();
+		let rect = Rect::from_dimensions(dimensions.0 as u32, dimensions.1 as u32);
+		self.read_rect(&rect)
+	}
+
+	/// Reads the content of the whole surface into an image that can be saved to a file.
+	///
+	/// The rows are returned top to bottom, as expected by the `image` library.
+	///
+	/// # Features
+	///
+	/// Only available if the `image` feature is enabled.
+	///
+	/// ## Example
+	///
+	/// ```no_run
+	/// # extern crate glium;
+	/// # extern crate image;
+	/// # use glium::Surface;
+	/// # fn main() {
+	/// # let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+	/// let target = display.draw();
+	/// let image = target.capture_image();
+	/// target.finish().unwrap();
+	/// # }
+	/// ```
+	#[cfg(feature = "image")]
+	fn capture_image(&self) -> image::DynamicImage {
+		self.read()
+	}
+
+	/// Reads the content of a rectangle of the surface.
+	///
+	/// # Panic
+	///
+	/// Panics if the rectangle is outside of the surface.
+	fn read_rect<P, T>(&self, rect: &Rect) -> T where P: texture::PixelValue + Clone + Send,
+		T: texture::Texture2dData<Data = P>
+	{
+		let format = texture::Texture2dData::get_format(None::<T>);
+		let data = ops::read(self, rect, format);
+		texture::Texture2dData::from_vec(data, rect.width)
+	}
+
+	/// Reads the content of a rectangle of the surface with the given client format.
+	///
+	/// The data is returned as a raw list of elements of type `P`, from the bottom-left hand
+	/// corner to the top-right hand corner, row by row.
+	///
+	/// # Panic
+	///
+	/// - Panics if the rectangle is outside of the surface.
+	/// - Panics if the size of the data is not a multiple of the size of `P`.
+	fn read_rect_with_format<P>(&self, rect: &Rect, format: texture::ClientFormat) -> Vec<P>
+		where P: Send
+	{
+		ops::read(self, rect, format)
+	}
+
+	/// Copies a rectangle of the surface into a new pixel buffer.
+	///
+	/// Contrary to the other `read` functions, this function doesn't wait for the data to be
+	/// available. The wait will happen only when you read the content of the buffer.
+	///
+	/// # Panic
+	///
+	/// - Panics if the rectangle is outside of the surface.
+	/// - Panics if the size of the data is not a multiple of the size of `P`.
+	fn read_to_pixel_buffer<P>(&self, rect: &Rect, format: texture::ClientFormat)
+		-> texture::PixelBuffer<P> where P: Send
+	{
+		ops::read_to_pixel_buffer(self, rect, format)
+	}
+
+	/// Returns the positions of the samples of each pixel of the surface, in the order
+	/// of their index.
+	///
+	/// The positions are between `(0.0, 0.0)` (bottom-left corner of the pixel) and
+	/// `(1.0, 1.0)` (top-right corner). The list contains a single element if the surface
+	/// isn't multisampled.
+	///
+	/// # Panic
+	///
+	/// Panics if the backend doesn't support querying the positions of samples.
+	fn get_sample_positions(&self) -> Vec<(f32, f32)> {
+		ops::get_sample_positions(self)
+	}
+
 	/// Copies a rectangle of pixels from this surface to another surface.
 	///
 	/// The `source_rect` defines the area of the source (`self`) that will be copied, and the
@@ -779,13 +2277,58 @@ pub trait Surface {
 			filter.to_glenum())
 	}
 
+	/// Copies a rectangle of pixels from a source surface to this surface.
+	///
+	/// The `source_rect` defines the area of `source` that will be copied, and the
+	/// `target_rect` defines the area of this surface where the copied image will be pasted.
+	/// The `mask` indicates whether the color, depth and stencil values are copied. If the
+	/// areas don't have the same dimensions, the image will be resized using `filter`.
+	///
+	/// This works between any two surfaces, including the default framebuffer.
+	///
+	/// - Panics if one of the rectangles is outside of its surface.
+	/// - Panics if depth or stencil values are copied with a filter other than `Nearest`.
+	/// - Panics if depth or stencil values are copied but one of the surfaces doesn't have
+	///   a depth or stencil buffer.
+	fn blit_from<S>(&self, source: &S, source_rect: &Rect, target_rect: &Rect, mask: BlitMask,
+		filter: uniforms::MagnifySamplerFilter) where S: Surface
+	{
+		fn check_rect(rect: &Rect, dimensions: (uint, uint)) {
+			if !rect.is_inside((dimensions.0 as u32, dimensions.1 as u32)) {
+				panic!("The blit rectangle is outside of the surface");
+			}
+		}
+
+		check_rect(source_rect, source.get_dimensions());
+		check_rect(target_rect, self.get_dimensions());
+
+		if (mask.depth || mask.stencil) && filter != uniforms::MagnifySamplerFilter::Nearest {
+			panic!("Depth and stencil values can only be blitted with the `Nearest` filter");
+		}
+
+		if mask.depth && (!source.has_depth_buffer() || !self.has_depth_buffer()) {
+			panic!("Requested a depth blit but one of the surfaces has no depth buffer");
+		}
+
+		if mask.stencil && (!source.has_stencil_buffer() || !self.has_stencil_buffer()) {
+			panic!("Requested a stencil blit but one of the surfaces has no stencil buffer");
+		}
+
+		if mask.to_glbitfield() == 0 {
+			return;
+		}
+
+		ops::blit(source, self, mask.to_glbitfield(), source_rect, target_rect,
+			filter.to_glenum())
+	}
+
 	/// Copies the entire surface to a target surface. See `blit_color`.
 	#[experimental = "The name will likely change"]
 	fn blit_whole_color_to<S>(&self, target: &S, target_rect: &Rect,
 		filter: uniforms::MagnifySamplerFilter) where S: Surface
 	{
 		let src_dim = self.get_dimensions();
-		let src_rect = Rect { left: 0, bottom: 0, width: src_dim.0 as u32, height: src_dim.1 as u32 };
+		let src_rect = Rect::from_dimensions(src_dim.0 as u32, src_dim.1 as u32);
 		self.blit_color(&src_rect, target, target_rect, filter)
 	}
 
@@ -793,11 +2336,62 @@ pub trait Surface {
 	#[experimental = "The name will likely change"]
 	fn fill<S>(&self, target: &S, filter: uniforms::MagnifySamplerFilter) where S: Surface {
 		let src_dim = self.get_dimensions();
-		let src_rect = Rect { left: 0, bottom: 0, width: src_dim.0 as u32, height: src_dim.1 as u32 };
+		let src_rect = Rect::from_dimensions(src_dim.0 as u32, src_dim.1 as u32);
 		let target_dim = target.get_dimensions();
-		let target_rect = Rect { left: 0, bottom: 0, width: target_dim.0 as u32, height: target_dim.1 as u32 };
+		let target_rect = Rect::from_dimensions(target_dim.0 as u32, target_dim.1 as u32);
 		self.blit_color(&src_rect, target, &target_rect, filter)
 	}
+
+	/// Copies the entire surface to the entire target, which must have the same dimensions.
+	///
+	/// If the surface is multisampled, the samples of each pixel are merged together. This is
+	/// how you get the result of drawing on a multisampled render buffer into a texture that
+	/// can be sampled or read. The target must not be multisampled.
+	fn resolve_to<S>(&self, target: &S) where S: Surface {
+		let dimensions = self.get_dimensions();
+
+		if target.get_dimensions() != dimensions {
+			panic!("The source and the target of a resolve must have the same dimensions");
+		}
+
+		let rect = Rect::from_dimensions(dimensions.0 as u32, dimensions.1 as u32);
+		self.blit_color(&rect, target, &rect, uniforms::MagnifySamplerFilter::Nearest)
+	}
+
+	/// Copies the content of the surface to a new texture of the given format.
+	///
+	/// This is useful for effects that need to sample what has been drawn so far, for example
+	/// refraction. The texture has the same dimensions as the surface and no mipmaps. If the
+	/// surface is multisampled, the samples are merged like with `resolve_to`.
+	///
+	/// The samples of a multisampled surface can only be merged into a texture of the same
+	/// format as the surface. If `format` is different, they are first merged into a temporary
+	/// texture, which is then converted. If the format of the surface can't be determined,
+	/// which is the case before OpenGL 3.0 and OpenGL ES 3.0, `format` must be the same as the
+	/// format of the surface.
+	fn snapshot_to_texture(&self, format: texture::UncompressedFloatFormat) -> Texture2d {
+		let BlitHelper(display, _) = self.get_blit_helper();
+		let display = Display { context: display.clone() };
+
+		let dimensions = self.get_dimensions();
+		let texture = Texture2d::new_empty_with_mipmaps(&display, format, dimensions.0 as u32,
+														dimensions.1 as u32,
+														texture::MipmapsOption::NoMipmap);
+
+		match ops::get_color_format(self) {
+			(true, Some(surface_format)) if surface_format != format => {
+				let resolved = Texture2d::new_empty_with_mipmaps(&display, surface_format,
+																 dimensions.0 as u32,
+																 dimensions.1 as u32,
+																 texture::MipmapsOption::NoMipmap);
+				self.resolve_to(&resolved.as_surface());
+				resolved.as_surface().resolve_to(&texture.as_surface());
+			},
+			_ => self.resolve_to(&texture.as_surface()),
+		};
+
+		texture
+	}
 }
 
 #[doc(hidden)]
@@ -805,17 +2399,142 @@ pub struct BlitHelper<'a>(&'a Arc<DisplayImpl>, Option<&'a fbo::FramebufferAttac
 
 /// Implementation of `Surface` targetting the default framebuffer.
 ///
-/// The back- and front-buffers are swapped when the `Frame` is destroyed. This operation is
+/// The back- and front-buffers are swapped when you call `finish`. This operation is
 /// instantaneous, even when vsync is enabled.
+///
+/// In debug builds, destroying a `Frame` without calling `finish` panics, so that forgotten
+/// frames and swap errors don't go unnoticed. Call `set_finish_on_drop(true)` to swap the
+/// buffers silently when the `Frame` is destroyed instead. Release builds always do so.
 pub struct Frame<'a> {
 	display: Display,
 	marker: std::kinds::marker::ContravariantLifetime<'a>,
 	dimensions: (uint, uint),
+	// false if this object is wrapped in a `CurrentFrame`
+	owns_frame: bool,
+	// true once the buffers have been swapped
+	finished: bool,
+	finish_on_drop: bool,
+}
+
+/// Error that can happen when swapping the buffers at the end of a `Frame`.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum SwapBuffersError {
+	/// The context has been reset, for example after a GPU hang or a driver update. All the
+	/// objects of the display are invalid and must be recreated with a new display.
+	///
+	/// Resets can only be detected if the backend supports OpenGL 4.5 or `GL_ARB_robustness`,
+	/// and if the context has been created with the `GL_LOSE_CONTEXT_ON_RESET` strategy.
+	ContextLost,
+}
+
+impl std::error::Error for SwapBuffersError {
+	fn description(&self) -> &str {
+		match self {
+			&SwapBuffersError::ContextLost => "The OpenGL context has been lost",
+		}
+	}
 }
 
 impl<'t> Frame<'t> {
+	/// Returns the number of frames since the content of the surface was presented, before
+	/// anything is drawn on it.
+	///
+	/// With an age of `n`, the surface contains the frame that was presented `n` frames ago,
+	/// and only the areas that have changed since then need to be redrawn. An age of `0` means
+	/// that the content is undefined and that the whole frame must be drawn.
+	///
+	/// This uses `GLX_EXT_buffer_age` or `EGL_EXT_buffer_age`, and returns `1` if the backend
+	/// preserves the content of the back buffer when the buffers are swapped. Returns `None`
+	/// if the content of the surface can't be known, which is always the case if the
+	/// `native_handles` feature is disabled.
+	pub fn get_buffer_age(&self) -> Option<u32> {
+		native::get_buffer_age(&self.display).or_else(|| {
+			match native::is_swap_preserved(&self.display) {
+				Some(true) => Some(1),
+				_ => None,
+			}
+		})
+	}
+
+	/// Returns true if the content of the back buffer is preserved when the buffers are
+	/// swapped, or `None` if the backend can't tell.
+	pub fn is_swap_preserved(&self) -> Option<bool> {
+		native::is_swap_preserved(&self.display)
+	}
+
+	/// Sets whether destroying the `Frame` without calling `finish` swaps the buffers
+	/// silently instead of panicking in debug builds.
+	///
+	/// This is useful when a `Frame` is stored in a structure whose destructor you don't
+	/// control.
+	pub fn set_finish_on_drop(&mut self, finish_on_drop: bool) {
+		self.finish_on_drop = finish_on_drop;
+	}
+
 	/// Stop drawing and swap the buffers.
-	pub fn finish(self) {
+	///
+	/// Returns an error if the context has been lost. Losses are detected at the end of
+	/// frames, so the error may concern a previous frame.
+	pub fn finish(mut self) -> Result<(), SwapBuffersError> {
+		self.swap()
+	}
+
+	/// Stop drawing, swap the buffers and insert a fence after the commands of the frame.
+	///
+	/// The fence is signaled once the GPU has finished executing the commands of this frame.
+	/// Returns `None` if the backend doesn't support fences.
+	pub fn finish_with_fence(mut self) -> Result<Option<sync::SyncFence>, SwapBuffersError> {
+		try!(self.swap());
+		Ok(sync::SyncFence::new(&self.display))
+	}
+
+	/// Stop drawing and swap the buffers, telling the backend that only the pixels inside the
+	/// `damage` rectangles have changed since the previous frame.
+	///
+	/// The compositor can then update only these areas of the screen. Combined with
+	/// `glium::native::get_buffer_age`, this allows mostly static interfaces to redraw only
+	/// what has changed. The whole frame is presented if the backend doesn't support
+	/// `EGL_KHR_swap_buffers_with_damage`.
+	///
+	/// The damage rectangles are only used on Android with the `native_handles` feature. The
+	/// other platforms ignore them and present the whole frame, which is always correct.
+	pub fn finish_with_damage(mut self, damage: &[Rect]) -> Result<(), SwapBuffersError> {
+		let rects = damage.iter().map(|r| {
+			[r.left as i32, r.bottom as i32, r.width as i32, r.height as i32]
+		}).collect::<Vec<_>>();
+
+		self.display.context.context.exec(move |: ctxt| {
+			ctxt.state.swap_damage = Some(rects);
+		});
+
+		self.swap()
+	}
+
+	/// Draws the overlays and swaps the buffers, unless this has already been done.
+	fn swap(&mut self) -> Result<(), SwapBuffersError> {
+		if self.finished || !self.owns_frame {
+			return Ok(());
+		}
+
+		self.finished = true;
+
+		// drawing the overlays on top of everything else
+		{
+			let display = self.display.clone();
+			let mut overlays = display.context.overlays.lock().unwrap();
+			for overlay in overlays.iter_mut() {
+				overlay.render(self, &display);
+			}
+		}
+
+		*self.display.context.current_frame.lock().unwrap() = None;
+		self.display.context.context.swap_buffers();
+
+		if self.display.context.context.is_context_lost() {
+			Err(SwapBuffersError::ContextLost)
+		} else {
+			Ok(())
+		}
 	}
 }
 
@@ -846,24 +2565,13 @@ impl<'t> Surface for Frame<'t> {
 
 	fn draw<'a, 'b, V, I, ID, U>(&mut self, vertex_buffer: V,
 						 index_buffer: &I, program: &Program, uniforms: U,
-						 draw_parameters: &DrawParameters)
+						 draw_parameters: &DrawParameters) -> Result<(), DrawError>
 						 where I: index_buffer::ToIndicesSource<ID>, U: uniforms::Uniforms,
 						 ID: index_buffer::Index, V: vertex_buffer::IntoVerticesSource<'b>
 	{
 		use index_buffer::ToIndicesSource;
 
-		draw_parameters.validate();
-
-		if draw_parameters.depth_function.requires_depth_buffer() && !self.has_depth_buffer() {
-			panic!("Requested a depth function but no depth buffer is attached");
-		}
-
-		if let Some(viewport) = draw_parameters.viewport {
-			assert!(viewport.width <= self.display.context.context.capabilities().max_viewport_dims.0
-					as u32, "Viewport dimensions are too large");
-			assert!(viewport.height <= self.display.context.context.capabilities().max_viewport_dims.1
-					as u32, "Viewport dimensions are too large");
-		}
+		try!(draw_parameters.validate_for_surface(&self.display, &*self));
 
 		ops::draw(&self.display, None, vertex_buffer.into_vertices_source(),
 				  &index_buffer.to_indices_source(), program, uniforms, draw_parameters,
@@ -878,10 +2586,88 @@ impl<'t> Surface for Frame<'t> {
 #[unsafe_destructor]
 impl<'t> Drop for Frame<'t> {
 	fn drop(&mut self) {
-		self.display.context.context.swap_buffers();
+		if self.finished || !self.owns_frame {
+			return;
+		}
+
+		let _ = self.swap();
+
+		if cfg!(not(ndebug)) && !self.finish_on_drop && !std::thread::Thread::panicking() {
+			panic!("The `Frame` has been destroyed without calling `finish`");
+		}
 	}
 }
 
+/// A read-only handle to the `Frame` currently being drawn.
+///
+/// Obtained by calling `Display::get_current_frame`. Destroying the handle doesn't swap the
+/// buffers.
+pub struct CurrentFrame<'a> {
+	frame: Frame<'a>,
+}
+
+impl<'a> CurrentFrame<'a> {
+	/// Returns the dimensions in pixels of the frame.
+	pub fn get_dimensions(&self) -> (uint, uint) {
+		self.frame.get_dimensions()
+	}
+
+	/// Reads the content of the whole frame. See `Surface::read`.
+	pub fn read<P, T>(&self) -> T where P: texture::PixelValue + Clone + Send,
+		T: texture::Texture2dData<Data = P>
+	{
+		self.frame.read()
+	}
+
+	/// Reads the content of a rectangle of the frame. See `Surface::read_rect`.
+	pub fn read_rect<P, T>(&self, rect: &Rect) -> T
+		where P: texture::PixelValue + Clone + Send, T: texture::Texture2dData<Data = P>
+	{
+		self.frame.read_rect(rect)
+	}
+
+	/// Copies a rectangle of pixels from the frame to another surface. See
+	/// `Surface::blit_color`.
+	pub fn blit_color<S>(&self, source_rect: &Rect, target: &S, target_rect: &Rect,
+		filter: uniforms::MagnifySamplerFilter) where S: Surface
+	{
+		self.frame.blit_color(source_rect, target, target_rect, filter)
+	}
+
+	/// Copies the content of the frame to a new texture. See `Surface::snapshot_to_texture`.
+	pub fn snapshot_to_texture(&self, format: texture::UncompressedFloatFormat) -> Texture2d {
+		self.frame.snapshot_to_texture(format)
+	}
+}
+
+/// Something that is drawn on top of every frame, right before the buffers are swapped.
+///
+/// This is designed for libraries that want to draw things like FPS counters or debug
+/// interfaces without having the application call them explicitly. Register an overlay with
+/// `Display::add_overlay`.
+///
+/// # Example
+///
+/// ```no_run
+/// struct MyOverlay;
+///
+/// impl glium::Overlay for MyOverlay {
+///     fn render(&mut self, target: &mut glium::Frame, display: &glium::Display) {
+///         // target.draw(...);
+///     }
+/// }
+///
+/// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+/// display.add_overlay(box MyOverlay);
+/// ```
+pub trait Overlay {
+	/// Draws the overlay on the frame.
+	///
+	/// Overlays are called in the order in which they have been registered. Registering or
+	/// removing overlays from within this function will deadlock.
+	fn render(&mut self, target: &mut Frame, display: &Display);
+}
+
 /// Objects that can build a `Display` object.
 pub trait DisplayBuild {
 	/// Build a context and a `Display` to draw on it.
@@ -899,6 +2685,11 @@ pub enum GliumCreationError {
 
 	/// The OpenGL implementation is too old.
 	IncompatibleOpenGl(String),
+
+	/// A shared context has been requested, but the display can't share its objects.
+	///
+	/// This happens with headless displays.
+	SharedContextsNotSupported,
 }
 
 impl std::error::Error for GliumCreationError {
@@ -906,6 +2697,7 @@ impl std::error::Error for GliumCreationError {
 		match self {
 			&GliumCreationError::GlutinCreationError(_) => "Error while creating glutin window or headless renderer",
 			&GliumCreationError::IncompatibleOpenGl(_) => "The OpenGL implementation is too old to work with glium",
+			&GliumCreationError::SharedContextsNotSupported => "The display can't share its objects with another context",
 		}
 	}
 
@@ -913,6 +2705,7 @@ impl std::error::Error for GliumCreationError {
 		match self {
 			&GliumCreationError::GlutinCreationError(_) => None,
 			&GliumCreationError::IncompatibleOpenGl(ref e) => Some(e.clone()),
+			&GliumCreationError::SharedContextsNotSupported => None,
 		}
 	}
 
@@ -920,46 +2713,280 @@ impl std::error::Error for GliumCreationError {
 		match self {
 			&GliumCreationError::GlutinCreationError(ref err) => Some(err as &std::error::Error),
 			&GliumCreationError::IncompatibleOpenGl(_) => None,
+			&GliumCreationError::SharedContextsNotSupported => None,
 		}
 	}
-}
+}
+
+impl std::error::FromError<glutin::CreationError> for GliumCreationError {
+	fn from_error(err: glutin::CreationError) -> GliumCreationError {
+		GliumCreationError::GlutinCreationError(err)
+	}
+}
+
+impl<'a> DisplayBuild for glutin::WindowBuilder<'a> {
+	fn build_glium(self) -> Result<Display, GliumCreationError> {
+		let context = try!(context::Context::new_from_window(self, None));
+
+		Ok(Display::from_context(context, None))
+	}
+}
+
+#[cfg(feature = "headless")]
+impl DisplayBuild for glutin::HeadlessRendererBuilder {
+	fn build_glium(self) -> Result<Display, GliumCreationError> {
+		let context = try!(context::Context::new_from_headless(self));
+
+		Ok(Display::from_context(context, None))
+	}
+}
+
+/// Counters of the operations that have been executed by the backend.
+///
+/// See `Display::get_statistics` and `Display::get_last_frame_statistics`.
+#[deriving(Show, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Statistics {
+	/// Number of draw commands that have been submitted, including indirect draws and
+	/// depth pre-passes.
+	pub draw_calls: uint,
+
+	/// Number of times a value of the OpenGL state has changed, for example the program, the
+	/// vertex array object, the framebuffer or one of the draw parameters. Changes that are
+	/// skipped because the value is already the current one are not counted.
+	pub state_changes: uint,
+
+	/// Number of times a texture has been binded to a texture unit before a draw.
+	pub texture_binds: uint,
+
+	/// Number of times data has been uploaded to a buffer.
+	pub buffer_uploads: uint,
+
+	/// Number of bytes that have been uploaded to or read from buffers and textures.
+	pub bytes_transferred: uint,
+}
+
+impl Statistics {
+	/// Adds the counters of `other` to this one.
+	fn add(&mut self, other: &Statistics) {
+		self.draw_calls += other.draw_calls;
+		self.state_changes += other.state_changes;
+		self.texture_binds += other.texture_binds;
+		self.buffer_uploads += other.buffer_uploads;
+		self.bytes_transferred += other.bytes_transferred;
+	}
+}
+
+/// A draw that has been recorded in the draw log.
+///
+/// See `Display::set_draw_log`.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct DrawRecord {
+	/// Type of the primitives, or `None` for mesh tasks, whose primitives are generated by
+	/// the mesh shader.
+	pub primitives: Option<index_buffer::PrimitiveType>,
+
+	/// Number of primitives that have been drawn, including all the instances.
+	///
+	/// `None` for indirect draws and mesh tasks, whose number of primitives is only known by
+	/// the GPU.
+	pub primitives_count: Option<uint>,
+
+	/// Possible mistakes that have been detected in the uniforms and the vertex attributes
+	/// passed to the draw. Always empty when glium is compiled with `ndebug`.
+	///
+	/// This is the only place where the warnings are reported. The draw functions don't
+	/// return them and don't print them.
+	pub warnings: Vec<DrawWarning>,
+
+	program: gl::types::GLuint,
+	target: Option<fbo::FramebufferAttachments>,
+}
+
+impl DrawRecord {
+	/// Returns true if the draw has been done with this program.
+	pub fn uses_program(&self, program: &Program) -> bool {
+		self.program == program.get_id()
+	}
+
+	/// Returns true if the draw has been done on the default framebuffer, ie. the window.
+	pub fn is_on_default_framebuffer(&self) -> bool {
+		self.target.is_none()
+	}
+
+	/// Returns true if the draw has been done on a framebuffer that has this color
+	/// attachment.
+	pub fn has_color_attachment<C>(&self, color: &C) -> bool
+								   where C: framebuffer::ToColorAttachment
+	{
+		let color = fbo::Attachment::from_color(color.to_color_attachment());
+
+		match self.target {
+			Some(ref target) => target.colors.iter().any(|&(_, a)| a == color),
+			None => false,
+		}
+	}
+
+	/// Returns true if the draw has been done on a framebuffer that has this depth
+	/// attachment.
+	pub fn has_depth_attachment<D>(&self, depth: &D) -> bool
+								   where D: framebuffer::ToDepthAttachment
+	{
+		let depth = fbo::Attachment::from_depth(depth.to_depth_attachment());
+
+		match self.target {
+			Some(ref target) => target.depth == Some(depth),
+			None => false,
+		}
+	}
+}
+
+/// A possible mistake in the inputs of a draw.
+///
+/// The program only knows the uniforms and attributes that are actually used by the shaders.
+/// The GLSL compiler removes the ones that don't contribute to the output, so a warning
+/// doesn't always indicate a bug. But a misspelled name, like `u_colour` instead of
+/// `u_color`, always produces one `UnusedUniform` and one `MissingUniform` warning.
+///
+/// Warnings are not returned by the draw functions. They are only recorded in the draw log,
+/// which must be enabled with `Display::set_draw_log` before drawing. See
+/// `DrawRecord::warnings`.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub enum DrawWarning {
+	/// A uniform has been passed but the program doesn't have an active uniform with this
+	/// name.
+	UnusedUniform(String),
+
+	/// The program has an active uniform with this name but it hasn't been passed. The
+	/// uniform keeps the last value that has been set, or zero.
+	MissingUniform(String),
+
+	/// The vertex buffer has an attribute with this name but the program doesn't use it.
+	///
+	/// The opposite situation is an error. See `VertexFormatMismatch::MissingAttribute`.
+	UnusedAttribute(String),
+}
+
+/// Describes the version and the limits of the backend.
+///
+/// See `Display::get_capabilities`. You can use it to choose between several code paths
+/// depending on what the hardware supports.
+#[deriving(Show, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+	/// Version of OpenGL or OpenGL ES, as `(major, minor)`.
+	pub opengl_version: (u8, u8),
+
+	/// True if the backend is OpenGL ES.
+	pub opengl_es: bool,
+
+	/// Version of GLSL or GLSL ES, as `(major, minor)`. For example `(3, 30)` for GLSL 3.30.
+	pub glsl_version: (u8, u8),
+
+	/// Names of the extensions supported by the backend, for example `GL_ARB_sync`.
+	pub extensions: Vec<String>,
+
+	/// Maximum width and height of a texture.
+	pub max_texture_size: u32,
+
+	/// Maximum number of samples of multisample render buffers and textures, or 0 if
+	/// multisampling is not supported.
+	pub max_samples: u32,
+
+	/// Maximum number of color attachments of a framebuffer.
+	pub max_color_attachments: u32,
+
+	/// Maximum dimensions of the viewport.
+	pub max_viewport_dimensions: (u32, u32),
+
+	/// Maximum number of textures that a program can use at once.
+	pub max_combined_texture_units: u32,
+
+	/// Maximum value for anisotropic filtering, or `None` if it is not supported.
+	pub max_anisotropy: Option<u16>,
+
+	/// Maximum number of viewports that can be used in `DrawParameters::viewports`, or `None`
+	/// if viewport arrays are not supported.
+	pub max_viewports: Option<u32>,
+
+	geometry_shader: bool,
+	compute_shader: bool,
+	texture_buffer: bool,
+	texture_multisample: bool,
+	draw_indirect: bool,
+	uniform_blocks: bool,
+	smoothing: bool,
+	primitive_restart: bool,
+	depth_clamp: bool,
+	polygon_mode: bool,
+	stencil_texturing: bool,
+	provoking_vertex: bool,
+}
+
+impl Capabilities {
+	/// Returns true if programs can have a geometry shader.
+	pub fn supports_geometry_shaders(&self) -> bool {
+		self.geometry_shader
+	}
+
+	/// Returns true if compute shaders are supported.
+	pub fn supports_compute_shaders(&self) -> bool {
+		self.compute_shader
+	}
+
+	/// Returns true if buffer textures are supported.
+	pub fn supports_buffer_textures(&self) -> bool {
+		self.texture_buffer
+	}
+
+	/// Returns true if multisample textures are supported.
+	pub fn supports_multisample_textures(&self) -> bool {
+		self.texture_multisample
+	}
+
+	/// Returns true if draw commands can be read from a buffer.
+	pub fn supports_indirect_drawing(&self) -> bool {
+		self.draw_indirect
+	}
+
+	/// Returns true if programs can have uniform blocks.
+	pub fn supports_uniform_blocks(&self) -> bool {
+		self.uniform_blocks
+	}
+
+	/// Returns true if the `line_smooth` and `polygon_smooth` draw parameters can be used.
+	pub fn supports_smoothing(&self) -> bool {
+		self.smoothing
+	}
+
+	/// Returns true if the `primitive_restart_index` draw parameter can be used.
+	pub fn supports_primitive_restart(&self) -> bool {
+		self.primitive_restart
+	}
 
-impl std::error::FromError<glutin::CreationError> for GliumCreationError {
-	fn from_error(err: glutin::CreationError) -> GliumCreationError {
-		GliumCreationError::GlutinCreationError(err)
+	/// Returns true if the `depth_clamp` draw parameter can be used.
+	pub fn supports_depth_clamp(&self) -> bool {
+		self.depth_clamp
 	}
-}
 
-impl<'a> DisplayBuild for glutin::WindowBuilder<'a> {
-	fn build_glium(self) -> Result<Display, GliumCreationError> {
-		let context = try!(context::Context::new_from_window(self, None));
+	/// Returns true if the `polygon_mode` draw parameter can be something else than `Fill`.
+	pub fn supports_polygon_mode(&self) -> bool {
+		self.polygon_mode
+	}
 
-		Ok(Display {
-			context: Arc::new(DisplayImpl {
-				context: context,
-				debug_callback: Mutex::new(None),
-				framebuffer_objects: Mutex::new(HashMap::new()),
-				vertex_array_objects: Mutex::new(HashMap::new()),
-				samplers: Mutex::new(HashMap::new()),
-			}),
-		})
+	/// Returns true if the stencil component of a `DepthStencilTexture2d` can be sampled.
+	pub fn supports_stencil_texturing(&self) -> bool {
+		self.stencil_texturing
 	}
-}
 
-#[cfg(feature = "headless")]
-impl DisplayBuild for glutin::HeadlessRendererBuilder {
-	fn build_glium(self) -> Result<Display, GliumCreationError> {
-		let context = try!(context::Context::new_from_headless(self));
+	/// Returns true if the `provoking_vertex` draw parameter can be something else than
+	/// `LastVertex`.
+	pub fn supports_provoking_vertex(&self) -> bool {
+		self.provoking_vertex
+	}
 
-		Ok(Display {
-			context: Arc::new(DisplayImpl {
-				context: context,
-				debug_callback: Mutex::new(None),
-				framebuffer_objects: Mutex::new(HashMap::new()),
-				vertex_array_objects: Mutex::new(HashMap::new()),
-				samplers: Mutex::new(HashMap::new()),
-			}),
-		})
+	/// Returns true if the backend supports the given extension, for example
+	/// `"GL_ARB_sync"`.
+	pub fn supports_extension(&self, name: &str) -> bool {
+		self.extensions.iter().any(|e| e.as_slice() == name)
 	}
 }
 
@@ -981,21 +3008,125 @@ struct DisplayImpl {
 	debug_callback: Mutex<Option<Box<FnMut(String, debug::Source, debug::MessageType, debug::Severity)
 									 + Send + Sync>>>,
 
+	// severities of the debug messages that must not be sent to the callback
+	disabled_debug_severities: Mutex<Vec<debug::Severity>>,
+
 	// we maintain a list of FBOs
 	// when something requirering a FBO is drawn, we look for an existing one in this hashmap
 	framebuffer_objects: Mutex<HashMap<fbo::FramebufferAttachments,
 									   fbo::FrameBufferObject>>,
 
-	// we maintain a list of VAOs for each vertexbuffer-indexbuffer-program association
-	// the key is a (vertexbuffer, program)
-	vertex_array_objects: Mutex<HashMap<(gl::types::GLuint, gl::types::GLuint, gl::types::GLuint),
+	// we maintain a list of VAOs for each vertexbuffers-indexbuffer-attributes association
+	// the key is a (vertexbuffers, indexbuffer, attribute layout), see `vertex_array_object`
+	vertex_array_objects: Mutex<HashMap<(Vec<vertex_array_object::BufferKey>,
+										 gl::types::GLuint, u32),
 										vertex_array_object::VertexArrayObject>>,
 
+	// the ids of the vertex formats in the VAOs keys, see `vertex_array_object::get_format_id`
+	vertex_formats: Mutex<HashMap<vertex_buffer::VertexFormat, u32>>,
+
+	// the attribute layouts of the programs, which identify the programs in the VAOs keys
+	attribute_layouts: Mutex<vertex_array_object::AttributeLayouts>,
+
 	// we maintain a list of samplers for each possible behavior
 	samplers: Mutex<HashMap<uniforms::SamplerBehavior, uniforms::SamplerObject>>,
+
+	// behavior of the samplers of the float textures that are used without a `Sampler`
+	default_sampler_behavior: Mutex<Option<uniforms::SamplerBehavior>>,
+
+	// dimensions of the `Frame` currently being drawn, if any
+	current_frame: Mutex<Option<(uint, uint)>>,
+
+	// list of overlays to draw at the end of each frame
+	overlays: Mutex<Vec<Box<Overlay + Send + Sync>>>,
+
+	// if true, the indices are checked against the vertex source before each draw
+	validate_indices: AtomicBool,
+
+	// draws that have been recorded since the log has been enabled, or `None` if disabled
+	draw_log: Mutex<Option<Vec<DrawRecord>>>,
+
+	// if true, the deletion of objects is deferred until the GPU has stopped using them
+	deferred_deletion: AtomicBool,
+
+	// if true, the content of new textures, render buffers and buffers is filled with zeroes
+	zero_initialization: AtomicBool,
+
+	// how the source code of the shaders is modified before being compiled
+	shader_precision: Mutex<program::ShaderPrecision>,
+
+	// the displays whose objects are shared with this one, including this one
+	share_group: Arc<Mutex<Vec<Weak<DisplayImpl>>>>,
 }
 
 impl Display {
+	fn from_context(context: context::Context,
+					share_group: Option<Arc<Mutex<Vec<Weak<DisplayImpl>>>>>) -> Display
+	{
+		let share_group = share_group.unwrap_or_else(|| Arc::new(Mutex::new(Vec::new())));
+
+		let display = Display {
+			context: Arc::new(DisplayImpl {
+				context: context,
+				debug_callback: Mutex::new(None),
+				disabled_debug_severities: Mutex::new(Vec::new()),
+				framebuffer_objects: Mutex::new(HashMap::new()),
+				vertex_array_objects: Mutex::new(HashMap::new()),
+				vertex_formats: Mutex::new(HashMap::new()),
+				attribute_layouts: Mutex::new(vertex_array_object::AttributeLayouts::new()),
+				samplers: Mutex::new(HashMap::new()),
+				default_sampler_behavior: Mutex::new(None),
+				current_frame: Mutex::new(None),
+				overlays: Mutex::new(Vec::new()),
+				validate_indices: AtomicBool::new(false),
+				draw_log: Mutex::new(None),
+				deferred_deletion: AtomicBool::new(true),
+				zero_initialization: AtomicBool::new(cfg!(not(ndebug))),
+				shader_precision: Mutex::new(std::default::Default::default()),
+				share_group: share_group.clone(),
+			}),
+		};
+
+		share_group.lock().unwrap().push(display.context.downgrade());
+		display
+	}
+
+	/// Builds a new display whose textures, buffers and programs are shared with this one.
+	///
+	/// The new display has its own rendering thread and draws on a hidden window. You can
+	/// send it to a worker thread to create and fill textures and buffers without stalling
+	/// the rendering of this display, then send the objects back and use them here.
+	///
+	/// Commands of two different displays are not ordered. Create a `SyncFence` with the
+	/// shared display after the uploads, and call `wait_server_in` with this display before
+	/// using the objects. If fences are not supported, call `synchronize` on the shared
+	/// display instead.
+	///
+	/// Returns `SharedContextsNotSupported` if this display is headless.
+	///
+	/// ## Example
+	///
+	/// ```no_run
+	/// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+	/// # let data: Vec<Vec<(u8, u8, u8)>> = vec![];
+	/// let worker = display.build_shared().unwrap();
+	/// let (tx, rx) = channel();
+	///
+	/// std::thread::Thread::spawn(move || {
+	///     let texture = glium::Texture2d::new(&worker, data);
+	///     let fence = glium::sync::SyncFence::new(&worker).unwrap();
+	///     tx.send((texture, fence));
+	/// }).detach();
+	///
+	/// let (texture, fence) = rx.recv();
+	/// fence.wait_server_in(&display);
+	/// // `texture` can now be used with `display`
+	/// ```
+	pub fn build_shared(&self) -> Result<Display, GliumCreationError> {
+		let context = try!(self.context.context.new_shared());
+		Ok(Display::from_context(context, Some(self.context.share_group.clone())))
+	}
+
 	/// Reads all events received by the window.
 	pub fn poll_events(&self) -> Vec<glutin::Event> {
 		self.context.context.recv()
@@ -1008,15 +3139,94 @@ impl Display {
 
 	/// Start drawing on the backbuffer.
 	///
-	/// This function returns a `Frame` which can be used to draw on it. When the `Frame` is
-	/// destroyed, the buffers are swapped.
+	/// This function returns a `Frame` which can be used to draw on it. Call `finish` on the
+	/// `Frame` to swap the buffers.
+	///
+	/// Frames can be started one after the other as often as needed. The dimensions of the
+	/// window are tracked by the rendering thread when it is resized, so starting a frame
+	/// doesn't query them.
 	///
-	/// Note that destroying a `Frame` is immediate, even if vsync is enabled.
+	/// Note that finishing a `Frame` is immediate, even if vsync is enabled.
 	pub fn draw(&self) -> Frame {
+		let dimensions = self.get_framebuffer_dimensions();
+		*self.context.current_frame.lock().unwrap() = Some(dimensions);
+
 		Frame {
 			display: self.clone(),
 			marker: std::kinds::marker::ContravariantLifetime,
-			dimensions: self.get_framebuffer_dimensions(),
+			dimensions: dimensions,
+			owns_frame: true,
+			finished: false,
+			finish_on_drop: false,
+		}
+	}
+
+	/// Returns the dimensions of the `Frame` currently being drawn, or `None` if no `Frame`
+	/// is alive.
+	pub fn get_current_frame_dimensions(&self) -> Option<(uint, uint)> {
+		self.context.current_frame.lock().unwrap().clone()
+	}
+
+	/// Returns a read-only handle to the `Frame` currently being drawn, or `None` if no
+	/// `Frame` is alive.
+	///
+	/// This allows libraries that only have access to the `Display` to read what has been
+	/// drawn so far. Only the owner of the `Frame` can draw on it or swap the buffers.
+	pub fn get_current_frame(&self) -> Option<CurrentFrame> {
+		self.get_current_frame_dimensions().map(|dimensions| {
+			CurrentFrame {
+				frame: Frame {
+					display: self.clone(),
+					marker: std::kinds::marker::ContravariantLifetime,
+					dimensions: dimensions,
+					owns_frame: false,
+					finished: false,
+					finish_on_drop: false,
+				},
+			}
+		})
+	}
+
+	/// Registers an overlay that will be drawn at the end of each frame.
+	pub fn add_overlay(&self, overlay: Box<Overlay + Send + Sync>) {
+		self.context.overlays.lock().unwrap().push(overlay);
+	}
+
+	/// Removes all the overlays that have been registered with `add_overlay`.
+	pub fn clear_overlays(&self) {
+		self.context.overlays.lock().unwrap().clear();
+	}
+
+	/// Returns the version, the extensions and the limits of the backend.
+	pub fn get_capabilities(&self) -> Capabilities {
+		let capabilities = self.context.context.capabilities();
+
+		Capabilities {
+			opengl_version: (capabilities.version.0, capabilities.version.1),
+			opengl_es: capabilities.opengl_es,
+			glsl_version: (capabilities.glsl_version.0, capabilities.glsl_version.1),
+			extensions: capabilities.extensions.clone(),
+			max_texture_size: capabilities.max_texture_size as u32,
+			max_samples: capabilities.max_samples as u32,
+			max_color_attachments: capabilities.max_color_attachments as u32,
+			max_viewport_dimensions: (capabilities.max_viewport_dims.0 as u32,
+			                          capabilities.max_viewport_dims.1 as u32),
+			max_combined_texture_units: capabilities.max_combined_texture_image_units as u32,
+			max_anisotropy: capabilities.max_texture_max_anisotropy.map(|v| v as u16),
+			max_viewports: capabilities.max_viewports.map(|v| v as u32),
+			geometry_shader: capabilities.geometry_shader,
+			compute_shader: capabilities.compute_shader,
+			texture_buffer: capabilities.texture_buffer,
+			texture_multisample: capabilities.texture_multisample,
+			draw_indirect: capabilities.draw_indirect,
+			uniform_blocks: capabilities.uniform_blocks,
+			smoothing: capabilities.smoothing,
+			primitive_restart: capabilities.primitive_restart_fixed_index ||
+							   capabilities.primitive_restart,
+			depth_clamp: capabilities.depth_clamp,
+			polygon_mode: capabilities.polygon_mode,
+			stencil_texturing: capabilities.stencil_texturing,
+			provoking_vertex: capabilities.provoking_vertex,
 		}
 	}
 
@@ -1028,12 +3238,239 @@ impl Display {
 
 	/// Returns the maximum dimensions of the viewport that you can pass when drawing.
 	///
-	/// Glium will panic if you request a larger viewport.
+	/// Drawing with a larger viewport returns `DrawError::ViewportTooLarge`.
 	pub fn get_max_viewport_dimensions(&self) -> (u32, u32) {
 		let d = self.context.context.capabilities().max_viewport_dims;
 		(d.0 as u32, d.1 as u32)
 	}
 
+	/// Returns true if textures can be created with the given format.
+	///
+	/// Textures created with an unsupported format can't be used. Use the `new_empty_checked`
+	/// and `new_empty_with_fallback` constructors of the textures to handle this situation.
+	pub fn is_texture_format_supported(&self, format: texture::TextureFormat) -> bool {
+		format.is_supported(self)
+	}
+
+	/// Returns true if glium modifies buffers, textures and framebuffers without binding
+	/// them, thanks to `GL_ARB_direct_state_access` or OpenGL 4.5.
+	///
+	/// This doesn't change the behavior of glium, but reduces the number of state changes.
+	pub fn is_direct_state_access_used(&self) -> bool {
+		self.context.context.capabilities().direct_state_access
+	}
+
+	/// Returns which wave-level operations (ballot, vote, shuffle, etc.) can be used in
+	/// shaders.
+	///
+	/// Use `get_glsl_preamble` on the result to obtain the `#extension` directives that
+	/// enable them.
+	pub fn get_subgroup_support(&self) -> SubgroupSupport {
+		let capabilities = self.context.context.capabilities();
+
+		SubgroupSupport {
+			ballot: capabilities.shader_ballot,
+			group_vote: capabilities.shader_group_vote,
+			subgroup: capabilities.shader_subgroup,
+			subgroup_features: program::subgroup_features_from_bits(capabilities.subgroup_features),
+			subgroup_stages: program::subgroup_stages_from_bits(capabilities.subgroup_stages),
+		}
+	}
+
+	/// Enables or disables the validation of indices.
+	///
+	/// When enabled, glium checks before each draw that all the indices point to a vertex of
+	/// the vertex source, and returns `DrawError::IndexOutOfRange` with the position of the
+	/// first offending index otherwise.
+	/// Without this check, out-of-range indices trigger undefined behaviors or even crash the
+	/// driver.
+	///
+	/// This check is slow, especially for index buffers whose content must be read back from
+	/// the video memory. You should only enable it while debugging. Index buffers can only be
+	/// checked if the `gl_extensions` feature is enabled.
+	pub fn set_indices_validation(&self, enabled: bool) {
+		self.context.validate_indices.store(enabled, Relaxed);
+	}
+
+	/// Enables or disables the draw log. Enabling the log clears it.
+	///
+	/// When enabled, each draw records a `DrawRecord` containing the program, the target and
+	/// the number of primitives. This lets tests check what has been drawn without comparing
+	/// pixels. In debug builds, each record also lists the uniforms and attributes that don't
+	/// match the program (see `DrawWarning`). Draws that don't draw anything, for example
+	/// because the vertex source is empty, are not recorded. Indirect draws and mesh tasks are
+	/// recorded without a number of primitives.
+	pub fn set_draw_log(&self, enabled: bool) {
+		*self.context.draw_log.lock().unwrap() = if enabled { Some(Vec::new()) } else { None };
+	}
+
+	/// Returns the draws that have been recorded since the draw log has been enabled or
+	/// cleared, in the order in which they have been submitted.
+	///
+	/// Returns an empty list if the draw log is disabled.
+	pub fn get_draw_log(&self) -> Vec<DrawRecord> {
+		match *self.context.draw_log.lock().unwrap() {
+			Some(ref log) => log.clone(),
+			None => Vec::new(),
+		}
+	}
+
+	/// Removes all the draws of the draw log without disabling it.
+	pub fn clear_draw_log(&self) {
+		if let Some(ref mut log) = *self.context.draw_log.lock().unwrap() {
+			log.clear();
+		}
+	}
+
+	/// Sets the sampler behavior to use for textures that are passed as uniforms without a
+	/// `Sampler`. `None`, which is the default value, uses the parameters of the texture.
+	///
+	/// This lets you change the filtering quality of the whole application, for example to
+	/// enable trilinear and anisotropic filtering, without modifying every `uniform!`.
+	/// The default behavior only applies to float and compressed textures and to cubemaps,
+	/// because integral textures and depth textures usually can't be filtered.
+	///
+	/// The anisotropy is clamped to the value supported by the backend.
+	pub fn set_default_sampler_behavior(&self, behavior: Option<uniforms::SamplerBehavior>) {
+		*self.context.default_sampler_behavior.lock().unwrap() = behavior;
+	}
+
+	/// Returns the sampler behavior set with `set_default_sampler_behavior`.
+	pub fn get_default_sampler_behavior(&self) -> Option<uniforms::SamplerBehavior> {
+		*self.context.default_sampler_behavior.lock().unwrap()
+	}
+
+	/// Sets whether the deletion of textures, buffers and render buffers is deferred. The
+	/// default value is `true`.
+	///
+	/// Deleting an object that the GPU is still reading can cause stalls or corruptions with
+	/// some drivers. When deferred deletion is enabled, glium waits until the GPU has finished
+	/// executing the commands of the current frame before deleting the objects that have been
+	/// destroyed during this frame. At most three frames of objects are kept alive this way.
+	/// Displays that don't draw frames, like headless displays, check regularly whether the
+	/// GPU is done with the objects, and `synchronize` deletes all of them.
+	///
+	/// Objects are deleted immediately if the backend doesn't support fences, or if this
+	/// option is disabled. This only applies to objects that are destroyed afterwards.
+	pub fn set_deferred_deletion(&self, enabled: bool) {
+		self.context.deferred_deletion.store(enabled, Relaxed);
+	}
+
+	/// Sets whether textures, render buffers and buffers that are created without any data
+	/// are filled with zeroes. The default value is `true` in debug mode and `false` otherwise.
+	///
+	/// The content of video memory that has just been allocated is undefined, and often
+	/// contains garbage left by other objects or other applications. Filling it makes
+	/// rendering deterministic at the cost of a small overhead when creating objects.
+	///
+	/// Compressed textures are never filled. This only applies to objects that are
+	/// created afterwards.
+	pub fn set_zero_initialization(&self, enabled: bool) {
+		self.context.zero_initialization.store(enabled, Relaxed);
+	}
+
+	/// Sets how the source code of the shaders is modified before being compiled.
+	///
+	/// By default, OpenGL ES fragment shaders that don't declare a default precision for
+	/// `float` receive `precision mediump float;`. See `ShaderPrecision` for the other
+	/// options, including the emulation of `mediump` on desktop OpenGL.
+	///
+	/// This only applies to programs that are created afterwards.
+	pub fn set_shader_precision(&self, precision: ShaderPrecision) {
+		*self.context.shader_precision.lock().unwrap() = precision;
+	}
+
+	/// Returns the counters of the operations that have been executed since the creation of
+	/// the display or the latest call to `reset_statistics`, including the current frame.
+	///
+	/// The counters are updated by the rendering thread, so this function waits for all
+	/// the previous commands to be processed.
+	pub fn get_statistics(&self) -> Statistics {
+		let (tx, rx) = channel();
+
+		self.context.context.exec(move |: ctxt| {
+			let mut statistics = ctxt.state.previous_frames_statistics;
+			statistics.add(&ctxt.state.statistics);
+			tx.send(statistics);
+		});
+
+		rx.recv()
+	}
+
+	/// Returns the counters of the operations of the latest frame that has been finished.
+	///
+	/// This is what you usually want to display in a profiling overlay.
+	pub fn get_last_frame_statistics(&self) -> Statistics {
+		let (tx, rx) = channel();
+
+		self.context.context.exec(move |: ctxt| {
+			tx.send(ctxt.state.last_frame_statistics);
+		});
+
+		rx.recv()
+	}
+
+	/// Resets all the counters returned by `get_statistics` and `get_last_frame_statistics`
+	/// to zero.
+	pub fn reset_statistics(&self) {
+		self.context.context.exec(move |: ctxt| {
+			ctxt.state.statistics = std::default::Default::default();
+			ctxt.state.previous_frames_statistics = std::default::Default::default();
+			ctxt.state.last_frame_statistics = std::default::Default::default();
+		});
+	}
+
+	/// Tells glium that the OpenGL state has been modified by code outside of glium.
+	///
+	/// Glium keeps a cache of the OpenGL state, like the current program, the textures bound
+	/// to each texture unit and the blending and depth parameters, so that consecutive draws
+	/// with identical state issue almost no state change. This cache becomes wrong if you
+	/// call OpenGL functions yourself, for example from a library that renders with the same
+	/// context.
+	///
+	/// Call this function after such code has run. The values of the cache are applied to the
+	/// OpenGL state again, and the texture units are considered unknown until they are used.
+	pub fn invalidate_state_cache(&self) {
+		self.context.context.exec(move |: mut ctxt| {
+			unsafe { context::restore_state(&mut ctxt) };
+		});
+	}
+
+	/// Executes a function while the OpenGL context of the display is current.
+	///
+	/// This is the way to call OpenGL functions yourself, or to use a library that draws
+	/// with the current context. The function runs on the thread that owns the context, and
+	/// this function blocks until it has returned. The ids of glium's objects can be obtained
+	/// with `GlObject::get_id`.
+	///
+	/// The state cache of glium is restored after the function has run, exactly like with
+	/// `invalidate_state_cache`.
+	///
+	/// ## Example
+	///
+	/// ```no_run
+	/// # let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+	/// unsafe {
+	///     display.exec_in_context(move |:| {
+	///         // call a C library that renders with the current context
+	///     });
+	/// }
+	/// ```
+	///
+	/// # Safety
+	///
+	/// The function must not delete objects that belong to glium or modify their storage, and
+	/// must not make another context current.
+	pub unsafe fn exec_in_context<F>(&self, f: F) where F: FnOnce() + Send {
+		let (tx, rx) = channel();
+		self.context.context.exec(move |: mut ctxt| {
+			f();
+			context::restore_state(&mut ctxt);
+			tx.send(());
+		});
+		rx.recv();
+	}
+
 	/// Releases the shader compiler, indicating that no new programs will be created for a while.
 	///
 	/// # Features
@@ -1103,6 +3540,130 @@ impl Display {
 		self.set_debug_callback_impl(callback, true);
 	}
 
+	/// Sets a callback that prints the debug messages to the standard error output.
+	///
+	/// Use `set_debug_severity_enabled` to hide the messages that you are not interested in.
+	#[experimental = "The API will probably change"]
+	pub fn set_default_debug_callback(&self) {
+		self.set_debug_callback(|&mut: message: String, source: debug::Source,
+									  ty: debug::MessageType, severity: debug::Severity|
+		{
+			let _ = writeln!(&mut std::io::stderr(), "glium debug message ({}, {}, {}): {}",
+							 severity, source, ty, message);
+		});
+	}
+
+	/// Chooses whether the debug messages of the given severity are sent to the debug
+	/// callback. All severities are enabled by default.
+	///
+	/// The messages are filtered by the backend, so disabling the notifications also saves
+	/// the cost of generating them.
+	pub fn set_debug_severity_enabled(&self, severity: debug::Severity, enabled: bool) {
+		{
+			let mut disabled = self.context.disabled_debug_severities.lock().unwrap();
+			disabled.retain(|s| *s != severity);
+			if !enabled {
+				disabled.push(severity);
+			}
+		}
+
+		self.context.context.exec(move |: ctxt| {
+			unsafe {
+				if ctxt.version >= &context::GlVersion(4,5) || ctxt.extensions.gl_khr_debug {
+					ctxt.gl.DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE,
+						severity as gl::types::GLenum, 0, std::ptr::null(),
+						if enabled { gl::TRUE } else { gl::FALSE });
+				}
+			}
+		});
+	}
+
+	/// Enables a watchdog that calls `callback` when the GPU spends more than `threshold_ns`
+	/// nanoseconds executing the commands of a frame.
+	///
+	/// This helps diagnosing shaders that run for too long, for example shaders with infinite
+	/// loops in user-generated content, before the operating system resets the GPU. Timestamp
+	/// queries are issued at the start and at the end of each frame, and a fence at the end.
+	/// The queries of the previous frames are checked without blocking, so frames that are
+	/// still being executed are reported too. Frames are only checked when a frame ends, so
+	/// the delay before a frame is reported depends on the frame rate. Each slow frame is
+	/// reported once.
+	///
+	/// The callback is called by the rendering thread, and must not call functions of glium
+	/// that wait for it. Replaces the previous watchdog, if any.
+	///
+	/// Returns false and does nothing if the backend doesn't support fences or timer queries.
+	pub fn set_gpu_watchdog<F>(&self, threshold_ns: u64, callback: F) -> bool
+		where F: FnMut(debug::SlowFrame) + Send
+	{
+		let capabilities = self.context.context.capabilities();
+		if !capabilities.fence_sync || !capabilities.timer_query {
+			return false;
+		}
+
+		let callback = box callback as Box<FnMut(debug::SlowFrame) + Send>;
+
+		self.context.context.exec(move |: ctxt| {
+			if let Some(previous) = ctxt.state.watchdog.take() {
+				unsafe { previous.destroy(ctxt.gl) };
+			}
+
+			ctxt.state.watchdog = Some(unsafe {
+				context::Watchdog::new(ctxt.gl, threshold_ns, callback)
+			});
+		});
+
+		true
+	}
+
+	/// Enables a watchdog that prints the slow frames to the standard error output.
+	///
+	/// See `set_gpu_watchdog`.
+	pub fn set_default_gpu_watchdog(&self, threshold_ns: u64) -> bool {
+		self.set_gpu_watchdog(threshold_ns, |&mut: frame: debug::SlowFrame| {
+			let _ = writeln!(&mut std::io::stderr(), "glium watchdog: frame {} is still being \
+													  executed {}ms after its end", frame.frame,
+							 frame.elapsed_ns / 1000000);
+		})
+	}
+
+	/// Disables the watchdog enabled with `set_gpu_watchdog`, if any.
+	pub fn disable_gpu_watchdog(&self) {
+		self.context.context.exec(move |: ctxt| {
+			if let Some(watchdog) = ctxt.state.watchdog.take() {
+				unsafe { watchdog.destroy(ctxt.gl) };
+			}
+		});
+	}
+
+	/// Sets the number of steps of background tasks that the rendering thread executes at the
+	/// end of each frame, like generating the mipmaps of textures created with
+	/// `MipmapsOption::BackgroundGeneratedMipmaps`.
+	///
+	/// The tasks are executed in small steps in the order in which they have been queued. For
+	/// mipmaps, a step generates one level of one texture. A budget of `0` pauses them. The
+	/// default is one step per frame.
+	pub fn set_background_budget(&self, steps: u32) {
+		self.context.context.exec(move |: ctxt| {
+			ctxt.state.background_budget = steps;
+		});
+	}
+
+	/// Executes all the background tasks that are still queued, regardless of the budget, and
+	/// waits until they are done.
+	///
+	/// This is useful during loading screens, where frame spikes don't matter.
+	pub fn flush_background_tasks(&self) {
+		let (tx, rx) = channel();
+
+		self.context.context.exec(move |: ctxt| {
+			context::process_background_tasks(ctxt, None);
+			tx.send(());
+		});
+
+		rx.recv();
+	}
+
 	fn set_debug_callback_impl<F>(&self, callback: F, sync: bool)
 		where F: FnMut(String, debug::Source, debug::MessageType, debug::Severity) + Send + Sync
 	{
@@ -1145,6 +3706,7 @@ impl Display {
 		// SAFETY NOTICE: we pass a raw pointer to the `DisplayImpl`
 		let ptr: &DisplayImpl = self.context.deref();
 		let ptr = std::ptr::Unique(ptr as *const DisplayImpl as *mut DisplayImpl);
+		let disabled_severities = self.context.disabled_debug_severities.lock().unwrap().clone();
 
 		// enabling the callback
 		self.context.context.exec(move |: ctxt| {
@@ -1154,9 +3716,11 @@ impl Display {
 						if sync {
 							ctxt.gl.Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
 							ctxt.state.enabled_debug_output_synchronous = true;
+							ctxt.state.statistics.state_changes += 1;
 						} else {
 							ctxt.gl.Disable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
 							ctxt.state.enabled_debug_output_synchronous = false;
+							ctxt.state.statistics.state_changes += 1;
 						}
 					}
 
@@ -1166,9 +3730,15 @@ impl Display {
 					ctxt.gl.DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, gl::DONT_CARE, 0,
 						std::ptr::null(), gl::TRUE);
 
+					for severity in disabled_severities.iter() {
+						ctxt.gl.DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE,
+							*severity as gl::types::GLenum, 0, std::ptr::null(), gl::FALSE);
+					}
+
 					if ctxt.state.enabled_debug_output != Some(true) {
 						ctxt.gl.Enable(gl::DEBUG_OUTPUT);
 						ctxt.state.enabled_debug_output = Some(true);
+						ctxt.state.statistics.state_changes += 1;
 					}
 				}
 			}
@@ -1211,10 +3781,12 @@ impl Display {
 					if ctxt.version >= &context::GlVersion(3, 0) {
 						ctxt.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
 						ctxt.state.read_framebuffer = 0;
+						ctxt.state.statistics.state_changes += 1;
 					} else {
 						ctxt.gl.BindFramebufferEXT(gl::FRAMEBUFFER_EXT, 0);
 						ctxt.state.draw_framebuffer = 0;
 						ctxt.state.read_framebuffer = 0;
+						ctxt.state.statistics.state_changes += 1;
 					}
 				}
 
@@ -1222,9 +3794,11 @@ impl Display {
 				if ctxt.state.default_framebuffer_read != Some(gl::FRONT_LEFT) {
 					ctxt.gl.ReadBuffer(gl::FRONT_LEFT);
 					ctxt.state.default_framebuffer_read = Some(gl::FRONT_LEFT);
+					ctxt.state.statistics.state_changes += 1;
 				}
 
 				// reading
+				ctxt.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
 				let total_data_size = pixels_count * pixels_size / mem::size_of::<P>();
 				let mut data: Vec<P> = Vec::with_capacity(total_data_size);
 				ctxt.gl.ReadPixels(0, 0, dimensions.0 as gl::types::GLint,
@@ -1265,8 +3839,12 @@ impl Display {
 	pub fn synchronize(&self) {
 		let (tx, rx) = channel();
 
-		self.context.context.exec(move |: ctxt| {
+		self.context.context.exec(move |: mut ctxt| {
 			unsafe { ctxt.gl.Finish(); }
+
+			// the GPU doesn't use the objects that are waiting to be deleted anymore
+			context::delete_deferred_objects_after_finish(&mut ctxt);
+
 			tx.send(());
 		});
 
@@ -1274,6 +3852,92 @@ impl Display {
 	}
 }
 
+impl DisplayImpl {
+	/// Deletes a texture, a buffer or a render buffer that has been destroyed.
+	///
+	/// FBOs and VAOs can't be shared between contexts, but the ids of the objects that they
+	/// use can. Since the driver can reuse the id of an object once it is deleted, the FBOs,
+	/// the VAOs and the cached bindings that use the object are removed from all the displays
+	/// of the share group, and not only from this one.
+	fn delete_object(&self, object: context::Deletion) {
+		let group = self.share_group.lock().unwrap().iter().filter_map(|d| d.upgrade())
+		                                        .collect::<Vec<_>>();
+
+		for display in group.iter() {
+			display.purge_caches(object);
+
+			// the bindings of this display are updated when the object is deleted
+			if &**display as *const DisplayImpl != self as *const DisplayImpl {
+				display.context.exec(move |: ctxt| {
+					context::forget_object(ctxt.state, object);
+				});
+			}
+		}
+
+		let deferred = self.deferred_deletion.load(Relaxed);
+		self.context.exec(move |: mut ctxt| {
+			context::delete_object(&mut ctxt, object, deferred);
+		});
+	}
+
+	/// Same as `delete_object`, but for an object that glium doesn't own. The FBOs, the VAOs
+	/// and the cached bindings that use it are removed, but the object itself is not deleted.
+	fn forget_object(&self, object: context::Deletion) {
+		let group = self.share_group.lock().unwrap().iter().filter_map(|d| d.upgrade())
+		                                        .collect::<Vec<_>>();
+
+		for display in group.iter() {
+			display.purge_caches(object);
+			display.context.exec(move |: ctxt| {
+				context::forget_object(ctxt.state, object);
+			});
+		}
+	}
+
+	/// Removes the FBOs and the VAOs that use an object from the caches of this display.
+	fn purge_caches(&self, object: context::Deletion) {
+		match object {
+			context::Deletion::Buffer(id) => {
+				let mut vaos = self.vertex_array_objects.lock().unwrap();
+				let to_delete = vaos.keys().filter(|&&(ref v, i, _)| {
+					i == id || v.iter().any(|&(b, _, _, _)| b == id)
+				}).map(|k| k.clone()).collect::<Vec<_>>();
+
+				for k in to_delete.into_iter() {
+					vaos.remove(&k);
+				}
+			},
+
+			context::Deletion::Texture(id) => {
+				let mut fbos = self.framebuffer_objects.lock().unwrap();
+				let to_delete = fbos.keys().filter(|b| {
+					b.colors.iter().any(|&(_, a)| a.get_texture_id() == Some(id)) ||
+					b.depth.and_then(|a| a.get_texture_id()) == Some(id) ||
+					b.stencil.and_then(|a| a.get_texture_id()) == Some(id)
+				}).map(|k| k.clone()).collect::<Vec<_>>();
+
+				for k in to_delete.into_iter() {
+					fbos.remove(&k);
+				}
+			},
+
+			context::Deletion::Renderbuffer(id) => {
+				let attachment = fbo::Attachment::RenderBuffer(id);
+
+				let mut fbos = self.framebuffer_objects.lock().unwrap();
+				let to_delete = fbos.keys().filter(|b| {
+					b.colors.iter().any(|&(_, a)| a == attachment) ||
+					b.depth == Some(attachment) || b.stencil == Some(attachment)
+				}).map(|k| k.clone()).collect::<Vec<_>>();
+
+				for k in to_delete.into_iter() {
+					fbos.remove(&k);
+				}
+			},
+		}
+	}
+}
+
 // this destructor is here because objects in `Display` contain an `Arc<DisplayImpl>`,
 // which would lead to a leak
 impl Drop for DisplayImpl {
@@ -1284,6 +3948,7 @@ impl Drop for DisplayImpl {
 				if ctxt.state.enabled_debug_output != Some(false) {
 					ctxt.gl.Disable(gl::DEBUG_OUTPUT);
 					ctxt.state.enabled_debug_output = Some(false);
+					ctxt.state.statistics.state_changes += 1;
 					ctxt.gl.Finish();
 				}
 			}
@@ -1303,6 +3968,11 @@ impl Drop for DisplayImpl {
 			let mut samplers = self.samplers.lock().unwrap();
 			samplers.clear();
 		}
+
+		{
+			let mut overlays = self.overlays.lock().unwrap();
+			overlays.clear();
+		}
 	}
 }
 
@@ -0,0 +1,402 @@
+/*!
+Wide lines and large points built out of triangles.
+
+The width of lines drawn with `LinesList` or `LineStrip` is limited by the backend, and core
+profiles only guarantee a width of one pixel. This module expands lines and points into
+triangles on the CPU instead, so that they can have any width, with caps at their ends and
+joins between the segments of a strip.
+
+The positions are expected to be in pixels, because the width is only meaningful in screen
+space. The resulting vertex buffer has a single `position` attribute of type `vec2`, which
+your vertex shader must convert to normalized device coordinates, for example with
+`gl_Position = vec4(position / viewport_size * 2.0 - 1.0, 0.0, 1.0);`.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+use glium::lines::{WideLinesBuilder, LineStyle, LineCap, LineJoin};
+
+let mut builder = WideLinesBuilder::new();
+
+// a polyline with rounded joins
+builder.add_strip(&[[10.0, 10.0], [200.0, 50.0], [300.0, 200.0]], &LineStyle {
+    width: 8.0,
+    cap: LineCap::Round,
+    join: LineJoin::Round,
+});
+
+// each segment of a list can have its own width
+builder.add_segment([10.0, 300.0], [300.0, 300.0], 2.0, LineCap::Butt);
+builder.add_segment([10.0, 320.0], [300.0, 320.0], 6.0, LineCap::Square);
+
+// a point of 12 pixels
+builder.add_point([150.0, 150.0], 12.0, true);
+
+let (vertex_buffer, index_buffer) = builder.build(&display);
+```
+
+*/
+use std::cmp::max;
+use std::default::Default;
+use std::f32::consts::PI;
+use std::num::Float;
+
+use Display;
+use index_buffer::{IndexBuffer, TrianglesList};
+use vertex_buffer::{AttributeType, Vertex, VertexBuffer, VertexFormat};
+
+/// Number of triangles used for a half circle of a round cap or a round point.
+const ROUND_SEGMENTS: uint = 8;
+
+/// Shape of the ends of a line.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    /// The line stops exactly at its end point.
+    Butt,
+
+    /// The line is extended by half of its width past its end point.
+    Square,
+
+    /// The end of the line is a half circle centered on its end point.
+    Round,
+}
+
+/// Shape of the junction between two segments of a strip.
+#[deriving(Show, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    /// Nothing is added between the segments, which leaves a notch on the outer side of
+    /// the turn.
+    None,
+
+    /// The outer corners of the segments are joined by a straight edge.
+    Bevel,
+
+    /// The outer edges of the segments are extended until they meet.
+    ///
+    /// If the miter would be longer than `limit` times half of the width of the line, a
+    /// bevel join is used instead, which avoids spikes on sharp turns.
+    Miter {
+        /// Maximal length of the miter, relative to half of the width of the line.
+        limit: f32,
+    },
+
+    /// The outer corners of the segments are joined by an arc.
+    Round,
+}
+
+/// How a strip is drawn.
+#[deriving(Show, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    /// Width of the line, in pixels.
+    ///
+    /// The default is `1.0`.
+    pub width: f32,
+
+    /// Shape of the two ends of the strip.
+    ///
+    /// The default is `Butt`.
+    pub cap: LineCap,
+
+    /// Shape of the junctions between the segments.
+    ///
+    /// The default is `Miter { limit: 4.0 }`.
+    pub join: LineJoin,
+}
+
+impl Default for LineStyle {
+    fn default() -> LineStyle {
+        LineStyle {
+            width: 1.0,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter { limit: 4.0 },
+        }
+    }
+}
+
+/// A vertex produced by a `WideLinesBuilder`.
+#[deriving(Show, Clone, Copy, PartialEq)]
+pub struct LineVertex {
+    /// Position of the vertex, in pixels.
+    pub position: [f32, ..2],
+}
+
+impl Vertex for LineVertex {
+    fn build_bindings(_: Option<LineVertex>) -> VertexFormat {
+        vec![("position".to_string(), 0, AttributeType::F32F32, false)]
+    }
+}
+
+/// Accumulates lines and points, and expands them into a list of triangles.
+#[deriving(Show, Clone)]
+pub struct WideLinesBuilder {
+    vertices: Vec<LineVertex>,
+    indices: Vec<u32>,
+}
+
+impl WideLinesBuilder {
+    /// Builds an empty builder.
+    pub fn new() -> WideLinesBuilder {
+        WideLinesBuilder {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Adds a single segment between `start` and `end`, with its own width.
+    ///
+    /// Segments whose two ends are at the same position are ignored, unless `cap` is `Square`
+    /// or `Round`, in which case they are drawn as a point.
+    pub fn add_segment(&mut self, start: [f32, ..2], end: [f32, ..2], width: f32, cap: LineCap) {
+        let direction = match normalize(sub(end, start)) {
+            Some(d) => d,
+            None => {
+                match cap {
+                    LineCap::Butt => (),
+                    LineCap::Square => self.add_point(start, width, false),
+                    LineCap::Round => self.add_point(start, width, true),
+                }
+                return;
+            }
+        };
+
+        self.add_quad(start, end, direction, width * 0.5);
+        self.add_cap(start, neg(direction), width * 0.5, cap);
+        self.add_cap(end, direction, width * 0.5, cap);
+    }
+
+    /// Adds a polyline going through all the `points`.
+    ///
+    /// Consecutive points at the same position are merged. Nothing is added if the strip
+    /// contains less than two different points.
+    pub fn add_strip(&mut self, points: &[[f32, ..2]], style: &LineStyle) {
+        let half_width = style.width * 0.5;
+
+        // skipping the points that are too close to the previous one for the segment between
+        // them to have a direction
+        let mut filtered: Vec<[f32, ..2]> = Vec::with_capacity(points.len());
+        let mut directions: Vec<[f32, ..2]> = Vec::with_capacity(points.len());
+        for point in points.iter() {
+            match filtered.last().map(|last| normalize(sub(*point, *last))) {
+                Some(Some(direction)) => directions.push(direction),
+                Some(None) => continue,
+                None => ()
+            }
+            filtered.push(*point);
+        }
+
+        if filtered.len() < 2 {
+            return;
+        }
+
+        for (segment, direction) in filtered.as_slice().windows(2).zip(directions.iter()) {
+            self.add_quad(segment[0], segment[1], *direction, half_width);
+        }
+
+        for (i, pair) in directions.as_slice().windows(2).enumerate() {
+            self.add_join(filtered[i + 1], pair[0], pair[1], half_width, style.join);
+        }
+
+        self.add_cap(filtered[0], neg(directions[0]), half_width, style.cap);
+        self.add_cap(*filtered.last().unwrap(), *directions.last().unwrap(), half_width,
+                     style.cap);
+    }
+
+    /// Adds a point of `size` pixels centered on `center`. The point is a disc if `round` is
+    /// true, and a square otherwise.
+    pub fn add_point(&mut self, center: [f32, ..2], size: f32, round: bool) {
+        let radius = size * 0.5;
+
+        if round {
+            let first = self.vertices.len() as u32;
+            self.push_vertex(center);
+
+            for i in range(0, ROUND_SEGMENTS * 2) {
+                let angle = i as f32 * PI / ROUND_SEGMENTS as f32;
+                self.push_vertex(add(center, [angle.cos() * radius, angle.sin() * radius]));
+            }
+
+            for i in range(0, ROUND_SEGMENTS * 2) {
+                let next = (i + 1) % (ROUND_SEGMENTS * 2);
+                self.indices.push_all(&[first, first + 1 + i as u32, first + 1 + next as u32]);
+            }
+
+        } else {
+            let first = self.vertices.len() as u32;
+            self.push_vertex(add(center, [-radius, -radius]));
+            self.push_vertex(add(center, [radius, -radius]));
+            self.push_vertex(add(center, [radius, radius]));
+            self.push_vertex(add(center, [-radius, radius]));
+            self.indices.push_all(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+        }
+    }
+
+    /// Returns the vertices that have been produced so far.
+    pub fn get_vertices(&self) -> &[LineVertex] {
+        self.vertices.as_slice()
+    }
+
+    /// Returns the indices that have been produced so far. Each group of three indices is
+    /// a triangle.
+    pub fn get_indices(&self) -> &[u32] {
+        self.indices.as_slice()
+    }
+
+    /// Uploads the triangles in a vertex buffer and an index buffer.
+    pub fn build(&self, display: &Display) -> (VertexBuffer<LineVertex>, IndexBuffer) {
+        let vertex_buffer = VertexBuffer::new(display, self.vertices.clone());
+        let index_buffer = IndexBuffer::new(display, TrianglesList(self.indices.clone()));
+        (vertex_buffer, index_buffer)
+    }
+
+    fn push_vertex(&mut self, position: [f32, ..2]) {
+        self.vertices.push(LineVertex { position: position });
+    }
+
+    /// Adds the rectangle that covers a segment, without its caps.
+    fn add_quad(&mut self, start: [f32, ..2], end: [f32, ..2], direction: [f32, ..2],
+                half_width: f32)
+    {
+        let normal = scale(perpendicular(direction), half_width);
+
+        let first = self.vertices.len() as u32;
+        self.push_vertex(add(start, normal));
+        self.push_vertex(sub(start, normal));
+        self.push_vertex(sub(end, normal));
+        self.push_vertex(add(end, normal));
+        self.indices.push_all(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    /// Adds the cap at the end `point` of a line going towards `direction`.
+    fn add_cap(&mut self, point: [f32, ..2], direction: [f32, ..2], half_width: f32,
+               cap: LineCap)
+    {
+        match cap {
+            LineCap::Butt => (),
+            LineCap::Square => {
+                let extended = add(point, scale(direction, half_width));
+                self.add_quad(point, extended, direction, half_width);
+            },
+            LineCap::Round => {
+                let normal = perpendicular(direction);
+                self.add_arc(point, normal, neg(normal), half_width, PI);
+            },
+        }
+    }
+
+    /// Adds the join at `point` between a segment going towards `incoming` and a segment
+    /// going towards `outgoing`.
+    fn add_join(&mut self, point: [f32, ..2], incoming: [f32, ..2], outgoing: [f32, ..2],
+                half_width: f32, join: LineJoin)
+    {
+        let turn = cross(incoming, outgoing);
+        if turn == 0.0 && dot(incoming, outgoing) > 0.0 {
+            return;     // the segments are aligned
+        }
+
+        // the join is on the outer side of the turn
+        let (outer_in, outer_out) = if turn > 0.0 {
+            (neg(perpendicular(incoming)), neg(perpendicular(outgoing)))
+        } else {
+            (perpendicular(incoming), perpendicular(outgoing))
+        };
+
+        match join {
+            LineJoin::None => (),
+            LineJoin::Bevel => self.add_bevel(point, outer_in, outer_out, half_width),
+            LineJoin::Miter { limit } => {
+                // the miter goes along the bisector of the two normals
+                let bisector = normalize(add(outer_in, outer_out));
+                let length = bisector.map(|b| 1.0 / dot(b, outer_in));
+
+                match (bisector, length) {
+                    (Some(bisector), Some(length)) if length <= limit => {
+                        let first = self.vertices.len() as u32;
+                        self.push_vertex(point);
+                        self.push_vertex(add(point, scale(outer_in, half_width)));
+                        self.push_vertex(add(point, scale(bisector, length * half_width)));
+                        self.push_vertex(add(point, scale(outer_out, half_width)));
+                        self.indices.push_all(&[first, first + 1, first + 2,
+                                                first, first + 2, first + 3]);
+                    },
+                    _ => self.add_bevel(point, outer_in, outer_out, half_width),
+                }
+            },
+            LineJoin::Round => {
+                let angle = dot(outer_in, outer_out).max(-1.0).min(1.0).acos();
+                self.add_arc(point, outer_in, outer_out, half_width, angle);
+            },
+        }
+    }
+
+    fn add_bevel(&mut self, point: [f32, ..2], outer_in: [f32, ..2], outer_out: [f32, ..2],
+                 half_width: f32)
+    {
+        let first = self.vertices.len() as u32;
+        self.push_vertex(point);
+        self.push_vertex(add(point, scale(outer_in, half_width)));
+        self.push_vertex(add(point, scale(outer_out, half_width)));
+        self.indices.push_all(&[first, first + 1, first + 2]);
+    }
+
+    /// Adds a fan of triangles around `center`, going from the unit vector `from` to the unit
+    /// vector `to` over `angle` radians.
+    fn add_arc(&mut self, center: [f32, ..2], from: [f32, ..2], to: [f32, ..2], radius: f32,
+               angle: f32)
+    {
+        // the arc goes through the shortest way, except for half circles where `from` and
+        // `to` are opposite and the arc goes clockwise
+        let sign = if cross(from, to) > 0.0 { 1.0 } else { -1.0 };
+        let steps = max((angle / PI * ROUND_SEGMENTS as f32).ceil() as uint, 1);
+
+        let first = self.vertices.len() as u32;
+        self.push_vertex(center);
+
+        for i in range(0, steps + 1) {
+            let a = sign * angle * i as f32 / steps as f32;
+            let rotated = [from[0] * a.cos() - from[1] * a.sin(),
+                           from[0] * a.sin() + from[1] * a.cos()];
+            self.push_vertex(add(center, scale(rotated, radius)));
+        }
+
+        for i in range(0, steps as u32) {
+            self.indices.push_all(&[first, first + 1 + i, first + 2 + i]);
+        }
+    }
+}
+
+fn add(a: [f32, ..2], b: [f32, ..2]) -> [f32, ..2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32, ..2], b: [f32, ..2]) -> [f32, ..2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn neg(a: [f32, ..2]) -> [f32, ..2] {
+    [-a[0], -a[1]]
+}
+
+fn scale(a: [f32, ..2], s: f32) -> [f32, ..2] {
+    [a[0] * s, a[1] * s]
+}
+
+fn dot(a: [f32, ..2], b: [f32, ..2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f32, ..2], b: [f32, ..2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// Rotates a vector by 90 degrees counter-clockwise.
+fn perpendicular(a: [f32, ..2]) -> [f32, ..2] {
+    [-a[1], a[0]]
+}
+
+fn normalize(a: [f32, ..2]) -> Option<[f32, ..2]> {
+    let length = dot(a, a).sqrt();
+    if length == 0.0 {
+        None
+    } else {
+        Some(scale(a, 1.0 / length))
+    }
+}
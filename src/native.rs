@@ -0,0 +1,416 @@
+/*!
+Native handles of the OpenGL context of a display.
+
+Some platform APIs, like VAAPI and VDPAU on Linux or DXVA on Windows, and some libraries
+need the native objects that are behind the OpenGL context in order to interoperate with it.
+Use `get_handles` to obtain them.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+match glium::native::get_handles(&display) {
+    Some(glium::native::NativeHandles::Glx { display, context, .. }) => {
+        // pass the X11 display and the GLX context to another library
+    },
+    _ => ()
+}
+```
+
+The context is owned by the rendering thread of glium, where it is current. You must not make
+it current on another thread or destroy any of these objects. The handles are valid as long as
+the `Display` is alive.
+
+This module also gives access to the age of the back buffer with `get_buffer_age`, which
+allows redrawing only the areas that have changed since the back buffer was last presented.
+
+The platform libraries (`libGL` and `libX11`, `libEGL`, `opengl32` or the `OpenGL` framework)
+are only linked when the `native_handles` feature is enabled. Without it, the functions of
+this module behave as if the backend didn't support them.
+
+*/
+use Display;
+use libc;
+
+/// Native handles of an OpenGL context.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum NativeHandles {
+    /// A GLX context, used on Linux and the BSDs.
+    Glx {
+        /// The X11 `Display*`.
+        display: *mut libc::c_void,
+        /// The `GLXContext`.
+        context: *mut libc::c_void,
+        /// The `GLXDrawable` of the window.
+        drawable: libc::c_ulong,
+    },
+
+    /// A WGL context, used on Windows.
+    Wgl {
+        /// The `HDC` of the window.
+        device_context: *mut libc::c_void,
+        /// The `HGLRC`.
+        context: *mut libc::c_void,
+    },
+
+    /// An EGL context, used on Android.
+    Egl {
+        /// The `EGLDisplay`.
+        display: *mut libc::c_void,
+        /// The `EGLContext`.
+        context: *mut libc::c_void,
+        /// The `EGLSurface` of the window.
+        surface: *mut libc::c_void,
+    },
+
+    /// A CGL context, used on OS X.
+    Cgl {
+        /// The `CGLContextObj`.
+        context: *mut libc::c_void,
+    },
+}
+
+/// Returns the native handles of the OpenGL context of a display.
+///
+/// Returns `None` if the context doesn't have native handles, for example with a headless
+/// renderer.
+pub fn get_handles(display: &Display) -> Option<NativeHandles> {
+    // raw pointers can't be sent between threads, so the handles are sent as integers
+    let (tx, rx) = channel();
+    display.context.context.exec(move |: _| {
+        tx.send(platform::get_current());
+    });
+
+    rx.recv().map(platform::from_raw)
+}
+
+/// Returns the age of the back buffer of a display, in frames.
+///
+/// The age is the number of frames since the content of the back buffer was presented. For
+/// example with an age of `2`, the back buffer contains the frame before the previous one, and
+/// only the areas that have changed during the last two frames need to be redrawn. An age of
+/// `0` means that the content of the back buffer is undefined.
+///
+/// Returns `None` if the backend doesn't support `GLX_EXT_buffer_age` or
+/// `EGL_EXT_buffer_age`.
+pub fn get_buffer_age(display: &Display) -> Option<u32> {
+    let (tx, rx) = channel();
+    display.context.context.exec(move |: _| {
+        tx.send(platform::get_buffer_age());
+    });
+
+    rx.recv()
+}
+
+/// Returns true if the content of the back buffer of a display is preserved when the buffers
+/// are swapped.
+///
+/// In this situation the back buffer always contains the previous frame, as if its age was
+/// `1`. Returns `None` if the backend can't tell.
+pub fn is_swap_preserved(display: &Display) -> Option<bool> {
+    let (tx, rx) = channel();
+    display.context.context.exec(move |: _| {
+        tx.send(platform::is_swap_preserved());
+    });
+
+    rx.recv()
+}
+
+/// Returns true if `extension` is in the list of extensions `list`.
+#[cfg(feature = "native_handles")]
+unsafe fn has_extension(list: *const libc::c_char, extension: &str) -> bool {
+    use std::c_str::CString;
+
+    if list.is_null() {
+        return false;
+    }
+
+    let list = CString::new(list, false);
+    list.as_str().map(|l| l.words().any(|e| e == extension)).unwrap_or(false)
+}
+
+#[cfg(all(feature = "native_handles", unix, not(target_os = "android"),
+          not(target_os = "macos")))]
+mod platform {
+    use libc;
+    use super::NativeHandles;
+
+    const GLX_SCREEN: libc::c_int = 0x800C;
+    const GLX_FBCONFIG_ID: libc::c_int = 0x8013;
+    const GLX_BACK_BUFFER_AGE_EXT: libc::c_int = 0x20F4;
+    const GLX_SWAP_METHOD_OML: libc::c_int = 0x8060;
+    const GLX_SWAP_COPY_OML: libc::c_int = 0x8062;
+
+    #[link(name = "GL")]
+    extern {
+        fn glXGetCurrentDisplay() -> *mut libc::c_void;
+        fn glXGetCurrentContext() -> *mut libc::c_void;
+        fn glXGetCurrentDrawable() -> libc::c_ulong;
+        fn glXQueryContext(dpy: *mut libc::c_void, ctx: *mut libc::c_void,
+                           attribute: libc::c_int, value: *mut libc::c_int) -> libc::c_int;
+        fn glXQueryExtensionsString(dpy: *mut libc::c_void, screen: libc::c_int)
+                                    -> *const libc::c_char;
+        fn glXQueryDrawable(dpy: *mut libc::c_void, draw: libc::c_ulong,
+                            attribute: libc::c_int, value: *mut libc::c_uint);
+        fn glXChooseFBConfig(dpy: *mut libc::c_void, screen: libc::c_int,
+                             attrib_list: *const libc::c_int, nelements: *mut libc::c_int)
+                             -> *mut *mut libc::c_void;
+        fn glXGetFBConfigAttrib(dpy: *mut libc::c_void, config: *mut libc::c_void,
+                                attribute: libc::c_int, value: *mut libc::c_int)
+                                -> libc::c_int;
+    }
+
+    #[link(name = "X11")]
+    extern {
+        fn XFree(data: *mut libc::c_void) -> libc::c_int;
+    }
+
+    pub fn get_current() -> Option<[uint, ..3]> {
+        unsafe {
+            let context = glXGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            Some([glXGetCurrentDisplay() as uint, context as uint,
+                  glXGetCurrentDrawable() as uint])
+        }
+    }
+
+    pub fn from_raw(raw: [uint, ..3]) -> NativeHandles {
+        NativeHandles::Glx {
+            display: raw[0] as *mut libc::c_void,
+            context: raw[1] as *mut libc::c_void,
+            drawable: raw[2] as libc::c_ulong,
+        }
+    }
+
+    pub fn get_buffer_age() -> Option<u32> {
+        unsafe {
+            let context = glXGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            let display = glXGetCurrentDisplay();
+
+            let mut screen = 0;
+            glXQueryContext(display, context, GLX_SCREEN, &mut screen);
+            if !super::has_extension(glXQueryExtensionsString(display, screen),
+                                     "GLX_EXT_buffer_age")
+            {
+                return None;
+            }
+
+            let mut age = 0;
+            glXQueryDrawable(display, glXGetCurrentDrawable(), GLX_BACK_BUFFER_AGE_EXT,
+                             &mut age);
+            Some(age as u32)
+        }
+    }
+
+    pub fn is_swap_preserved() -> Option<bool> {
+        unsafe {
+            let context = glXGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            let display = glXGetCurrentDisplay();
+
+            let mut screen = 0;
+            let mut config_id = 0;
+            glXQueryContext(display, context, GLX_SCREEN, &mut screen);
+            glXQueryContext(display, context, GLX_FBCONFIG_ID, &mut config_id);
+
+            let attributes = [GLX_FBCONFIG_ID, config_id, 0];
+            let mut count = 0;
+            let configs = glXChooseFBConfig(display, screen, attributes.as_ptr(), &mut count);
+            if configs.is_null() {
+                return None;
+            }
+
+            // the swap method is only known with `GLX_OML_swap_method`
+            let mut method = 0;
+            let result = if count >= 1 &&
+                            glXGetFBConfigAttrib(display, *configs, GLX_SWAP_METHOD_OML,
+                                                 &mut method) == 0
+            {
+                Some(method == GLX_SWAP_COPY_OML)
+            } else {
+                None
+            };
+
+            XFree(configs as *mut libc::c_void);
+            result
+        }
+    }
+}
+
+#[cfg(all(feature = "native_handles", target_os = "windows"))]
+mod platform {
+    use libc;
+    use super::NativeHandles;
+
+    #[link(name = "opengl32")]
+    extern "system" {
+        fn wglGetCurrentDC() -> *mut libc::c_void;
+        fn wglGetCurrentContext() -> *mut libc::c_void;
+    }
+
+    pub fn get_current() -> Option<[uint, ..3]> {
+        unsafe {
+            let context = wglGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            Some([wglGetCurrentDC() as uint, context as uint, 0])
+        }
+    }
+
+    pub fn from_raw(raw: [uint, ..3]) -> NativeHandles {
+        NativeHandles::Wgl {
+            device_context: raw[0] as *mut libc::c_void,
+            context: raw[1] as *mut libc::c_void,
+        }
+    }
+
+    pub fn get_buffer_age() -> Option<u32> {
+        None
+    }
+
+    pub fn is_swap_preserved() -> Option<bool> {
+        None
+    }
+}
+
+#[cfg(all(feature = "native_handles", target_os = "android"))]
+mod platform {
+    use libc;
+    use super::NativeHandles;
+
+    const EGL_DRAW: libc::c_int = 0x3059;
+    const EGL_EXTENSIONS: libc::c_int = 0x3055;
+    const EGL_BUFFER_AGE_EXT: libc::c_int = 0x313D;
+    const EGL_SWAP_BEHAVIOR: libc::c_int = 0x3093;
+    const EGL_BUFFER_PRESERVED: libc::c_int = 0x3094;
+
+    #[link(name = "EGL")]
+    extern {
+        fn eglGetCurrentDisplay() -> *mut libc::c_void;
+        fn eglGetCurrentContext() -> *mut libc::c_void;
+        fn eglGetCurrentSurface(readdraw: libc::c_int) -> *mut libc::c_void;
+        fn eglQueryString(dpy: *mut libc::c_void, name: libc::c_int) -> *const libc::c_char;
+        fn eglQuerySurface(dpy: *mut libc::c_void, surface: *mut libc::c_void,
+                           attribute: libc::c_int, value: *mut libc::c_int) -> libc::c_uint;
+    }
+
+    pub fn get_current() -> Option<[uint, ..3]> {
+        unsafe {
+            let context = eglGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            Some([eglGetCurrentDisplay() as uint, context as uint,
+                  eglGetCurrentSurface(EGL_DRAW) as uint])
+        }
+    }
+
+    pub fn from_raw(raw: [uint, ..3]) -> NativeHandles {
+        NativeHandles::Egl {
+            display: raw[0] as *mut libc::c_void,
+            context: raw[1] as *mut libc::c_void,
+            surface: raw[2] as *mut libc::c_void,
+        }
+    }
+
+    pub fn get_buffer_age() -> Option<u32> {
+        unsafe {
+            let display = eglGetCurrentDisplay();
+            if !super::has_extension(eglQueryString(display, EGL_EXTENSIONS),
+                                     "EGL_EXT_buffer_age")
+            {
+                return None;
+            }
+
+            let mut age = 0;
+            if eglQuerySurface(display, eglGetCurrentSurface(EGL_DRAW), EGL_BUFFER_AGE_EXT,
+                               &mut age) == 0
+            {
+                return None;
+            }
+
+            Some(age as u32)
+        }
+    }
+
+    pub fn is_swap_preserved() -> Option<bool> {
+        unsafe {
+            let mut behavior = 0;
+            if eglQuerySurface(eglGetCurrentDisplay(), eglGetCurrentSurface(EGL_DRAW),
+                               EGL_SWAP_BEHAVIOR, &mut behavior) == 0
+            {
+                return None;
+            }
+
+            Some(behavior == EGL_BUFFER_PRESERVED)
+        }
+    }
+}
+
+#[cfg(all(feature = "native_handles", target_os = "macos"))]
+mod platform {
+    use libc;
+    use super::NativeHandles;
+
+    #[link(name = "OpenGL", kind = "framework")]
+    extern {
+        fn CGLGetCurrentContext() -> *mut libc::c_void;
+    }
+
+    pub fn get_current() -> Option<[uint, ..3]> {
+        unsafe {
+            let context = CGLGetCurrentContext();
+            if context.is_null() {
+                return None;
+            }
+
+            Some([context as uint, 0, 0])
+        }
+    }
+
+    pub fn from_raw(raw: [uint, ..3]) -> NativeHandles {
+        NativeHandles::Cgl {
+            context: raw[0] as *mut libc::c_void,
+        }
+    }
+
+    pub fn get_buffer_age() -> Option<u32> {
+        None
+    }
+
+    pub fn is_swap_preserved() -> Option<bool> {
+        None
+    }
+}
+
+#[cfg(not(feature = "native_handles"))]
+mod platform {
+    use super::NativeHandles;
+
+    pub fn get_current() -> Option<[uint, ..3]> {
+        None
+    }
+
+    pub fn from_raw(_: [uint, ..3]) -> NativeHandles {
+        unreachable!()
+    }
+
+    pub fn get_buffer_age() -> Option<u32> {
+        None
+    }
+
+    pub fn is_swap_preserved() -> Option<bool> {
+        None
+    }
+}
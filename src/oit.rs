@@ -0,0 +1,296 @@
+/*!
+Weighted blended order-independent transparency.
+
+Transparent objects usually have to be sorted from back to front before being drawn, which
+is expensive and isn't always possible when objects intersect. Weighted blended
+order-independent transparency approximates the result of the sorted composition without
+any sorting: the transparent objects are drawn in any order to two intermediate textures,
+then a composite pass merges these textures with the opaque scene.
+
+Order-independent transparency with per-pixel linked lists is not provided. It needs atomic
+counter buffers and shader storage buffers to be bound to programs, which glium doesn't
+support. Use `is_supported` to know whether the weighted blended technique is available.
+
+The fragment shaders of the transparent objects must write to two outputs named
+`oit_accumulation` and `oit_weights`. For a color `color` with an alpha `a`:
+
+```notrust
+out vec4 oit_accumulation;
+out vec4 oit_weights;
+
+void main() {
+    // any positive function of the depth works, as long as nearer fragments have
+    // a larger weight
+    float w = clamp(pow(min(1.0, a * 10.0) + 0.01, 3.0) * 1e8 *
+                    pow(1.0 - gl_FragCoord.z * 0.9, 3.0), 1e-2, 3e3);
+
+    oit_accumulation = vec4(color.rgb * a * w, a);
+    oit_weights = vec4(a * w, 0.0, 0.0, 0.0);
+}
+```
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+# let vertex_buffer: glium::vertex_buffer::VertexBufferAny = unsafe { std::mem::uninitialized() };
+# let index_buffer: glium::IndexBuffer = unsafe { ::std::mem::uninitialized() };
+# let program: glium::Program = unsafe { ::std::mem::uninitialized() };
+# let depth: glium::texture::DepthTexture2d = unsafe { ::std::mem::uninitialized() };
+use glium::oit::OitBuffers;
+
+let mut oit = OitBuffers::new(&display, 1024, 768);
+
+let mut target = display.draw();
+// ... draws the opaque objects to `target` and their depth to `depth` ...
+
+oit.clear();
+oit.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+         &std::default::Default::default(), Some(&depth)).unwrap();
+oit.composite(&mut target).unwrap();
+target.finish().unwrap();
+```
+
+*/
+use std::default::Default;
+
+use {Display, DrawError, DrawParameters, Program, Surface, GlObject};
+use {BlendingFunction, LinearBlendingFactor};
+use context::GlVersion;
+use fbo::{mod, FramebufferAttachments};
+use index_buffer::{mod, IndexBuffer, TrianglesList};
+use ops;
+use texture::{DepthTexture2d, Texture2d, UncompressedFloatFormat};
+use uniforms::{Sampler, SamplerBehavior, SamplerWrapFunction, Uniforms, UniformsStorage};
+use uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+use vertex_buffer::{mod, AttributeType, Vertex, VertexBuffer, VertexBufferAny, VertexFormat};
+
+/// Returns true if the backend supports the features needed by `OitBuffers`.
+///
+/// These features are floating-point render targets and multiple color attachments, which
+/// are available with OpenGL 3.0 and OpenGL ES 3.2.
+pub fn is_supported(display: &Display) -> bool {
+    let capabilities = display.context.context.capabilities();
+
+    if capabilities.opengl_es {
+        capabilities.version >= GlVersion(3, 2)
+    } else {
+        capabilities.version >= GlVersion(3, 0)
+    }
+}
+
+#[deriving(Copy)]
+struct CompositeVertex {
+    position: [f32, ..2],
+}
+
+impl Vertex for CompositeVertex {
+    fn build_bindings(_: Option<CompositeVertex>) -> VertexFormat {
+        vec![("position".to_string(), 0, AttributeType::F32F32, false)]
+    }
+}
+
+/// The intermediate textures of the transparent objects, and the pass that composites them.
+///
+/// Creating an `OitBuffers` compiles a program and allocates two floating-point textures, so
+/// you should create it once and only recreate it when the dimensions of the target change.
+pub struct OitBuffers {
+    display: Display,
+    accumulation: Texture2d,
+    weights: Texture2d,
+    vertex_buffer: VertexBufferAny,
+    index_buffer: IndexBuffer,
+    program: Program,
+}
+
+impl OitBuffers {
+    /// Builds the buffers for a target of the given dimensions.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the backend doesn't support order-independent transparency. See
+    /// `is_supported`.
+    pub fn new(display: &Display, width: u32, height: u32) -> OitBuffers {
+        if !is_supported(display) {
+            panic!("Order-independent transparency is not supported by the backend");
+        }
+
+        let accumulation = Texture2d::new_empty(display, UncompressedFloatFormat::F16F16F16F16,
+                                                width, height);
+        let weights = Texture2d::new_empty(display, UncompressedFloatFormat::F16F16F16F16,
+                                           width, height);
+
+        // a single triangle that covers the whole viewport
+        let vertex_buffer = VertexBuffer::new(display, vec![
+            CompositeVertex { position: [-1.0, -1.0] },
+            CompositeVertex { position: [3.0, -1.0] },
+            CompositeVertex { position: [-1.0, 3.0] },
+        ]).into_vertex_buffer_any();
+
+        let index_buffer = IndexBuffer::new(display, TrianglesList(vec![0u8, 1, 2]));
+
+        let program = Program::from_source(display, VERTEX_SHADER, FRAGMENT_SHADER, None)
+                                           .unwrap();
+
+        let mut buffers = OitBuffers {
+            display: display.clone(),
+            accumulation: accumulation,
+            weights: weights,
+            vertex_buffer: vertex_buffer,
+            index_buffer: index_buffer,
+            program: program,
+        };
+
+        buffers.clear();
+        buffers
+    }
+
+    /// Returns the dimensions of the buffers.
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        (self.accumulation.get_width(), self.accumulation.get_height().unwrap())
+    }
+
+    /// Removes all the transparent objects from the buffers.
+    ///
+    /// You must call this function every frame before drawing the transparent objects.
+    pub fn clear(&mut self) {
+        let attachments = self.build_attachments(None, None);
+        ops::clear_color(&self.display.context, Some(&attachments), 0.0, 0.0, 0.0, 1.0);
+    }
+
+    /// Draws transparent objects to the buffers.
+    ///
+    /// The fragment shader of `program` must write to `oit_accumulation` and `oit_weights`, as
+    /// explained in the module documentation.
+    ///
+    /// `depth` is the depth buffer of the opaque objects. Pass it with a `depth_function`
+    /// that tests against it in order to hide the transparent objects behind the opaque
+    /// ones. The depth buffer is never written to.
+    ///
+    /// The blending functions and `depth_write` of `draw_parameters` are ignored.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `program` doesn't have the `oit_accumulation` and `oit_weights` outputs.
+    pub fn draw<'v, V, I, ID, U>(&mut self, vb: V, ib: &I, program: &Program, uniforms: U,
+                                 draw_parameters: &DrawParameters,
+                                 depth: Option<&DepthTexture2d>) -> Result<(), DrawError>
+                                 where I: index_buffer::ToIndicesSource<ID>, U: Uniforms,
+                                 ID: index_buffer::Index,
+                                 V: vertex_buffer::IntoVerticesSource<'v>
+    {
+        use index_buffer::ToIndicesSource;
+
+        try!(draw_parameters.validate(&self.display));
+
+        if draw_parameters.depth_function.requires_depth_buffer() && depth.is_none() {
+            return Err(DrawError::NoDepthBuffer);
+        }
+
+        let attachments = self.build_attachments(Some(program), depth);
+
+        // the colors and the weights are summed, while the alpha of the accumulation
+        // buffer is the product of `1 - alpha` of all the fragments
+        let draw_parameters = DrawParameters {
+            depth_write: false,
+            blending_function: Some(BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::One,
+            }),
+            blending_alpha_function: Some(BlendingFunction::Addition {
+                source: LinearBlendingFactor::Zero,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            }),
+            attachments_blending: [None, ..::MAX_BLENDING_ATTACHMENTS],
+            .. *draw_parameters
+        };
+
+        ops::draw(&self.display, Some(&attachments), vb.into_vertices_source(),
+                  &ib.to_indices_source(), program, uniforms, &draw_parameters,
+                  self.get_dimensions())
+    }
+
+    /// Blends the transparent objects over the content of `target`.
+    ///
+    /// The buffers are sampled with linear filtering, so `target` doesn't need to have the
+    /// same dimensions as the buffers.
+    pub fn composite<S>(&self, target: &mut S) -> Result<(), DrawError> where S: Surface {
+        let behavior = SamplerBehavior {
+            wrap_function: (SamplerWrapFunction::Clamp, SamplerWrapFunction::Clamp,
+                            SamplerWrapFunction::Clamp),
+            minify_filter: MinifySamplerFilter::Linear,
+            magnify_filter: MagnifySamplerFilter::Linear,
+            .. Default::default()
+        };
+
+        let uniforms = UniformsStorage::new("accumulation", Sampler(&self.accumulation, behavior))
+                                       .add("weights", Sampler(&self.weights, behavior));
+
+        let draw_parameters = DrawParameters {
+            blending_function: Some(BlendingFunction::LerpBySourceAlpha),
+            .. Default::default()
+        };
+
+        target.draw(&self.vertex_buffer, &self.index_buffer, &self.program, &uniforms,
+                    &draw_parameters)
+    }
+
+    fn build_attachments(&self, program: Option<&Program>, depth: Option<&DepthTexture2d>)
+                         -> FramebufferAttachments
+    {
+        let location = |&: name: &str, default: u32| {
+            match program {
+                Some(program) => match program.get_frag_data_location(name) {
+                    Some(l) => l,
+                    None => panic!("The fragment output `{}` was not found in the program", name)
+                },
+                None => default,
+            }
+        };
+
+        let accumulation = fbo::Attachment::Texture(self.accumulation.get_id());
+        let weights = fbo::Attachment::Texture(self.weights.get_id());
+
+        FramebufferAttachments {
+            colors: vec![
+                (location("oit_accumulation", 0), accumulation),
+                (location("oit_weights", 1), weights),
+            ],
+            depth: depth.map(|d| fbo::Attachment::Texture(d.get_id())),
+            stencil: None,
+        }
+    }
+}
+
+static VERTEX_SHADER: &'static str = "
+    #version 110
+
+    attribute vec2 position;
+    varying vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        v_tex_coords = position * 0.5 + 0.5;
+    }
+";
+
+static FRAGMENT_SHADER: &'static str = "
+    #version 110
+
+    uniform sampler2D accumulation;
+    uniform sampler2D weights;
+
+    varying vec2 v_tex_coords;
+
+    void main() {
+        vec4 accum = texture2D(accumulation, v_tex_coords);
+
+        // the alpha of the accumulation buffer is the fraction of the background that
+        // is still visible
+        float revealage = accum.a;
+        if (revealage >= 1.0) {
+            discard;
+        }
+
+        float weight = texture2D(weights, v_tex_coords).r;
+        gl_FragColor = vec4(accum.rgb / max(weight, 0.00001), 1.0 - revealage);
+    }
+";
@@ -1,62 +1,109 @@
+use std::{cmp, mem, ptr};
+use std::num::Int;
 use std::sync::Arc;
+use std::sync::atomic::Relaxed;
 
 use Display;
 
 use fbo::{mod, FramebufferAttachments};
 
-use uniforms::{Uniforms, UniformValue, SamplerBehavior};
-use {DisplayImpl, Program, DrawParameters, Rect, Surface, GlObject, ToGlEnum};
-use index_buffer::IndicesSource;
-use vertex_buffer::VerticesSource;
+use uniforms::{Uniforms, UniformValue, SamplerBehavior, ImageUnitBinding};
+use uniforms::{MinifySamplerFilter, MagnifySamplerFilter};
+use {DisplayImpl, Program, DrawParameters, DrawError, DrawRecord, DrawWarning, Rect};
+use {Surface, GlObject};
+use ToGlEnum;
+use draw_commands::{DrawCommand, DrawCommandsBuffer};
+use index_buffer::{Index, IndexBuffer, IndexType, IndicesSource, PrimitiveType};
+use vertex_buffer::{VerticesSource, VertexFormat};
+use texture::{BufferTextureType, ClientFormat, DepthStencilTextureMode, PixelBuffer, Texture};
+use texture::UncompressedFloatFormat;
 
 use {program, vertex_array_object};
-use {gl, context};
+use {gl, context, libc};
 
 /// Draws everything.
 pub fn draw<'a, I, U>(display: &Display,
     framebuffer: Option<&FramebufferAttachments>, vertex_buffer: VerticesSource,
     indices: &IndicesSource<I>, program: &Program, uniforms: U, draw_parameters: &DrawParameters,
-    dimensions: (u32, u32)) where U: Uniforms, I: ::index_buffer::Index
+    dimensions: (u32, u32)) -> Result<(), DrawError> where U: Uniforms, I: ::index_buffer::Index
 {
-    let fbo_id = fbo::get_framebuffer(&display.context, framebuffer);
+    // the vertex format is checked even if nothing is drawn
+    let vertex_format = vertex_buffer.get_format();
+    if let Err(err) = program.check_vertex_format(&vertex_format) {
+        return Err(DrawError::VertexFormatMismatch(err));
+    }
+
+    if vertex_buffer.has_per_instance() &&
+       !display.context.context.capabilities().instanced_arrays
+    {
+        return Err(DrawError::InstancingNotSupported);
+    }
+
+    // drawing nothing is a no-op, instead of relying on the behavior of the driver
+    if vertex_buffer.get_range().1 == 0 {
+        return Ok(());
+    }
+
+    if indices.get_length() == 0 || is_empty_target(draw_parameters, dimensions) {
+        return Ok(());
+    }
+
+    let vao = vertex_array_object::get_vertex_array_object(&display.context, &vertex_buffer,
+                                                           indices, program);
 
-    let vao_id = vertex_array_object::get_vertex_array_object(&display.context, vertex_buffer.clone(),
-                                                              indices, program);
+    let fbo_id = fbo::get_framebuffer(&display.context, framebuffer);
 
+    // the offset of the indices is passed as a pointer, relative to the start of the
+    // index buffer or to the start of the data in RAM
+    let indices_offset = indices.get_offset() * indices.get_indices_type().get_size();
     let pointer = ::std::ptr::Unique(match indices {
-        &IndicesSource::IndexBuffer { .. } => ::std::ptr::null_mut(),
-        &IndicesSource::Buffer { ref pointer, .. } => pointer.as_ptr() as *mut ::libc::c_void,
+        &IndicesSource::IndexBuffer { .. } => indices_offset as *mut ::libc::c_void,
+        &IndicesSource::Buffer { ref pointer, .. } => {
+            (pointer.as_ptr() as uint + indices_offset) as *mut ::libc::c_void
+        },
     });
 
     let primitives = indices.get_primitives_type().to_glenum();
-    let data_type = indices.get_indices_type().to_glenum();
-    assert!(indices.get_offset() == 0); // not yet implemented
+    let indices_type = indices.get_indices_type();
+    let data_type = indices_type.to_glenum();
     let indices_count = indices.get_length();
 
-    // building the list of uniforms binders
-    let uniforms: Vec<Box<Fn(&mut context::CommandContext) + Send>> = {
-        let uniforms_locations = program::get_uniforms_locations(program);
-        let mut active_texture = 0;
+    let (vertex_buffers, base_vertex, vertices_count) = vertex_buffer.into_bindings();
 
-        let mut uniforms_storage = Vec::new();
-        uniforms.visit_values(|&mut: name, value| {
-            if let Some(uniform) = uniforms_locations.get(name) {
-                // TODO: check uniform types
-                let binder = uniform_to_binder(display, *value, uniform.location, &mut active_texture);
-                uniforms_storage.push(binder);
-            }
-        });
+    // one instance is drawn for each element of the smallest per-instance buffer
+    let instances_count = vertex_buffers.iter().filter(|b| b.per_instance)
+                                        .map(|b| b.length).min();
 
-        uniforms_storage
-    };
-    // TODO: panick if uniforms of the program are not found in the parameter
+    if instances_count == Some(0) {
+        return Ok(());
+    }
+
+    if display.context.validate_indices.load(Relaxed) {
+        try!(validate_indices(indices, vertices_count, draw_parameters.primitive_restart_index));
+    }
 
-    let draw_parameters = draw_parameters.clone();
+    if base_vertex != 0 && !display.context.context.capabilities().draw_elements_base_vertex {
+        return Err(DrawError::BaseVertexNotSupported);
+    }
+
+    // building the list of uniforms binders
+    let mut uniforms_names = Vec::new();
+    let uniforms = try!(build_uniforms_binders(display, program, uniforms,
+                                               Some(&mut uniforms_names)));
 
-    let VerticesSource::VertexBuffer(vertex_buffer) = vertex_buffer;
-    let vb_id = vertex_buffer.get_id();
+    let (draw_parameters, condition) = draw_parameters.split_condition();
+
+    let vb_id = vertex_buffers[0].buffer.get_id();
     let program_id = program.get_id();
 
+    {
+        let primitives_type = indices.get_primitives_type();
+        let primitives_count = primitives_type.get_primitives_count(indices_count) *
+                               instances_count.unwrap_or(1);
+        record_draw(display, framebuffer, Some(primitives_type), Some(primitives_count),
+                    program, uniforms_names.as_slice(), &vertex_format);
+    }
+
     display.context.context.exec(move |: mut ctxt| {
         unsafe {
             fbo::bind_framebuffer(&mut ctxt, fbo_id, true, false);
@@ -65,6 +112,7 @@ pub fn draw<'a, I, U>(display: &Display,
             if ctxt.state.program != program_id {
                 ctxt.gl.UseProgram(program_id);
                 ctxt.state.program = program_id;
+                ctxt.state.statistics.state_changes += 1;
             }
 
             // binding program uniforms
@@ -73,24 +121,577 @@ pub fn draw<'a, I, U>(display: &Display,
             }
 
             // binding VAO
-            if ctxt.state.vertex_array != vao_id {
-                ctxt.gl.BindVertexArray(vao_id);
-                ctxt.state.vertex_array = vao_id;
-            }
+            vao.bind(&mut ctxt);
 
             // binding vertex buffer
             if ctxt.state.array_buffer_binding != vb_id {
                 ctxt.gl.BindBuffer(gl::ARRAY_BUFFER, vb_id);
                 ctxt.state.array_buffer_binding = vb_id;
+                ctxt.state.statistics.state_changes += 1;
             }
 
             // sync-ing parameters
             draw_parameters.sync(&mut ctxt, dimensions);
+            sync_primitive_restart(&mut ctxt, draw_parameters.primitive_restart_index,
+                                   indices_type);
+
+            if let Some((query, wait)) = condition {
+                ctxt.gl.BeginConditionalRender(query, wait);
+            }
 
             // drawing
-            ctxt.gl.DrawElements(primitives, indices_count as i32, data_type, pointer.0);
+            match (instances_count, base_vertex) {
+                (Some(instances), 0) => {
+                    ctxt.gl.DrawElementsInstanced(primitives, indices_count as i32, data_type,
+                                                  pointer.0, instances as gl::types::GLsizei);
+                },
+                (Some(instances), base_vertex) => {
+                    ctxt.gl.DrawElementsInstancedBaseVertex(primitives, indices_count as i32,
+                                                            data_type, pointer.0,
+                                                            instances as gl::types::GLsizei,
+                                                            base_vertex as gl::types::GLint);
+                },
+                (None, 0) => {
+                    ctxt.gl.DrawElements(primitives, indices_count as i32, data_type,
+                                         pointer.0);
+                },
+                (None, base_vertex) => {
+                    ctxt.gl.DrawElementsBaseVertex(primitives, indices_count as i32,
+                                                   data_type, pointer.0,
+                                                   base_vertex as gl::types::GLint);
+                },
+            }
+
+            if condition.is_some() {
+                ctxt.gl.EndConditionalRender();
+            }
+
+            ctxt.state.statistics.draw_calls += 1;
+        }
+    });
+
+    Ok(())
+}
+
+
+/// Returns true if nothing can be drawn on the target, either because the surface, the viewport
+/// or the scissor box has a width or a height of zero.
+fn is_empty_target(draw_parameters: &DrawParameters, dimensions: (u32, u32)) -> bool {
+    if dimensions.0 == 0 || dimensions.1 == 0 {
+        return true;
+    }
+
+    let is_empty = |&: rect: &Option<Rect>| match rect {
+        &Some(ref rect) => rect.width == 0 || rect.height == 0,
+        &None => false,
+    };
+
+    is_empty(&draw_parameters.viewport) || is_empty(&draw_parameters.scissor)
+}
+
+/// Returns the index that restarts the primitive when primitive restart is enabled.
+fn get_primitive_restart_index(ty: IndexType) -> u32 {
+    match ty {
+        IndexType::U8 => 0xff,
+        IndexType::U16 => 0xffff,
+        IndexType::U32 => 0xffffffff,
+    }
+}
+
+/// Enables or disables primitive restart for indices of the given type.
+unsafe fn sync_primitive_restart(ctxt: &mut context::CommandContext, enabled: bool,
+                                 indices_type: IndexType)
+{
+    // the fixed index always corresponds to the type of the indices being drawn
+    if ctxt.capabilities.primitive_restart_fixed_index {
+        if ctxt.state.enabled_primitive_restart_fixed_index != enabled {
+            if enabled {
+                ctxt.gl.Enable(gl::PRIMITIVE_RESTART_FIXED_INDEX);
+            } else {
+                ctxt.gl.Disable(gl::PRIMITIVE_RESTART_FIXED_INDEX);
+            }
+
+            ctxt.state.enabled_primitive_restart_fixed_index = enabled;
+            ctxt.state.statistics.state_changes += 1;
+        }
+
+        return;
+    }
+
+    if !ctxt.capabilities.primitive_restart {
+        return;
+    }
+
+    if enabled {
+        let index = get_primitive_restart_index(indices_type);
+        if ctxt.state.primitive_restart_index != index {
+            ctxt.gl.PrimitiveRestartIndex(index);
+            ctxt.state.primitive_restart_index = index;
+            ctxt.state.statistics.state_changes += 1;
+        }
+    }
+
+    if ctxt.state.enabled_primitive_restart != enabled {
+        if enabled {
+            ctxt.gl.Enable(gl::PRIMITIVE_RESTART);
+        } else {
+            ctxt.gl.Disable(gl::PRIMITIVE_RESTART);
+        }
+
+        ctxt.state.enabled_primitive_restart = enabled;
+        ctxt.state.statistics.state_changes += 1;
+    }
+}
+
+/// Returns an error if one of the indices is outside of the vertex source.
+///
+/// If `primitive_restart` is true, the restart index is not checked.
+fn validate_indices<I>(indices: &IndicesSource<I>, vertices_count: uint, primitive_restart: bool)
+                       -> Result<(), DrawError> where I: Index
+{
+    let restart_index = get_primitive_restart_index(indices.get_indices_type());
+
+    let check = |&: position: uint, index: u32| {
+        if primitive_restart && index == restart_index {
+            Ok(())
+        } else if index as uint >= vertices_count {
+            Err(DrawError::IndexOutOfRange {
+                position: position,
+                index: index,
+                vertices_count: vertices_count,
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    match indices {
+        &IndicesSource::Buffer { pointer, offset, length, .. } => {
+            for (i, index) in pointer.slice(offset, offset + length).iter().enumerate() {
+                try!(check(offset + i, index.to_u32()));
+            }
+        },
+
+        &IndicesSource::IndexBuffer { buffer, offset, length } => {
+            let data = read_index_buffer(buffer, offset, length);
+            for (i, index) in data.into_iter().enumerate() {
+                try!(check(offset + i, index));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "gl_extensions")]
+fn read_index_buffer(buffer: &IndexBuffer, offset: uint, length: uint) -> Vec<u32> {
+    buffer.read_indices(offset, length)
+}
+
+// index buffers can't be read back without `gl_extensions`
+#[cfg(not(feature = "gl_extensions"))]
+fn read_index_buffer(_: &IndexBuffer, _: uint, _: uint) -> Vec<u32> {
+    Vec::new()
+}
+
+/// Draws with a program that contains a mesh shader.
+#[cfg(feature = "nv_mesh_shader")]
+pub fn draw_mesh_tasks<S, U>(surface: &S, first: u32, count: u32, program: &Program,
+                             uniforms: U, draw_parameters: &DrawParameters)
+                             -> Result<(), DrawError> where S: Surface, U: Uniforms
+{
+    let ::BlitHelper(display, framebuffer) = surface.get_blit_helper();
+    let display = Display { context: display.clone() };
+
+    if !display.context.context.capabilities().mesh_shaders {
+        return Err(DrawError::MeshShadersNotSupported);
+    }
+
+    try!(draw_parameters.validate_for_surface(&display, surface));
+
+    let fbo_id = fbo::get_framebuffer(&display.context, framebuffer);
+
+    let mut uniforms_names = Vec::new();
+    let uniforms = try!(build_uniforms_binders(&display, program, uniforms,
+                                               Some(&mut uniforms_names)));
+    let (draw_parameters, condition) = draw_parameters.split_condition();
+
+    // the primitives are generated by the mesh shader, and there is no vertex source
+    record_draw(&display, framebuffer, None, None, program, uniforms_names.as_slice(),
+                &Vec::new());
+    let program_id = program.get_id();
+    let dimensions = surface.get_dimensions();
+    let dimensions = (dimensions.0 as u32, dimensions.1 as u32);
+
+    display.context.context.exec(move |: mut ctxt| {
+        unsafe {
+            fbo::bind_framebuffer(&mut ctxt, fbo_id, true, false);
+
+            if ctxt.state.program != program_id {
+                ctxt.gl.UseProgram(program_id);
+                ctxt.state.program = program_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            for binder in uniforms.into_iter() {
+                binder.call((&mut ctxt,));
+            }
+
+            draw_parameters.sync(&mut ctxt, dimensions);
+
+            if let Some((query, wait)) = condition {
+                ctxt.gl.BeginConditionalRender(query, wait);
+            }
+
+            ctxt.gl.DrawMeshTasksNV(first, count);
+            ctxt.state.statistics.draw_calls += 1;
+
+            if condition.is_some() {
+                ctxt.gl.EndConditionalRender();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Draws by reading the commands stored in a buffer.
+///
+/// If `indices` is an `IndicesSource::Buffer`, its content is ignored and the commands are
+/// executed without an index buffer.
+pub fn draw_indirect<S, U, C>(surface: &S, vertex_buffer: VerticesSource,
+                              indices: &IndicesSource<u16>, commands: &DrawCommandsBuffer<C>,
+                              program: &Program, uniforms: U, draw_parameters: &DrawParameters)
+                              -> Result<(), DrawError>
+                              where S: Surface, U: Uniforms, C: DrawCommand
+{
+    let ::BlitHelper(display, framebuffer) = surface.get_blit_helper();
+    let display = Display { context: display.clone() };
+
+    if !display.context.context.capabilities().draw_indirect {
+        return Err(DrawError::DrawIndirectNotSupported);
+    }
+
+    try!(draw_parameters.validate_for_surface(&display, surface));
+
+    let vertex_format = vertex_buffer.get_format();
+    if let Err(err) = program.check_vertex_format(&vertex_format) {
+        return Err(DrawError::VertexFormatMismatch(err));
+    }
+
+    if vertex_buffer.has_per_instance() &&
+       !display.context.context.capabilities().instanced_arrays
+    {
+        return Err(DrawError::InstancingNotSupported);
+    }
+
+    let (width, height) = surface.get_dimensions();
+    if commands.len() == 0 || is_empty_target(draw_parameters, (width as u32, height as u32)) {
+        return Ok(());
+    }
+
+    let fbo_id = fbo::get_framebuffer(&display.context, framebuffer);
+
+    let vao = vertex_array_object::get_vertex_array_object(&display.context, &vertex_buffer,
+                                                           indices, program);
+
+    let primitives = indices.get_primitives_type().to_glenum();
+    let indices_type = indices.get_indices_type();
+    let data_type = match indices {
+        &IndicesSource::IndexBuffer { .. } => Some(indices_type.to_glenum()),
+        &IndicesSource::Buffer { .. } => None,
+    };
+
+    let mut uniforms_names = Vec::new();
+    let uniforms = try!(build_uniforms_binders(&display, program, uniforms,
+                                               Some(&mut uniforms_names)));
+    let (draw_parameters, condition) = draw_parameters.split_condition();
+
+    // the number of primitives is in the commands, which are only read by the GPU
+    record_draw(&display, framebuffer, Some(indices.get_primitives_type()), None, program,
+                uniforms_names.as_slice(), &vertex_format);
+
+    let (vertex_buffers, _, _) = vertex_buffer.into_bindings();
+    let vb_id = vertex_buffers[0].buffer.get_id();
+    let program_id = program.get_id();
+    let commands_id = commands.get_id();
+    let commands_count = commands.len();
+    let command_size = mem::size_of::<C>();
+    let multi_draw = display.context.context.capabilities().multi_draw_indirect;
+
+    let dimensions = surface.get_dimensions();
+    let dimensions = (dimensions.0 as u32, dimensions.1 as u32);
+
+    display.context.context.exec(move |: mut ctxt| {
+        unsafe {
+            fbo::bind_framebuffer(&mut ctxt, fbo_id, true, false);
+
+            if ctxt.state.program != program_id {
+                ctxt.gl.UseProgram(program_id);
+                ctxt.state.program = program_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            for binder in uniforms.into_iter() {
+                binder.call((&mut ctxt,));
+            }
+
+            vao.bind(&mut ctxt);
+
+            if ctxt.state.array_buffer_binding != vb_id {
+                ctxt.gl.BindBuffer(gl::ARRAY_BUFFER, vb_id);
+                ctxt.state.array_buffer_binding = vb_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            if ctxt.state.draw_indirect_buffer_binding != commands_id {
+                ctxt.gl.BindBuffer(gl::DRAW_INDIRECT_BUFFER, commands_id);
+                ctxt.state.draw_indirect_buffer_binding = commands_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            draw_parameters.sync(&mut ctxt, dimensions);
+
+            if data_type.is_some() {
+                sync_primitive_restart(&mut ctxt, draw_parameters.primitive_restart_index,
+                                       indices_type);
+            }
+
+            if let Some((query, wait)) = condition {
+                ctxt.gl.BeginConditionalRender(query, wait);
+            }
+
+            // executing all the commands at once if possible, or one by one otherwise
+            match (data_type, multi_draw) {
+                (Some(data_type), true) => {
+                    ctxt.gl.MultiDrawElementsIndirect(primitives, data_type, ptr::null(),
+                                                      commands_count as gl::types::GLsizei, 0);
+                    ctxt.state.statistics.draw_calls += 1;
+                },
+                (None, true) => {
+                    ctxt.gl.MultiDrawArraysIndirect(primitives, ptr::null(),
+                                                    commands_count as gl::types::GLsizei, 0);
+                    ctxt.state.statistics.draw_calls += 1;
+                },
+                (Some(data_type), false) => {
+                    for i in range(0, commands_count) {
+                        let offset = (i * command_size) as *const libc::c_void;
+                        ctxt.gl.DrawElementsIndirect(primitives, data_type, offset);
+                    }
+                    ctxt.state.statistics.draw_calls += commands_count;
+                },
+                (None, false) => {
+                    for i in range(0, commands_count) {
+                        let offset = (i * command_size) as *const libc::c_void;
+                        ctxt.gl.DrawArraysIndirect(primitives, offset);
+                    }
+                    ctxt.state.statistics.draw_calls += commands_count;
+                },
+            }
+
+            if condition.is_some() {
+                ctxt.gl.EndConditionalRender();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Executes a compute shader.
+pub fn dispatch_compute<U>(display: &Display, program: &Program, uniforms: U,
+                           x: u32, y: u32, z: u32) -> Result<(), DrawError> where U: Uniforms
+{
+    let max = display.context.context.capabilities().max_compute_work_group_count;
+    if x > max[0] as u32 || y > max[1] as u32 || z > max[2] as u32 {
+        return Err(DrawError::TooManyWorkGroups);
+    }
+
+    let uniforms = try!(build_uniforms_binders(display, program, uniforms, None));
+    let program_id = program.get_id();
+
+    display.context.context.exec(move |: mut ctxt| {
+        unsafe {
+            if ctxt.state.program != program_id {
+                ctxt.gl.UseProgram(program_id);
+                ctxt.state.program = program_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            for binder in uniforms.into_iter() {
+                binder.call((&mut ctxt,));
+            }
+
+            ctxt.gl.DispatchCompute(x, y, z);
+
+            // making sure that the writes are visible to the next commands
+            ctxt.gl.MemoryBarrier(gl::ALL_BARRIER_BITS);
+        }
+    });
+
+    Ok(())
+}
+
+/// Builds the list of functions that bind the uniforms of a program.
+///
+/// Returns an error if the type of a uniform doesn't match the program. If `names` is
+/// `Some`, the names of all the uniforms that have been passed are added to it.
+fn build_uniforms_binders<U>(display: &Display, program: &Program, uniforms: U,
+                             mut names: Option<&mut Vec<String>>)
+                             -> Result<Vec<Box<Fn(&mut context::CommandContext) + Send>>,
+                                       DrawError>
+                             where U: Uniforms
+{
+    let uniforms_locations = program::get_uniforms_locations(program);
+    let mut active_texture = 0;
+    let mut active_image_unit = 0;
+    let mut depth_stencil_modes = Vec::new();
+
+    let mut uniforms_storage = Vec::new();
+    let mut error = None;
+    uniforms.visit_values(|&mut: name, value| {
+        if error.is_some() {
+            return;
+        }
+
+        if let Some(ref mut names) = names {
+            names.push(name.to_string());
+        }
+
+        if let Some(uniform) = uniforms_locations.get(name) {
+            let type_matches = match value {
+                &UniformValue::BufferTexture(ref texture) => {
+                    uniform.ty == match texture.get_texture_type() {
+                        BufferTextureType::Float => gl::SAMPLER_BUFFER,
+                        BufferTextureType::Integral => gl::INT_SAMPLER_BUFFER,
+                        BufferTextureType::Unsigned => gl::UNSIGNED_INT_SAMPLER_BUFFER,
+                    }
+                },
+                value => value.is_usable_with(&program::glenum_to_uniform_type(uniform.ty)),
+            };
+
+            if !type_matches {
+                error = Some(DrawError::UniformTypeMismatch {
+                    name: name.to_string(),
+                    expected: program::glenum_to_uniform_type(uniform.ty),
+                    got: match value {
+                        &UniformValue::Image(_) => None,
+                        value => Some(value.get_type()),
+                    },
+                });
+                return;
+            }
+
+            // the component that is read is a parameter of the texture, so it is the same for
+            // all the uniforms of a draw that use the texture
+            if let &UniformValue::DepthStencilTexture2d(texture, _, mode) = value {
+                if mode == DepthStencilTextureMode::Stencil &&
+                   !display.context.context.capabilities().stencil_texturing
+                {
+                    error = Some(DrawError::StencilTexturingNotSupported);
+                    return;
+                }
+
+                let id = texture.get_id();
+                if depth_stencil_modes.iter().any(|&(i, m)| i == id && m != mode) {
+                    error = Some(DrawError::DepthStencilModeConflict { name: name.to_string() });
+                    return;
+                }
+
+                depth_stencil_modes.push((id, mode));
+            }
+
+            // sampling an incomplete texture silently returns black
+            if cfg!(not(ndebug)) {
+                if let Err(err) = validate_texture_completeness(display, name, value) {
+                    error = Some(err);
+                    return;
+                }
+            }
+
+            // image units are numbered separately from texture units
+            let binder = match value {
+                &UniformValue::Image(binding) => {
+                    match build_image_unit_binder(display, binding, uniform.location,
+                                                  &mut active_image_unit)
+                    {
+                        Ok(binder) => binder,
+                        Err(err) => {
+                            error = Some(err);
+                            return;
+                        }
+                    }
+                },
+                _ => uniform_to_binder(display, *value, uniform.location, &mut active_texture)
+            };
+
+            uniforms_storage.push(binder);
         }
     });
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+
+    Ok(uniforms_storage)
+}
+
+/// Compares the uniforms and the vertex attributes that have been passed to a draw with the
+/// active ones of the program.
+fn get_draw_warnings(program: &Program, uniforms: &[String], bindings: &VertexFormat)
+                     -> Vec<DrawWarning>
+{
+    let uniforms_locations = program::get_uniforms_locations(program);
+    let attributes = program::get_attributes(program);
+
+    let mut warnings = Vec::new();
+
+    for name in uniforms.iter() {
+        if !uniforms_locations.contains_key(name) {
+            warnings.push(DrawWarning::UnusedUniform(name.clone()));
+        }
+    }
+
+    // uniforms without a location are members of uniform blocks or built-in variables
+    let mut missing = uniforms_locations.iter()
+        .filter(|&(name, uniform)| uniform.location != -1 && !name.starts_with("gl_"))
+        .filter(|&(name, _)| !uniforms.contains(name))
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    missing.sort();
+    warnings.extend(missing.into_iter().map(|name| DrawWarning::MissingUniform(name)));
+
+    // attributes that the program needs but that the vertex buffer doesn't provide are
+    // already errors
+    for &(ref name, _, _, _) in bindings.iter() {
+        if !attributes.contains_key(name) {
+            warnings.push(DrawWarning::UnusedAttribute(name.clone()));
+        }
+    }
+
+    warnings
+}
+
+/// Adds a draw to the draw log, if it is enabled.
+///
+/// `primitives` and `primitives_count` are `None` if they are only known by the GPU.
+fn record_draw(display: &Display, framebuffer: Option<&FramebufferAttachments>,
+               primitives: Option<PrimitiveType>, primitives_count: Option<uint>,
+               program: &Program, uniforms: &[String], bindings: &VertexFormat)
+{
+    if let Some(ref mut log) = *display.context.draw_log.lock().unwrap() {
+        log.push(DrawRecord {
+            primitives: primitives,
+            primitives_count: primitives_count,
+            program: program.get_id(),
+            target: framebuffer.map(|f| f.clone()),
+            warnings: if cfg!(not(ndebug)) {
+                get_draw_warnings(program, uniforms, bindings)
+            } else {
+                Vec::new()
+            },
+        });
+    }
 }
 
 pub fn clear_color(display: &Arc<DisplayImpl>, framebuffer: Option<&FramebufferAttachments>,
@@ -112,8 +713,18 @@ pub fn clear_color(display: &Arc<DisplayImpl>, framebuffer: Option<&FramebufferA
             if ctxt.state.clear_color != (red, green, blue, alpha) {
                 ctxt.gl.ClearColor(red, green, blue, alpha);
                 ctxt.state.clear_color = (red, green, blue, alpha);
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            // the color mask also applies to `glClear`
+            if ctxt.state.color_mask != (true, true, true, true) {
+                ctxt.gl.ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                ctxt.state.color_mask = (true, true, true, true);
+                ctxt.state.statistics.state_changes += 1;
             }
 
+            disable_scissor_test(&mut ctxt);
+
             ctxt.gl.Clear(gl::COLOR_BUFFER_BIT);
         }
     });
@@ -130,10 +741,25 @@ pub fn clear_depth(display: &Arc<DisplayImpl>, framebuffer: Option<&FramebufferA
 
         unsafe {
             if ctxt.state.clear_depth != value {
-                ctxt.gl.ClearDepth(value as f64);        // TODO: find out why this needs "as"
+                // `glClearDepth` doesn't exist in OpenGL ES
+                if ctxt.opengl_es {
+                    ctxt.gl.ClearDepthf(value);
+                } else {
+                    ctxt.gl.ClearDepth(value as f64);
+                }
                 ctxt.state.clear_depth = value;
+                ctxt.state.statistics.state_changes += 1;
             }
 
+            // the depth mask also applies to `glClear`
+            if !ctxt.state.depth_mask {
+                ctxt.gl.DepthMask(gl::TRUE);
+                ctxt.state.depth_mask = true;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            disable_scissor_test(&mut ctxt);
+
             ctxt.gl.Clear(gl::DEPTH_BUFFER_BIT);
         }
     });
@@ -152,13 +778,117 @@ pub fn clear_stencil(display: &Arc<DisplayImpl>, framebuffer: Option<&Framebuffe
             if ctxt.state.clear_stencil != value {
                 ctxt.gl.ClearStencil(value);
                 ctxt.state.clear_stencil = value;
+                ctxt.state.statistics.state_changes += 1;
             }
 
+            disable_scissor_test(&mut ctxt);
+
             ctxt.gl.Clear(gl::STENCIL_BUFFER_BIT);
         }
     });
 }
 
+pub fn clear_rect<S: Surface>(surface: &S, rect: &Rect, color: Option<(f32, f32, f32, f32)>,
+    depth: Option<f32>, stencil: Option<int>)
+{
+    if rect.width == 0 || rect.height == 0 {
+        return;
+    }
+
+    let ::BlitHelper(display, framebuffer) = surface.get_blit_helper();
+    let fbo_id = fbo::get_framebuffer(display, framebuffer);
+
+    let scissor = (rect.left as gl::types::GLint, rect.bottom as gl::types::GLint,
+                   rect.width as gl::types::GLsizei, rect.height as gl::types::GLsizei);
+
+    display.context.exec(move |: mut ctxt| {
+        fbo::bind_framebuffer(&mut ctxt, fbo_id, true, false);
+
+        unsafe {
+            let mut mask = 0;
+
+            if let Some((red, green, blue, alpha)) = color {
+                let value = (red as gl::types::GLclampf, green as gl::types::GLclampf,
+                             blue as gl::types::GLclampf, alpha as gl::types::GLclampf);
+                if ctxt.state.clear_color != value {
+                    ctxt.gl.ClearColor(value.0, value.1, value.2, value.3);
+                    ctxt.state.clear_color = value;
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if ctxt.state.color_mask != (true, true, true, true) {
+                    ctxt.gl.ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                    ctxt.state.color_mask = (true, true, true, true);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                mask |= gl::COLOR_BUFFER_BIT;
+            }
+
+            if let Some(value) = depth {
+                let value = value as gl::types::GLclampf;
+                if ctxt.state.clear_depth != value {
+                    if ctxt.opengl_es {
+                        ctxt.gl.ClearDepthf(value);
+                    } else {
+                        ctxt.gl.ClearDepth(value as f64);
+                    }
+                    ctxt.state.clear_depth = value;
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if !ctxt.state.depth_mask {
+                    ctxt.gl.DepthMask(gl::TRUE);
+                    ctxt.state.depth_mask = true;
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                mask |= gl::DEPTH_BUFFER_BIT;
+            }
+
+            if let Some(value) = stencil {
+                let value = value as gl::types::GLint;
+                if ctxt.state.clear_stencil != value {
+                    ctxt.gl.ClearStencil(value);
+                    ctxt.state.clear_stencil = value;
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                mask |= gl::STENCIL_BUFFER_BIT;
+            }
+
+            if mask == 0 {
+                return;
+            }
+
+            // the scissor box is tracked in the state, so the next draw commands restore
+            // their own one
+            if ctxt.state.scissor != scissor {
+                ctxt.gl.Scissor(scissor.0, scissor.1, scissor.2, scissor.3);
+                ctxt.state.scissor = scissor;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            if !ctxt.state.enabled_scissor_test {
+                ctxt.gl.Enable(gl::SCISSOR_TEST);
+                ctxt.state.enabled_scissor_test = true;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            ctxt.gl.Clear(mask);
+        }
+    });
+}
+
+/// Disables the scissor test, which also applies to `glClear` and `glBlitFramebuffer`.
+unsafe fn disable_scissor_test(ctxt: &mut context::CommandContext) {
+    if ctxt.state.enabled_scissor_test {
+        ctxt.gl.Disable(gl::SCISSOR_TEST);
+        ctxt.state.enabled_scissor_test = false;
+        ctxt.state.statistics.state_changes += 1;
+    }
+}
+
 pub fn blit<S1: Surface, S2: Surface>(source: &S1, target: &S2, mask: gl::types::GLbitfield,
     src_rect: &Rect, target_rect: &Rect, filter: gl::types::GLenum)
 {
@@ -171,10 +901,12 @@ pub fn blit<S1: Surface, S2: Surface>(source: &S1, target: &S2, mask: gl::types:
     let source = fbo::get_framebuffer(display, source);
     let target = fbo::get_framebuffer(display, target);
 
-    display.context.exec(move |: ctxt| {
+    display.context.exec(move |: mut ctxt| {
         unsafe {
+            disable_scissor_test(&mut ctxt);
+
             // trying to do a named blit if possible
-            if ctxt.version >= &context::GlVersion(4, 5) {
+            if ctxt.capabilities.direct_state_access {
                 ctxt.gl.BlitNamedFramebuffer(source.unwrap_or(0), target.unwrap_or(0),
                     src_rect.left as gl::types::GLint,
                     src_rect.bottom as gl::types::GLint,
@@ -192,10 +924,12 @@ pub fn blit<S1: Surface, S2: Surface>(source: &S1, target: &S2, mask: gl::types:
                 if ctxt.version >= &context::GlVersion(3, 0) {
                     ctxt.gl.BindFramebuffer(gl::READ_FRAMEBUFFER, source.unwrap_or(0));
                     ctxt.state.read_framebuffer = source.unwrap_or(0);
+                    ctxt.state.statistics.state_changes += 1;
 
                 } else {
                     ctxt.gl.BindFramebufferEXT(gl::READ_FRAMEBUFFER_EXT, source.unwrap_or(0));
                     ctxt.state.read_framebuffer = source.unwrap_or(0);
+                    ctxt.state.statistics.state_changes += 1;
                 }
             }
 
@@ -204,10 +938,12 @@ pub fn blit<S1: Surface, S2: Surface>(source: &S1, target: &S2, mask: gl::types:
                 if ctxt.version >= &context::GlVersion(3, 0) {
                     ctxt.gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.unwrap_or(0));
                     ctxt.state.draw_framebuffer = target.unwrap_or(0);
+                    ctxt.state.statistics.state_changes += 1;
 
                 } else {
                     ctxt.gl.BindFramebufferEXT(gl::DRAW_FRAMEBUFFER_EXT, target.unwrap_or(0));
                     ctxt.state.draw_framebuffer = target.unwrap_or(0);
+                    ctxt.state.statistics.state_changes += 1;
                 }
             }
 
@@ -234,6 +970,340 @@ pub fn blit<S1: Surface, S2: Surface>(source: &S1, target: &S2, mask: gl::types:
     });
 }
 
+/// Reads a rectangle of pixels from a surface into a `Vec`.
+///
+/// The number of elements of the returned `Vec` depends on the size of `P`. For example reading
+/// a 2x2 rectangle with the `U8U8U8U8` format returns 16 elements if `P` is `u8` and
+/// 4 elements if `P` is `(u8, u8, u8, u8)`.
+pub fn read<S, P>(surface: &S, rect: &Rect, format: ClientFormat) -> Vec<P>
+                  where S: Surface, P: Send
+{
+    use std::mem;
+
+    let ::BlitHelper(display, source) = surface.get_blit_helper();
+    check_read_rect(surface, rect);
+
+    let total_data_size = rect.width as uint * rect.height as uint * format.get_size();
+    assert!(total_data_size % mem::size_of::<P>() == 0,
+            "The size of the pixels type doesn't match the client format");
+    let elements_count = total_data_size / mem::size_of::<P>();
+
+    let source = fbo::get_framebuffer(display, source);
+    let rect = rect.clone();
+
+    let (tx, rx) = channel();
+    display.context.exec(move |: ctxt| {
+        unsafe {
+            if ctxt.state.pixel_pack_buffer_binding != 0 {
+                ctxt.gl.BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+                ctxt.state.pixel_pack_buffer_binding = 0;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            let mut data: Vec<P> = Vec::with_capacity(elements_count);
+            read_pixels(ctxt, source, &rect, format, data.as_mut_ptr() as *mut libc::c_void);
+            data.set_len(elements_count);
+            ctxt.state.statistics.bytes_transferred += total_data_size;
+            tx.send(data);
+        }
+    });
+
+    rx.recv()
+}
+
+/// Reads a rectangle of pixels from a surface into a newly-created pixel buffer.
+///
+/// The transfer is done asynchronously by the GPU and this function doesn't block.
+pub fn read_to_pixel_buffer<S, P>(surface: &S, rect: &Rect, format: ClientFormat)
+                                  -> PixelBuffer<P> where S: Surface, P: Send
+{
+    use std::mem;
+
+    let ::BlitHelper(display, source) = surface.get_blit_helper();
+    check_read_rect(surface, rect);
+
+    let total_data_size = rect.width as uint * rect.height as uint * format.get_size();
+    assert!(total_data_size % mem::size_of::<P>() == 0,
+            "The size of the pixels type doesn't match the client format");
+
+    let buffer = PixelBuffer::new_empty(&Display { context: display.clone() },
+                                        total_data_size / mem::size_of::<P>());
+    let buffer_id = buffer.get_id();
+
+    let source = fbo::get_framebuffer(display, source);
+    let rect = rect.clone();
+
+    display.context.exec(move |: ctxt| {
+        unsafe {
+            if ctxt.state.pixel_pack_buffer_binding != buffer_id {
+                ctxt.gl.BindBuffer(gl::PIXEL_PACK_BUFFER, buffer_id);
+                ctxt.state.pixel_pack_buffer_binding = buffer_id;
+                ctxt.state.statistics.state_changes += 1;
+            }
+
+            read_pixels(ctxt, source, &rect, format, ::std::ptr::null_mut());
+        }
+    });
+
+    buffer
+}
+
+pub fn get_sample_positions<S>(surface: &S) -> Vec<(f32, f32)> where S: Surface {
+    let ::BlitHelper(display, framebuffer) = surface.get_blit_helper();
+
+    if !display.context.capabilities().texture_multisample {
+        panic!("Querying the positions of samples is not supported by the backend");
+    }
+
+    let framebuffer = fbo::get_framebuffer(display, framebuffer);
+
+    let (tx, rx) = channel();
+    display.context.exec(move |: mut ctxt| {
+        unsafe {
+            fbo::bind_framebuffer(&mut ctxt, framebuffer, true, false);
+
+            let mut samples = mem::uninitialized();
+            ctxt.gl.GetIntegerv(gl::SAMPLES, &mut samples);
+
+            // a surface that isn't multisampled has a single sample in the center of the pixel
+            if samples == 0 {
+                tx.send(vec![(0.5, 0.5)]);
+                return;
+            }
+
+            let positions = range(0, samples).map(|index| {
+                let mut position: [gl::types::GLfloat, ..2] = mem::uninitialized();
+                ctxt.gl.GetMultisamplefv(gl::SAMPLE_POSITION, index as gl::types::GLuint,
+                                         position.as_mut_ptr());
+                (position[0], position[1])
+            }).collect();
+
+            tx.send(positions);
+        }
+    });
+
+    rx.recv()
+}
+
+/// Returns true if the surface is multisampled, and the format of its color buffer if it
+/// matches one of the `UncompressedFloatFormat`s.
+pub fn get_color_format<S>(surface: &S) -> (bool, Option<UncompressedFloatFormat>)
+                           where S: Surface
+{
+    let ::BlitHelper(display, framebuffer) = surface.get_blit_helper();
+    let framebuffer = fbo::get_framebuffer(display, framebuffer);
+
+    let (tx, rx) = channel();
+    display.context.exec(move |: mut ctxt| {
+        unsafe {
+            fbo::bind_framebuffer(&mut ctxt, framebuffer, false, true);
+
+            let mut samples = mem::uninitialized();
+            ctxt.gl.GetIntegerv(gl::SAMPLES, &mut samples);
+
+            // the attachments can only be queried since OpenGL 3.0 and OpenGL ES 3.0
+            if ctxt.version < &context::GlVersion(3, 0) {
+                tx.send((samples != 0, None));
+                return;
+            }
+
+            let attachment = match framebuffer {
+                Some(_) => gl::COLOR_ATTACHMENT0,
+                None if ctxt.opengl_es => gl::BACK,
+                None => gl::BACK_LEFT,
+            };
+
+            let mut values = Vec::new();
+            for &pname in [gl::FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE,
+                           gl::FRAMEBUFFER_ATTACHMENT_RED_SIZE,
+                           gl::FRAMEBUFFER_ATTACHMENT_GREEN_SIZE,
+                           gl::FRAMEBUFFER_ATTACHMENT_BLUE_SIZE,
+                           gl::FRAMEBUFFER_ATTACHMENT_ALPHA_SIZE].iter()
+            {
+                let mut value = mem::uninitialized();
+                ctxt.gl.GetFramebufferAttachmentParameteriv(gl::READ_FRAMEBUFFER, attachment,
+                                                            pname, &mut value);
+                values.push(value);
+            }
+
+            let format = match (values[0] as gl::types::GLenum, values[1], values[2],
+                                values[3], values[4])
+            {
+                (gl::UNSIGNED_NORMALIZED, 8, 0, 0, 0) => Some(UncompressedFloatFormat::U8),
+                (gl::UNSIGNED_NORMALIZED, 8, 8, 0, 0) => Some(UncompressedFloatFormat::U8U8),
+                (gl::UNSIGNED_NORMALIZED, 8, 8, 8, 0) => Some(UncompressedFloatFormat::U8U8U8),
+                (gl::UNSIGNED_NORMALIZED, 8, 8, 8, 8) => {
+                    Some(UncompressedFloatFormat::U8U8U8U8)
+                },
+                (gl::UNSIGNED_NORMALIZED, 4, 4, 4, 4) => {
+                    Some(UncompressedFloatFormat::U4U4U4U4)
+                },
+                (gl::UNSIGNED_NORMALIZED, 5, 5, 5, 1) => {
+                    Some(UncompressedFloatFormat::U5U5U5U1)
+                },
+                (gl::UNSIGNED_NORMALIZED, 10, 10, 10, 2) => {
+                    Some(UncompressedFloatFormat::U10U10U10U2)
+                },
+                (gl::UNSIGNED_NORMALIZED, 16, 16, 16, 16) => {
+                    Some(UncompressedFloatFormat::U16U16U16U16)
+                },
+                (gl::SIGNED_NORMALIZED, 8, 8, 8, 8) => Some(UncompressedFloatFormat::I8I8I8I8),
+                (gl::FLOAT, 16, 0, 0, 0) => Some(UncompressedFloatFormat::F16),
+                (gl::FLOAT, 16, 16, 0, 0) => Some(UncompressedFloatFormat::F16F16),
+                (gl::FLOAT, 16, 16, 16, 0) => Some(UncompressedFloatFormat::F16F16F16),
+                (gl::FLOAT, 16, 16, 16, 16) => Some(UncompressedFloatFormat::F16F16F16F16),
+                (gl::FLOAT, 32, 0, 0, 0) => Some(UncompressedFloatFormat::F32),
+                (gl::FLOAT, 32, 32, 0, 0) => Some(UncompressedFloatFormat::F32F32),
+                (gl::FLOAT, 32, 32, 32, 0) => Some(UncompressedFloatFormat::F32F32F32),
+                (gl::FLOAT, 32, 32, 32, 32) => Some(UncompressedFloatFormat::F32F32F32F32),
+                (gl::FLOAT, 11, 11, 10, 0) => Some(UncompressedFloatFormat::F11F11F10),
+                _ => None
+            };
+
+            tx.send((samples != 0, format));
+        }
+    });
+
+    rx.recv()
+}
+
+/// Panics if the rect is outside of the surface.
+fn check_read_rect<S>(surface: &S, rect: &Rect) where S: Surface {
+    let (width, height) = surface.get_dimensions();
+
+    if !rect.is_inside((width as u32, height as u32)) {
+        panic!("The rectangle to read is outside of the surface");
+    }
+}
+
+/// Calls `glReadPixels` on the given framebuffer. The data is written to the pixel pack buffer
+/// if one is bound, or to `dest` otherwise.
+unsafe fn read_pixels(ctxt: &mut context::CommandContext, source: Option<gl::types::GLuint>,
+                      rect: &Rect, format: ClientFormat, dest: *mut libc::c_void)
+{
+    fbo::bind_framebuffer(ctxt, source, false, true);
+
+    // selecting the buffer to read from
+    match source {
+        Some(_) => {
+            ctxt.gl.ReadBuffer(gl::COLOR_ATTACHMENT0);
+        },
+        None => {
+            if ctxt.state.default_framebuffer_read != Some(gl::BACK_LEFT) {
+                ctxt.gl.ReadBuffer(gl::BACK_LEFT);
+                ctxt.state.default_framebuffer_read = Some(gl::BACK_LEFT);
+                ctxt.state.statistics.state_changes += 1;
+            }
+        },
+    };
+
+    let (format, gltype) = format.to_gl_enum();
+
+    ctxt.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+    ctxt.gl.ReadPixels(rect.left as gl::types::GLint, rect.bottom as gl::types::GLint,
+                       rect.width as gl::types::GLsizei, rect.height as gl::types::GLsizei,
+                       format, gltype, dest);
+}
+
+/// Returns an error if a texture is sampled with a behavior that makes it incomplete, or with
+/// a mipmapped filter while it doesn't have any mipmap.
+///
+/// Textures without a `Sampler` use their own parameters, which are always consistent with
+/// their mipmaps.
+fn validate_texture_completeness(display: &Display, name: &str, value: &UniformValue)
+                                 -> Result<(), DrawError>
+{
+    let (texture, integral, behavior) = match value {
+        &UniformValue::Texture1d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::CompressedTexture1d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::IntegralTexture1d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::UnsignedTexture1d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::Texture2d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::CompressedTexture2d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::IntegralTexture2d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::UnsignedTexture2d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::Texture3d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::CompressedTexture3d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::IntegralTexture3d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::UnsignedTexture3d(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::Texture1dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::CompressedTexture1dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::IntegralTexture1dArray(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::UnsignedTexture1dArray(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::Texture2dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::CompressedTexture2dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::IntegralTexture2dArray(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::UnsignedTexture2dArray(t, Some(s)) => (t as &Texture, true, s),
+        &UniformValue::DepthTexture1d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::DepthTexture2d(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::DepthTexture1dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::DepthTexture2dArray(t, Some(s)) => (t as &Texture, false, s),
+        &UniformValue::DepthStencilTexture2d(t, Some(s), mode) => {
+            (t as &Texture, mode == DepthStencilTextureMode::Stencil, s)
+        },
+        &UniformValue::Cubemap(t, Some(s)) => (t as &Texture, false, s),
+        _ => return Ok(())
+    };
+
+    let mipmapped = match behavior.minify_filter {
+        MinifySamplerFilter::Nearest | MinifySamplerFilter::Linear => false,
+        _ => true,
+    };
+
+    // a texture with all the levels down to 1x1 is always mipmap-complete, in other words
+    // `1 + floor(log2(max(width, height, depth)))` levels
+    let max_dimension = cmp::max(texture.get_width(),
+                                 cmp::max(texture.get_height().unwrap_or(1),
+                                          texture.get_depth().unwrap_or(1)));
+    let complete_levels = 32 - max_dimension.leading_zeros() as u32;
+
+    // `GL_TEXTURE_MAX_LEVEL` is set to the last allocated level, so on desktop any mipmap
+    // besides the main level is enough ; OpenGL ES 2 doesn't have this parameter and needs
+    // the full chain
+    let capabilities = display.context.context.capabilities();
+    let required_levels = if capabilities.opengl_es &&
+                             capabilities.version < context::GlVersion(3, 0)
+    {
+        complete_levels
+    } else {
+        cmp::min(2, complete_levels)
+    };
+
+    if mipmapped && texture.get_mipmap_levels() < required_levels {
+        return Err(DrawError::TextureMipmapsIncomplete { name: name.to_string() });
+    }
+
+    // integral textures can't be filtered
+    if integral && (behavior.magnify_filter != MagnifySamplerFilter::Nearest ||
+                    (behavior.minify_filter != MinifySamplerFilter::Nearest &&
+                     behavior.minify_filter != MinifySamplerFilter::NearestMipmapNearest))
+    {
+        return Err(DrawError::IntegralTextureFiltered { name: name.to_string() });
+    }
+
+    Ok(())
+}
+
+/// Returns the sampler behavior set with `Display::set_default_sampler_behavior`, adjusted so
+/// that textures without mipmaps stay complete.
+fn get_default_sampler_behavior(display: &Display, mipmap_levels: u32)
+                                -> Option<SamplerBehavior>
+{
+    let behavior = *display.context.default_sampler_behavior.lock().unwrap();
+
+    behavior.map(|mut behavior| {
+        if mipmap_levels < 2 {
+            behavior.minify_filter = match behavior.minify_filter {
+                MinifySamplerFilter::Nearest | MinifySamplerFilter::NearestMipmapNearest |
+                MinifySamplerFilter::NearestMipmapLinear => MinifySamplerFilter::Nearest,
+                _ => MinifySamplerFilter::Linear,
+            };
+        }
+
+        behavior
+    })
+}
+
 // TODO: we use a `Fn` instead of `FnOnce` because of that "std::thunk" issue
 fn uniform_to_binder(display: &Display, value: UniformValue, location: gl::types::GLint,
                      active_texture: &mut gl::types::GLenum)
@@ -304,111 +1374,279 @@ fn uniform_to_binder(display: &Display, value: UniformValue, location: gl::types
             }
         },
         UniformValue::Texture1d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::CompressedTexture1d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::IntegralTexture1d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::UnsignedTexture1d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::Texture2d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::CompressedTexture2d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::IntegralTexture2d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::UnsignedTexture2d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::Texture3d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_3D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::CompressedTexture3d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_3D, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::IntegralTexture3d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_3D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::UnsignedTexture3d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_3D, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::Texture1dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::CompressedTexture1dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::IntegralTexture1dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D_ARRAY, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::UnsignedTexture1dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D_ARRAY, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::Texture2dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::CompressedTexture2dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
         },
         UniformValue::IntegralTexture2dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_2D_ARRAY, sampler, levels, true,
+                                 location, active_texture)
         },
         UniformValue::UnsignedTexture2dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_2D_ARRAY, sampler, levels, true,
+                                 location, active_texture)
+        },
+        UniformValue::DepthTexture1d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_1D, sampler, levels, false,
+                                 location, active_texture)
+        },
+        UniformValue::DepthTexture2d(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels, false,
+                                 location, active_texture)
+        },
+        UniformValue::DepthTexture1dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
             let texture = texture.get_id();
-            build_texture_binder(display, texture, sampler, location, active_texture)
+            build_texture_binder(display, texture, gl::TEXTURE_1D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
         },
+        UniformValue::DepthTexture2dArray(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_2D_ARRAY, sampler, levels, false,
+                                 location, active_texture)
+        },
+        UniformValue::DepthStencilTexture2d(texture, sampler, mode) => {
+            // the stencil mode has already been rejected if stencil texturing is not supported
+            let capabilities = display.context.context.capabilities();
+            if !capabilities.stencil_texturing {
+                let levels = texture.get_mipmap_levels();
+                let texture = texture.get_id();
+                return build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels,
+                                            false, location, active_texture);
+            }
+
+            // the stencil component is integral and can't be filtered
+            let sampler = match (sampler, mode) {
+                (None, DepthStencilTextureMode::Stencil) => Some(SamplerBehavior {
+                    minify_filter: MinifySamplerFilter::Nearest,
+                    magnify_filter: MagnifySamplerFilter::Nearest,
+                    .. Default::default()
+                }),
+                (sampler, _) => sampler,
+            };
+
+            let levels = texture.get_mipmap_levels();
+            let texture = texture.get_id();
+            let binder = build_texture_binder(display, texture, gl::TEXTURE_2D, sampler, levels,
+                                              false, location, active_texture);
+
+            // `GL_DEPTH_STENCIL_TEXTURE_MODE` is a parameter of the texture, so it is set
+            // after the texture has been bound
+            let mode = mode.to_glenum();
+            box move |&: ctxt| {
+                binder.call((ctxt,));
+                unsafe {
+                    ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::DEPTH_STENCIL_TEXTURE_MODE,
+                                          mode as gl::types::GLint);
+                }
+            }
+        },
+        UniformValue::Cubemap(texture, sampler) => {
+            let levels = texture.get_mipmap_levels();
+            let sampler = sampler.or_else(|| get_default_sampler_behavior(display, levels));
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_CUBE_MAP, sampler, levels, false,
+                                 location, active_texture)
+        },
+        UniformValue::Texture2dMultisampleArray(texture) => {
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_2D_MULTISAMPLE_ARRAY, None, 1, false,
+                                 location, active_texture)
+        },
+        UniformValue::BufferTexture(texture) => {
+            let texture = texture.get_id();
+            build_texture_binder(display, texture, gl::TEXTURE_BUFFER, None, 1, false,
+                                 location, active_texture)
+        },
+        UniformValue::Image(_) => unreachable!(),
+    }
+}
+
+fn build_image_unit_binder(display: &Display, binding: ImageUnitBinding,
+                           location: gl::types::GLint, active_image_unit: &mut gl::types::GLuint)
+                           -> Result<Box<Fn(&mut context::CommandContext) + Send>, DrawError>
+{
+    let capabilities = display.context.context.capabilities();
+
+    if !capabilities.image_load_store {
+        return Err(DrawError::ImageLoadStoreNotSupported);
     }
+
+    if *active_image_unit >= capabilities.max_image_units as gl::types::GLuint {
+        return Err(DrawError::TooManyImageUnits);
+    }
+
+    let current_unit = *active_image_unit;
+    *active_image_unit += 1;
+
+    let texture = binding.get_texture_id();
+    let layered = if binding.is_layered() { gl::TRUE } else { gl::FALSE };
+    let access = binding.get_access().to_glenum();
+    let format = binding.get_format().to_glenum();
+
+    let binder: Box<Fn(&mut context::CommandContext) + Send> = box move |&: ctxt| {
+        unsafe {
+            ctxt.gl.BindImageTexture(current_unit, texture, 0, layered, 0, access, format);
+            ctxt.gl.Uniform1i(location, current_unit as gl::types::GLint);
+        }
+    };
+
+    Ok(binder)
 }
 
 fn build_texture_binder(display: &Display, texture: gl::types::GLuint,
-                        sampler: Option<SamplerBehavior>, location: gl::types::GLint,
-                        active_texture: &mut gl::types::GLenum)
+                        bind_point: gl::types::GLenum, sampler: Option<SamplerBehavior>,
+                        mipmap_levels: u32, integral: bool,
+                        location: gl::types::GLint, active_texture: &mut gl::types::GLenum)
                         -> Box<Fn(&mut context::CommandContext) + Send>
 {
     assert!(*active_texture < display.context.context.capabilities()
                                      .max_combined_texture_image_units as gl::types::GLenum);
 
-    let sampler = sampler.map(|b| ::uniforms::get_sampler(display, &b));
+    let sampler_objects = display.context.context.capabilities().sampler_objects;
+    let sampler_id = sampler.and_then(|b| ::uniforms::get_sampler(display, &b));
 
     let current_texture = *active_texture;
     *active_texture += 1;
 
     box move |&: ctxt| {
         unsafe {
-            ctxt.gl.ActiveTexture(current_texture + gl::TEXTURE0);
-            ctxt.gl.BindTexture(gl::TEXTURE_2D, texture);      // FIXME: check bind point
+            context::set_active_texture(ctxt, current_texture);
+            context::bind_texture(ctxt, bind_point, texture);
             ctxt.gl.Uniform1i(location, current_texture as gl::types::GLint);
 
-            if let Some(sampler) = sampler {
-                ctxt.gl.BindSampler(current_texture, sampler);
+            if bind_point == gl::TEXTURE_BUFFER {
+                // buffer textures don't have any sampling parameter
+            } else if sampler_objects {
+                context::bind_sampler(ctxt, current_texture, sampler_id.unwrap_or(0));
             } else {
-                ctxt.gl.BindSampler(current_texture, 0);
+                // no sampler objects available, so we modify the texture's parameters instead
+                let behavior = sampler.unwrap_or_else(|| {
+                    ::uniforms::get_texture_default_behavior(mipmap_levels, integral)
+                });
+                ::uniforms::apply_to_texture(ctxt, bind_point, &behavior);
             }
         }
     }
@@ -0,0 +1,294 @@
+/*!
+A final pass that presents a high dynamic range image on a surface.
+
+Scenes are usually rendered in linear space in a floating-point texture. Before being
+displayed, the content of this texture must be scaled by an exposure value, mapped to the
+`[0, 1]` range by a tonemapping operator, and encoded for the display (usually in the sRGB
+color space). This module provides a `PresentPass` that does all this in one draw.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+# let hdr_texture: glium::Texture2d = unsafe { ::std::mem::uninitialized() };
+use glium::present::{PresentPass, PresentParameters, Tonemapping};
+
+let present = PresentPass::new(&display).unwrap();
+
+let mut target = display.draw();
+present.draw(&mut target, &hdr_texture, &PresentParameters {
+    exposure: 1.5,
+    tonemapping: Tonemapping::Reinhard,
+    .. std::default::Default::default()
+}).unwrap();
+target.finish().unwrap();
+```
+
+*/
+use std::default::Default;
+
+use {Display, DrawError, DrawParameters, Program, ProgramCreationError, Surface};
+use context::GlVersion;
+use index_buffer::{IndexBuffer, TrianglesList};
+use texture::Texture2d;
+use uniforms::{Sampler, SamplerBehavior, SamplerWrapFunction, UniformsStorage};
+use uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+use vertex_buffer::{AttributeType, Vertex, VertexBuffer, VertexBufferAny, VertexFormat};
+
+/// Operator that maps the colors of the source to the `[0, 1]` range.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapping {
+    /// The colors are simply clamped to `[0, 1]`.
+    Clamp,
+
+    /// `color / (1 + color)`.
+    Reinhard,
+
+    /// An approximation of the filmic curve of the ACES reference rendering transform.
+    AcesFilmic,
+}
+
+/// How the colors are encoded before being written to the surface.
+#[deriving(Show, Clone, Copy, PartialEq)]
+pub enum OutputEncoding {
+    /// The colors are written as they are.
+    ///
+    /// Use this if the surface is an sRGB texture or an sRGB window and you draw with
+    /// `framebuffer_srgb` enabled yourself.
+    Linear,
+
+    /// The colors are encoded with the sRGB transfer function.
+    Srgb,
+
+    /// The colors are raised to the power of `1 / gamma`.
+    Gamma(f32),
+}
+
+/// Parameters of a `PresentPass`.
+#[deriving(Show, Clone, Copy, PartialEq)]
+pub struct PresentParameters {
+    /// Value that the colors of the source are multiplied with before tonemapping.
+    ///
+    /// The default is `1.0`.
+    pub exposure: f32,
+
+    /// The tonemapping operator.
+    ///
+    /// The default is `Clamp`.
+    pub tonemapping: Tonemapping,
+
+    /// How the colors are encoded.
+    ///
+    /// The conversion is done in the shader, so the result is the same whether the surface
+    /// supports sRGB or not. The default is `Srgb`.
+    pub encoding: OutputEncoding,
+
+    /// If `true`, a small amount of noise is added to the result to hide the banding caused
+    /// by the quantization to 8 bits.
+    ///
+    /// The default is `false`.
+    pub dithering: bool,
+}
+
+impl Default for PresentParameters {
+    fn default() -> PresentParameters {
+        PresentParameters {
+            exposure: 1.0,
+            tonemapping: Tonemapping::Clamp,
+            encoding: OutputEncoding::Srgb,
+            dithering: false,
+        }
+    }
+}
+
+#[deriving(Copy)]
+struct PresentVertex {
+    position: [f32, ..2],
+}
+
+impl Vertex for PresentVertex {
+    fn build_bindings(_: Option<PresentVertex>) -> VertexFormat {
+        vec![("position".to_string(), 0, AttributeType::F32F32, false)]
+    }
+}
+
+/// Draws a linear texture on a surface with exposure, tonemapping and gamma correction.
+///
+/// Creating a `PresentPass` compiles a program, so you should create it once and reuse it
+/// every frame. The program is written in GLSL ES 1.00 on OpenGL ES, in GLSL 1.50 if the
+/// backend supports it (which is required by core profiles), and in GLSL 1.10 otherwise.
+pub struct PresentPass {
+    vertex_buffer: VertexBufferAny,
+    index_buffer: IndexBuffer,
+    program: Program,
+}
+
+impl PresentPass {
+    /// Builds the resources needed by the pass.
+    ///
+    /// Returns an error if the program can't be compiled by the backend.
+    pub fn new(display: &Display) -> Result<PresentPass, ProgramCreationError> {
+        // a single triangle that covers the whole viewport
+        let vertex_buffer = VertexBuffer::new(display, vec![
+            PresentVertex { position: [-1.0, -1.0] },
+            PresentVertex { position: [3.0, -1.0] },
+            PresentVertex { position: [-1.0, 3.0] },
+        ]).into_vertex_buffer_any();
+
+        let index_buffer = IndexBuffer::new(display, TrianglesList(vec![0u8, 1, 2]));
+
+        let (vertex_shader, fragment_shader) = {
+            let capabilities = display.context.context.capabilities();
+
+            if capabilities.opengl_es {
+                (format!("#version 100\n{}", VERTEX_SHADER_110),
+                 format!("#version 100\n{}{}", FRAGMENT_FUNCTIONS, FRAGMENT_MAIN_110))
+            } else if capabilities.glsl_version >= GlVersion(1, 50) {
+                (VERTEX_SHADER_150.to_string(),
+                 format!("#version 150\n{}{}", FRAGMENT_FUNCTIONS, FRAGMENT_MAIN_150))
+            } else {
+                (format!("#version 110\n{}", VERTEX_SHADER_110),
+                 format!("#version 110\n{}{}", FRAGMENT_FUNCTIONS, FRAGMENT_MAIN_110))
+            }
+        };
+
+        let program = try!(Program::from_source(display, vertex_shader.as_slice(),
+                                                fragment_shader.as_slice(), None));
+
+        Ok(PresentPass {
+            vertex_buffer: vertex_buffer,
+            index_buffer: index_buffer,
+            program: program,
+        })
+    }
+
+    /// Draws `source` on the whole `target`.
+    ///
+    /// The source is sampled with linear filtering, so it doesn't need to have the same
+    /// dimensions as the target.
+    pub fn draw<S>(&self, target: &mut S, source: &Texture2d, parameters: &PresentParameters)
+                   -> Result<(), DrawError> where S: Surface
+    {
+        let sampler = Sampler(source, SamplerBehavior {
+            wrap_function: (SamplerWrapFunction::Clamp, SamplerWrapFunction::Clamp,
+                            SamplerWrapFunction::Clamp),
+            minify_filter: MinifySamplerFilter::Linear,
+            magnify_filter: MagnifySamplerFilter::Linear,
+            .. Default::default()
+        });
+
+        let tonemapping = match parameters.tonemapping {
+            Tonemapping::Clamp => 0i32,
+            Tonemapping::Reinhard => 1,
+            Tonemapping::AcesFilmic => 2,
+        };
+
+        let (encoding, gamma) = match parameters.encoding {
+            OutputEncoding::Linear => (0i32, 1.0f32),
+            OutputEncoding::Srgb => (1, 1.0),
+            OutputEncoding::Gamma(gamma) => (2, gamma),
+        };
+
+        let dithering = if parameters.dithering { 1i32 } else { 0 };
+
+        let uniforms = UniformsStorage::new("source", sampler)
+                                       .add("exposure", parameters.exposure)
+                                       .add("tonemapping", tonemapping)
+                                       .add("encoding", encoding)
+                                       .add("gamma", gamma)
+                                       .add("dithering", dithering);
+
+        // the encoding is done in the shader, so the automatic conversion must be disabled
+        let draw_parameters = DrawParameters {
+            framebuffer_srgb: false,
+            .. Default::default()
+        };
+
+        target.draw(&self.vertex_buffer, &self.index_buffer, &self.program, &uniforms,
+                    &draw_parameters)
+    }
+}
+
+static VERTEX_SHADER_110: &'static str = "
+    attribute vec2 position;
+    varying vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        v_tex_coords = position * 0.5 + 0.5;
+    }
+";
+
+static VERTEX_SHADER_150: &'static str = "
+    #version 150
+
+    in vec2 position;
+    out vec2 v_tex_coords;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        v_tex_coords = position * 0.5 + 0.5;
+    }
+";
+
+/// Part of the fragment shader that is the same in all the versions of GLSL.
+static FRAGMENT_FUNCTIONS: &'static str = "
+    uniform sampler2D source;
+    uniform float exposure;
+    uniform int tonemapping;
+    uniform int encoding;
+    uniform float gamma;
+    uniform int dithering;
+
+    vec3 aces_filmic(vec3 x) {
+        return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+    }
+
+    vec3 srgb_encode(vec3 c) {
+        vec3 low = c * 12.92;
+        vec3 high = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;
+        return mix(low, high, step(vec3(0.0031308), c));
+    }
+
+    vec4 present(vec4 source_color) {
+        vec3 color = max(source_color.rgb * exposure, vec3(0.0));
+
+        if (tonemapping == 1) {
+            color = color / (1.0 + color);
+        } else if (tonemapping == 2) {
+            color = aces_filmic(color);
+        }
+
+        color = clamp(color, 0.0, 1.0);
+
+        if (encoding == 1) {
+            color = srgb_encode(color);
+        } else if (encoding == 2) {
+            color = pow(color, vec3(1.0 / gamma));
+        }
+
+        if (dithering != 0) {
+            // interleaved gradient noise, in the range [-0.5, 0.5] of one 8 bits step
+            float noise = fract(52.9829189 * fract(dot(gl_FragCoord.xy,
+                                                       vec2(0.06711056, 0.00583715))));
+            color += (noise - 0.5) / 255.0;
+        }
+
+        return vec4(color, source_color.a);
+    }
+";
+
+static FRAGMENT_MAIN_110: &'static str = "
+    varying vec2 v_tex_coords;
+
+    void main() {
+        gl_FragColor = present(texture2D(source, v_tex_coords));
+    }
+";
+
+static FRAGMENT_MAIN_150: &'static str = "
+    in vec2 v_tex_coords;
+    out vec4 color;
+
+    void main() {
+        color = present(texture(source, v_tex_coords));
+    }
+";
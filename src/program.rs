@@ -1,9 +1,13 @@
 use gl;
-use std::{fmt, mem, ptr};
+use libc;
+use std::{fmt, hash, mem, ptr};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, StaticMutex, MUTEX_INIT};
 use {Display, DisplayImpl, GlObject};
-use context::CommandContext;
+use context::{CommandContext, GlVersion};
+use uniforms::UniformType;
+use vertex_buffer::{AttributeType, VertexFormat};
+use vertex_array_object;
 
 /// Some shader compilers have race-condition issues.
 /// We lock this mutex in the GL thread every time we compile a shader or link a program.
@@ -33,7 +37,9 @@ pub struct Program {
     id: gl::types::GLuint,
     uniforms: Arc<HashMap<String, Uniform>>,
     attributes: Arc<HashMap<String, Attribute>>,
+    attribute_layout: u32,
     frag_data_locations: Mutex<HashMap<String, Option<u32>>>,
+    transform_feedback_varyings: Vec<TransformFeedbackVarying>,
 }
 
 /// Informations about a uniform (except its name).
@@ -55,6 +61,339 @@ struct Attribute {
     pub size: gl::types::GLint,
 }
 
+/// Describes which wave-level operations are available in shaders.
+///
+/// Obtained with `display.get_subgroup_support()`.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub struct SubgroupSupport {
+    /// True if `GL_ARB_shader_ballot` is available.
+    pub ballot: bool,
+
+    /// True if `GL_ARB_shader_group_vote` is available.
+    pub group_vote: bool,
+
+    /// True if the `GL_KHR_shader_subgroup` family of extensions is available.
+    pub subgroup: bool,
+
+    /// The operations of `GL_KHR_shader_subgroup` that are supported.
+    pub subgroup_features: SubgroupFeatures,
+
+    /// The shader stages in which the operations of `GL_KHR_shader_subgroup` are supported.
+    pub subgroup_stages: SubgroupStages,
+}
+
+/// The groups of operations of `GL_KHR_shader_subgroup`. Each of them is enabled by the
+/// `GL_KHR_shader_subgroup_<name>` GLSL extension.
+#[allow(missing_docs)]
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub struct SubgroupFeatures {
+    pub basic: bool,
+    pub vote: bool,
+    pub arithmetic: bool,
+    pub ballot: bool,
+    pub shuffle: bool,
+    pub shuffle_relative: bool,
+    pub clustered: bool,
+    pub quad: bool,
+}
+
+/// The shader stages in which the operations of `GL_KHR_shader_subgroup` can be used.
+#[allow(missing_docs)]
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub struct SubgroupStages {
+    pub vertex: bool,
+    pub tessellation_control: bool,
+    pub tessellation_evaluation: bool,
+    pub geometry: bool,
+    pub fragment: bool,
+    pub compute: bool,
+}
+
+/// A stage of the pipeline, which corresponds to a kind of shader.
+#[allow(missing_docs)]
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl SubgroupStages {
+    /// Returns true if the operations are supported in the given stage.
+    pub fn contains(&self, stage: ShaderStage) -> bool {
+        match stage {
+            ShaderStage::Vertex => self.vertex,
+            ShaderStage::TessellationControl => self.tessellation_control,
+            ShaderStage::TessellationEvaluation => self.tessellation_evaluation,
+            ShaderStage::Geometry => self.geometry,
+            ShaderStage::Fragment => self.fragment,
+            ShaderStage::Compute => self.compute,
+        }
+    }
+}
+
+/// Builds a `SubgroupFeatures` from the value of `GL_SUBGROUP_SUPPORTED_FEATURES_KHR`.
+pub fn subgroup_features_from_bits(bits: gl::types::GLbitfield) -> SubgroupFeatures {
+    SubgroupFeatures {
+        basic: bits & 0x1 != 0,
+        vote: bits & 0x2 != 0,
+        arithmetic: bits & 0x4 != 0,
+        ballot: bits & 0x8 != 0,
+        shuffle: bits & 0x10 != 0,
+        shuffle_relative: bits & 0x20 != 0,
+        clustered: bits & 0x40 != 0,
+        quad: bits & 0x80 != 0,
+    }
+}
+
+/// Builds a `SubgroupStages` from the value of `GL_SUBGROUP_SUPPORTED_STAGES_KHR`.
+pub fn subgroup_stages_from_bits(bits: gl::types::GLbitfield) -> SubgroupStages {
+    SubgroupStages {
+        vertex: bits & gl::VERTEX_SHADER_BIT != 0,
+        tessellation_control: bits & gl::TESS_CONTROL_SHADER_BIT != 0,
+        tessellation_evaluation: bits & gl::TESS_EVALUATION_SHADER_BIT != 0,
+        geometry: bits & gl::GEOMETRY_SHADER_BIT != 0,
+        fragment: bits & gl::FRAGMENT_SHADER_BIT != 0,
+        compute: bits & gl::COMPUTE_SHADER_BIT != 0,
+    }
+}
+
+impl SubgroupSupport {
+    /// Returns the `#extension` directives required to use the supported operations in a
+    /// shader of the given stage.
+    ///
+    /// The result must be inserted in the shader's source code right after the `#version`
+    /// directive. Only the extensions that are supported are enabled, and a
+    /// `GLIUM_<EXTENSION>` macro is defined for each of them so that the shader can use
+    /// `#ifdef` to pick the best implementation. The `GL_KHR_shader_subgroup_<name>`
+    /// extensions are only enabled if their operations are supported in `stage`.
+    pub fn get_glsl_preamble(&self, stage: ShaderStage) -> String {
+        let mut preamble = String::new();
+
+        if self.ballot {
+            preamble.push_str("#extension GL_ARB_shader_ballot : enable\n");
+            preamble.push_str("#define GLIUM_ARB_SHADER_BALLOT 1\n");
+        }
+
+        if self.group_vote {
+            preamble.push_str("#extension GL_ARB_shader_group_vote : enable\n");
+            preamble.push_str("#define GLIUM_ARB_SHADER_GROUP_VOTE 1\n");
+        }
+
+        if self.subgroup && self.subgroup_stages.contains(stage) {
+            let features = self.subgroup_features;
+
+            for &(ext, name, supported) in [
+                ("basic", "BASIC", features.basic),
+                ("vote", "VOTE", features.vote),
+                ("ballot", "BALLOT", features.ballot),
+                ("shuffle", "SHUFFLE", features.shuffle),
+                ("shuffle_relative", "SHUFFLE_RELATIVE", features.shuffle_relative),
+                ("arithmetic", "ARITHMETIC", features.arithmetic),
+                ("clustered", "CLUSTERED", features.clustered),
+                ("quad", "QUAD", features.quad),
+            ].iter()
+            {
+                if !supported {
+                    continue;
+                }
+
+                preamble.push_str(format!("#extension GL_KHR_shader_subgroup_{} : enable\n",
+                                          ext).as_slice());
+                preamble.push_str(format!("#define GLIUM_KHR_SHADER_SUBGROUP_{} 1\n",
+                                          name).as_slice());
+            }
+        }
+
+        preamble
+    }
+}
+
+/// How the varyings captured by transform feedback are written in buffers.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum TransformFeedbackMode {
+    /// All the varyings are written one after another in the same buffer.
+    ///
+    /// You can use the special names `gl_NextBuffer` and `gl_SkipComponents1` to
+    /// `gl_SkipComponents4` to switch to the next buffer or to leave some space between
+    /// the varyings. These require OpenGL 4.0.
+    Interleaved,
+
+    /// Each varying is written in its own buffer.
+    Separate,
+}
+
+/// Error returned by `Program::check_vertex_format`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub enum VertexFormatMismatch {
+    /// An active attribute of the program is missing in the vertex format.
+    MissingAttribute(String),
+
+    /// The type of an attribute of the vertex format doesn't match the type of the
+    /// attribute in the program.
+    TypeMismatch {
+        /// Name of the attribute.
+        name: String,
+        /// Type of the attribute in the program (of one element if it's an array).
+        program_type: UniformType,
+        /// Number of elements if the attribute is an array in the program, or 1 otherwise.
+        program_size: uint,
+        /// Type of the attribute in the vertex format.
+        format_type: AttributeType,
+    },
+}
+
+impl ::std::error::Error for VertexFormatMismatch {
+    fn description(&self) -> &str {
+        match self {
+            &VertexFormatMismatch::MissingAttribute(_) => "An attribute of the program is \
+                                                           missing in the vertex format",
+            &VertexFormatMismatch::TypeMismatch { .. } => "The type of an attribute doesn't \
+                                                           match the program",
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match self {
+            &VertexFormatMismatch::MissingAttribute(ref name) => {
+                Some(format!("The program attribute `{}` is missing in the vertex bindings",
+                             name))
+            },
+            &VertexFormatMismatch::TypeMismatch { ref name, program_type, program_size,
+                                                  format_type } =>
+            {
+                Some(format!("The program attribute `{}` does not match the vertex format: the \
+                              program expects `{}` (array size {}) but the vertex format \
+                              contains `{}`", name, program_type, program_size, format_type))
+            },
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        None
+    }
+}
+
+/// Informations about an active uniform of a program.
+///
+/// Obtained with `program.get_uniforms()`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct UniformInfo {
+    /// Name of the uniform in the shader. The name of an array ends with `[0]`.
+    pub name: String,
+
+    /// Location of the uniform, or `-1` if the uniform is a member of a uniform block.
+    pub location: i32,
+
+    /// Type of the uniform (of one element if it's an array).
+    pub ty: UniformType,
+
+    /// Number of elements if the uniform is an array, or 1 otherwise.
+    pub size: uint,
+}
+
+/// Informations about an active vertex attribute of a program.
+///
+/// Obtained with `program.get_attributes()`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct AttributeInfo {
+    /// Name of the attribute in the shader.
+    pub name: String,
+
+    /// Location of the attribute.
+    pub location: i32,
+
+    /// The type of the attribute (of one element if it's an array), or `None` if it doesn't
+    /// match any `AttributeType`. This is the case of matrices and doubles.
+    pub ty: Option<AttributeType>,
+
+    /// Number of elements if the attribute is an array, or 1 otherwise.
+    pub size: uint,
+}
+
+/// Informations about an active uniform block of a program.
+///
+/// Obtained with `program.get_uniform_blocks()`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    /// Name of the block in the shader.
+    pub name: String,
+
+    /// Index of the uniform buffer binding point the block is binded to.
+    pub binding: u32,
+
+    /// Minimum size in bytes of the buffer that contains the data of the block.
+    pub size: uint,
+
+    /// Members of the block, in the order of their offset.
+    pub members: Vec<UniformBlockMember>,
+}
+
+/// A member of a uniform block.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct UniformBlockMember {
+    /// Name of the member in the shader, including the name of the block if the block
+    /// has an instance name.
+    pub name: String,
+
+    /// Type of the member (of one element if it's an array).
+    pub ty: UniformType,
+
+    /// Number of elements if the member is an array, or 1 otherwise.
+    pub size: uint,
+
+    /// Offset in bytes of the member from the start of the block.
+    pub offset: uint,
+
+    /// Number of bytes between two elements if the member is an array, or 0 otherwise.
+    pub array_stride: uint,
+
+    /// Number of bytes between two columns (or rows if the matrix is row-major) if the
+    /// member is a matrix, or 0 otherwise.
+    pub matrix_stride: uint,
+}
+
+/// Informations about a varying captured by transform feedback.
+///
+/// Obtained with `program.get_transform_feedback_varyings()`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct TransformFeedbackVarying {
+    /// Name of the varying in the shader.
+    pub name: String,
+
+    /// Index of the buffer the varying is written to.
+    pub buffer_index: uint,
+
+    /// Offset in bytes of the varying from the start of each vertex in the buffer.
+    pub offset: uint,
+
+    /// Number of bytes taken by the varying, including all the elements of an array.
+    pub size: uint,
+
+    /// Number of bytes between two consecutive vertices in the buffer.
+    pub stride: uint,
+
+    /// Number of elements if the varying is an array, or 1 otherwise.
+    pub array_size: uint,
+
+    /// The type of the varying (of one element if it's an array), or `None` if it can't
+    /// be used as a vertex attribute. This is the case of matrices and doubles.
+    pub ty: Option<AttributeType>,
+}
+
+/// Binary representation of a linked program, returned by `Program::get_binary`.
+#[deriving(Clone, Show, PartialEq, Eq)]
+pub struct ProgramBinary {
+    /// Format of the binary. Its meaning is specific to the driver.
+    pub format: u32,
+
+    /// Content of the binary.
+    pub content: Vec<u8>,
+}
+
 /// Error that can be triggered when creating a `Program`.
 #[deriving(Clone, Show)]
 pub enum ProgramCreationError {
@@ -68,6 +407,20 @@ pub enum ProgramCreationError {
     ///
     /// Usually the case of geometry shaders.
     ShaderTypeNotSupported,
+
+    /// Transform feedback is not supported by the backend.
+    TransformFeedbackNotSupported,
+
+    /// The type of one of the varyings captured by transform feedback is unknown. Contains
+    /// the name of the varying.
+    UnknownTransformFeedbackVaryingType(String),
+
+    /// Program binaries are not supported by the backend.
+    BinaryNotSupported,
+
+    /// Some vertex attributes use the same locations, or didn't get the location that was
+    /// requested, for example because a `layout(location = ...)` qualifier overrides it.
+    AttributeLocationConflict(String),
 }
 
 impl ::std::error::Error for ProgramCreationError {
@@ -78,6 +431,16 @@ impl ::std::error::Error for ProgramCreationError {
             &ProgramCreationError::LinkingError(_) => "Error while linking shaders together",
             &ProgramCreationError::ShaderTypeNotSupported => "One of the request shader type is \
                                                               not supported by the backend",
+            &ProgramCreationError::TransformFeedbackNotSupported => "Transform feedback is not \
+                                                                     supported by the backend",
+            &ProgramCreationError::UnknownTransformFeedbackVaryingType(_) => "The type of a \
+                                                                           captured varying \
+                                                                           is unknown",
+            &ProgramCreationError::BinaryNotSupported => "Program binaries are not supported \
+                                                          by the backend",
+            &ProgramCreationError::AttributeLocationConflict(_) => "Conflict between the \
+                                                                    locations of vertex \
+                                                                    attributes",
         }
     }
 
@@ -86,6 +449,10 @@ impl ::std::error::Error for ProgramCreationError {
             &ProgramCreationError::CompilationError(ref s) => Some(s.clone()),
             &ProgramCreationError::LinkingError(ref s) => Some(s.clone()),
             &ProgramCreationError::ShaderTypeNotSupported => None,
+            &ProgramCreationError::TransformFeedbackNotSupported => None,
+            &ProgramCreationError::UnknownTransformFeedbackVaryingType(ref s) => Some(s.clone()),
+            &ProgramCreationError::BinaryNotSupported => None,
+            &ProgramCreationError::AttributeLocationConflict(ref s) => Some(s.clone()),
         }
     }
 
@@ -134,6 +501,149 @@ impl Program {
         }
         shaders_store.push(try!(build_shader(display, gl::FRAGMENT_SHADER, fragment_shader)));
 
+        Program::from_shaders(display, shaders_store, None, Vec::new())
+    }
+
+    /// Builds a new program from GLSL source code, and assigns explicit locations to some of
+    /// the vertex attributes.
+    ///
+    /// The locations are assigned with `glBindAttribLocation` before the program is linked.
+    /// Giving the same locations to the attributes of all your programs ensures that vertex
+    /// buffers are always binded the same way, whatever the program.
+    ///
+    /// Returns `AttributeLocationConflict` if two attributes would share a location, or if an
+    /// active attribute didn't get the requested location. This happens if the shader
+    /// declares a different location with a `layout(location = ...)` qualifier.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+    /// # let vertex_source = ""; let fragment_source = "";
+    /// let program = glium::Program::from_source_with_attribute_locations(&display,
+    ///     vertex_source, fragment_source, None, &[("position", 0), ("normal", 1)]);
+    /// ```
+    #[experimental = "The list of shaders and the result error will probably change"]
+    pub fn from_source_with_attribute_locations(display: &Display, vertex_shader: &str,
+                                                fragment_shader: &str,
+                                                geometry_shader: Option<&str>,
+                                                locations: &[(&str, u32)])
+                                                -> Result<Program, ProgramCreationError>
+    {
+        for (n, &(name, location)) in locations.iter().enumerate() {
+            for &(other_name, other_location) in locations.slice_from(n + 1).iter() {
+                if name == other_name {
+                    return Err(ProgramCreationError::AttributeLocationConflict(
+                        format!("A location is requested twice for the attribute `{}`", name)));
+                }
+
+                if location == other_location {
+                    return Err(ProgramCreationError::AttributeLocationConflict(
+                        format!("The attributes `{}` and `{}` are both requested at location \
+                                 {}", name, other_name, location)));
+                }
+            }
+        }
+
+        let mut shaders_store = Vec::new();
+        shaders_store.push(try!(build_shader(display, gl::VERTEX_SHADER, vertex_shader)));
+        match geometry_shader {
+            Some(gs) => shaders_store.push(try!(build_shader(display, gl::GEOMETRY_SHADER, gs))),
+            None => ()
+        }
+        shaders_store.push(try!(build_shader(display, gl::FRAGMENT_SHADER, fragment_shader)));
+
+        let locations = locations.iter().map(|&(n, l)| (n.to_string(), l)).collect::<Vec<_>>();
+        let program = try!(Program::from_shaders(display, shaders_store, None,
+                                                 locations.clone()));
+
+        for &(ref name, location) in locations.iter() {
+            match program.attributes.get(name) {
+                Some(attribute) if attribute.location != location as gl::types::GLint => {
+                    return Err(ProgramCreationError::AttributeLocationConflict(
+                        format!("The attribute `{}` was requested at location {} but is at \
+                                 location {}", name, location, attribute.location)));
+                },
+                _ => ()
+            }
+        }
+
+        Ok(program)
+    }
+
+    /// Builds a new program from GLSL source code, and captures some of the output varyings
+    /// of the vertex or geometry shader with transform feedback.
+    ///
+    /// The layout of the captured varyings can then be retrieved with
+    /// `get_transform_feedback_varyings`.
+    ///
+    /// Returns `TransformFeedbackNotSupported` if the backend doesn't support transform
+    /// feedback.
+    #[experimental = "The list of shaders and the result error will probably change"]
+    pub fn from_source_with_transform_feedback(display: &Display, vertex_shader: &str,
+                                               fragment_shader: &str,
+                                               geometry_shader: Option<&str>,
+                                               varyings: &[&str], mode: TransformFeedbackMode)
+                                               -> Result<Program, ProgramCreationError>
+    {
+        if !display.context.context.capabilities().transform_feedback {
+            return Err(ProgramCreationError::TransformFeedbackNotSupported);
+        }
+
+        let mut shaders_store = Vec::new();
+        shaders_store.push(try!(build_shader(display, gl::VERTEX_SHADER, vertex_shader)));
+        match geometry_shader {
+            Some(gs) => shaders_store.push(try!(build_shader(display, gl::GEOMETRY_SHADER, gs))),
+            None => ()
+        }
+        shaders_store.push(try!(build_shader(display, gl::FRAGMENT_SHADER, fragment_shader)));
+
+        let varyings = varyings.iter().map(|v| v.to_string()).collect();
+        Program::from_shaders(display, shaders_store, Some((varyings, mode)), Vec::new())
+    }
+
+    /// Builds a new program made of a mesh shader, an optional task shader and a fragment
+    /// shader.
+    ///
+    /// Programs built with this function must be drawn with `Surface::draw_mesh_tasks`.
+    ///
+    /// Returns `ShaderTypeNotSupported` if the backend doesn't support the
+    /// `GL_NV_mesh_shader` extension.
+    ///
+    /// # Features
+    ///
+    /// Only available if the `nv_mesh_shader` feature is enabled.
+    #[cfg(feature = "nv_mesh_shader")]
+    #[experimental = "The list of shaders and the result error will probably change"]
+    pub fn from_mesh_source(display: &Display, task_shader: Option<&str>, mesh_shader: &str,
+                            fragment_shader: &str) -> Result<Program, ProgramCreationError>
+    {
+        if !display.context.context.capabilities().mesh_shaders {
+            return Err(ProgramCreationError::ShaderTypeNotSupported);
+        }
+
+        let mut shaders_store = Vec::new();
+        match task_shader {
+            Some(ts) => shaders_store.push(try!(build_shader(display, gl::TASK_SHADER_NV, ts))),
+            None => ()
+        }
+        shaders_store.push(try!(build_shader(display, gl::MESH_SHADER_NV, mesh_shader)));
+        shaders_store.push(try!(build_shader(display, gl::FRAGMENT_SHADER, fragment_shader)));
+
+        Program::from_shaders(display, shaders_store, None, Vec::new())
+    }
+
+    /// Links the shaders together and builds the program.
+    ///
+    /// Returns `AttributeLocationConflict` if some of the active vertex attributes of the
+    /// linked program overlap.
+    fn from_shaders(display: &Display, shaders_store: Vec<Shader>,
+                    transform_feedback: Option<(Vec<String>, TransformFeedbackMode)>,
+                    attribute_locations: Vec<(String, u32)>)
+                    -> Result<Program, ProgramCreationError>
+    {
+        let has_transform_feedback = transform_feedback.is_some();
+
         let mut shaders_ids = Vec::new();
         for sh in shaders_store.iter() {
             shaders_ids.push(sh.id);
@@ -152,6 +662,38 @@ impl Program {
                     ctxt.gl.AttachShader(id, sh.clone());
                 }
 
+                // allowing the binary to be retrieved with `get_binary`
+                if ctxt.capabilities.program_binary {
+                    ctxt.gl.ProgramParameteri(id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+                                              gl::TRUE as gl::types::GLint);
+                }
+
+                // assigning the requested locations to the attributes
+                for &(ref name, location) in attribute_locations.iter() {
+                    ctxt.gl.BindAttribLocation(id, location, name.to_c_str().as_ptr());
+                }
+
+                // declaring the varyings to capture
+                if let Some((varyings, mode)) = transform_feedback {
+                    let varyings = varyings.iter().map(|v| v.to_c_str()).collect::<Vec<_>>();
+                    let pointers = varyings.iter().map(|v| v.as_ptr()).collect::<Vec<_>>();
+
+                    let mode = match mode {
+                        TransformFeedbackMode::Interleaved => gl::INTERLEAVED_ATTRIBS,
+                        TransformFeedbackMode::Separate => gl::SEPARATE_ATTRIBS,
+                    };
+
+                    if ctxt.version >= &GlVersion(3, 0) {
+                        ctxt.gl.TransformFeedbackVaryings(id,
+                                                          pointers.len() as gl::types::GLsizei,
+                                                          pointers.as_ptr(), mode);
+                    } else {
+                        ctxt.gl.TransformFeedbackVaryingsEXT(id,
+                                                             pointers.len() as gl::types::GLsizei,
+                                                             pointers.as_ptr(), mode);
+                    }
+                }
+
                 // linking
                 {
                     let _lock = COMPILER_GLOBAL_LOCK.lock();
@@ -183,16 +725,7 @@ impl Program {
                             }
                         };
 
-                        let mut error_log_size: gl::types::GLint = mem::uninitialized();
-                        ctxt.gl.GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut error_log_size);
-
-                        let mut error_log: Vec<u8> = Vec::with_capacity(error_log_size as uint);
-                        ctxt.gl.GetProgramInfoLog(id, error_log_size, &mut error_log_size,
-                            error_log.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
-                        error_log.set_len(error_log_size as uint);
-
-                        let msg = String::from_utf8(error_log).unwrap();
-                        tx.send(Err(LinkingError(msg)));
+                        tx.send(Err(LinkingError(get_program_info_log(&ctxt, id))));
                         return;
                     }
                 }
@@ -202,18 +735,97 @@ impl Program {
         });
 
         let id = try!(rx.recv());
+        let program = try!(Program::from_linked_program(display, id, shaders_store,
+                                                        has_transform_feedback));
+        try!(check_attributes_aliasing(&*program.attributes));
+        Ok(program)
+    }
+
+    /// Builds a new program from a binary previously returned by `get_binary`.
+    ///
+    /// Returns `BinaryNotSupported` if the backend doesn't support program binaries, and
+    /// `LinkingError` if the binary has been rejected by the driver. This happens for example
+    /// if the driver or the hardware has changed since the binary has been retrieved, in which
+    /// case you should build the program from its source code again.
+    pub fn from_binary(display: &Display, binary: &ProgramBinary)
+                       -> Result<Program, ProgramCreationError>
+    {
+        if !display.context.context.capabilities().program_binary {
+            return Err(ProgramCreationError::BinaryNotSupported);
+        }
 
+        let format = binary.format;
+        let content = binary.content.clone();
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: ctxt| {
+            unsafe {
+                let id = ctxt.gl.CreateProgram();
+                if id == 0 {
+                    panic!("glCreateProgram failed");
+                }
+
+                ctxt.gl.ProgramBinary(id, format, content.as_ptr() as *const libc::c_void,
+                                      content.len() as gl::types::GLsizei);
+
+                let mut link_success: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetProgramiv(id, gl::LINK_STATUS, &mut link_success);
+                if link_success == 0 {
+                    let msg = get_program_info_log(&ctxt, id);
+                    ctxt.gl.DeleteProgram(id);
+                    tx.send(Err(ProgramCreationError::LinkingError(msg)));
+                    return;
+                }
+
+                tx.send(Ok(id));
+            }
+        });
+
+        let id = try!(rx.recv());
+        let transform_feedback = display.context.context.capabilities().transform_feedback;
+        Program::from_linked_program(display, id, Vec::new(), transform_feedback)
+    }
+
+    /// Builds the `Program` object that corresponds to a program that has been linked.
+    ///
+    /// The program is destroyed if an error is returned.
+    fn from_linked_program(display: &Display, id: gl::types::GLuint, shaders_store: Vec<Shader>,
+                           has_transform_feedback: bool)
+                           -> Result<Program, ProgramCreationError>
+    {
         let (tx, rx) = channel();
         display.context.context.exec(move |: mut ctxt| {
             unsafe {
-                tx.send((
+                let transform_feedback = if has_transform_feedback {
+                    match reflect_transform_feedback(&mut ctxt, id) {
+                        Ok(varyings) => varyings,
+                        Err(err) => {
+                            ctxt.gl.DeleteProgram(id);
+                            tx.send(Err(err));
+                            return;
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+
+                tx.send(Ok((
                     reflect_uniforms(&mut ctxt, id),
-                    reflect_attributes(&mut ctxt, id)
-                ))
+                    reflect_attributes(&mut ctxt, id),
+                    transform_feedback
+                )))
             }
         });
 
-        let (uniforms, attributes) = rx.recv();
+        let (uniforms, attributes, transform_feedback_varyings) = try!(rx.recv());
+
+        let attribute_layout = {
+            let mut layout = attributes.iter()
+                .map(|(name, a)| (name.clone(), a.location, a.ty, a.size))
+                .collect::<Vec<_>>();
+            layout.sort();
+            vertex_array_object::register_layout(&*display.context, layout)
+        };
 
         Ok(Program {
             display: display.context.clone(),
@@ -221,10 +833,195 @@ impl Program {
             id: id,
             uniforms: Arc::new(uniforms),
             attributes: Arc::new(attributes),
+            attribute_layout: attribute_layout,
             frag_data_locations: Mutex::new(HashMap::new()),
+            transform_feedback_varyings: transform_feedback_varyings,
         })
     }
 
+    /// Returns the binary of the program, which can be stored and used later to rebuild the
+    /// program with `from_binary` without compiling its source code again.
+    ///
+    /// The binary is specific to the driver and the hardware that produced it.
+    ///
+    /// Returns `None` if the backend doesn't support program binaries.
+    pub fn get_binary(&self) -> Option<ProgramBinary> {
+        if !self.display.context.capabilities().program_binary {
+            return None;
+        }
+
+        let id = self.id;
+
+        let (tx, rx) = channel();
+        self.display.context.exec(move |: ctxt| {
+            unsafe {
+                let mut length: gl::types::GLint = mem::uninitialized();
+                ctxt.gl.GetProgramiv(id, gl::PROGRAM_BINARY_LENGTH, &mut length);
+
+                let mut format: gl::types::GLenum = mem::uninitialized();
+                let mut content: Vec<u8> = Vec::with_capacity(length as uint);
+                ctxt.gl.GetProgramBinary(id, length, &mut length, &mut format,
+                                         content.as_mut_ptr() as *mut libc::c_void);
+                content.set_len(length as uint);
+
+                tx.send(ProgramBinary {
+                    format: format,
+                    content: content,
+                });
+            }
+        });
+
+        Some(rx.recv())
+    }
+
+    /// Returns the list of varyings captured by transform feedback, in the order in which
+    /// they were passed when creating the program.
+    ///
+    /// The list is empty if the program was not created with
+    /// `from_source_with_transform_feedback`.
+    pub fn get_transform_feedback_varyings(&self) -> &[TransformFeedbackVarying] {
+        self.transform_feedback_varyings.as_slice()
+    }
+
+    /// Returns the list of the active uniforms of the program, sorted by name.
+    ///
+    /// Uniforms that are declared but not used by the shaders are usually optimized away by
+    /// the driver and are not in the list.
+    pub fn get_uniforms(&self) -> Vec<UniformInfo> {
+        let mut uniforms = self.uniforms.iter().map(|(name, uniform)| {
+            UniformInfo {
+                name: name.clone(),
+                location: uniform.location,
+                ty: glenum_to_uniform_type(uniform.ty),
+                size: uniform.size as uint,
+            }
+        }).collect::<Vec<_>>();
+
+        uniforms.sort_by(|a, b| a.name.cmp(&b.name));
+        uniforms
+    }
+
+    /// Returns the list of the active vertex attributes of the program, sorted by name.
+    pub fn get_attributes(&self) -> Vec<AttributeInfo> {
+        let mut attributes = self.attributes.iter().map(|(name, attribute)| {
+            AttributeInfo {
+                name: name.clone(),
+                location: attribute.location,
+                ty: get_varying_attribute_type(attribute.ty),
+                size: attribute.size as uint,
+            }
+        }).collect::<Vec<_>>();
+
+        attributes.sort_by(|a, b| a.name.cmp(&b.name));
+        attributes
+    }
+
+    /// Returns the list of the active uniform blocks of the program, with the layout of
+    /// their members.
+    ///
+    /// The list is empty if the backend doesn't support uniform blocks.
+    pub fn get_uniform_blocks(&self) -> Vec<UniformBlockInfo> {
+        if !self.display.context.capabilities().uniform_blocks {
+            return Vec::new();
+        }
+
+        let id = self.id;
+
+        let (tx, rx) = channel();
+        self.display.context.exec(move |: mut ctxt| {
+            unsafe {
+                tx.send(reflect_uniform_blocks(&mut ctxt, id));
+            }
+        });
+
+        rx.recv()
+    }
+
+    /// Checks that a vertex format can be used with this program.
+    ///
+    /// All the active attributes of the program must be in the vertex format with a matching
+    /// type. Attributes of the vertex format that are not used by the program are ignored.
+    ///
+    /// The same check is done when drawing, but drawing panics if it fails. This function
+    /// can be used to check the vertex formats of your meshes once after loading the
+    /// program, and to report the error.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let program: glium::Program = unsafe { std::mem::uninitialized() };
+    /// # let vertex_buffer: glium::vertex_buffer::VertexBufferAny = unsafe {
+    /// #     std::mem::uninitialized() };
+    /// if let Err(e) = program.check_vertex_format(vertex_buffer.get_bindings()) {
+    ///     println!("{}", std::error::Error::detail(&e).unwrap());
+    /// }
+    /// ```
+    pub fn check_vertex_format(&self, format: &VertexFormat)
+                               -> Result<(), VertexFormatMismatch>
+    {
+        for &(ref name, _, ty, normalize) in format.iter() {
+            let attribute = match self.attributes.get(name) {
+                Some(a) => a,
+                None => continue
+            };
+
+            if !vertex_array_object::vertex_type_matches(ty, normalize, attribute.ty,
+                                                           attribute.size) {
+                return Err(VertexFormatMismatch::TypeMismatch {
+                    name: name.clone(),
+                    program_type: glenum_to_uniform_type(attribute.ty),
+                    program_size: attribute.size as uint,
+                    format_type: ty,
+                });
+            }
+        }
+
+        for (name, _) in self.attributes.iter() {
+            if format.iter().find(|&&(ref n, _, _, _)| n == name).is_none() {
+                return Err(VertexFormatMismatch::MissingAttribute(name.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds a `VertexFormat` that matches the layout of one of the buffers written by
+    /// transform feedback.
+    ///
+    /// This allows you to use the captured data as a source of vertices. Arrays are split
+    /// into one attribute per element, named `name[0]`, `name[1]`, etc. Varyings that can't
+    /// be used as vertex attributes are ignored.
+    pub fn get_transform_feedback_vertex_format(&self, buffer_index: uint) -> VertexFormat {
+        let mut format = Vec::new();
+
+        for varying in self.transform_feedback_varyings.iter() {
+            if varying.buffer_index != buffer_index {
+                continue;
+            }
+
+            let ty = match varying.ty {
+                Some(ty) => ty,
+                None => continue
+            };
+
+            if varying.array_size == 1 {
+                format.push((varying.name.clone(), varying.offset, ty, false));
+                continue;
+            }
+
+            // the name of an array returned by OpenGL ends with `[0]`
+            let name = varying.name.as_slice();
+            let name = if name.ends_with("[0]") { name.slice_to(name.len() - 3) } else { name };
+            let element_size = varying.size / varying.array_size;
+            for i in range(0, varying.array_size) {
+                format.push((format!("{}[{}]", name, i), varying.offset + i * element_size, ty,
+                             false));
+            }
+        }
+
+        format
+    }
+
     /// Returns the *location* of an output fragment, if it exists.
     ///
     /// The *location* is a low-level information that is used internally by glium.
@@ -263,6 +1060,74 @@ impl Program {
     }
 }
 
+/// A program made of a single compute shader.
+///
+/// Compute shaders don't draw anything. Instead they are executed on a grid of work groups,
+/// and read and write textures with image units (see `uniforms::ImageUnit`).
+pub struct ComputeShader {
+    program: Program,
+}
+
+impl ComputeShader {
+    /// Builds a new compute shader from GLSL source code.
+    ///
+    /// Returns `ShaderTypeNotSupported` if the backend doesn't support compute shaders.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+    /// let compute = glium::ComputeShader::from_source(&display, "
+    ///     #version 430
+    ///     layout(local_size_x = 16, local_size_y = 16) in;
+    ///     layout(rgba8) uniform writeonly image2D image;
+    ///
+    ///     void main() {
+    ///         imageStore(image, ivec2(gl_GlobalInvocationID.xy), vec4(1.0, 0.0, 0.0, 1.0));
+    ///     }
+    /// ");
+    /// ```
+    #[experimental = "The result error will probably change"]
+    pub fn from_source(display: &Display, source: &str)
+                       -> Result<ComputeShader, ProgramCreationError>
+    {
+        if !display.context.context.capabilities().compute_shader {
+            return Err(ProgramCreationError::ShaderTypeNotSupported);
+        }
+
+        let shader = try!(build_shader(display, gl::COMPUTE_SHADER, source));
+        let program = try!(Program::from_shaders(display, vec![shader], None, Vec::new()));
+
+        Ok(ComputeShader {
+            program: program,
+        })
+    }
+
+    /// Executes the compute shader on a grid of `x * y * z` work groups.
+    ///
+    /// The function returns immediately. A memory barrier is inserted after the execution
+    /// so that the results are visible to the next commands.
+    ///
+    /// Returns an error and doesn't execute anything if the number of work groups is larger
+    /// than what the backend supports, or if the type of a uniform doesn't match the shader.
+    ///
+    /// # Panic
+    ///
+    /// Panics if an image unit is used and image load/store is not supported.
+    pub fn dispatch<U>(&self, uniforms: U, x: u32, y: u32, z: u32) -> Result<(), ::DrawError>
+                       where U: ::uniforms::Uniforms
+    {
+        let display = Display { context: self.program.display.clone() };
+        ::ops::dispatch_compute(&display, &self.program, uniforms, x, y, z)
+    }
+}
+
+impl GlObject for ComputeShader {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.program.get_id()
+    }
+}
+
 impl fmt::Show for Program {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         (format!("Program #{}", self.id)).fmt(formatter)
@@ -287,17 +1152,17 @@ pub fn get_attributes(program: &Program) -> Arc<HashMap<String, Attribute>>
     program.attributes.clone()
 }
 
+/// Returns the id of the attribute layout of the program, which identifies the program in the
+/// keys of the VAOs.
+pub fn get_attribute_layout(program: &Program) -> u32 {
+    program.attribute_layout
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
-        // removing VAOs which contain this program
-        {
-            let mut vaos = self.display.vertex_array_objects.lock().unwrap();
-            let to_delete = vaos.keys().filter(|&&(_, _, p)| p == self.id)
-                .map(|k| k.clone()).collect::<Vec<_>>();
-            for k in to_delete.into_iter() {
-                vaos.remove(&k);
-            }
-        }
+        // removing VAOs which have been built for this program, unless other programs have
+        // the same attributes
+        vertex_array_object::release_layout(&*self.display, self.attribute_layout);
 
         // sending the destroy command
         let id = self.id.clone();
@@ -306,6 +1171,7 @@ impl Drop for Program {
                 if ctxt.state.program == id {
                     ctxt.gl.UseProgram(0);
                     ctxt.state.program = 0;
+                    ctxt.state.statistics.state_changes += 1;
                 }
 
                 ctxt.gl.DeleteProgram(id);
@@ -314,11 +1180,190 @@ impl Drop for Program {
     }
 }
 
+/// Source code of the shaders that compose a program.
+///
+/// This is the key used by `ProgramCache`.
+#[deriving(Clone, Show, PartialEq, Eq, Hash)]
+pub struct ProgramSources {
+    /// Source code of the vertex shader.
+    pub vertex_shader: String,
+
+    /// Source code of the fragment shader.
+    pub fragment_shader: String,
+
+    /// Source code of the geometry shader, if any.
+    pub geometry_shader: Option<String>,
+}
+
+impl ProgramSources {
+    /// Builds a `ProgramSources` from the source code of each shader.
+    pub fn new(vertex_shader: &str, fragment_shader: &str, geometry_shader: Option<&str>)
+               -> ProgramSources
+    {
+        ProgramSources {
+            vertex_shader: vertex_shader.to_string(),
+            fragment_shader: fragment_shader.to_string(),
+            geometry_shader: geometry_shader.map(|s| s.to_string()),
+        }
+    }
+
+    /// Returns a hash of the sources.
+    ///
+    /// This value is stable for a given build of glium and can be used as a file name
+    /// when storing programs on the disk.
+    pub fn get_hash(&self) -> u64 {
+        hash::hash(self)
+    }
+}
+
+/// Caches programs so that identical sets of shaders are only compiled once.
+///
+/// Calling `get_or_create` twice with the same source code returns the same `Program`
+/// instead of compiling and linking the shaders again.
+///
+/// # Persistence
+///
+/// The list of programs that have been compiled can be retrieved with `get_sources` and
+/// written somewhere by the user. On the next run, passing this list to `preload` compiles
+/// all the programs upfront.
+///
+/// If the backend supports program binaries, `get_binaries` returns the binary of each program
+/// along with its sources. Passing this list to `preload_binaries` on the next run rebuilds the
+/// programs without compiling their source code again.
+///
+/// ```no_run
+/// # let display: glium::Display = unsafe { std::mem::uninitialized() };
+/// # let vertex_source = ""; let fragment_source = "";
+/// let cache = glium::ProgramCache::new(&display);
+///
+/// let program1 = cache.get_or_create(vertex_source, fragment_source, None).unwrap();
+/// let program2 = cache.get_or_create(vertex_source, fragment_source, None).unwrap();
+/// assert_eq!(cache.len(), 1);
+/// ```
+pub struct ProgramCache {
+    display: Display,
+    programs: Mutex<HashMap<ProgramSources, Arc<Program>>>,
+}
+
+impl ProgramCache {
+    /// Builds a new empty cache.
+    pub fn new(display: &Display) -> ProgramCache {
+        ProgramCache {
+            display: display.clone(),
+            programs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the program corresponding to these sources, compiling it if it is not
+    /// in the cache yet.
+    ///
+    /// Compilation errors are not cached.
+    pub fn get_or_create(&self, vertex_shader: &str, fragment_shader: &str,
+                         geometry_shader: Option<&str>)
+                         -> Result<Arc<Program>, ProgramCreationError>
+    {
+        let sources = ProgramSources::new(vertex_shader, fragment_shader, geometry_shader);
+        self.get_or_create_from_sources(sources)
+    }
+
+    /// Same as `get_or_create`, but takes a `ProgramSources`.
+    pub fn get_or_create_from_sources(&self, sources: ProgramSources)
+                                      -> Result<Arc<Program>, ProgramCreationError>
+    {
+        if let Some(program) = self.programs.lock().unwrap().get(&sources) {
+            return Ok(program.clone());
+        }
+
+        // the lock is not held while compiling, so two threads may compile the same program
+        // at the same time ; in this situation the first one to finish is kept
+        let program = try!(Program::from_source(&self.display, sources.vertex_shader.as_slice(),
+                                                sources.fragment_shader.as_slice(),
+                                                sources.geometry_shader.as_ref()
+                                                       .map(|s| s.as_slice())));
+        let program = Arc::new(program);
+
+        let mut programs = self.programs.lock().unwrap();
+        if let Some(program) = programs.get(&sources) {
+            return Ok(program.clone());
+        }
+        programs.insert(sources, program.clone());
+        Ok(program)
+    }
+
+    /// Compiles all the programs of the list and stores them in the cache.
+    ///
+    /// This is usually called at initialization with a list previously obtained with
+    /// `get_sources`.
+    pub fn preload(&self, sources: Vec<ProgramSources>) -> Result<(), ProgramCreationError> {
+        for src in sources.into_iter() {
+            try!(self.get_or_create_from_sources(src));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the sources of all the programs currently in the cache.
+    pub fn get_sources(&self) -> Vec<ProgramSources> {
+        self.programs.lock().unwrap().keys().map(|k| k.clone()).collect()
+    }
+
+    /// Returns the sources and the binary of all the programs currently in the cache.
+    ///
+    /// Programs whose binary can't be retrieved are skipped. The list is empty if the backend
+    /// doesn't support program binaries.
+    pub fn get_binaries(&self) -> Vec<(ProgramSources, ProgramBinary)> {
+        self.programs.lock().unwrap().iter()
+            .filter_map(|(sources, program)| {
+                program.get_binary().map(|binary| (sources.clone(), binary))
+            })
+            .collect()
+    }
+
+    /// Rebuilds the programs of the list from their binary and stores them in the cache.
+    ///
+    /// This is usually called at initialization with a list previously obtained with
+    /// `get_binaries`. If a binary is rejected, for example because the driver has changed
+    /// since it was retrieved, the program is compiled from its sources instead.
+    pub fn preload_binaries(&self, binaries: Vec<(ProgramSources, ProgramBinary)>)
+                            -> Result<(), ProgramCreationError>
+    {
+        for (sources, binary) in binaries.into_iter() {
+            if self.programs.lock().unwrap().contains_key(&sources) {
+                continue;
+            }
+
+            match Program::from_binary(&self.display, &binary) {
+                Ok(program) => {
+                    self.programs.lock().unwrap().insert(sources, Arc::new(program));
+                },
+                Err(_) => {
+                    try!(self.get_or_create_from_sources(sources));
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of programs in the cache.
+    pub fn len(&self) -> uint {
+        self.programs.lock().unwrap().len()
+    }
+
+    /// Removes all the programs from the cache.
+    ///
+    /// Programs that are still in use elsewhere are not destroyed until they are dropped.
+    pub fn clear(&self) {
+        self.programs.lock().unwrap().clear();
+    }
+}
+
 /// Builds an individual shader.
-fn build_shader<S: ToCStr>(display: &Display, shader_type: gl::types::GLenum, source_code: S)
+fn build_shader(display: &Display, shader_type: gl::types::GLenum, source_code: &str)
     -> Result<Shader, ProgramCreationError>
 {
-    let source_code = source_code.to_c_str();
+    let source_code = source_code.to_string();
+    let precision = *display.context.shader_precision.lock().unwrap();
 
     let (tx, rx) = channel();
     display.context.context.exec(move |: ctxt| {
@@ -328,6 +1373,9 @@ fn build_shader<S: ToCStr>(display: &Display, shader_type: gl::types::GLenum, so
                 return;
             }
 
+            let source_code = preprocess_shader(source_code.as_slice(), shader_type,
+                                                ctxt.opengl_es, &precision).to_c_str();
+
             let id = ctxt.gl.CreateShader(shader_type);
 
             if id == 0 {
@@ -377,6 +1425,191 @@ fn build_shader<S: ToCStr>(display: &Display, shader_type: gl::types::GLenum, so
     })
 }
 
+/// Precision qualifier of a GLSL ES variable.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// `lowp`
+    Low,
+    /// `mediump`
+    Medium,
+    /// `highp`
+    High,
+}
+
+impl Precision {
+    fn to_glsl(&self) -> &'static str {
+        match *self {
+            Precision::Low => "lowp",
+            Precision::Medium => "mediump",
+            Precision::High => "highp",
+        }
+    }
+}
+
+/// How the source code of the shaders is modified before being compiled.
+///
+/// See `Display::set_shader_precision`.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub struct ShaderPrecision {
+    /// Default precision of `float` that is injected in OpenGL ES shaders which don't
+    /// declare one.
+    ///
+    /// If `None`, only fragment shaders receive a default precision of `mediump`, since
+    /// they don't compile without one.
+    pub float: Option<Precision>,
+
+    /// Default precision of `int` that is injected in OpenGL ES shaders which don't
+    /// declare one.
+    pub int: Option<Precision>,
+
+    /// If true, a `glium_mediump` function is made available to all the shaders.
+    ///
+    /// On desktop OpenGL this function rounds its `float`, `vec2`, `vec3` or `vec4` parameter
+    /// to the precision of a 16-bits float, so that precision bugs that would only appear on
+    /// mobile can be caught during development. On OpenGL ES it returns its parameter
+    /// unchanged.
+    pub emulate_mediump: bool,
+}
+
+impl ::std::default::Default for ShaderPrecision {
+    fn default() -> ShaderPrecision {
+        ShaderPrecision {
+            float: None,
+            int: None,
+            emulate_mediump: false,
+        }
+    }
+}
+
+/// Injects default precisions and the mediump emulation helpers into the source code
+/// of a shader.
+fn preprocess_shader(source: &str, shader_type: gl::types::GLenum, opengl_es: bool,
+                     precision: &ShaderPrecision) -> String
+{
+    // returns true if the source declares a default precision for the given type
+    let declares_precision = |&: ty: &str| {
+        source.lines().any(|line| {
+            let words = line.split(|&: c: char| c.is_whitespace() || c == ';')
+                            .filter(|w| !w.is_empty()).collect::<Vec<_>>();
+            words.len() >= 3 && words[0] == "precision" && words[2] == ty
+        })
+    };
+
+    let version = source.lines()
+                        .map(|l| l.trim())
+                        .find(|l| l.starts_with("#version"))
+                        .and_then(|l| l.words().nth(1))
+                        .and_then(|v| v.parse::<uint>())
+                        .unwrap_or(if opengl_es { 100 } else { 110 });
+
+    let mut header = String::new();
+
+    if opengl_es {
+        let float = match precision.float {
+            Some(p) => Some(p),
+            None if shader_type == gl::FRAGMENT_SHADER => Some(Precision::Medium),
+            None => None,
+        };
+
+        if let Some(float) = float {
+            if !declares_precision("float") {
+                header.push_str(format!("precision {} float;\n", float.to_glsl()).as_slice());
+            }
+        }
+
+        if let Some(int) = precision.int {
+            if !declares_precision("int") {
+                header.push_str(format!("precision {} int;\n", int.to_glsl()).as_slice());
+            }
+        }
+    }
+
+    if precision.emulate_mediump {
+        if opengl_es {
+            header.push_str("#define glium_mediump(x) (x)\n");
+
+        } else {
+            // `packHalf2x16` is only available starting with GLSL 4.20
+            if version >= 420 {
+                header.push_str("
+                    float glium_mediump(float x) {
+                        return unpackHalf2x16(packHalf2x16(vec2(x, 0.0))).x;
+                    }
+                ");
+            } else {
+                header.push_str("
+                    float glium_mediump(float x) {
+                        if (x == 0.0) return 0.0;
+                        float e = exp2(floor(log2(abs(x))));
+                        float m = floor(abs(x) / e * 1024.0 + 0.5) / 1024.0;
+                        return sign(x) * min(m * e, 65504.0);
+                    }
+                ");
+            }
+
+            header.push_str("
+                vec2 glium_mediump(vec2 x) {
+                    return vec2(glium_mediump(x.x), glium_mediump(x.y));
+                }
+                vec3 glium_mediump(vec3 x) {
+                    return vec3(glium_mediump(x.xy), glium_mediump(x.z));
+                }
+                vec4 glium_mediump(vec4 x) {
+                    return vec4(glium_mediump(x.xy), glium_mediump(x.zw));
+                }
+            ");
+        }
+    }
+
+    if header.is_empty() {
+        return source.to_string();
+    }
+
+    // the header must be put after the `#version` and `#extension` directives
+    let lines = source.lines().collect::<Vec<_>>();
+    let insert_pos = lines.iter().position(|l| {
+        let l = l.trim();
+        !(l.is_empty() || l.starts_with("//") || l.starts_with("#version") ||
+          l.starts_with("#extension"))
+    }).unwrap_or(lines.len());
+
+    let mut result = String::with_capacity(source.len() + header.len());
+    for line in lines.slice_to(insert_pos).iter() {
+        result.push_str(*line);
+        result.push('\n');
+    }
+    result.push_str(header.as_slice());
+
+    // restores the line numbers so that the compilation errors point to the lines of the
+    // original source; before GLSL 3.30 and GLSL ES 3.00, the number given to `#line` is the
+    // one of the directive itself instead of the next line
+    let next_line = if version >= 330 || (opengl_es && version >= 300) {
+        insert_pos + 1
+    } else {
+        insert_pos
+    };
+    result.push_str(format!("\n#line {}\n", next_line).as_slice());
+
+    for line in lines.slice_from(insert_pos).iter() {
+        result.push_str(*line);
+        result.push('\n');
+    }
+    result
+}
+
+/// Returns the info log of a program, which contains the linking errors.
+unsafe fn get_program_info_log(ctxt: &CommandContext, program: gl::types::GLuint) -> String {
+    let mut error_log_size: gl::types::GLint = mem::uninitialized();
+    ctxt.gl.GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut error_log_size);
+
+    let mut error_log: Vec<u8> = Vec::with_capacity(error_log_size as uint);
+    ctxt.gl.GetProgramInfoLog(program, error_log_size, &mut error_log_size,
+        error_log.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+    error_log.set_len(error_log_size as uint);
+
+    String::from_utf8(error_log).unwrap()
+}
+
 unsafe fn reflect_uniforms(ctxt: &mut CommandContext, program: gl::types::GLuint)
     -> HashMap<String, Uniform>
 {
@@ -410,6 +1643,373 @@ unsafe fn reflect_uniforms(ctxt: &mut CommandContext, program: gl::types::GLuint
     uniforms
 }
 
+unsafe fn reflect_uniform_blocks(ctxt: &mut CommandContext, program: gl::types::GLuint)
+    -> Vec<UniformBlockInfo>
+{
+    let mut active_blocks: gl::types::GLint = mem::uninitialized();
+    ctxt.gl.GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut active_blocks);
+
+    let mut blocks = Vec::with_capacity(active_blocks as uint);
+
+    for block_id in range(0, active_blocks as gl::types::GLuint) {
+        let get_block_param = |&: param: gl::types::GLenum| {
+            let mut value: gl::types::GLint = mem::uninitialized();
+            ctxt.gl.GetActiveUniformBlockiv(program, block_id, param, &mut value);
+            value
+        };
+
+        let name_len = get_block_param(gl::UNIFORM_BLOCK_NAME_LENGTH);
+        let mut name_tmp: Vec<u8> = Vec::with_capacity(name_len as uint);
+        let mut name_tmp_len = 0;
+        ctxt.gl.GetActiveUniformBlockName(program, block_id, name_len, &mut name_tmp_len,
+            name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+        name_tmp.set_len(name_tmp_len as uint);
+
+        let binding = get_block_param(gl::UNIFORM_BLOCK_BINDING);
+        let size = get_block_param(gl::UNIFORM_BLOCK_DATA_SIZE);
+        let members_count = get_block_param(gl::UNIFORM_BLOCK_ACTIVE_UNIFORMS);
+
+        let mut indices: Vec<gl::types::GLint> = Vec::from_elem(members_count as uint, 0);
+        ctxt.gl.GetActiveUniformBlockiv(program, block_id,
+                                        gl::UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES,
+                                        indices.as_mut_ptr());
+        let indices = indices.into_iter().map(|i| i as gl::types::GLuint).collect::<Vec<_>>();
+
+        let get_members_param = |&: param: gl::types::GLenum| {
+            let mut values: Vec<gl::types::GLint> = Vec::from_elem(indices.len(), 0);
+            ctxt.gl.GetActiveUniformsiv(program, indices.len() as gl::types::GLsizei,
+                                        indices.as_ptr(), param, values.as_mut_ptr());
+            values
+        };
+
+        let types = get_members_param(gl::UNIFORM_TYPE);
+        let sizes = get_members_param(gl::UNIFORM_SIZE);
+        let offsets = get_members_param(gl::UNIFORM_OFFSET);
+        let array_strides = get_members_param(gl::UNIFORM_ARRAY_STRIDE);
+        let matrix_strides = get_members_param(gl::UNIFORM_MATRIX_STRIDE);
+
+        let mut members = Vec::with_capacity(indices.len());
+        for (n, &index) in indices.iter().enumerate() {
+            let mut member_name_tmp: Vec<u8> = Vec::with_capacity(64);
+            let mut member_name_tmp_len = 63;
+            let mut data_type: gl::types::GLenum = mem::uninitialized();
+            let mut data_size: gl::types::GLint = mem::uninitialized();
+            ctxt.gl.GetActiveUniform(program, index, member_name_tmp_len,
+                &mut member_name_tmp_len, &mut data_size, &mut data_type,
+                member_name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+            member_name_tmp.set_len(member_name_tmp_len as uint);
+
+            members.push(UniformBlockMember {
+                name: String::from_utf8(member_name_tmp).unwrap(),
+                ty: glenum_to_uniform_type(types[n] as gl::types::GLenum),
+                size: sizes[n] as uint,
+                offset: offsets[n] as uint,
+                array_stride: array_strides[n] as uint,
+                matrix_stride: matrix_strides[n] as uint,
+            });
+        }
+
+        members.sort_by(|a, b| a.offset.cmp(&b.offset));
+
+        blocks.push(UniformBlockInfo {
+            name: String::from_utf8(name_tmp).unwrap(),
+            binding: binding as u32,
+            size: size as uint,
+            members: members,
+        });
+    }
+
+    blocks
+}
+
+unsafe fn reflect_transform_feedback(ctxt: &mut CommandContext, program: gl::types::GLuint)
+    -> Result<Vec<TransformFeedbackVarying>, ProgramCreationError>
+{
+    let mut num_varyings: gl::types::GLint = mem::uninitialized();
+    ctxt.gl.GetProgramiv(program, gl::TRANSFORM_FEEDBACK_VARYINGS, &mut num_varyings);
+
+    let mut buffer_mode: gl::types::GLint = mem::uninitialized();
+    ctxt.gl.GetProgramiv(program, gl::TRANSFORM_FEEDBACK_BUFFER_MODE, &mut buffer_mode);
+    let interleaved = buffer_mode as gl::types::GLenum == gl::INTERLEAVED_ATTRIBS;
+
+    let mut varyings: Vec<TransformFeedbackVarying> = Vec::new();
+    let mut buffer_index = 0;
+    let mut offset = 0;
+
+    for varying_id in range(0, num_varyings) {
+        let mut name_tmp: Vec<u8> = Vec::with_capacity(64);
+        let mut name_tmp_len = 63;
+
+        let mut data_type: gl::types::GLenum = mem::uninitialized();
+        let mut data_size: gl::types::GLint = mem::uninitialized();
+        if ctxt.version >= &GlVersion(3, 0) {
+            ctxt.gl.GetTransformFeedbackVarying(program, varying_id as gl::types::GLuint,
+                name_tmp_len, &mut name_tmp_len, &mut data_size, &mut data_type,
+                name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+        } else {
+            ctxt.gl.GetTransformFeedbackVaryingEXT(program, varying_id as gl::types::GLuint,
+                name_tmp_len, &mut name_tmp_len, &mut data_size, &mut data_type,
+                name_tmp.as_mut_slice().as_mut_ptr() as *mut gl::types::GLchar);
+        }
+        name_tmp.set_len(name_tmp_len as uint);
+
+        let name = String::from_utf8(name_tmp).unwrap();
+
+        if interleaved && ctxt.version >= &GlVersion(4, 0) {
+            // special names that control the layout of the buffers
+            match name.as_slice() {
+                "gl_NextBuffer" => {
+                    buffer_index += 1;
+                    offset = 0;
+                    continue;
+                },
+                "gl_SkipComponents1" => { offset += 4; continue; },
+                "gl_SkipComponents2" => { offset += 8; continue; },
+                "gl_SkipComponents3" => { offset += 12; continue; },
+                "gl_SkipComponents4" => { offset += 16; continue; },
+                _ => ()
+            }
+        }
+
+        let size = match get_varying_type_size(data_type) {
+            Some(size) => size * data_size as uint,
+            None => return Err(ProgramCreationError::UnknownTransformFeedbackVaryingType(name)),
+        };
+
+        varyings.push(TransformFeedbackVarying {
+            name: name,
+            buffer_index: buffer_index,
+            offset: offset,
+            size: size,
+            stride: size,
+            array_size: data_size as uint,
+            ty: get_varying_attribute_type(data_type),
+        });
+
+        if interleaved {
+            offset += size;
+        } else {
+            buffer_index += 1;
+        }
+    }
+
+    // in interleaved mode, the stride is the total size of the varyings of each buffer
+    if interleaved {
+        let strides = varyings.iter().fold(HashMap::new(), |mut strides, v| {
+            let stride = strides.get(&v.buffer_index).map(|s| *s).unwrap_or(0);
+            strides.insert(v.buffer_index, ::std::cmp::max(stride, v.offset + v.size));
+            strides
+        });
+
+        for varying in varyings.iter_mut() {
+            varying.stride = *strides.get(&varying.buffer_index).unwrap();
+        }
+    }
+
+    Ok(varyings)
+}
+
+/// Returns the number of bytes taken by a varying of the given type, or `None` if the type
+/// is unknown.
+fn get_varying_type_size(ty: gl::types::GLenum) -> Option<uint> {
+    Some(match ty {
+        gl::FLOAT | gl::INT | gl::UNSIGNED_INT => 4,
+        gl::FLOAT_VEC2 | gl::INT_VEC2 | gl::UNSIGNED_INT_VEC2 | gl::DOUBLE => 8,
+        gl::FLOAT_VEC3 | gl::INT_VEC3 | gl::UNSIGNED_INT_VEC3 => 12,
+        gl::FLOAT_VEC4 | gl::INT_VEC4 | gl::UNSIGNED_INT_VEC4 | gl::FLOAT_MAT2 |
+        gl::DOUBLE_VEC2 => 16,
+        gl::DOUBLE_VEC3 | gl::FLOAT_MAT2x3 | gl::FLOAT_MAT3x2 => 24,
+        gl::DOUBLE_VEC4 | gl::FLOAT_MAT2x4 | gl::FLOAT_MAT4x2 | gl::DOUBLE_MAT2 => 32,
+        gl::FLOAT_MAT3 => 36,
+        gl::FLOAT_MAT3x4 | gl::FLOAT_MAT4x3 | gl::DOUBLE_MAT2x3 | gl::DOUBLE_MAT3x2 => 48,
+        gl::FLOAT_MAT4 | gl::DOUBLE_MAT2x4 | gl::DOUBLE_MAT4x2 => 64,
+        gl::DOUBLE_MAT3 => 72,
+        gl::DOUBLE_MAT3x4 | gl::DOUBLE_MAT4x3 => 96,
+        gl::DOUBLE_MAT4 => 128,
+        _ => return None
+    })
+}
+
+/// Returns the `UniformType` corresponding to the type of a uniform returned by
+/// `glGetActiveUniform`.
+pub fn glenum_to_uniform_type(ty: gl::types::GLenum) -> UniformType {
+    match ty {
+        gl::FLOAT => UniformType::Float,
+        gl::FLOAT_VEC2 => UniformType::FloatVec2,
+        gl::FLOAT_VEC3 => UniformType::FloatVec3,
+        gl::FLOAT_VEC4 => UniformType::FloatVec4,
+        gl::DOUBLE => UniformType::Double,
+        gl::DOUBLE_VEC2 => UniformType::DoubleVec2,
+        gl::DOUBLE_VEC3 => UniformType::DoubleVec3,
+        gl::DOUBLE_VEC4 => UniformType::DoubleVec4,
+        gl::INT => UniformType::Int,
+        gl::INT_VEC2 => UniformType::IntVec2,
+        gl::INT_VEC3 => UniformType::IntVec3,
+        gl::INT_VEC4 => UniformType::IntVec4,
+        gl::UNSIGNED_INT => UniformType::UnsignedInt,
+        gl::UNSIGNED_INT_VEC2 => UniformType::UnsignedIntVec2,
+        gl::UNSIGNED_INT_VEC3 => UniformType::UnsignedIntVec3,
+        gl::UNSIGNED_INT_VEC4 => UniformType::UnsignedIntVec4,
+        gl::BOOL => UniformType::Bool,
+        gl::BOOL_VEC2 => UniformType::BoolVec2,
+        gl::BOOL_VEC3 => UniformType::BoolVec3,
+        gl::BOOL_VEC4 => UniformType::BoolVec4,
+        gl::FLOAT_MAT2 => UniformType::FloatMat2,
+        gl::FLOAT_MAT3 => UniformType::FloatMat3,
+        gl::FLOAT_MAT4 => UniformType::FloatMat4,
+        gl::FLOAT_MAT2x3 => UniformType::FloatMat2x3,
+        gl::FLOAT_MAT2x4 => UniformType::FloatMat2x4,
+        gl::FLOAT_MAT3x2 => UniformType::FloatMat3x2,
+        gl::FLOAT_MAT3x4 => UniformType::FloatMat3x4,
+        gl::FLOAT_MAT4x2 => UniformType::FloatMat4x2,
+        gl::FLOAT_MAT4x3 => UniformType::FloatMat4x3,
+        gl::DOUBLE_MAT2 => UniformType::DoubleMat2,
+        gl::DOUBLE_MAT3 => UniformType::DoubleMat3,
+        gl::DOUBLE_MAT4 => UniformType::DoubleMat4,
+        gl::DOUBLE_MAT2x3 => UniformType::DoubleMat2x3,
+        gl::DOUBLE_MAT2x4 => UniformType::DoubleMat2x4,
+        gl::DOUBLE_MAT3x2 => UniformType::DoubleMat3x2,
+        gl::DOUBLE_MAT3x4 => UniformType::DoubleMat3x4,
+        gl::DOUBLE_MAT4x2 => UniformType::DoubleMat4x2,
+        gl::DOUBLE_MAT4x3 => UniformType::DoubleMat4x3,
+        gl::SAMPLER_1D => UniformType::Sampler1d,
+        gl::INT_SAMPLER_1D => UniformType::ISampler1d,
+        gl::UNSIGNED_INT_SAMPLER_1D => UniformType::USampler1d,
+        gl::SAMPLER_2D => UniformType::Sampler2d,
+        gl::INT_SAMPLER_2D => UniformType::ISampler2d,
+        gl::UNSIGNED_INT_SAMPLER_2D => UniformType::USampler2d,
+        gl::SAMPLER_3D => UniformType::Sampler3d,
+        gl::INT_SAMPLER_3D => UniformType::ISampler3d,
+        gl::UNSIGNED_INT_SAMPLER_3D => UniformType::USampler3d,
+        gl::SAMPLER_1D_ARRAY => UniformType::Sampler1dArray,
+        gl::INT_SAMPLER_1D_ARRAY => UniformType::ISampler1dArray,
+        gl::UNSIGNED_INT_SAMPLER_1D_ARRAY => UniformType::USampler1dArray,
+        gl::SAMPLER_2D_ARRAY => UniformType::Sampler2dArray,
+        gl::INT_SAMPLER_2D_ARRAY => UniformType::ISampler2dArray,
+        gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => UniformType::USampler2dArray,
+        gl::SAMPLER_CUBE => UniformType::SamplerCube,
+        gl::INT_SAMPLER_CUBE => UniformType::ISamplerCube,
+        gl::UNSIGNED_INT_SAMPLER_CUBE => UniformType::USamplerCube,
+        gl::SAMPLER_2D_RECT => UniformType::Sampler2dRect,
+        gl::INT_SAMPLER_2D_RECT => UniformType::ISampler2dRect,
+        gl::UNSIGNED_INT_SAMPLER_2D_RECT => UniformType::USampler2dRect,
+        gl::SAMPLER_CUBE_MAP_ARRAY => UniformType::SamplerCubeArray,
+        gl::INT_SAMPLER_CUBE_MAP_ARRAY => UniformType::ISamplerCubeArray,
+        gl::UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY => UniformType::USamplerCubeArray,
+        gl::SAMPLER_BUFFER => UniformType::SamplerBuffer,
+        gl::INT_SAMPLER_BUFFER => UniformType::ISamplerBuffer,
+        gl::UNSIGNED_INT_SAMPLER_BUFFER => UniformType::USamplerBuffer,
+        gl::SAMPLER_2D_MULTISAMPLE => UniformType::Sampler2dMultisample,
+        gl::INT_SAMPLER_2D_MULTISAMPLE => UniformType::ISampler2dMultisample,
+        gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE => UniformType::USampler2dMultisample,
+        gl::SAMPLER_2D_MULTISAMPLE_ARRAY => UniformType::Sampler2dMultisampleArray,
+        gl::INT_SAMPLER_2D_MULTISAMPLE_ARRAY => UniformType::ISampler2dMultisampleArray,
+        gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY => UniformType::USampler2dMultisampleArray,
+        gl::SAMPLER_1D_SHADOW => UniformType::Sampler1dShadow,
+        gl::SAMPLER_2D_SHADOW => UniformType::Sampler2dShadow,
+        gl::SAMPLER_CUBE_SHADOW => UniformType::SamplerCubeShadow,
+        gl::SAMPLER_1D_ARRAY_SHADOW => UniformType::Sampler1dArrayShadow,
+        gl::SAMPLER_2D_ARRAY_SHADOW => UniformType::Sampler2dArrayShadow,
+        gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW => UniformType::SamplerCubeArrayShadow,
+        gl::IMAGE_1D => UniformType::Image1d,
+        gl::INT_IMAGE_1D => UniformType::IImage1d,
+        gl::UNSIGNED_INT_IMAGE_1D => UniformType::UImage1d,
+        gl::IMAGE_2D => UniformType::Image2d,
+        gl::INT_IMAGE_2D => UniformType::IImage2d,
+        gl::UNSIGNED_INT_IMAGE_2D => UniformType::UImage2d,
+        gl::IMAGE_3D => UniformType::Image3d,
+        gl::INT_IMAGE_3D => UniformType::IImage3d,
+        gl::UNSIGNED_INT_IMAGE_3D => UniformType::UImage3d,
+        gl::IMAGE_2D_RECT => UniformType::Image2dRect,
+        gl::INT_IMAGE_2D_RECT => UniformType::IImage2dRect,
+        gl::UNSIGNED_INT_IMAGE_2D_RECT => UniformType::UImage2dRect,
+        gl::IMAGE_CUBE => UniformType::ImageCube,
+        gl::INT_IMAGE_CUBE => UniformType::IImageCube,
+        gl::UNSIGNED_INT_IMAGE_CUBE => UniformType::UImageCube,
+        gl::IMAGE_BUFFER => UniformType::ImageBuffer,
+        gl::INT_IMAGE_BUFFER => UniformType::IImageBuffer,
+        gl::UNSIGNED_INT_IMAGE_BUFFER => UniformType::UImageBuffer,
+        gl::IMAGE_1D_ARRAY => UniformType::Image1dArray,
+        gl::INT_IMAGE_1D_ARRAY => UniformType::IImage1dArray,
+        gl::UNSIGNED_INT_IMAGE_1D_ARRAY => UniformType::UImage1dArray,
+        gl::IMAGE_2D_ARRAY => UniformType::Image2dArray,
+        gl::INT_IMAGE_2D_ARRAY => UniformType::IImage2dArray,
+        gl::UNSIGNED_INT_IMAGE_2D_ARRAY => UniformType::UImage2dArray,
+        gl::IMAGE_2D_MULTISAMPLE => UniformType::Image2dMultisample,
+        gl::INT_IMAGE_2D_MULTISAMPLE => UniformType::IImage2dMultisample,
+        gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE => UniformType::UImage2dMultisample,
+        gl::IMAGE_2D_MULTISAMPLE_ARRAY => UniformType::Image2dMultisampleArray,
+        gl::INT_IMAGE_2D_MULTISAMPLE_ARRAY => UniformType::IImage2dMultisampleArray,
+        gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE_ARRAY => UniformType::UImage2dMultisampleArray,
+        gl::UNSIGNED_INT_ATOMIC_COUNTER => UniformType::AtomicCounterUint,
+        _ => panic!("Unknown type of uniform: {}", ty)
+    }
+}
+
+/// Returns the vertex attribute type corresponding to a varying type, if any.
+fn get_varying_attribute_type(ty: gl::types::GLenum) -> Option<AttributeType> {
+    match ty {
+        gl::FLOAT => Some(AttributeType::F32),
+        gl::FLOAT_VEC2 => Some(AttributeType::F32F32),
+        gl::FLOAT_VEC3 => Some(AttributeType::F32F32F32),
+        gl::FLOAT_VEC4 => Some(AttributeType::F32F32F32F32),
+        gl::INT => Some(AttributeType::I32),
+        gl::INT_VEC2 => Some(AttributeType::I32I32),
+        gl::INT_VEC3 => Some(AttributeType::I32I32I32),
+        gl::INT_VEC4 => Some(AttributeType::I32I32I32I32),
+        gl::UNSIGNED_INT => Some(AttributeType::U32),
+        gl::UNSIGNED_INT_VEC2 => Some(AttributeType::U32U32),
+        gl::UNSIGNED_INT_VEC3 => Some(AttributeType::U32U32U32),
+        gl::UNSIGNED_INT_VEC4 => Some(AttributeType::U32U32U32U32),
+        _ => None
+    }
+}
+
+/// Returns an error if some of the attributes use the same locations.
+fn check_attributes_aliasing(attributes: &HashMap<String, Attribute>)
+                             -> Result<(), ProgramCreationError>
+{
+    // list of `(first location, number of locations, name)`
+    let mut ranges = attributes.iter()
+        .filter(|&(_, attribute)| attribute.location >= 0)
+        .map(|(name, attribute)| {
+            let count = get_attribute_locations_count(attribute.ty) * attribute.size as uint;
+            (attribute.location as uint, count, name)
+        })
+        .collect::<Vec<_>>();
+
+    ranges.sort();
+
+    for pair in ranges.windows(2) {
+        let (first_location, first_count, first_name) = pair[0];
+        let (second_location, _, second_name) = pair[1];
+
+        if first_location + first_count > second_location {
+            return Err(ProgramCreationError::AttributeLocationConflict(
+                format!("The attributes `{}` and `{}` both use location {}", first_name,
+                        second_name, second_location)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of locations used by a vertex attribute of this type.
+fn get_attribute_locations_count(ty: gl::types::GLenum) -> uint {
+    match ty {
+        gl::FLOAT_MAT2 | gl::FLOAT_MAT2x3 | gl::FLOAT_MAT2x4 => 2,
+        gl::FLOAT_MAT3 | gl::FLOAT_MAT3x2 | gl::FLOAT_MAT3x4 => 3,
+        gl::FLOAT_MAT4 | gl::FLOAT_MAT4x2 | gl::FLOAT_MAT4x3 => 4,
+        gl::DOUBLE_VEC3 | gl::DOUBLE_VEC4 => 2,
+        gl::DOUBLE_MAT2 => 2,
+        gl::DOUBLE_MAT3x2 => 3,
+        gl::DOUBLE_MAT2x3 | gl::DOUBLE_MAT2x4 | gl::DOUBLE_MAT4x2 => 4,
+        gl::DOUBLE_MAT3 | gl::DOUBLE_MAT3x4 => 6,
+        gl::DOUBLE_MAT4 | gl::DOUBLE_MAT4x3 => 8,
+        _ => 1
+    }
+}
+
 unsafe fn reflect_attributes(ctxt: &mut CommandContext, program: gl::types::GLuint)
     -> HashMap<String, Attribute>
 {
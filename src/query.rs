@@ -0,0 +1,217 @@
+/*!
+Occlusion queries and conditional rendering.
+
+An `OcclusionQuery` counts the number of samples that pass the depth test while it is active.
+A typical use is to draw a cheap bounding box of an object with the query active, then to
+draw the real object only if some of the bounding box was visible. Reading the result of the
+query on the CPU would stall until the GPU has caught up, so instead the draw can be made
+conditional with the `condition` member of the `DrawParameters`. The GPU then decides by
+itself whether the draw commands are executed.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+use glium::query::{OcclusionQuery, WaitMode};
+
+let query = OcclusionQuery::new(&display).expect("occlusion queries are not supported");
+
+{
+    let _active = query.begin();
+    // draw the bounding box here
+}
+
+let parameters = glium::DrawParameters {
+    condition: Some(query.condition(WaitMode::Wait)),
+    .. std::default::Default::default()
+};
+
+// draw the object with these parameters
+```
+
+*/
+use {Display, ConditionalRendering, GlObject};
+
+use gl;
+
+/// What the GPU does when a conditional draw command is reached while the result of the
+/// query is not yet available.
+#[deriving(Clone, Copy, Show, PartialEq, Eq)]
+pub enum WaitMode {
+    /// The GPU waits for the result of the query.
+    Wait,
+
+    /// The GPU doesn't wait and executes the draw commands if the result is not available.
+    NoWait,
+
+    /// Same as `Wait`, but the result may be evaluated separately for each region of the
+    /// framebuffer. The backend is free to fall back to `Wait`.
+    ByRegionWait,
+
+    /// Same as `NoWait`, but the result may be evaluated separately for each region of the
+    /// framebuffer. The backend is free to fall back to `NoWait`.
+    ByRegionNoWait,
+}
+
+impl ::ToGlEnum for WaitMode {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            WaitMode::Wait => gl::QUERY_WAIT,
+            WaitMode::NoWait => gl::QUERY_NO_WAIT,
+            WaitMode::ByRegionWait => gl::QUERY_BY_REGION_WAIT,
+            WaitMode::ByRegionNoWait => gl::QUERY_BY_REGION_NO_WAIT,
+        }
+    }
+}
+
+/// Counts the number of samples that pass the depth test.
+///
+/// The query is deleted when the object is destroyed.
+pub struct OcclusionQuery {
+    display: Display,
+    id: gl::types::GLuint,
+    target: gl::types::GLenum,
+}
+
+impl OcclusionQuery {
+    /// Creates a new query. Returns `None` if the backend doesn't support occlusion queries.
+    pub fn new(display: &Display) -> Option<OcclusionQuery> {
+        use std::mem;
+
+        if !display.context.context.capabilities().occlusion_query {
+            return None;
+        }
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: ctxt| {
+            unsafe {
+                let mut id = mem::uninitialized();
+                ctxt.gl.GenQueries(1, &mut id);
+                tx.send(id);
+            }
+        });
+
+        // OpenGL ES can only tell whether any sample has passed
+        let target = if display.context.context.capabilities().opengl_es {
+            gl::ANY_SAMPLES_PASSED
+        } else {
+            gl::SAMPLES_PASSED
+        };
+
+        Some(OcclusionQuery {
+            display: display.clone(),
+            id: rx.recv(),
+            target: target,
+        })
+    }
+
+    /// Starts counting the samples of the draw commands that are submitted until the
+    /// returned object is destroyed. The previous result of the query is discarded.
+    ///
+    /// Only one occlusion query can be active at a time.
+    pub fn begin(&self) -> ActiveOcclusionQuery {
+        let id = self.id;
+        let target = self.target;
+
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.BeginQuery(target, id);
+            }
+        });
+
+        ActiveOcclusionQuery {
+            query: self,
+        }
+    }
+
+    /// Returns true if the result of the query is available. Doesn't block.
+    pub fn is_ready(&self) -> bool {
+        self.get_value(gl::QUERY_RESULT_AVAILABLE) != 0
+    }
+
+    /// Returns the number of samples that passed the depth test. Blocks until the result is
+    /// available.
+    ///
+    /// With OpenGL ES, the backend can only tell whether any sample has passed, and this
+    /// function returns either 0 or 1.
+    pub fn get(&self) -> u32 {
+        self.get_value(gl::QUERY_RESULT)
+    }
+
+    /// Returns true if at least one sample passed the depth test. Blocks until the result is
+    /// available.
+    pub fn any_samples_passed(&self) -> bool {
+        self.get() != 0
+    }
+
+    /// Builds a condition for the `DrawParameters`, so that draw commands are only executed
+    /// if at least one sample passed the depth test while this query was active.
+    ///
+    /// The query must have been used at least once before the draw commands that use the
+    /// condition are submitted.
+    pub fn condition(&self, wait: WaitMode) -> ConditionalRendering {
+        ConditionalRendering {
+            query: self,
+            wait: wait,
+        }
+    }
+
+    fn get_value(&self, pname: gl::types::GLenum) -> u32 {
+        use std::mem;
+
+        let id = self.id;
+        let (tx, rx) = channel();
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                let mut value = mem::uninitialized();
+                ctxt.gl.GetQueryObjectuiv(id, pname, &mut value);
+                tx.send(value);
+            }
+        });
+
+        rx.recv()
+    }
+}
+
+impl Drop for OcclusionQuery {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.DeleteQueries(1, [id].as_ptr());
+            }
+        });
+    }
+}
+
+impl GlObject for OcclusionQuery {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.id
+    }
+}
+
+/// An occlusion query that is currently counting samples.
+///
+/// The query ends when the object is destroyed.
+pub struct ActiveOcclusionQuery<'a> {
+    query: &'a OcclusionQuery,
+}
+
+impl<'a> Drop for ActiveOcclusionQuery<'a> {
+    fn drop(&mut self) {
+        let target = self.query.target;
+        self.query.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.EndQuery(target);
+            }
+        });
+    }
+}
+
+/// Returns true if the backend supports occlusion queries.
+pub fn is_supported(display: &Display) -> bool {
+    display.context.context.capabilities().occlusion_query
+}
+
+/// Returns true if the backend supports conditional rendering.
+pub fn is_conditional_rendering_supported(display: &Display) -> bool {
+    display.context.context.capabilities().conditional_render
+}
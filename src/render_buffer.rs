@@ -8,6 +8,7 @@ the data of the render buffer.
 
 */
 use std::sync::Arc;
+use std::sync::atomic::Relaxed;
 use std::mem;
 
 use framebuffer::{ColorAttachment, ToColorAttachment};
@@ -16,7 +17,7 @@ use framebuffer::{StencilAttachment, ToStencilAttachment};
 use framebuffer::{DepthStencilAttachment, ToDepthStencilAttachment};
 use texture::{UncompressedFloatFormat, DepthFormat, StencilFormat, DepthStencilFormat};
 
-use {fbo, gl, context};
+use {fbo, gl, context, ops};
 use {GlObject, DisplayImpl, ToGlEnum};
 
 /// A render buffer is similar to a texture, but is optimized for usage as a draw target.
@@ -31,8 +32,31 @@ impl RenderBuffer {
     pub fn new(display: &::Display, format: UncompressedFloatFormat, width: u32, height: u32)
                -> RenderBuffer
     {
+        RenderBuffer::new_impl(display, format, width, height, None)
+    }
+
+    /// Builds a new multisampled render buffer.
+    ///
+    /// The content of a multisampled render buffer can't be read directly. Attach it to a
+    /// framebuffer, draw on it, then resolve it to a regular texture or render buffer with
+    /// `Surface::resolve_to`.
+    ///
+    /// Panics if `samples` is 0 or is greater than the maximum number of samples supported
+    /// by the backend.
+    pub fn new_multisample(display: &::Display, format: UncompressedFloatFormat, width: u32,
+                           height: u32, samples: u32) -> RenderBuffer
+    {
+        RenderBuffer::new_impl(display, format, width, height, Some(samples))
+    }
+
+    fn new_impl(display: &::Display, format: UncompressedFloatFormat, width: u32, height: u32,
+                samples: Option<u32>) -> RenderBuffer
+    {
+        let buffer = RenderBufferImpl::new(display, format.to_glenum(), width, height, samples);
+        buffer.zero_initialize(display, true, false, false);
+
         RenderBuffer {
-            buffer: RenderBufferImpl::new(display, format.to_glenum(), width, height)
+            buffer: buffer
         }
     }
 
@@ -40,6 +64,12 @@ impl RenderBuffer {
     pub fn get_dimensions(&self) -> (u32, u32) {
         (self.buffer.width, self.buffer.height)
     }
+
+    /// Returns the number of samples per pixel, or `None` if the render buffer is not
+    /// multisampled.
+    pub fn get_samples(&self) -> Option<u32> {
+        self.buffer.samples
+    }
 }
 
 impl ToColorAttachment for RenderBuffer {
@@ -67,8 +97,26 @@ impl DepthRenderBuffer {
     pub fn new(display: &::Display, format: DepthFormat, width: u32, height: u32)
                -> DepthRenderBuffer
     {
+        DepthRenderBuffer::new_impl(display, format, width, height, None)
+    }
+
+    /// Builds a new multisampled render buffer.
+    ///
+    /// See `RenderBuffer::new_multisample`.
+    pub fn new_multisample(display: &::Display, format: DepthFormat, width: u32,
+                           height: u32, samples: u32) -> DepthRenderBuffer
+    {
+        DepthRenderBuffer::new_impl(display, format, width, height, Some(samples))
+    }
+
+    fn new_impl(display: &::Display, format: DepthFormat, width: u32, height: u32,
+                samples: Option<u32>) -> DepthRenderBuffer
+    {
+        let buffer = RenderBufferImpl::new(display, format.to_glenum(), width, height, samples);
+        buffer.zero_initialize(display, false, true, false);
+
         DepthRenderBuffer {
-            buffer: RenderBufferImpl::new(display, format.to_glenum(), width, height)
+            buffer: buffer
         }
     }
 
@@ -76,6 +124,12 @@ impl DepthRenderBuffer {
     pub fn get_dimensions(&self) -> (u32, u32) {
         (self.buffer.width, self.buffer.height)
     }
+
+    /// Returns the number of samples per pixel, or `None` if the render buffer is not
+    /// multisampled.
+    pub fn get_samples(&self) -> Option<u32> {
+        self.buffer.samples
+    }
 }
 
 impl ToDepthAttachment for DepthRenderBuffer {
@@ -103,8 +157,26 @@ impl StencilRenderBuffer {
     pub fn new(display: &::Display, format: StencilFormat, width: u32, height: u32)
                -> StencilRenderBuffer
     {
+        StencilRenderBuffer::new_impl(display, format, width, height, None)
+    }
+
+    /// Builds a new multisampled render buffer.
+    ///
+    /// See `RenderBuffer::new_multisample`.
+    pub fn new_multisample(display: &::Display, format: StencilFormat, width: u32,
+                           height: u32, samples: u32) -> StencilRenderBuffer
+    {
+        StencilRenderBuffer::new_impl(display, format, width, height, Some(samples))
+    }
+
+    fn new_impl(display: &::Display, format: StencilFormat, width: u32, height: u32,
+                samples: Option<u32>) -> StencilRenderBuffer
+    {
+        let buffer = RenderBufferImpl::new(display, format.to_glenum(), width, height, samples);
+        buffer.zero_initialize(display, false, false, true);
+
         StencilRenderBuffer {
-            buffer: RenderBufferImpl::new(display, format.to_glenum(), width, height)
+            buffer: buffer
         }
     }
 
@@ -112,6 +184,12 @@ impl StencilRenderBuffer {
     pub fn get_dimensions(&self) -> (u32, u32) {
         (self.buffer.width, self.buffer.height)
     }
+
+    /// Returns the number of samples per pixel, or `None` if the render buffer is not
+    /// multisampled.
+    pub fn get_samples(&self) -> Option<u32> {
+        self.buffer.samples
+    }
 }
 
 impl ToStencilAttachment for StencilRenderBuffer {
@@ -139,8 +217,26 @@ impl DepthStencilRenderBuffer {
     pub fn new(display: &::Display, format: DepthStencilFormat, width: u32, height: u32)
                -> DepthStencilRenderBuffer
     {
+        DepthStencilRenderBuffer::new_impl(display, format, width, height, None)
+    }
+
+    /// Builds a new multisampled render buffer.
+    ///
+    /// See `RenderBuffer::new_multisample`.
+    pub fn new_multisample(display: &::Display, format: DepthStencilFormat, width: u32,
+                           height: u32, samples: u32) -> DepthStencilRenderBuffer
+    {
+        DepthStencilRenderBuffer::new_impl(display, format, width, height, Some(samples))
+    }
+
+    fn new_impl(display: &::Display, format: DepthStencilFormat, width: u32, height: u32,
+                samples: Option<u32>) -> DepthStencilRenderBuffer
+    {
+        let buffer = RenderBufferImpl::new(display, format.to_glenum(), width, height, samples);
+        buffer.zero_initialize(display, false, true, true);
+
         DepthStencilRenderBuffer {
-            buffer: RenderBufferImpl::new(display, format.to_glenum(), width, height)
+            buffer: buffer
         }
     }
 
@@ -148,6 +244,12 @@ impl DepthStencilRenderBuffer {
     pub fn get_dimensions(&self) -> (u32, u32) {
         (self.buffer.width, self.buffer.height)
     }
+
+    /// Returns the number of samples per pixel, or `None` if the render buffer is not
+    /// multisampled.
+    pub fn get_samples(&self) -> Option<u32> {
+        self.buffer.samples
+    }
 }
 
 impl ToDepthStencilAttachment for DepthStencilRenderBuffer {
@@ -168,19 +270,34 @@ struct RenderBufferImpl {
     id: gl::types::GLuint,
     width: u32,
     height: u32,
+    samples: Option<u32>,
 }
 
 impl RenderBufferImpl {
     /// Builds a new render buffer.
-    fn new(display: &::Display, format: gl::types::GLenum, width: u32, height: u32)
-           -> RenderBufferImpl
+    fn new(display: &::Display, format: gl::types::GLenum, width: u32, height: u32,
+           samples: Option<u32>) -> RenderBufferImpl
     {
+        if let Some(samples) = samples {
+            let max_samples = display.context.context.capabilities().max_samples;
+
+            if max_samples == 0 {
+                panic!("Multisampled render buffers are not supported by the backend");
+            }
+
+            if samples == 0 || samples > max_samples as u32 {
+                panic!("The number of samples must be between 1 and {}", max_samples);
+            }
+        }
+
         let (tx, rx) = channel();
 
         display.context.context.exec(move |: ctxt| {
             unsafe {
                 let id: gl::types::GLuint = mem::uninitialized();
-                if ctxt.version >= &context::GlVersion(3, 0) {
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.CreateRenderbuffers(1, mem::transmute(&id));
+                } else if ctxt.version >= &context::GlVersion(3, 0) {
                     ctxt.gl.GenRenderbuffers(1, mem::transmute(&id));
                 } else {
                     ctxt.gl.GenRenderbuffersEXT(1, mem::transmute(&id));
@@ -189,20 +306,51 @@ impl RenderBufferImpl {
                 tx.send(id);
 
                 // TODO: check that dimensions don't exceed GL_MAX_RENDERBUFFER_SIZE
-                if ctxt.version >= &context::GlVersion(4, 5) {
-                    ctxt.gl.NamedRenderbufferStorage(id, format, width as gl::types::GLsizei,
-                                                     height as gl::types::GLsizei);
-
-                } else if ctxt.extensions.gl_ext_direct_state_access {
-                    ctxt.gl.NamedRenderbufferStorageEXT(id, format, width as gl::types::GLsizei,
+                match samples {
+                    None => {
+                        if ctxt.capabilities.direct_state_access {
+                            ctxt.gl.NamedRenderbufferStorage(id, format,
+                                                             width as gl::types::GLsizei,
+                                                             height as gl::types::GLsizei);
+
+                        } else if ctxt.extensions.gl_ext_direct_state_access {
+                            ctxt.gl.NamedRenderbufferStorageEXT(id, format,
+                                                                width as gl::types::GLsizei,
+                                                                height as gl::types::GLsizei);
+
+                        } else {
+                            ctxt.gl.BindRenderbuffer(gl::RENDERBUFFER, id);
+                            ctxt.state.renderbuffer = id;
+                            ctxt.state.statistics.state_changes += 1;
+                            ctxt.gl.RenderbufferStorage(gl::RENDERBUFFER, format,
+                                                        width as gl::types::GLsizei,
                                                         height as gl::types::GLsizei);
-
-                } else {
-                    ctxt.gl.BindRenderbuffer(gl::RENDERBUFFER, id);
-                    ctxt.state.renderbuffer = id;
-                    ctxt.gl.RenderbufferStorage(gl::RENDERBUFFER, format,
-                                                width as gl::types::GLsizei,
-                                                height as gl::types::GLsizei);
+                        }
+                    },
+
+                    Some(samples) => {
+                        let samples = samples as gl::types::GLsizei;
+
+                        if ctxt.capabilities.direct_state_access {
+                            ctxt.gl.NamedRenderbufferStorageMultisample(id, samples, format,
+                                                                width as gl::types::GLsizei,
+                                                                height as gl::types::GLsizei);
+
+                        } else if ctxt.extensions.gl_ext_direct_state_access {
+                            ctxt.gl.NamedRenderbufferStorageMultisampleEXT(id, samples, format,
+                                                                width as gl::types::GLsizei,
+                                                                height as gl::types::GLsizei);
+
+                        } else {
+                            ctxt.gl.BindRenderbuffer(gl::RENDERBUFFER, id);
+                            ctxt.state.renderbuffer = id;
+                            ctxt.state.statistics.state_changes += 1;
+                            ctxt.gl.RenderbufferStorageMultisample(gl::RENDERBUFFER, samples,
+                                                                   format,
+                                                                   width as gl::types::GLsizei,
+                                                                   height as gl::types::GLsizei);
+                        }
+                    },
                 }
             }
         });
@@ -212,41 +360,41 @@ impl RenderBufferImpl {
             id: rx.recv(),
             width: width,
             height: height,
+            samples: samples,
         }
     }
-}
 
-impl Drop for RenderBufferImpl {
-    fn drop(&mut self) {
-        // removing FBOs which contain this buffer
-        {
-            let mut fbos = self.display.framebuffer_objects.lock().unwrap();
+    /// Fills the render buffer with zeroes if the display requires it.
+    fn zero_initialize(&self, display: &::Display, color: bool, depth: bool, stencil: bool) {
+        if !display.context.zero_initialization.load(Relaxed) {
+            return;
+        }
 
-            let to_delete = fbos.keys().filter(|b| {
-                b.colors.iter().find(|&&(_, id)| id == fbo::Attachment::RenderBuffer(self.id)).is_some() ||
-                b.depth == Some(fbo::Attachment::RenderBuffer(self.id)) || b.stencil == Some(fbo::Attachment::RenderBuffer(self.id))
-            }).map(|k| k.clone()).collect::<Vec<_>>();
+        let attachment = fbo::Attachment::RenderBuffer(self.id);
+        let attachments = fbo::FramebufferAttachments {
+            colors: if color { vec![(0, attachment)] } else { Vec::new() },
+            depth: if depth { Some(attachment) } else { None },
+            stencil: if stencil { Some(attachment) } else { None },
+        };
 
-            for k in to_delete.into_iter() {
-                fbos.remove(&k);
-            }
+        if color {
+            ops::clear_color(&display.context, Some(&attachments), 0.0, 0.0, 0.0, 0.0);
         }
 
-        let id = self.id.clone();
-        self.display.context.exec(move |: ctxt| {
-            unsafe {
-                if ctxt.state.renderbuffer == id {
-                    ctxt.gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
-                    ctxt.state.renderbuffer = 0;
-                }
+        if depth {
+            ops::clear_depth(&display.context, Some(&attachments), 0.0);
+        }
 
-                if ctxt.version >= &context::GlVersion(3, 0) {
-                    ctxt.gl.DeleteRenderbuffers(1, [ id ].as_ptr());
-                } else {
-                    ctxt.gl.DeleteRenderbuffersEXT(1, [ id ].as_ptr());
-                }
-            }
-        });
+        if stencil {
+            ops::clear_stencil(&display.context, Some(&attachments), 0);
+        }
+    }
+}
+
+impl Drop for RenderBufferImpl {
+    fn drop(&mut self) {
+        // also removes the FBOs which contain this buffer
+        self.display.delete_object(context::Deletion::Renderbuffer(self.id));
     }
 }
 
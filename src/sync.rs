@@ -0,0 +1,138 @@
+/*!
+Fences that let you know when the GPU has finished executing some commands.
+
+When you call functions in glium, they are not instantly executed. Instead they are added in
+a commands queue that the backend executes asynchronously. A `SyncFence` is inserted in this
+queue and becomes signaled once all the commands that were submitted before it have been
+executed. This lets you know when a readback into a pixel buffer or an upload has actually
+completed, without calling `Display::synchronize`.
+
+```no_run
+# let display: glium::Display = unsafe { ::std::mem::uninitialized() };
+use glium::sync::SyncFence;
+
+// ... submit some commands ...
+let fence = SyncFence::new(&display).expect("fences are not supported");
+
+while !fence.is_signaled() {
+    // do some other work
+}
+```
+
+*/
+use Display;
+use gl;
+
+/// A fence in the commands queue.
+///
+/// The fence is deleted when the object is destroyed.
+pub struct SyncFence {
+    display: Display,
+    // the `GLsync` is a raw pointer, which can't be sent to the rendering thread
+    id: uint,
+}
+
+impl SyncFence {
+    /// Inserts a fence in the commands queue, after all the commands that have been
+    /// submitted so far.
+    ///
+    /// Returns `None` if the backend doesn't support fences.
+    pub fn new(display: &Display) -> Option<SyncFence> {
+        if !is_supported(display) {
+            return None;
+        }
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: ctxt| {
+            unsafe {
+                let fence = ctxt.gl.FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+                tx.send(fence as uint);
+
+                // other contexts can only wait for fences that have been flushed
+                ctxt.gl.Flush();
+            }
+        });
+
+        Some(SyncFence {
+            display: display.clone(),
+            id: rx.recv(),
+        })
+    }
+
+    /// Returns true if all the commands that were submitted before the fence have been
+    /// executed. Doesn't block.
+    pub fn is_signaled(&self) -> bool {
+        self.client_wait(0)
+    }
+
+    /// Blocks until all the commands that were submitted before the fence have been
+    /// executed.
+    pub fn wait(&self) {
+        self.client_wait(::std::u64::MAX);
+    }
+
+    /// Blocks until all the commands that were submitted before the fence have been
+    /// executed, or until `timeout_ns` nanoseconds have elapsed.
+    ///
+    /// Returns true if the fence has been signaled, and false if the timeout has expired.
+    pub fn wait_timeout(&self, timeout_ns: u64) -> bool {
+        self.client_wait(timeout_ns)
+    }
+
+    /// Makes the GPU wait for the fence before executing the commands that are submitted
+    /// afterwards. Doesn't block.
+    ///
+    /// Commands of the same display are already executed in order, so you usually want
+    /// `wait_server_in` instead.
+    pub fn wait_server(&self) {
+        self.wait_server_in(&self.display);
+    }
+
+    /// Makes the GPU wait for the fence before executing the commands that are submitted to
+    /// `display` afterwards. Doesn't block.
+    ///
+    /// `display` must share its objects with the display that created the fence, for example
+    /// because it has been built with `Display::build_shared`. This is how the objects that
+    /// have been uploaded by a shared display are made safe to use.
+    pub fn wait_server_in(&self, display: &Display) {
+        let id = self.id;
+        display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.WaitSync(id as gl::types::GLsync, 0, gl::TIMEOUT_IGNORED);
+            }
+        });
+    }
+
+    fn client_wait(&self, timeout_ns: u64) -> bool {
+        let id = self.id;
+        let (tx, rx) = channel();
+
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                // the flush ensures that the fence is submitted, otherwise it would never
+                // be signaled
+                let result = ctxt.gl.ClientWaitSync(id as gl::types::GLsync,
+                                                    gl::SYNC_FLUSH_COMMANDS_BIT, timeout_ns);
+                tx.send(result == gl::ALREADY_SIGNALED || result == gl::CONDITION_SATISFIED);
+            }
+        });
+
+        rx.recv()
+    }
+}
+
+impl Drop for SyncFence {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.DeleteSync(id as gl::types::GLsync);
+            }
+        });
+    }
+}
+
+/// Returns true if the backend supports fences.
+pub fn is_supported(display: &Display) -> bool {
+    display.context.context.capabilities().fence_sync
+}
@@ -0,0 +1,481 @@
+use gl;
+
+use std::kinds::marker::ContravariantLifetime;
+use std::mem;
+use std::sync::Arc;
+
+use buffer::{mod, Buffer};
+use uniforms::{UniformValue, IntoUniformValue};
+use {context, Display, DisplayImpl, GlObject};
+
+/// Type of the data contained in a buffer texture.
+///
+/// Determines which kind of sampler the texture can be binded to.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum BufferTextureType {
+    /// The texture must be binded to a `samplerBuffer`.
+    Float,
+    /// The texture must be binded to an `isamplerBuffer`.
+    Integral,
+    /// The texture must be binded to a `usamplerBuffer`.
+    Unsigned,
+}
+
+/// Error that can happen when creating a buffer texture.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum BufferTextureCreationError {
+    /// Buffer textures are not supported by the backend.
+    NotSupported,
+
+    /// Buffer textures of three 32-bits components require OpenGL 4.0 or the
+    /// `GL_ARB_texture_buffer_object_rgb32` extension.
+    FormatNotSupported,
+
+    /// The number of texels is larger than `GL_MAX_TEXTURE_BUFFER_SIZE`.
+    TooLarge,
+
+    /// Binding a range of a buffer requires OpenGL 4.3 or the `GL_ARB_texture_buffer_range`
+    /// extension.
+    RangeNotSupported,
+
+    /// The requested range is outside of the buffer.
+    OutOfRange,
+
+    /// The offset of the requested range is not a multiple of
+    /// `GL_TEXTURE_BUFFER_OFFSET_ALIGNMENT`.
+    WrongAlignment,
+}
+
+impl ::std::error::Error for BufferTextureCreationError {
+    fn description(&self) -> &str {
+        match self {
+            &BufferTextureCreationError::NotSupported => "Buffer textures are not supported by \
+                                                          the backend",
+            &BufferTextureCreationError::FormatNotSupported => "The format of the buffer \
+                                                                texture is not supported by \
+                                                                the backend",
+            &BufferTextureCreationError::TooLarge => "The buffer texture is too large",
+            &BufferTextureCreationError::RangeNotSupported => "Binding a range of a buffer is \
+                                                               not supported by the backend",
+            &BufferTextureCreationError::OutOfRange => "The range is outside of the buffer",
+            &BufferTextureCreationError::WrongAlignment => "The offset of the range is not \
+                                                            correctly aligned",
+        }
+    }
+}
+
+/// A trait that must be implemented for any type that can be stored in a buffer texture.
+pub trait TextureBufferContent: Copy + Send {
+    /// Returns the internal format of the texture when it contains this type.
+    fn get_internal_format(Option<Self>) -> gl::types::GLenum;
+
+    /// Returns the type of sampler the texture can be binded to.
+    fn get_type(Option<Self>) -> BufferTextureType;
+}
+
+impl TextureBufferContent for u8 {
+    fn get_internal_format(_: Option<u8>) -> gl::types::GLenum { gl::R8 }
+    fn get_type(_: Option<u8>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (u8, u8) {
+    fn get_internal_format(_: Option<(u8, u8)>) -> gl::types::GLenum { gl::RG8 }
+    fn get_type(_: Option<(u8, u8)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (u8, u8, u8, u8) {
+    fn get_internal_format(_: Option<(u8, u8, u8, u8)>) -> gl::types::GLenum { gl::RGBA8 }
+    fn get_type(_: Option<(u8, u8, u8, u8)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for u16 {
+    fn get_internal_format(_: Option<u16>) -> gl::types::GLenum { gl::R16 }
+    fn get_type(_: Option<u16>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (u16, u16) {
+    fn get_internal_format(_: Option<(u16, u16)>) -> gl::types::GLenum { gl::RG16 }
+    fn get_type(_: Option<(u16, u16)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (u16, u16, u16, u16) {
+    fn get_internal_format(_: Option<(u16, u16, u16, u16)>) -> gl::types::GLenum { gl::RGBA16 }
+    fn get_type(_: Option<(u16, u16, u16, u16)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for f32 {
+    fn get_internal_format(_: Option<f32>) -> gl::types::GLenum { gl::R32F }
+    fn get_type(_: Option<f32>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (f32, f32) {
+    fn get_internal_format(_: Option<(f32, f32)>) -> gl::types::GLenum { gl::RG32F }
+    fn get_type(_: Option<(f32, f32)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (f32, f32, f32) {
+    fn get_internal_format(_: Option<(f32, f32, f32)>) -> gl::types::GLenum { gl::RGB32F }
+    fn get_type(_: Option<(f32, f32, f32)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for (f32, f32, f32, f32) {
+    fn get_internal_format(_: Option<(f32, f32, f32, f32)>) -> gl::types::GLenum { gl::RGBA32F }
+    fn get_type(_: Option<(f32, f32, f32, f32)>) -> BufferTextureType { BufferTextureType::Float }
+}
+
+impl TextureBufferContent for i8 {
+    fn get_internal_format(_: Option<i8>) -> gl::types::GLenum { gl::R8I }
+    fn get_type(_: Option<i8>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i8, i8) {
+    fn get_internal_format(_: Option<(i8, i8)>) -> gl::types::GLenum { gl::RG8I }
+    fn get_type(_: Option<(i8, i8)>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i8, i8, i8, i8) {
+    fn get_internal_format(_: Option<(i8, i8, i8, i8)>) -> gl::types::GLenum { gl::RGBA8I }
+    fn get_type(_: Option<(i8, i8, i8, i8)>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for i16 {
+    fn get_internal_format(_: Option<i16>) -> gl::types::GLenum { gl::R16I }
+    fn get_type(_: Option<i16>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i16, i16) {
+    fn get_internal_format(_: Option<(i16, i16)>) -> gl::types::GLenum { gl::RG16I }
+    fn get_type(_: Option<(i16, i16)>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i16, i16, i16, i16) {
+    fn get_internal_format(_: Option<(i16, i16, i16, i16)>) -> gl::types::GLenum { gl::RGBA16I }
+    fn get_type(_: Option<(i16, i16, i16, i16)>) -> BufferTextureType {
+        BufferTextureType::Integral
+    }
+}
+
+impl TextureBufferContent for i32 {
+    fn get_internal_format(_: Option<i32>) -> gl::types::GLenum { gl::R32I }
+    fn get_type(_: Option<i32>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i32, i32) {
+    fn get_internal_format(_: Option<(i32, i32)>) -> gl::types::GLenum { gl::RG32I }
+    fn get_type(_: Option<(i32, i32)>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i32, i32, i32) {
+    fn get_internal_format(_: Option<(i32, i32, i32)>) -> gl::types::GLenum { gl::RGB32I }
+    fn get_type(_: Option<(i32, i32, i32)>) -> BufferTextureType { BufferTextureType::Integral }
+}
+
+impl TextureBufferContent for (i32, i32, i32, i32) {
+    fn get_internal_format(_: Option<(i32, i32, i32, i32)>) -> gl::types::GLenum { gl::RGBA32I }
+    fn get_type(_: Option<(i32, i32, i32, i32)>) -> BufferTextureType {
+        BufferTextureType::Integral
+    }
+}
+
+impl TextureBufferContent for u32 {
+    fn get_internal_format(_: Option<u32>) -> gl::types::GLenum { gl::R32UI }
+    fn get_type(_: Option<u32>) -> BufferTextureType { BufferTextureType::Unsigned }
+}
+
+impl TextureBufferContent for (u32, u32) {
+    fn get_internal_format(_: Option<(u32, u32)>) -> gl::types::GLenum { gl::RG32UI }
+    fn get_type(_: Option<(u32, u32)>) -> BufferTextureType { BufferTextureType::Unsigned }
+}
+
+impl TextureBufferContent for (u32, u32, u32) {
+    fn get_internal_format(_: Option<(u32, u32, u32)>) -> gl::types::GLenum { gl::RGB32UI }
+    fn get_type(_: Option<(u32, u32, u32)>) -> BufferTextureType { BufferTextureType::Unsigned }
+}
+
+impl TextureBufferContent for (u32, u32, u32, u32) {
+    fn get_internal_format(_: Option<(u32, u32, u32, u32)>) -> gl::types::GLenum { gl::RGBA32UI }
+    fn get_type(_: Option<(u32, u32, u32, u32)>) -> BufferTextureType {
+        BufferTextureType::Unsigned
+    }
+}
+
+/// A one-dimensional texture whose content is stored in a buffer.
+///
+/// Buffer textures can't be sampled with filtering. Instead you access their content with
+/// `texelFetch` in your shaders. Depending on `T`, a buffer texture must be binded to a
+/// `samplerBuffer`, an `isamplerBuffer` or a `usamplerBuffer`.
+///
+/// Buffer textures can be much larger than regular one-dimensional textures, which makes them
+/// the best way to send large arrays of per-object data to your shaders.
+pub struct BufferTexture<T> {
+    display: Arc<DisplayImpl>,
+    buffer: Buffer,
+    texture: gl::types::GLuint,
+}
+
+impl<T> BufferTexture<T> where T: TextureBufferContent {
+    /// Builds a new buffer texture.
+    ///
+    /// Returns an error if buffer textures or their format are not supported by the backend,
+    /// or if there are more elements than `GL_MAX_TEXTURE_BUFFER_SIZE`.
+    pub fn new(display: &Display, data: Vec<T>)
+               -> Result<BufferTexture<T>, BufferTextureCreationError>
+    {
+        try!(check_support::<T>(display, data.len()));
+        let buffer = Buffer::new::<buffer::TextureBuffer, T>(display, data, gl::STATIC_DRAW);
+        Ok(BufferTexture::from_buffer(display, buffer))
+    }
+
+    /// Builds a new buffer texture.
+    ///
+    /// This function will create a buffer that has better performances when it is modified
+    /// frequently.
+    ///
+    /// Returns an error if buffer textures or their format are not supported by the backend,
+    /// or if there are more elements than `GL_MAX_TEXTURE_BUFFER_SIZE`.
+    pub fn new_dynamic(display: &Display, data: Vec<T>)
+                       -> Result<BufferTexture<T>, BufferTextureCreationError>
+    {
+        try!(check_support::<T>(display, data.len()));
+        let buffer = Buffer::new::<buffer::TextureBuffer, T>(display, data, gl::DYNAMIC_DRAW);
+        Ok(BufferTexture::from_buffer(display, buffer))
+    }
+
+    fn from_buffer(display: &Display, buffer: Buffer) -> BufferTexture<T> {
+        let buffer_id = buffer.get_id();
+        let internal_format = TextureBufferContent::get_internal_format(None::<T>);
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let mut id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, &mut id);
+                context::bind_texture(&mut ctxt, gl::TEXTURE_BUFFER, id);
+                ctxt.gl.TexBuffer(gl::TEXTURE_BUFFER, internal_format, buffer_id);
+                tx.send(id);
+            }
+        });
+
+        BufferTexture {
+            display: display.context.clone(),
+            buffer: buffer,
+            texture: rx.recv(),
+        }
+    }
+
+    /// Returns the number of elements in the texture.
+    pub fn len(&self) -> uint {
+        self.buffer.get_elements_count()
+    }
+
+    /// Maps the buffer to allow write access to it.
+    ///
+    /// **Warning**: using this function can slow things down a lot because the function
+    /// waits for all the previous commands to be executed before returning.
+    pub fn map<'a>(&'a mut self) -> BufferTextureMapping<'a, T> {
+        let len = self.buffer.get_elements_count();
+        BufferTextureMapping(self.buffer.map::<buffer::TextureBuffer, T>(0, len))
+    }
+
+    /// Reads the content of the buffer.
+    ///
+    /// # Features
+    ///
+    /// Only available if the `gl_extensions` feature is enabled.
+    #[cfg(feature = "gl_extensions")]
+    pub fn read(&self) -> Vec<T> {
+        self.buffer.read::<buffer::TextureBuffer, T>()
+    }
+
+    /// Returns a reference to the texture that can be passed as a uniform.
+    pub fn as_ref<'a>(&'a self) -> BufferTextureRef<'a> {
+        BufferTextureRef {
+            texture: self.texture,
+            ty: TextureBufferContent::get_type(None::<T>),
+            marker: ContravariantLifetime,
+        }
+    }
+
+    /// Builds a texture that gives access to a range of the content of this buffer texture.
+    ///
+    /// `offset` and `length` are in number of elements. The data is not copied: modifying
+    /// the content of this buffer texture also modifies the content of the slice.
+    ///
+    /// Returns an error if binding a range of a buffer is not supported by the backend, if
+    /// the range is out of bounds, or if the offset in bytes is not a multiple of
+    /// `GL_TEXTURE_BUFFER_OFFSET_ALIGNMENT`.
+    pub fn slice<'a>(&'a self, offset: uint, length: uint)
+                     -> Result<BufferTextureSlice<'a, T>, BufferTextureCreationError>
+    {
+        let capabilities = self.display.context.capabilities();
+
+        if !capabilities.texture_buffer_range {
+            return Err(BufferTextureCreationError::RangeNotSupported);
+        }
+
+        if offset > self.len() || offset + length > self.len() {
+            return Err(BufferTextureCreationError::OutOfRange);
+        }
+
+        let elements_size = self.buffer.get_elements_size();
+        let offset_bytes = offset * elements_size;
+        let size_bytes = length * elements_size;
+
+        if offset_bytes % capabilities.texture_buffer_offset_alignment as uint != 0 {
+            return Err(BufferTextureCreationError::WrongAlignment);
+        }
+
+        let buffer_id = self.buffer.get_id();
+        let internal_format = TextureBufferContent::get_internal_format(None::<T>);
+
+        let (tx, rx) = channel();
+        self.display.context.exec(move |: mut ctxt| {
+            unsafe {
+                let mut id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, &mut id);
+                context::bind_texture(&mut ctxt, gl::TEXTURE_BUFFER, id);
+                ctxt.gl.TexBufferRange(gl::TEXTURE_BUFFER, internal_format, buffer_id,
+                                       offset_bytes as gl::types::GLintptr,
+                                       size_bytes as gl::types::GLsizeiptr);
+                tx.send(id);
+            }
+        });
+
+        Ok(BufferTextureSlice {
+            display: self.display.clone(),
+            texture: rx.recv(),
+            length: length,
+            marker: ContravariantLifetime,
+        })
+    }
+}
+
+/// Checks whether a buffer texture with `len` elements of type `T` can be created.
+///
+/// This must be called before the buffer is allocated.
+fn check_support<T>(display: &Display, len: uint) -> Result<(), BufferTextureCreationError>
+                    where T: TextureBufferContent
+{
+    let capabilities = display.context.context.capabilities();
+
+    if !capabilities.texture_buffer {
+        return Err(BufferTextureCreationError::NotSupported);
+    }
+
+    match TextureBufferContent::get_internal_format(None::<T>) {
+        gl::RGB32F | gl::RGB32I | gl::RGB32UI if !capabilities.texture_buffer_rgb32 => {
+            return Err(BufferTextureCreationError::FormatNotSupported);
+        },
+        _ => ()
+    }
+
+    if len > capabilities.max_texture_buffer_size as uint {
+        return Err(BufferTextureCreationError::TooLarge);
+    }
+
+    Ok(())
+}
+
+impl<T> GlObject for BufferTexture<T> {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.texture
+    }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for BufferTexture<T> {
+    fn drop(&mut self) {
+        self.display.delete_object(context::Deletion::Texture(self.texture));
+    }
+}
+
+impl<'a, T> IntoUniformValue<'a> for &'a BufferTexture<T> where T: TextureBufferContent {
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        UniformValue::BufferTexture(self.as_ref())
+    }
+}
+
+/// A texture that gives access to a range of a buffer texture.
+///
+/// Obtained by calling `slice()` on a `BufferTexture`.
+pub struct BufferTextureSlice<'a, T> {
+    display: Arc<DisplayImpl>,
+    texture: gl::types::GLuint,
+    length: uint,
+    marker: ContravariantLifetime<'a>,
+}
+
+impl<'a, T> BufferTextureSlice<'a, T> where T: TextureBufferContent {
+    /// Returns the number of elements in the slice.
+    pub fn len(&self) -> uint {
+        self.length
+    }
+
+    /// Returns a reference to the texture that can be passed as a uniform.
+    pub fn as_ref<'b>(&'b self) -> BufferTextureRef<'b> {
+        BufferTextureRef {
+            texture: self.texture,
+            ty: TextureBufferContent::get_type(None::<T>),
+            marker: ContravariantLifetime,
+        }
+    }
+}
+
+impl<'a, T> GlObject for BufferTextureSlice<'a, T> {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.texture
+    }
+}
+
+#[unsafe_destructor]
+impl<'a, T> Drop for BufferTextureSlice<'a, T> {
+    fn drop(&mut self) {
+        self.display.delete_object(context::Deletion::Texture(self.texture));
+    }
+}
+
+impl<'a, 'b, T> IntoUniformValue<'a> for &'a BufferTextureSlice<'b, T>
+                                         where T: TextureBufferContent
+{
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        UniformValue::BufferTexture(self.as_ref())
+    }
+}
+
+/// A reference to a buffer texture, without the type of its content.
+///
+/// Obtained by calling `as_ref()` on a `BufferTexture`.
+#[deriving(Copy, Clone)]
+pub struct BufferTextureRef<'a> {
+    texture: gl::types::GLuint,
+    ty: BufferTextureType,
+    marker: ContravariantLifetime<'a>,
+}
+
+impl<'a> BufferTextureRef<'a> {
+    /// Returns the type of data contained in the texture.
+    pub fn get_texture_type(&self) -> BufferTextureType {
+        self.ty
+    }
+}
+
+impl<'a> GlObject for BufferTextureRef<'a> {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.texture
+    }
+}
+
+/// A mapping of the buffer of a buffer texture.
+pub struct BufferTextureMapping<'a, T>(buffer::Mapping<'a, buffer::TextureBuffer, T>);
+
+impl<'a, T> Deref<[T]> for BufferTextureMapping<'a, T> {
+    fn deref<'b>(&'b self) -> &'b [T] {
+        self.0.deref()
+    }
+}
+
+impl<'a, T> DerefMut<[T]> for BufferTextureMapping<'a, T> {
+    fn deref_mut<'b>(&'b mut self) -> &'b mut [T] {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,358 @@
+use gl;
+
+use {Display, GlObject, ToGlEnum};
+use framebuffer::{mod, ColorAttachment, ToColorAttachment};
+use uniforms::{UniformValue, IntoUniformValue, Sampler};
+
+use super::{CompressedFormat, MipmapsOption, Texture, Texture2dData, TextureCreationError};
+use super::{TextureSurface, UncompressedFloatFormat};
+use super::tex_impl::TextureImplementation;
+
+/// One of the six faces of a cubemap.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum CubeLayer {
+    /// The face in the direction of the positive X axis.
+    PositiveX,
+    /// The face in the direction of the negative X axis.
+    NegativeX,
+    /// The face in the direction of the positive Y axis.
+    PositiveY,
+    /// The face in the direction of the negative Y axis.
+    NegativeY,
+    /// The face in the direction of the positive Z axis.
+    PositiveZ,
+    /// The face in the direction of the negative Z axis.
+    NegativeZ,
+}
+
+impl CubeLayer {
+    /// Returns the list of all faces, in the order used by OpenGL.
+    pub fn all() -> [CubeLayer, ..6] {
+        [
+            CubeLayer::PositiveX, CubeLayer::NegativeX,
+            CubeLayer::PositiveY, CubeLayer::NegativeY,
+            CubeLayer::PositiveZ, CubeLayer::NegativeZ,
+        ]
+    }
+}
+
+impl ToGlEnum for CubeLayer {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            CubeLayer::PositiveX => gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+            CubeLayer::NegativeX => gl::TEXTURE_CUBE_MAP_NEGATIVE_X,
+            CubeLayer::PositiveY => gl::TEXTURE_CUBE_MAP_POSITIVE_Y,
+            CubeLayer::NegativeY => gl::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+            CubeLayer::PositiveZ => gl::TEXTURE_CUBE_MAP_POSITIVE_Z,
+            CubeLayer::NegativeZ => gl::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+        }
+    }
+}
+
+/// A cube texture containing floating-point data.
+///
+/// A cubemap is made of six square two-dimensional textures of the same size, one for each
+/// face of a cube. It can be binded to a `samplerCube` uniform in GLSL.
+pub struct Cubemap(TextureImplementation);
+
+impl Cubemap {
+    /// Builds a new cubemap by uploading data for each face.
+    ///
+    /// The faces must be in the order returned by `CubeLayer::all()`, in other words
+    /// `PositiveX`, `NegativeX`, `PositiveY`, `NegativeY`, `PositiveZ`, `NegativeZ`.
+    ///
+    /// The mipmaps of the faces are automatically generated.
+    ///
+    /// # Panic
+    ///
+    /// Panics if there are not exactly six faces, or if the faces are not squares of the
+    /// same size.
+    pub fn new<T>(display: &Display, faces: Vec<T>) -> Cubemap where T: Texture2dData {
+        Cubemap::with_mipmaps(display, faces, MipmapsOption::AutoGeneratedMipmaps)
+    }
+
+    /// Builds a new cubemap by uploading data for each face, with the given mipmaps policy.
+    ///
+    /// The faces must be in the order returned by `CubeLayer::all()`. With
+    /// `MipmapsOption::EmptyMipmaps`, only the main level of each face is filled and the other
+    /// levels can be written with `write_face_mipmap`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if there are not exactly six faces, or if the faces are not squares of the
+    ///   same size.
+    /// - Panics if the number of empty mipmaps is zero or too large for the dimension.
+    pub fn with_mipmaps<T>(display: &Display, faces: Vec<T>, mipmaps: MipmapsOption) -> Cubemap
+                           where T: Texture2dData
+    {
+        if faces.len() != 6 {
+            panic!("A cubemap must have exactly six faces");
+        }
+
+        let dimension = faces[0].get_dimensions().0;
+        for face in faces.iter() {
+            if face.get_dimensions() != (dimension, dimension) {
+                panic!("The faces of a cubemap must be squares of the same size");
+            }
+        }
+
+        let format = Texture2dData::get_format(None::<T>).to_default_float_format();
+        let (client_format, client_type) = Texture2dData::get_format(None::<T>).to_gl_enum();
+
+        let cubemap = Cubemap(TextureImplementation::new_cubemap(display, format, client_format,
+                                                                 client_type, dimension,
+                                                                 mipmaps));
+
+        // the mipmaps are generated once, after all the faces have been uploaded
+        for (face, data) in CubeLayer::all().iter().zip(faces.into_iter()) {
+            cubemap.0.upload_2d(face.to_glenum(), 0, 0, 0, dimension, dimension, client_format,
+                                client_type, data.into_vec());
+        }
+
+        cubemap.0.update_mipmaps(0);
+        cubemap
+    }
+
+    /// Builds a new cubemap from data that is already compressed with `format`.
+    ///
+    /// The faces must be in the order returned by `CubeLayer::all()`. The cubemap will only
+    /// have one mipmap level.
+    ///
+    /// A cubemap created with compressed data can't be used as a render target.
+    ///
+    /// Returns an error if `format` is not supported by the backend, or if the length of a
+    /// face doesn't match the dimension.
+    ///
+    /// # Panic
+    ///
+    /// Panics if there are not exactly six faces.
+    pub fn with_compressed_data(display: &Display, faces: Vec<Vec<u8>>, dimension: u32,
+                                format: CompressedFormat) -> Result<Cubemap, TextureCreationError>
+    {
+        let faces = faces.into_iter().map(|f| vec![f]).collect();
+        Cubemap::with_compressed_mipmaps(display, faces, dimension, format)
+    }
+
+    /// Builds a new cubemap from data that is already compressed with `format`, including
+    /// the mipmaps of each face.
+    ///
+    /// Each element of `faces` is the list of the mipmap levels of a face, starting with the
+    /// largest one. The faces must be in the order returned by `CubeLayer::all()` and must
+    /// all have the same number of levels.
+    ///
+    /// A cubemap created with compressed data can't be used as a render target.
+    ///
+    /// Returns an error if `format` is not supported by the backend, or if the length of a
+    /// level doesn't match its dimensions.
+    ///
+    /// # Panic
+    ///
+    /// Panics if there are not exactly six faces, or if the faces don't have the same number
+    /// of levels.
+    pub fn with_compressed_mipmaps(display: &Display, faces: Vec<Vec<Vec<u8>>>, dimension: u32,
+                                   format: CompressedFormat)
+                                   -> Result<Cubemap, TextureCreationError>
+    {
+        if !format.is_supported(display) {
+            return Err(TextureCreationError::FormatNotSupported);
+        }
+
+        if faces.len() != 6 {
+            panic!("A cubemap must have exactly six faces");
+        }
+
+        let levels_count = faces[0].len();
+        if levels_count == 0 {
+            panic!("At least one mipmap level must be provided");
+        }
+
+        for levels in faces.iter() {
+            if levels.len() != levels_count {
+                panic!("All the faces must have the same number of mipmap levels");
+            }
+
+            let mut level_dimension = dimension;
+            for level in levels.iter() {
+                if level.len() != format.get_data_size(level_dimension, level_dimension) {
+                    return Err(TextureCreationError::DataSizeMismatch);
+                }
+
+                level_dimension = ::std::cmp::max(1, level_dimension / 2);
+            }
+        }
+
+        Ok(Cubemap(TextureImplementation::new_compressed_layered(display, gl::TEXTURE_CUBE_MAP,
+                                                                 format.to_glenum(), dimension,
+                                                                 dimension, faces)))
+    }
+
+    /// Replaces the content of a mipmap level of one of the faces with data that is already
+    /// compressed with `format`.
+    ///
+    /// `format` must be the format that was used to create the cubemap.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the cubemap doesn't have this level.
+    /// - Panics if the length of the data doesn't match the dimensions of the level.
+    pub fn write_compressed_face(&self, face: CubeLayer, level: u32, data: Vec<u8>,
+                                 format: CompressedFormat)
+    {
+        let face = face.to_glenum() - gl::TEXTURE_CUBE_MAP_POSITIVE_X;
+        self.0.upload_compressed_layer(level, face, format, data)
+    }
+
+    /// Creates an empty cubemap whose faces are squares of `dimension` pixels.
+    ///
+    /// The texture will contain undefined data.
+    pub fn new_empty(display: &Display, format: UncompressedFloatFormat, dimension: u32)
+                     -> Cubemap
+    {
+        Cubemap::new_empty_with_mipmaps(display, format, dimension,
+                                        MipmapsOption::AutoGeneratedMipmaps)
+    }
+
+    /// Creates an empty cubemap whose faces are squares of `dimension` pixels, with the given
+    /// mipmaps policy.
+    ///
+    /// The texture will contain undefined data.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the number of empty mipmaps is zero or too large for the dimension.
+    pub fn new_empty_with_mipmaps(display: &Display, format: UncompressedFloatFormat,
+                                  dimension: u32, mipmaps: MipmapsOption) -> Cubemap
+    {
+        Cubemap(TextureImplementation::new_cubemap(display, format.to_glenum(), gl::RGBA,
+                                                   gl::UNSIGNED_BYTE, dimension, mipmaps))
+    }
+
+    /// Replaces the content of one of the faces.
+    ///
+    /// If the other mipmap levels are generated from the main level, they are regenerated.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the dimensions of the data don't match the dimensions of the cubemap.
+    pub fn write_face<T>(&self, face: CubeLayer, data: T) where T: Texture2dData {
+        self.write_face_mipmap(face, 0, data)
+    }
+
+    /// Replaces the content of a mipmap level of one of the faces.
+    ///
+    /// Level `0` is the main level. This is useful to fill the levels of a cubemap created
+    /// with `MipmapsOption::EmptyMipmaps`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the cubemap doesn't have this level.
+    /// - Panics if the dimensions of the data don't match the dimensions of the level.
+    pub fn write_face_mipmap<T>(&self, face: CubeLayer, level: u32, data: T)
+                                where T: Texture2dData
+    {
+        if level >= self.0.get_mipmap_levels() {
+            panic!("The texture doesn't have a mipmap level {}", level);
+        }
+
+        let dimension = ::std::cmp::max(1, self.0.get_width() >> level as uint);
+        if data.get_dimensions() != (dimension, dimension) {
+            panic!("The dimensions of the data must match the dimensions of the level");
+        }
+
+        let (client_format, client_type) = Texture2dData::get_format(None::<T>).to_gl_enum();
+        self.0.upload_2d(face.to_glenum(), level, 0, 0, dimension, dimension, client_format,
+                         client_type, data.into_vec());
+        self.0.update_mipmaps(level);
+    }
+
+    /// Returns an object representing one of the faces of the cubemap.
+    ///
+    /// This object can be used as the color attachment of a `SimpleFrameBuffer`.
+    pub fn face<'a>(&'a self, face: CubeLayer) -> CubemapFace<'a> {
+        CubemapFace {
+            cubemap: self,
+            face: face,
+        }
+    }
+
+    /// Starts drawing on one of the faces of the cubemap.
+    ///
+    /// See `Texture2d::as_surface` for more informations.
+    pub fn as_surface_for_face<'a>(&'a self, face: CubeLayer) -> TextureSurface<'a> {
+        TextureSurface(framebuffer::SimpleFrameBuffer::from_color_attachment(
+                       self.0.get_display(), ColorAttachment::CubemapFace(self, face)))
+    }
+}
+
+impl Texture for Cubemap {
+    fn get_width(&self) -> u32 {
+        self.0.get_width()
+    }
+
+    fn get_height(&self) -> Option<u32> {
+        self.0.get_height()
+    }
+
+    fn get_depth(&self) -> Option<u32> {
+        self.0.get_depth()
+    }
+
+    fn get_array_size(&self) -> Option<u32> {
+        self.0.get_array_size()
+    }
+
+    fn get_mipmap_levels(&self) -> u32 {
+        self.0.get_mipmap_levels()
+    }
+}
+
+impl GlObject for Cubemap {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.0.get_id()
+    }
+}
+
+impl ToColorAttachment for Cubemap {
+    fn to_color_attachment(&self) -> ColorAttachment {
+        ColorAttachment::Cubemap(self)
+    }
+}
+
+impl<'a> IntoUniformValue<'a> for &'a Cubemap {
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        UniformValue::Cubemap(self, None)
+    }
+}
+
+impl<'a> IntoUniformValue<'a> for Sampler<'a, Cubemap> {
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        UniformValue::Cubemap(self.0, Some(self.1))
+    }
+}
+
+/// One of the faces of a cubemap.
+///
+/// Obtained by calling `cubemap.face(layer)`.
+#[deriving(Copy, Clone)]
+pub struct CubemapFace<'a> {
+    cubemap: &'a Cubemap,
+    face: CubeLayer,
+}
+
+impl<'a> CubemapFace<'a> {
+    /// Returns the cubemap this face belongs to.
+    pub fn get_cubemap(&self) -> &'a Cubemap {
+        self.cubemap
+    }
+
+    /// Returns which face of the cubemap this is.
+    pub fn get_layer(&self) -> CubeLayer {
+        self.face
+    }
+}
+
+impl<'a> ToColorAttachment for CubemapFace<'a> {
+    fn to_color_attachment(&self) -> ColorAttachment {
+        ColorAttachment::CubemapFace(self.cubemap, self.face)
+    }
+}
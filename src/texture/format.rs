@@ -1,6 +1,7 @@
 use gl;
 
-use ToGlEnum;
+use {Display, ToGlEnum};
+use context::GlVersion;
 
 /// List of client-side pixel formats.
 ///
@@ -93,6 +94,40 @@ impl ClientFormat {
         }
     }
 
+    /// Returns true if data in this format can be uploaded to a texture by the backend.
+    ///
+    /// This is always the case except with OpenGL ES 2.0, where the format of the data also
+    /// determines the format of the texture. Floating-point data requires
+    /// `GL_OES_texture_float` or `GL_OES_texture_half_float` there, and data with one or two
+    /// components requires `GL_EXT_texture_rg`.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if !capabilities.opengl_es || capabilities.version >= GlVersion(3, 0) {
+            return true;
+        }
+
+        match *self {
+            ClientFormat::U8U8U8 | ClientFormat::U8U8U8U8 | ClientFormat::U5U6U5 |
+            ClientFormat::U4U4U4U4 | ClientFormat::U5U5U5U1 => true,
+            ClientFormat::U8 | ClientFormat::U8U8 => extensions.gl_ext_texture_rg,
+            ClientFormat::F16 | ClientFormat::F16F16 => {
+                extensions.gl_ext_texture_rg && extensions.gl_oes_texture_half_float
+            },
+            ClientFormat::F16F16F16 | ClientFormat::F16F16F16F16 => {
+                extensions.gl_oes_texture_half_float
+            },
+            ClientFormat::F32 | ClientFormat::F32F32 => {
+                extensions.gl_ext_texture_rg && extensions.gl_oes_texture_float
+            },
+            ClientFormat::F32F32F32 | ClientFormat::F32F32F32F32 => {
+                extensions.gl_oes_texture_float
+            },
+            _ => false,
+        }
+    }
+
     /// Returns a (format, type) tuple.
     #[doc(hidden)]      // TODO: shouldn't be pub
     pub fn to_gl_enum(&self) -> (gl::types::GLenum, gl::types::GLenum) {
@@ -291,6 +326,10 @@ impl ClientFormat {
 /// Some formats are marked as "guaranteed to be supported". What this means is that you are
 /// certain that the backend will use exactly these formats. If you try to use a format that
 /// is not supported by the backend, it will automatically fall back to a larger format.
+///
+/// These guarantees only apply to desktop OpenGL. OpenGL ES supports fewer formats, and
+/// OpenGL ES 2.0 chooses the precision of textures by itself. Use `is_supported` to know
+/// whether a format is available.
 #[deriving(Show, Clone, Copy, PartialEq, Eq)]
 pub enum UncompressedFloatFormat {
     /// 
@@ -413,6 +452,141 @@ pub enum UncompressedFloatFormat {
     ///
     /// Guaranteed to be supported for textures.
     F9F9F9,
+    /// Three components of 8 bits that are stored in the sRGB color space. They are
+    /// converted to linear values when sampled.
+    ///
+    /// Guaranteed to be supported for textures.
+    Srgb8,
+    /// Three components of 8 bits that are stored in the sRGB color space, and a linear
+    /// alpha component of 8 bits. The color components are converted to linear values when
+    /// sampled, and from linear values when written if `DrawParameters::framebuffer_srgb`
+    /// is `true`.
+    ///
+    /// Guaranteed to be supported for both textures and renderbuffers.
+    SrgbAlpha8,
+}
+
+impl UncompressedFloatFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if !capabilities.opengl_es {
+            let rg = capabilities.version >= GlVersion(3, 0) || extensions.gl_arb_texture_rg;
+            let float = capabilities.version >= GlVersion(3, 0) ||
+                        extensions.gl_arb_texture_float;
+            let snorm = capabilities.version >= GlVersion(3, 1) ||
+                        extensions.gl_ext_texture_snorm;
+
+            return match *self {
+                UncompressedFloatFormat::U8 | UncompressedFloatFormat::U16 |
+                UncompressedFloatFormat::U8U8 | UncompressedFloatFormat::U16U16 => rg,
+                UncompressedFloatFormat::I8 | UncompressedFloatFormat::I16 |
+                UncompressedFloatFormat::I8I8 | UncompressedFloatFormat::I16I16 => rg && snorm,
+                UncompressedFloatFormat::I8I8I8 | UncompressedFloatFormat::I16I16I16 |
+                UncompressedFloatFormat::I8I8I8I8 => snorm,
+                UncompressedFloatFormat::F16 | UncompressedFloatFormat::F16F16 |
+                UncompressedFloatFormat::F32 | UncompressedFloatFormat::F32F32 => rg && float,
+                UncompressedFloatFormat::F16F16F16 | UncompressedFloatFormat::F16F16F16F16 |
+                UncompressedFloatFormat::F32F32F32 |
+                UncompressedFloatFormat::F32F32F32F32 => float,
+                UncompressedFloatFormat::F11F11F10 => {
+                    capabilities.version >= GlVersion(3, 0) || extensions.gl_ext_packed_float
+                },
+                UncompressedFloatFormat::F9F9F9 => {
+                    capabilities.version >= GlVersion(3, 0) ||
+                    extensions.gl_ext_texture_shared_exponent
+                },
+                UncompressedFloatFormat::Srgb8 | UncompressedFloatFormat::SrgbAlpha8 => {
+                    capabilities.version >= GlVersion(2, 1) || extensions.gl_ext_texture_srgb
+                },
+                _ => true,
+            };
+        }
+
+        // OpenGL ES 2.0 uses the format of the data as internal format
+        if capabilities.version < GlVersion(3, 0) {
+            return match *self {
+                UncompressedFloatFormat::U8U8U8 | UncompressedFloatFormat::U8U8U8U8 |
+                UncompressedFloatFormat::U4U4U4U4 | UncompressedFloatFormat::U5U5U5U1 => true,
+                UncompressedFloatFormat::U8 | UncompressedFloatFormat::U8U8 => {
+                    extensions.gl_ext_texture_rg
+                },
+                UncompressedFloatFormat::F16 | UncompressedFloatFormat::F16F16 => {
+                    extensions.gl_ext_texture_rg && extensions.gl_oes_texture_half_float
+                },
+                UncompressedFloatFormat::F16F16F16 | UncompressedFloatFormat::F16F16F16F16 => {
+                    extensions.gl_oes_texture_half_float
+                },
+                UncompressedFloatFormat::F32 | UncompressedFloatFormat::F32F32 => {
+                    extensions.gl_ext_texture_rg && extensions.gl_oes_texture_float
+                },
+                UncompressedFloatFormat::F32F32F32 | UncompressedFloatFormat::F32F32F32F32 => {
+                    extensions.gl_oes_texture_float
+                },
+                _ => false,
+            };
+        }
+
+        match *self {
+            UncompressedFloatFormat::U16 | UncompressedFloatFormat::I16 |
+            UncompressedFloatFormat::U16U16 | UncompressedFloatFormat::I16I16 |
+            UncompressedFloatFormat::I16I16I16 |
+            UncompressedFloatFormat::U16U16U16U16 => extensions.gl_ext_texture_norm16,
+            UncompressedFloatFormat::U3U32U | UncompressedFloatFormat::U4U4U4 |
+            UncompressedFloatFormat::U5U5U5 | UncompressedFloatFormat::U10U10U10 |
+            UncompressedFloatFormat::U12U12U12 | UncompressedFloatFormat::U2U2U2U2 |
+            UncompressedFloatFormat::U12U12U12U12 => false,
+            _ => true,
+        }
+    }
+
+    /// Returns the closest format to try if this one is not supported, or `None` if there
+    /// is no other format to try.
+    ///
+    /// Floating-point formats fall back to half-floats, then to four half-floats, then to
+    /// 16 bits and 8 bits normalized formats, in order to lose as little precision as
+    /// possible. Every chain ends with `U8U8U8U8`, which is always supported, except for sRGB
+    /// formats.
+    pub fn get_fallback(&self) -> Option<UncompressedFloatFormat> {
+        Some(match *self {
+            UncompressedFloatFormat::U8 | UncompressedFloatFormat::U8U8 => {
+                UncompressedFloatFormat::U8U8U8U8
+            },
+            UncompressedFloatFormat::U16 => UncompressedFloatFormat::U8,
+            UncompressedFloatFormat::U16U16 => UncompressedFloatFormat::U8U8,
+            UncompressedFloatFormat::U16U16U16U16 => UncompressedFloatFormat::U8U8U8U8,
+            UncompressedFloatFormat::I16 => UncompressedFloatFormat::I8,
+            UncompressedFloatFormat::I16I16 => UncompressedFloatFormat::I8I8,
+            UncompressedFloatFormat::I16I16I16 => UncompressedFloatFormat::I8I8I8,
+            UncompressedFloatFormat::I8 => UncompressedFloatFormat::F16,
+            UncompressedFloatFormat::I8I8 => UncompressedFloatFormat::F16F16,
+            UncompressedFloatFormat::I8I8I8 => UncompressedFloatFormat::F16F16F16,
+            UncompressedFloatFormat::I8I8I8I8 => UncompressedFloatFormat::F16F16F16F16,
+            UncompressedFloatFormat::U3U32U | UncompressedFloatFormat::U4U4U4 |
+            UncompressedFloatFormat::U5U5U5 | UncompressedFloatFormat::U10U10U10 |
+            UncompressedFloatFormat::U12U12U12 => UncompressedFloatFormat::U8U8U8,
+            UncompressedFloatFormat::U8U8U8 | UncompressedFloatFormat::U2U2U2U2 |
+            UncompressedFloatFormat::U4U4U4U4 | UncompressedFloatFormat::U5U5U5U1 |
+            UncompressedFloatFormat::U10U10U10U2 |
+            UncompressedFloatFormat::U12U12U12U12 => UncompressedFloatFormat::U8U8U8U8,
+            UncompressedFloatFormat::F32 => UncompressedFloatFormat::F16,
+            UncompressedFloatFormat::F32F32 => UncompressedFloatFormat::F16F16,
+            UncompressedFloatFormat::F32F32F32 => UncompressedFloatFormat::F16F16F16,
+            UncompressedFloatFormat::F32F32F32F32 => UncompressedFloatFormat::F16F16F16F16,
+            UncompressedFloatFormat::F11F11F10 | UncompressedFloatFormat::F9F9F9 => {
+                UncompressedFloatFormat::F16F16F16
+            },
+            UncompressedFloatFormat::F16 | UncompressedFloatFormat::F16F16 |
+            UncompressedFloatFormat::F16F16F16 => UncompressedFloatFormat::F16F16F16F16,
+            UncompressedFloatFormat::F16F16F16F16 => UncompressedFloatFormat::U16U16U16U16,
+            UncompressedFloatFormat::Srgb8 => UncompressedFloatFormat::SrgbAlpha8,
+            UncompressedFloatFormat::U8U8U8U8 | UncompressedFloatFormat::SrgbAlpha8 => {
+                return None
+            },
+        })
+    }
 }
 
 impl ToGlEnum for UncompressedFloatFormat {
@@ -452,6 +626,8 @@ impl ToGlEnum for UncompressedFloatFormat {
             UncompressedFloatFormat::F32F32F32F32 => gl::RGBA32F,
             UncompressedFloatFormat::F11F11F10 => gl::R11F_G11F_B10F,
             UncompressedFloatFormat::F9F9F9 => gl::RGB9_E5,
+            UncompressedFloatFormat::Srgb8 => gl::SRGB8,
+            UncompressedFloatFormat::SrgbAlpha8 => gl::SRGB8_ALPHA8,
         }
     }
 }
@@ -477,6 +653,49 @@ pub enum UncompressedIntFormat {
     I32I32I32I32,
 }
 
+impl UncompressedIntFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if capabilities.opengl_es {
+            return capabilities.version >= GlVersion(3, 0);
+        }
+
+        if capabilities.version >= GlVersion(3, 0) {
+            return true;
+        }
+
+        extensions.gl_ext_texture_integer && match *self {
+            UncompressedIntFormat::I8 | UncompressedIntFormat::I16 |
+            UncompressedIntFormat::I32 | UncompressedIntFormat::I8I8 |
+            UncompressedIntFormat::I16I16 |
+            UncompressedIntFormat::I32I32 => extensions.gl_arb_texture_rg,
+            _ => true,
+        }
+    }
+
+    /// Returns the format to try if this one is not supported, or `None` if there is no other
+    /// format to try.
+    ///
+    /// Formats with one or two components fall back to the same format with four components.
+    pub fn get_fallback(&self) -> Option<UncompressedIntFormat> {
+        match *self {
+            UncompressedIntFormat::I8 | UncompressedIntFormat::I8I8 => {
+                Some(UncompressedIntFormat::I8I8I8I8)
+            },
+            UncompressedIntFormat::I16 | UncompressedIntFormat::I16I16 => {
+                Some(UncompressedIntFormat::I16I16I16I16)
+            },
+            UncompressedIntFormat::I32 | UncompressedIntFormat::I32I32 => {
+                Some(UncompressedIntFormat::I32I32I32I32)
+            },
+            _ => None,
+        }
+    }
+}
+
 impl ToGlEnum for UncompressedIntFormat {
     fn to_glenum(&self) -> gl::types::GLenum {
         match *self {
@@ -518,6 +737,52 @@ pub enum UncompressedUintFormat {
     U10U10U10U2,
 }
 
+impl UncompressedUintFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if capabilities.opengl_es {
+            return capabilities.version >= GlVersion(3, 0);
+        }
+
+        match *self {
+            UncompressedUintFormat::U10U10U10U2 => {
+                capabilities.version >= GlVersion(3, 3) ||
+                extensions.gl_arb_texture_rgb10_a2ui
+            },
+            _ if capabilities.version >= GlVersion(3, 0) => true,
+            UncompressedUintFormat::U8 | UncompressedUintFormat::U16 |
+            UncompressedUintFormat::U32 | UncompressedUintFormat::U8U8 |
+            UncompressedUintFormat::U16U16 | UncompressedUintFormat::U32U32 => {
+                extensions.gl_ext_texture_integer && extensions.gl_arb_texture_rg
+            },
+            _ => extensions.gl_ext_texture_integer,
+        }
+    }
+
+    /// Returns the format to try if this one is not supported, or `None` if there is no other
+    /// format to try.
+    ///
+    /// Formats with one or two components fall back to the same format with four components.
+    pub fn get_fallback(&self) -> Option<UncompressedUintFormat> {
+        match *self {
+            UncompressedUintFormat::U8 | UncompressedUintFormat::U8U8 => {
+                Some(UncompressedUintFormat::U8U8U8U8)
+            },
+            UncompressedUintFormat::U16 | UncompressedUintFormat::U16U16 => {
+                Some(UncompressedUintFormat::U16U16U16U16)
+            },
+            UncompressedUintFormat::U32 | UncompressedUintFormat::U32U32 => {
+                Some(UncompressedUintFormat::U32U32U32U32)
+            },
+            UncompressedUintFormat::U10U10U10U2 => Some(UncompressedUintFormat::U16U16U16U16),
+            _ => None,
+        }
+    }
+}
+
 impl ToGlEnum for UncompressedUintFormat {
     fn to_glenum(&self) -> gl::types::GLenum {
         match *self {
@@ -540,6 +805,9 @@ impl ToGlEnum for UncompressedUintFormat {
 
 /// List of compressed texture formats.
 ///
+/// The RGTC, S3TC, ETC2 and BPTC formats may not be supported by all hardwares. Use
+/// `is_supported` to check whether a format is available.
+///
 /// TODO: many formats are missing
 #[deriving(Show, Clone, Copy, PartialEq, Eq)]
 pub enum CompressedFormat {
@@ -551,6 +819,92 @@ pub enum CompressedFormat {
     RGTCFormatUU,
     /// Red/green compressed texture with two signed components.
     RGTCFormatII,
+    /// S3TC DXT1 compression without alpha.
+    S3tcDxt1NoAlpha,
+    /// S3TC DXT1 compression with one bit of alpha.
+    S3tcDxt1Alpha,
+    /// S3TC DXT3 compression with explicit alpha.
+    S3tcDxt3Alpha,
+    /// S3TC DXT5 compression with interpolated alpha.
+    S3tcDxt5Alpha,
+    /// ETC2 compression of RGB data.
+    Etc2Rgb8,
+    /// ETC2 compression of RGBA data.
+    Etc2Rgba8,
+    /// BPTC compression of normalized RGBA data.
+    BptcUnorm4,
+    /// BPTC compression of signed floating-point RGB data.
+    BptcSignedFloat3,
+    /// BPTC compression of unsigned floating-point RGB data.
+    BptcUnsignedFloat3,
+    /// S3TC DXT1 compression of sRGB data without alpha.
+    S3tcDxt1SrgbNoAlpha,
+    /// S3TC DXT1 compression of sRGB data with one bit of alpha.
+    S3tcDxt1SrgbAlpha,
+    /// S3TC DXT3 compression of sRGB data with explicit alpha.
+    S3tcDxt3SrgbAlpha,
+    /// S3TC DXT5 compression of sRGB data with interpolated alpha.
+    S3tcDxt5SrgbAlpha,
+    /// ETC2 compression of sRGB data.
+    Etc2Srgb8,
+    /// ETC2 compression of sRGB data with a linear alpha.
+    Etc2SrgbAlpha8,
+    /// BPTC compression of sRGB data with a linear alpha.
+    BptcSrgb4,
+}
+
+impl CompressedFormat {
+    /// Returns the number of bytes of a block of 4x4 pixels compressed with this format.
+    pub fn get_block_size(&self) -> uint {
+        match *self {
+            CompressedFormat::RGTCFormatU | CompressedFormat::RGTCFormatI => 8,
+            CompressedFormat::RGTCFormatUU | CompressedFormat::RGTCFormatII => 16,
+            CompressedFormat::S3tcDxt1NoAlpha | CompressedFormat::S3tcDxt1Alpha => 8,
+            CompressedFormat::S3tcDxt3Alpha | CompressedFormat::S3tcDxt5Alpha => 16,
+            CompressedFormat::Etc2Rgb8 => 8,
+            CompressedFormat::Etc2Rgba8 => 16,
+            CompressedFormat::BptcUnorm4 | CompressedFormat::BptcSignedFloat3 |
+            CompressedFormat::BptcUnsignedFloat3 => 16,
+            CompressedFormat::S3tcDxt1SrgbNoAlpha | CompressedFormat::S3tcDxt1SrgbAlpha => 8,
+            CompressedFormat::S3tcDxt3SrgbAlpha | CompressedFormat::S3tcDxt5SrgbAlpha => 16,
+            CompressedFormat::Etc2Srgb8 => 8,
+            CompressedFormat::Etc2SrgbAlpha8 => 16,
+            CompressedFormat::BptcSrgb4 => 16,
+        }
+    }
+
+    /// Returns the number of bytes of an image of the given dimensions compressed with
+    /// this format.
+    pub fn get_data_size(&self, width: u32, height: u32) -> uint {
+        ((width as uint + 3) / 4) * ((height as uint + 3) / 4) * self.get_block_size()
+    }
+
+    /// Returns true if this format is supported by the backend.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+
+        match *self {
+            CompressedFormat::RGTCFormatU | CompressedFormat::RGTCFormatI |
+            CompressedFormat::RGTCFormatUU | CompressedFormat::RGTCFormatII => {
+                capabilities.texture_compression_rgtc
+            },
+            CompressedFormat::S3tcDxt1NoAlpha | CompressedFormat::S3tcDxt1Alpha |
+            CompressedFormat::S3tcDxt3Alpha | CompressedFormat::S3tcDxt5Alpha => {
+                capabilities.texture_compression_s3tc
+            },
+            CompressedFormat::Etc2Rgb8 | CompressedFormat::Etc2Rgba8 |
+            CompressedFormat::Etc2Srgb8 | CompressedFormat::Etc2SrgbAlpha8 => {
+                capabilities.texture_compression_etc2
+            },
+            CompressedFormat::BptcUnorm4 | CompressedFormat::BptcSignedFloat3 |
+            CompressedFormat::BptcUnsignedFloat3 |
+            CompressedFormat::BptcSrgb4 => capabilities.texture_compression_bptc,
+            CompressedFormat::S3tcDxt1SrgbNoAlpha | CompressedFormat::S3tcDxt1SrgbAlpha |
+            CompressedFormat::S3tcDxt3SrgbAlpha | CompressedFormat::S3tcDxt5SrgbAlpha => {
+                capabilities.texture_compression_s3tc_srgb
+            },
+        }
+    }
 }
 
 impl ToGlEnum for CompressedFormat {
@@ -560,6 +914,22 @@ impl ToGlEnum for CompressedFormat {
             CompressedFormat::RGTCFormatI => gl::COMPRESSED_SIGNED_RED_RGTC1,
             CompressedFormat::RGTCFormatUU => gl::COMPRESSED_RG_RGTC2,
             CompressedFormat::RGTCFormatII => gl::COMPRESSED_SIGNED_RG_RGTC2,
+            CompressedFormat::S3tcDxt1NoAlpha => gl::COMPRESSED_RGB_S3TC_DXT1_EXT,
+            CompressedFormat::S3tcDxt1Alpha => gl::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            CompressedFormat::S3tcDxt3Alpha => gl::COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            CompressedFormat::S3tcDxt5Alpha => gl::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            CompressedFormat::Etc2Rgb8 => gl::COMPRESSED_RGB8_ETC2,
+            CompressedFormat::Etc2Rgba8 => gl::COMPRESSED_RGBA8_ETC2_EAC,
+            CompressedFormat::BptcUnorm4 => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            CompressedFormat::BptcSignedFloat3 => gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT,
+            CompressedFormat::BptcUnsignedFloat3 => gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+            CompressedFormat::S3tcDxt1SrgbNoAlpha => gl::COMPRESSED_SRGB_S3TC_DXT1_EXT,
+            CompressedFormat::S3tcDxt1SrgbAlpha => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+            CompressedFormat::S3tcDxt3SrgbAlpha => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,
+            CompressedFormat::S3tcDxt5SrgbAlpha => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+            CompressedFormat::Etc2Srgb8 => gl::COMPRESSED_SRGB8_ETC2,
+            CompressedFormat::Etc2SrgbAlpha8 => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+            CompressedFormat::BptcSrgb4 => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
         }
     }
 }
@@ -578,6 +948,44 @@ pub enum DepthFormat {
     F32,
 }
 
+impl DepthFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if capabilities.opengl_es {
+            if capabilities.version >= GlVersion(3, 0) {
+                return *self != DepthFormat::I32;
+            }
+
+            return extensions.gl_oes_depth_texture && match *self {
+                DepthFormat::I16 => true,
+                DepthFormat::I24 => extensions.gl_oes_depth24,
+                DepthFormat::I32 => extensions.gl_oes_depth32,
+                DepthFormat::F32 => false,
+            };
+        }
+
+        match *self {
+            DepthFormat::F32 => {
+                capabilities.version >= GlVersion(3, 0) || extensions.gl_arb_depth_buffer_float
+            },
+            _ => true,
+        }
+    }
+
+    /// Returns the format to try if this one is not supported, or `None` if there is no other
+    /// format to try.
+    pub fn get_fallback(&self) -> Option<DepthFormat> {
+        match *self {
+            DepthFormat::F32 | DepthFormat::I32 => Some(DepthFormat::I24),
+            DepthFormat::I24 => Some(DepthFormat::I16),
+            DepthFormat::I16 => None,
+        }
+    }
+}
+
 impl ToGlEnum for DepthFormat {
     fn to_glenum(&self) -> gl::types::GLenum {
         match *self {
@@ -599,6 +1007,40 @@ pub enum DepthStencilFormat {
     F32I8,
 }
 
+impl DepthStencilFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if capabilities.opengl_es {
+            return capabilities.version >= GlVersion(3, 0) || match *self {
+                DepthStencilFormat::I24I8 => {
+                    extensions.gl_oes_packed_depth_stencil && extensions.gl_oes_depth_texture
+                },
+                DepthStencilFormat::F32I8 => false,
+            };
+        }
+
+        capabilities.version >= GlVersion(3, 0) || match *self {
+            DepthStencilFormat::I24I8 => {
+                extensions.gl_ext_packed_depth_stencil ||
+                extensions.gl_arb_framebuffer_object
+            },
+            DepthStencilFormat::F32I8 => extensions.gl_arb_depth_buffer_float,
+        }
+    }
+
+    /// Returns the format to try if this one is not supported, or `None` if there is no other
+    /// format to try.
+    pub fn get_fallback(&self) -> Option<DepthStencilFormat> {
+        match *self {
+            DepthStencilFormat::F32I8 => Some(DepthStencilFormat::I24I8),
+            DepthStencilFormat::I24I8 => None,
+        }
+    }
+}
+
 impl ToGlEnum for DepthStencilFormat {
     fn to_glenum(&self) -> gl::types::GLenum {
         match *self {
@@ -622,6 +1064,37 @@ pub enum StencilFormat {
     I16,
 }
 
+impl StencilFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    ///
+    /// Only `I8` can be used for textures, and it requires OpenGL 4.4, OpenGL ES 3.2 or the
+    /// `GL_ARB_texture_stencil8` extension. The other formats are only available for render
+    /// buffers.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        let capabilities = display.context.context.capabilities();
+        let extensions = &capabilities.extensions_list;
+
+        if *self != StencilFormat::I8 {
+            return false;
+        }
+
+        if capabilities.opengl_es {
+            capabilities.version >= GlVersion(3, 2) || extensions.gl_oes_texture_stencil8
+        } else {
+            capabilities.version >= GlVersion(4, 4) || extensions.gl_arb_texture_stencil8
+        }
+    }
+
+    /// Returns the format to try if this one is not supported, or `None` if there is no other
+    /// format to try.
+    pub fn get_fallback(&self) -> Option<StencilFormat> {
+        match *self {
+            StencilFormat::I8 => None,
+            _ => Some(StencilFormat::I8),
+        }
+    }
+}
+
 impl ToGlEnum for StencilFormat {
     fn to_glenum(&self) -> gl::types::GLenum {
         match *self {
@@ -636,8 +1109,36 @@ impl ToGlEnum for StencilFormat {
 /// Format of the internal representation of a texture.
 #[deriving(Show, Clone, Copy, PartialEq, Eq)]
 pub enum TextureFormat {
-    /// 
+    /// Format of the regular textures.
     UncompressedFloat(UncompressedFloatFormat),
-    /// 
+    /// Format of the textures that contain signed integers.
     UncompressedIntegral(UncompressedIntFormat),
+    /// Format of the textures that contain unsigned integers.
+    UncompressedUnsigned(UncompressedUintFormat),
+    /// Format of the compressed textures.
+    CompressedFormat(CompressedFormat),
+    /// Format of the depth textures.
+    DepthFormat(DepthFormat),
+    /// Format of the stencil textures.
+    StencilFormat(StencilFormat),
+    /// Format of the depth-stencil textures.
+    DepthStencilFormat(DepthStencilFormat),
+}
+
+impl TextureFormat {
+    /// Returns true if this format is supported by the backend for textures.
+    ///
+    /// Creating a texture with an unsupported format doesn't fail immediately, but the
+    /// texture is unusable.
+    pub fn is_supported(&self, display: &Display) -> bool {
+        match *self {
+            TextureFormat::UncompressedFloat(format) => format.is_supported(display),
+            TextureFormat::UncompressedIntegral(format) => format.is_supported(display),
+            TextureFormat::UncompressedUnsigned(format) => format.is_supported(display),
+            TextureFormat::CompressedFormat(format) => format.is_supported(display),
+            TextureFormat::DepthFormat(format) => format.is_supported(display),
+            TextureFormat::StencilFormat(format) => format.is_supported(display),
+            TextureFormat::DepthStencilFormat(format) => format.is_supported(display),
+        }
+    }
 }
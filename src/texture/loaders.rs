@@ -0,0 +1,283 @@
+/*!
+Loaders for the KTX and DDS texture containers.
+
+These containers usually hold textures that have been compressed ahead of time, along with
+their mipmaps. The loaders parse the container, pick the right `CompressedFormat` and upload
+all the mipmap levels at once.
+
+Only two-dimensional compressed textures are supported right now.
+
+# Features
+
+Only available if the `texture_loaders` feature is enabled.
+
+*/
+use gl;
+
+use std::io::{IoError, Reader};
+
+use Display;
+
+use super::{CompressedFormat, CompressedTexture2d, TextureCreationError};
+
+/// Error that can happen when loading a texture from a container.
+#[deriving(Show)]
+pub enum TextureLoadError {
+    /// Error while reading the data.
+    IoError(IoError),
+
+    /// The data is not a valid container, or is truncated.
+    InvalidData,
+
+    /// The container is valid, but the format of the texture is not supported by glium or
+    /// by the backend.
+    UnsupportedFormat,
+
+    /// The container holds a cubemap, a volume or an array of textures. Only two-dimensional
+    /// textures are supported.
+    UnsupportedTextureType,
+
+    /// The data was valid, but the backend failed to create the texture.
+    CreationError(TextureCreationError),
+}
+
+impl ::std::error::Error for TextureLoadError {
+    fn description(&self) -> &str {
+        match self {
+            &TextureLoadError::IoError(_) => "Error while reading the data",
+            &TextureLoadError::InvalidData => "The data is not a valid texture container",
+            &TextureLoadError::UnsupportedFormat => "The format of the texture is not supported",
+            &TextureLoadError::UnsupportedTextureType => "Only two-dimensional textures are \
+                                                          supported",
+            &TextureLoadError::CreationError(_) => "Error while creating the texture",
+        }
+    }
+
+    fn detail(&self) -> Option<String> {
+        match self {
+            &TextureLoadError::IoError(ref e) => e.detail.clone(),
+            _ => None,
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match self {
+            &TextureLoadError::IoError(ref e) => Some(e as &::std::error::Error),
+            &TextureLoadError::CreationError(ref e) => Some(e as &::std::error::Error),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::error::FromError<IoError> for TextureLoadError {
+    fn from_error(err: IoError) -> TextureLoadError {
+        TextureLoadError::IoError(err)
+    }
+}
+
+/// Loads a texture from a DDS file.
+///
+/// Supports the DXT1, DXT3, DXT5, ATI1 and ATI2 codes, and BC4 to BC7 with the DX10 header.
+///
+/// Cubemaps, volume textures and texture arrays are rejected with `UnsupportedTextureType`.
+pub fn load_dds<R>(display: &Display, mut reader: R)
+                   -> Result<CompressedTexture2d, TextureLoadError> where R: Reader
+{
+    let data = try!(reader.read_to_end());
+
+    if data.len() < 128 || data.slice_to(4) != b"DDS " {
+        return Err(TextureLoadError::InvalidData);
+    }
+
+    let height = try!(read_u32(data.as_slice(), 12, false));
+    let width = try!(read_u32(data.as_slice(), 16, false));
+    let mipmaps = try!(read_u32(data.as_slice(), 28, false));
+    let four_cc = data.slice(84, 88);
+
+    // cubemaps and volume textures are flagged in the header
+    let flags = try!(read_u32(data.as_slice(), 8, false));
+    let depth = try!(read_u32(data.as_slice(), 24, false));
+    let caps2 = try!(read_u32(data.as_slice(), 112, false));
+    if (flags & 0x800000 != 0 && depth > 1) || caps2 & (0x200 | 0x200000) != 0 {
+        return Err(TextureLoadError::UnsupportedTextureType);
+    }
+
+    let (format, offset) = match four_cc {
+        b"DXT1" => (CompressedFormat::S3tcDxt1Alpha, 128),
+        b"DXT3" => (CompressedFormat::S3tcDxt3Alpha, 128),
+        b"DXT5" => (CompressedFormat::S3tcDxt5Alpha, 128),
+        b"ATI1" | b"BC4U" => (CompressedFormat::RGTCFormatU, 128),
+        b"BC4S" => (CompressedFormat::RGTCFormatI, 128),
+        b"ATI2" | b"BC5U" => (CompressedFormat::RGTCFormatUU, 128),
+        b"BC5S" => (CompressedFormat::RGTCFormatII, 128),
+        b"DX10" => {
+            let format = match try!(read_u32(data.as_slice(), 128, false)) {
+                71 => CompressedFormat::S3tcDxt1Alpha,
+                72 => CompressedFormat::S3tcDxt1SrgbAlpha,
+                74 => CompressedFormat::S3tcDxt3Alpha,
+                75 => CompressedFormat::S3tcDxt3SrgbAlpha,
+                77 => CompressedFormat::S3tcDxt5Alpha,
+                78 => CompressedFormat::S3tcDxt5SrgbAlpha,
+                80 => CompressedFormat::RGTCFormatU,
+                81 => CompressedFormat::RGTCFormatI,
+                83 => CompressedFormat::RGTCFormatUU,
+                84 => CompressedFormat::RGTCFormatII,
+                95 => CompressedFormat::BptcUnsignedFloat3,
+                96 => CompressedFormat::BptcSignedFloat3,
+                98 => CompressedFormat::BptcUnorm4,
+                99 => CompressedFormat::BptcSrgb4,
+                _ => return Err(TextureLoadError::UnsupportedFormat)
+            };
+
+            // the DX10 header also contains the dimension, the cubemap flag and the array size
+            let dimension = try!(read_u32(data.as_slice(), 132, false));
+            let misc_flags = try!(read_u32(data.as_slice(), 136, false));
+            let array_size = try!(read_u32(data.as_slice(), 140, false));
+            if dimension != 3 || misc_flags & 0x4 != 0 || array_size > 1 {
+                return Err(TextureLoadError::UnsupportedTextureType);
+            }
+
+            (format, 128 + 20)
+        },
+        _ => return Err(TextureLoadError::UnsupportedFormat)
+    };
+
+    // in DDS files, the mipmaps are stored one after another without any header
+    let mut levels = Vec::new();
+    let mut offset = offset;
+    let (mut level_width, mut level_height) = (width, height);
+    for _ in range(0, ::std::cmp::max(mipmaps, 1)) {
+        let size = format.get_data_size(level_width, level_height);
+        if offset + size > data.len() {
+            return Err(TextureLoadError::InvalidData);
+        }
+
+        levels.push(data.slice(offset, offset + size).to_vec());
+        offset += size;
+
+        level_width = ::std::cmp::max(1, level_width / 2);
+        level_height = ::std::cmp::max(1, level_height / 2);
+    }
+
+    build_texture(display, levels, width, height, format)
+}
+
+/// Loads a texture from a KTX file.
+///
+/// Only compressed formats that are listed in `CompressedFormat` are supported.
+pub fn load_ktx<R>(display: &Display, mut reader: R)
+                   -> Result<CompressedTexture2d, TextureLoadError> where R: Reader
+{
+    static IDENTIFIER: &'static [u8] = &[0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB,
+                                         0x0D, 0x0A, 0x1A, 0x0A];
+
+    let data = try!(reader.read_to_end());
+
+    if data.len() < 64 || data.slice_to(12) != IDENTIFIER {
+        return Err(TextureLoadError::InvalidData);
+    }
+
+    // the endianness field contains 0x04030201 written with the endianness of the file
+    let big_endian = match try!(read_u32(data.as_slice(), 12, false)) {
+        0x04030201 => false,
+        0x01020304 => true,
+        _ => return Err(TextureLoadError::InvalidData)
+    };
+
+    let gl_type = try!(read_u32(data.as_slice(), 16, big_endian));
+    let internal_format = try!(read_u32(data.as_slice(), 28, big_endian));
+    let width = try!(read_u32(data.as_slice(), 36, big_endian));
+    let height = try!(read_u32(data.as_slice(), 40, big_endian));
+    let depth = try!(read_u32(data.as_slice(), 44, big_endian));
+    let array_elements = try!(read_u32(data.as_slice(), 48, big_endian));
+    let faces = try!(read_u32(data.as_slice(), 52, big_endian));
+    let mipmaps = try!(read_u32(data.as_slice(), 56, big_endian));
+    let key_value_bytes = try!(read_u32(data.as_slice(), 60, big_endian));
+
+    // only two-dimensional compressed textures are supported
+    if gl_type != 0 {
+        return Err(TextureLoadError::UnsupportedFormat);
+    }
+
+    if height == 0 || depth != 0 || array_elements != 0 || faces != 1 {
+        return Err(TextureLoadError::UnsupportedTextureType);
+    }
+
+    let format = match internal_format as gl::types::GLenum {
+        gl::COMPRESSED_RED_RGTC1 => CompressedFormat::RGTCFormatU,
+        gl::COMPRESSED_SIGNED_RED_RGTC1 => CompressedFormat::RGTCFormatI,
+        gl::COMPRESSED_RG_RGTC2 => CompressedFormat::RGTCFormatUU,
+        gl::COMPRESSED_SIGNED_RG_RGTC2 => CompressedFormat::RGTCFormatII,
+        gl::COMPRESSED_RGB_S3TC_DXT1_EXT => CompressedFormat::S3tcDxt1NoAlpha,
+        gl::COMPRESSED_RGBA_S3TC_DXT1_EXT => CompressedFormat::S3tcDxt1Alpha,
+        gl::COMPRESSED_RGBA_S3TC_DXT3_EXT => CompressedFormat::S3tcDxt3Alpha,
+        gl::COMPRESSED_RGBA_S3TC_DXT5_EXT => CompressedFormat::S3tcDxt5Alpha,
+        gl::COMPRESSED_RGB8_ETC2 => CompressedFormat::Etc2Rgb8,
+        gl::COMPRESSED_RGBA8_ETC2_EAC => CompressedFormat::Etc2Rgba8,
+        gl::COMPRESSED_RGBA_BPTC_UNORM => CompressedFormat::BptcUnorm4,
+        gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT => CompressedFormat::BptcSignedFloat3,
+        gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT => CompressedFormat::BptcUnsignedFloat3,
+        gl::COMPRESSED_SRGB_S3TC_DXT1_EXT => CompressedFormat::S3tcDxt1SrgbNoAlpha,
+        gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT => CompressedFormat::S3tcDxt1SrgbAlpha,
+        gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT => CompressedFormat::S3tcDxt3SrgbAlpha,
+        gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT => CompressedFormat::S3tcDxt5SrgbAlpha,
+        gl::COMPRESSED_SRGB8_ETC2 => CompressedFormat::Etc2Srgb8,
+        gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC => CompressedFormat::Etc2SrgbAlpha8,
+        gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM => CompressedFormat::BptcSrgb4,
+        _ => return Err(TextureLoadError::UnsupportedFormat)
+    };
+
+    // each mipmap level is preceded by its size and padded to four bytes
+    let mut levels = Vec::new();
+    let mut offset = 64 + key_value_bytes as uint;
+    for _ in range(0, ::std::cmp::max(mipmaps, 1)) {
+        let size = try!(read_u32(data.as_slice(), offset, big_endian)) as uint;
+        offset += 4;
+
+        if offset + size > data.len() {
+            return Err(TextureLoadError::InvalidData);
+        }
+
+        levels.push(data.slice(offset, offset + size).to_vec());
+        offset += (size + 3) & !3;
+    }
+
+    build_texture(display, levels, width, height, format)
+}
+
+/// Checks the content of the levels and builds the texture.
+fn build_texture(display: &Display, levels: Vec<Vec<u8>>, width: u32, height: u32,
+                 format: CompressedFormat) -> Result<CompressedTexture2d, TextureLoadError>
+{
+    if !format.is_supported(display) {
+        return Err(TextureLoadError::UnsupportedFormat);
+    }
+
+    let (mut level_width, mut level_height) = (width, height);
+    for level in levels.iter() {
+        if level.len() != format.get_data_size(level_width, level_height) {
+            return Err(TextureLoadError::InvalidData);
+        }
+
+        level_width = ::std::cmp::max(1, level_width / 2);
+        level_height = ::std::cmp::max(1, level_height / 2);
+    }
+
+    CompressedTexture2d::with_compressed_mipmaps(display, levels, width, height, format)
+        .map_err(TextureLoadError::CreationError)
+}
+
+/// Reads a `u32` at the given offset.
+fn read_u32(data: &[u8], offset: uint, big_endian: bool) -> Result<u32, TextureLoadError> {
+    if offset + 4 > data.len() {
+        return Err(TextureLoadError::InvalidData);
+    }
+
+    let b = data.slice(offset, offset + 4);
+
+    Ok(if big_endian {
+        (b[0] as u32 << 24) | (b[1] as u32 << 16) | (b[2] as u32 << 8) | b[3] as u32
+    } else {
+        (b[3] as u32 << 24) | (b[2] as u32 << 16) | (b[1] as u32 << 8) | b[0] as u32
+    })
+}
@@ -0,0 +1,148 @@
+/*!
+Moving textures between displays that don't share their objects.
+
+Displays created with `build_shared` can use each other's textures directly. Other displays,
+for example the windows of an editor plugin that has its own context, need a copy of the
+texture that belongs to them. The `migrate` function builds this copy.
+
+The content of the texture is read back by the display that owns it, then uploaded to the
+other display. The copy has the same internal format and dimensions as the original, but only
+its main level is transferred. If the original has mipmaps, they are generated again.
+
+`GL_EXT_external_objects` only allows OpenGL to import memory that has been allocated by
+another API, and not to export its own textures. Migrations always go through the CPU.
+
+# Features
+
+Only available if the `gl_extensions` feature is enabled.
+
+*/
+use gl;
+
+use std::mem;
+
+use context;
+use Display;
+use GlObject;
+
+use super::{MipmapsOption, Texture, Texture2d};
+use super::tex_impl::TextureImplementation;
+
+/// Error that can happen when migrating a texture to another display.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The display that owns the texture can't read back the content of textures. This is
+    /// the case with OpenGL ES.
+    ReadNotSupported,
+
+    /// The display that receives the texture can't create it. This is the case with
+    /// OpenGL ES.
+    UploadNotSupported,
+
+    /// The format of the texture can't be transferred. Only floating-point and normalized
+    /// color textures can be migrated. Compressed, integral, depth and stencil textures
+    /// can't.
+    UnsupportedFormat,
+}
+
+impl ::std::error::Error for MigrationError {
+    fn description(&self) -> &str {
+        match self {
+            &MigrationError::ReadNotSupported => {
+                "The display that owns the texture can't read back its content"
+            },
+            &MigrationError::UploadNotSupported => {
+                "The display that receives the texture can't create it"
+            },
+            &MigrationError::UnsupportedFormat => {
+                "The format of the texture can't be transferred to another display"
+            },
+        }
+    }
+}
+
+/// Builds a copy of a texture that belongs to another display.
+///
+/// The original texture is not modified and can still be used by its own display.
+///
+/// # Example
+///
+/// ```no_run
+/// # let plugin_display: glium::Display = unsafe { std::mem::uninitialized() };
+/// # let texture: glium::Texture2d = unsafe { std::mem::uninitialized() };
+/// let copy = glium::texture::migrate(&texture, &plugin_display).unwrap();
+/// ```
+pub fn migrate(texture: &Texture2d, display: &Display) -> Result<Texture2d, MigrationError> {
+    let source = texture.0.get_display();
+
+    if source.context.context.capabilities().opengl_es {
+        return Err(MigrationError::ReadNotSupported);
+    }
+
+    if display.context.context.capabilities().opengl_es {
+        return Err(MigrationError::UploadNotSupported);
+    }
+
+    let format = match get_float_format(&texture.0) {
+        Some(format) => format,
+        None => return Err(MigrationError::UnsupportedFormat),
+    };
+
+    let data = texture.0.read::<(f32, f32, f32, f32)>(0);
+
+    let mipmaps = if texture.get_mipmap_levels() > 1 {
+        MipmapsOption::AutoGeneratedMipmaps
+    } else {
+        MipmapsOption::NoMipmap
+    };
+
+    Ok(Texture2d(TextureImplementation::new(display, format, Some(data), gl::RGBA, gl::FLOAT,
+                                            texture.get_width(), texture.get_height(), None,
+                                            None, mipmaps)))
+}
+
+/// Returns the internal format of the main level of a texture, or `None` if its content
+/// can't be read back as floating-point values.
+fn get_float_format(texture: &TextureImplementation) -> Option<gl::types::GLenum> {
+    let id = texture.get_id();
+    let bind_point = texture.get_bind_point();
+
+    let (tx, rx) = channel();
+    texture.get_display().context.context.exec(move |: mut ctxt| {
+        unsafe {
+            context::bind_texture(&mut ctxt, bind_point, id);
+
+            let mut compressed = mem::uninitialized();
+            ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_COMPRESSED,
+                                           &mut compressed);
+
+            let mut depth_size = mem::uninitialized();
+            ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_DEPTH_SIZE,
+                                           &mut depth_size);
+
+            let mut stencil_size = mem::uninitialized();
+            ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_STENCIL_SIZE,
+                                           &mut stencil_size);
+
+            // integral formats return `GL_INT` or `GL_UNSIGNED_INT`
+            let mut red_type = mem::uninitialized();
+            ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_RED_TYPE,
+                                           &mut red_type);
+            let red_type = red_type as gl::types::GLenum;
+
+            let mut format = mem::uninitialized();
+            ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_INTERNAL_FORMAT,
+                                           &mut format);
+
+            if compressed != 0 || depth_size != 0 || stencil_size != 0 ||
+               red_type == gl::INT || red_type == gl::UNSIGNED_INT
+            {
+                tx.send(None);
+            } else {
+                tx.send(Some(format as gl::types::GLenum));
+            }
+        }
+    });
+
+    rx.recv()
+}
@@ -53,11 +53,33 @@ use self::tex_impl::TextureImplementation;
 pub use self::format::{ClientFormat, TextureFormat};
 pub use self::format::{UncompressedFloatFormat, UncompressedIntFormat, UncompressedUintFormat};
 pub use self::format::{CompressedFormat, DepthFormat, DepthStencilFormat, StencilFormat};
-pub use self::pixel::PixelValue;
-
+pub use self::pixel::{PixelValue, f16};
+pub use self::buffer_texture::{BufferTexture, BufferTextureRef, BufferTextureMapping};
+pub use self::buffer_texture::{BufferTextureType, TextureBufferContent};
+pub use self::buffer_texture::{BufferTextureCreationError, BufferTextureSlice};
+pub use self::cubemap::{Cubemap, CubemapFace, CubeLayer};
+pub use self::multisample::{Texture2dMultisampleArray, Texture2dMultisampleArrayLayer};
+pub use self::uploader::TextureUploader;
+#[cfg(feature = "texture_loaders")]
+pub use self::loaders::{load_dds, load_ktx, TextureLoadError};
+#[cfg(feature = "gl_extensions")]
+pub use self::migrate::{migrate, MigrationError};
+#[cfg(all(feature = "video_interop", target_os = "linux"))]
+pub use self::video::{VdpauInterop, VdpauSurface, MappedVdpauSurface, VaapiSurface};
+
+mod buffer_texture;
+mod cubemap;
+#[cfg(feature = "texture_loaders")]
+mod loaders;
 mod format;
+#[cfg(feature = "gl_extensions")]
+mod migrate;
+mod multisample;
 mod pixel;
 mod tex_impl;
+mod uploader;
+#[cfg(all(feature = "video_interop", target_os = "linux"))]
+mod video;
 
 include!(concat!(env!("OUT_DIR"), "/textures.rs"));
 
@@ -74,6 +96,147 @@ pub trait Texture {
 
 	/// Returns the number of textures in the array, or `None` for non-arrays.
 	fn get_array_size(&self) -> Option<u32>;
+
+	/// Returns the number of mipmap levels of the texture, including the main one.
+	fn get_mipmap_levels(&self) -> u32;
+}
+
+/// Describes what to do about mipmaps when creating a texture.
+///
+/// The number of levels that are allocated determines whether the texture is complete
+/// with the default sampling parameters. Textures that have only one level are sampled
+/// without mipmapping, while textures with more levels use trilinear filtering by default.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum MipmapsOption {
+	/// The texture only has its main level.
+	NoMipmap,
+
+	/// All the mipmap levels are allocated and generated from the main level.
+	///
+	/// This is the default for textures that contain colors. Mipmaps can't be generated
+	/// for depth and stencil textures.
+	AutoGeneratedMipmaps,
+
+	/// All the mipmap levels are allocated, but they are generated from the main level by
+	/// the rendering thread at the end of later frames, one level per step of
+	/// `Display::set_background_budget`.
+	///
+	/// Until then, the texture is sampled as if it only had the levels that are ready. This
+	/// avoids frame spikes when textures are streamed in while the application is running.
+	BackgroundGeneratedMipmaps,
+
+	/// The given number of mipmap levels, including the main one, are allocated but only
+	/// the main level is filled. The other levels contain undefined data until they are
+	/// written with `write_mipmap`.
+	EmptyMipmaps(u32),
+}
+
+/// Describes how the pixels of an upload are laid out in memory.
+///
+/// This allows uploading data whose rows are padded, or which is a part of a larger image,
+/// without repacking it first. The default value describes tightly packed rows, starting with
+/// the bottom row.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub struct UploadParameters {
+	/// Alignment in bytes of the start of each row. Must be 1, 2, 4 or 8.
+	///
+	/// The default value is `1`.
+	pub alignment: u32,
+
+	/// Number of pixels between the start of a row and the start of the next one, or `None`
+	/// if it is the width of the uploaded rectangle.
+	///
+	/// The default value is `None`.
+	pub row_length: Option<u32>,
+
+	/// Number of pixels to skip at the start of each row.
+	///
+	/// The default value is `0`.
+	pub skip_pixels: u32,
+
+	/// Number of rows to skip at the start of the data.
+	///
+	/// The default value is `0`.
+	pub skip_rows: u32,
+
+	/// If true, the first row of the data is the top of the rectangle instead of the bottom,
+	/// like with most image files and video decoders.
+	///
+	/// The default value is `false`.
+	pub flip_vertically: bool,
+}
+
+impl ::std::default::Default for UploadParameters {
+	fn default() -> UploadParameters {
+		UploadParameters {
+			alignment: 1,
+			row_length: None,
+			skip_pixels: 0,
+			skip_rows: 0,
+			flip_vertically: false,
+		}
+	}
+}
+
+impl UploadParameters {
+	/// Returns the minimal number of bytes of the data to upload a rectangle of the given
+	/// dimensions.
+	///
+	/// # Panic
+	///
+	/// Panics if the alignment is not 1, 2, 4 or 8.
+	fn get_data_size(&self, width: u32, height: u32, pixel_size: uint) -> uint {
+		match self.alignment {
+			1 | 2 | 4 | 8 => (),
+			_ => panic!("The alignment must be 1, 2, 4 or 8")
+		};
+
+		if width == 0 || height == 0 {
+			return 0;
+		}
+
+		let alignment = self.alignment as uint;
+		let row_length = self.row_length.unwrap_or(width) as uint;
+		let stride = (row_length * pixel_size + alignment - 1) / alignment * alignment;
+
+		(self.skip_rows + height - 1) as uint * stride +
+			(self.skip_pixels + width) as uint * pixel_size
+	}
+}
+
+/// Error that can happen while creating a texture.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum TextureCreationError {
+	/// The requested format is not supported by the backend.
+	FormatNotSupported,
+
+	/// This kind of texture is not supported by the backend.
+	TypeNotSupported,
+
+	/// The requested number of samples is not supported by the backend.
+	SamplesNotSupported,
+
+	/// The length of the data doesn't match the dimensions and the format of the texture.
+	DataSizeMismatch,
+}
+
+impl ::std::error::Error for TextureCreationError {
+	fn description(&self) -> &str {
+		match self {
+			&TextureCreationError::FormatNotSupported => {
+				"The requested format is not supported by the backend"
+			},
+			&TextureCreationError::TypeNotSupported => {
+				"This kind of texture is not supported by the backend"
+			},
+			&TextureCreationError::SamplesNotSupported => {
+				"The requested number of samples is not supported by the backend"
+			},
+			&TextureCreationError::DataSizeMismatch => {
+				"Texture data size mismatch"
+			},
+		}
+	}
 }
 
 /// Trait that describes data for a one-dimensional texture.
@@ -271,26 +434,267 @@ impl<P: PixelValue> Texture3dData for Vec<Vec<Vec<P>>> {
 ///
 /// The generic type represents the type of pixels that the buffer contains.
 ///
-/// **Note**: pixel buffers are unusable for the moment (they are not yet implemented).
+/// A pixel buffer can be obtained by calling `read_to_pixel_buffer` on a surface. The transfer
+/// between the surface and the buffer is done asynchronously by the GPU.
 pub struct PixelBuffer<T> {
 	buffer: Buffer,
 }
 
-impl<T> PixelBuffer<T> where T: PixelValue {
+impl<T> PixelBuffer<T> where T: Send {
 	/// Builds a new buffer with an uninitialized content.
+	///
+	/// The capacity is the number of elements of type `T` that the buffer can contain.
 	pub fn new_empty(display: &super::Display, capacity: uint) -> PixelBuffer<T> {
+		use std::mem;
+
 		PixelBuffer {
-			buffer: Buffer::new_empty::<buffer::PixelUnpackBuffer>(display, 1, capacity,
-																   gl::DYNAMIC_READ),
+			buffer: Buffer::new_empty::<buffer::PixelPackBuffer>(display, mem::size_of::<T>(),
+																 capacity, gl::DYNAMIC_READ),
 		}
 	}
 
+	/// Returns the number of elements that the buffer can contain.
+	pub fn get_capacity(&self) -> uint {
+		self.buffer.get_elements_count()
+	}
+
+	/// Reads the content of the pixel buffer.
+	///
+	/// This function blocks until the data has been transferred to the buffer.
+	///
+	/// # Features
+	///
+	/// Only available if the `gl_extensions` feature is enabled.
+	#[cfg(feature = "gl_extensions")]
+	pub fn read(&self) -> Vec<T> {
+		self.buffer.read::<buffer::PixelPackBuffer, T>()
+	}
+
 	/// Turns a `PixelBuffer<T>` into a `PixelBuffer<U>` without any check.
-	pub unsafe fn transmute<U>(self) -> PixelBuffer<U> where U: PixelValue {
+	pub unsafe fn transmute<U>(self) -> PixelBuffer<U> where U: Send {
 		PixelBuffer { buffer: self.buffer }
 	}
 }
 
+impl<T> GlObject for PixelBuffer<T> {
+	fn get_id(&self) -> gl::types::GLuint {
+		self.buffer.get_id()
+	}
+}
+
+/// Handle to an upload that has been started with `write_async`.
+///
+/// The upload continues even if the handle is destroyed.
+pub struct TextureUpload {
+	display: super::Display,
+	fence: Option<Arc<::sync::SyncFence>>,
+}
+
+impl TextureUpload {
+	/// Inserts a fence after the upload that has just been submitted.
+	fn new(display: &super::Display) -> TextureUpload {
+		TextureUpload::with_fence(display, ::sync::SyncFence::new(display).map(|f| Arc::new(f)))
+	}
+
+	/// Builds a handle from a fence that has been inserted after the upload.
+	fn with_fence(display: &super::Display, fence: Option<Arc<::sync::SyncFence>>)
+		-> TextureUpload
+	{
+		TextureUpload {
+			display: display.clone(),
+			fence: fence,
+		}
+	}
+
+	/// Returns true if the GPU has finished copying the data into the texture.
+	///
+	/// Doesn't block, except if the backend doesn't support fences. In this situation, this
+	/// function waits for all the commands to be executed and returns true.
+	pub fn is_complete(&self) -> bool {
+		match self.fence {
+			Some(ref fence) => fence.is_signaled(),
+			None => {
+				self.display.synchronize();
+				true
+			}
+		}
+	}
+
+	/// Blocks until the GPU has finished copying the data into the texture.
+	pub fn wait(self) {
+		match self.fence {
+			Some(ref fence) => fence.wait(),
+			None => self.display.synchronize(),
+		}
+	}
+}
+
+/// A texture that shares the storage of another texture.
+///
+/// A view is built without copying any data, and can reinterpret the content of a texture
+/// with another format or expose only some of its mipmap levels or layers. Writing to the view
+/// modifies the original texture, and the other way around.
+///
+/// The view derefs to a regular texture and can be used wherever this type of texture is
+/// expected. The storage stays alive as long as the view or the original texture exists.
+pub struct TextureView<T>(T);
+
+impl<T> Deref<T> for TextureView<T> {
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+
+impl Texture2d {
+	/// Wraps a two-dimensional texture that has been created outside of glium, for example by
+	/// a library that uses the same OpenGL context.
+	///
+	/// Glium takes the ownership of the texture, which is deleted when the returned object is
+	/// destroyed. Only the main level of the texture is used: the minifying filter of the
+	/// texture is set to `GL_LINEAR` and its maximum level to 0.
+	///
+	/// # Safety
+	///
+	/// `id` must be the name of a `GL_TEXTURE_2D` texture that belongs to the context of the
+	/// display or to a context that shares its objects, and its main level must have been
+	/// allocated with the given dimensions.
+	pub unsafe fn from_id(display: &super::Display, id: gl::types::GLuint, width: u32,
+		height: u32) -> Texture2d
+	{
+		Texture2d(TextureImplementation::from_id_2d(display, id, width, height, true))
+	}
+
+	/// Same as `from_id`, except that glium doesn't take the ownership of the texture. The
+	/// texture is not deleted when the returned object is destroyed.
+	///
+	/// # Safety
+	///
+	/// Same as `from_id`. In addition, the texture must not be deleted while the returned
+	/// object is alive.
+	pub unsafe fn from_id_unowned(display: &super::Display, id: gl::types::GLuint, width: u32,
+		height: u32) -> Texture2d
+	{
+		Texture2d(TextureImplementation::from_id_2d(display, id, width, height, false))
+	}
+}
+
+impl Texture2dArray {
+	/// Returns an object representing one of the layers of the array.
+	///
+	/// This object can be used as the color attachment of a `SimpleFrameBuffer`, to draw on
+	/// the layer or to update its content.
+	///
+	/// # Panic
+	///
+	/// Panics if the layer is outside of the array.
+	pub fn layer<'a>(&'a self, layer: u32) -> Texture2dArrayLayer<'a> {
+		if layer >= self.get_array_size().unwrap() {
+			panic!("The layer {} is outside of the texture array", layer);
+		}
+
+		Texture2dArrayLayer {
+			texture: self,
+			layer: layer,
+		}
+	}
+}
+
+/// One of the layers of a texture array.
+///
+/// Obtained by calling `texture.layer(index)`.
+#[deriving(Copy, Clone)]
+pub struct Texture2dArrayLayer<'a> {
+	texture: &'a Texture2dArray,
+	layer: u32,
+}
+
+impl<'a> Texture2dArrayLayer<'a> {
+	/// Returns the texture array this layer belongs to.
+	pub fn get_texture(&self) -> &'a Texture2dArray {
+		self.texture
+	}
+
+	/// Returns the index of the layer in the array.
+	pub fn get_layer(&self) -> u32 {
+		self.layer
+	}
+
+	/// Starts drawing on the layer.
+	///
+	/// See `Texture2d::as_surface` for more informations.
+	pub fn as_surface(&self) -> TextureSurface<'a> {
+		TextureSurface(framebuffer::SimpleFrameBuffer::from_color_attachment(
+			self.texture.0.get_display(),
+			framebuffer::ColorAttachment::Texture2dArrayLayer(self.texture, self.layer)))
+	}
+
+	/// Uploads some data in the layer. See `Texture2dArray::write`.
+	pub fn write<T>(&self, rect: ::Rect, data: T) where T: Texture2dData {
+		self.texture.write(self.layer, rect, data)
+	}
+}
+
+impl<'a> framebuffer::ToColorAttachment for Texture2dArrayLayer<'a> {
+	fn to_color_attachment(&self) -> framebuffer::ColorAttachment {
+		framebuffer::ColorAttachment::Texture2dArrayLayer(self.texture, self.layer)
+	}
+}
+
+/// Component of a depth-stencil texture that is read when the texture is sampled.
+#[deriving(Show, Clone, Copy, PartialEq, Eq)]
+pub enum DepthStencilTextureMode {
+	/// The depth component is read as a floating-point value. The uniform must be a
+	/// `sampler2D`, or a `sampler2DShadow` if the sampler compares the depth.
+	Depth,
+
+	/// The stencil component is read as an unsigned integer. The uniform must be a
+	/// `usampler2D`.
+	Stencil,
+}
+
+impl ToGlEnum for DepthStencilTextureMode {
+	fn to_glenum(&self) -> gl::types::GLenum {
+		match *self {
+			DepthStencilTextureMode::Depth => gl::DEPTH_COMPONENT,
+			DepthStencilTextureMode::Stencil => gl::STENCIL_INDEX,
+		}
+	}
+}
+
+impl DepthStencilTexture2d {
+	/// Returns an object that reads the stencil component of the texture when it is used as a
+	/// uniform. Using the texture itself reads the depth component.
+	///
+	/// The stencil component is always sampled with a `Nearest` filter.
+	///
+	/// The texture can't be sampled both for its depth and for its stencil component by the
+	/// same draw.
+	///
+	/// # Features
+	///
+	/// Drawing with this uniform returns an error if the backend doesn't support stencil
+	/// texturing. It requires OpenGL 4.3, OpenGL ES 3.1 or the `GL_ARB_stencil_texturing`
+	/// extension.
+	pub fn stencil<'a>(&'a self) -> DepthStencilTexture2dStencil<'a> {
+		DepthStencilTexture2dStencil {
+			texture: self,
+		}
+	}
+}
+
+/// The stencil component of a depth-stencil texture.
+///
+/// Obtained by calling `texture.stencil()`.
+#[deriving(Copy, Clone)]
+pub struct DepthStencilTexture2dStencil<'a> {
+	texture: &'a DepthStencilTexture2d,
+}
+
+impl<'a> IntoUniformValue<'a> for DepthStencilTexture2dStencil<'a> {
+	fn into_uniform_value(self) -> UniformValue<'a> {
+		UniformValue::DepthStencilTexture2d(self.texture, None, DepthStencilTextureMode::Stencil)
+	}
+}
 
 /// Struct that allows you to draw on a texture.
 ///
@@ -323,7 +727,7 @@ impl<'a> Surface for TextureSurface<'a> {
 	}
 
 	fn draw<'b, 'v, V, I, ID, U>(&mut self, vb: V, ib: &I, program: &::Program,
-		uniforms: U, draw_parameters: &::DrawParameters)
+		uniforms: U, draw_parameters: &::DrawParameters) -> Result<(), ::DrawError>
 		where I: ::index_buffer::ToIndicesSource<ID>,
 		U: ::uniforms::Uniforms, V: ::vertex_buffer::IntoVerticesSource<'v>
 	{
@@ -0,0 +1,158 @@
+use gl;
+
+use {Display, GlObject, ToGlEnum};
+use framebuffer::{mod, ColorAttachment, ToColorAttachment};
+use uniforms::{UniformValue, IntoUniformValue};
+
+use super::{Texture, TextureCreationError, TextureSurface, UncompressedFloatFormat};
+use super::tex_impl::TextureImplementation;
+
+/// An array of two-dimensional multisample textures containing floating-point data.
+///
+/// Each texel of a multisample texture contains several samples. The layers of the array can
+/// be used as color attachments, for example to render the same scene several times with
+/// different jitters in a temporal antialiasing implementation.
+///
+/// Multisample textures can't be sampled with filtering. They must be binded to a
+/// `sampler2DMSArray` uniform in GLSL and read with `texelFetch`.
+pub struct Texture2dMultisampleArray {
+    texture: TextureImplementation,
+    samples: u32,
+}
+
+impl Texture2dMultisampleArray {
+    /// Returns true if the backend supports multisample texture arrays.
+    pub fn is_supported(display: &Display) -> bool {
+        display.context.context.capabilities().texture_multisample
+    }
+
+    /// Creates an empty multisample texture array.
+    ///
+    /// The texture will contain undefined data.
+    ///
+    /// Returns an error if the backend doesn't support multisample textures or the format, or
+    /// if the number of samples is 0 or is greater than the maximum supported by the backend.
+    pub fn new_empty(display: &Display, format: UncompressedFloatFormat, width: u32,
+                     height: u32, array_size: u32, samples: u32)
+                     -> Result<Texture2dMultisampleArray, TextureCreationError>
+    {
+        let capabilities = display.context.context.capabilities();
+
+        if !capabilities.texture_multisample {
+            return Err(TextureCreationError::TypeNotSupported);
+        }
+
+        if !format.is_supported(display) {
+            return Err(TextureCreationError::FormatNotSupported);
+        }
+
+        if samples == 0 || samples > capabilities.max_samples as u32 {
+            return Err(TextureCreationError::SamplesNotSupported);
+        }
+
+        Ok(Texture2dMultisampleArray {
+            texture: TextureImplementation::new_multisample_array(display, format.to_glenum(),
+                                                                  width, height, array_size,
+                                                                  samples),
+            samples: samples,
+        })
+    }
+
+    /// Returns the number of samples of each texel.
+    pub fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns an object representing one of the layers of the array.
+    ///
+    /// This object can be used as the color attachment of a `SimpleFrameBuffer`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the layer is outside of the array.
+    pub fn layer<'a>(&'a self, layer: u32) -> Texture2dMultisampleArrayLayer<'a> {
+        if layer >= self.texture.get_array_size().unwrap() {
+            panic!("The layer {} is outside of the texture array", layer);
+        }
+
+        Texture2dMultisampleArrayLayer {
+            texture: self,
+            layer: layer,
+        }
+    }
+
+    /// Starts drawing on one of the layers of the array.
+    ///
+    /// See `Texture2d::as_surface` for more informations.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the layer is outside of the array.
+    pub fn as_surface_for_layer<'a>(&'a self, layer: u32) -> TextureSurface<'a> {
+        let layer = self.layer(layer).get_layer();
+        TextureSurface(framebuffer::SimpleFrameBuffer::from_color_attachment(
+                       self.texture.get_display(),
+                       ColorAttachment::Texture2dMultisampleArrayLayer(self, layer)))
+    }
+}
+
+impl Texture for Texture2dMultisampleArray {
+    fn get_width(&self) -> u32 {
+        self.texture.get_width()
+    }
+
+    fn get_height(&self) -> Option<u32> {
+        self.texture.get_height()
+    }
+
+    fn get_depth(&self) -> Option<u32> {
+        self.texture.get_depth()
+    }
+
+    fn get_array_size(&self) -> Option<u32> {
+        self.texture.get_array_size()
+    }
+
+    fn get_mipmap_levels(&self) -> u32 {
+        self.texture.get_mipmap_levels()
+    }
+}
+
+impl GlObject for Texture2dMultisampleArray {
+    fn get_id(&self) -> gl::types::GLuint {
+        self.texture.get_id()
+    }
+}
+
+impl<'a> IntoUniformValue<'a> for &'a Texture2dMultisampleArray {
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        UniformValue::Texture2dMultisampleArray(self)
+    }
+}
+
+/// One of the layers of a multisample texture array.
+///
+/// Obtained by calling `texture.layer(index)`.
+#[deriving(Copy, Clone)]
+pub struct Texture2dMultisampleArrayLayer<'a> {
+    texture: &'a Texture2dMultisampleArray,
+    layer: u32,
+}
+
+impl<'a> Texture2dMultisampleArrayLayer<'a> {
+    /// Returns the texture array this layer belongs to.
+    pub fn get_texture(&self) -> &'a Texture2dMultisampleArray {
+        self.texture
+    }
+
+    /// Returns the index of the layer in the array.
+    pub fn get_layer(&self) -> u32 {
+        self.layer
+    }
+}
+
+impl<'a> ToColorAttachment for Texture2dMultisampleArrayLayer<'a> {
+    fn to_color_attachment(&self) -> ColorAttachment {
+        ColorAttachment::Texture2dMultisampleArrayLayer(self.texture, self.layer)
+    }
+}
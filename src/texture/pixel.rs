@@ -176,6 +176,112 @@ impl PixelValue for (f32, f32, f32, f32) {
     }
 }
 
+/// A half-precision floating-point number, stored as its 16 bits.
+///
+/// Use this type to upload the content of `F16` textures or to read them back without
+/// converting their pixels to `f32`.
+#[allow(non_camel_case_types)]
+#[deriving(Show, Copy, Clone, PartialEq, Eq)]
+pub struct f16(pub u16);
+
+impl f16 {
+    /// Converts a single-precision number to the nearest half-precision number.
+    ///
+    /// Values that are too large become infinite, and values that are too small become 0.
+    pub fn from_f32(value: f32) -> f16 {
+        let bits: u32 = unsafe { ::std::mem::transmute(value) };
+
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7fffff;
+
+        if exponent == 0xff {
+            // infinity and NaN, which must keep at least one bit of mantissa
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return f16(sign | 0x7c00 | nan);
+        }
+
+        let exponent = exponent - 127 + 15;
+
+        if exponent >= 0x1f {
+            f16(sign | 0x7c00)
+
+        } else if exponent <= 0 {
+            // subnormal half, or 0 if the value is too small
+            if exponent < -10 {
+                return f16(sign);
+            }
+
+            let mantissa = mantissa | 0x800000;
+            let shift = (14 - exponent) as uint;
+            let rounding = (mantissa >> (shift - 1)) & 1;
+            f16(sign | ((mantissa >> shift) + rounding) as u16)
+
+        } else {
+            // the rounding can overflow into the exponent, which gives the correct result
+            let value = ((exponent as u32) << 10) | (mantissa >> 13);
+            let rounding = (mantissa >> 12) & 1;
+            f16(sign | (value + rounding) as u16)
+        }
+    }
+
+    /// Converts the half-precision number to a single-precision number, without any loss.
+    pub fn to_f32(&self) -> f32 {
+        let f16(bits) = *self;
+
+        let sign = ((bits & 0x8000) as u32) << 16;
+        let exponent = ((bits >> 10) & 0x1f) as u32;
+        let mantissa = (bits & 0x3ff) as u32;
+
+        let bits = if exponent == 0x1f {
+            sign | 0x7f800000 | (mantissa << 13)
+
+        } else if exponent == 0 {
+            if mantissa == 0 {
+                sign
+            } else {
+                // subnormal half, which is normalized
+                let mut exponent = 127 - 15 + 1;
+                let mut mantissa = mantissa;
+                while mantissa & 0x400 == 0 {
+                    mantissa <<= 1;
+                    exponent -= 1;
+                }
+                sign | (exponent << 23) | ((mantissa & 0x3ff) << 13)
+            }
+
+        } else {
+            sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)
+        };
+
+        unsafe { ::std::mem::transmute(bits) }
+    }
+}
+
+impl PixelValue for f16 {
+    fn get_format(_: Option<f16>) -> super::ClientFormat {
+        super::ClientFormat::F16
+    }
+}
+
+impl PixelValue for (f16, f16) {
+    fn get_format(_: Option<(f16, f16)>) -> super::ClientFormat {
+        super::ClientFormat::F16F16
+    }
+}
+
+impl PixelValue for (f16, f16, f16) {
+    fn get_format(_: Option<(f16, f16, f16)>) -> super::ClientFormat {
+        super::ClientFormat::F16F16F16
+    }
+}
+
+impl PixelValue for (f16, f16, f16, f16) {
+    fn get_format(_: Option<(f16, f16, f16, f16)>) -> super::ClientFormat {
+        super::ClientFormat::F16F16F16F16
+    }
+}
+
 #[cfg(feature = "image")]
 impl PixelValue for image::Rgb<u8> {
     fn get_format(_: Option<image::Rgb<u8>>) -> super::ClientFormat {
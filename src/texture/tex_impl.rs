@@ -1,15 +1,19 @@
 use Display;
 
-use super::PixelValue;
+use super::{CompressedFormat, MipmapsOption, PixelValue, UploadParameters};
 
 use gl;
 use GlObject;
-use context::GlVersion;
+use ToGlEnum;
+use buffer::{mod, Buffer};
+use context::{mod, GlVersion};
 
 use libc;
 use std::fmt;
 use std::mem;
+use std::num::Int;
 use std::ptr;
+use std::sync::atomic::Relaxed;
 
 pub struct TextureImplementation {
     display: Display,
@@ -19,13 +23,22 @@ pub struct TextureImplementation {
     height: Option<u32>,
     depth: Option<u32>,
     array_size: Option<u32>,
+    levels: u32,
+    // true if the levels other than the main one are generated from the main level
+    generated_mipmaps: bool,
+    // false if the texture has been created outside of glium and must not be deleted
+    owned: bool,
 }
 
 impl TextureImplementation {
     /// Builds a new texture.
+    ///
+    /// `data` is the content of the first mipmap level. The other levels are allocated and
+    /// filled according to `mipmaps`.
     pub fn new<P>(display: &Display, format: gl::types::GLenum, data: Option<Vec<P>>,
         client_format: gl::types::GLenum, client_type: gl::types::GLenum, width: u32,
-        height: Option<u32>, depth: Option<u32>, array_size: Option<u32>) -> TextureImplementation
+        height: Option<u32>, depth: Option<u32>, array_size: Option<u32>,
+        mipmaps: MipmapsOption) -> TextureImplementation
         where P: Send
     {
         use std::num::Float;
@@ -52,11 +65,79 @@ impl TextureImplementation {
             gl::TEXTURE_3D
         };
 
-        let texture_levels = 1 + (::std::cmp::max(width, ::std::cmp::max(height.unwrap_or(1),
-                                 depth.unwrap_or(1))) as f32).log2() as gl::types::GLsizei;
+        let (opengl_es, es2) = {
+            let capabilities = display.context.context.capabilities();
+            (capabilities.opengl_es,
+             capabilities.opengl_es && capabilities.version < GlVersion(3, 0))
+        };
+
+        if opengl_es && (texture_type == gl::TEXTURE_1D || texture_type == gl::TEXTURE_1D_ARRAY) {
+            panic!("One-dimensional textures are not supported by OpenGL ES");
+        }
+
+        if es2 && (texture_type == gl::TEXTURE_3D || texture_type == gl::TEXTURE_2D_ARRAY) {
+            panic!("Three-dimensional textures and texture arrays are not supported by \
+                    OpenGL ES 2.0");
+        }
+
+        let format = get_internal_format(es2, format, client_format);
+        let client_type = get_client_type(es2, client_type);
+
+        // mipmaps can't be generated for depth and stencil textures
+        let can_generate = client_format != gl::DEPTH_COMPONENT &&
+                           client_format != gl::DEPTH_STENCIL &&
+                           client_format != gl::STENCIL_INDEX;
+
+        // integral textures are incomplete if they are sampled with a linear filter
+        let integral = client_format == gl::RED_INTEGER || client_format == gl::RG_INTEGER ||
+                       client_format == gl::RGB_INTEGER || client_format == gl::RGBA_INTEGER ||
+                       client_format == gl::BGR_INTEGER || client_format == gl::BGRA_INTEGER;
+
+        let max_levels = 1 + (::std::cmp::max(width, ::std::cmp::max(height.unwrap_or(1),
+                              depth.unwrap_or(1))) as f32).log2() as u32;
+
+        let (texture_levels, generate_mipmaps, background) = match mipmaps {
+            MipmapsOption::NoMipmap => (1, false, false),
+            MipmapsOption::AutoGeneratedMipmaps => {
+                if !can_generate {
+                    panic!("Mipmaps can't be generated for depth and stencil textures");
+                }
+
+                (max_levels, true, false)
+            },
+            MipmapsOption::BackgroundGeneratedMipmaps => {
+                if !can_generate {
+                    panic!("Mipmaps can't be generated for depth and stencil textures");
+                }
+
+                (max_levels, false, true)
+            },
+            MipmapsOption::EmptyMipmaps(levels) => {
+                if levels == 0 || levels > max_levels {
+                    panic!("The number of mipmap levels must be between 1 and {} for these \
+                            dimensions", max_levels);
+                }
+
+                (levels, false, false)
+            },
+        };
+
+        // OpenGL ES 2.0 doesn't have `GL_TEXTURE_MAX_LEVEL`, so the levels can't be hidden
+        // until they are generated in the background
+        let (generate_mipmaps, background) = if es2 && background {
+            (true, false)
+        } else {
+            (generate_mipmaps, background)
+        };
+
+        // the levels that are allocated by `glTexImage*` instead of `glTexStorage*`
+        let allocated_levels = if generate_mipmaps || background { 1 } else { texture_levels };
+
+        let zero_initialize = data.is_none() &&
+                              display.context.zero_initialization.load(Relaxed);
 
         let (tx, rx) = channel();
-        display.context.context.exec(move |: ctxt| {
+        display.context.context.exec(move |: mut ctxt| {
             unsafe {
                 let data = data;
                 let data_raw = if let Some(ref data) = data {
@@ -70,12 +151,13 @@ impl TextureImplementation {
                 if ctxt.state.pixel_unpack_buffer_binding != 0 {
                     ctxt.state.pixel_unpack_buffer_binding = 0;
                     ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
                 }
 
                 let id: gl::types::GLuint = mem::uninitialized();
                 ctxt.gl.GenTextures(1, mem::transmute(&id));
 
-                ctxt.gl.BindTexture(texture_type, id);
+                context::bind_texture(&mut ctxt, texture_type, id);
 
                 ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
                 if height.is_some() || depth.is_some() || array_size.is_some() {
@@ -84,13 +166,33 @@ impl TextureImplementation {
                 if depth.is_some() || array_size.is_some() {
                     ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_WRAP_R, gl::REPEAT as i32);
                 }
-                ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-                ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_MIN_FILTER,
-                    gl::LINEAR_MIPMAP_LINEAR as i32);
+                let (min_filter, mag_filter) = match (integral, texture_levels >= 2) {
+                    (false, true) => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR),
+                    (false, false) => (gl::LINEAR, gl::LINEAR),
+                    (true, true) => (gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST),
+                    (true, false) => (gl::NEAREST, gl::NEAREST),
+                };
+
+                ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_MAG_FILTER, mag_filter as i32);
+                ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+
+                // the texture would be incomplete if the levels that are not allocated
+                // were taken into account; the levels generated in the background are
+                // ignored until they are ready
+                if !es2 {
+                    let max_level = if background { 0 } else { texture_levels - 1 };
+                    ctxt.gl.TexParameteri(texture_type, gl::TEXTURE_MAX_LEVEL, max_level as i32);
+                }
+
+                let texture_storage = if ctxt.opengl_es {
+                    ctxt.version >= &GlVersion(3, 0)
+                } else {
+                    ctxt.version >= &GlVersion(4, 2) || ctxt.extensions.gl_arb_texture_storage
+                };
 
                 if texture_type == gl::TEXTURE_3D || texture_type == gl::TEXTURE_2D_ARRAY {
-                    if ctxt.version >= &GlVersion(4, 2) || ctxt.extensions.gl_arb_texture_storage {
-                        ctxt.gl.TexStorage3D(texture_type, texture_levels,
+                    if texture_storage {
+                        ctxt.gl.TexStorage3D(texture_type, texture_levels as gl::types::GLsizei,
                                              format as gl::types::GLenum,
                                              width as gl::types::GLsizei,
                                              height.unwrap() as gl::types::GLsizei,
@@ -105,17 +207,19 @@ impl TextureImplementation {
                         }
 
                     } else {
-                        ctxt.gl.TexImage3D(texture_type, 0, format as i32, width as i32,
-                                           height.unwrap() as i32,
-                                           if let Some(d) = depth { d } else {
-                                               array_size.unwrap_or(1)
-                                           } as i32, 0,
-                                           client_format as u32, client_type, data_raw);
+                        for level in range(0, allocated_levels) {
+                            let (w, h, d) = level_dimensions(texture_type, level, width,
+                                                             height, depth, array_size);
+                            let data_raw = if level == 0 { data_raw } else { ptr::null() };
+                            ctxt.gl.TexImage3D(texture_type, level as i32, format as i32,
+                                               w as i32, h as i32, d as i32, 0,
+                                               client_format as u32, client_type, data_raw);
+                        }
                     }
 
                 } else if texture_type == gl::TEXTURE_2D || texture_type == gl::TEXTURE_1D_ARRAY {
-                    if ctxt.version >= &GlVersion(4, 2) || ctxt.extensions.gl_arb_texture_storage {
-                        ctxt.gl.TexStorage2D(texture_type, texture_levels,
+                    if texture_storage {
+                        ctxt.gl.TexStorage2D(texture_type, texture_levels as gl::types::GLsizei,
                                              format as gl::types::GLenum,
                                              width as gl::types::GLsizei,
                                              height.unwrap() as gl::types::GLsizei);
@@ -127,14 +231,19 @@ impl TextureImplementation {
                         }
 
                     } else {
-                        ctxt.gl.TexImage2D(texture_type, 0, format as i32, width as i32,
-                                           height.unwrap() as i32, 0, client_format as u32,
-                                           client_type, data_raw);   
+                        for level in range(0, allocated_levels) {
+                            let (w, h, _) = level_dimensions(texture_type, level, width,
+                                                             height, depth, array_size);
+                            let data_raw = if level == 0 { data_raw } else { ptr::null() };
+                            ctxt.gl.TexImage2D(texture_type, level as i32, format as i32,
+                                               w as i32, h as i32, 0, client_format as u32,
+                                               client_type, data_raw);
+                        }
                     }
 
                 } else {
-                    if ctxt.version >= &GlVersion(4, 2) || ctxt.extensions.gl_arb_texture_storage {
-                        ctxt.gl.TexStorage1D(texture_type, texture_levels,
+                    if texture_storage {
+                        ctxt.gl.TexStorage1D(texture_type, texture_levels as gl::types::GLsizei,
                                              format as gl::types::GLenum,
                                              width as gl::types::GLsizei);
 
@@ -144,15 +253,49 @@ impl TextureImplementation {
                         }
 
                     } else {
-                        ctxt.gl.TexImage1D(texture_type, 0, format as i32, width as i32, 0,
-                                           client_format as u32, client_type, data_raw);
+                        for level in range(0, allocated_levels) {
+                            let (w, _, _) = level_dimensions(texture_type, level, width,
+                                                             height, depth, array_size);
+                            let data_raw = if level == 0 { data_raw } else { ptr::null() };
+                            ctxt.gl.TexImage1D(texture_type, level as i32, format as i32,
+                                               w as i32, 0, client_format as u32, client_type,
+                                               data_raw);
+                        }
+                    }
+                }
+
+                // the content of the levels is undefined if no data is passed; when mipmaps
+                // are generated, the other levels are computed from the first one
+                if zero_initialize && !is_compressed(&ctxt, texture_type) {
+                    for level in range(0, allocated_levels) {
+                        let dimensions = level_dimensions(texture_type, level, width, height,
+                                                          depth, array_size);
+                        zero_level(&ctxt, id, texture_type, level, dimensions,
+                                   client_format, client_type);
+                    }
+                }
+
+                if generate_mipmaps {
+                    if ctxt.version >= &GlVersion(3, 0) {
+                        ctxt.gl.GenerateMipmap(texture_type);
+                    } else {
+                        ctxt.gl.GenerateMipmapEXT(texture_type);
                     }
                 }
 
-                if ctxt.version >= &GlVersion(3, 0) {
-                    ctxt.gl.GenerateMipmap(texture_type);
-                } else {
-                    ctxt.gl.GenerateMipmapEXT(texture_type);
+                if background && texture_levels >= 2 {
+                    let task = box GenerateMipmaps {
+                        id: id,
+                        bind_point: texture_type,
+                        levels: texture_levels,
+                        next_level: 1,
+                    } as Box<context::BackgroundTask + Send>;
+
+                    ctxt.state.background_tasks.push_back(task);
+                }
+
+                if let Some(ref data) = data {
+                    ctxt.state.statistics.bytes_transferred += data.len() * mem::size_of::<P>();
                 }
 
                 tx.send(id);
@@ -167,7 +310,1042 @@ impl TextureImplementation {
             height: height,
             depth: depth,
             array_size: array_size,
+            levels: texture_levels,
+            generated_mipmaps: generate_mipmaps || background,
+            owned: true,
+        }
+    }
+
+    /// Wraps a two-dimensional texture whose storage is defined outside of glium, for example
+    /// by an interoperability extension.
+    ///
+    /// Only the main level of the texture is used. The minifying filter of the texture is set
+    /// to `GL_LINEAR` and its maximum level to 0, so that the texture is complete even if the
+    /// other levels don't exist.
+    ///
+    /// If `owned` is true, the texture is deleted when the object is destroyed.
+    pub fn from_id_2d(display: &Display, id: gl::types::GLuint, width: u32, height: u32,
+                      owned: bool) -> TextureImplementation
+    {
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                context::bind_texture(&mut ctxt, gl::TEXTURE_2D, id);
+                ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+
+                // `GL_TEXTURE_MAX_LEVEL` doesn't exist in OpenGL ES 2.0
+                if !ctxt.opengl_es || ctxt.version >= &GlVersion(3, 0) {
+                    ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, 0);
+                }
+            }
+        });
+
+        TextureImplementation {
+            display: display.clone(),
+            id: id,
+            bind_point: gl::TEXTURE_2D,
+            width: width,
+            height: Some(height),
+            depth: None,
+            array_size: None,
+            levels: 1,
+            generated_mipmaps: false,
+            owned: owned,
+        }
+    }
+
+    /// Builds a new cubemap texture with an undefined content.
+    ///
+    /// The levels other than the main one are allocated according to `mipmaps`. Mipmaps that
+    /// are generated are only computed once `update_mipmaps` is called, since the faces don't
+    /// have any content yet.
+    pub fn new_cubemap(display: &Display, format: gl::types::GLenum,
+                       client_format: gl::types::GLenum, client_type: gl::types::GLenum,
+                       dimension: u32, mipmaps: MipmapsOption) -> TextureImplementation
+    {
+        use std::num::Float;
+
+        let zero_initialize = display.context.zero_initialization.load(Relaxed);
+
+        let es2 = {
+            let capabilities = display.context.context.capabilities();
+            capabilities.opengl_es && capabilities.version < GlVersion(3, 0)
+        };
+
+        let format = get_internal_format(es2, format, client_format);
+        let client_type = get_client_type(es2, client_type);
+
+        let max_levels = 1 + (dimension as f32).log2() as u32;
+
+        let (texture_levels, generate_mipmaps, background) = match mipmaps {
+            MipmapsOption::NoMipmap => (1, false, false),
+            MipmapsOption::AutoGeneratedMipmaps => (max_levels, true, false),
+            MipmapsOption::BackgroundGeneratedMipmaps if es2 => (max_levels, true, false),
+            MipmapsOption::BackgroundGeneratedMipmaps => (max_levels, false, true),
+            MipmapsOption::EmptyMipmaps(levels) => {
+                if levels == 0 || levels > max_levels {
+                    panic!("The number of mipmap levels must be between 1 and {} for these \
+                            dimensions", max_levels);
+                }
+
+                (levels, false, false)
+            },
+        };
+
+        // the levels that are allocated by `glTexImage2D` instead of `glTexStorage2D`
+        let allocated_levels = if generate_mipmaps || background { 1 } else { texture_levels };
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                let id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, mem::transmute(&id));
+
+                context::bind_texture(&mut ctxt, gl::TEXTURE_CUBE_MAP, id);
+
+                ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S,
+                                      gl::CLAMP_TO_EDGE as i32);
+                ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T,
+                                      gl::CLAMP_TO_EDGE as i32);
+                ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER,
+                                      gl::LINEAR as i32);
+                let min_filter = if texture_levels >= 2 {
+                    gl::LINEAR_MIPMAP_LINEAR
+                } else {
+                    gl::LINEAR
+                };
+                ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER,
+                                      min_filter as i32);
+
+                // `GL_TEXTURE_WRAP_R` and `GL_TEXTURE_MAX_LEVEL` don't exist in OpenGL ES 2.0
+                if !es2 {
+                    let max_level = if background { 0 } else { texture_levels - 1 };
+                    ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R,
+                                          gl::CLAMP_TO_EDGE as i32);
+                    ctxt.gl.TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAX_LEVEL,
+                                          max_level as i32);
+                }
+
+                let texture_storage = if ctxt.opengl_es {
+                    ctxt.version >= &GlVersion(3, 0)
+                } else {
+                    ctxt.version >= &GlVersion(4, 2) || ctxt.extensions.gl_arb_texture_storage
+                };
+
+                if texture_storage {
+                    ctxt.gl.TexStorage2D(gl::TEXTURE_CUBE_MAP,
+                                         texture_levels as gl::types::GLsizei, format,
+                                         dimension as gl::types::GLsizei,
+                                         dimension as gl::types::GLsizei);
+
+                } else {
+                    for level in range(0, allocated_levels) {
+                        let level_dimension = ::std::cmp::max(1, dimension >> level as uint);
+                        for face in range(0, 6) {
+                            ctxt.gl.TexImage2D(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face,
+                                               level as i32, format as i32,
+                                               level_dimension as i32, level_dimension as i32,
+                                               0, client_format, client_type, ptr::null());
+                        }
+                    }
+                }
+
+                if zero_initialize {
+                    // `glClearTexImage` fills all the faces at once
+                    let faces = if ctxt.capabilities.clear_texture { 1 } else { 6 };
+                    for level in range(0, allocated_levels) {
+                        let level_dimension = ::std::cmp::max(1, dimension >> level as uint);
+                        for face in range(0, faces) {
+                            zero_level(&ctxt, id, gl::TEXTURE_CUBE_MAP_POSITIVE_X + face, level,
+                                       (level_dimension, level_dimension, 1), client_format,
+                                       client_type);
+                        }
+                    }
+                }
+
+                // the faces are uploaded right after the creation, before the background
+                // tasks are processed
+                if background && texture_levels >= 2 {
+                    let task = box GenerateMipmaps {
+                        id: id,
+                        bind_point: gl::TEXTURE_CUBE_MAP,
+                        levels: texture_levels,
+                        next_level: 1,
+                    } as Box<context::BackgroundTask + Send>;
+
+                    ctxt.state.background_tasks.push_back(task);
+                }
+
+                tx.send(id);
+            }
+        });
+
+        TextureImplementation {
+            display: display.clone(),
+            id: rx.recv(),
+            bind_point: gl::TEXTURE_CUBE_MAP,
+            width: dimension,
+            height: Some(dimension),
+            depth: None,
+            array_size: None,
+            levels: texture_levels,
+            generated_mipmaps: generate_mipmaps || background,
+            owned: true,
+        }
+    }
+
+    /// Builds a new two-dimensional multisample texture array with an undefined content.
+    ///
+    /// The capabilities and the number of samples must have already been checked.
+    pub fn new_multisample_array(display: &Display, format: gl::types::GLenum, width: u32,
+                                 height: u32, array_size: u32, samples: u32)
+                                 -> TextureImplementation
+    {
+        let zero_initialize = display.context.zero_initialization.load(Relaxed);
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, mem::transmute(&id));
+
+                context::bind_texture(&mut ctxt, gl::TEXTURE_2D_MULTISAMPLE_ARRAY, id);
+
+                if ctxt.opengl_es || ctxt.version >= &GlVersion(4, 3) ||
+                   ctxt.extensions.gl_arb_texture_storage_multisample
+                {
+                    ctxt.gl.TexStorage3DMultisample(gl::TEXTURE_2D_MULTISAMPLE_ARRAY,
+                                                    samples as gl::types::GLsizei, format,
+                                                    width as gl::types::GLsizei,
+                                                    height as gl::types::GLsizei,
+                                                    array_size as gl::types::GLsizei,
+                                                    gl::TRUE);
+
+                } else {
+                    ctxt.gl.TexImage3DMultisample(gl::TEXTURE_2D_MULTISAMPLE_ARRAY,
+                                                  samples as gl::types::GLsizei, format,
+                                                  width as gl::types::GLsizei,
+                                                  height as gl::types::GLsizei,
+                                                  array_size as gl::types::GLsizei, gl::TRUE);
+                }
+
+                // multisample textures can't be filled with `glTexSubImage`
+                if zero_initialize && ctxt.capabilities.clear_texture {
+                    ctxt.gl.ClearTexImage(id, 0, gl::RGBA, gl::UNSIGNED_BYTE, ptr::null());
+                }
+
+                tx.send(id);
+            }
+        });
+
+        TextureImplementation {
+            display: display.clone(),
+            id: rx.recv(),
+            bind_point: gl::TEXTURE_2D_MULTISAMPLE_ARRAY,
+            width: width,
+            height: Some(height),
+            depth: None,
+            array_size: Some(array_size),
+            levels: 1,
+            generated_mipmaps: false,
+            owned: true,
+        }
+    }
+
+    /// Builds a new two-dimensional texture from data that is already compressed.
+    ///
+    /// Each element of `levels` is the content of a mipmap level, starting with the largest one.
+    pub fn new_compressed_2d(display: &Display, format: gl::types::GLenum, width: u32,
+                             height: u32, levels: Vec<Vec<u8>>) -> TextureImplementation
+    {
+        let levels_count = levels.len() as u32;
+        let max_levels = 1 + (::std::cmp::max(width, height) as f32).log2() as u32;
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let levels = levels;
+                let es2 = ctxt.opengl_es && ctxt.version < &GlVersion(3, 0);
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                let id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, mem::transmute(&id));
+
+                context::bind_texture(&mut ctxt, gl::TEXTURE_2D, id);
+
+                ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+                ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+                ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                if levels_count >= 2 && (!es2 || levels_count == max_levels) {
+                    ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER,
+                                          gl::LINEAR_MIPMAP_LINEAR as i32);
+                } else {
+                    ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER,
+                                          gl::LINEAR as i32);
+                }
+
+                // the texture would be incomplete if the mipmaps that we don't provide
+                // were taken into account
+                // `GL_TEXTURE_MAX_LEVEL` doesn't exist in OpenGL ES 2.0, so the mipmaps are
+                // only sampled there if all of them are provided
+                if !es2 {
+                    ctxt.gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL,
+                                          (levels_count - 1) as i32);
+                }
+
+                let (mut level_width, mut level_height) = (width, height);
+                for (level, data) in levels.iter().enumerate() {
+                    ctxt.gl.CompressedTexImage2D(gl::TEXTURE_2D, level as gl::types::GLint,
+                                                 format, level_width as gl::types::GLsizei,
+                                                 level_height as gl::types::GLsizei, 0,
+                                                 data.len() as gl::types::GLsizei,
+                                                 data.as_ptr() as *const libc::c_void);
+
+                    level_width = ::std::cmp::max(1, level_width / 2);
+                    level_height = ::std::cmp::max(1, level_height / 2);
+                }
+
+                tx.send(id);
+            }
+        });
+
+        TextureImplementation {
+            display: display.clone(),
+            id: rx.recv(),
+            bind_point: gl::TEXTURE_2D,
+            width: width,
+            height: Some(height),
+            depth: None,
+            array_size: None,
+            levels: levels_count,
+            generated_mipmaps: false,
+            owned: true,
+        }
+    }
+
+    /// Builds a new two-dimensional texture array or cubemap from data that is already
+    /// compressed.
+    ///
+    /// `bind_point` must be `GL_TEXTURE_2D_ARRAY` or `GL_TEXTURE_CUBE_MAP`. `layers` contains
+    /// the levels of each layer, or of each face in the order of the `GL_TEXTURE_CUBE_MAP_*`
+    /// values. All the layers must have the same number of levels, and the sizes of the
+    /// levels must have already been checked.
+    pub fn new_compressed_layered(display: &Display, bind_point: gl::types::GLenum,
+                                  format: gl::types::GLenum, width: u32, height: u32,
+                                  layers: Vec<Vec<Vec<u8>>>) -> TextureImplementation
+    {
+        let array_size = layers.len() as u32;
+        let levels_count = layers[0].len() as u32;
+        let max_levels = 1 + (::std::cmp::max(width, height) as f32).log2() as u32;
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let layers = layers;
+                let es2 = ctxt.opengl_es && ctxt.version < &GlVersion(3, 0);
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                let id: gl::types::GLuint = mem::uninitialized();
+                ctxt.gl.GenTextures(1, mem::transmute(&id));
+
+                context::bind_texture(&mut ctxt, bind_point, id);
+
+                let wrap = if bind_point == gl::TEXTURE_CUBE_MAP {
+                    gl::CLAMP_TO_EDGE
+                } else {
+                    gl::REPEAT
+                };
+
+                ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_WRAP_S, wrap as i32);
+                ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_WRAP_T, wrap as i32);
+                ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                if levels_count >= 2 && (!es2 || levels_count == max_levels) {
+                    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MIN_FILTER,
+                                          gl::LINEAR_MIPMAP_LINEAR as i32);
+                } else {
+                    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                }
+
+                // the texture would be incomplete if the mipmaps that we don't provide
+                // were taken into account
+                // `GL_TEXTURE_MAX_LEVEL` doesn't exist in OpenGL ES 2.0, so the mipmaps are
+                // only sampled there if all of them are provided
+                if !es2 {
+                    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MAX_LEVEL,
+                                          (levels_count - 1) as i32);
+                }
+
+                let (mut level_width, mut level_height) = (width, height);
+                for level in range(0, levels_count as uint) {
+                    if bind_point == gl::TEXTURE_CUBE_MAP {
+                        for (face, levels) in layers.iter().enumerate() {
+                            let data = &levels[level];
+                            ctxt.gl.CompressedTexImage2D(gl::TEXTURE_CUBE_MAP_POSITIVE_X +
+                                                         face as gl::types::GLenum,
+                                                         level as gl::types::GLint, format,
+                                                         level_width as gl::types::GLsizei,
+                                                         level_height as gl::types::GLsizei, 0,
+                                                         data.len() as gl::types::GLsizei,
+                                                         data.as_ptr() as *const libc::c_void);
+                        }
+
+                    } else {
+                        // allocating the level for all the layers, then uploading each layer
+                        let layer_size = layers[0][level].len();
+                        ctxt.gl.CompressedTexImage3D(bind_point, level as gl::types::GLint,
+                                                     format, level_width as gl::types::GLsizei,
+                                                     level_height as gl::types::GLsizei,
+                                                     array_size as gl::types::GLsizei, 0,
+                                                     (layer_size * array_size as uint)
+                                                        as gl::types::GLsizei,
+                                                     ptr::null());
+
+                        for (layer, levels) in layers.iter().enumerate() {
+                            let data = &levels[level];
+                            ctxt.gl.CompressedTexSubImage3D(bind_point,
+                                                            level as gl::types::GLint, 0, 0,
+                                                            layer as gl::types::GLint,
+                                                            level_width as gl::types::GLsizei,
+                                                            level_height as gl::types::GLsizei,
+                                                            1, format,
+                                                            data.len() as gl::types::GLsizei,
+                                                            data.as_ptr() as *const libc::c_void);
+                        }
+                    }
+
+                    level_width = ::std::cmp::max(1, level_width / 2);
+                    level_height = ::std::cmp::max(1, level_height / 2);
+                }
+
+                tx.send(id);
+            }
+        });
+
+        TextureImplementation {
+            display: display.clone(),
+            id: rx.recv(),
+            bind_point: bind_point,
+            width: width,
+            height: Some(height),
+            depth: None,
+            array_size: if bind_point == gl::TEXTURE_CUBE_MAP { None } else { Some(array_size) },
+            levels: levels_count,
+            generated_mipmaps: false,
+            owned: true,
+        }
+    }
+
+    /// Replaces the content of a mipmap level of one layer of a compressed texture array, or
+    /// of one face of a compressed cubemap.
+    ///
+    /// `layer` is the index of the face in the order of the `GL_TEXTURE_CUBE_MAP_*` values for
+    /// cubemaps. `format` must be the format that was used to create the texture.
+    pub fn upload_compressed_layer(&self, level: u32, layer: u32, format: CompressedFormat,
+                                   data: Vec<u8>)
+    {
+        if level >= self.levels {
+            panic!("The texture doesn't have a mipmap level {}", level);
+        }
+
+        let layers = if self.bind_point == gl::TEXTURE_CUBE_MAP {
+            6
+        } else {
+            self.array_size.unwrap_or(1)
+        };
+
+        if layer >= layers {
+            panic!("The layer {} is outside of the texture", layer);
+        }
+
+        let (level_width, level_height, _) = level_dimensions(self.bind_point, level,
+                                                              self.width, self.height,
+                                                              self.depth, self.array_size);
+
+        if data.len() != format.get_data_size(level_width, level_height) {
+            panic!("Compressed texture data size mismatch");
+        }
+
+        let id = self.id;
+        let bind_point = self.bind_point;
+        let format = format.to_glenum();
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let data = data;
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                context::bind_texture(&mut ctxt, bind_point, id);
+
+                if bind_point == gl::TEXTURE_CUBE_MAP {
+                    ctxt.gl.CompressedTexSubImage2D(gl::TEXTURE_CUBE_MAP_POSITIVE_X + layer,
+                                                    level as gl::types::GLint, 0, 0,
+                                                    level_width as gl::types::GLsizei,
+                                                    level_height as gl::types::GLsizei, format,
+                                                    data.len() as gl::types::GLsizei,
+                                                    data.as_ptr() as *const libc::c_void);
+                } else {
+                    ctxt.gl.CompressedTexSubImage3D(bind_point, level as gl::types::GLint, 0, 0,
+                                                    layer as gl::types::GLint,
+                                                    level_width as gl::types::GLsizei,
+                                                    level_height as gl::types::GLsizei, 1,
+                                                    format, data.len() as gl::types::GLsizei,
+                                                    data.as_ptr() as *const libc::c_void);
+                }
+
+                ctxt.state.statistics.bytes_transferred += data.len();
+            }
+        });
+    }
+
+    /// Uploads data to a two-dimensional part of the texture.
+    ///
+    /// `target` is the target to pass to `glTexSubImage2D`, for example `GL_TEXTURE_2D` or
+    /// one of the `GL_TEXTURE_CUBE_MAP_*` values.
+    pub fn upload_2d<P>(&self, target: gl::types::GLenum, level: u32, x: u32, y: u32,
+                        width: u32, height: u32, client_format: gl::types::GLenum,
+                        client_type: gl::types::GLenum, data: Vec<P>) where P: Send
+    {
+        let id = self.id;
+        let bind_point = self.bind_point;
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let data = data;
+                let es2 = ctxt.opengl_es && ctxt.version < &GlVersion(3, 0);
+                let client_type = get_client_type(es2, client_type);
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if ctxt.capabilities.direct_state_access && bind_point == gl::TEXTURE_CUBE_MAP {
+                    // the faces of a cubemap are accessed as layers with DSA
+                    let face = target - gl::TEXTURE_CUBE_MAP_POSITIVE_X;
+                    ctxt.gl.TextureSubImage3D(id, level as gl::types::GLint,
+                                              x as gl::types::GLint, y as gl::types::GLint,
+                                              face as gl::types::GLint,
+                                              width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei, 1,
+                                              client_format, client_type,
+                                              data.as_ptr() as *const libc::c_void);
+
+                } else if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.TextureSubImage2D(id, level as gl::types::GLint,
+                                              x as gl::types::GLint, y as gl::types::GLint,
+                                              width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei,
+                                              client_format, client_type,
+                                              data.as_ptr() as *const libc::c_void);
+
+                } else {
+                    context::bind_texture(&mut ctxt, bind_point, id);
+                    ctxt.gl.TexSubImage2D(target, level as gl::types::GLint,
+                                          x as gl::types::GLint, y as gl::types::GLint,
+                                          width as gl::types::GLsizei,
+                                          height as gl::types::GLsizei,
+                                          client_format, client_type,
+                                          data.as_ptr() as *const libc::c_void);
+                }
+
+                ctxt.state.statistics.bytes_transferred += data.len() * mem::size_of::<P>();
+            }
+        });
+    }
+
+    /// Uploads data to a part of a mipmap level of the texture.
+    ///
+    /// The coordinates and dimensions that don't apply to the texture's type are ignored.
+    pub fn upload<P>(&self, level: u32, x: u32, y: u32, z: u32, width: u32, height: u32,
+                     depth: u32, client_format: gl::types::GLenum,
+                     client_type: gl::types::GLenum, data: Vec<P>) where P: Send
+    {
+        if level >= self.levels {
+            panic!("The texture doesn't have a mipmap level {}", level);
+        }
+
+        let (level_width, level_height, level_depth) = level_dimensions(self.bind_point, level,
+                                                                        self.width, self.height,
+                                                                        self.depth,
+                                                                        self.array_size);
+
+        let fits = |&: offset: u32, size: u32, max: u32| {
+            offset.checked_add(size).map(|end| end <= max).unwrap_or(false)
+        };
+
+        if !fits(x, width, level_width) || !fits(y, height, level_height) ||
+           !fits(z, depth, level_depth)
+        {
+            panic!("The area to write is outside of the texture");
+        }
+
+        let pixels = width as uint * height as uint * depth as uint;
+        if pixels != data.len() && pixels * 2 != data.len() && pixels * 3 != data.len() &&
+           pixels * 4 != data.len()
+        {
+            panic!("Texture data size mismatch");
+        }
+
+        if pixels == 0 {
+            return;
+        }
+
+        let id = self.id;
+        let bind_point = self.bind_point;
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let data = data;
+                let data_raw = data.as_ptr() as *const libc::c_void;
+                let es2 = ctxt.opengl_es && ctxt.version < &GlVersion(3, 0);
+                let client_type = get_client_type(es2, client_type);
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if ctxt.capabilities.direct_state_access {
+                    match bind_point {
+                        gl::TEXTURE_1D => {
+                            ctxt.gl.TextureSubImage1D(id, level as gl::types::GLint,
+                                                      x as gl::types::GLint,
+                                                      width as gl::types::GLsizei,
+                                                      client_format, client_type, data_raw);
+                        },
+                        gl::TEXTURE_3D | gl::TEXTURE_2D_ARRAY => {
+                            ctxt.gl.TextureSubImage3D(id, level as gl::types::GLint,
+                                                      x as gl::types::GLint,
+                                                      y as gl::types::GLint,
+                                                      z as gl::types::GLint,
+                                                      width as gl::types::GLsizei,
+                                                      height as gl::types::GLsizei,
+                                                      depth as gl::types::GLsizei,
+                                                      client_format, client_type, data_raw);
+                        },
+                        _ => {
+                            ctxt.gl.TextureSubImage2D(id, level as gl::types::GLint,
+                                                      x as gl::types::GLint,
+                                                      y as gl::types::GLint,
+                                                      width as gl::types::GLsizei,
+                                                      height as gl::types::GLsizei,
+                                                      client_format, client_type, data_raw);
+                        },
+                    }
+
+                    ctxt.state.statistics.bytes_transferred +=
+                        data.len() * mem::size_of::<P>();
+                    return;
+                }
+
+                context::bind_texture(&mut ctxt, bind_point, id);
+
+                match bind_point {
+                    gl::TEXTURE_1D => {
+                        ctxt.gl.TexSubImage1D(bind_point, level as gl::types::GLint,
+                                              x as gl::types::GLint,
+                                              width as gl::types::GLsizei, client_format,
+                                              client_type, data_raw);
+                    },
+                    gl::TEXTURE_3D | gl::TEXTURE_2D_ARRAY => {
+                        ctxt.gl.TexSubImage3D(bind_point, level as gl::types::GLint,
+                                              x as gl::types::GLint,
+                                              y as gl::types::GLint, z as gl::types::GLint,
+                                              width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei,
+                                              depth as gl::types::GLsizei, client_format,
+                                              client_type, data_raw);
+                    },
+                    _ => {
+                        ctxt.gl.TexSubImage2D(bind_point, level as gl::types::GLint,
+                                              x as gl::types::GLint,
+                                              y as gl::types::GLint, width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei, client_format,
+                                              client_type, data_raw);
+                    },
+                }
+
+                ctxt.state.statistics.bytes_transferred += data.len() * mem::size_of::<P>();
+            }
+        });
+
+        self.update_mipmaps(level);
+    }
+
+    /// Regenerates the other mipmap levels after `level` has been modified, if `level` is the
+    /// main level and the other levels are generated from it.
+    ///
+    /// Levels that have been allocated with `MipmapsOption::EmptyMipmaps` are left untouched.
+    pub fn update_mipmaps(&self, level: u32) {
+        if level != 0 || !self.generated_mipmaps || self.levels < 2 {
+            return;
+        }
+
+        let id = self.id;
+        let bind_point = self.bind_point;
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                context::bind_texture(&mut ctxt, bind_point, id);
+
+                if ctxt.version >= &GlVersion(3, 0) {
+                    ctxt.gl.GenerateMipmap(bind_point);
+                } else {
+                    ctxt.gl.GenerateMipmapEXT(bind_point);
+                }
+            }
+        });
+    }
+
+    /// Uploads data to a part of a mipmap level of a two-dimensional texture, with the given
+    /// layout of the data in memory.
+    ///
+    /// The area and the length of the data must have already been checked.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the row length or the origin are used and the backend doesn't support them.
+    pub fn upload_2d_with_parameters<P>(&self, level: u32, x: u32, y: u32, width: u32,
+                                        height: u32, client_format: gl::types::GLenum,
+                                        client_type: gl::types::GLenum, data: Vec<P>,
+                                        parameters: UploadParameters) where P: Send
+    {
+        let unpack_subimage = self.display.context.context.capabilities().unpack_subimage;
+
+        if !unpack_subimage && (parameters.row_length.is_some() || parameters.skip_pixels != 0 ||
+                                parameters.skip_rows != 0)
+        {
+            panic!("The row length and the origin of the data are not supported by the \
+                    backend");
+        }
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        if parameters.flip_vertically {
+            // OpenGL reads the rows from the bottom, so the rows are reversed on the CPU and
+            // uploaded at once
+            let texel_size = client_texel_size(client_format, client_type);
+            let flipped = flip_rows(data.as_slice(), width, height, texel_size, &parameters);
+            let parameters = UploadParameters {
+                skip_rows: 0,
+                flip_vertically: false,
+                .. parameters
+            };
+
+            return self.upload_2d_with_parameters(level, x, y, width, height, client_format,
+                                                  client_type, flipped, parameters);
+        }
+
+        let id = self.id;
+        let bind_point = self.bind_point;
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let data = data;
+                let data_raw = data.as_ptr() as *const libc::c_void;
+
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT,
+                                    parameters.alignment as gl::types::GLint);
+
+                if unpack_subimage {
+                    ctxt.gl.PixelStorei(gl::UNPACK_ROW_LENGTH,
+                                        parameters.row_length.unwrap_or(0) as gl::types::GLint);
+                    ctxt.gl.PixelStorei(gl::UNPACK_SKIP_PIXELS,
+                                        parameters.skip_pixels as gl::types::GLint);
+                    ctxt.gl.PixelStorei(gl::UNPACK_SKIP_ROWS,
+                                        parameters.skip_rows as gl::types::GLint);
+                }
+
+                if ctxt.state.pixel_unpack_buffer_binding != 0 {
+                    ctxt.state.pixel_unpack_buffer_binding = 0;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.TextureSubImage2D(id, level as gl::types::GLint,
+                                              x as gl::types::GLint, y as gl::types::GLint,
+                                              width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei,
+                                              client_format, client_type, data_raw);
+                } else {
+                    context::bind_texture(&mut ctxt, bind_point, id);
+                    ctxt.gl.TexSubImage2D(bind_point, level as gl::types::GLint,
+                                          x as gl::types::GLint, y as gl::types::GLint,
+                                          width as gl::types::GLsizei,
+                                          height as gl::types::GLsizei,
+                                          client_format, client_type, data_raw);
+                }
+
+                // the other uploads expect tightly packed data
+                if unpack_subimage {
+                    ctxt.gl.PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+                    ctxt.gl.PixelStorei(gl::UNPACK_SKIP_PIXELS, 0);
+                    ctxt.gl.PixelStorei(gl::UNPACK_SKIP_ROWS, 0);
+                }
+
+                ctxt.state.statistics.bytes_transferred += data.len() * mem::size_of::<P>();
+            }
+        });
+
+        self.update_mipmaps(level);
+    }
+
+    /// Uploads data to a part of the main level of a two-dimensional texture by going through
+    /// a pixel unpack buffer.
+    ///
+    /// The data is first copied into the buffer, then the GPU copies the buffer into the
+    /// texture asynchronously, which avoids a stall if the texture is still being used.
+    pub fn upload_2d_async<P>(&self, x: u32, y: u32, width: u32, height: u32,
+                              client_format: gl::types::GLenum, client_type: gl::types::GLenum,
+                              data: Vec<P>) where P: Send + Copy
+    {
+        let rect = ::Rect { left: x, bottom: y, width: width, height: height };
+        if !rect.is_inside((self.width, self.height.unwrap_or(1))) {
+            panic!("The area to write is outside of the texture");
+        }
+
+        let pixels = width as uint * height as uint;
+        if pixels != data.len() && pixels * 2 != data.len() && pixels * 3 != data.len() &&
+           pixels * 4 != data.len()
+        {
+            panic!("Texture data size mismatch");
+        }
+
+        if pixels == 0 {
+            return;
+        }
+
+        // the buffer is destroyed at the end of this function, but OpenGL keeps it alive
+        // until the copy is finished
+        let buffer = Buffer::new::<buffer::PixelUnpackBuffer, P>(&self.display, data,
+                                                                 gl::STREAM_DRAW);
+        self.upload_2d_from_buffer(x, y, width, height, client_format, client_type, &buffer);
+    }
+
+    /// Copies the beginning of a pixel unpack buffer to a part of the main level of a
+    /// two-dimensional texture. The copy is executed asynchronously by the GPU.
+    ///
+    /// The rectangle and the size of the buffer must have already been checked.
+    pub fn upload_2d_from_buffer(&self, x: u32, y: u32, width: u32, height: u32,
+                                 client_format: gl::types::GLenum,
+                                 client_type: gl::types::GLenum, buffer: &Buffer)
+    {
+        let buffer_id = buffer.get_id();
+
+        let id = self.id;
+        let bind_point = self.bind_point;
+
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                ctxt.gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+
+                if ctxt.state.pixel_unpack_buffer_binding != buffer_id {
+                    ctxt.state.pixel_unpack_buffer_binding = buffer_id;
+                    ctxt.gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, buffer_id);
+                    ctxt.state.statistics.state_changes += 1;
+                }
+
+                if ctxt.capabilities.direct_state_access {
+                    ctxt.gl.TextureSubImage2D(id, 0, x as gl::types::GLint,
+                                              y as gl::types::GLint,
+                                              width as gl::types::GLsizei,
+                                              height as gl::types::GLsizei,
+                                              client_format, client_type, ptr::null());
+                } else {
+                    context::bind_texture(&mut ctxt, bind_point, id);
+                    ctxt.gl.TexSubImage2D(bind_point, 0, x as gl::types::GLint,
+                                          y as gl::types::GLint, width as gl::types::GLsizei,
+                                          height as gl::types::GLsizei, client_format,
+                                          client_type, ptr::null());
+                }
+            }
+        });
+
+        self.update_mipmaps(0);
+    }
+
+    /// Copies a rectangle of the main level of the texture to the main level of another
+    /// texture with `glCopyImageSubData`, without going through the CPU.
+    ///
+    /// Returns `false` without doing anything if the backend doesn't support it, or if the
+    /// formats of the two textures are not in the same copy class. The rectangles must have
+    /// already been checked.
+    pub fn copy_image_2d(&self, target: &TextureImplementation, x: u32, y: u32, width: u32,
+                         height: u32, target_x: u32, target_y: u32) -> bool
+    {
+        if !self.display.context.context.capabilities().copy_image {
+            return false;
+        }
+
+        let (source_id, source_bind_point) = (self.id, self.bind_point);
+        let (target_id, target_bind_point) = (target.id, target.bind_point);
+
+        let (tx, rx) = channel();
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                let mut source_format = mem::uninitialized();
+                context::bind_texture(&mut ctxt, source_bind_point, source_id);
+                ctxt.gl.GetTexLevelParameteriv(source_bind_point, 0,
+                                               gl::TEXTURE_INTERNAL_FORMAT, &mut source_format);
+
+                let mut target_format = mem::uninitialized();
+                context::bind_texture(&mut ctxt, target_bind_point, target_id);
+                ctxt.gl.GetTexLevelParameteriv(target_bind_point, 0,
+                                               gl::TEXTURE_INTERNAL_FORMAT, &mut target_format);
+
+                // two different formats can be copied if they have the same texel size
+                if source_format != target_format {
+                    match (copy_class_size(source_format as gl::types::GLenum),
+                           copy_class_size(target_format as gl::types::GLenum))
+                    {
+                        (Some(a), Some(b)) if a == b => (),
+                        _ => {
+                            tx.send(false);
+                            return;
+                        }
+                    }
+                }
+
+                ctxt.gl.CopyImageSubData(source_id, source_bind_point, 0,
+                                         x as gl::types::GLint, y as gl::types::GLint, 0,
+                                         target_id, target_bind_point, 0,
+                                         target_x as gl::types::GLint,
+                                         target_y as gl::types::GLint, 0,
+                                         width as gl::types::GLsizei,
+                                         height as gl::types::GLsizei, 1);
+                tx.send(true);
+            }
+        });
+
+        rx.recv()
+    }
+
+    /// Builds a texture that shares the storage of this one with `glTextureView`.
+    ///
+    /// `format` is the internal format of the view, or `None` to keep the format of this
+    /// texture. The view contains `levels` levels starting at `first_level`, and `layers`
+    /// layers starting at `first_layer`. The ranges must have already been checked.
+    ///
+    /// Returns `None` if the backend doesn't support views, if the storage of this texture is
+    /// not immutable, or if `format` is not compatible with the format of this texture.
+    pub fn new_view(&self, bind_point: gl::types::GLenum, format: Option<gl::types::GLenum>,
+                    first_level: u32, levels: u32, first_layer: u32, layers: u32)
+                    -> Option<TextureImplementation>
+    {
+        if !self.display.context.context.capabilities().texture_view {
+            return None;
         }
+
+        let (source_id, source_bind_point) = (self.id, self.bind_point);
+
+        let (tx, rx) = channel();
+        self.display.context.context.exec(move |: mut ctxt| {
+            unsafe {
+                context::bind_texture(&mut ctxt, source_bind_point, source_id);
+
+                // textures allocated with `glTexImage*` can't have views
+                let mut immutable = mem::uninitialized();
+                ctxt.gl.GetTexParameteriv(source_bind_point, gl::TEXTURE_IMMUTABLE_FORMAT,
+                                          &mut immutable);
+                if immutable == 0 {
+                    tx.send(None);
+                    return;
+                }
+
+                let mut source_format = mem::uninitialized();
+                ctxt.gl.GetTexLevelParameteriv(source_bind_point, 0,
+                                               gl::TEXTURE_INTERNAL_FORMAT, &mut source_format);
+                let source_format = source_format as gl::types::GLenum;
+                let format = format.unwrap_or(source_format);
+
+                // two different formats are compatible if they are in the same view class
+                if format != source_format {
+                    let mut source_class = mem::uninitialized();
+                    ctxt.gl.GetInternalformativ(source_bind_point, source_format,
+                                                gl::VIEW_COMPATIBILITY_CLASS, 1,
+                                                &mut source_class);
+                    let mut class = mem::uninitialized();
+                    ctxt.gl.GetInternalformativ(bind_point, format,
+                                                gl::VIEW_COMPATIBILITY_CLASS, 1, &mut class);
+
+                    if class == gl::NONE as gl::types::GLint || class != source_class {
+                        tx.send(None);
+                        return;
+                    }
+                }
+
+                let mut id = mem::uninitialized();
+                ctxt.gl.GenTextures(1, &mut id);
+                ctxt.gl.TextureView(id, bind_point, source_id, format, first_level, levels,
+                                    first_layer, layers);
+                tx.send(Some(id));
+            }
+        });
+
+        let id = match rx.recv() {
+            Some(id) => id,
+            None => return None
+        };
+
+        let (width, height, depth) = level_dimensions(self.bind_point, first_level, self.width,
+                                                      self.height, self.depth,
+                                                      self.array_size);
+
+        let (height, depth, array_size) = match bind_point {
+            gl::TEXTURE_1D => (None, None, None),
+            gl::TEXTURE_1D_ARRAY => (None, None, Some(layers)),
+            gl::TEXTURE_2D => (Some(height), None, None),
+            gl::TEXTURE_2D_ARRAY => (Some(height), None, Some(layers)),
+            _ => (Some(height), Some(depth), None),
+        };
+
+        Some(TextureImplementation {
+            display: self.display.clone(),
+            id: id,
+            bind_point: bind_point,
+            width: width,
+            height: height,
+            depth: depth,
+            array_size: array_size,
+            levels: levels,
+            generated_mipmaps: false,
+            owned: true,
+        })
     }
 
     /// Reads the content of a mipmap level of the texture.
@@ -175,23 +1353,80 @@ impl TextureImplementation {
     //       width/height need adjustements
     #[cfg(feature = "gl_extensions")]
     pub fn read<P>(&self, level: u32) -> Vec<P> where P: PixelValue {
+        let (format, gltype) = PixelValue::get_format(None::<P>).to_gl_enum();
+        self.read_with_format(level, format, gltype)
+    }
+
+    /// Reads the content of a mipmap level of the texture with the given client format.
+    ///
+    /// The format must be one of the `_INTEGER` formats if the texture is integral, which
+    /// `read` doesn't handle.
+    #[cfg(feature = "gl_extensions")]
+    pub fn read_with_format<P>(&self, level: u32, format: gl::types::GLenum,
+                               gltype: gl::types::GLenum) -> Vec<P> where P: PixelValue
+    {
         assert_eq!(level, 0);   // TODO: 
 
         let pixels_count = (self.width * self.height.unwrap_or(1) * self.depth.unwrap_or(1))
                             as uint;
 
-        // FIXME: WRONG
-        let (format, gltype) = PixelValue::get_format(None::<P>).to_gl_enum();
         let my_id = self.id;
+        let width = self.width;
+        let height = self.height.unwrap_or(1);
 
         let (tx, rx) = channel();
-        self.display.context.context.exec(move |: ctxt| {
+        self.display.context.context.exec(move |: mut ctxt| {
             unsafe {
                 let mut data: Vec<P> = Vec::with_capacity(pixels_count);
 
                 ctxt.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
 
-                if ctxt.version >= &GlVersion(4, 5) {
+                if ctxt.opengl_es {
+                    // OpenGL ES can't read textures directly, so the texture is attached to a
+                    // temporary framebuffer
+                    let mut fbo = mem::uninitialized();
+                    ctxt.gl.GenFramebuffers(1, &mut fbo);
+                    ctxt.gl.BindFramebuffer(gl::FRAMEBUFFER, fbo);
+                    ctxt.gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0,
+                                                 gl::TEXTURE_2D, my_id,
+                                                 level as gl::types::GLint);
+
+                    // only `GL_RGBA`/`GL_UNSIGNED_BYTE` is guaranteed to be supported, so other
+                    // formats are converted afterwards ; integral textures can only be read
+                    // with an `_INTEGER` format
+                    let integral = match format {
+                        gl::RED_INTEGER | gl::RG_INTEGER | gl::RGB_INTEGER |
+                        gl::RGBA_INTEGER => true,
+                        _ => false
+                    };
+
+                    if integral || (format == gl::RGBA && gltype == gl::UNSIGNED_BYTE) {
+                        ctxt.gl.ReadPixels(0, 0, width as gl::types::GLsizei,
+                                           height as gl::types::GLsizei, format, gltype,
+                                           data.as_mut_ptr() as *mut libc::c_void);
+
+                    } else {
+                        let size = width as uint * height as uint * 4;
+                        let mut rgba: Vec<u8> = Vec::with_capacity(size);
+                        ctxt.gl.ReadPixels(0, 0, width as gl::types::GLsizei,
+                                           height as gl::types::GLsizei, gl::RGBA,
+                                           gl::UNSIGNED_BYTE,
+                                           rgba.as_mut_ptr() as *mut libc::c_void);
+                        rgba.set_len(size);
+
+                        let converted = convert_rgba8(rgba.as_slice(), format, gltype);
+                        assert_eq!(converted.len(), pixels_count * mem::size_of::<P>());
+                        ptr::copy_nonoverlapping_memory(data.as_mut_ptr() as *mut u8,
+                                                        converted.as_ptr(), converted.len());
+                    }
+                    ctxt.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+                    ctxt.gl.DeleteFramebuffers(1, &fbo);
+
+                    ctxt.state.draw_framebuffer = 0;
+                    ctxt.state.read_framebuffer = 0;
+                    ctxt.state.statistics.state_changes += 1;
+
+                } else if ctxt.capabilities.direct_state_access {
                     ctxt.gl.GetTextureImage(my_id, level as gl::types::GLint, format, gltype,
                         (pixels_count * mem::size_of::<P>()) as gl::types::GLsizei,
                         data.as_mut_ptr() as *mut libc::c_void);
@@ -201,12 +1436,13 @@ impl TextureImplementation {
                         format, gltype, data.as_mut_ptr() as *mut libc::c_void);
 
                 } else {
-                    ctxt.gl.BindTexture(gl::TEXTURE_2D, my_id);
+                    context::bind_texture(&mut ctxt, gl::TEXTURE_2D, my_id);
                     ctxt.gl.GetTexImage(gl::TEXTURE_2D, level as gl::types::GLint, format, gltype,
                         data.as_mut_ptr() as *mut libc::c_void);
                 }
 
                 data.set_len(pixels_count);
+                ctxt.state.statistics.bytes_transferred += pixels_count * mem::size_of::<P>();
                 tx.send(data);
             }
         });
@@ -219,6 +1455,11 @@ impl TextureImplementation {
         &self.display
     }
 
+    /// Returns the bind point of the texture, for example `GL_TEXTURE_2D`.
+    pub fn get_bind_point(&self) -> gl::types::GLenum {
+        self.bind_point
+    }
+
     /// Returns the width of the texture.
     pub fn get_width(&self) -> u32 {
         self.width
@@ -238,6 +1479,234 @@ impl TextureImplementation {
     pub fn get_array_size(&self) -> Option<u32> {
         self.array_size.clone()
     }
+
+    /// Returns the number of mipmap levels of the texture, including the main one.
+    pub fn get_mipmap_levels(&self) -> u32 {
+        self.levels
+    }
+}
+
+/// Returns the internal format to pass when allocating a texture.
+///
+/// OpenGL ES 2.0 doesn't support sized internal formats. The internal format must instead be
+/// the same as the format of the data, and the implementation chooses the precision.
+fn get_internal_format(es2: bool, format: gl::types::GLenum, client_format: gl::types::GLenum)
+                       -> gl::types::GLenum
+{
+    if es2 { client_format } else { format }
+}
+
+/// Returns the type of the data to pass when uploading to a texture.
+///
+/// `GL_OES_texture_half_float` uses a different value than `GL_HALF_FLOAT` for half-floats.
+fn get_client_type(es2: bool, client_type: gl::types::GLenum) -> gl::types::GLenum {
+    const HALF_FLOAT_OES: gl::types::GLenum = 0x8D61;
+
+    if es2 && client_type == gl::HALF_FLOAT { HALF_FLOAT_OES } else { client_type }
+}
+
+/// Converts pixels that have been read as `GL_RGBA`/`GL_UNSIGNED_BYTE` to the given client
+/// format and type.
+///
+/// This is the only combination that `glReadPixels` is guaranteed to support on OpenGL ES.
+fn convert_rgba8(rgba: &[u8], format: gl::types::GLenum, gltype: gl::types::GLenum) -> Vec<u8> {
+    use std::num::Float;
+
+    let components: &[uint] = match format {
+        gl::RED => &[0],
+        gl::RG => &[0, 1],
+        gl::RGB => &[0, 1, 2],
+        gl::RGBA => &[0, 1, 2, 3],
+        gl::BGRA => &[2, 1, 0, 3],
+        _ => panic!("This client format can't be read back on OpenGL ES")
+    };
+
+    let mut result = Vec::with_capacity(rgba.len() / 4 * client_texel_size(format, gltype));
+
+    for pixel in rgba.chunks(4) {
+        for &c in components.iter() {
+            let value = pixel[c];
+            let normalized = value as f64 / 255.0;
+
+            unsafe {
+                match gltype {
+                    gl::UNSIGNED_BYTE => result.push(value),
+                    gl::BYTE => result.push((normalized * 127.0).round() as i8 as u8),
+                    gl::UNSIGNED_SHORT => {
+                        let v = value as u16 * 257;
+                        result.push_all(&mem::transmute::<u16, [u8, ..2]>(v));
+                    },
+                    gl::SHORT => {
+                        let v = (normalized * 32767.0).round() as i16;
+                        result.push_all(&mem::transmute::<i16, [u8, ..2]>(v));
+                    },
+                    gl::UNSIGNED_INT => {
+                        let v = value as u32 * 0x01010101;
+                        result.push_all(&mem::transmute::<u32, [u8, ..4]>(v));
+                    },
+                    gl::INT => {
+                        let v = (normalized * 2147483647.0).round() as i32;
+                        result.push_all(&mem::transmute::<i32, [u8, ..4]>(v));
+                    },
+                    gl::FLOAT => {
+                        let v = normalized as f32;
+                        result.push_all(&mem::transmute::<f32, [u8, ..4]>(v));
+                    },
+                    _ => panic!("This client type can't be read back on OpenGL ES")
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Returns true if the first level of the texture binded to `bind_point` is compressed.
+unsafe fn is_compressed(ctxt: &context::CommandContext, bind_point: gl::types::GLenum) -> bool {
+    // compressed textures can't be created empty with OpenGL ES
+    if ctxt.opengl_es {
+        return false;
+    }
+
+    let mut compressed = mem::uninitialized();
+    ctxt.gl.GetTexLevelParameteriv(bind_point, 0, gl::TEXTURE_COMPRESSED, &mut compressed);
+    compressed != 0
+}
+
+/// Fills a level of a texture with zeroes.
+///
+/// `target` must be the bind point of the texture, or a face of a cubemap. The texture must
+/// be binded and the unpack parameters must have their default values.
+unsafe fn zero_level(ctxt: &context::CommandContext, id: gl::types::GLuint,
+                     target: gl::types::GLenum, level: u32, (width, height, depth): (u32, u32, u32),
+                     client_format: gl::types::GLenum, client_type: gl::types::GLenum)
+{
+    if ctxt.capabilities.clear_texture {
+        ctxt.gl.ClearTexImage(id, level as gl::types::GLint, client_format, client_type,
+                              ptr::null());
+        return;
+    }
+
+    let size = width as uint * height as uint * depth as uint *
+               client_texel_size(client_format, client_type);
+    let zeroes: Vec<u8> = Vec::from_elem(size, 0);
+    let data = zeroes.as_ptr() as *const libc::c_void;
+
+    match target {
+        gl::TEXTURE_3D | gl::TEXTURE_2D_ARRAY => {
+            ctxt.gl.TexSubImage3D(target, level as i32, 0, 0, 0, width as i32, height as i32,
+                                  depth as i32, client_format, client_type, data);
+        },
+        gl::TEXTURE_1D => {
+            ctxt.gl.TexSubImage1D(target, level as i32, 0, width as i32, client_format,
+                                  client_type, data);
+        },
+        _ => {
+            ctxt.gl.TexSubImage2D(target, level as i32, 0, 0, width as i32, height as i32,
+                                  client_format, client_type, data);
+        },
+    }
+}
+
+/// Copies the rows of the rectangle described by `parameters` in reverse order.
+///
+/// The rows of the result keep the same layout, so `parameters` can still be used to upload
+/// them, except for `skip_rows` which must be set to 0.
+fn flip_rows<P>(data: &[P], width: u32, height: u32, texel_size: uint,
+                parameters: &UploadParameters) -> Vec<u8>
+{
+    let alignment = parameters.alignment as uint;
+    let row_length = parameters.row_length.unwrap_or(width) as uint;
+    let stride = (row_length * texel_size + alignment - 1) / alignment * alignment;
+
+    // the last row of the data can be shorter than the stride
+    let row_size = (parameters.skip_pixels + width) as uint * texel_size;
+
+    let data = data.as_ptr() as *const u8;
+    let mut flipped = Vec::with_capacity(height as uint * stride);
+
+    for row in range(0, height).rev() {
+        let start = (parameters.skip_rows + row) as uint * stride;
+
+        for offset in range(0, row_size) {
+            flipped.push(unsafe { *data.offset((start + offset) as int) });
+        }
+
+        if row != 0 {
+            for _ in range(row_size, stride) {
+                flipped.push(0);
+            }
+        }
+    }
+
+    flipped
+}
+
+/// Returns the size in bits of a texel of an uncompressed color format, which determines the
+/// formats that `glCopyImageSubData` can copy between. Returns `None` for the other formats,
+/// which can only be copied to the same format.
+fn copy_class_size(format: gl::types::GLenum) -> Option<u32> {
+    match format {
+        gl::RGBA32F | gl::RGBA32UI | gl::RGBA32I => Some(128),
+        gl::RGB32F | gl::RGB32UI | gl::RGB32I => Some(96),
+        gl::RGBA16F | gl::RG32F | gl::RGBA16UI | gl::RG32UI | gl::RGBA16I | gl::RG32I |
+        gl::RGBA16 | gl::RGBA16_SNORM => Some(64),
+        gl::RGB16 | gl::RGB16_SNORM | gl::RGB16F | gl::RGB16UI | gl::RGB16I => Some(48),
+        gl::RG16F | gl::R11F_G11F_B10F | gl::R32F | gl::RGB10_A2UI | gl::RGBA8UI |
+        gl::RG16UI | gl::R32UI | gl::RGBA8I | gl::RG16I | gl::R32I | gl::RGB10_A2 |
+        gl::RGBA8 | gl::RG16 | gl::RGBA8_SNORM | gl::RG16_SNORM | gl::SRGB8_ALPHA8 |
+        gl::RGB9_E5 => Some(32),
+        gl::RGB8 | gl::RGB8_SNORM | gl::SRGB8 | gl::RGB8UI | gl::RGB8I => Some(24),
+        gl::R16F | gl::RG8UI | gl::R16UI | gl::RG8I | gl::R16I | gl::RG8 | gl::R16 |
+        gl::RG8_SNORM | gl::R16_SNORM => Some(16),
+        gl::R8UI | gl::R8I | gl::R8 | gl::R8_SNORM => Some(8),
+        _ => None,
+    }
+}
+
+/// Returns the size in bytes of a texel in the given client format.
+fn client_texel_size(client_format: gl::types::GLenum, client_type: gl::types::GLenum) -> uint {
+    let components = match client_format {
+        gl::RED | gl::RED_INTEGER | gl::DEPTH_COMPONENT | gl::STENCIL_INDEX |
+        gl::DEPTH_STENCIL => 1,
+        gl::RG | gl::RG_INTEGER => 2,
+        gl::RGB | gl::RGB_INTEGER | gl::BGR | gl::BGR_INTEGER => 3,
+        _ => 4,
+    };
+
+    match client_type {
+        gl::BYTE | gl::UNSIGNED_BYTE => components,
+        gl::SHORT | gl::UNSIGNED_SHORT | gl::HALF_FLOAT => components * 2,
+        gl::INT | gl::UNSIGNED_INT | gl::FLOAT => components * 4,
+
+        // packed types contain all the components of a texel
+        gl::UNSIGNED_BYTE_3_3_2 | gl::UNSIGNED_BYTE_2_3_3_REV => 1,
+        gl::UNSIGNED_SHORT_5_6_5 | gl::UNSIGNED_SHORT_5_6_5_REV |
+        gl::UNSIGNED_SHORT_4_4_4_4 | gl::UNSIGNED_SHORT_4_4_4_4_REV |
+        gl::UNSIGNED_SHORT_5_5_5_1 | gl::UNSIGNED_SHORT_1_5_5_5_REV => 2,
+        gl::FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
+        _ => 4,
+    }
+}
+
+/// Returns the width, height and depth of a mipmap level of a texture.
+///
+/// The number of elements of arrays is not divided between levels. The dimensions that don't
+/// apply to the texture's type are `1`.
+fn level_dimensions(bind_point: gl::types::GLenum, level: u32, width: u32, height: Option<u32>,
+                    depth: Option<u32>, array_size: Option<u32>) -> (u32, u32, u32)
+{
+    use std::cmp;
+
+    let width = cmp::max(1, width >> level as uint);
+
+    match bind_point {
+        gl::TEXTURE_1D_ARRAY => (width, array_size.unwrap_or(1), 1),
+        gl::TEXTURE_2D_ARRAY => (width, cmp::max(1, height.unwrap_or(1) >> level as uint),
+                                 array_size.unwrap_or(1)),
+        _ => (width, cmp::max(1, height.unwrap_or(1) >> level as uint),
+              cmp::max(1, depth.unwrap_or(1) >> level as uint)),
+    }
 }
 
 impl GlObject for TextureImplementation {
@@ -253,27 +1722,55 @@ impl fmt::Show for TextureImplementation {
     }
 }
 
-impl Drop for TextureImplementation {
-    fn drop(&mut self) {
-        use fbo;
+/// Background task that generates the mipmaps of a texture created with
+/// `MipmapsOption::BackgroundGeneratedMipmaps`.
+///
+/// Each step generates a single level from the previous one, then makes it visible by
+/// advancing `GL_TEXTURE_MAX_LEVEL`.
+struct GenerateMipmaps {
+    id: gl::types::GLuint,
+    bind_point: gl::types::GLenum,
+    levels: u32,
+    next_level: u32,
+}
 
-        // removing FBOs which contain this texture
-        {
-            let mut fbos = self.display.context.framebuffer_objects.lock().unwrap();
+impl context::BackgroundTask for GenerateMipmaps {
+    fn step(&mut self, ctxt: &mut context::CommandContext) -> bool {
+        let level = self.next_level;
+
+        unsafe {
+            context::bind_texture(ctxt, self.bind_point, self.id);
 
-            let to_delete = fbos.keys().filter(|b| {
-                b.colors.iter().find(|&&(_, id)| id == fbo::Attachment::Texture(self.id)).is_some() ||
-                b.depth == Some(fbo::Attachment::Texture(self.id)) || b.stencil == Some(fbo::Attachment::Texture(self.id))
-            }).map(|k| k.clone()).collect::<Vec<_>>();
+            // `glGenerateMipmap` fills the levels between the base level and the max level,
+            // so restricting them to `level - 1` and `level` only generates `level`
+            ctxt.gl.TexParameteri(self.bind_point, gl::TEXTURE_BASE_LEVEL, (level - 1) as i32);
+            ctxt.gl.TexParameteri(self.bind_point, gl::TEXTURE_MAX_LEVEL, level as i32);
 
-            for k in to_delete.into_iter() {
-                fbos.remove(&k);
+            if ctxt.version >= &GlVersion(3, 0) {
+                ctxt.gl.GenerateMipmap(self.bind_point);
+            } else {
+                ctxt.gl.GenerateMipmapEXT(self.bind_point);
             }
+
+            ctxt.gl.TexParameteri(self.bind_point, gl::TEXTURE_BASE_LEVEL, 0);
         }
 
-        let id = self.id.clone();
-        self.display.context.context.exec(move |: ctxt| {
-            unsafe { ctxt.gl.DeleteTextures(1, [ id ].as_ptr()); }
-        });
+        self.next_level += 1;
+        self.next_level >= self.levels
+    }
+
+    fn get_texture(&self) -> Option<gl::types::GLuint> {
+        Some(self.id)
+    }
+}
+
+impl Drop for TextureImplementation {
+    fn drop(&mut self) {
+        // also removes the FBOs which contain this texture
+        if self.owned {
+            self.display.context.delete_object(context::Deletion::Texture(self.id));
+        } else {
+            self.display.context.forget_object(context::Deletion::Texture(self.id));
+        }
     }
 }
@@ -0,0 +1,237 @@
+/*!
+Streaming of texture data without stalling the rendering.
+
+A `TextureUploader` owns a ring of pixel unpack buffers. Each upload copies its data into the
+next buffer of the ring, then the GPU copies the buffer into the texture asynchronously. A
+fence is inserted after each copy, which lets the uploader know when a buffer can be reused and
+when an upload has completed.
+
+The completion of an upload can be observed in two ways:
+
+ - With the `TextureUpload` handle returned by `upload`, which can be polled or waited upon.
+ - With a callback passed to `upload_with_callback`. Callbacks are called by `poll` and
+   `finish`, on the thread that owns the uploader, in the order in which the uploads were
+   submitted.
+
+```no_run
+# let display: glium::Display = unsafe { std::mem::uninitialized() };
+# let texture: glium::Texture2d = unsafe { std::mem::uninitialized() };
+# let rect: glium::Rect = unsafe { std::mem::uninitialized() };
+# let data: Vec<Vec<(u8, u8, u8, u8)>> = unsafe { std::mem::uninitialized() };
+// four buffers of 4 MB each
+let mut uploader = glium::texture::TextureUploader::new(&display, 4, 4 * 1024 * 1024);
+
+uploader.upload_with_callback(&texture, rect, data, move |&mut:| {
+    println!("the texture is ready");
+});
+
+// once per frame
+uploader.poll();
+```
+
+*/
+use gl;
+
+use std::collections::RingBuf;
+use std::mem;
+use std::sync::Arc;
+
+use buffer::{mod, Buffer};
+use sync::SyncFence;
+use Display;
+
+use super::{Texture, Texture2d, Texture2dData, TextureUpload};
+
+/// Uploads data to textures through a ring of pixel buffers.
+///
+/// The uploader doesn't need to stay alive until the uploads are finished, but the callbacks
+/// of the uploads that haven't been observed as complete are not called if it is destroyed.
+pub struct TextureUploader {
+    display: Display,
+    slots: Vec<UploadSlot>,
+    next_slot: uint,
+    pending: RingBuf<PendingUpload>,
+}
+
+/// One of the buffers of the ring.
+struct UploadSlot {
+    buffer: Buffer,
+    // fence inserted after the last copy from this buffer
+    fence: Option<Arc<SyncFence>>,
+}
+
+/// An upload whose completion hasn't been observed yet.
+struct PendingUpload {
+    fence: Option<Arc<SyncFence>>,
+    callback: Option<Box<FnMut() + Send>>,
+}
+
+impl TextureUploader {
+    /// Builds a new uploader with `slots` buffers of `slot_size` bytes each.
+    ///
+    /// The number of slots is the number of uploads that can be in flight before `upload`
+    /// has to wait for the GPU. Uploads that are larger than a slot go through a temporary
+    /// buffer instead.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `slots` or `slot_size` is 0.
+    pub fn new(display: &Display, slots: uint, slot_size: uint) -> TextureUploader {
+        if slots == 0 || slot_size == 0 {
+            panic!("A texture uploader must have at least one non-empty slot");
+        }
+
+        let slots = range(0, slots).map(|_| {
+            UploadSlot {
+                buffer: Buffer::new_empty::<buffer::PixelUnpackBuffer>(display, 1, slot_size,
+                                                                       gl::STREAM_DRAW),
+                fence: None,
+            }
+        }).collect();
+
+        TextureUploader {
+            display: display.clone(),
+            slots: slots,
+            next_slot: 0,
+            pending: RingBuf::new(),
+        }
+    }
+
+    /// Returns the number of uploads whose completion hasn't been observed by `poll` yet.
+    pub fn get_pending_uploads(&self) -> uint {
+        self.pending.len()
+    }
+
+    /// Uploads some data to a part of the main level of a texture.
+    ///
+    /// This function only blocks if all the slots are still being copied by the GPU. Use the
+    /// returned handle to know when the texture contains the new data.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the rectangle is outside of the texture.
+    /// - Panics if the dimensions of the data don't match the rectangle.
+    pub fn upload<T>(&mut self, texture: &Texture2d, rect: ::Rect, data: T) -> TextureUpload
+                     where T: Texture2dData
+    {
+        self.submit(texture, rect, data, None)
+    }
+
+    /// Same as `upload`, but `callback` is called by `poll` or `finish` once the texture
+    /// contains the new data.
+    pub fn upload_with_callback<T, F>(&mut self, texture: &Texture2d, rect: ::Rect, data: T,
+                                      callback: F) -> TextureUpload
+                                      where T: Texture2dData, F: FnMut() + Send
+    {
+        self.submit(texture, rect, data, Some(box callback as Box<FnMut() + Send>))
+    }
+
+    /// Calls the callbacks of the uploads that have completed, in the order in which they
+    /// were submitted. Doesn't block.
+    ///
+    /// Returns the number of uploads that have completed since the last call.
+    ///
+    /// If the backend doesn't support fences, this function waits for all the commands to
+    /// be executed and considers every upload as complete.
+    pub fn poll(&mut self) -> uint {
+        let mut completed = 0;
+
+        loop {
+            let complete = match self.pending.front() {
+                None => break,
+                Some(&PendingUpload { fence: Some(ref fence), .. }) => fence.is_signaled(),
+                Some(&PendingUpload { fence: None, .. }) => {
+                    self.display.synchronize();
+                    true
+                },
+            };
+
+            if !complete {
+                break;
+            }
+
+            let upload = self.pending.pop_front().unwrap();
+            if let Some(mut callback) = upload.callback {
+                callback.call_mut(());
+            }
+
+            completed += 1;
+        }
+
+        completed
+    }
+
+    /// Blocks until all the uploads have completed and calls their callbacks.
+    pub fn finish(&mut self) {
+        for upload in self.pending.iter() {
+            match upload.fence {
+                Some(ref fence) => fence.wait(),
+                None => {
+                    self.display.synchronize();
+                    break;
+                },
+            }
+        }
+
+        for upload in self.pending.drain() {
+            if let Some(mut callback) = upload.callback {
+                callback.call_mut(());
+            }
+        }
+    }
+
+    fn submit<T>(&mut self, texture: &Texture2d, rect: ::Rect, data: T,
+                 callback: Option<Box<FnMut() + Send>>) -> TextureUpload
+                 where T: Texture2dData
+    {
+        if data.get_dimensions() != (rect.width, rect.height) {
+            panic!("The dimensions of the data must match the rectangle");
+        }
+
+        if !rect.is_inside((texture.get_width(), texture.get_height().unwrap())) {
+            panic!("The area to write is outside of the texture");
+        }
+
+        // the callbacks of the previous uploads are called first
+        self.poll();
+
+        let (client_format, client_type) = Texture2dData::get_format(None::<T>).to_gl_enum();
+        let data = data.into_vec();
+        let size = data.len() * mem::size_of::<<T as Texture2dData>::Data>();
+
+        let slot = if size == 0 {
+            None
+
+        } else if size > self.slots[0].buffer.get_total_size() {
+            texture.0.upload_2d_async(rect.left, rect.bottom, rect.width, rect.height,
+                                      client_format, client_type, data);
+            None
+
+        } else {
+            let index = self.next_slot;
+            self.next_slot = (self.next_slot + 1) % self.slots.len();
+
+            let slot = &self.slots[index];
+
+            // without fences, the driver waits for the previous copy from this buffer
+            // by itself when the buffer is modified
+            if let Some(ref fence) = slot.fence {
+                fence.wait();
+            }
+
+            slot.buffer.upload::<buffer::PixelUnpackBuffer, <T as Texture2dData>::Data>(0, data);
+            texture.0.upload_2d_from_buffer(rect.left, rect.bottom, rect.width, rect.height,
+                                            client_format, client_type, &slot.buffer);
+            Some(index)
+        };
+
+        let fence = SyncFence::new(&self.display).map(|f| Arc::new(f));
+
+        if let Some(index) = slot {
+            self.slots[index].fence = fence.clone();
+        }
+
+        self.pending.push_back(PendingUpload { fence: fence.clone(), callback: callback });
+        TextureUpload::with_fence(&self.display, fence)
+    }
+}
@@ -0,0 +1,278 @@
+/*!
+Import of the surfaces of the hardware video decoders of Linux.
+
+Media players usually decode videos with VDPAU or VAAPI, which write the decoded frames in
+surfaces that live in video memory. These helpers make the frames available as textures
+without reading them back on the CPU.
+
+ - `VdpauInterop` uses `GL_NV_vdpau_interop` to share VDPAU output surfaces with OpenGL. The
+   texture and the surface use the same memory.
+ - `VaapiSurface` uses the GLX extension of libva to copy VAAPI surfaces to a texture on the
+   GPU. The display of VAAPI must have been obtained with `vaGetDisplayGLX`.
+
+The X11 display and the GLX context that are needed to create the VDPAU device or the VAAPI
+display can be obtained with `glium::native::get_handles`.
+
+# Features
+
+Only available on Linux if the `video_interop` feature is enabled.
+
+*/
+use gl;
+use libc;
+
+use std::mem;
+use std::ptr;
+
+use Display;
+
+use super::{Texture2d, MipmapsOption, UncompressedFloatFormat};
+use super::tex_impl::TextureImplementation;
+
+#[link(name = "va-glx")]
+extern {
+    fn vaCreateSurfaceGLX(dpy: *mut libc::c_void, target: gl::types::GLenum,
+                          texture: gl::types::GLuint, gl_surface: *mut *mut libc::c_void)
+                          -> libc::c_int;
+    fn vaCopySurfaceGLX(dpy: *mut libc::c_void, gl_surface: *mut libc::c_void,
+                        surface: libc::c_uint, flags: libc::c_uint) -> libc::c_int;
+    fn vaDestroySurfaceGLX(dpy: *mut libc::c_void, gl_surface: *mut libc::c_void)
+                           -> libc::c_int;
+}
+
+/// Interoperability between VDPAU and the OpenGL context of a display.
+///
+/// Only one of these objects must exist at a time for a given display.
+pub struct VdpauInterop {
+    display: Display,
+}
+
+impl VdpauInterop {
+    /// Initializes the interoperability with a VDPAU device.
+    ///
+    /// `device` is the `VdpDevice` and `get_proc_address` is the `VdpGetProcAddress` function
+    /// that were returned when creating the device. The device must have been created on the
+    /// same X11 display as the context.
+    ///
+    /// Returns `None` if the backend doesn't support `GL_NV_vdpau_interop`.
+    ///
+    /// # Safety
+    ///
+    /// The device must stay alive as long as this object exists.
+    pub unsafe fn new(display: &Display, device: u32, get_proc_address: *const libc::c_void)
+                      -> Option<VdpauInterop>
+    {
+        if !display.context.context.capabilities().vdpau_interop {
+            return None;
+        }
+
+        // raw pointers can't be sent between threads
+        let get_proc_address = get_proc_address as uint;
+
+        display.context.context.exec(move |: ctxt| {
+            ctxt.gl.VDPAUInitNV(device as uint as *const libc::c_void,
+                                get_proc_address as *const libc::c_void);
+        });
+
+        Some(VdpauInterop {
+            display: display.clone(),
+        })
+    }
+
+    /// Registers a VDPAU output surface, so that it can be used as a texture.
+    ///
+    /// `surface` is the `VdpOutputSurface`, and `width` and `height` are its dimensions. The
+    /// surface is registered as read-only: glium can sample it but not draw on it.
+    ///
+    /// # Safety
+    ///
+    /// The surface must stay alive as long as the returned object exists, and its dimensions
+    /// must be correct.
+    pub unsafe fn register_output_surface(&self, surface: u32, width: u32, height: u32)
+                                          -> VdpauSurface
+    {
+        let (tx, rx) = channel();
+        self.display.context.context.exec(move |: ctxt| {
+            let mut id = mem::uninitialized();
+            ctxt.gl.GenTextures(1, &mut id);
+
+            let registered = ctxt.gl.VDPAURegisterOutputSurfaceNV(
+                                        surface as uint as *const libc::c_void,
+                                        gl::TEXTURE_2D, 1, &id);
+            ctxt.gl.VDPAUSurfaceAccessNV(registered, gl::READ_ONLY);
+
+            tx.send((id, registered));
+        });
+
+        let (id, registered) = rx.recv();
+
+        VdpauSurface {
+            interop: self,
+            texture: Texture2d(TextureImplementation::from_id_2d(&self.display, id, width,
+                                                                 height, true)),
+            surface: registered,
+        }
+    }
+}
+
+impl Drop for VdpauInterop {
+    fn drop(&mut self) {
+        self.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.VDPAUFiniNV();
+            }
+        });
+    }
+}
+
+/// A VDPAU output surface that is registered in the OpenGL context.
+///
+/// The surface is unregistered when the object is destroyed.
+pub struct VdpauSurface<'a> {
+    interop: &'a VdpauInterop,
+    texture: Texture2d,
+    surface: gl::types::GLvdpauSurfaceNV,
+}
+
+impl<'a> VdpauSurface<'a> {
+    /// Maps the surface so that its content can be used as a texture.
+    ///
+    /// VDPAU must not write to the surface while it is mapped. The surface is unmapped when
+    /// the returned object is destroyed, and can't be mapped again in the meantime.
+    pub fn map<'b>(&'b mut self) -> MappedVdpauSurface<'b, 'a> {
+        let surface = self.surface;
+        self.interop.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.VDPAUMapSurfacesNV(1, &surface);
+            }
+        });
+
+        MappedVdpauSurface {
+            surface: self,
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for VdpauSurface<'a> {
+    fn drop(&mut self) {
+        // the texture is deleted after the surface is unregistered
+        let surface = self.surface;
+        self.interop.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.VDPAUUnregisterSurfaceNV(surface);
+            }
+        });
+    }
+}
+
+/// A VDPAU output surface that is mapped and that can be used as a texture.
+///
+/// The surface is unmapped when the object is destroyed.
+pub struct MappedVdpauSurface<'b, 'a: 'b> {
+    surface: &'b mut VdpauSurface<'a>,
+}
+
+impl<'b, 'a> Deref<Texture2d> for MappedVdpauSurface<'b, 'a> {
+    fn deref(&self) -> &Texture2d {
+        &self.surface.texture
+    }
+}
+
+#[unsafe_destructor]
+impl<'b, 'a> Drop for MappedVdpauSurface<'b, 'a> {
+    fn drop(&mut self) {
+        let surface = self.surface.surface;
+        self.surface.interop.display.context.context.exec(move |: ctxt| {
+            unsafe {
+                ctxt.gl.VDPAUUnmapSurfacesNV(1, &surface);
+            }
+        });
+    }
+}
+
+/// A texture that VAAPI surfaces can be copied to.
+///
+/// The texture derefs to a `Texture2d`.
+pub struct VaapiSurface {
+    texture: Texture2d,
+    va_display: uint,
+    gl_surface: uint,
+}
+
+impl VaapiSurface {
+    /// Creates a texture with the given dimensions and registers it to VAAPI.
+    ///
+    /// `va_display` is the `VADisplay` returned by `vaGetDisplayGLX`. Returns `None` if VAAPI
+    /// refuses to use the texture.
+    ///
+    /// # Safety
+    ///
+    /// The VAAPI display must stay alive as long as this object exists.
+    pub unsafe fn new(display: &Display, va_display: *mut libc::c_void, width: u32,
+                      height: u32) -> Option<VaapiSurface>
+    {
+        use GlObject;
+
+        let texture = Texture2d::new_empty_with_mipmaps(display,
+                                                        UncompressedFloatFormat::U8U8U8U8,
+                                                        width, height, MipmapsOption::NoMipmap);
+
+        // raw pointers can't be sent between threads
+        let va_display = va_display as uint;
+        let id = texture.get_id();
+
+        let (tx, rx) = channel();
+        display.context.context.exec(move |: _| {
+            let mut gl_surface = ptr::null_mut();
+            let status = vaCreateSurfaceGLX(va_display as *mut libc::c_void, gl::TEXTURE_2D, id,
+                                            &mut gl_surface);
+            tx.send(if status == 0 { Some(gl_surface as uint) } else { None });
+        });
+
+        rx.recv().map(|gl_surface| {
+            VaapiSurface {
+                texture: texture,
+                va_display: va_display,
+                gl_surface: gl_surface,
+            }
+        })
+    }
+
+    /// Copies the content of a VAAPI surface to the texture, without going through the CPU.
+    ///
+    /// `surface` is the `VASurfaceID`. Returns `false` if VAAPI failed to copy the surface.
+    pub fn copy_from(&self, surface: u32) -> bool {
+        let (va_display, gl_surface) = (self.va_display, self.gl_surface);
+
+        let (tx, rx) = channel();
+        self.texture.0.get_display().context.context.exec(move |: _| {
+            unsafe {
+                let status = vaCopySurfaceGLX(va_display as *mut libc::c_void,
+                                              gl_surface as *mut libc::c_void,
+                                              surface as libc::c_uint, 0);
+                tx.send(status == 0);
+            }
+        });
+
+        rx.recv()
+    }
+}
+
+impl Deref<Texture2d> for VaapiSurface {
+    fn deref(&self) -> &Texture2d {
+        &self.texture
+    }
+}
+
+impl Drop for VaapiSurface {
+    fn drop(&mut self) {
+        let (va_display, gl_surface) = (self.va_display, self.gl_surface);
+        self.texture.0.get_display().context.context.exec(move |: _| {
+            unsafe {
+                vaDestroySurfaceGLX(va_display as *mut libc::c_void,
+                                    gl_surface as *mut libc::c_void);
+            }
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use gl;
+
+use std::kinds::marker::ContravariantLifetime;
+
+use ToGlEnum;
+
+/// How a shader is allowed to access an image.
+#[deriving(Show, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ImageUnitAccess {
+    /// The shader can only use `imageLoad`.
+    Read,
+    /// The shader can only use `imageStore`.
+    Write,
+    /// The shader can use both `imageLoad` and `imageStore`.
+    ReadWrite,
+}
+
+impl ToGlEnum for ImageUnitAccess {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            ImageUnitAccess::Read => gl::READ_ONLY,
+            ImageUnitAccess::Write => gl::WRITE_ONLY,
+            ImageUnitAccess::ReadWrite => gl::READ_WRITE,
+        }
+    }
+}
+
+/// Format used to interpret the texels of an image.
+///
+/// It must match the layout qualifier of the image in the shader, for example
+/// `layout(rgba32f) uniform image2D img;`, and have the same size as the format of the texture.
+#[allow(missing_docs)]
+#[deriving(Show, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ImageUnitFormat {
+    R32F,
+    RG32F,
+    RGBA32F,
+    R16F,
+    RG16F,
+    RGBA16F,
+    R8,
+    RG8,
+    RGBA8,
+    R32I,
+    RG32I,
+    RGBA32I,
+    R32UI,
+    RG32UI,
+    RGBA32UI,
+}
+
+impl ToGlEnum for ImageUnitFormat {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            ImageUnitFormat::R32F => gl::R32F,
+            ImageUnitFormat::RG32F => gl::RG32F,
+            ImageUnitFormat::RGBA32F => gl::RGBA32F,
+            ImageUnitFormat::R16F => gl::R16F,
+            ImageUnitFormat::RG16F => gl::RG16F,
+            ImageUnitFormat::RGBA16F => gl::RGBA16F,
+            ImageUnitFormat::R8 => gl::R8,
+            ImageUnitFormat::RG8 => gl::RG8,
+            ImageUnitFormat::RGBA8 => gl::RGBA8,
+            ImageUnitFormat::R32I => gl::R32I,
+            ImageUnitFormat::RG32I => gl::RG32I,
+            ImageUnitFormat::RGBA32I => gl::RGBA32I,
+            ImageUnitFormat::R32UI => gl::R32UI,
+            ImageUnitFormat::RG32UI => gl::RG32UI,
+            ImageUnitFormat::RGBA32UI => gl::RGBA32UI,
+        }
+    }
+}
+
+/// An image unit, ie. a texture that can be read and written by a shader with `imageLoad`
+/// and `imageStore`.
+///
+/// Must be binded to an `image1D`, `image2D`, `image3D`, etc. uniform in GLSL.
+pub struct ImageUnit<'t, T: 't>(pub &'t T, pub ImageUnitAccess, pub ImageUnitFormat);
+
+/// A texture binded to an image unit, without the type of the texture.
+#[deriving(Copy, Clone)]
+pub struct ImageUnitBinding<'a> {
+    texture: gl::types::GLuint,
+    layered: bool,
+    access: ImageUnitAccess,
+    format: ImageUnitFormat,
+    marker: ContravariantLifetime<'a>,
+}
+
+impl<'a> ImageUnitBinding<'a> {
+    #[doc(hidden)]      // TODO: hack
+    pub fn new(texture: gl::types::GLuint, layered: bool, access: ImageUnitAccess,
+               format: ImageUnitFormat) -> ImageUnitBinding<'a>
+    {
+        ImageUnitBinding {
+            texture: texture,
+            layered: layered,
+            access: access,
+            format: format,
+            marker: ContravariantLifetime,
+        }
+    }
+
+    /// Returns the id of the texture.
+    #[doc(hidden)]      // TODO: hack
+    pub fn get_texture_id(&self) -> gl::types::GLuint {
+        self.texture
+    }
+
+    /// Returns true if all the layers of the texture are binded.
+    pub fn is_layered(&self) -> bool {
+        self.layered
+    }
+
+    /// Returns how the shader is allowed to access the image.
+    pub fn get_access(&self) -> ImageUnitAccess {
+        self.access
+    }
+
+    /// Returns the format used to interpret the texels.
+    pub fn get_format(&self) -> ImageUnitFormat {
+        self.format
+    }
+}
@@ -31,7 +31,7 @@ let uniforms = Uniforms {
 # }
 ```
 
-Each field must implement the `UniformValue` trait.
+Each field must implement the `UniformValue` trait and `Copy`.
 
 ## Sampler
 
@@ -48,15 +48,35 @@ let uniforms = glium::uniforms::UniformsStorage::new("texture",
     }));
 ```
 
+The same texture can be used with different samplers in different draws. If the backend supports
+sampler objects (OpenGL 3.3 or `GL_ARB_sampler_objects`), glium uses them. Otherwise the
+parameters are applied to the texture itself every time it is bound.
+
+## Image units
+
+In order to read and write a texture with `imageLoad` and `imageStore`, for example from a
+compute shader, you must use an `ImageUnit`.
+
+```no_run
+# let display: glium::Display = unsafe { std::mem::uninitialized() };
+# let texture: glium::texture::Texture2d = unsafe { std::mem::uninitialized() };
+let uniforms = glium::uniforms::UniformsStorage::new("image",
+    glium::uniforms::ImageUnit(&texture, glium::uniforms::ImageUnitAccess::Write,
+                               glium::uniforms::ImageUnitFormat::RGBA8));
+```
+
 */
 pub use self::sampler::{SamplerWrapFunction, MagnifySamplerFilter, MinifySamplerFilter};
-pub use self::sampler::{Sampler, SamplerBehavior};
+pub use self::sampler::{Sampler, SamplerBehavior, DepthTextureComparison};
+pub use self::image_unit::{ImageUnit, ImageUnitAccess, ImageUnitFormat, ImageUnitBinding};
 pub use self::uniforms::{EmptyUniforms, UniformsStorage};
 pub use self::value::{UniformValue, IntoUniformValue, UniformType};
 
 // TODO: remove
-pub use self::sampler::{SamplerObject, get_sampler};
+pub use self::sampler::{SamplerObject, get_sampler, apply_to_texture};
+pub use self::sampler::get_texture_default_behavior;
 
+mod image_unit;
 mod sampler;
 mod uniforms;
 mod value;
@@ -64,13 +84,11 @@ mod value;
 /// Object that contains the values of all the uniforms to bind to a program.
 pub trait Uniforms {
     /// Calls the parameter once with the name and value of each uniform.
-    fn visit_values<F: FnMut(&str, &UniformValue)>(self, F);
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, F);
 }
 
-// TODO: hacky (see #189)
-impl<'a, T: 'a> Uniforms for &'a T where T: Uniforms + Copy {
-    fn visit_values<F: FnMut(&str, &UniformValue)>(self, output: F) {
-        let me = *self;
-        me.visit_values(output);
+impl<'a, T: 'a> Uniforms for &'a T where T: Uniforms {
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, output: F) {
+        (**self).visit_values(output);
     }
 }
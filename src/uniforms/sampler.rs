@@ -5,6 +5,11 @@ use ToGlEnum;
 
 use Display;
 
+use context::{CommandContext, GlVersion};
+
+use std::hash::{mod, Hash};
+use std::mem;
+
 /// Function to use for out-of-bounds samples.
 ///
 /// This is how GL must handle samples that are outside the texture.
@@ -88,13 +93,52 @@ impl ToGlEnum for MinifySamplerFilter {
     }
 }
 
+/// The function that the GPU will use to compare the value of a depth texture with the
+/// reference value.
+///
+/// This is used by `sampler*Shadow` uniforms in GLSL. The result of the sampling is `1.0` if
+/// the comparison passes and `0.0` if it fails.
+#[deriving(Show, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum DepthTextureComparison {
+    /// Passes if the reference value is inferior or equal to the value in the texture.
+    LessOrEqual,
+    /// Passes if the reference value is superior or equal to the value in the texture.
+    GreaterOrEqual,
+    /// Passes if the reference value is strictly inferior to the value in the texture.
+    Less,
+    /// Passes if the reference value is strictly superior to the value in the texture.
+    Greater,
+    /// Passes if the reference value is equal to the value in the texture.
+    Equal,
+    /// Passes if the reference value is different from the value in the texture.
+    NotEqual,
+    /// Always passes.
+    Always,
+    /// Never passes.
+    Never,
+}
+
+impl ToGlEnum for DepthTextureComparison {
+    fn to_glenum(&self) -> gl::types::GLenum {
+        match *self {
+            DepthTextureComparison::LessOrEqual => gl::LEQUAL,
+            DepthTextureComparison::GreaterOrEqual => gl::GEQUAL,
+            DepthTextureComparison::Less => gl::LESS,
+            DepthTextureComparison::Greater => gl::GREATER,
+            DepthTextureComparison::Equal => gl::EQUAL,
+            DepthTextureComparison::NotEqual => gl::NOTEQUAL,
+            DepthTextureComparison::Always => gl::ALWAYS,
+            DepthTextureComparison::Never => gl::NEVER,
+        }
+    }
+}
+
 /// A sampler.
 pub struct Sampler<'t, T: 't>(pub &'t T, pub SamplerBehavior);
 
 /// Behavior of a sampler.
-// TODO: GL_TEXTURE_BORDER_COLOR, GL_TEXTURE_MIN_LOD, GL_TEXTURE_MAX_LOD, GL_TEXTURE_LOD_BIAS,
-//       GL_TEXTURE_COMPARE_MODE, GL_TEXTURE_COMPARE_FUNC
-#[deriving(Show, Clone, Copy, Hash, PartialEq, Eq)]
+// TODO: GL_TEXTURE_BORDER_COLOR
+#[deriving(Show, Clone, Copy)]
 pub struct SamplerBehavior {
     /// Functions to use for the X, Y, and Z coordinates.
     pub wrap_function: (SamplerWrapFunction, SamplerWrapFunction, SamplerWrapFunction),
@@ -112,6 +156,60 @@ pub struct SamplerBehavior {
     /// If you set the value to a value higher than what the hardware supports, it will
     /// be clamped.
     pub max_anisotropy: u16,
+    /// Value added to the mipmap level that the GPU computes before sampling. Positive
+    /// values make the texture blurrier, negative values make it sharper.
+    ///
+    /// ## Compatibility
+    ///
+    /// This parameter is ignored with OpenGL ES.
+    pub lod_bias: f32,
+    /// The lowest mipmap level of detail that can be sampled. Can be used to hide the most
+    /// detailed levels while they are not loaded yet.
+    ///
+    /// ## Compatibility
+    ///
+    /// This parameter is ignored with OpenGL ES 2.
+    pub min_lod: f32,
+    /// The highest mipmap level of detail that can be sampled.
+    ///
+    /// ## Compatibility
+    ///
+    /// This parameter is ignored with OpenGL ES 2.
+    pub max_lod: f32,
+    /// If `Some`, the texture is a depth texture and the sampling will compare the reference
+    /// value with the value in the texture.
+    ///
+    /// The texture must be bound to a `sampler*Shadow` uniform.
+    pub depth_texture_comparison: Option<DepthTextureComparison>,
+}
+
+impl SamplerBehavior {
+    /// Returns a value that identifies the behavior. The levels of detail are compared with
+    /// their bits, so that the behavior can be used as the key of a map.
+    fn get_key(&self) -> ((SamplerWrapFunction, SamplerWrapFunction, SamplerWrapFunction),
+                          MinifySamplerFilter, MagnifySamplerFilter, u16,
+                          (u32, u32, u32), Option<DepthTextureComparison>)
+    {
+        let bits = |&: value: f32| -> u32 { unsafe { mem::transmute(value) } };
+
+        (self.wrap_function, self.minify_filter, self.magnify_filter, self.max_anisotropy,
+         (bits(self.lod_bias), bits(self.min_lod), bits(self.max_lod)),
+         self.depth_texture_comparison)
+    }
+}
+
+impl PartialEq for SamplerBehavior {
+    fn eq(&self, other: &SamplerBehavior) -> bool {
+        self.get_key() == other.get_key()
+    }
+}
+
+impl Eq for SamplerBehavior {}
+
+impl<S: hash::Writer> Hash<S> for SamplerBehavior {
+    fn hash(&self, state: &mut S) {
+        self.get_key().hash(state)
+    }
 }
 
 impl ::std::default::Default for SamplerBehavior {
@@ -125,6 +223,10 @@ impl ::std::default::Default for SamplerBehavior {
             minify_filter: MinifySamplerFilter::Linear,
             magnify_filter: MagnifySamplerFilter::Linear,
             max_anisotropy: 1,
+            lod_bias: 0.0,
+            min_lod: -1000.0,
+            max_lod: 1000.0,
+            depth_texture_comparison: None,
         }
     }
 }
@@ -171,6 +273,21 @@ impl SamplerObject {
 
                     ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MAX_ANISOTROPY_EXT, value);
                 }
+
+                // sampler objects always support the levels of detail, but not the bias
+                // with OpenGL ES
+                ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MIN_LOD, behavior.min_lod);
+                ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_MAX_LOD, behavior.max_lod);
+                if !ctxt.opengl_es {
+                    ctxt.gl.SamplerParameterf(sampler, gl::TEXTURE_LOD_BIAS, behavior.lod_bias);
+                }
+
+                if let Some(comparison) = behavior.depth_texture_comparison {
+                    ctxt.gl.SamplerParameteri(sampler, gl::TEXTURE_COMPARE_MODE,
+                        gl::COMPARE_REF_TO_TEXTURE as gl::types::GLint);
+                    ctxt.gl.SamplerParameteri(sampler, gl::TEXTURE_COMPARE_FUNC,
+                        comparison.to_glenum() as gl::types::GLint);
+                }
             }
 
             tx.send(sampler);
@@ -194,21 +311,125 @@ impl Drop for SamplerObject {
         let id = self.id;
         self.display.context.context.exec(move |: ctxt| {
             unsafe {
+                // deleting a sampler unbinds it from all the texture units
+                for unit in ctxt.state.sampler_units.iter_mut() {
+                    if *unit == Some(id) {
+                        *unit = None;
+                    }
+                }
+
                 ctxt.gl.DeleteSamplers(1, [id].as_ptr());
             }
         });
     }
 }
 
+/// Returns the id of a sampler object corresponding to this behavior, or `None` if sampler
+/// objects are not supported by the backend.
 #[doc(hidden)]      // TODO: hack
-pub fn get_sampler(display: &::Display, behavior: &SamplerBehavior) -> gl::types::GLuint {
+pub fn get_sampler(display: &::Display, behavior: &SamplerBehavior) -> Option<gl::types::GLuint> {
+    if !display.context.context.capabilities().sampler_objects {
+        return None;
+    }
+
     match display.context.samplers.lock().unwrap().get(behavior) {
-        Some(obj) => return obj.get_id(),
+        Some(obj) => return Some(obj.get_id()),
         None => ()
     };
 
     let sampler = SamplerObject::new(display, behavior);
     let id = sampler.get_id();
     display.context.samplers.lock().unwrap().insert(behavior.clone(), sampler);
-    id
+    Some(id)
+}
+
+/// Sets the parameters of the texture currently bound to `bind_point`.
+///
+/// This is used as a fallback when sampler objects are not supported.
+#[doc(hidden)]      // TODO: hack
+pub unsafe fn apply_to_texture(ctxt: &mut CommandContext, bind_point: gl::types::GLenum,
+                               behavior: &SamplerBehavior)
+{
+    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_WRAP_S,
+        behavior.wrap_function.0.to_glenum() as gl::types::GLint);
+    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_WRAP_T,
+        behavior.wrap_function.1.to_glenum() as gl::types::GLint);
+
+    // only the textures that have a third coordinate have a `GL_TEXTURE_WRAP_R`
+    if bind_point == gl::TEXTURE_3D || bind_point == gl::TEXTURE_2D_ARRAY {
+        ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_WRAP_R,
+            behavior.wrap_function.2.to_glenum() as gl::types::GLint);
+    }
+
+    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MIN_FILTER,
+        behavior.minify_filter.to_glenum() as gl::types::GLint);
+    ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_MAG_FILTER,
+        behavior.magnify_filter.to_glenum() as gl::types::GLint);
+
+    if let Some(max_value) = ctxt.capabilities.max_texture_max_anisotropy {
+        let value = if behavior.max_anisotropy as f32 > max_value {
+            max_value
+        } else {
+            behavior.max_anisotropy as f32
+        };
+
+        ctxt.gl.TexParameterf(bind_point, gl::TEXTURE_MAX_ANISOTROPY_EXT, value);
+    }
+
+    if !ctxt.opengl_es || ctxt.version >= &GlVersion(3, 0) {
+        ctxt.gl.TexParameterf(bind_point, gl::TEXTURE_MIN_LOD, behavior.min_lod);
+        ctxt.gl.TexParameterf(bind_point, gl::TEXTURE_MAX_LOD, behavior.max_lod);
+    }
+
+    if !ctxt.opengl_es {
+        ctxt.gl.TexParameterf(bind_point, gl::TEXTURE_LOD_BIAS, behavior.lod_bias);
+    }
+
+    // the comparison parameters don't exist without shadow samplers, in which case no
+    // texture can have a comparison enabled
+    if !ctxt.capabilities.shadow_samplers {
+        return;
+    }
+
+    if let Some(comparison) = behavior.depth_texture_comparison {
+        ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_COMPARE_MODE,
+            gl::COMPARE_REF_TO_TEXTURE as gl::types::GLint);
+        ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_COMPARE_FUNC,
+            comparison.to_glenum() as gl::types::GLint);
+    } else {
+        ctxt.gl.TexParameteri(bind_point, gl::TEXTURE_COMPARE_MODE,
+            gl::NONE as gl::types::GLint);
+    }
+}
+
+/// Returns the behavior that textures have when they are created.
+///
+/// When sampler objects are not supported, this is applied to textures that are used without
+/// a `Sampler` so that a previous sampler doesn't leak into the next draw. The filters depend
+/// on the number of mipmap levels and on whether the texture is integral, otherwise textures
+/// without mipmaps would be incomplete.
+#[doc(hidden)]      // TODO: hack
+pub fn get_texture_default_behavior(mipmap_levels: u32, integral: bool) -> SamplerBehavior {
+    let (minify_filter, magnify_filter) = match (integral, mipmap_levels >= 2) {
+        (false, true) => (MinifySamplerFilter::LinearMipmapLinear, MagnifySamplerFilter::Linear),
+        (false, false) => (MinifySamplerFilter::Linear, MagnifySamplerFilter::Linear),
+        (true, true) => (MinifySamplerFilter::NearestMipmapNearest,
+                         MagnifySamplerFilter::Nearest),
+        (true, false) => (MinifySamplerFilter::Nearest, MagnifySamplerFilter::Nearest),
+    };
+
+    SamplerBehavior {
+        wrap_function: (
+            SamplerWrapFunction::Repeat,
+            SamplerWrapFunction::Repeat,
+            SamplerWrapFunction::Repeat
+        ),
+        minify_filter: minify_filter,
+        magnify_filter: magnify_filter,
+        max_anisotropy: 1,
+        lod_bias: 0.0,
+        min_lod: -1000.0,
+        max_lod: 1000.0,
+        depth_texture_comparison: None,
+    }
 }
@@ -5,7 +5,29 @@ use uniforms::{Uniforms, UniformValue, IntoUniformValue};
 pub struct EmptyUniforms;
 
 impl Uniforms for EmptyUniforms {
-    fn visit_values<F: FnMut(&str, &UniformValue)>(self, _: F) {
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, _: F) {
+    }
+}
+
+/// Two sets of uniforms can be combined in a tuple. The values of the first one are visited
+/// first.
+///
+/// Tuples can be nested, which lets a library add its own uniforms on top of the ones
+/// provided by the user, for example `(&user_uniforms, (fog_color, fog_density))`.
+impl<A, B> Uniforms for (A, B) where A: Uniforms, B: Uniforms {
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, mut output: F) {
+        let &(ref a, ref b) = self;
+        a.visit_values(|&mut: name: &str, value: &UniformValue| output(name, value));
+        b.visit_values(|&mut: name: &str, value: &UniformValue| output(name, value));
+    }
+}
+
+impl<A, B, C> Uniforms for (A, B, C) where A: Uniforms, B: Uniforms, C: Uniforms {
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, mut output: F) {
+        let &(ref a, ref b, ref c) = self;
+        a.visit_values(|&mut: name: &str, value: &UniformValue| output(name, value));
+        b.visit_values(|&mut: name: &str, value: &UniformValue| output(name, value));
+        c.visit_values(|&mut: name: &str, value: &UniformValue| output(name, value));
     }
 }
 
@@ -51,8 +73,8 @@ impl<'a> UniformsStorage<'a> {
     }
 }
 
-impl<'a: 'b, 'b> Uniforms for &'b UniformsStorage<'a> {
-    fn visit_values<F: FnMut(&str, &UniformValue)>(self, mut output: F) {
+impl<'a> Uniforms for UniformsStorage<'a> {
+    fn visit_values<F: FnMut(&str, &UniformValue)>(&self, mut output: F) {
         for &(n, ref v) in self.uniforms.iter() {
             output(n, v)
         }
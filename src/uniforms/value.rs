@@ -1,5 +1,5 @@
 use texture;
-use uniforms::SamplerBehavior;
+use uniforms::{SamplerBehavior, ImageUnitBinding};
 
 #[cfg(feature = "cgmath")]
 use cgmath;
@@ -78,6 +78,9 @@ pub enum UniformType {
     Sampler2dMultisample,
     ISampler2dMultisample,
     USampler2dMultisample,
+    Sampler2dMultisampleArray,
+    ISampler2dMultisampleArray,
+    USampler2dMultisampleArray,
     Sampler1dShadow,
     Sampler2dShadow,
     SamplerCubeShadow,
@@ -159,12 +162,32 @@ pub enum UniformValue<'a> {
     CompressedTexture2dArray(&'a texture::CompressedTexture2dArray, Option<SamplerBehavior>),
     IntegralTexture2dArray(&'a texture::IntegralTexture2dArray, Option<SamplerBehavior>),
     UnsignedTexture2dArray(&'a texture::UnsignedTexture2dArray, Option<SamplerBehavior>),
+    DepthTexture1d(&'a texture::DepthTexture1d, Option<SamplerBehavior>),
+    DepthTexture2d(&'a texture::DepthTexture2d, Option<SamplerBehavior>),
+    DepthTexture1dArray(&'a texture::DepthTexture1dArray, Option<SamplerBehavior>),
+    DepthTexture2dArray(&'a texture::DepthTexture2dArray, Option<SamplerBehavior>),
+    DepthStencilTexture2d(&'a texture::DepthStencilTexture2d, Option<SamplerBehavior>,
+                          texture::DepthStencilTextureMode),
+    Cubemap(&'a texture::Cubemap, Option<SamplerBehavior>),
+    Texture2dMultisampleArray(&'a texture::Texture2dMultisampleArray),
+    BufferTexture(texture::BufferTextureRef<'a>),
+    /// A texture binded to an image unit.
+    Image(ImageUnitBinding<'a>),
 }
 
 impl<'a> UniformValue<'a> {
     /// Returns the corresponding `UniformType`.
     pub fn get_type(&self) -> UniformType {
         match *self {
+            UniformValue::SignedInt(_) => UniformType::Int,
+            UniformValue::UnsignedInt(_) => UniformType::UnsignedInt,
+            UniformValue::Float(_) => UniformType::Float,
+            UniformValue::Mat2(_) => UniformType::FloatMat2,
+            UniformValue::Mat3(_) => UniformType::FloatMat3,
+            UniformValue::Mat4(_) => UniformType::FloatMat4,
+            UniformValue::Vec2(_) => UniformType::FloatVec2,
+            UniformValue::Vec3(_) => UniformType::FloatVec3,
+            UniformValue::Vec4(_) => UniformType::FloatVec4,
             UniformValue::Texture1d(_, _) => UniformType::Sampler1d,
             UniformValue::CompressedTexture1d(_, _) => UniformType::Sampler1d,
             UniformValue::IntegralTexture1d(_, _) => UniformType::ISampler1d,
@@ -185,9 +208,75 @@ impl<'a> UniformValue<'a> {
             UniformValue::CompressedTexture2dArray(_, _) => UniformType::Sampler2dArray,
             UniformValue::IntegralTexture2dArray(_, _) => UniformType::ISampler2dArray,
             UniformValue::UnsignedTexture2dArray(_, _) => UniformType::USampler2dArray,
+            UniformValue::DepthTexture1d(_, s) if is_shadow(s) => UniformType::Sampler1dShadow,
+            UniformValue::DepthTexture1d(_, _) => UniformType::Sampler1d,
+            UniformValue::DepthTexture2d(_, s) if is_shadow(s) => UniformType::Sampler2dShadow,
+            UniformValue::DepthTexture2d(_, _) => UniformType::Sampler2d,
+            UniformValue::DepthTexture1dArray(_, s) if is_shadow(s) => {
+                UniformType::Sampler1dArrayShadow
+            },
+            UniformValue::DepthTexture1dArray(_, _) => UniformType::Sampler1dArray,
+            UniformValue::DepthTexture2dArray(_, s) if is_shadow(s) => {
+                UniformType::Sampler2dArrayShadow
+            },
+            UniformValue::DepthTexture2dArray(_, _) => UniformType::Sampler2dArray,
+            UniformValue::DepthStencilTexture2d(_, _, mode)
+                if mode == texture::DepthStencilTextureMode::Stencil => UniformType::USampler2d,
+            UniformValue::DepthStencilTexture2d(_, s, _) if is_shadow(s) => {
+                UniformType::Sampler2dShadow
+            },
+            UniformValue::DepthStencilTexture2d(_, _, _) => UniformType::Sampler2d,
+            UniformValue::Cubemap(_, _) => UniformType::SamplerCube,
+            UniformValue::Texture2dMultisampleArray(_) => UniformType::Sampler2dMultisampleArray,
+            UniformValue::BufferTexture(texture) => match texture.get_texture_type() {
+                texture::BufferTextureType::Float => UniformType::SamplerBuffer,
+                texture::BufferTextureType::Integral => UniformType::ISamplerBuffer,
+                texture::BufferTextureType::Unsigned => UniformType::USamplerBuffer,
+            },
             _ => unimplemented!()
         }
     }
+
+    /// Returns true if this value can be binded to a uniform of the given type.
+    pub fn is_usable_with(&self, ty: &UniformType) -> bool {
+        match (*self, *ty) {
+            // booleans can be set with integers or floats
+            (UniformValue::SignedInt(_), UniformType::Bool) => true,
+            (UniformValue::UnsignedInt(_), UniformType::Bool) => true,
+            (UniformValue::Float(_), UniformType::Bool) => true,
+            (UniformValue::Vec2(_), UniformType::BoolVec2) => true,
+            (UniformValue::Vec3(_), UniformType::BoolVec3) => true,
+            (UniformValue::Vec4(_), UniformType::BoolVec4) => true,
+
+            // the dimensions of the image are not known, only check that it is an image
+            (UniformValue::Image(_), ty) => is_image(ty),
+
+            (ref value, ty) => value.get_type() == ty,
+        }
+    }
+}
+
+/// Returns true if the type is one of the `image*` types.
+fn is_image(ty: UniformType) -> bool {
+    match ty {
+        UniformType::Image1d | UniformType::IImage1d | UniformType::UImage1d |
+        UniformType::Image2d | UniformType::IImage2d | UniformType::UImage2d |
+        UniformType::Image3d | UniformType::IImage3d | UniformType::UImage3d |
+        UniformType::Image2dRect | UniformType::IImage2dRect | UniformType::UImage2dRect |
+        UniformType::ImageCube | UniformType::IImageCube | UniformType::UImageCube |
+        UniformType::ImageBuffer | UniformType::IImageBuffer | UniformType::UImageBuffer |
+        UniformType::Image1dArray | UniformType::IImage1dArray | UniformType::UImage1dArray |
+        UniformType::Image2dArray | UniformType::IImage2dArray | UniformType::UImage2dArray |
+        UniformType::Image2dMultisample | UniformType::IImage2dMultisample |
+        UniformType::UImage2dMultisample | UniformType::Image2dMultisampleArray |
+        UniformType::IImage2dMultisampleArray | UniformType::UImage2dMultisampleArray => true,
+        _ => false
+    }
+}
+
+/// Returns true if the sampler behavior turns a depth texture into a shadow sampler.
+fn is_shadow(sampler: Option<SamplerBehavior>) -> bool {
+    sampler.map(|s| s.depth_texture_comparison.is_some()).unwrap_or(false)
 }
 
 impl IntoUniformValue<'static> for i8 {
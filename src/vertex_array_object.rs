@@ -1,13 +1,108 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::mem;
 
 use program::Program;
 use index_buffer::IndicesSource;
-use vertex_buffer::{VerticesSource, AttributeType};
+use vertex_buffer::{VerticesSource, VertexBufferAny, VertexFormat, AttributeType};
 use {DisplayImpl, GlObject};
+use context::{CommandContext, GlVersion};
 
 use {libc, gl};
 
+/// Identifies a vertex buffer bound to a VAO.
+///
+/// Contains the id of the buffer, the id of the attributes read from it (see `get_format_id`),
+/// the number of elements to skip, and whether the attributes are per-instance.
+pub type BufferKey = (gl::types::GLuint, u32, uint, bool);
+
+/// Returns the id of a vertex format, which identifies it in the keys of the VAOs.
+///
+/// The ids are never released, as a program only uses a handful of different formats.
+pub fn get_format_id(display: &DisplayImpl, format: &VertexFormat) -> u32 {
+    let mut formats = display.vertex_formats.lock().unwrap();
+
+    if let Some(&id) = formats.get(format) {
+        return id;
+    }
+
+    let id = formats.len() as u32 + 1;
+    formats.insert(format.clone(), id);
+    id
+}
+
+/// The attributes of a program, as `(name, location, type, size)` sorted by name.
+pub type AttributeLayout = Vec<(String, gl::types::GLint, gl::types::GLenum, gl::types::GLint)>;
+
+/// The attribute layouts of the programs that are alive.
+///
+/// A VAO only depends on the locations of the attributes of the program it was built for, so
+/// the VAOs are keyed by layout instead of by program. Programs with the same layout share
+/// their VAOs.
+pub struct AttributeLayouts {
+    next_id: u32,
+    // the id of each layout and the number of programs that use it
+    layouts: HashMap<AttributeLayout, (u32, uint)>,
+}
+
+impl AttributeLayouts {
+    pub fn new() -> AttributeLayouts {
+        AttributeLayouts {
+            next_id: 1,
+            layouts: HashMap::new(),
+        }
+    }
+}
+
+/// Returns the id of a layout, registering it if no other program uses it yet.
+///
+/// Must be balanced by a call to `release_layout` when the program is destroyed.
+pub fn register_layout(display: &DisplayImpl, layout: AttributeLayout) -> u32 {
+    let mut layouts = display.attribute_layouts.lock().unwrap();
+
+    if let Some(entry) = layouts.layouts.get_mut(&layout) {
+        entry.1 += 1;
+        return entry.0;
+    }
+
+    let id = layouts.next_id;
+    layouts.next_id += 1;
+    layouts.layouts.insert(layout, (id, 1));
+    id
+}
+
+/// Releases a layout registered with `register_layout`. If no other program uses it, the
+/// VAOs that have been built for it are destroyed.
+pub fn release_layout(display: &DisplayImpl, id: u32) {
+    {
+        let mut layouts = display.attribute_layouts.lock().unwrap();
+
+        let layout = match layouts.layouts.iter().find(|&(_, &(i, _))| i == id) {
+            Some((layout, _)) => layout.clone(),
+            None => return
+        };
+
+        let count = {
+            let entry = layouts.layouts.get_mut(&layout).unwrap();
+            entry.1 -= 1;
+            entry.1
+        };
+
+        if count != 0 {
+            return;
+        }
+
+        layouts.layouts.remove(&layout);
+    }
+
+    let mut vaos = display.vertex_array_objects.lock().unwrap();
+    let to_delete = vaos.keys().filter(|&&(_, _, l)| l == id)
+        .map(|k| k.clone()).collect::<Vec<_>>();
+    for k in to_delete.into_iter() {
+        vaos.remove(&k);
+    }
+}
+
 /// 
 pub struct VertexArrayObject {
     display: Arc<DisplayImpl>,
@@ -15,38 +110,15 @@ pub struct VertexArrayObject {
 }
 
 impl VertexArrayObject {
-    /// 
-    fn new(display: Arc<DisplayImpl>, vertex_buffer: VerticesSource,
+    /// The attributes of the vertex buffers must have been checked against the program.
+    fn new(display: Arc<DisplayImpl>, vertex_buffer: &VerticesSource,
            ib_id: gl::types::GLuint, program: &Program) -> VertexArrayObject
     {
-        let VerticesSource::VertexBuffer(vertex_buffer) = vertex_buffer;
-        let bindings = vertex_buffer.get_bindings().clone();
-        let vb_elementssize = vertex_buffer.get_elements_size();
-        let vertex_buffer = GlObject::get_id(vertex_buffer);
-        let attributes = ::program::get_attributes(program);
-
-        // checking the attributes types
-        for &(ref name, _, ty) in bindings.iter() {
-            let attribute = match attributes.get(name) {
-                Some(a) => a,
-                None => continue
-            };
-
-            if !vertex_type_matches(ty, attribute.ty, attribute.size) {
-                panic!("The program attribute `{}` does not match the vertex format", name);
-            }
-        }
-
-        // checking for missing attributes
-        for (&ref name, _) in attributes.iter() {
-            if bindings.iter().find(|&&(ref n, _, _)| n == name).is_none() {
-                panic!("The program attribute `{}` is missing in the vertex bindings", name);
-            }
-        };
+        let bindings = resolve_bindings(vertex_buffer, program);
 
         let (tx, rx) = channel();
 
-        display.context.exec(move |: ctxt| {
+        display.context.exec(move |: mut ctxt| {
             unsafe {
                 let id: gl::types::GLuint = mem::uninitialized();
                 ctxt.gl.GenVertexArrays(1, mem::transmute(&id));
@@ -54,41 +126,12 @@ impl VertexArrayObject {
 
                 ctxt.gl.BindVertexArray(id);
                 ctxt.state.vertex_array = id;
-
-                // binding vertex buffer
-                if ctxt.state.array_buffer_binding != vertex_buffer {
-                    ctxt.gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
-                    ctxt.state.array_buffer_binding = vertex_buffer;
-                }
+                ctxt.state.statistics.state_changes += 1;
 
                 // binding index buffer
                 ctxt.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ib_id);
 
-                // binding attributes
-                for (name, offset, ty) in bindings.into_iter() {
-                    let (data_type, elements_count) = vertex_binding_type_to_gl(ty);
-
-                    let attribute = match attributes.get(&name) {
-                        Some(a) => a,
-                        None => continue
-                    };
-
-                    if attribute.location != -1 {
-                        match data_type {
-                            gl::BYTE | gl::UNSIGNED_BYTE | gl::SHORT | gl::UNSIGNED_SHORT |
-                            gl::INT | gl::UNSIGNED_INT =>
-                                ctxt.gl.VertexAttribIPointer(attribute.location as u32,
-                                    elements_count as gl::types::GLint, data_type,
-                                    vb_elementssize as i32, offset as *const libc::c_void),
-
-                            _ => ctxt.gl.VertexAttribPointer(attribute.location as u32,
-                                    elements_count as gl::types::GLint, data_type, 0,
-                                    vb_elementssize as i32, offset as *const libc::c_void)
-                        }
-                        
-                        ctxt.gl.EnableVertexAttribArray(attribute.location as u32);
-                    }
-                }
+                bind_attributes(&mut ctxt, bindings);
             }
         });
 
@@ -99,6 +142,147 @@ impl VertexArrayObject {
     }
 }
 
+/// Returns the buffers of a source as `(buffer, attributes, format id, offset, per_instance)`,
+/// without copying their attributes.
+fn get_buffers<'a>(vertex_buffer: &'a VerticesSource)
+                   -> Vec<(&'a VertexBufferAny, &'a VertexFormat, u32, uint, bool)>
+{
+    match vertex_buffer {
+        &VerticesSource::VertexBuffer(buffer, _, _) => {
+            vec![(buffer, buffer.get_bindings(), buffer.get_format_id(), 0, false)]
+        },
+        &VerticesSource::Bindings(ref bindings, _, _) => {
+            bindings.iter().map(|b| (b.buffer, &b.format, b.format_id, b.offset, b.per_instance))
+                    .collect()
+        },
+    }
+}
+
+/// A vertex buffer and the attributes of a program that are read from it, as
+/// `(buffer, stride, attributes, per_instance)`. Each attribute is a
+/// `(location, offset, type, normalize)`, where the offset includes the elements to skip.
+pub type ResolvedBinding = (gl::types::GLuint, uint,
+                        Vec<(gl::types::GLuint, uint, AttributeType, bool)>, bool);
+
+/// Matches the attributes of the vertex buffers with the attributes of the program.
+fn resolve_bindings(vertex_buffer: &VerticesSource, program: &Program) -> Vec<ResolvedBinding> {
+    let attributes = ::program::get_attributes(program);
+
+    get_buffers(vertex_buffer).into_iter().map(|(buffer, format, _, skip, per_instance)| {
+        let stride = buffer.get_elements_size();
+
+        let vb_attributes = format.iter().filter_map(|&(ref name, offset, ty, normalize)| {
+            match attributes.get(name) {
+                Some(a) if a.location != -1 => {
+                    Some((a.location as gl::types::GLuint, skip * stride + offset, ty,
+                          normalize))
+                },
+                _ => None
+            }
+        }).collect();
+
+        (buffer.get_id(), stride, vb_attributes, per_instance)
+    }).collect()
+}
+
+/// Binds and enables the attributes of the currently bound vertex array.
+///
+/// Returns the locations of the attributes that have been enabled.
+unsafe fn bind_attributes(ctxt: &mut CommandContext, bindings: Vec<ResolvedBinding>)
+                          -> Vec<gl::types::GLuint>
+{
+    // OpenGL ES 2.0 doesn't have integral attributes, so integral data is
+    // converted to floating-point values
+    let integral_attributes = !ctxt.opengl_es || ctxt.version >= &GlVersion(3, 0);
+
+    let mut enabled = Vec::new();
+
+    for (vertex_buffer, stride, attributes, per_instance) in bindings.into_iter() {
+        // binding vertex buffer
+        if ctxt.state.array_buffer_binding != vertex_buffer {
+            ctxt.gl.BindBuffer(gl::ARRAY_BUFFER, vertex_buffer);
+            ctxt.state.array_buffer_binding = vertex_buffer;
+            ctxt.state.statistics.state_changes += 1;
+        }
+
+        // binding attributes
+        for (location, offset, ty, normalize) in attributes.into_iter() {
+            let (data_type, elements_count) = vertex_binding_type_to_gl(ty);
+
+            match data_type {
+                gl::BYTE | gl::UNSIGNED_BYTE | gl::SHORT | gl::UNSIGNED_SHORT |
+                gl::INT | gl::UNSIGNED_INT if !normalize && integral_attributes =>
+                    ctxt.gl.VertexAttribIPointer(location,
+                        elements_count as gl::types::GLint, data_type,
+                        stride as i32, offset as *const libc::c_void),
+
+                gl::DOUBLE =>
+                    ctxt.gl.VertexAttribLPointer(location,
+                        elements_count as gl::types::GLint, data_type,
+                        stride as i32, offset as *const libc::c_void),
+
+                _ => ctxt.gl.VertexAttribPointer(location,
+                        elements_count as gl::types::GLint, data_type,
+                        if normalize { gl::TRUE } else { gl::FALSE },
+                        stride as i32, offset as *const libc::c_void)
+            }
+
+            // without VAOs, the divisor set by a previous draw must be reset
+            if per_instance {
+                ctxt.gl.VertexAttribDivisor(location, 1);
+            } else if ctxt.state.vertex_array == 0 && ctxt.capabilities.instanced_arrays {
+                ctxt.gl.VertexAttribDivisor(location, 0);
+            }
+
+            ctxt.gl.EnableVertexAttribArray(location);
+            enabled.push(location);
+        }
+    }
+
+    enabled
+}
+
+/// How the vertex attributes of a draw are bound.
+pub enum VertexAttributesBinding {
+    /// A VAO that contains the attributes and the index buffer.
+    Vao(gl::types::GLuint),
+
+    /// VAOs are not supported, so the attributes and the index buffer are bound to the
+    /// default vertex array before each draw.
+    Direct(Vec<ResolvedBinding>, gl::types::GLuint),
+}
+
+impl VertexAttributesBinding {
+    /// Binds the attributes before a draw.
+    pub unsafe fn bind(self, ctxt: &mut CommandContext) {
+        match self {
+            VertexAttributesBinding::Vao(id) => {
+                if ctxt.state.vertex_array != id {
+                    ctxt.gl.BindVertexArray(id);
+                    ctxt.state.vertex_array = id;
+                    ctxt.state.statistics.state_changes += 1;
+                }
+            },
+
+            VertexAttributesBinding::Direct(bindings, ib_id) => {
+                ctxt.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ib_id);
+
+                let enabled = bind_attributes(ctxt, bindings);
+
+                // disabling the attributes of the previous draw that are not used anymore
+                for &location in ctxt.state.enabled_vertex_attrib_arrays.iter() {
+                    if !enabled.contains(&location) {
+                        ctxt.gl.DisableVertexAttribArray(location);
+                    }
+                }
+
+                ctxt.state.enabled_vertex_attrib_arrays = enabled;
+                ctxt.state.statistics.state_changes += 1;
+            },
+        }
+    }
+}
+
 impl Drop for VertexArrayObject {
     fn drop(&mut self) {
         let id = self.id.clone();
@@ -108,6 +292,7 @@ impl Drop for VertexArrayObject {
                 if ctxt.state.vertex_array == id {
                     ctxt.gl.BindVertexArray(0);
                     ctxt.state.vertex_array = 0;
+                    ctxt.state.statistics.state_changes += 1;
                 }
 
                 // deleting
@@ -123,31 +308,42 @@ impl GlObject for VertexArrayObject {
     }
 }
 
-pub fn get_vertex_array_object<I>(display: &Arc<DisplayImpl>, vertex_buffer: VerticesSource,
+/// Returns how to bind the attributes of a draw.
+///
+/// The attributes of the vertex buffers must have been checked against the program with
+/// `check_vertex_format`.
+pub fn get_vertex_array_object<I>(display: &Arc<DisplayImpl>, vertex_buffer: &VerticesSource,
                                   indices: &IndicesSource<I>, program: &Program)
-                                  -> gl::types::GLuint where I: ::index_buffer::Index
+                                  -> VertexAttributesBinding where I: ::index_buffer::Index
 {
     let ib_id = match indices {
         &IndicesSource::Buffer { .. } => 0,
         &IndicesSource::IndexBuffer { ref buffer, .. } => buffer.get_id()
     };
 
-    let vb_id = match vertex_buffer {
-        VerticesSource::VertexBuffer(vb) => vb.get_id(),
-    };
+    if !display.context.capabilities().vertex_array_objects {
+        let bindings = resolve_bindings(vertex_buffer, program);
+        return VertexAttributesBinding::Direct(bindings, ib_id);
+    }
+
+    let vb_keys = get_buffers(vertex_buffer).into_iter()
+        .map(|(buffer, _, format_id, offset, per_instance)| {
+            (buffer.get_id(), format_id, offset, per_instance)
+        })
+        .collect::<Vec<BufferKey>>();
 
-    let program_id = program.get_id();
+    let layout_id = ::program::get_attribute_layout(program);
+    let key = (vb_keys, ib_id, layout_id);
 
-    if let Some(value) = display.vertex_array_objects.lock().unwrap()
-                                .get(&(vb_id, ib_id, program_id)) {
-        return value.id;
+    if let Some(value) = display.vertex_array_objects.lock().unwrap().get(&key) {
+        return VertexAttributesBinding::Vao(value.id);
     }
 
     // we create the new VAO without the mutex locked
-    let new_vao = VertexArrayObject::new(display.clone(), vertex_buffer.clone(), ib_id, program);
+    let new_vao = VertexArrayObject::new(display.clone(), vertex_buffer, ib_id, program);
     let new_vao_id = new_vao.id;
-    display.vertex_array_objects.lock().unwrap().insert((vb_id, ib_id, program_id), new_vao);
-    new_vao_id
+    display.vertex_array_objects.lock().unwrap().insert(key, new_vao);
+    VertexAttributesBinding::Vao(new_vao_id)
 }
 
 fn vertex_binding_type_to_gl(ty: AttributeType) -> (gl::types::GLenum, gl::types::GLint) {
@@ -180,12 +376,33 @@ fn vertex_binding_type_to_gl(ty: AttributeType) -> (gl::types::GLenum, gl::types
         AttributeType::F32F32 => (gl::FLOAT, 2),
         AttributeType::F32F32F32 => (gl::FLOAT, 3),
         AttributeType::F32F32F32F32 => (gl::FLOAT, 4),
+        AttributeType::F64 => (gl::DOUBLE, 1),
+        AttributeType::F64F64 => (gl::DOUBLE, 2),
+        AttributeType::F64F64F64 => (gl::DOUBLE, 3),
+        AttributeType::F64F64F64F64 => (gl::DOUBLE, 4),
     }
 }
 
-fn vertex_type_matches(ty: AttributeType, gl_ty: gl::types::GLenum,
-                       gl_size: gl::types::GLint) -> bool
+pub fn vertex_type_matches(ty: AttributeType, normalize: bool, gl_ty: gl::types::GLenum,
+                           gl_size: gl::types::GLint) -> bool
 {
+    // normalized integers are converted to floats in the range `[0.0, 1.0]` or `[-1.0, 1.0]`
+    if normalize {
+        let (data_type, count) = vertex_binding_type_to_gl(ty);
+
+        return match data_type {
+            gl::BYTE | gl::UNSIGNED_BYTE | gl::SHORT | gl::UNSIGNED_SHORT |
+            gl::INT | gl::UNSIGNED_INT => match (count, gl_ty, gl_size) {
+                (1, gl::FLOAT, 1) => true,
+                (2, gl::FLOAT, 2) | (2, gl::FLOAT_VEC2, 1) => true,
+                (3, gl::FLOAT, 3) | (3, gl::FLOAT_VEC3, 1) => true,
+                (4, gl::FLOAT, 4) | (4, gl::FLOAT_VEC4, 1) | (4, gl::FLOAT_VEC2, 2) => true,
+                _ => false,
+            },
+            _ => false,
+        };
+    }
+
     match (ty, gl_ty, gl_size) {
         (AttributeType::I8, gl::BYTE, 1) => true,
         (AttributeType::I8I8, gl::BYTE, 2) => true,
@@ -227,6 +444,38 @@ fn vertex_type_matches(ty: AttributeType, gl_ty: gl::types::GLenum,
         (AttributeType::F32F32F32F32, gl::FLOAT, 4) => true,
         (AttributeType::F32F32F32F32, gl::FLOAT_VEC4, 1) => true,
         (AttributeType::F32F32F32F32, gl::FLOAT_VEC2, 2) => true,
+        (AttributeType::F64, gl::DOUBLE, 1) => true,
+        (AttributeType::F64F64, gl::DOUBLE, 2) => true,
+        (AttributeType::F64F64, gl::DOUBLE_VEC2, 1) => true,
+        (AttributeType::F64F64F64, gl::DOUBLE, 3) => true,
+        (AttributeType::F64F64F64, gl::DOUBLE_VEC3, 1) => true,
+        (AttributeType::F64F64F64F64, gl::DOUBLE, 4) => true,
+        (AttributeType::F64F64F64F64, gl::DOUBLE_VEC4, 1) => true,
+        (AttributeType::F64F64F64F64, gl::DOUBLE_VEC2, 2) => true,
+
+        // 8 and 16 bits integers are extended to 32 bits by `glVertexAttribIPointer`, and
+        // unsigned ones fit in signed integers
+        (AttributeType::I8, gl::INT, 1) | (AttributeType::I16, gl::INT, 1) |
+        (AttributeType::U8, gl::INT, 1) | (AttributeType::U16, gl::INT, 1) => true,
+        (AttributeType::I8I8, gl::INT_VEC2, 1) | (AttributeType::I16I16, gl::INT_VEC2, 1) |
+        (AttributeType::U8U8, gl::INT_VEC2, 1) | (AttributeType::U16U16, gl::INT_VEC2, 1) => true,
+        (AttributeType::I8I8I8, gl::INT_VEC3, 1) |
+        (AttributeType::I16I16I16, gl::INT_VEC3, 1) |
+        (AttributeType::U8U8U8, gl::INT_VEC3, 1) |
+        (AttributeType::U16U16U16, gl::INT_VEC3, 1) => true,
+        (AttributeType::I8I8I8I8, gl::INT_VEC4, 1) |
+        (AttributeType::I16I16I16I16, gl::INT_VEC4, 1) |
+        (AttributeType::U8U8U8U8, gl::INT_VEC4, 1) |
+        (AttributeType::U16U16U16U16, gl::INT_VEC4, 1) => true,
+        (AttributeType::U8, gl::UNSIGNED_INT, 1) |
+        (AttributeType::U16, gl::UNSIGNED_INT, 1) => true,
+        (AttributeType::U8U8, gl::UNSIGNED_INT_VEC2, 1) |
+        (AttributeType::U16U16, gl::UNSIGNED_INT_VEC2, 1) => true,
+        (AttributeType::U8U8U8, gl::UNSIGNED_INT_VEC3, 1) |
+        (AttributeType::U16U16U16, gl::UNSIGNED_INT_VEC3, 1) => true,
+        (AttributeType::U8U8U8U8, gl::UNSIGNED_INT_VEC4, 1) |
+        (AttributeType::U16U16U16U16, gl::UNSIGNED_INT_VEC4, 1) => true,
+
         _ => false,
     }
 }
@@ -56,22 +56,227 @@ let data = vec![
 let vertex_buffer = glium::vertex_buffer::VertexBuffer::new(&display, data);
 ```
 
+## Attribute types
+
+The type of each field determines how the attribute is passed to the vertex shader:
+
+ - `f32` fields are read by `float` and `vec*` attributes.
+ - Integer fields are read by `int` and `ivec*` attributes if they are signed, or by `uint`
+   and `uvec*` attributes. Unsigned integers of 8 and 16 bits can also be read by `int` and
+   `ivec*` attributes, for example bone indices stored as `[u8, ..4]` and declared as
+   `ivec4` in the shader. The values are not normalized or converted to floats, unless the
+   field is marked as normalized (see below).
+ - `f64` fields are read by `double` and `dvec*` attributes. This requires OpenGL 4.1 or
+   `GL_ARB_vertex_attrib_64bit`.
+
+Integer fields can be marked with `#[normalized]` when using `#[vertex_format]`. They are then
+read by `float` and `vec*` attributes, and their values are mapped to the range `[0.0, 1.0]`
+for unsigned integers, or `[-1.0, 1.0]` for signed integers. For example colors can be stored
+as `[u8, ..4]` instead of `[f32, ..4]`, which divides their size by four:
+
+```ignore
+#[vertex_format]
+#[deriving(Copy)]
+struct Vertex {
+    position: [f32, ..3],
+    #[normalized]
+    color: [u8, ..4],       // read as `vec4` in the shader
+}
+```
+
+## Adapting vertex sources
+
+The methods of `IntoVerticesSource` let you change the attributes that are passed to the
+program without duplicating the data. For example, if a program expects an attribute named
+`pos` and your vertex buffer has a field named `position`, and you want to read the color of
+each instance from a second buffer:
+
+```ignore
+use glium::vertex_buffer::IntoVerticesSource;
+
+let source = (&vertex_buffer).rename_attribute("position", "pos")
+                             .filter_attributes(&["pos"])
+                             .with_per_instance(&colors_buffer);
+target.draw(source, &indices, &program, &uniforms, &Default::default());
+```
+
+Per-instance buffers require OpenGL 3.3, OpenGL ES 3.0 or the `GL_ARB_instanced_arrays`
+extension.
+
 */
 use buffer::{mod, Buffer};
 use gl;
+use vertex_array_object;
 use GlObject;
 
 /// Describes the source to use for the vertices when drawing.
-#[deriving(Clone, Copy)]
+#[deriving(Clone)]
 pub enum VerticesSource<'a> {
     /// A buffer uploaded in the video memory.
-    VertexBuffer(&'a VertexBufferAny),
+    ///
+    /// The second and third parameters are the offset and the number of vertices to use.
+    VertexBuffer(&'a VertexBufferAny, uint, uint),
+
+    /// Buffers whose attributes are passed to the program at the same time. Built with the
+    /// methods of `IntoVerticesSource`.
+    ///
+    /// The second and third parameters are the offset and the number of vertices to use in
+    /// the buffers that are not per-instance.
+    Bindings(Vec<VertexBufferBinding<'a>>, uint, uint),
+}
+
+impl<'a> VerticesSource<'a> {
+    /// Returns the offset and the number of vertices to use.
+    pub fn get_range(&self) -> (uint, uint) {
+        match self {
+            &VerticesSource::VertexBuffer(_, offset, length) => (offset, length),
+            &VerticesSource::Bindings(_, offset, length) => (offset, length),
+        }
+    }
+
+    /// Returns the attributes of all the buffers of this source, with the names that are
+    /// passed to the program.
+    pub fn get_format(&self) -> VertexFormat {
+        match self {
+            &VerticesSource::VertexBuffer(buffer, _, _) => buffer.get_bindings().clone(),
+            &VerticesSource::Bindings(ref bindings, _, _) => {
+                bindings.iter().flat_map(|b| b.format.iter().map(|a| a.clone())).collect()
+            },
+        }
+    }
+
+    /// Returns true if one of the buffers is per-instance.
+    pub fn has_per_instance(&self) -> bool {
+        match self {
+            &VerticesSource::VertexBuffer(_, _, _) => false,
+            &VerticesSource::Bindings(ref bindings, _, _) => {
+                bindings.iter().any(|b| b.per_instance)
+            },
+        }
+    }
+
+    /// Turns this source into a list of buffers, the offset and the number of vertices.
+    pub fn into_bindings(self) -> (Vec<VertexBufferBinding<'a>>, uint, uint) {
+        match self {
+            VerticesSource::VertexBuffer(buffer, offset, length) => {
+                let binding = VertexBufferBinding {
+                    buffer: buffer,
+                    format: buffer.get_bindings().clone(),
+                    format_id: buffer.format_id,
+                    offset: 0,
+                    length: buffer.len(),
+                    per_instance: false,
+                };
+
+                (vec![binding], offset, length)
+            },
+            VerticesSource::Bindings(bindings, offset, length) => (bindings, offset, length),
+        }
+    }
+}
+
+/// A buffer and the attributes that are read from it. Part of a `VerticesSource`.
+#[deriving(Clone)]
+pub struct VertexBufferBinding<'a> {
+    /// The buffer that contains the data.
+    pub buffer: &'a VertexBufferAny,
+
+    /// The attributes to read from the buffer, with the names that are passed to the program.
+    pub format: VertexFormat,
+
+    /// Identifies `format` in the keys of the VAOs. Updated whenever `format` is modified.
+    #[doc(hidden)]      // TODO: shouldn't be pub
+    pub format_id: u32,
+
+    /// Number of elements to skip at the start of the buffer.
+    ///
+    /// The offset of the per-vertex buffers is the one of the `VerticesSource` instead.
+    pub offset: uint,
+
+    /// Number of elements that can be read after `offset`. For a per-instance buffer, this is
+    /// the number of instances that it provides.
+    pub length: uint,
+
+    /// If true, the attributes advance once per instance instead of once per vertex.
+    pub per_instance: bool,
 }
 
 /// Objects that can be used as vertex sources.
 pub trait IntoVerticesSource<'a> {
     /// Builds the `VerticesSource`.
     fn into_vertices_source(self) -> VerticesSource<'a>;
+
+    /// Passes the attribute named `from` to the program under the name `to`.
+    fn rename_attribute(self, from: &str, to: &str) -> VerticesSource<'a> {
+        let (mut bindings, offset, length) = self.into_vertices_source().into_bindings();
+
+        for binding in bindings.iter_mut() {
+            let mut renamed = false;
+
+            for attribute in binding.format.iter_mut() {
+                if attribute.0.as_slice() == from {
+                    attribute.0 = to.to_string();
+                    renamed = true;
+                }
+            }
+
+            if renamed {
+                binding.format_id = vertex_array_object::get_format_id(
+                    &**binding.buffer.buffer.get_display(), &binding.format);
+            }
+        }
+
+        VerticesSource::Bindings(bindings, offset, length)
+    }
+
+    /// Only passes the attributes whose name is in `names` to the program.
+    ///
+    /// The names are the ones after renaming.
+    fn filter_attributes(self, names: &[&str]) -> VerticesSource<'a> {
+        let (mut bindings, offset, length) = self.into_vertices_source().into_bindings();
+
+        for binding in bindings.iter_mut() {
+            let len = binding.format.len();
+
+            binding.format.retain(|&: attribute| {
+                names.iter().any(|name| *name == attribute.0.as_slice())
+            });
+
+            if binding.format.len() != len {
+                binding.format_id = vertex_array_object::get_format_id(
+                    &**binding.buffer.buffer.get_display(), &binding.format);
+            }
+        }
+
+        VerticesSource::Bindings(bindings, offset, length)
+    }
+
+    /// Adds the attributes of `instances` to this source. Each element of `instances` is
+    /// used for a whole instance instead of a single vertex.
+    ///
+    /// One instance is drawn for each element of `instances`. If there are several
+    /// per-instance sources, the number of instances is the length of the smallest one.
+    ///
+    /// The attributes of both sources must have different names.
+    fn with_per_instance<I>(self, instances: I) -> VerticesSource<'a>
+                            where I: IntoVerticesSource<'a>
+    {
+        let (mut bindings, offset, length) = self.into_vertices_source().into_bindings();
+        let (instances, instances_offset, instances_length) =
+            instances.into_vertices_source().into_bindings();
+
+        for mut binding in instances.into_iter() {
+            if !binding.per_instance {
+                binding.offset += instances_offset;
+                binding.length = instances_length;
+                binding.per_instance = true;
+            }
+
+            bindings.push(binding);
+        }
+
+        VerticesSource::Bindings(bindings, offset, length)
+    }
 }
 
 impl<'a> IntoVerticesSource<'a> for VerticesSource<'a> {
@@ -116,6 +321,8 @@ impl<T: Vertex + 'static + Send> VertexBuffer<T> {
     pub fn new(display: &super::Display, data: Vec<T>) -> VertexBuffer<T> {
         let bindings = Vertex::build_bindings(None::<T>);
 
+        let format_id = vertex_array_object::get_format_id(&display.context, &bindings);
+
         let buffer = Buffer::new::<buffer::ArrayBuffer, T>(display, data, gl::STATIC_DRAW);
         let elements_size = buffer.get_elements_size();
 
@@ -123,6 +330,7 @@ impl<T: Vertex + 'static + Send> VertexBuffer<T> {
             buffer: VertexBufferAny {
                 buffer: buffer,
                 bindings: bindings,
+                format_id: format_id,
                 elements_size: elements_size,
             }
         }
@@ -135,6 +343,8 @@ impl<T: Vertex + 'static + Send> VertexBuffer<T> {
     pub fn new_dynamic(display: &super::Display, data: Vec<T>) -> VertexBuffer<T> {
         let bindings = Vertex::build_bindings(None::<T>);
 
+        let format_id = vertex_array_object::get_format_id(&display.context, &bindings);
+
         let buffer = Buffer::new::<buffer::ArrayBuffer, T>(display, data, gl::DYNAMIC_DRAW);
         let elements_size = buffer.get_elements_size();
 
@@ -142,6 +352,83 @@ impl<T: Vertex + 'static + Send> VertexBuffer<T> {
             buffer: VertexBufferAny {
                 buffer: buffer,
                 bindings: bindings,
+                format_id: format_id,
+                elements_size: elements_size,
+            }
+        }
+    }
+
+    /// Builds a new vertex buffer whose content is permanently mapped in memory.
+    ///
+    /// Calling `map` on this buffer doesn't need any synchronization with the backend, and
+    /// the modifications are immediately visible to the GPU. This is useful for geometry that
+    /// is streamed every frame. However it is your responsability not to modify the data
+    /// that is being used by a draw command that is still executing.
+    ///
+    /// Returns `None` if the backend doesn't support persistent mapping (it requires
+    /// OpenGL 4.4 or the `GL_ARB_buffer_storage` extension).
+    pub fn new_persistent(display: &super::Display, data: Vec<T>) -> Option<VertexBuffer<T>> {
+        let bindings = Vertex::build_bindings(None::<T>);
+        let format_id = vertex_array_object::get_format_id(&display.context, &bindings);
+
+        let buffer = match Buffer::new_persistent::<buffer::ArrayBuffer, T>(display, data) {
+            Some(b) => b,
+            None => return None
+        };
+
+        let elements_size = buffer.get_elements_size();
+
+        Some(VertexBuffer {
+            buffer: VertexBufferAny {
+                buffer: buffer,
+                bindings: bindings,
+                format_id: format_id,
+                elements_size: elements_size,
+            }
+        })
+    }
+
+    /// Wraps a buffer that has been created outside of glium, for example by a library that
+    /// uses the same OpenGL context. The buffer must contain `count` vertices of type `T`.
+    ///
+    /// Glium takes the ownership of the buffer, which is deleted when the returned object is
+    /// destroyed.
+    ///
+    /// # Safety
+    ///
+    /// `id` must be the name of a buffer that belongs to the context of the display or to a
+    /// context that shares its objects, and whose size is at least `count` vertices.
+    pub unsafe fn from_id(display: &super::Display, id: gl::types::GLuint, count: uint)
+                          -> VertexBuffer<T>
+    {
+        VertexBuffer::from_id_impl(display, id, count, true)
+    }
+
+    /// Same as `from_id`, except that glium doesn't take the ownership of the buffer. The
+    /// buffer is not deleted when the returned object is destroyed.
+    ///
+    /// # Safety
+    ///
+    /// Same as `from_id`. In addition, the buffer must not be deleted while the returned
+    /// object is alive.
+    pub unsafe fn from_id_unowned(display: &super::Display, id: gl::types::GLuint, count: uint)
+                                  -> VertexBuffer<T>
+    {
+        VertexBuffer::from_id_impl(display, id, count, false)
+    }
+
+    unsafe fn from_id_impl(display: &super::Display, id: gl::types::GLuint, count: uint,
+                           owned: bool) -> VertexBuffer<T>
+    {
+        let bindings = Vertex::build_bindings(None::<T>);
+        let format_id = vertex_array_object::get_format_id(&display.context, &bindings);
+        let elements_size = ::std::mem::size_of::<T>();
+
+        VertexBuffer {
+            buffer: VertexBufferAny {
+                buffer: Buffer::from_id(display, id, elements_size, count, owned),
+                bindings: bindings,
+                format_id: format_id,
                 elements_size: elements_size,
             }
         }
@@ -162,10 +449,10 @@ impl<T: Send + Copy> VertexBuffer<T> {
     /// # fn main() {
     /// let bindings = vec![(
     ///         "position".to_string(), 0,
-    ///         glium::vertex_buffer::AttributeType::F32F32,
+    ///         glium::vertex_buffer::AttributeType::F32F32, false,
     ///     ), (
     ///         "color".to_string(), 2 * ::std::mem::size_of::<f32>(),
-    ///         glium::vertex_buffer::AttributeType::F32,
+    ///         glium::vertex_buffer::AttributeType::F32, false,
     ///     ),
     /// ];
     ///
@@ -185,10 +472,13 @@ impl<T: Send + Copy> VertexBuffer<T> {
     pub unsafe fn new_raw(display: &super::Display, data: Vec<T>,
                           bindings: VertexFormat, elements_size: uint) -> VertexBuffer<T>
     {
+        let format_id = vertex_array_object::get_format_id(&display.context, &bindings);
+
         VertexBuffer {
             buffer: VertexBufferAny {
                 buffer: Buffer::new::<buffer::ArrayBuffer, T>(display, data, gl::STATIC_DRAW),
                 bindings: bindings,
+                format_id: format_id,
                 elements_size: elements_size,
             }
         }
@@ -197,7 +487,8 @@ impl<T: Send + Copy> VertexBuffer<T> {
     /// Maps the buffer to allow write access to it.
     ///
     /// **Warning**: using this function can slow things down a lot because the function
-    /// waits for all the previous commands to be executed before returning.
+    /// waits for all the previous commands to be executed before returning. This is not the
+    /// case for buffers created with `new_persistent`.
     ///
     /// # Panic
     ///
@@ -258,11 +549,41 @@ impl<T: Send + Copy> VertexBuffer<T> {
 }
 
 impl<T> VertexBuffer<T> {
+    /// Returns true if the buffer was created with `new_persistent`.
+    pub fn is_persistent(&self) -> bool {
+        self.buffer.buffer.is_persistent()
+    }
+
     /// Returns the number of bytes between two consecutive elements in the buffer.
     pub fn get_elements_size(&self) -> uint {
         self.buffer.elements_size
     }
 
+    /// Returns the number of vertices in the buffer.
+    pub fn len(&self) -> uint {
+        self.buffer.len()
+    }
+
+    /// Returns a slice of this vertex buffer that can be used as a source of vertices.
+    ///
+    /// `offset` and `length` are in number of vertices. When drawing, the index `0` will
+    /// correspond to the vertex at `offset`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the slice is out of range of the buffer.
+    pub fn slice<'a>(&'a self, offset: uint, length: uint) -> VertexBufferSlice<'a, T> {
+        if offset > self.len() || offset + length > self.len() {
+            panic!("Trying to slice out of range of the vertex buffer");
+        }
+
+        VertexBufferSlice {
+            buffer: self,
+            offset: offset,
+            length: length,
+        }
+    }
+
     /// Returns the associated `VertexFormat`.
     pub fn get_bindings(&self) -> &VertexFormat {
         &self.buffer.bindings
@@ -282,7 +603,24 @@ impl<T> GlObject for VertexBuffer<T> {
 
 impl<'a, T> IntoVerticesSource<'a> for &'a VertexBuffer<T> {
     fn into_vertices_source(self) -> VerticesSource<'a> {
-        VerticesSource::VertexBuffer(&self.buffer)
+        (&self.buffer).into_vertices_source()
+    }
+}
+
+/// A slice of a `VertexBuffer`.
+///
+/// Obtained with `vertex_buffer.slice(offset, length)`.
+pub struct VertexBufferSlice<'a, T: 'a> {
+    buffer: &'a VertexBuffer<T>,
+    offset: uint,
+    length: uint,
+}
+
+impl<'a, T> Copy for VertexBufferSlice<'a, T> {}
+
+impl<'a, T> IntoVerticesSource<'a> for VertexBufferSlice<'a, T> {
+    fn into_vertices_source(self) -> VerticesSource<'a> {
+        VerticesSource::VertexBuffer(&self.buffer.buffer, self.offset, self.length)
     }
 }
 
@@ -297,6 +635,7 @@ impl<'a, T> IntoVerticesSource<'a> for &'a VertexBuffer<T> {
 pub struct VertexBufferAny {
     buffer: Buffer,
     bindings: VertexFormat,
+    format_id: u32,
     elements_size: uint,
 }
 
@@ -311,6 +650,32 @@ impl VertexBufferAny {
         &self.bindings
     }
 
+    /// Returns the number of vertices in the buffer.
+    pub fn len(&self) -> uint {
+        self.buffer.get_elements_count()
+    }
+
+    /// Returns the id of the format of the buffer in the keys of the VAOs.
+    #[doc(hidden)]      // TODO: shouldn't be pub
+    pub fn get_format_id(&self) -> u32 {
+        self.format_id
+    }
+
+    /// Returns a slice of this vertex buffer that can be used as a source of vertices.
+    ///
+    /// See `VertexBuffer::slice`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the slice is out of range of the buffer.
+    pub fn slice<'a>(&'a self, offset: uint, length: uint) -> VerticesSource<'a> {
+        if offset > self.len() || offset + length > self.len() {
+            panic!("Trying to slice out of range of the vertex buffer");
+        }
+
+        VerticesSource::VertexBuffer(self, offset, length)
+    }
+
     /// Turns the vertex buffer into a `VertexBuffer` without checking the type.
     pub unsafe fn into_vertex_buffer<T>(self) -> VertexBuffer<T> {
         VertexBuffer {
@@ -319,18 +684,6 @@ impl VertexBufferAny {
     }
 }
 
-impl Drop for VertexBufferAny {
-    fn drop(&mut self) {
-        // removing VAOs which contain this vertex buffer
-        let mut vaos = self.buffer.get_display().vertex_array_objects.lock().unwrap();
-        let to_delete = vaos.keys().filter(|&&(v, _, _)| v == self.buffer.get_id())
-            .map(|k| k.clone()).collect::<Vec<_>>();
-        for k in to_delete.into_iter() {
-            vaos.remove(&k);
-        }
-    }
-}
-
 impl GlObject for VertexBufferAny {
     fn get_id(&self) -> gl::types::GLuint {
         self.buffer.get_id()
@@ -339,7 +692,7 @@ impl GlObject for VertexBufferAny {
 
 impl<'a> IntoVerticesSource<'a> for &'a VertexBufferAny {
     fn into_vertices_source(self) -> VerticesSource<'a> {
-        VerticesSource::VertexBuffer(self)
+        VerticesSource::VertexBuffer(self, 0, self.len())
     }
 }
 
@@ -363,7 +716,7 @@ impl<'a, T> DerefMut<[T]> for Mapping<'a, T> {
 }
 
 #[allow(missing_docs)]
-#[deriving(Copy, Clone, Show, PartialEq, Eq)]
+#[deriving(Copy, Clone, Show, PartialEq, Eq, Hash)]
 pub enum AttributeType {
     I8,
     I8I8,
@@ -393,13 +746,18 @@ pub enum AttributeType {
     F32F32,
     F32F32F32,
     F32F32F32F32,
+    F64,
+    F64F64,
+    F64F64F64,
+    F64F64F64F64,
 }
 
 /// Describes the layout of each vertex in a vertex buffer.
 ///
 /// The first element is the name of the binding, the second element is the offset
-/// from the start of each vertex to this element, and the third element is the type.
-pub type VertexFormat = Vec<(String, uint, AttributeType)>;
+/// from the start of each vertex to this element, the third element is the type, and the
+/// fourth element indicates whether integers should be normalized.
+pub type VertexFormat = Vec<(String, uint, AttributeType, bool)>;
 
 /// Trait for structures that represent a vertex.
 ///
@@ -710,3 +1068,45 @@ unsafe impl Attribute for [f32, ..4] {
         AttributeType::F32F32F32F32
     }
 }
+
+unsafe impl Attribute for f64 {
+    fn get_type(_: Option<f64>) -> AttributeType {
+        AttributeType::F64
+    }
+}
+
+unsafe impl Attribute for (f64, f64) {
+    fn get_type(_: Option<(f64, f64)>) -> AttributeType {
+        AttributeType::F64F64
+    }
+}
+
+unsafe impl Attribute for [f64, ..2] {
+    fn get_type(_: Option<[f64, ..2]>) -> AttributeType {
+        AttributeType::F64F64
+    }
+}
+
+unsafe impl Attribute for (f64, f64, f64) {
+    fn get_type(_: Option<(f64, f64, f64)>) -> AttributeType {
+        AttributeType::F64F64F64
+    }
+}
+
+unsafe impl Attribute for [f64, ..3] {
+    fn get_type(_: Option<[f64, ..3]>) -> AttributeType {
+        AttributeType::F64F64F64
+    }
+}
+
+unsafe impl Attribute for (f64, f64, f64, f64) {
+    fn get_type(_: Option<(f64, f64, f64, f64)>) -> AttributeType {
+        AttributeType::F64F64F64F64
+    }
+}
+
+unsafe impl Attribute for [f64, ..4] {
+    fn get_type(_: Option<[f64, ..4]>) -> AttributeType {
+        AttributeType::F64F64F64F64
+    }
+}
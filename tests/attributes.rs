@@ -52,14 +52,13 @@ fn attribute_types_matching() {
     // drawing a frame
     let mut target = display.draw();
     target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
-                &std::default::Default::default());
-    target.finish();
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
     
     display.assert_no_error();
 }
 
 #[test]
-#[should_fail(expected = "The program attribute `field1` does not match the vertex format")]
 fn attribute_types_mismatch() {
     let display = support::build_display();
 
@@ -97,15 +96,21 @@ fn attribute_types_mismatch() {
 
     // drawing a frame
     let mut target = display.draw();
-    target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
-                &std::default::Default::default());
-    target.finish();
+    match target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+                      &std::default::Default::default())
+    {
+        Err(glium::DrawError::VertexFormatMismatch(
+            glium::VertexFormatMismatch::TypeMismatch { ref name, .. })) => {
+            assert_eq!(name.as_slice(), "field1");
+        },
+        _ => panic!()
+    };
+    target.finish().unwrap();
     
     display.assert_no_error();
 }
 
 #[test]
-#[should_fail(expected = "The program attribute `field2` is missing in the vertex bindings")]
 fn missing_attribute() {
     let display = support::build_display();
 
@@ -143,9 +148,493 @@ fn missing_attribute() {
 
     // drawing a frame
     let mut target = display.draw();
-    target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
-                &std::default::Default::default());
-    target.finish();
+    match target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+                      &std::default::Default::default())
+    {
+        Err(glium::DrawError::VertexFormatMismatch(
+            glium::VertexFormatMismatch::MissingAttribute(ref name))) => {
+            assert_eq!(name.as_slice(), "field2");
+        },
+        _ => panic!()
+    };
+    target.finish().unwrap();
     
     display.assert_no_error();
 }
+
+#[test]
+fn check_vertex_format() {
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        field1: [f32, ..4],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, Vec::<Vertex>::new());
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 field1;
+
+            void main() {
+                gl_Position = vec4(field1, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+            void main() {
+                gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None)
+        .unwrap();
+
+    match program.check_vertex_format(vertex_buffer.get_bindings()) {
+        Err(glium::VertexFormatMismatch::TypeMismatch { ref name, format_type, .. }) => {
+            assert_eq!(name.as_slice(), "field1");
+            assert_eq!(format_type, glium::vertex_buffer::AttributeType::F32F32F32F32);
+        },
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn attribute_u8_to_ivec4() {
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+        bones: [u8, ..4],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [0.0, 0.0], bones: [0, 1, 2, 255] }
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::PointsList(vec![0u16]));
+
+    // integer attributes require GLSL 1.30
+    let program = match glium::Program::from_source(&display,
+        "
+            #version 130
+
+            in vec2 position;
+            in ivec4 bones;
+
+            void main() {
+                gl_Position = vec4(position + vec2(bones.w - 255), 0.0, 1.0);
+            }
+        ",
+        "
+            #version 130
+            void main() {
+                gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None)
+    {
+        Ok(p) => p,
+        Err(_) => return
+    };
+
+    let mut target = display.draw();
+    target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn attribute_f64_to_dvec2() {
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f64, ..2],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [0.0, 0.0] }
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::PointsList(vec![0u16]));
+
+    // double attributes require OpenGL 4.1
+    let program = match glium::Program::from_source(&display,
+        "
+            #version 410
+
+            in dvec2 position;
+
+            void main() {
+                gl_Position = vec4(vec2(position), 0.0, 1.0);
+            }
+        ",
+        "
+            #version 410
+            out vec4 color;
+            void main() {
+                color = vec4(0.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None)
+    {
+        Ok(p) => p,
+        Err(_) => return
+    };
+
+    let mut target = display.draw();
+    target.draw(&vertex_buffer, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn attribute_normalized_u8_to_vec4() {
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+        #[normalized]
+        color: [u8, ..4],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [-1.0,  1.0], color: [255, 0, 0, 255] },
+            Vertex { position: [ 1.0,  1.0], color: [255, 0, 0, 255] },
+            Vertex { position: [-1.0, -1.0], color: [255, 0, 0, 255] },
+            Vertex { position: [ 1.0, -1.0], color: [255, 0, 0, 255] },
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 3]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute vec4 color;
+            varying vec4 v_color;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+                v_color = color;
+            }
+        ",
+        "
+            #version 110
+
+            varying vec4 v_color;
+
+            void main() {
+                gl_FragColor = v_color;
+            }
+        ",
+        None).unwrap();
+
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+    texture.as_surface().draw(&vertex_buffer, &index_buffer, &program,
+                              &glium::uniforms::EmptyUniforms,
+                              &std::default::Default::default()).unwrap();
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    for row in data.iter() {
+        for pixel in row.iter() {
+            assert_eq!(pixel, &(1.0, 0.0, 0.0, 1.0));
+        }
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn renamed_attribute() {
+    use glium::vertex_buffer::IntoVerticesSource;
+
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        field1: [f32, ..2],
+        field2: [f32, ..2],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { field1: [0.0, 0.0], field2: [0.0, 0.0] }
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::PointsList(vec![0u16]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+            void main() {
+                gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None)
+        .unwrap();
+
+    let source = (&vertex_buffer).rename_attribute("field1", "position")
+                                 .filter_attributes(&["position"]);
+
+    let mut target = display.draw();
+    target.draw(source, &index_buffer, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn per_instance_attribute() {
+    use glium::vertex_buffer::IntoVerticesSource;
+
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+    }
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Instance {
+        color: [f32, ..3],
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+            Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+        ]);
+    let instances = glium::VertexBuffer::new(&display, vec![Instance { color: [1.0, 0.0, 0.0] }]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 3]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute vec3 color;
+            varying vec3 v_color;
+
+            void main() {
+                v_color = color;
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            varying vec3 v_color;
+
+            void main() {
+                gl_FragColor = vec4(v_color, 1.0);
+            }
+        ",
+        None)
+        .unwrap();
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    match target.draw((&vertex_buffer).with_per_instance(&instances), &index_buffer, &program,
+                      &glium::uniforms::EmptyUniforms, &std::default::Default::default())
+    {
+        Ok(_) => (),
+        Err(glium::DrawError::InstancingNotSupported) => {
+            target.finish().unwrap();
+            return;
+        },
+        Err(_) => panic!()
+    };
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn per_instance_attribute_multiple_instances() {
+    use glium::vertex_buffer::IntoVerticesSource;
+
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+    }
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Instance {
+        offset: f32,
+        color: [f32, ..3],
+    }
+
+    // a rectangle that covers the left half of the surface
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [-1.0,  1.0] }, Vertex { position: [0.0,  1.0] },
+            Vertex { position: [-1.0, -1.0] }, Vertex { position: [0.0, -1.0] },
+        ]);
+    let instances = glium::VertexBuffer::new(&display, vec![
+            Instance { offset: 0.0, color: [1.0, 0.0, 0.0] },
+            Instance { offset: 1.0, color: [0.0, 1.0, 0.0] },
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 3]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute float offset;
+            attribute vec3 color;
+            varying vec3 v_color;
+
+            void main() {
+                v_color = color;
+                gl_Position = vec4(position.x + offset, position.y, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            varying vec3 v_color;
+
+            void main() {
+                gl_FragColor = vec4(v_color, 1.0);
+            }
+        ",
+        None)
+        .unwrap();
+
+    display.set_draw_log(true);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    match target.draw((&vertex_buffer).with_per_instance(&instances), &index_buffer, &program,
+                      &glium::uniforms::EmptyUniforms, &std::default::Default::default())
+    {
+        Ok(_) => (),
+        Err(glium::DrawError::InstancingNotSupported) => {
+            target.finish().unwrap();
+            return;
+        },
+        Err(_) => panic!()
+    };
+    target.finish().unwrap();
+
+    // two triangles for each instance
+    assert_eq!(display.get_draw_log()[0].primitives_count, Some(4));
+
+    // the first instance covers the left half and the second one the right half
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(0, 255, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn per_instance_attribute_slice() {
+    use glium::vertex_buffer::IntoVerticesSource;
+
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+    }
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Instance {
+        offset: f32,
+    }
+
+    let vertex_buffer = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [-1.0,  1.0] }, Vertex { position: [0.0,  1.0] },
+            Vertex { position: [-1.0, -1.0] }, Vertex { position: [0.0, -1.0] },
+        ]);
+    let instances = glium::VertexBuffer::new(&display, vec![
+            Instance { offset: 0.0 }, Instance { offset: 1.0 }, Instance { offset: 0.5 },
+            Instance { offset: 0.25 },
+        ]);
+    let index_buffer = glium::IndexBuffer::new(&display,
+                            glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 3]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute float offset;
+
+            void main() {
+                gl_Position = vec4(position.x + offset, position.y, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
+            }
+        ",
+        None)
+        .unwrap();
+
+    display.set_draw_log(true);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    match target.draw((&vertex_buffer).with_per_instance(instances.slice(1, 2)), &index_buffer,
+                      &program, &glium::uniforms::EmptyUniforms,
+                      &std::default::Default::default())
+    {
+        Ok(_) => (),
+        Err(glium::DrawError::InstancingNotSupported) => {
+            target.finish().unwrap();
+            return;
+        },
+        Err(_) => panic!()
+    };
+    target.finish().unwrap();
+
+    // only the two instances of the slice are drawn
+    assert_eq!(display.get_draw_log()[0].primitives_count, Some(4));
+
+    display.assert_no_error();
+}
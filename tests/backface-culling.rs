@@ -61,8 +61,8 @@ fn cull_clockwise() {
         &glium::DrawParameters {
             backface_culling: glium::BackfaceCullingMode::CullClockWise,
             .. std::default::Default::default()
-        });
-    target.finish();
+        }).unwrap();
+    target.finish().unwrap();
 
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = display.read_front_buffer();
     assert_eq!(read_back[0][0], (0.0, 0.0, 0.0, 0.0));
@@ -121,8 +121,8 @@ fn cull_counterclockwise() {
         &glium::DrawParameters {
             backface_culling: glium::BackfaceCullingMode::CullCounterClockWise,
             .. std::default::Default::default()
-        });
-    target.finish();
+        }).unwrap();
+    target.finish().unwrap();
 
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = display.read_front_buffer();
     assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
@@ -180,8 +180,8 @@ fn cull_clockwise_trianglestrip() {
         &glium::DrawParameters {
             backface_culling: glium::BackfaceCullingMode::CullClockWise,
             .. std::default::Default::default()
-        });
-    target.finish();
+        }).unwrap();
+    target.finish().unwrap();
 
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = display.read_front_buffer();
     assert_eq!(read_back[0][0], (0.0, 0.0, 0.0, 0.0));
@@ -239,8 +239,8 @@ fn cull_counterclockwise_trianglestrip() {
         &glium::DrawParameters {
             backface_culling: glium::BackfaceCullingMode::CullCounterClockWise,
             .. std::default::Default::default()
-        });
-    target.finish();
+        }).unwrap();
+    target.finish().unwrap();
 
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = display.read_front_buffer();
     assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
@@ -43,7 +43,7 @@ fn blit_texture_to_window() {
     texture.as_surface().blit_color(&src_rect, &target, &dest_rect,
                                     glium::uniforms::MagnifySamplerFilter::Nearest);
 
-    target.finish();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(f32, f32, f32)>> = display.read_front_buffer();
 
@@ -70,3 +70,82 @@ fn blit_texture_to_window() {
     
     display.assert_no_error();
 }
+
+#[test]
+fn blit_from_texture_to_texture() {
+    let display = support::build_display();
+
+    let source = support::build_unicolor_texture2d(&display, 0.0, 1.0, 0.0);
+    let target = glium::Texture2d::new_empty(&display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             4, 4);
+    target.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+
+    let src_rect = Rect { left: 0, bottom: 0, width: 2, height: 2 };
+    let dest_rect = Rect { left: 2, bottom: 2, width: 2, height: 2 };
+
+    target.as_surface().blit_from(&source.as_surface(), &src_rect, &dest_rect,
+                                  glium::BlitMask::color(),
+                                  glium::uniforms::MagnifySamplerFilter::Nearest);
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = target.read();
+
+    assert_eq!(data[0][0], (0.0, 0.0, 0.0, 0.0));
+    assert_eq!(data[1][1], (0.0, 0.0, 0.0, 0.0));
+    assert_eq!(data[2][2], (0.0, 1.0, 0.0, 1.0));
+    assert_eq!(data[3][3], (0.0, 1.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The blit rectangle is outside of the surface")]
+fn blit_from_outside_of_surface() {
+    let display = support::build_display();
+
+    let source = support::build_unicolor_texture2d(&display, 0.0, 1.0, 0.0);
+    let target = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+    let dimensions = source.as_surface().get_dimensions();
+
+    let src_rect = Rect { left: 1, bottom: 0, width: dimensions.0 as u32, height: 1 };
+    let dest_rect = Rect { left: 0, bottom: 0, width: 1, height: 1 };
+
+    target.as_surface().blit_from(&source.as_surface(), &src_rect, &dest_rect,
+                                  glium::BlitMask::color(),
+                                  glium::uniforms::MagnifySamplerFilter::Nearest);
+}
+
+#[test]
+#[should_fail(expected = "Depth and stencil values can only be blitted with the `Nearest` filter")]
+fn blit_from_depth_with_linear_filter() {
+    let display = support::build_display();
+
+    let source = support::build_unicolor_texture2d(&display, 0.0, 1.0, 0.0);
+    let target = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+
+    let rect = Rect { left: 0, bottom: 0, width: 1, height: 1 };
+    let mask = glium::BlitMask { color: false, depth: true, stencil: false };
+
+    target.as_surface().blit_from(&source.as_surface(), &rect, &rect, mask,
+                                  glium::uniforms::MagnifySamplerFilter::Linear);
+}
+
+#[test]
+fn snapshot_to_texture() {
+    use glium::Texture;
+
+    let display = support::build_display();
+
+    let source = support::build_unicolor_texture2d(&display, 0.0, 1.0, 0.0);
+    let snapshot = source.as_surface()
+                         .snapshot_to_texture(glium::texture::UncompressedFloatFormat::U8U8U8U8);
+
+    assert_eq!(snapshot.get_width(), source.get_width());
+    assert_eq!(snapshot.get_height(), source.get_height());
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = snapshot.read();
+    assert_eq!(data[0][0], (0.0, 1.0, 0.0, 1.0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(0.0, 1.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
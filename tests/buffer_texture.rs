@@ -0,0 +1,163 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use std::default::Default;
+use glium::Surface;
+
+mod support;
+
+#[test]
+fn buffer_texture_creation() {
+    let display = support::build_display();
+
+    let texture = glium::texture::BufferTexture::new(&display, vec![1.0f32, 2.0, 3.0, 4.0])
+                      .unwrap();
+    assert_eq!(texture.len(), 4);
+    assert_eq!(texture.as_ref().get_texture_type(), glium::texture::BufferTextureType::Float);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn buffer_texture_integral_type() {
+    let display = support::build_display();
+
+    let texture = glium::texture::BufferTexture::new(&display, vec![(1i32, 2), (3, 4)]).unwrap();
+    assert_eq!(texture.as_ref().get_texture_type(),
+               glium::texture::BufferTextureType::Integral);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn buffer_texture_rgb32() {
+    let display = support::build_display();
+
+    match glium::texture::BufferTexture::new(&display, vec![(1.0f32, 2.0, 3.0)]) {
+        Ok(texture) => assert_eq!(texture.len(), 1),
+        Err(glium::texture::BufferTextureCreationError::NotSupported) => return,
+        Err(glium::texture::BufferTextureCreationError::FormatNotSupported) => return,
+        Err(_) => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn buffer_texture_sampling() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 140
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 140
+
+            uniform samplerBuffer data;
+            out vec4 color;
+
+            void main() {
+                color = texelFetch(data, 1);
+            }
+        ",
+        None).unwrap();
+
+    let data = vec![(0.0f32, 0.0, 0.0, 1.0), (1.0, 0.0, 1.0, 1.0), (0.0, 0.0, 0.0, 1.0)];
+    let buffer_texture = glium::texture::BufferTexture::new(&display, data).unwrap();
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("data", &buffer_texture);
+
+    texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+    texture.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read();
+    assert_eq!(read_back[0][0], (255, 0, 255, 255));
+    assert_eq!(read_back[127][127], (255, 0, 255, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn buffer_texture_wrong_sampler_type() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 140
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 140
+
+            uniform samplerBuffer data;
+            out vec4 color;
+
+            void main() {
+                color = texelFetch(data, 0);
+            }
+        ",
+        None).unwrap();
+
+    let buffer_texture = glium::texture::BufferTexture::new(&display, vec![1u32, 2, 3]).unwrap();
+    let uniforms = glium::uniforms::UniformsStorage::new("data", &buffer_texture);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              16, 16);
+    match texture.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()) {
+        Err(glium::DrawError::UniformTypeMismatch { ref name, .. }) => {
+            assert_eq!(name.as_slice(), "data");
+        },
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn buffer_texture_slice() {
+    let display = support::build_display();
+
+    let data = Vec::from_elem(4096, (0.0f32, 0.0, 0.0, 1.0));
+    let texture = glium::texture::BufferTexture::new(&display, data).unwrap();
+
+    let slice = match texture.slice(1024, 2048) {
+        Ok(s) => s,
+        Err(glium::texture::BufferTextureCreationError::RangeNotSupported) => return,
+        Err(glium::texture::BufferTextureCreationError::WrongAlignment) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    assert_eq!(slice.len(), 2048);
+
+    match texture.slice(4000, 100) {
+        Err(glium::texture::BufferTextureCreationError::OutOfRange) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
@@ -0,0 +1,78 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+
+mod support;
+
+#[test]
+fn compute_shader_write_image() {
+    let display = support::build_display();
+
+    let compute = glium::ComputeShader::from_source(&display, "
+        #version 430
+
+        layout(local_size_x = 1, local_size_y = 1) in;
+        layout(rgba8) uniform writeonly image2D image;
+
+        void main() {
+            imageStore(image, ivec2(gl_GlobalInvocationID.xy), vec4(1.0, 0.0, 0.0, 1.0));
+        }
+    ");
+
+    let compute = match compute {
+        Ok(c) => c,
+        Err(glium::ProgramCreationError::ShaderTypeNotSupported) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              16, 16);
+    texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("image",
+        glium::uniforms::ImageUnit(&texture, glium::uniforms::ImageUnitAccess::Write,
+                                   glium::uniforms::ImageUnitFormat::RGBA8));
+
+    compute.dispatch(&uniforms, 16, 16, 1).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+    assert_eq!(read_back[15][15], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compute_shader_too_many_work_groups() {
+    let display = support::build_display();
+
+    let compute = glium::ComputeShader::from_source(&display, "
+        #version 430
+
+        layout(local_size_x = 1) in;
+
+        void main() {
+        }
+    ");
+
+    let compute = match compute {
+        Ok(c) => c,
+        Err(glium::ProgramCreationError::ShaderTypeNotSupported) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    match compute.dispatch(&glium::uniforms::EmptyUniforms, std::u32::MAX, 1, 1) {
+        Err(glium::DrawError::TooManyWorkGroups) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
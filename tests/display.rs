@@ -17,7 +17,7 @@ fn display_clear_color() {
 
     let mut target = display.draw();
     target.clear_color(1.0, 0.0, 0.0, 1.0);
-    target.finish();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(f32, f32, f32)>> = display.read_front_buffer();
 
@@ -38,7 +38,6 @@ fn release_shader_compiler() {
 }
 
 #[test]
-#[should_fail(expected = "Viewport dimensions are too large")]
 fn viewport_too_large() {
     let display = support::build_display();
 
@@ -53,7 +52,36 @@ fn viewport_too_large() {
     };
 
     let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
-    display.draw().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params);
+    let mut target = display.draw();
+    match target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params) {
+        Err(glium::DrawError::ViewportTooLarge) => (),
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn viewport_array() {
+    let display = support::build_display();
+
+    let mut params: glium::DrawParameters = std::default::Default::default();
+    params.viewports[1] = Some(glium::Rect { left: 0, bottom: 0, width: 2, height: 2 });
+
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let mut target = display.draw();
+
+    match target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params) {
+        Ok(_) => assert!(display.get_capabilities().max_viewports.unwrap() >= 2),
+        Err(glium::DrawError::ViewportArrayNotSupported) => {
+            assert!(display.get_capabilities().max_viewports.is_none());
+        },
+        Err(_) => panic!()
+    };
+
+    target.finish().unwrap();
+    display.assert_no_error();
 }
 
 #[test]
@@ -75,7 +103,6 @@ fn timestamp_query() {
 }
 
 #[test]
-#[should_fail(expected = "Depth range must be between 0 and 1")]
 fn wrong_depth_range() {
     let display = support::build_display();
 
@@ -85,5 +112,611 @@ fn wrong_depth_range() {
     };
 
     let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
-    display.draw().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params);
+    let mut target = display.draw();
+    match target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params) {
+        Err(glium::DrawError::InvalidDepthRange) => (),
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn get_current_frame() {
+    let display = support::build_display();
+    assert!(display.get_current_frame().is_none());
+
+    {
+        let mut target = display.draw();
+        assert_eq!(display.get_current_frame_dimensions(), Some(target.get_dimensions()));
+
+        target.clear_color(1.0, 0.0, 0.0, 1.0);
+
+        {
+            let current = display.get_current_frame().unwrap();
+            assert_eq!(current.get_dimensions(), target.get_dimensions());
+
+            let rect = glium::Rect { left: 0, bottom: 0, width: 1, height: 1 };
+            let pixels: Vec<Vec<(u8, u8, u8, u8)>> = current.read_rect(&rect);
+            assert_eq!(pixels[0][0], (255, 0, 0, 255));
+        }
+
+        target.finish().unwrap();
+    }
+
+    assert!(display.get_current_frame_dimensions().is_none());
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(not(ndebug))]
+#[should_fail(expected = "The `Frame` has been destroyed without calling `finish`")]
+fn frame_destroyed_without_finish() {
+    let display = support::build_display();
+    display.draw();
+}
+
+#[test]
+fn frame_finish_on_drop() {
+    let display = support::build_display();
+
+    {
+        let mut target = display.draw();
+        target.set_finish_on_drop(true);
+        target.clear_color(0.0, 0.0, 0.0, 1.0);
+    }
+
+    assert!(display.get_current_frame_dimensions().is_none());
+    display.assert_no_error();
+}
+
+#[test]
+fn overlay_called_on_frame_finish() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUint, SeqCst};
+
+    struct Counter(Arc<AtomicUint>);
+
+    impl glium::Overlay for Counter {
+        fn render(&mut self, target: &mut glium::Frame, _: &glium::Display) {
+            target.clear_color(1.0, 0.0, 0.0, 1.0);
+            self.0.fetch_add(1, SeqCst);
+        }
+    }
+
+    let display = support::build_display();
+
+    let counter = Arc::new(AtomicUint::new(0));
+    display.add_overlay(box Counter(counter.clone()));
+
+    display.draw().finish().unwrap();
+    display.draw().finish().unwrap();
+    assert_eq!(counter.load(SeqCst), 2);
+
+    display.clear_overlays();
+    display.draw().finish().unwrap();
+    assert_eq!(counter.load(SeqCst), 2);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn subgroup_support() {
+    let display = support::build_display();
+
+    let support = display.get_subgroup_support();
+    let preamble = support.get_glsl_preamble(glium::ShaderStage::Fragment);
+
+    assert_eq!(preamble.contains("GL_ARB_shader_ballot"), support.ballot);
+    assert_eq!(preamble.contains("GL_ARB_shader_group_vote"), support.group_vote);
+    assert_eq!(preamble.contains("GL_KHR_shader_subgroup_basic"),
+               support.subgroup && support.subgroup_stages.fragment &&
+               support.subgroup_features.basic);
+    assert_eq!(preamble.contains("GL_KHR_shader_subgroup_quad"),
+               support.subgroup && support.subgroup_stages.fragment &&
+               support.subgroup_features.quad);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn deferred_deletion() {
+    let display = support::build_display();
+
+    for &deferred in [true, false].iter() {
+        display.set_deferred_deletion(deferred);
+
+        for _ in range(0u, 5) {
+            let texture = glium::texture::Texture2d::new_empty(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8, 16, 16);
+            let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+            let mut target = display.draw();
+            target.clear_color(0.0, 0.0, 0.0, 0.0);
+            target.finish().unwrap();
+
+            drop(texture);
+            drop(vb);
+            drop(ib);
+        }
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn deferred_deletion_without_frames() {
+    let display = support::build_display();
+    display.set_deferred_deletion(true);
+
+    // more objects than the number of commands between two checks of the deletions
+    for _ in range(0u, 100) {
+        let texture = glium::texture::Texture2d::new_empty(&display,
+                                    glium::texture::UncompressedFloatFormat::U8U8U8U8, 16, 16);
+        let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+        drop(texture);
+        drop(vb);
+        drop(ib);
+    }
+
+    display.synchronize();
+    display.assert_no_error();
+}
+
+#[test]
+fn statistics_count_draw_calls() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    display.reset_statistics();
+    assert_eq!(display.get_statistics(), Default::default());
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    assert_eq!(display.get_last_frame_statistics().draw_calls, 2);
+    assert_eq!(display.get_statistics().draw_calls, 2);
+
+    display.reset_statistics();
+    assert_eq!(display.get_statistics().draw_calls, 0);
+    assert_eq!(display.get_last_frame_statistics().draw_calls, 0);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn statistics_count_draw_parameters_changes() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let culling = glium::DrawParameters {
+        backface_culling: glium::BackfaceCullingMode::CullClockWise,
+        .. Default::default()
+    };
+
+    let draw_frame = |&: params: &glium::DrawParameters| {
+        let mut target = display.draw();
+        target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                    &Default::default()).unwrap();
+        target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, params).unwrap();
+        target.finish().unwrap();
+        display.get_last_frame_statistics().state_changes
+    };
+
+    draw_frame(&Default::default());
+    let same_parameters = draw_frame(&Default::default());
+    let different_parameters = draw_frame(&culling);
+    assert!(different_parameters > same_parameters);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn draw_log() {
+    use std::default::Default;
+    use glium::index_buffer::PrimitiveType;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+    let other_texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+
+    // draws are not recorded while the log is disabled
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+    assert!(display.get_draw_log().is_empty());
+
+    display.set_draw_log(true);
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &Default::default()).unwrap();
+
+    let log = display.get_draw_log();
+    assert_eq!(log.len(), 2);
+
+    assert!(log[0].uses_program(&program));
+    assert!(log[0].is_on_default_framebuffer());
+    assert_eq!(log[0].primitives, Some(PrimitiveType::TriangleStrip));
+    assert_eq!(log[0].primitives_count, Some(2));
+
+    assert!(!log[1].is_on_default_framebuffer());
+    assert!(log[1].has_color_attachment(&texture));
+    assert!(!log[1].has_color_attachment(&other_texture));
+
+    display.clear_draw_log();
+    assert!(display.get_draw_log().is_empty());
+
+    display.set_draw_log(false);
+    display.assert_no_error();
+}
+
+#[test]
+fn draw_log_warnings() {
+    use std::default::Default;
+    use glium::DrawWarning;
+
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        position: [f32, ..2],
+        normal: [f32, ..3],
+    }
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+            Vertex { position: [-1.0, 1.0], normal: [0.0, 0.0, 1.0] },
+            Vertex { position: [1.0, 1.0], normal: [0.0, 0.0, 1.0] },
+            Vertex { position: [-1.0, -1.0], normal: [0.0, 0.0, 1.0] },
+        ]);
+    let ib = glium::IndexBuffer::new(&display,
+                                     glium::index_buffer::TrianglesList(vec![0u16, 1, 2]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform vec4 u_color;
+
+            void main() {
+                gl_FragColor = u_color;
+            }
+        ",
+        None).unwrap();
+
+    let uniforms = glium::uniforms::UniformsStorage::new("u_colour", [1.0, 0.0, 0.0, 1.0f32]);
+
+    display.set_draw_log(true);
+
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+    texture.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let log = display.get_draw_log();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].warnings, vec![
+        DrawWarning::UnusedUniform("u_colour".to_string()),
+        DrawWarning::MissingUniform("u_color".to_string()),
+        DrawWarning::UnusedAttribute("normal".to_string()),
+    ]);
+
+    display.set_draw_log(false);
+    display.assert_no_error();
+}
+
+#[test]
+fn debug_group() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    {
+        let _outer = glium::debug::DebugGroup::new(&display, "Outer");
+        let _inner = glium::debug::DebugGroup::new(&display, "Inner");
+
+        let mut target = display.draw();
+        target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                    &Default::default()).unwrap();
+        target.finish().unwrap();
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn direct_state_access_query() {
+    let display = support::build_display();
+
+    // only checking that it doesn't crash, the result depends on the backend
+    display.is_direct_state_access_used();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn get_capabilities() {
+    let display = support::build_display();
+
+    let capabilities = display.get_capabilities();
+
+    assert!(capabilities.opengl_version >= (2, 0));
+    assert!(capabilities.glsl_version >= (1, 0));
+    assert!(capabilities.max_texture_size >= 64);
+    assert!(capabilities.max_color_attachments >= 1);
+    assert_eq!(capabilities.max_viewport_dimensions, display.get_max_viewport_dimensions());
+    assert_eq!(capabilities.max_anisotropy, display.get_max_anisotropy_support());
+
+    for extension in capabilities.extensions.iter() {
+        assert!(capabilities.supports_extension(extension.as_slice()));
+    }
+    assert!(!capabilities.supports_extension("GL_GLIUM_not_an_extension"));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn debug_severity_toggle() {
+    let display = support::build_display();
+
+    display.set_debug_severity_enabled(glium::debug::Severity::Notification, false);
+    display.set_debug_severity_enabled(glium::debug::Severity::Low, false);
+    display.set_default_debug_callback();
+    display.set_debug_severity_enabled(glium::debug::Severity::Low, true);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn headless_dimensions() {
+    if !support::is_headless() {
+        return;
+    }
+
+    // `build_display` creates a 1024x768 headless renderer
+    let display = support::build_display();
+    assert_eq!(display.get_framebuffer_dimensions(), (1024, 768));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn shared_display_upload() {
+    let display = support::build_display();
+
+    let worker = match display.build_shared() {
+        Ok(d) => d,
+        Err(glium::GliumCreationError::SharedContextsNotSupported) => {
+            assert!(support::is_headless());
+            return;
+        },
+        Err(_) => panic!()
+    };
+
+    let (tx, rx) = channel();
+    std::thread::Thread::spawn(move || {
+        let texture = support::build_unicolor_texture2d(&worker, 1.0, 0.0, 0.0);
+        let fence = glium::sync::SyncFence::new(&worker);
+        if fence.is_none() {
+            worker.synchronize();
+        }
+        tx.send((texture, fence));
+    }).detach();
+
+    let (texture, fence) = rx.recv();
+    if let Some(fence) = fence {
+        fence.wait_server_in(&display);
+    }
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+    assert_eq!(data[0][0], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn shared_display_deletion_invalidates_bindings() {
+    let display = support::build_display();
+
+    let worker = match display.build_shared() {
+        Ok(d) => d,
+        Err(glium::GliumCreationError::SharedContextsNotSupported) => {
+            assert!(support::is_headless());
+            return;
+        },
+        Err(_) => panic!()
+    };
+
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    // the texture is bound to a texture unit of `display`, then deleted by `worker`
+    let texture = support::build_unicolor_texture2d(&worker, 1.0, 0.0, 0.0);
+    worker.synchronize();
+    glium::golden::render_offscreen(&display, 4, 4, |&mut: surface| {
+        let uniforms = glium::uniforms::UniformsStorage::new("texture", &texture);
+        surface.draw(&vb, &ib, &program, &uniforms, &std::default::Default::default()).unwrap();
+    });
+    drop(texture);
+    worker.synchronize();
+
+    // the driver is free to reuse the id of the deleted texture
+    let texture = support::build_unicolor_texture2d(&worker, 0.0, 1.0, 0.0);
+    worker.synchronize();
+    let image = glium::golden::render_offscreen(&display, 4, 4, |&mut: surface| {
+        let uniforms = glium::uniforms::UniformsStorage::new("texture", &texture);
+        surface.draw(&vb, &ib, &program, &uniforms, &std::default::Default::default()).unwrap();
+    });
+
+    assert_eq!(image.get_pixel(2, 2), (0, 255, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "native_handles")]
+fn native_handles() {
+    let display = support::build_display();
+
+    // headless renderers don't have a native context
+    let handles = glium::native::get_handles(&display);
+    assert_eq!(handles.is_some(), !support::is_headless());
+
+    display.assert_no_error();
+}
+
+#[test]
+fn frame_buffer_age() {
+    let display = support::build_display();
+
+    let target = display.draw();
+    if target.is_swap_preserved() == Some(true) {
+        assert_eq!(target.get_buffer_age(), Some(1));
+    }
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn finish_with_damage() {
+    let display = support::build_display();
+
+    // the age is unknown with headless renderers
+    if let Some(age) = glium::native::get_buffer_age(&display) {
+        assert!(age <= 4);
+    }
+
+    let mut target = display.draw();
+    target.clear_color(1.0, 0.0, 0.0, 1.0);
+    target.finish_with_damage(&[glium::Rect { left: 0, bottom: 0, width: 16, height: 16 }])
+          .unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn polygon_offset() {
+    let display = support::build_display();
+
+    let params = glium::DrawParameters {
+        polygon_offset: Some((1.0, 1.0)),
+        .. std::default::Default::default()
+    };
+
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_clamp() {
+    let display = support::build_display();
+
+    let params = glium::DrawParameters {
+        depth_clamp: true,
+        .. std::default::Default::default()
+    };
+
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let mut target = display.draw();
+
+    match target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params) {
+        Ok(_) => assert!(display.get_capabilities().supports_depth_clamp()),
+        Err(glium::DrawError::DepthClampNotSupported) => {
+            assert!(!display.get_capabilities().supports_depth_clamp());
+        },
+        Err(_) => panic!()
+    };
+
+    target.finish().unwrap();
+    display.assert_no_error();
+}
+
+#[test]
+fn exec_in_context() {
+    let display = support::build_display();
+
+    let (tx, rx) = channel();
+    unsafe {
+        display.exec_in_context(move |:| {
+            tx.send(5u32);
+        });
+    }
+    assert_eq!(rx.try_recv(), Ok(5));
+
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn polygon_mode() {
+    let display = support::build_display();
+
+    let params = glium::DrawParameters {
+        polygon_mode: glium::PolygonMode::Line,
+        .. std::default::Default::default()
+    };
+
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let mut target = display.draw();
+
+    match target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params) {
+        Ok(_) => assert!(display.get_capabilities().supports_polygon_mode()),
+        Err(glium::DrawError::PolygonModeNotSupported) => {
+            assert!(!display.get_capabilities().supports_polygon_mode());
+        },
+        Err(_) => panic!()
+    };
+
+    target.finish().unwrap();
+    display.assert_no_error();
 }
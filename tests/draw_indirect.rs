@@ -0,0 +1,145 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use std::default::Default;
+use glium::Surface;
+use glium::draw_commands::{DrawCommandsBuffer, DrawArraysIndirectCommand};
+use glium::draw_commands::DrawElementsIndirectCommand;
+
+mod support;
+
+fn build_program(display: &glium::Display) -> glium::Program {
+    glium::Program::from_source(display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None).unwrap()
+}
+
+#[vertex_format]
+#[deriving(Copy)]
+struct Vertex {
+    position: [f32, ..2],
+}
+
+#[test]
+fn draw_elements_indirect() {
+    let display = support::build_display();
+    if !glium::draw_commands::is_supported(&display) {
+        return;
+    }
+
+    let program = build_program(&display);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::IndexBuffer::new(&display,
+        glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 3]));
+
+    // each command draws one of the two triangles
+    let commands = DrawCommandsBuffer::new(&display, vec![
+        DrawElementsIndirectCommand { count: 3, instance_count: 1, first_index: 0,
+                                      base_vertex: 0, base_instance: 0 },
+        DrawElementsIndirectCommand { count: 3, instance_count: 1, first_index: 3,
+                                      base_vertex: 0, base_instance: 0 },
+    ]);
+
+    assert_eq!(commands.len(), 2);
+
+    display.set_draw_log(true);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw_indirect(&vb, &indices, &commands, &program, &glium::uniforms::EmptyUniforms,
+                         &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    // the number of primitives is only known by the GPU
+    let log = display.get_draw_log();
+    assert_eq!(log.len(), 1);
+    assert!(log[0].uses_program(&program));
+    assert_eq!(log[0].primitives_count, None);
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn draw_arrays_indirect() {
+    let display = support::build_display();
+    if !glium::draw_commands::is_supported(&display) {
+        return;
+    }
+
+    let program = build_program(&display);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let commands = DrawCommandsBuffer::new(&display, vec![
+        DrawArraysIndirectCommand { count: 4, instance_count: 1, first_vertex: 0,
+                                    base_instance: 0 },
+    ]);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw_arrays_indirect(&vb, glium::index_buffer::PrimitiveType::TriangleStrip,
+                                &commands, &program, &glium::uniforms::EmptyUniforms,
+                                &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn draw_commands_mapping() {
+    let display = support::build_display();
+
+    let mut commands = DrawCommandsBuffer::new(&display, vec![
+        DrawArraysIndirectCommand { count: 4, instance_count: 1, first_vertex: 0,
+                                    base_instance: 0 },
+    ]);
+
+    {
+        let mut mapping = commands.map();
+        mapping[0].instance_count = 5;
+    }
+
+    let mapping = commands.map();
+    assert_eq!(mapping[0].instance_count, 5);
+
+    display.assert_no_error();
+}
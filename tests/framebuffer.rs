@@ -12,7 +12,6 @@ use glium::Surface;
 mod support;
 
 #[test]
-#[should_fail(expected="Requested a depth function but no depth buffer is attached")]
 fn no_depth_buffer() {
     let display = support::build_display();
     let (vertex_buffer, index_buffer, program) = support::build_fullscreen_red_pipeline(&display);
@@ -26,8 +25,14 @@ fn no_depth_buffer() {
         .. std::default::Default::default()
     };
 
-    framebuffer.draw(&vertex_buffer, &index_buffer, &program,
-                     &glium::uniforms::EmptyUniforms, &parameters);
+    match framebuffer.draw(&vertex_buffer, &index_buffer, &program,
+                           &glium::uniforms::EmptyUniforms, &parameters)
+    {
+        Err(glium::DrawError::NoDepthBuffer) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
 }
 
 #[test]
@@ -57,7 +62,8 @@ fn simple_render_to_texture() {
                                               128, 128);
 
     let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture);
-    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &Default::default());
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &Default::default()).unwrap();
 
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
 
@@ -108,14 +114,15 @@ fn depth_texture2d() {
 
     // drawing with the `IfLess` depth test
     let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display,
-                                                                                   &color, &depth);
+                                                                                   &color, &depth)
+                                                                                   .unwrap();
     let params = glium::DrawParameters {
         depth_function: glium::DepthFunction::IfLess,
         .. std::default::Default::default()
     };
 
     framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
 
     // reading back the color
     let read_back: Vec<Vec<(f32, f32, f32, f32)>> = color.read();
@@ -125,3 +132,649 @@ fn depth_texture2d() {
 
     display.assert_no_error();
 }
+
+#[test]
+fn empty_depth_texture2d_attachment() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.5, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth = glium::texture::DepthTexture2d::empty(&display, 128, 128);
+
+    let params = glium::DrawParameters {
+        depth_function: glium::DepthFunction::IfLess,
+        .. std::default::Default::default()
+    };
+
+    // the depth buffer is cleared with a value inferior to the Z coordinate of the rectangle,
+    // so nothing should be drawn
+    let mut surface = color.as_surface_with_depth_buffer(&depth).unwrap();
+    surface.clear_color(0.0, 0.0, 0.0, 1.0);
+    surface.clear_depth(0.2);
+    surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = surface.read();
+    assert_eq!(read_back[0][0], (0, 0, 0, 255));
+    assert_eq!(read_back[127][127], (0, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn color_mask() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let params = glium::DrawParameters {
+        color_mask: (false, true, true, true),
+        .. std::default::Default::default()
+    };
+
+    let mut surface = texture.as_surface();
+    surface.clear_color(0.0, 0.0, 0.0, 0.0);
+    surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = surface.read();
+    assert_eq!(read_back[0][0], (0, 0, 0, 255));
+    assert_eq!(read_back[127][127], (0, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_prepass() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.5, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth = glium::texture::DepthTexture2d::empty(&display, 128, 128);
+
+    let params = glium::DrawParameters {
+        depth_function: glium::DepthFunction::IfLess,
+        .. std::default::Default::default()
+    };
+
+    let mut surface = color.as_surface_with_depth_buffer(&depth).unwrap();
+    surface.clear_color(0.0, 0.0, 0.0, 1.0);
+    surface.clear_depth(1.0);
+
+    let mut passes = 0u;
+    surface.draw_with_depth_prepass(&params, |surface, params| {
+        passes += 1;
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, params).unwrap();
+    });
+    assert_eq!(passes, 2);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = surface.read();
+    assert_eq!(read_back[0][0], (255, 255, 255, 255));
+    assert_eq!(read_back[127][127], (255, 255, 255, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "A depth pre-pass requires a depth buffer")]
+fn depth_prepass_without_depth_buffer() {
+    let display = support::build_display();
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let params = glium::DrawParameters {
+        depth_function: glium::DepthFunction::IfLess,
+        .. std::default::Default::default()
+    };
+
+    texture.as_surface().draw_with_depth_prepass(&params, |_, _| ());
+}
+
+#[test]
+#[cfg(feature = "gl_extensions")]       // TODO: remove
+fn scissor_render_to_texture() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let parameters = glium::DrawParameters {
+        scissor: Some(glium::Rect { left: 0, bottom: 0, width: 64, height: 128 }),
+        .. Default::default()
+    };
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture);
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &parameters).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[127][63], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[0][64], (0.0, 0.0, 0.0, 0.0));
+    assert_eq!(read_back[127][127], (0.0, 0.0, 0.0, 0.0));
+
+    // the scissor box must not apply to clears
+    framebuffer.clear_color(0.0, 0.0, 1.0, 1.0);
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (0.0, 0.0, 1.0, 1.0));
+    assert_eq!(read_back[127][127], (0.0, 0.0, 1.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn blending_max_render_to_texture() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let parameters = glium::DrawParameters {
+        blending_function: Some(glium::BlendingFunction::Max),
+        .. Default::default()
+    };
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture);
+    framebuffer.clear_color(0.0, 0.0, 1.0, 1.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &parameters).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 1.0, 1.0));
+    assert_eq!(read_back[127][127], (1.0, 0.0, 1.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn blending_separate_alpha_function() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let parameters = glium::DrawParameters {
+        blending_function: Some(glium::BlendingFunction::AlwaysReplace),
+        blending_alpha_function: Some(glium::BlendingFunction::Addition {
+            source: glium::LinearBlendingFactor::Zero,
+            destination: glium::LinearBlendingFactor::One,
+        }),
+        .. Default::default()
+    };
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture);
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &parameters).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 0.0));
+    assert_eq!(read_back[127][127], (1.0, 0.0, 0.0, 0.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn multisample_render_buffer_resolve() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let render_buffer = glium::render_buffer::RenderBuffer::new_multisample(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                        128, 128, 4);
+    assert_eq!(render_buffer.get_samples(), Some(4));
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              128, 128);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &render_buffer);
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &Default::default()).unwrap();
+
+    framebuffer.resolve_to(&texture.as_surface());
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[127][127], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn multisample_render_buffer_snapshot_other_format() {
+    use std::default::Default;
+    use glium::Texture;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let render_buffer = glium::render_buffer::RenderBuffer::new_multisample(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                        128, 128, 4);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &render_buffer);
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &Default::default()).unwrap();
+
+    let snapshot = framebuffer.snapshot_to_texture(
+                                    glium::texture::UncompressedFloatFormat::F32F32F32F32);
+    assert_eq!(snapshot.get_width(), 128);
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = snapshot.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[127][127], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The number of samples must be between 1 and")]
+fn multisample_render_buffer_zero_samples() {
+    let display = support::build_display();
+
+    glium::render_buffer::RenderBuffer::new_multisample(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                        128, 128, 0);
+}
+
+#[test]
+fn depth_render_buffer() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    // the program returns a Z coordinate between 0 (left of screen) and 1 (right of screen)
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, position.x, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 1.0, 1.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth = glium::render_buffer::DepthRenderBuffer::new(&display,
+                                                             glium::texture::DepthFormat::I24,
+                                                             128, 128);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display,
+                                                                                   &color, &depth)
+                                                                                   .unwrap();
+    let params = glium::DrawParameters {
+        depth_function: glium::DepthFunction::IfLess,
+        .. std::default::Default::default()
+    };
+
+    framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+    framebuffer.clear_depth(0.5);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = color.read();
+
+    assert_eq!(read_back[0][0], (1.0, 1.0, 1.0, 1.0));
+    assert_eq!(read_back[127][127], (0.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_stencil_render_buffer() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth_stencil = glium::render_buffer::DepthStencilRenderBuffer::new(&display,
+                                        glium::texture::DepthStencilFormat::I24I8, 128, 128);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_stencil_buffer(
+                                                            &display, &color, &depth_stencil)
+                                                            .unwrap();
+    assert!(framebuffer.get_depth_buffer_bits().is_some());
+    assert!(framebuffer.get_stencil_buffer_bits().is_some());
+
+    let params = glium::DrawParameters {
+        depth_function: glium::DepthFunction::IfLess,
+        .. std::default::Default::default()
+    };
+
+    framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+    framebuffer.clear_depth(1.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = color.read();
+    assert_eq!(read_back[64][64], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_render_buffer_wrong_dimensions() {
+    let display = support::build_display();
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth = glium::render_buffer::DepthRenderBuffer::new(&display,
+                                                             glium::texture::DepthFormat::I24,
+                                                             64, 64);
+
+    match glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display, &color, &depth) {
+        Err(glium::framebuffer::FramebufferValidationError::DimensionsMismatch) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn primitive_bounding_box_hint() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              64, 64);
+
+    let params = glium::DrawParameters {
+        primitive_bounding_box: Some(((-1.0, -1.0, -1.0, 1.0), (1.0, 1.0, 1.0, 1.0))),
+        .. std::default::Default::default()
+    };
+
+    texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &params).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[32][32], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn smoothing() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              64, 64);
+
+    let params = glium::DrawParameters {
+        line_smooth: Some(glium::Smooth::Nicest),
+        polygon_smooth: Some(glium::Smooth::Fastest),
+        .. std::default::Default::default()
+    };
+
+    texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+    match texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                                    &params)
+    {
+        Ok(_) => assert!(display.get_capabilities().supports_smoothing()),
+        Err(glium::DrawError::SmoothingNotSupported) => {
+            assert!(!display.get_capabilities().supports_smoothing());
+        },
+        Err(_) => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn clear_rect() {
+    let display = support::build_display();
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+
+    texture.as_surface().clear_rect(&glium::Rect { left: 0, bottom: 0, width: 1, height: 1 },
+                                    Some((1.0, 0.0, 0.0, 1.0)), None, None);
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(data[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(data[0][1], (0.0, 0.0, 0.0, 1.0));
+    assert_eq!(data[1][0], (0.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn layered_framebuffer() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_geometry_shaders() {
+        return;
+    }
+
+    let color = glium::texture::Texture2dArray::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128, 6);
+    let depth = glium::texture::DepthTexture2dArray::new_empty(&display,
+                                                               glium::texture::DepthFormat::I24,
+                                                               128, 128, 6);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display,
+                                                                                   &color, &depth)
+                                                                                   .unwrap();
+    assert_eq!(framebuffer.get_dimensions(), (128, 128));
+
+    framebuffer.clear_color(0.0, 1.0, 0.0, 1.0);
+    framebuffer.clear_depth(1.0);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn layered_framebuffer_mismatch() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_geometry_shaders() {
+        return;
+    }
+
+    let color = glium::texture::Texture2dArray::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128, 6);
+    let depth = glium::render_buffer::DepthRenderBuffer::new(&display,
+                                                             glium::texture::DepthFormat::I24,
+                                                             128, 128);
+
+    match glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display, &color, &depth) {
+        Err(glium::framebuffer::FramebufferValidationError::LayeredAttachmentsMismatch) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn layered_framebuffer_targets_mismatch() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_geometry_shaders() {
+        return;
+    }
+
+    let color = glium::texture::Texture3d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128, 6);
+    let depth = glium::texture::DepthTexture2dArray::new_empty(&display,
+                                                               glium::texture::DepthFormat::I24,
+                                                               128, 128, 6);
+
+    match glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(&display, &color, &depth) {
+        Err(glium::framebuffer::FramebufferValidationError::LayeredAttachmentsMismatch) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn layered_framebuffer_gl_layer() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_geometry_shaders() {
+        return;
+    }
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 150
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 150
+
+            in vec4 g_color;
+            out vec4 color;
+
+            void main() {
+                color = g_color;
+            }
+        ",
+        Some("
+            #version 150
+
+            layout(triangles) in;
+            layout(triangle_strip, max_vertices = 6) out;
+
+            out vec4 g_color;
+
+            void main() {
+                for (int layer = 0; layer < 2; layer++) {
+                    for (int i = 0; i < 3; i++) {
+                        gl_Layer = layer;
+                        g_color = layer == 0 ? vec4(1.0, 0.0, 0.0, 1.0) : vec4(0.0, 1.0, 0.0, 1.0);
+                        gl_Position = gl_in[i].gl_Position;
+                        EmitVertex();
+                    }
+                    EndPrimitive();
+                }
+            }
+        ")).unwrap();
+
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let texture = glium::texture::Texture2dArray::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            16, 16, 2);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &texture);
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    framebuffer.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &std::default::Default::default()).unwrap();
+
+    let layer0: Vec<Vec<(u8, u8, u8, u8)>> = texture.layer(0).as_surface().read();
+    let layer1: Vec<Vec<(u8, u8, u8, u8)>> = texture.layer(1).as_surface().read();
+    assert_eq!(layer0[0][0], (255, 0, 0, 255));
+    assert_eq!(layer0[15][15], (255, 0, 0, 255));
+    assert_eq!(layer1[0][0], (0, 255, 0, 255));
+    assert_eq!(layer1[15][15], (0, 255, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn layered_framebuffer_cubemap() {
+    use glium::texture::CubeLayer;
+
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_geometry_shaders() {
+        return;
+    }
+
+    let cubemap = glium::texture::Cubemap::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            16);
+
+    // clearing a layered framebuffer clears all its layers
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &cubemap);
+    assert_eq!(framebuffer.get_dimensions(), (16, 16));
+    framebuffer.clear_color(1.0, 0.0, 0.0, 1.0);
+
+    for face in CubeLayer::all().iter() {
+        let data: Vec<Vec<(u8, u8, u8, u8)>> = cubemap.as_surface_for_face(*face).read();
+        assert_eq!(data[0][0], (255, 0, 0, 255));
+    }
+
+    display.assert_no_error();
+}
@@ -0,0 +1,68 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+use glium::golden::{mod, GoldenImage, ImageMismatch};
+
+mod support;
+
+#[test]
+fn render_offscreen_row_order() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    // only drawing on the top half of the surface
+    let image = golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+        let parameters = glium::DrawParameters {
+            scissor: Some(glium::Rect { left: 0, bottom: 32, width: 64, height: 32 }),
+            .. std::default::Default::default()
+        };
+
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &parameters).unwrap();
+    });
+
+    assert_eq!(image.get_dimensions(), (64, 64));
+    assert_eq!(image.get_pixel(0, 0), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(63, 31), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(0, 32), (0, 0, 0, 0));
+    assert_eq!(image.get_pixel(63, 63), (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compare_with_tolerance() {
+    let reference = GoldenImage::from_raw(2, 1, vec![10, 20, 30, 255, 0, 0, 0, 255]);
+    let image = GoldenImage::from_raw(2, 1, vec![11, 20, 30, 255, 0, 0, 0, 255]);
+
+    assert!(golden::compare(&reference, &reference, 0).is_ok());
+    assert!(golden::compare(&reference, &image, 1).is_ok());
+
+    match golden::compare(&reference, &image, 0) {
+        Err(ImageMismatch::PixelsMismatch { differing_pixels, max_difference, diff }) => {
+            assert_eq!(differing_pixels, 1);
+            assert_eq!(max_difference, 1);
+            assert_eq!(diff.get_pixel(0, 0), (255, 0, 0, 255));
+            assert_eq!(diff.get_pixel(1, 0), (0, 0, 0, 255));
+        },
+        _ => panic!()
+    };
+}
+
+#[test]
+fn compare_dimensions_mismatch() {
+    let reference = GoldenImage::from_raw(1, 1, vec![0, 0, 0, 255]);
+    let image = GoldenImage::from_raw(1, 2, vec![0, 0, 0, 255, 0, 0, 0, 255]);
+
+    match golden::compare(&reference, &image, 255) {
+        Err(ImageMismatch::DimensionsMismatch { expected: (1, 1), obtained: (1, 2) }) => (),
+        _ => panic!()
+    };
+}
@@ -53,8 +53,9 @@ fn triangles_list_cpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -78,8 +79,9 @@ fn triangle_strip_cpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -104,8 +106,9 @@ fn triangle_fan_cpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -130,8 +133,9 @@ fn triangles_list_gpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -156,8 +160,9 @@ fn triangle_strip_gpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -183,8 +188,9 @@ fn triangle_fan_gpu() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
 
@@ -241,3 +247,242 @@ fn get_indices_type_u32() {
 
     display.assert_no_error();
 }
+
+#[test]
+fn index_buffer_from_u32() {
+    use glium::index_buffer::{IndexType, PrimitiveType};
+
+    let display = support::build_display();
+
+    let build = |&: indices: &[u32]| {
+        glium::IndexBuffer::from_u32(&display, PrimitiveType::TriangleStrip, indices)
+    };
+
+    assert_eq!(build(&[0, 1, 2, 254]).get_indices_type(), IndexType::U16);
+    assert_eq!(build(&[0, 1, 2, 65534]).get_indices_type(), IndexType::U16);
+    assert_eq!(build(&[0, 1, 2, 65535]).get_indices_type(), IndexType::U32);
+
+    // the restart index doesn't count
+    assert_eq!(build(&[0, 1, 2, 0xffffffff, 3]).get_indices_type(), IndexType::U16);
+
+    let indices = build(&[0, 1, 2, 3, 4]);
+    assert_eq!(indices.len(), 5);
+    assert_eq!(indices.get_primitives_type(), PrimitiveType::TriangleStrip);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn index_buffer_slice() {
+    let display = support::build_display();
+    let program = build_program(&display);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    // the first triangle is degenerate and must be skipped
+    let indices = glium::IndexBuffer::new(&display,
+        glium::index_buffer::TrianglesList(vec![0u16, 0, 0, 0, 1, 2, 2, 1, 3]));
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &indices.slice(3, 6), &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "Trying to slice out of range of the index buffer")]
+fn index_buffer_slice_out_of_range() {
+    let display = support::build_display();
+
+    let indices = glium::IndexBuffer::new(&display,
+        glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 3]));
+
+    indices.slice(3, 6);
+}
+
+#[test]
+fn vertex_buffer_slice() {
+    let display = support::build_display();
+    let program = build_program(&display);
+
+    // the first two vertices belong to another object
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [ 5.0,  5.0] }, Vertex { position: [5.0,  5.0] },
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 3]);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(vb.slice(2, 4), &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn indices_validation_cpu() {
+    let display = support::build_display();
+    let program = build_program(&display);
+    display.set_indices_validation(true);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 4]);
+
+    let mut target = display.draw();
+    match target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                      &Default::default())
+    {
+        Err(glium::DrawError::IndexOutOfRange { position: 5, index: 4, vertices_count: 4 }) => (),
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn indices_validation_gpu() {
+    let display = support::build_display();
+    let program = build_program(&display);
+    display.set_indices_validation(true);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::IndexBuffer::new(&display,
+        glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 4]));
+
+    let mut target = display.draw();
+    match target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                      &Default::default())
+    {
+        Err(glium::DrawError::IndexOutOfRange { position: 5, index: 4, vertices_count: 4 }) => (),
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn empty_indices_draw_nothing() {
+    let display = support::build_display();
+    let program = build_program(&display);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::index_buffer::TrianglesList(Vec::<u16>::new());
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms,
+                &Default::default()).unwrap();
+    target.draw(vb.slice(0, 0), &glium::index_buffer::TrianglesList(vec![0u16, 1, 2]),
+                &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (0, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn empty_viewport_draws_nothing() {
+    let display = support::build_display();
+    let program = build_program(&display);
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0,  1.0] }, Vertex { position: [1.0,  1.0] },
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [1.0, -1.0] },
+    ]);
+
+    let indices = glium::index_buffer::TrianglesList(vec![0u16, 1, 2, 2, 1, 3]);
+
+    let parameters = glium::DrawParameters {
+        viewport: Some(glium::Rect { left: 0, bottom: 0, width: 0, height: 0 }),
+        .. Default::default()
+    };
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &parameters).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (0, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn primitive_restart_index() {
+    let display = support::build_display();
+    let program = build_program(&display);
+    display.set_indices_validation(true);
+
+    if !display.get_capabilities().supports_primitive_restart() {
+        return;
+    }
+
+    let vb = glium::VertexBuffer::new(&display, vec![
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [0.0, -1.0] },
+        Vertex { position: [-1.0,  0.0] },
+        Vertex { position: [ 1.0,  1.0] }, Vertex { position: [0.0,  1.0] },
+        Vertex { position: [ 1.0,  0.0] },
+    ]);
+
+    // two separate triangles in the corners
+    let indices = glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 0xffff, 3, 4, 5]);
+
+    let parameters = glium::DrawParameters {
+        primitive_restart_index: true,
+        .. Default::default()
+    };
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &indices, &program, &glium::uniforms::EmptyUniforms, &parameters).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+    assert_eq!(data[data.len() / 2][data[0].len() / 2], (0, 0, 0));
+
+    display.assert_no_error();
+}
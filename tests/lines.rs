@@ -0,0 +1,121 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+use glium::lines::{WideLinesBuilder, LineCap, LineJoin, LineStyle};
+
+mod support;
+
+#[test]
+fn segment_butt_cap() {
+    let mut builder = WideLinesBuilder::new();
+    builder.add_segment([0.0, 0.0], [10.0, 0.0], 4.0, LineCap::Butt);
+
+    assert_eq!(builder.get_indices().len(), 6);
+
+    let positions: Vec<[f32, ..2]> = builder.get_vertices().iter().map(|v| v.position)
+                                            .collect();
+    assert!(positions.contains(&[0.0, 2.0]));
+    assert!(positions.contains(&[0.0, -2.0]));
+    assert!(positions.contains(&[10.0, 2.0]));
+    assert!(positions.contains(&[10.0, -2.0]));
+}
+
+#[test]
+fn segment_square_cap() {
+    let mut builder = WideLinesBuilder::new();
+    builder.add_segment([0.0, 0.0], [10.0, 0.0], 4.0, LineCap::Square);
+
+    let min_x = builder.get_vertices().iter().map(|v| v.position[0]).fold(0.0, |a, b| a.min(b));
+    let max_x = builder.get_vertices().iter().map(|v| v.position[0]).fold(0.0, |a, b| a.max(b));
+    assert_eq!(min_x, -2.0);
+    assert_eq!(max_x, 12.0);
+}
+
+#[test]
+fn strip_joins() {
+    let points = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]];
+
+    let count = |join| {
+        let mut builder = WideLinesBuilder::new();
+        builder.add_strip(&points, &LineStyle { width: 2.0, cap: LineCap::Butt, join: join });
+        builder.get_indices().len() / 3
+    };
+
+    // two quads, plus the join
+    assert_eq!(count(LineJoin::None), 4);
+    assert_eq!(count(LineJoin::Bevel), 5);
+    assert_eq!(count(LineJoin::Miter { limit: 4.0 }), 6);
+    assert!(count(LineJoin::Round) > 5);
+
+    // a right angle has a miter of sqrt(2), so this falls back to a bevel
+    assert_eq!(count(LineJoin::Miter { limit: 1.0 }), 5);
+}
+
+#[test]
+fn strip_degenerate() {
+    let mut builder = WideLinesBuilder::new();
+    builder.add_strip(&[[5.0, 5.0], [5.0, 5.0]], &std::default::Default::default());
+    assert_eq!(builder.get_indices().len(), 0);
+}
+
+#[test]
+fn strip_nearly_duplicate_points() {
+    // the two first points are different, but too close for the segment to have a direction
+    let style = LineStyle { width: 2.0, cap: LineCap::Butt, join: LineJoin::Bevel };
+
+    let mut with_duplicate = WideLinesBuilder::new();
+    with_duplicate.add_strip(&[[0.0, 0.0], [0.0, 1e-30], [10.0, 0.0]], &style);
+
+    let mut without_duplicate = WideLinesBuilder::new();
+    without_duplicate.add_strip(&[[0.0, 0.0], [10.0, 0.0]], &style);
+
+    assert_eq!(with_duplicate.get_indices().len(), without_duplicate.get_indices().len());
+}
+
+#[test]
+fn wide_line_draw() {
+    let display = support::build_display();
+
+    let mut builder = WideLinesBuilder::new();
+    builder.add_segment([0.0, 32.0], [64.0, 32.0], 16.0, LineCap::Butt);
+    builder.add_point([32.0, 8.0], 4.0, true);
+    let (vb, ib) = builder.build(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position / 32.0 - 1.0, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let image = glium::golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &std::default::Default::default()).unwrap();
+    });
+
+    // the rows of the image start at the top
+    assert_eq!(image.get_pixel(10, 31), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(10, 20), (0, 0, 0, 0));
+    assert_eq!(image.get_pixel(32, 55), (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
@@ -0,0 +1,117 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+use glium::oit::OitBuffers;
+
+mod support;
+
+#[vertex_format]
+#[deriving(Copy)]
+struct Vertex {
+    position: [f32, ..2],
+}
+
+/// Draws a red layer and a green layer with an alpha of 0.5 over a black texture, in the
+/// given order, and returns the pixel at the center of the result.
+fn draw_layers(display: &glium::Display, red_first: bool) -> (u8, u8, u8, u8) {
+    let vb = glium::VertexBuffer::new(display, vec![
+        Vertex { position: [-1.0, -1.0] }, Vertex { position: [-1.0, 1.0] },
+        Vertex { position: [1.0, -1.0] }, Vertex { position: [1.0, 1.0] },
+    ]);
+    let ib = glium::IndexBuffer::new(display,
+                                     glium::index_buffer::TriangleStrip(vec![0u16, 1, 2, 3]));
+
+    let program = glium::Program::from_source(display,
+        "
+            #version 130
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 130
+
+            uniform vec4 color;
+            out vec4 oit_accumulation;
+            out vec4 oit_weights;
+
+            void main() {
+                float w = 1.0;
+                oit_accumulation = vec4(color.rgb * color.a * w, color.a);
+                oit_weights = vec4(color.a * w, 0.0, 0.0, 0.0);
+            }
+        ",
+        None).unwrap();
+
+    let red = glium::uniforms::UniformsStorage::new("color", [1.0, 0.0, 0.0, 0.5f32]);
+    let green = glium::uniforms::UniformsStorage::new("color", [0.0, 1.0, 0.0, 0.5f32]);
+
+    let mut oit = OitBuffers::new(display, 64, 64);
+    oit.clear();
+
+    if red_first {
+        oit.draw(&vb, &ib, &program, &red, &std::default::Default::default(), None).unwrap();
+        oit.draw(&vb, &ib, &program, &green, &std::default::Default::default(), None).unwrap();
+    } else {
+        oit.draw(&vb, &ib, &program, &green, &std::default::Default::default(), None).unwrap();
+        oit.draw(&vb, &ib, &program, &red, &std::default::Default::default(), None).unwrap();
+    }
+
+    let target = glium::Texture2d::new_empty(display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             64, 64);
+    target.as_surface().clear_color(0.0, 0.0, 0.0, 1.0);
+    oit.composite(&mut target.as_surface()).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+    read_back[32][32]
+}
+
+#[test]
+fn oit_order_independent() {
+    let display = support::build_display();
+
+    if !glium::oit::is_supported(&display) {
+        return;
+    }
+
+    let red_first = draw_layers(&display, true);
+    let green_first = draw_layers(&display, false);
+    assert_eq!(red_first, green_first);
+
+    // the average of the two colors covers 75% of the black background
+    assert!(red_first.0 >= 94 && red_first.0 <= 97);
+    assert!(red_first.1 >= 94 && red_first.1 <= 97);
+    assert_eq!(red_first.2, 0);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn oit_empty_keeps_target() {
+    let display = support::build_display();
+
+    if !glium::oit::is_supported(&display) {
+        return;
+    }
+
+    let oit = OitBuffers::new(&display, 64, 64);
+
+    let target = support::build_unicolor_texture2d(&display, 0.0, 0.0, 1.0);
+    oit.composite(&mut target.as_surface()).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+    assert_eq!(read_back[0][0], (0, 0, 255, 255));
+
+    display.assert_no_error();
+}
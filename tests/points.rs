@@ -0,0 +1,274 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+
+mod support;
+
+/// Builds a VB and an IB that contain a single point at the center of the surface.
+fn build_center_point(display: &glium::Display) -> (glium::VertexBuffer<Vertex>,
+                                                    glium::IndexBuffer)
+{
+    (
+        glium::VertexBuffer::new(display, vec![Vertex { position: [0.0, 0.0] }]),
+        glium::IndexBuffer::new(display, glium::index_buffer::PointsList(vec![0u16])),
+    )
+}
+
+#[vertex_format]
+#[deriving(Copy)]
+struct Vertex {
+    position: [f32, ..2],
+}
+
+#[test]
+fn fixed_point_size() {
+    let display = support::build_display();
+    let (vb, ib) = build_center_point(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let params = glium::DrawParameters {
+        point_size: Some(10.0),
+        .. std::default::Default::default()
+    };
+
+    if display.get_capabilities().opengl_es {
+        let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+        match texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                                        &params)
+        {
+            Err(glium::DrawError::PointSizeNotSupported) => (),
+            _ => panic!()
+        };
+        return;
+    }
+
+    let image = glium::golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+    });
+
+    assert_eq!(image.get_pixel(32, 32), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(29, 35), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(40, 32), (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn invalid_point_size() {
+    let display = support::build_display();
+    let (vb, ib) = build_center_point(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let params = glium::DrawParameters {
+        point_size: Some(-2.0),
+        .. std::default::Default::default()
+    };
+
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+    match texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                                    &params)
+    {
+        Err(glium::DrawError::InvalidPointSize) => (),
+        Err(glium::DrawError::PointSizeNotSupported) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_point_size_and_point_coord() {
+    let display = support::build_display();
+    let (vb, ib) = build_center_point(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 120
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+                gl_PointSize = 10.0;
+            }
+        ",
+        "
+            #version 120
+
+            void main() {
+                if (gl_PointCoord.x < 0.5) {
+                    gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+                } else {
+                    gl_FragColor = vec4(0.0, 1.0, 0.0, 1.0);
+                }
+            }
+        ",
+        None).unwrap();
+
+    let params = glium::DrawParameters {
+        point_size: None,
+        .. std::default::Default::default()
+    };
+
+    let image = glium::golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+    });
+
+    assert_eq!(image.get_pixel(29, 32), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(35, 32), (0, 255, 0, 255));
+    assert_eq!(image.get_pixel(40, 32), (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn provoking_vertex_first() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_provoking_vertex() {
+        return;
+    }
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct ColoredVertex {
+        position: [f32, ..2],
+        color: [f32, ..3],
+    }
+
+    // a triangle that covers the whole surface
+    let vb = glium::VertexBuffer::new(&display, vec![
+        ColoredVertex { position: [-1.0, -1.0], color: [1.0, 0.0, 0.0] },
+        ColoredVertex { position: [3.0, -1.0], color: [0.0, 1.0, 0.0] },
+        ColoredVertex { position: [-1.0, 3.0], color: [0.0, 0.0, 1.0] },
+    ]);
+    let ib = glium::IndexBuffer::new(&display,
+                                     glium::index_buffer::TrianglesList(vec![0u16, 1, 2]));
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 130
+
+            in vec2 position;
+            in vec3 color;
+            flat out vec3 v_color;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+                v_color = color;
+            }
+        ",
+        "
+            #version 130
+
+            flat in vec3 v_color;
+            out vec4 f_color;
+
+            void main() {
+                f_color = vec4(v_color, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let params = glium::DrawParameters {
+        provoking_vertex: glium::ProvokingVertex::FirstVertex,
+        .. std::default::Default::default()
+    };
+
+    let image = glium::golden::render_offscreen(&display, 16, 16, |&mut: surface| {
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params).unwrap();
+    });
+
+    assert_eq!(image.get_pixel(0, 0), (255, 0, 0, 255));
+    assert_eq!(image.get_pixel(15, 15), (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn default_point_size() {
+    let display = support::build_display();
+    let (vb, ib) = build_center_point(&display);
+
+    // OpenGL ES always uses `gl_PointSize`
+    if display.get_capabilities().opengl_es {
+        return;
+    }
+
+    // the vertex shader doesn't write `gl_PointSize`
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let image = glium::golden::render_offscreen(&display, 64, 64, |&mut: surface| {
+        surface.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                     &std::default::Default::default()).unwrap();
+    });
+
+    // the point is one pixel wide, around the center of the image
+    let red = [(31, 31), (31, 32), (32, 31), (32, 32)].iter()
+                  .filter(|&&(x, y)| image.get_pixel(x, y) == (255, 0, 0, 255)).count();
+    assert_eq!(red, 1);
+    assert_eq!(image.get_pixel(29, 35), (0, 0, 0, 0));
+    assert_eq!(image.get_pixel(35, 32), (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
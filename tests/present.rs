@@ -0,0 +1,55 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::present::{PresentPass, PresentParameters, Tonemapping, OutputEncoding};
+
+mod support;
+
+#[test]
+fn present_linear() {
+    let display = support::build_display();
+    let source = support::build_unicolor_texture2d(&display, 1.0, 0.0, 0.0);
+    let target = glium::Texture2d::new_empty(&display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             64, 64);
+
+    let present = PresentPass::new(&display).unwrap();
+    present.draw(&mut target.as_surface(), &source, &PresentParameters {
+        encoding: OutputEncoding::Linear,
+        .. std::default::Default::default()
+    }).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = target.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[63][63], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn present_reinhard() {
+    let display = support::build_display();
+    let source = support::build_unicolor_texture2d(&display, 1.0, 1.0, 1.0);
+    let target = glium::Texture2d::new_empty(&display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             64, 64);
+
+    let present = PresentPass::new(&display).unwrap();
+    present.draw(&mut target.as_surface(), &source, &PresentParameters {
+        tonemapping: Tonemapping::Reinhard,
+        encoding: OutputEncoding::Linear,
+        .. std::default::Default::default()
+    }).unwrap();
+
+    // 1 / (1 + 1) = 0.5
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = target.read();
+    assert!((read_back[32][32].0 - 0.5).abs() < 0.01);
+
+    display.assert_no_error();
+}
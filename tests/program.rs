@@ -7,6 +7,8 @@ extern crate glium_macros;
 extern crate glutin;
 extern crate glium;
 
+use glium::Surface;
+
 mod support;
 
 #[test]
@@ -154,3 +156,445 @@ fn get_frag_data_location() {
     
     display.assert_no_error();
 }
+
+#[test]
+fn program_cache() {
+    let display = support::build_display();
+    let cache = glium::ProgramCache::new(&display);
+
+    let vertex = "
+        #version 110
+
+        attribute vec2 position;
+
+        void main() {
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+    ";
+
+    let fragment = "
+        #version 110
+
+        void main() {
+            gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+        }
+    ";
+
+    let program1 = cache.get_or_create(vertex, fragment, None).unwrap();
+    let program2 = cache.get_or_create(vertex, fragment, None).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert_eq!(format!("{}", program1), format!("{}", program2));
+
+    let sources = cache.get_sources();
+    assert_eq!(sources.len(), 1);
+    cache.clear();
+    assert_eq!(cache.len(), 0);
+    cache.preload(sources).unwrap();
+    assert_eq!(cache.len(), 1);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_cache_binaries() {
+    let display = support::build_display();
+    let cache = glium::ProgramCache::new(&display);
+
+    let vertex = "
+        #version 110
+
+        attribute vec2 position;
+
+        void main() {
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+    ";
+
+    let fragment = "
+        #version 110
+
+        void main() {
+            gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+        }
+    ";
+
+    cache.get_or_create(vertex, fragment, None).unwrap();
+
+    let binaries = cache.get_binaries();
+    if binaries.len() == 0 {
+        return;
+    }
+
+    cache.clear();
+    cache.preload_binaries(binaries).unwrap();
+    assert_eq!(cache.len(), 1);
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "nv_mesh_shader")]
+fn mesh_shader_program() {
+    use glium::Surface;
+
+    let display = support::build_display();
+
+    let program = glium::Program::from_mesh_source(&display, None,
+        "
+            #version 450
+            #extension GL_NV_mesh_shader : require
+
+            layout(local_size_x = 1) in;
+            layout(triangles, max_vertices = 3, max_primitives = 1) out;
+
+            void main() {
+                gl_MeshVerticesNV[0].gl_Position = vec4(-1.0, -1.0, 0.0, 1.0);
+                gl_MeshVerticesNV[1].gl_Position = vec4(3.0, -1.0, 0.0, 1.0);
+                gl_MeshVerticesNV[2].gl_Position = vec4(-1.0, 3.0, 0.0, 1.0);
+                gl_PrimitiveIndicesNV[0] = 0;
+                gl_PrimitiveIndicesNV[1] = 1;
+                gl_PrimitiveIndicesNV[2] = 2;
+                gl_PrimitiveCountNV = 1;
+            }
+        ",
+        "
+            #version 450
+
+            out vec4 color;
+
+            void main() {
+                color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ");
+
+    let program = match program {
+        Ok(p) => p,
+        Err(glium::ProgramCreationError::ShaderTypeNotSupported) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              16, 16);
+
+    let mut surface = texture.as_surface();
+    surface.clear_color(0.0, 0.0, 0.0, 0.0);
+    surface.draw_mesh_tasks(0, 1, &program, &glium::uniforms::EmptyUniforms,
+                            &std::default::Default::default()).unwrap();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = surface.read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn transform_feedback_varyings_interleaved() {
+    let display = support::build_display();
+
+    let program = glium::Program::from_source_with_transform_feedback(&display,
+        "
+            #version 130
+
+            in vec2 position;
+
+            out vec4 out_position;
+            out float out_size;
+
+            void main() {
+                out_position = vec4(position, 0.0, 1.0);
+                out_size = 1.0;
+                gl_Position = out_position;
+            }
+        ",
+        "
+            #version 130
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None, &["out_position", "out_size"],
+        glium::TransformFeedbackMode::Interleaved);
+
+    let program = match program {
+        Ok(p) => p,
+        Err(glium::ProgramCreationError::TransformFeedbackNotSupported) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    let varyings = program.get_transform_feedback_varyings();
+    assert_eq!(varyings.len(), 2);
+
+    assert_eq!(varyings[0].name.as_slice(), "out_position");
+    assert_eq!(varyings[0].buffer_index, 0);
+    assert_eq!(varyings[0].offset, 0);
+    assert_eq!(varyings[0].stride, 20);
+
+    assert_eq!(varyings[1].name.as_slice(), "out_size");
+    assert_eq!(varyings[1].buffer_index, 0);
+    assert_eq!(varyings[1].offset, 16);
+    assert_eq!(varyings[1].stride, 20);
+
+    let format = program.get_transform_feedback_vertex_format(0);
+    assert_eq!(format, vec![
+        ("out_position".to_string(), 0, glium::vertex_buffer::AttributeType::F32F32F32F32,
+         false),
+        ("out_size".to_string(), 16, glium::vertex_buffer::AttributeType::F32, false),
+    ]);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn transform_feedback_varyings_separate() {
+    let display = support::build_display();
+
+    let program = glium::Program::from_source_with_transform_feedback(&display,
+        "
+            #version 130
+
+            in vec2 position;
+
+            out vec4 out_position;
+            out float out_size;
+
+            void main() {
+                out_position = vec4(position, 0.0, 1.0);
+                out_size = 1.0;
+                gl_Position = out_position;
+            }
+        ",
+        "
+            #version 130
+
+            void main() {
+                gl_FragColor = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        ",
+        None, &["out_position", "out_size"],
+        glium::TransformFeedbackMode::Separate);
+
+    let program = match program {
+        Ok(p) => p,
+        Err(glium::ProgramCreationError::TransformFeedbackNotSupported) => return,
+        Err(e) => panic!("{}", e)
+    };
+
+    let varyings = program.get_transform_feedback_varyings();
+    assert_eq!(varyings.len(), 2);
+
+    assert_eq!(varyings[0].buffer_index, 0);
+    assert_eq!(varyings[0].offset, 0);
+    assert_eq!(varyings[0].stride, 16);
+
+    assert_eq!(varyings[1].buffer_index, 1);
+    assert_eq!(varyings[1].offset, 0);
+    assert_eq!(varyings[1].stride, 4);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn emulate_mediump() {
+    let display = support::build_display();
+    display.set_shader_precision(glium::ShaderPrecision {
+        emulate_mediump: true,
+        .. std::default::Default::default()
+    });
+
+    glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = glium_mediump(vec4(position, 0.0, 1.0));
+            }
+        ",
+        "
+            #version 110
+
+            void main() {
+                gl_FragColor = vec4(glium_mediump(vec3(0.1, 0.2, 0.3)), glium_mediump(1.0));
+            }
+        ",
+        None).unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_binary() {
+    let display = support::build_display();
+    let (_, _, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let binary = match program.get_binary() {
+        Some(b) => b,
+        None => return
+    };
+
+    let program2 = match glium::Program::from_binary(&display, &binary) {
+        Ok(p) => p,
+        Err(glium::ProgramCreationError::LinkingError(_)) => return,   // driver rejected it
+        Err(e) => panic!("{}", e)
+    };
+
+    let (vb, ib, _) = support::build_fullscreen_red_pipeline(&display);
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              64, 64);
+    texture.as_surface().draw(&vb, &ib, &program2, &glium::uniforms::EmptyUniforms,
+                              &std::default::Default::default()).unwrap();
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[32][32], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_introspection() {
+    let display = support::build_display();
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            uniform mat4 matrix;
+
+            attribute vec2 position;
+            attribute vec3 color;
+
+            varying vec3 vColor;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0) * matrix;
+                vColor = color;
+            }
+        ",
+        "
+            #version 110
+            varying vec3 vColor;
+
+            void main() {
+                gl_FragColor = vec4(vColor, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let uniforms = program.get_uniforms();
+    assert_eq!(uniforms.len(), 1);
+    assert_eq!(uniforms[0].name.as_slice(), "matrix");
+    assert_eq!(uniforms[0].ty, glium::uniforms::UniformType::FloatMat4);
+    assert_eq!(uniforms[0].size, 1);
+
+    let attributes = program.get_attributes();
+    assert_eq!(attributes.len(), 2);
+    assert_eq!(attributes[0].name.as_slice(), "color");
+    assert_eq!(attributes[0].ty, Some(glium::vertex_buffer::AttributeType::F32F32F32));
+    assert_eq!(attributes[1].name.as_slice(), "position");
+    assert_eq!(attributes[1].ty, Some(glium::vertex_buffer::AttributeType::F32F32));
+
+    assert!(program.get_uniform_blocks().is_empty());
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_attribute_locations() {
+    let display = support::build_display();
+
+    let program = glium::Program::from_source_with_attribute_locations(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute vec3 color;
+
+            varying vec3 vColor;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+                vColor = color;
+            }
+        ",
+        "
+            #version 110
+            varying vec3 vColor;
+
+            void main() {
+                gl_FragColor = vec4(vColor, 1.0);
+            }
+        ",
+        None, &[("position", 3), ("color", 5)]).unwrap();
+
+    let attributes = program.get_attributes();
+    assert_eq!(attributes[0].name.as_slice(), "color");
+    assert_eq!(attributes[0].location, 5);
+    assert_eq!(attributes[1].name.as_slice(), "position");
+    assert_eq!(attributes[1].location, 3);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn program_attribute_locations_conflict() {
+    let display = support::build_display();
+
+    let program = glium::Program::from_source_with_attribute_locations(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+            attribute vec3 color;
+
+            varying vec3 vColor;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+                vColor = color;
+            }
+        ",
+        "
+            #version 110
+            varying vec3 vColor;
+
+            void main() {
+                gl_FragColor = vec4(vColor, 1.0);
+            }
+        ",
+        None, &[("position", 2), ("color", 2)]);
+
+    match program {
+        Err(glium::ProgramCreationError::AttributeLocationConflict(_)) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn programs_with_same_attributes_share_vaos() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let (_, _, other_program) = support::build_fullscreen_red_pipeline(&display);
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.draw(&vb, &ib, &other_program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+
+    // the VAO must stay alive as long as one of the programs uses it
+    drop(program);
+    target.draw(&vb, &ib, &other_program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(f32, f32, f32)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (1.0, 0.0, 0.0));
+
+    display.assert_no_error();
+}
@@ -0,0 +1,71 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+use glium::query::{OcclusionQuery, WaitMode};
+
+mod support;
+
+#[test]
+fn occlusion_query_counts_samples() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+
+    let query = match OcclusionQuery::new(&display) {
+        Some(q) => q,
+        None => return
+    };
+
+    {
+        let _active = query.begin();
+        texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                                  &std::default::Default::default()).unwrap();
+    }
+
+    assert!(query.any_samples_passed());
+    assert!(query.is_ready());
+
+    display.assert_no_error();
+}
+
+#[test]
+fn conditional_rendering() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+    let texture = support::build_unicolor_texture2d(&display, 0.0, 0.0, 0.0);
+
+    if !glium::query::is_conditional_rendering_supported(&display) {
+        return;
+    }
+
+    let query = OcclusionQuery::new(&display).unwrap();
+
+    // nothing is drawn while the query is active
+    {
+        let _active = query.begin();
+    }
+
+    let parameters = glium::DrawParameters {
+        condition: Some(query.condition(WaitMode::Wait)),
+        .. std::default::Default::default()
+    };
+
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &parameters).unwrap();
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    for row in data.iter() {
+        for pixel in row.iter() {
+            assert_eq!(pixel, &(0.0, 0.0, 0.0, 1.0));
+        }
+    }
+
+    display.assert_no_error();
+}
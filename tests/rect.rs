@@ -0,0 +1,34 @@
+extern crate glium;
+
+use glium::Rect;
+
+#[test]
+fn rect_from_top_left() {
+    let rect = Rect::from_top_left(2, 10, 4, 5, 100);
+    assert_eq!(rect, Rect { left: 2, bottom: 85, width: 4, height: 5 });
+    assert_eq!(rect.get_top(100), 10);
+}
+
+#[test]
+#[should_fail(expected = "The rectangle goes past the bottom of the surface")]
+fn rect_from_top_left_outside() {
+    Rect::from_top_left(0, 98, 4, 5, 100);
+}
+
+#[test]
+fn rect_normalized() {
+    let rect = Rect::from_normalized(0.25, 0.5, 0.5, 0.5, (64, 32));
+    assert_eq!(rect, Rect { left: 16, bottom: 16, width: 32, height: 16 });
+    assert_eq!(rect.to_normalized((64, 32)), (0.25, 0.5, 0.5, 0.5));
+
+    // the rectangle is clamped to the surface
+    let rect = Rect::from_normalized(-1.0, 0.5, 3.0, 1.0, (64, 32));
+    assert_eq!(rect, Rect { left: 0, bottom: 16, width: 64, height: 16 });
+}
+
+#[test]
+fn rect_is_inside() {
+    assert!(Rect::from_dimensions(64, 32).is_inside((64, 32)));
+    assert!(!Rect { left: 1, bottom: 0, width: 64, height: 32 }.is_inside((64, 32)));
+    assert!(!Rect { left: 0, bottom: 0, width: 4, height: 0xffffffff }.is_inside((64, 32)));
+}
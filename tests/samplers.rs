@@ -55,11 +55,493 @@ fn magnify_nearest_filtering() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &ib, &program, &uniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
     assert_eq!(data[0][0], (255, 255, 255));
 
     display.assert_no_error();
 }
+
+#[test]
+fn texture_binds_cached() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    let texture = support::build_unicolor_texture2d(&display, 1.0, 0.0, 0.0);
+    let uniforms = glium::uniforms::UniformsStorage::new("texture", &texture);
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    // the texture is already bound to its unit during the second frame
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    assert_eq!(display.get_last_frame_statistics().texture_binds, 0);
+
+    // after an invalidation, the texture is bound again
+    display.invalidate_state_cache();
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    assert_eq!(display.get_last_frame_statistics().texture_binds, 1);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn same_texture_different_samplers() {
+    // ignoring test on travis
+    // TODO: find out why they are failing
+    if ::std::os::getenv("TRAVIS").is_some() {
+        return;
+    }
+
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.51, 0.0));
+            }
+        ",
+        None).unwrap();
+
+    let texture_data = vec![vec![(0u8, 0, 0), (255, 255, 255)]];
+    let texture = glium::texture::Texture2d::new(&display, texture_data);
+
+    let nearest = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            1, 1);
+    let linear = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            1, 1);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Nearest,
+            .. Default::default()
+        }));
+    nearest.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+            .. Default::default()
+        }));
+    linear.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = nearest.as_surface().read();
+    assert_eq!(data[0][0], (255, 255, 255, 255));
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = linear.as_surface().read();
+    assert!(data[0][0].0 != 0 && data[0][0].0 != 255);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_texture_comparison() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2DShadow shadow_map;
+
+            void main() {
+                float lit = shadow2D(shadow_map, vec3(0.5, 0.5, 0.3)).r;
+                gl_FragColor = vec4(lit, lit, lit, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    // filling the depth texture with 0.5
+    let color = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            16, 16);
+    let shadow_map = glium::texture::DepthTexture2d::empty(&display, 16, 16);
+    color.as_surface_with_depth_buffer(&shadow_map).unwrap().clear_depth(0.5);
+
+    let output = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            1, 1);
+
+    // 0.3 <= 0.5, so the comparison passes
+    let uniforms = glium::uniforms::UniformsStorage::new("shadow_map",
+        glium::uniforms::Sampler(&shadow_map, glium::uniforms::SamplerBehavior {
+            depth_texture_comparison: Some(glium::uniforms::DepthTextureComparison::LessOrEqual),
+            .. Default::default()
+        }));
+    output.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = output.as_surface().read();
+    assert_eq!(data[0][0], (255, 255, 255, 255));
+
+    // 0.3 > 0.5 is false
+    let uniforms = glium::uniforms::UniformsStorage::new("shadow_map",
+        glium::uniforms::Sampler(&shadow_map, glium::uniforms::SamplerBehavior {
+            depth_texture_comparison: Some(glium::uniforms::DepthTextureComparison::Greater),
+            .. Default::default()
+        }));
+    output.as_surface().draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = output.as_surface().read();
+    assert_eq!(data[0][0], (0, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn mipmapped_filter_without_mipmaps() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    let texture_data = vec![vec![(0u8, 0, 0), (255, 255, 255)]];
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, texture_data,
+                                                    glium::texture::MipmapsOption::NoMipmap);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+            .. Default::default()
+        }));
+
+    let mut target = display.draw();
+    match target.draw(&vb, &ib, &program, &uniforms, &Default::default()) {
+        Err(glium::DrawError::TextureMipmapsIncomplete { ref name }) => {
+            assert_eq!(name.as_slice(), "texture");
+        },
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn mipmapped_filter_single_pixel() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    // a 1x1 texture is already mipmap-complete with its main level
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, vec![vec![(255u8, 0, 0)]],
+                                                    glium::texture::MipmapsOption::NoMipmap);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+            .. Default::default()
+        }));
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn default_sampler_behavior() {
+    // ignoring test on travis
+    // TODO: find out why they are failing
+    if ::std::os::getenv("TRAVIS").is_some() {
+        return;
+    }
+
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.51, 0.0));
+            }
+        ",
+        None).unwrap();
+
+    let texture_data = vec![vec![(0u8, 0, 0), (255, 255, 255)]];
+    let texture = glium::texture::Texture2d::new(&display, texture_data);
+
+    let behavior = glium::uniforms::SamplerBehavior {
+        minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+        magnify_filter: glium::uniforms::MagnifySamplerFilter::Nearest,
+        max_anisotropy: 8,
+        .. Default::default()
+    };
+
+    assert_eq!(display.get_default_sampler_behavior(), None);
+    display.set_default_sampler_behavior(Some(behavior));
+    assert_eq!(display.get_default_sampler_behavior(), Some(behavior));
+
+    // the texture is used without a `Sampler`
+    let uniforms = glium::uniforms::UniformsStorage::new("texture", &texture);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 255, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn min_lod() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.51, 0.0));
+            }
+        ",
+        None).unwrap();
+
+    // the second level contains the average of the two texels
+    let texture_data = vec![vec![(0u8, 0, 0), (255, 255, 255)]];
+    let texture = glium::texture::Texture2d::new(&display, texture_data);
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            minify_filter: glium::uniforms::MinifySamplerFilter::NearestMipmapNearest,
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Nearest,
+            min_lod: 1.0,
+            .. Default::default()
+        }));
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert!(data[0][0].0 > 100 && data[0][0].0 < 155);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn no_mipmap_without_sampler_after_sampler() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    let texture_data = vec![vec![(255u8, 0, 0), (255, 0, 0)], vec![(255, 0, 0), (255, 0, 0)]];
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, texture_data,
+                                                    glium::texture::MipmapsOption::NoMipmap);
+
+    let with_sampler = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            minify_filter: glium::uniforms::MinifySamplerFilter::Nearest,
+            magnify_filter: glium::uniforms::MagnifySamplerFilter::Nearest,
+            .. Default::default()
+        }));
+    let without_sampler = glium::uniforms::UniformsStorage::new("texture", &texture);
+
+    // the texture must stay complete when it is used again without a sampler
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, &with_sampler, &Default::default()).unwrap();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, &without_sampler, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn mipmapped_filter_partial_mipmaps() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform sampler2D texture;
+
+            void main() {
+                gl_FragColor = texture2D(texture, vec2(0.5, 0.5));
+            }
+        ",
+        None).unwrap();
+
+    // only two of the three levels of a 4x4 texture, which is enough because
+    // `GL_TEXTURE_MAX_LEVEL` is set to the last level
+    let texture_data = Vec::from_elem(4, Vec::from_elem(4, (255u8, 0, 0)));
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, texture_data,
+                                                    glium::texture::MipmapsOption::EmptyMipmaps(2));
+
+    let uniforms = glium::uniforms::UniformsStorage::new("texture",
+        glium::uniforms::Sampler(&texture, glium::uniforms::SamplerBehavior {
+            minify_filter: glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+            .. Default::default()
+        }));
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
@@ -0,0 +1,87 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+use glium::Surface;
+use glium::sync::SyncFence;
+
+mod support;
+
+#[test]
+fn fence_wait() {
+    let display = support::build_display();
+
+    let fence = match SyncFence::new(&display) {
+        Some(f) => f,
+        None => return
+    };
+
+    fence.wait();
+    assert!(fence.is_signaled());
+    assert!(fence.wait_timeout(0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn fence_server_wait() {
+    let display = support::build_display();
+
+    let fence = match SyncFence::new(&display) {
+        Some(f) => f,
+        None => return
+    };
+
+    fence.wait_server();
+    fence.wait();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn frame_finish_with_fence() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let mut target = display.draw();
+    target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                &std::default::Default::default()).unwrap();
+
+    match target.finish_with_fence().unwrap() {
+        Some(fence) => fence.wait(),
+        None => assert!(!glium::sync::is_supported(&display))
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn gpu_watchdog() {
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let (tx, rx) = channel();
+    if !display.set_gpu_watchdog(::std::u64::MAX, move |&mut: frame| { tx.send(frame); }) {
+        return;
+    }
+
+    for _ in range(0u, 5) {
+        let mut target = display.draw();
+        target.draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                    &std::default::Default::default()).unwrap();
+        target.finish().unwrap();
+    }
+
+    display.disable_gpu_watchdog();
+    display.synchronize();
+
+    // no frame can take longer than the threshold
+    assert!(rx.try_recv().is_err());
+
+    display.assert_no_error();
+}
@@ -7,6 +7,9 @@ extern crate glium_macros;
 extern crate glutin;
 extern crate glium;
 
+#[cfg(feature = "image")]
+extern crate image;
+
 use glium::{Texture, Surface};
 
 mod support;
@@ -142,7 +145,8 @@ fn render_to_texture2d() {
                                               glium::texture::UncompressedFloatFormat::U8U8U8U8,
                                               1024, 1024);
     let params = Default::default();
-    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms, &params);
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &params).unwrap();
 
     let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
 
@@ -152,3 +156,1157 @@ fn render_to_texture2d() {
     
     display.assert_no_error();
 }
+
+#[test]
+fn texture_surface_read_rect() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4, 4);
+    texture.as_surface().clear_color(1.0, 0.0, 0.0, 1.0);
+
+    let rect = glium::Rect { left: 1, bottom: 1, width: 2, height: 2 };
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read_rect(&rect);
+    assert_eq!(read_back.len(), 2);
+    assert_eq!(read_back[0].len(), 2);
+    assert_eq!(read_back[1][1], (255, 0, 0, 255));
+
+    let raw: Vec<u8> = texture.as_surface()
+                              .read_rect_with_format(&rect, glium::texture::ClientFormat::U8U8U8);
+    assert_eq!(raw.len(), 12);
+    assert_eq!(raw.as_slice(), [255, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0].as_slice());
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "gl_extensions")]
+fn texture_surface_read_to_pixel_buffer() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            2, 2);
+    texture.as_surface().clear_color(0.0, 1.0, 0.0, 1.0);
+
+    let rect = glium::Rect { left: 0, bottom: 0, width: 2, height: 2 };
+    let buffer: glium::texture::PixelBuffer<(u8, u8, u8, u8)> = texture.as_surface()
+                        .read_to_pixel_buffer(&rect, glium::texture::ClientFormat::U8U8U8U8);
+    assert_eq!(buffer.get_capacity(), 4);
+
+    let data = buffer.read();
+    assert_eq!(data[0], (0, 255, 0, 255));
+    assert_eq!(data[3], (0, 255, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The rectangle to read is outside of the surface")]
+fn texture_surface_read_out_of_range() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            2, 2);
+
+    let rect = glium::Rect { left: 1, bottom: 1, width: 2, height: 2 };
+    let _: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read_rect(&rect);
+}
+
+#[test]
+fn cubemap_creation() {
+    let display = support::build_display();
+
+    let faces = Vec::from_elem(6, Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8))));
+    let cubemap = glium::texture::Cubemap::new(&display, faces);
+
+    assert_eq!(cubemap.get_width(), 4);
+    assert_eq!(cubemap.get_height(), Some(4));
+    assert_eq!(cubemap.get_depth(), None);
+    assert_eq!(cubemap.get_array_size(), None);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn cubemap_render_to_face() {
+    use glium::texture::CubeLayer;
+
+    let display = support::build_display();
+
+    let cubemap = glium::texture::Cubemap::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4);
+
+    for face in CubeLayer::all().iter() {
+        cubemap.as_surface_for_face(*face).clear_color(0.0, 0.0, 0.0, 1.0);
+    }
+    cubemap.as_surface_for_face(CubeLayer::NegativeY).clear_color(1.0, 0.0, 0.0, 1.0);
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = cubemap.as_surface_for_face(CubeLayer::NegativeY)
+                                                  .read();
+    assert_eq!(data[0][0], (255, 0, 0, 255));
+
+    let face = cubemap.face(CubeLayer::PositiveX);
+    let framebuffer = glium::framebuffer::SimpleFrameBuffer::new(&display, &face);
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = framebuffer.read();
+    assert_eq!(data[3][3], (0, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "A cubemap must have exactly six faces")]
+fn cubemap_wrong_faces_count() {
+    let display = support::build_display();
+
+    let faces = vec![Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8)))];
+    glium::texture::Cubemap::new(&display, faces);
+}
+
+#[test]
+fn cubemap_mipmaps() {
+    use glium::texture::{CubeLayer, MipmapsOption};
+
+    let display = support::build_display();
+
+    let faces = Vec::from_elem(6, Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8))));
+    let cubemap = glium::texture::Cubemap::new(&display, faces.clone());
+    assert_eq!(cubemap.get_mipmap_levels(), 3);
+
+    let cubemap = glium::texture::Cubemap::with_mipmaps(&display, faces.clone(),
+                                                        MipmapsOption::NoMipmap);
+    assert_eq!(cubemap.get_mipmap_levels(), 1);
+
+    let cubemap = glium::texture::Cubemap::with_mipmaps(&display, faces,
+                                                        MipmapsOption::EmptyMipmaps(2));
+    assert_eq!(cubemap.get_mipmap_levels(), 2);
+
+    for face in CubeLayer::all().iter() {
+        cubemap.write_face_mipmap(*face, 1,
+                                  Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+    }
+
+    let cubemap = glium::texture::Cubemap::new_empty_with_mipmaps(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8, 4,
+                                            MipmapsOption::NoMipmap);
+    assert_eq!(cubemap.get_mipmap_levels(), 1);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_arrays_write_mipmap() {
+    use glium::texture::MipmapsOption;
+
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2dArray::new_empty_with_mipmaps(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4, 4, 2, MipmapsOption::EmptyMipmaps(2));
+    texture.write_mipmap(1, 1, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 },
+                         Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+
+    let texture = glium::texture::Texture3d::new_empty_with_mipmaps(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4, 4, 4, MipmapsOption::EmptyMipmaps(2));
+    texture.write_mipmap(1, (0, 0, 0),
+                         Vec::from_elem(2, Vec::from_elem(2, Vec::from_elem(2, (0u8, 0u8, 0u8)))));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compressed_texture_2d_from_compressed_data() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::CompressedTexture2d::with_compressed_data(&display,
+                                    Vec::from_elem(8, 0u8), 4, 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU).unwrap();
+
+    assert_eq!(texture.get_width(), 4);
+    assert_eq!(texture.get_height(), Some(4));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compressed_texture_2d_with_mipmaps() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let levels = vec![Vec::from_elem(32, 0u8), Vec::from_elem(8, 0u8), Vec::from_elem(8, 0u8),
+                      Vec::from_elem(8, 0u8)];
+    let texture = glium::texture::CompressedTexture2d::with_compressed_mipmaps(&display,
+                                    levels, 8, 8, glium::texture::CompressedFormat::RGTCFormatU)
+                                    .unwrap();
+
+    assert_eq!(texture.get_width(), 8);
+    assert_eq!(texture.get_height(), Some(8));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compressed_texture_2d_wrong_data_size() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::CompressedTexture2d::with_compressed_data(&display,
+                                    Vec::from_elem(7, 0u8), 4, 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU);
+
+    match texture {
+        Err(glium::texture::TextureCreationError::DataSizeMismatch) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compressed_texture_2d_array_with_mipmaps() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let layer = vec![Vec::from_elem(32, 0u8), Vec::from_elem(8, 0u8), Vec::from_elem(8, 0u8),
+                     Vec::from_elem(8, 0u8)];
+    let texture = glium::texture::CompressedTexture2dArray::with_compressed_mipmaps(&display,
+                                    vec![layer.clone(), layer.clone(), layer], 8, 8,
+                                    glium::texture::CompressedFormat::RGTCFormatU).unwrap();
+
+    assert_eq!(texture.get_width(), 8);
+    assert_eq!(texture.get_height(), Some(8));
+    assert_eq!(texture.get_array_size(), Some(3));
+    assert_eq!(texture.get_mipmap_levels(), 4);
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "All the layers must have the same number of mipmap levels")]
+fn compressed_texture_2d_array_inconsistent_levels() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    glium::texture::CompressedTexture2dArray::with_compressed_mipmaps(&display,
+                                    vec![vec![Vec::from_elem(8, 0u8)], vec![]], 4, 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU);
+}
+
+#[test]
+fn compressed_cubemap_from_compressed_data() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::Cubemap::with_compressed_data(&display,
+                                    Vec::from_elem(6, Vec::from_elem(8, 0u8)), 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU).unwrap();
+
+    assert_eq!(texture.get_width(), 4);
+    assert_eq!(texture.get_height(), Some(4));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn compressed_cubemap_write_face() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::Cubemap::with_compressed_data(&display,
+                                    Vec::from_elem(6, Vec::from_elem(8, 0u8)), 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU).unwrap();
+
+    texture.write_compressed_face(glium::texture::CubeLayer::NegativeY, 0,
+                                  Vec::from_elem(8, 255u8),
+                                  glium::texture::CompressedFormat::RGTCFormatU);
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The layer 3 is outside of the texture")]
+fn compressed_texture_2d_array_write_layer_out_of_range() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::CompressedTexture2dArray::with_compressed_data(&display,
+                                    Vec::from_elem(3, Vec::from_elem(8, 0u8)), 4, 4,
+                                    glium::texture::CompressedFormat::RGTCFormatU).unwrap();
+
+    texture.write_compressed_layer(3, 0, Vec::from_elem(8, 0u8),
+                                   glium::texture::CompressedFormat::RGTCFormatU);
+}
+
+#[test]
+fn texture_2d_write() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0)],
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0)],
+    ]);
+
+    texture.write(glium::Rect { left: 1, bottom: 0, width: 2, height: 1 },
+                  vec![vec![(255u8, 0u8, 0u8, 255u8), (0, 255, 0, 255)]]);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read();
+    assert_eq!(read_back[0][0], (0, 0, 0, 0));
+    assert_eq!(read_back[0][1], (255, 0, 0, 255));
+    assert_eq!(read_back[0][2], (0, 255, 0, 255));
+    assert_eq!(read_back[1][1], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_write_async() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0)],
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0)],
+    ]);
+
+    let upload = texture.write_async(glium::Rect { left: 1, bottom: 1, width: 2, height: 1 },
+                                     vec![vec![(255u8, 0u8, 0u8, 255u8), (0, 255, 0, 255)]]);
+    upload.wait();
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read();
+    assert_eq!(read_back[1][0], (0, 0, 0, 0));
+    assert_eq!(read_back[1][1], (255, 0, 0, 255));
+    assert_eq!(read_back[1][2], (0, 255, 0, 255));
+    assert_eq!(read_back[0][1], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_1d_write() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture1d::new(&display, vec![
+        (0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0),
+    ]);
+
+    texture.write(1, vec![(255u8, 255u8, 255u8, 255u8)]);
+
+    let read_back: Vec<(u8, u8, u8, u8)> = texture.read();
+    assert_eq!(read_back[0], (0, 0, 0, 0));
+    assert_eq!(read_back[1], (255, 255, 255, 255));
+    assert_eq!(read_back[2], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The area to write is outside of the texture")]
+fn texture_2d_write_out_of_range() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+    ]);
+
+    texture.write(glium::Rect { left: 1, bottom: 1, width: 2, height: 1 },
+                  vec![vec![(255u8, 0u8, 0u8, 255u8), (0, 255, 0, 255)]]);
+}
+
+#[test]
+fn texture_2d_mipmaps_option() {
+    let display = support::build_display();
+
+    let data = Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8)));
+
+    let texture = glium::texture::Texture2d::new(&display, data.clone());
+    assert_eq!(texture.get_mipmap_levels(), 3);
+
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, data.clone(),
+                                                          glium::texture::MipmapsOption::NoMipmap);
+    assert_eq!(texture.get_mipmap_levels(), 1);
+
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, data,
+                                            glium::texture::MipmapsOption::EmptyMipmaps(2));
+    assert_eq!(texture.get_mipmap_levels(), 2);
+
+    texture.write_mipmap(1, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 },
+                         Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_background_mipmaps() {
+    let display = support::build_display();
+
+    let data = Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8)));
+
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, data.clone(),
+                                glium::texture::MipmapsOption::BackgroundGeneratedMipmaps);
+    assert_eq!(texture.get_mipmap_levels(), 3);
+
+    // this texture is destroyed before its mipmaps are generated
+    glium::texture::Texture2d::with_mipmaps(&display, data,
+                                glium::texture::MipmapsOption::BackgroundGeneratedMipmaps);
+
+    display.set_background_budget(0);
+    display.draw().finish().unwrap();
+    display.flush_background_tasks();
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The number of mipmap levels must be between 1 and 3")]
+fn texture_2d_too_many_mipmaps() {
+    let display = support::build_display();
+
+    glium::texture::Texture2d::new_empty_with_mipmaps(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8, 4, 4,
+                                        glium::texture::MipmapsOption::EmptyMipmaps(4));
+}
+
+#[test]
+#[should_fail(expected = "The texture doesn't have a mipmap level 1")]
+fn texture_2d_write_missing_mipmap() {
+    let display = support::build_display();
+
+    let data = Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8)));
+    let texture = glium::texture::Texture2d::with_mipmaps(&display, data,
+                                            glium::texture::MipmapsOption::NoMipmap);
+
+    texture.write_mipmap(1, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 },
+                         Vec::from_elem(2, Vec::from_elem(2, (0u8, 0u8, 0u8, 0u8))));
+}
+
+#[test]
+fn empty_texture_zero_initialized() {
+    let display = support::build_display();
+    display.set_zero_initialization(true);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              64, 64);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+
+    assert_eq!(read_back[0][0], (0, 0, 0, 0));
+    assert_eq!(read_back[32][32], (0, 0, 0, 0));
+    assert_eq!(read_back[63][63], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn render_to_srgb_texture2d() {
+    use std::default::Default;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::SrgbAlpha8,
+                                              64, 64);
+
+    let params = glium::DrawParameters {
+        framebuffer_srgb: true,
+        .. Default::default()
+    };
+
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &params).unwrap();
+
+    // pure red is the same in linear and sRGB color spaces
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+    assert_eq!(read_back[63][63], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_copy_to() {
+    let display = support::build_display();
+
+    let source = glium::texture::Texture2d::new(&display,
+                                    Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+    let target = glium::texture::Texture2d::new(&display,
+                                    Vec::from_elem(4, Vec::from_elem(4, (0u8, 0u8, 0u8, 0u8))));
+
+    source.copy_to(&target, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 }, (1, 1));
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = target.read();
+
+    assert_eq!(read_back[0][0], (0, 0, 0, 0));
+    assert_eq!(read_back[1][1], (255, 0, 0, 255));
+    assert_eq!(read_back[2][2], (255, 0, 0, 255));
+    assert_eq!(read_back[3][3], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_copy_to_other_format() {
+    let display = support::build_display();
+
+    let source = glium::texture::Texture2d::new(&display,
+                                    Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+    let target = glium::texture::Texture2d::new_empty(&display,
+                                    glium::texture::UncompressedFloatFormat::F32F32F32F32, 2, 2);
+
+    source.copy_to(&target, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 }, (0, 0));
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = target.read();
+    assert_eq!(read_back[0][0], (1.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[1][1], (1.0, 0.0, 0.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The copied area is outside of the target texture")]
+fn texture_2d_copy_to_outside() {
+    let display = support::build_display();
+
+    let source = glium::texture::Texture2d::new(&display,
+                                    Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+    let target = glium::texture::Texture2d::new(&display,
+                                    Vec::from_elem(2, Vec::from_elem(2, (0u8, 0u8, 0u8, 0u8))));
+
+    source.copy_to(&target, glium::Rect { left: 0, bottom: 0, width: 2, height: 2 }, (1, 1));
+}
+
+#[test]
+fn multisample_texture_array_sample_positions() {
+    let display = support::build_display();
+    if !glium::texture::Texture2dMultisampleArray::is_supported(&display) {
+        return;
+    }
+
+    let texture = glium::texture::Texture2dMultisampleArray::new_empty(&display,
+                                        glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                        64, 64, 2, 4).unwrap();
+    assert_eq!(texture.get_samples(), 4);
+
+    let mut surface = texture.as_surface_for_layer(1);
+    surface.clear_color(1.0, 0.0, 0.0, 1.0);
+
+    let positions = surface.get_sample_positions();
+    assert!(positions.len() >= 4);
+    for &(x, y) in positions.iter() {
+        assert!(x >= 0.0 && x <= 1.0);
+        assert!(y >= 0.0 && y <= 1.0);
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_view() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty_with_mipmaps(&display,
+                    glium::texture::UncompressedFloatFormat::SrgbAlpha8, 4, 4,
+                    glium::texture::MipmapsOption::EmptyMipmaps(3));
+
+    let levels = match texture.view_mipmaps(1, 2) {
+        Some(v) => v,
+        None => return
+    };
+
+    assert_eq!(levels.get_width(), 2);
+    assert_eq!(levels.get_height(), Some(2));
+    assert_eq!(levels.get_mipmap_levels(), 2);
+
+    // writing through a linear view of the texture modifies the original
+    let linear = texture.view_as(glium::texture::UncompressedFloatFormat::U8U8U8U8).unwrap();
+    linear.write(glium::Rect { left: 0, bottom: 0, width: 1, height: 1 },
+                 vec![vec![(255u8, 0u8, 0u8, 255u8)]]);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = linear.as_surface().read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_write_with_parameters() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+    ]);
+
+    // rows of three pixels whose first pixel is skipped, starting with the top row
+    let data = vec![
+        (9u8, 9u8, 9u8, 9u8), (0, 0, 255, 255), (255, 255, 255, 255),
+        (9u8, 9u8, 9u8, 9u8), (255, 0, 0, 255), (0, 255, 0, 255),
+    ];
+
+    let parameters = glium::texture::UploadParameters {
+        row_length: Some(3),
+        skip_pixels: 1,
+        flip_vertically: true,
+        .. std::default::Default::default()
+    };
+
+    texture.write_with_parameters(glium::Rect { left: 0, bottom: 0, width: 2, height: 2 },
+                                  data.as_slice(), &parameters);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.as_surface().read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+    assert_eq!(read_back[0][1], (0, 255, 0, 255));
+    assert_eq!(read_back[1][0], (0, 0, 255, 255));
+    assert_eq!(read_back[1][1], (255, 255, 255, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The data is too small for the upload parameters")]
+fn texture_2d_write_with_parameters_too_small() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+        vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)],
+    ]);
+
+    let parameters = glium::texture::UploadParameters {
+        row_length: Some(4),
+        .. std::default::Default::default()
+    };
+
+    let data = vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0), (0, 0, 0, 0), (0, 0, 0, 0)];
+    texture.write_with_parameters(glium::Rect { left: 0, bottom: 0, width: 2, height: 2 },
+                                  data.as_slice(), &parameters);
+}
+
+#[test]
+fn texture_2d_array_layers() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2dArray::new(&display, vec![
+        vec![vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)], vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)]],
+        vec![vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)], vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)]],
+    ]);
+
+    assert_eq!(texture.get_array_size(), Some(2));
+
+    texture.layer(0).as_surface().clear_color(1.0, 0.0, 0.0, 1.0);
+    texture.write(1, glium::Rect { left: 0, bottom: 0, width: 2, height: 1 },
+                  vec![vec![(0u8, 255u8, 0u8, 255u8), (0, 255, 0, 255)]]);
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = texture.layer(0).as_surface().read();
+    assert_eq!(data[1][1], (255, 0, 0, 255));
+
+    let data: Vec<Vec<(u8, u8, u8, u8)>> = texture.layer(1).as_surface().read();
+    assert_eq!(data[0][0], (0, 255, 0, 255));
+    assert_eq!(data[1][0], (0, 0, 0, 0));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "All the layers of a texture array must have the same dimensions")]
+fn texture_2d_array_different_dimensions() {
+    let display = support::build_display();
+
+    glium::texture::Texture2dArray::new(&display, vec![
+        vec![vec![(0u8, 0u8, 0u8, 0u8), (0, 0, 0, 0)]],
+        vec![vec![(0u8, 0u8, 0u8, 0u8)]],
+    ]);
+}
+
+#[test]
+#[cfg(feature = "gl_extensions")]
+fn texture_2d_migrate() {
+    let display = support::build_display();
+    let other = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(0u8, 1u8, 2u8, 255u8), (4u8, 8u8, 16u8, 255u8)],
+        vec![(32u8, 64u8, 128u8, 255u8), (32u8, 16u8, 4u8, 255u8)],
+    ]);
+
+    let copy = match glium::texture::migrate(&texture, &other) {
+        Ok(t) => t,
+        Err(glium::texture::MigrationError::ReadNotSupported) => return,
+        Err(glium::texture::MigrationError::UploadNotSupported) => return,
+        Err(_) => panic!()
+    };
+
+    assert_eq!(copy.get_width(), 2);
+    assert_eq!(copy.get_height(), Some(2));
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = copy.read();
+    assert_eq!(read_back[0][0], (0, 1, 2, 255));
+    assert_eq!(read_back[0][1], (4, 8, 16, 255));
+    assert_eq!(read_back[1][0], (32, 64, 128, 255));
+    assert_eq!(read_back[1][1], (32, 16, 4, 255));
+
+    display.assert_no_error();
+    other.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "image")]
+fn texture_surface_capture_image() {
+    use image::GenericImage;
+
+    let display = support::build_display();
+    let (vb, ib, program) = support::build_fullscreen_red_pipeline(&display);
+
+    let texture = glium::Texture2d::new_empty(&display,
+                                              glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                              5, 4);
+    texture.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+
+    // only drawing on the top half of the surface
+    let params = glium::DrawParameters {
+        scissor: Some(glium::Rect { left: 0, bottom: 2, width: 5, height: 2 }),
+        .. std::default::Default::default()
+    };
+    texture.as_surface().draw(&vb, &ib, &program, &glium::uniforms::EmptyUniforms,
+                              &params).unwrap();
+
+    let image = texture.as_surface().capture_image();
+
+    assert_eq!(image.dimensions(), (5, 4));
+
+    // the first row of the image is the top of the surface
+    let pixels = image.raw_pixels();
+    assert_eq!(pixels.slice(0, 4), [255, 0, 0, 255].as_slice());
+    assert_eq!(pixels.slice(36, 40), [255, 0, 0, 255].as_slice());
+    assert_eq!(pixels.slice(40, 44), [0, 0, 0, 0].as_slice());
+    assert_eq!(pixels.slice(76, 80), [0, 0, 0, 0].as_slice());
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_from_id() {
+    use glium::GlObject;
+
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(255u8, 0u8, 0u8, 255u8), (0, 255, 0, 255)],
+        vec![(0u8, 0u8, 255u8, 255u8), (255, 255, 255, 255)],
+    ]);
+
+    // the original object must not delete the texture
+    let id = texture.get_id();
+    unsafe { std::mem::forget(texture) };
+
+    let texture = unsafe { glium::texture::Texture2d::from_id(&display, id, 2, 2) };
+    assert_eq!(texture.get_id(), id);
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+    assert_eq!(read_back[1][1], (255, 255, 255, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_from_id_unowned() {
+    use glium::GlObject;
+
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(255u8, 0u8, 0u8, 255u8), (0, 255, 0, 255)],
+        vec![(0u8, 0u8, 255u8, 255u8), (255, 255, 255, 255)],
+    ]);
+
+    // destroying the wrapper must not delete the texture of the original object
+    {
+        let wrapper = unsafe {
+            glium::texture::Texture2d::from_id_unowned(&display, texture.get_id(), 2, 2)
+        };
+        assert_eq!(wrapper.get_id(), texture.get_id());
+    }
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+    assert_eq!(read_back[0][0], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn uncompressed_format_supported() {
+    let display = support::build_display();
+
+    // RGBA8 is available with every backend, including OpenGL ES 2.0
+    assert!(glium::texture::UncompressedFloatFormat::U8U8U8U8.is_supported(&display));
+
+    if !display.get_capabilities().opengl_es {
+        assert!(glium::texture::UncompressedFloatFormat::U16U16U16U16.is_supported(&display));
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_stencil_texture_2d_attachment() {
+    let display = support::build_display();
+
+    let color = glium::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            128, 128);
+    let depth_stencil = glium::texture::DepthStencilTexture2d::empty(&display, 128, 128);
+
+    let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_stencil_buffer(
+                                                            &display, &color, &depth_stencil)
+                                                            .unwrap();
+    assert!(framebuffer.get_depth_buffer_bits().is_some());
+    assert!(framebuffer.get_stencil_buffer_bits().is_some());
+
+    framebuffer.clear_depth(1.0);
+    framebuffer.clear_stencil(3);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_stencil_texture_2d_sample_stencil() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_stencil_texturing() {
+        return;
+    }
+
+    // depth of 1.0 and stencil of 5, packed as `GL_UNSIGNED_INT_24_8`
+    let texture = glium::texture::DepthStencilTexture2d::new(&display, vec![
+        vec![(0xffffff << 8) | 5u32, (0xffffff << 8) | 5],
+        vec![(0xffffff << 8) | 5, (0xffffff << 8) | 5],
+    ]);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 430
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 430
+
+            uniform usampler2D tex;
+            out vec4 color;
+
+            void main() {
+                if (texture(tex, vec2(0.5, 0.5)).r == 5u) {
+                    color = vec4(1.0, 0.0, 0.0, 1.0);
+                } else {
+                    color = vec4(0.0, 1.0, 0.0, 1.0);
+                }
+            }
+        ",
+        None).unwrap();
+
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let output = glium::Texture2d::new_empty(&display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             64, 64);
+    let uniforms = glium::uniforms::UniformsStorage::new("tex", texture.stencil());
+
+    output.as_surface().clear_color(0.0, 0.0, 0.0, 0.0);
+    output.as_surface().draw(&vb, &ib, &program, &uniforms,
+                             &std::default::Default::default()).unwrap();
+
+    let data: Vec<Vec<(f32, f32, f32, f32)>> = output.read();
+    for row in data.iter() {
+        for pixel in row.iter() {
+            assert_eq!(pixel, &(1.0, 0.0, 0.0, 1.0));
+        }
+    }
+
+    display.assert_no_error();
+}
+
+#[test]
+fn depth_stencil_texture_2d_depth_and_stencil_conflict() {
+    let display = support::build_display();
+
+    if !display.get_capabilities().supports_stencil_texturing() {
+        return;
+    }
+
+    let texture = glium::texture::DepthStencilTexture2d::empty(&display, 2, 2);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 430
+
+            in vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 430
+
+            uniform sampler2D depth;
+            uniform usampler2D stencil;
+            out vec4 color;
+
+            void main() {
+                color = vec4(texture(depth, vec2(0.5, 0.5)).r,
+                             float(texture(stencil, vec2(0.5, 0.5)).r), 0.0, 1.0);
+            }
+        ",
+        None).unwrap();
+
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let output = glium::Texture2d::new_empty(&display,
+                                             glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                             2, 2);
+    let uniforms = glium::uniforms::UniformsStorage::new("depth", &texture)
+                                                    .add("stencil", texture.stencil());
+
+    match output.as_surface().draw(&vb, &ib, &program, &uniforms,
+                                   &std::default::Default::default())
+    {
+        Err(glium::DrawError::DepthStencilModeConflict { .. }) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_uploader() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4, 4);
+
+    let mut uploader = glium::texture::TextureUploader::new(&display, 2, 1024);
+
+    let (tx, rx) = channel();
+    for _ in range(0u, 3) {
+        let tx = tx.clone();
+        let rect = glium::Rect { left: 1, bottom: 1, width: 2, height: 2 };
+        uploader.upload_with_callback(&texture, rect, vec![
+            vec![(255u8, 0u8, 0u8, 255u8), (255, 0, 0, 255)],
+            vec![(255u8, 0u8, 0u8, 255u8), (255, 0, 0, 255)],
+        ], move |&mut:| tx.send(()));
+    }
+
+    uploader.finish();
+    assert_eq!(uploader.get_pending_uploads(), 0);
+    for _ in range(0u, 3) {
+        assert!(rx.try_recv().is_ok());
+    }
+
+    let read_back: Vec<Vec<(u8, u8, u8, u8)>> = texture.read();
+    assert_eq!(read_back[1][1], (255, 0, 0, 255));
+    assert_eq!(read_back[2][2], (255, 0, 0, 255));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[should_fail(expected = "The area to write is outside of the texture")]
+fn texture_uploader_out_of_range_overflow() {
+    use std::num::Int;
+
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            4, 4);
+
+    let mut uploader = glium::texture::TextureUploader::new(&display, 1, 1024);
+
+    // `left + width` overflows
+    let rect = glium::Rect { left: Int::max_value(), bottom: 0, width: 1, height: 1 };
+    uploader.upload(&texture, rect, vec![vec![(255u8, 0u8, 0u8, 255u8)]]);
+}
+
+#[test]
+fn texture_2d_read_f32_lossless() {
+    let display = support::build_display();
+
+    // values outside of [0.0, 1.0] are only kept by floating-point textures
+    let texture = glium::texture::Texture2d::new(&display, vec![
+        vec![(2.5f32, -1.0f32, 0.125f32, 1.0f32), (1000.0, 0.0, 0.0, 1.0)],
+        vec![(0.0f32, 0.0f32, 0.0f32, 0.0f32), (0.5, 0.25, 64.0, 1.0)],
+    ]);
+
+    let read_back: Vec<Vec<(f32, f32, f32, f32)>> = texture.read();
+    assert_eq!(read_back[0][0], (2.5, -1.0, 0.125, 1.0));
+    assert_eq!(read_back[0][1], (1000.0, 0.0, 0.0, 1.0));
+    assert_eq!(read_back[1][1], (0.5, 0.25, 64.0, 1.0));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_read_f16() {
+    use glium::texture::f16;
+
+    let display = support::build_display();
+
+    let pixel = (f16::from_f32(2.5), f16::from_f32(-1.0), f16::from_f32(0.125),
+                 f16::from_f32(1.0));
+    let texture = glium::texture::Texture2d::new(&display, vec![vec![pixel, pixel]]);
+
+    let read_back: Vec<Vec<(f16, f16, f16, f16)>> = texture.read();
+    assert_eq!(read_back[0][0], pixel);
+    assert_eq!(read_back[0][1].0.to_f32(), 2.5);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn half_float_conversions() {
+    use glium::texture::f16;
+
+    assert_eq!(f16::from_f32(1.0), f16(0x3c00));
+    assert_eq!(f16::from_f32(-2.0), f16(0xc000));
+    assert_eq!(f16::from_f32(65504.0), f16(0x7bff));
+    assert_eq!(f16::from_f32(1.0e6), f16(0x7c00));
+    assert_eq!(f16(0x0001).to_f32(), 5.9604645e-8);
+
+    for &value in [0.0f32, 0.5, 1.0, -3.75, 1024.0, 0.00006103515625].iter() {
+        assert_eq!(f16::from_f32(value).to_f32(), value);
+    }
+}
+
+#[test]
+fn integral_texture_2d_read() {
+    let display = support::build_display();
+
+    let texture = glium::texture::UnsignedTexture2d::new(&display, vec![
+        vec![(70000u32, 1u32, 2u32, 3u32), (4, 5, 6, 7)],
+    ]);
+
+    let read_back: Vec<Vec<(u32, u32, u32, u32)>> = texture.read();
+    assert_eq!(read_back[0][0], (70000, 1, 2, 3));
+    assert_eq!(read_back[0][1], (4, 5, 6, 7));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_format_supported() {
+    let display = support::build_display();
+
+    let format = glium::texture::TextureFormat::UncompressedFloat(
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8);
+    assert!(display.is_texture_format_supported(format));
+
+    // stencil-only formats other than `I8` can't be used for textures
+    let format = glium::texture::TextureFormat::StencilFormat(
+                                            glium::texture::StencilFormat::I1);
+    assert!(!display.is_texture_format_supported(format));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_new_empty_checked() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_empty_checked(&display,
+                                            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+                                            16, 16, glium::texture::MipmapsOption::NoMipmap);
+    assert!(texture.is_ok());
+
+    match glium::texture::StencilTexture2d::new_empty_checked(&display,
+                                            glium::texture::StencilFormat::I1, 16, 16,
+                                            glium::texture::MipmapsOption::NoMipmap)
+    {
+        Err(glium::texture::TextureCreationError::FormatNotSupported) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_new_checked() {
+    let display = support::build_display();
+
+    let texture = glium::texture::Texture2d::new_checked(&display,
+                                    Vec::from_elem(2, Vec::from_elem(2, (255u8, 0u8, 0u8, 255u8))));
+    assert_eq!(texture.unwrap().get_width(), 2);
+
+    display.assert_no_error();
+}
+
+#[test]
+fn float_format_fallback_keeps_precision() {
+    use glium::texture::UncompressedFloatFormat;
+
+    assert_eq!(UncompressedFloatFormat::F16.get_fallback(),
+               Some(UncompressedFloatFormat::F16F16F16F16));
+    assert_eq!(UncompressedFloatFormat::F16F16F16F16.get_fallback(),
+               Some(UncompressedFloatFormat::U16U16U16U16));
+    assert_eq!(UncompressedFloatFormat::U16U16U16U16.get_fallback(),
+               Some(UncompressedFloatFormat::U8U8U8U8));
+}
+
+#[test]
+fn client_format_rgba8_supported() {
+    let display = support::build_display();
+
+    assert!(glium::texture::ClientFormat::U8U8U8U8.is_supported(&display));
+
+    display.assert_no_error();
+}
+
+#[test]
+fn texture_2d_new_empty_with_fallback() {
+    let display = support::build_display();
+
+    let (texture, format) = glium::texture::Texture2d::new_empty_with_fallback(&display,
+                                        glium::texture::UncompressedFloatFormat::F32F32F32F32,
+                                        16, 16, glium::texture::MipmapsOption::NoMipmap)
+                                        .unwrap();
+
+    assert!(format.is_supported(&display));
+    assert_eq!(texture.get_width(), 16);
+
+    display.assert_no_error();
+}
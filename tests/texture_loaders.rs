@@ -0,0 +1,153 @@
+#![feature(phase)]
+#![feature(unboxed_closures)]
+
+#[phase(plugin)]
+extern crate glium_macros;
+
+extern crate glutin;
+extern crate glium;
+
+#[cfg(feature = "texture_loaders")]
+use glium::Texture;
+
+mod support;
+
+#[cfg(feature = "texture_loaders")]
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.push(value as u8);
+    data.push((value >> 8) as u8);
+    data.push((value >> 16) as u8);
+    data.push((value >> 24) as u8);
+}
+
+/// Builds a 4x4 DDS file with the `ATI1` code and one mipmap.
+#[cfg(feature = "texture_loaders")]
+fn build_dds() -> Vec<u8> {
+    let mut data = b"DDS ".to_vec();
+    push_u32(&mut data, 124);       // size of the header
+    push_u32(&mut data, 0);         // flags
+    push_u32(&mut data, 4);         // height
+    push_u32(&mut data, 4);         // width
+    push_u32(&mut data, 8);         // linear size
+    push_u32(&mut data, 0);         // depth
+    push_u32(&mut data, 1);         // mipmaps count
+    for _ in range(0u, 11) { push_u32(&mut data, 0); }
+    push_u32(&mut data, 32);        // size of the pixel format
+    push_u32(&mut data, 4);         // flags of the pixel format
+    data.push_all(b"ATI1");
+    for _ in range(0u, 5) { push_u32(&mut data, 0); }
+    for _ in range(0u, 5) { push_u32(&mut data, 0); }
+    assert_eq!(data.len(), 128);
+
+    data.push_all(&[0u8, ..8]);
+    data
+}
+
+/// Builds a 4x4 KTX file with the `COMPRESSED_RED_RGTC1` format and one mipmap.
+#[cfg(feature = "texture_loaders")]
+fn build_ktx() -> Vec<u8> {
+    let mut data = vec![0xABu8, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+    push_u32(&mut data, 0x04030201);    // endianness
+    push_u32(&mut data, 0);             // gl type
+    push_u32(&mut data, 1);             // gl type size
+    push_u32(&mut data, 0);             // gl format
+    push_u32(&mut data, 0x8DBB);        // gl internal format
+    push_u32(&mut data, 0x1903);        // gl base internal format
+    push_u32(&mut data, 4);             // width
+    push_u32(&mut data, 4);             // height
+    push_u32(&mut data, 0);             // depth
+    push_u32(&mut data, 0);             // array elements
+    push_u32(&mut data, 1);             // faces
+    push_u32(&mut data, 1);             // mipmaps
+    push_u32(&mut data, 0);             // bytes of key/value data
+    assert_eq!(data.len(), 64);
+
+    push_u32(&mut data, 8);
+    data.push_all(&[0u8, ..8]);
+    data
+}
+
+#[test]
+#[cfg(feature = "texture_loaders")]
+fn load_dds() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let reader = std::io::MemReader::new(build_dds());
+    let texture = glium::texture::load_dds(&display, reader).unwrap();
+
+    assert_eq!(texture.get_width(), 4);
+    assert_eq!(texture.get_height(), Some(4));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "texture_loaders")]
+fn load_dds_truncated() {
+    let display = support::build_display();
+
+    let mut data = build_dds();
+    data.pop();
+
+    match glium::texture::load_dds(&display, std::io::MemReader::new(data)) {
+        Err(glium::texture::TextureLoadError::InvalidData) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "texture_loaders")]
+fn load_dds_cubemap() {
+    let display = support::build_display();
+
+    // sets DDSCAPS2_CUBEMAP in the caps2 field of the header
+    let mut data = build_dds();
+    data[113] = 0x02;
+
+    match glium::texture::load_dds(&display, std::io::MemReader::new(data)) {
+        Err(glium::texture::TextureLoadError::UnsupportedTextureType) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "texture_loaders")]
+fn load_ktx() {
+    let display = support::build_display();
+
+    if !glium::texture::CompressedFormat::RGTCFormatU.is_supported(&display) {
+        return;
+    }
+
+    let reader = std::io::MemReader::new(build_ktx());
+    let texture = glium::texture::load_ktx(&display, reader).unwrap();
+
+    assert_eq!(texture.get_width(), 4);
+    assert_eq!(texture.get_height(), Some(4));
+
+    display.assert_no_error();
+}
+
+#[test]
+#[cfg(feature = "texture_loaders")]
+fn load_ktx_invalid_identifier() {
+    let display = support::build_display();
+
+    let mut data = build_ktx();
+    data[0] = 0;
+
+    match glium::texture::load_ktx(&display, std::io::MemReader::new(data)) {
+        Err(glium::texture::TextureLoadError::InvalidData) => (),
+        _ => panic!()
+    };
+
+    display.assert_no_error();
+}
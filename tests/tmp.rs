@@ -88,8 +88,9 @@ fn test() {
 
     // drawing a frame
     let mut target = display.draw();
-    target.draw(&vertex_buffer, &index_buffer, &program, &uniforms, &std::default::Default::default());
-    target.finish();
+    target.draw(&vertex_buffer, &index_buffer, &program, &uniforms,
+                &std::default::Default::default()).unwrap();
+    target.finish().unwrap();
     
     display.assert_no_error();
 }
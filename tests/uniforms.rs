@@ -48,8 +48,8 @@ fn uniforms_storage_single_value() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &ib, &program, &uniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
     assert_eq!(data[0][0], (255, 0, 0));
@@ -90,8 +90,8 @@ fn uniforms_storage_multiple_values() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &ib, &program, &uniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
     assert_eq!(data[0][0], (255, 0, 0));
@@ -132,8 +132,8 @@ fn uniforms_storage_ignore_inactive_uniforms() {
 
     let mut target = display.draw();
     target.clear_color(0.0, 0.0, 0.0, 0.0);
-    target.draw(&vb, &ib, &program, &uniforms, &Default::default());
-    target.finish();
+    target.draw(&vb, &ib, &program, &uniforms, &Default::default()).unwrap();
+    target.finish().unwrap();
 
     let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
     assert_eq!(data[0][0], (255, 0, 0));
@@ -141,3 +141,90 @@ fn uniforms_storage_ignore_inactive_uniforms() {
     
     display.assert_no_error();
 }
+
+#[test]
+fn uniforms_type_mismatch() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform vec4 color;
+
+            void main() {
+                gl_FragColor = color;
+            }
+        ",
+        None).unwrap();
+
+    let uniforms = glium::uniforms::UniformsStorage::new("color", 1.0f32);
+
+    let mut target = display.draw();
+    match target.draw(&vb, &ib, &program, &uniforms, &Default::default()) {
+        Err(glium::DrawError::UniformTypeMismatch { ref name, expected, got }) => {
+            assert_eq!(name.as_slice(), "color");
+            assert_eq!(expected, glium::uniforms::UniformType::FloatVec4);
+            assert_eq!(got, Some(glium::uniforms::UniformType::Float));
+        },
+        _ => panic!()
+    };
+    target.finish().unwrap();
+
+    display.assert_no_error();
+}
+
+#[test]
+fn uniforms_tuple() {
+    let display = support::build_display();
+    let (vb, ib) = support::build_rectangle_vb_ib(&display);
+
+    let program = glium::Program::from_source(&display,
+        "
+            #version 110
+
+            attribute vec2 position;
+
+            void main() {
+                gl_Position = vec4(position, 0.0, 1.0);
+            }
+        ",
+        "
+            #version 110
+
+            uniform vec4 color1;
+            uniform vec4 color2;
+            uniform vec4 color3;
+
+            void main() {
+                gl_FragColor = color1 + color2 + color3;
+            }
+        ",
+        None).unwrap();
+
+    let uniforms1 = glium::uniforms::UniformsStorage::new("color1", [0.5, 0.0, 0.0, 0.5f32]);
+    let uniforms2 = glium::uniforms::UniformsStorage::new("color2", [0.3, 0.0, 0.0, 0.5f32]);
+    let uniforms3 = glium::uniforms::UniformsStorage::new("color3", [0.2, 0.0, 0.0, 0.0f32]);
+
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.0, 0.0, 0.0);
+    target.draw(&vb, &ib, &program, (&uniforms1, (&uniforms2, &uniforms3)),
+                &Default::default()).unwrap();
+    target.finish().unwrap();
+
+    let data: Vec<Vec<(u8, u8, u8)>> = display.read_front_buffer();
+    assert_eq!(data[0][0], (255, 0, 0));
+    assert_eq!(data.last().unwrap().last().unwrap(), &(255, 0, 0));
+
+    display.assert_no_error();
+}
@@ -183,3 +183,38 @@ fn vertex_buffer_any() {
 
     display.assert_no_error();
 }
+
+#[test]
+fn vertex_buffer_persistent_mapping() {
+    let display = support::build_display();
+
+    #[vertex_format]
+    #[deriving(Copy)]
+    struct Vertex {
+        field1: [u8, ..2],
+        field2: [u8, ..2],
+    }
+
+    let mut vb = match glium::VertexBuffer::new_persistent(&display,
+        vec![
+            Vertex { field1: [ 2,  3], field2: [ 5,  7] },
+            Vertex { field1: [12, 13], field2: [15, 17] },
+        ])
+    {
+        Some(vb) => vb,
+        None => return
+    };
+
+    assert!(vb.is_persistent());
+
+    {
+        let mut mapping = vb.map();
+        mapping[0].field1 = [0, 1];
+    }
+
+    let mapping = vb.map();
+    assert_eq!(mapping[0].field1.as_slice(), [0, 1].as_slice());
+    assert_eq!(mapping[1].field2.as_slice(), [15, 17].as_slice());
+
+    display.assert_no_error();
+}